@@ -0,0 +1,51 @@
+/*
+Criterion benchmarks of the state vector simulation on the standard circuits.
+
+Run with:
+cargo bench --bench circuits
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use quantum_simulation::benchmarks::{ghz_circuit, qft_circuit, random_clifford_t_circuit};
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const QUBIT_COUNTS: [usize; 3] = [12, 16, 20];
+const DEPTH: usize = 10;
+
+// The throughput of each benchmark is in gates, so that the reports are in gates per second.
+fn bench_circuit(criterion: &mut Criterion, name: &str, circuit: fn(usize) -> Circuit) {
+    let mut group = criterion.benchmark_group(name);
+    group.sample_size(10);
+    for &qubit_count in QUBIT_COUNTS.iter() {
+        let circuit = circuit(qubit_count);
+        group.throughput(Throughput::Elements(circuit.gates().len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(qubit_count),
+            &circuit,
+            |bencher, circuit| {
+                let mut simulation = QuantumSimulation::new(qubit_count, 0u64);
+                bencher.iter(|| {
+                    simulation.reset();
+                    circuit.run(&mut simulation);
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_circuits(criterion: &mut Criterion) {
+    bench_circuit(criterion, "qft", qft_circuit);
+    bench_circuit(criterion, "clifford_t", |qubit_count| {
+        random_clifford_t_circuit(qubit_count, DEPTH, 0)
+    });
+    bench_circuit(criterion, "ghz", ghz_circuit);
+}
+
+criterion_group!(benches, bench_circuits);
+criterion_main!(benches);
@@ -0,0 +1,595 @@
+/*
+Quantum algorithms built from the circuits of the user's own subroutines.
+
+Amplitude amplification generalizes the Grover search to any state preparation A with
+A|0⟩ = sin θ |good⟩ + cos θ |bad⟩, where the oracle flips the phase of the good states. Each
+iteration Q = -A S_0 A† S_χ, with the oracle S_χ and the reflection S_0 = I - 2|0⟩⟨0|,
+rotates the state by 2θ towards the good states, so k iterations succeed with the probability
+sin^2((2k + 1)θ).
+
+Amplitude estimation finds a = sin^2 θ. Its quantum phase estimation variant estimates the
+eigenphases π ± 2θ of the iteration, whose eigenvectors make up A|0⟩, with a register of
+counting qubits controlling its powers. The iterative variant needs no counting qubits: it
+measures A Q^k |0⟩ for growing k, each time narrowing the confidence interval of θ by the
+Chernoff–Hoeffding bound (Grinko, Gacon, Zoufal, and Woerner, 2021). For the uniform
+superposition A = H^⊗n of N = 2^n items, Na is the number of the marked items, which quantum
+counting estimates.
+
+The iterative phase estimation of Kitaev finds the phase φ of the eigenvalue e^(2πiφ) of
+a unitary U bit by bit with a single ancilla qubit instead of a register of counting qubits.
+The step s applies the controlled U^(2^(t-1-s)), which kicks back the phase 2π·0.b_s…b_0 of
+the bits of 2^t φ, rotates away the phase of the bits b_(s-1)…b_0 measured before by the RZ
+gates conditional on their classical bits, and measures the bit b_s in the X basis, the
+ancilla being reset for the next step.
+
+The transverse-field Ising model H = -J Σ Z_i Z_(i+1) - h Σ X_i of a chain of n qubits is
+evolved by the first-order Trotter formula, each step of the time dt being the layer of the
+RZZ(-2J dt) rotations of the neighbouring qubits followed by the layer of the RX(-2h dt)
+rotations, with the error O(dt) over a fixed time.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::blocks::{controlled, iqft, mcx_with_ancilla};
+use crate::circuit::{Circuit, Condition, Gate};
+use crate::hamiltonian::{Hamiltonian, Pauli};
+use crate::simulation::Simulation;
+use crate::state_vector_simulation::QuantumSimulation;
+
+mod vqe;
+
+pub use vqe::{vqe, Optimizer, VqeResult};
+
+// The number of the shots of each round of the iterative amplitude estimation.
+const SHOT_COUNT: usize = 100;
+
+// An estimated value with its confidence interval at the confidence level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub value: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub confidence_level: f64,
+}
+
+impl Estimate {
+    fn scaled(&self, factor: f64) -> Estimate {
+        Estimate {
+            value: self.value * factor,
+            lower_bound: self.lower_bound * factor,
+            upper_bound: self.upper_bound * factor,
+            confidence_level: self.confidence_level,
+        }
+    }
+}
+
+// The reflection I - 2|0⟩⟨0| of the qubits 0..n, up to a global phase, as the phase flip of
+// |1...1⟩ between X gates. The multi-controlled X of the phase flip has its controls on the
+// qubits 0..n-1, its target on the qubit n-1, and its clean ancilla qubits from the qubit n.
+fn reflection_about_zero(qubit_count: usize) -> Circuit {
+    let target = qubit_count - 1;
+    let mcx = mcx_with_ancilla(target);
+    let mut circuit = Circuit::new(mcx.qubit_count());
+    for qubit_number in 0..qubit_count {
+        circuit.pauli_x(qubit_number);
+    }
+    circuit.hadamard(target);
+    circuit.extend(&mcx);
+    circuit.hadamard(target);
+    for qubit_number in 0..qubit_count {
+        circuit.pauli_x(qubit_number);
+    }
+    circuit
+}
+
+// The state preparation followed by the iterations of the amplitude amplification. The state
+// preparation acts on the qubits 0..n and the oracle flips the phase of the good states of them,
+// possibly with the qubits from n on as clean ancilla qubits, which the reflection about |0⟩
+// also uses for n above 3.
+pub fn amplitude_amplification(
+    state_prep: &Circuit,
+    oracle: &Circuit,
+    iterations: usize,
+) -> Circuit {
+    let n = state_prep.qubit_count();
+    assert!(n > 0, "The state preparation needs at least 1 qubit.");
+    assert!(
+        oracle.qubit_count() >= n,
+        "The oracle has to act on at least the {} qubits of the state preparation.",
+        n
+    );
+    let reflection = reflection_about_zero(n);
+    let qubit_count = oracle.qubit_count().max(reflection.qubit_count());
+    let state_prep_qubits: Vec<usize> = (0..n).collect();
+    let state_unprep = state_prep.inverse();
+
+    let mut circuit = Circuit::new(qubit_count);
+    circuit.append(state_prep, &state_prep_qubits);
+    for _ in 0..iterations {
+        circuit.extend(oracle);
+        circuit.append(&state_unprep, &state_prep_qubits);
+        circuit.extend(&reflection);
+        circuit.append(state_prep, &state_prep_qubits);
+    }
+    circuit
+}
+
+// The number of the iterations maximizing the probability of the good states, given their
+// probability after the state preparation.
+pub fn optimal_iteration_count(success_probability: f64) -> usize {
+    assert!(
+        success_probability > 0.0 && success_probability <= 1.0,
+        "The success probability has to be in the interval (0, 1]."
+    );
+    let theta = success_probability.sqrt().asin();
+    (PI / (4.0 * theta) - 0.5).round().max(0.0) as usize
+}
+
+// The uniform superposition of the items of n qubits.
+fn uniform_superposition(qubit_count: usize) -> Circuit {
+    let mut circuit = Circuit::new(qubit_count);
+    for qubit_number in 0..qubit_count {
+        circuit.hadamard(qubit_number);
+    }
+    circuit
+}
+
+// Estimate the probability a of the good states after the state preparation by the phase
+// estimation of the amplitude amplification with the number of counting qubits. The estimate is
+// within the interval with the probability of at least 8/π^2.
+pub fn amplitude_estimation(
+    state_prep: &Circuit,
+    oracle: &Circuit,
+    precision: usize,
+    rnd_seed: u64,
+) -> Estimate {
+    let n = state_prep.qubit_count();
+    assert!(
+        precision > 0,
+        "The phase estimation needs at least 1 counting qubit."
+    );
+    let reflection = reflection_about_zero(n);
+    // The qubits of the iteration, the clean ancilla qubit of the controlled gates, and the
+    // counting qubits.
+    let qubit_count = oracle.qubit_count().max(reflection.qubit_count());
+    let ancilla = qubit_count;
+    let counting: Vec<usize> = (qubit_count + 1..qubit_count + 1 + precision).collect();
+    let state_prep_qubits: Vec<usize> = (0..n).collect();
+    let state_unprep = state_prep.inverse();
+    let controlled_oracle = controlled(oracle);
+    let controlled_reflection = controlled(&reflection);
+    let controlled_qubits = |block: &Circuit, control: usize| {
+        let mut qubit_numbers: Vec<usize> = (0..block.qubit_count()).collect();
+        qubit_numbers.extend_from_slice(&[control, ancilla]);
+        qubit_numbers
+    };
+
+    // The state preparation commutes with the control, as it is undone when the control is |0⟩.
+    let mut circuit = Circuit::new(qubit_count + 1 + precision);
+    circuit.append(state_prep, &state_prep_qubits);
+    for (j, &control) in counting.iter().enumerate() {
+        circuit.hadamard(control);
+        for _ in 0..1 << j {
+            circuit.append(&controlled_oracle, &controlled_qubits(oracle, control));
+            circuit.append(&state_unprep, &state_prep_qubits);
+            circuit.append(
+                &controlled_reflection,
+                &controlled_qubits(&reflection, control),
+            );
+            circuit.append(state_prep, &state_prep_qubits);
+        }
+    }
+    circuit.append(&iqft(precision), &counting);
+
+    let mut simulation = QuantumSimulation::new(circuit.qubit_count(), rnd_seed);
+    circuit.run(&mut simulation);
+    let measured_value = simulation
+        .measure(counting)
+        .iter()
+        .enumerate()
+        .map(|(bit, &value)| (value as usize) << bit)
+        .sum::<usize>();
+
+    // The iteration is -Q, with the eigenphases π ± 2θ, so the measured phase of the turn
+    // 1/2 ± θ/π gives θ to within π / 2^t.
+    let phase = measured_value as f64 / (1usize << precision) as f64;
+    let theta = PI * (phase - 0.5).abs();
+    let delta = PI / (1usize << precision) as f64;
+    Estimate {
+        value: theta.sin().powi(2),
+        lower_bound: (theta - delta).max(0.0).sin().powi(2),
+        upper_bound: (theta + delta).min(PI / 2.0).sin().powi(2),
+        confidence_level: 8.0 / (PI * PI),
+    }
+}
+
+// Estimate the number of the items of n qubits marked by the oracle by the amplitude estimation
+// of the uniform superposition.
+pub fn quantum_counting(oracle: &Circuit, n: usize, precision: usize, rnd_seed: u64) -> Estimate {
+    amplitude_estimation(&uniform_superposition(n), oracle, precision, rnd_seed)
+        .scaled((1u64 << n) as f64)
+}
+
+// The largest k above twice the current one for which the angles K θ of the interval of θ,
+// with K = 4k + 2, are all in the upper or all in the lower half of the circle, and which half.
+fn next_iteration_count(
+    iteration_count: usize,
+    is_upper: bool,
+    theta_lower: f64,
+    theta_upper: f64,
+) -> (usize, bool) {
+    let current_factor = 4 * iteration_count + 2;
+    let max_factor = (PI / (theta_upper - theta_lower)).floor() as usize;
+    if max_factor < 2 {
+        return (iteration_count, is_upper);
+    }
+    let mut factor = max_factor - (max_factor - 2) % 4;
+    while factor >= 2 * current_factor {
+        let angle_lower = (factor as f64 * theta_lower).rem_euclid(2.0 * PI);
+        let angle_upper = (factor as f64 * theta_upper).rem_euclid(2.0 * PI);
+        if angle_lower <= angle_upper && angle_upper <= PI {
+            return ((factor - 2) / 4, true);
+        }
+        if angle_lower >= PI && angle_lower <= angle_upper {
+            return ((factor - 2) / 4, false);
+        }
+        factor -= 4;
+    }
+    (iteration_count, is_upper)
+}
+
+// Estimate the probability a of the good states after the state preparation to within epsilon
+// with the confidence level 1 - alpha, without the counting qubits. The function is_good tells
+// whether the measured values of the qubits of the state preparation are a good state.
+pub fn iterative_amplitude_estimation<F>(
+    state_prep: &Circuit,
+    oracle: &Circuit,
+    mut is_good: F,
+    epsilon: f64,
+    alpha: f64,
+    rnd_seed: u64,
+) -> Estimate
+where
+    F: FnMut(&[bool]) -> bool,
+{
+    assert!(
+        epsilon > 0.0 && epsilon < 0.5,
+        "The precision epsilon has to be in the interval (0, 0.5)."
+    );
+    assert!(
+        alpha > 0.0 && alpha < 1.0,
+        "The significance level alpha has to be in the interval (0, 1)."
+    );
+    let qubit_numbers: Vec<usize> = (0..state_prep.qubit_count()).collect();
+    let qubit_count = amplitude_amplification(state_prep, oracle, 0).qubit_count();
+    let mut simulation = QuantumSimulation::new(qubit_count, rnd_seed);
+    // The confidence intervals of the at most T rounds hold together with the probability of
+    // 1 - alpha.
+    let max_round_count = (PI / (8.0 * epsilon)).log2().ceil().max(1.0);
+
+    let (mut theta_lower, mut theta_upper): (f64, f64) = (0.0, PI / 2.0);
+    let (mut iteration_count, mut is_upper) = (0, true);
+    let (mut good_count, mut shot_count) = (0, 0);
+    while theta_upper.sin().powi(2) - theta_lower.sin().powi(2) > 2.0 * epsilon {
+        let (next_count, next_is_upper) =
+            next_iteration_count(iteration_count, is_upper, theta_lower, theta_upper);
+        // The shots of the same number of iterations are pooled.
+        if next_count != iteration_count {
+            (good_count, shot_count) = (0, 0);
+        }
+        (iteration_count, is_upper) = (next_count, next_is_upper);
+
+        let circuit = amplitude_amplification(state_prep, oracle, iteration_count);
+        simulation.reset();
+        circuit.run(&mut simulation);
+        good_count += simulation
+            .sample(&qubit_numbers, SHOT_COUNT)
+            .iter()
+            .filter(|values| is_good(values))
+            .count();
+        shot_count += SHOT_COUNT;
+
+        // The interval of the probability sin^2(Kθ/2) = (1 - cos Kθ) / 2, mapped back to θ.
+        let half_width = ((2.0 * max_round_count / alpha).ln() / (2.0 * shot_count as f64)).sqrt();
+        let probability = good_count as f64 / shot_count as f64;
+        let probability_lower = (probability - half_width).max(0.0);
+        let probability_upper = (probability + half_width).min(1.0);
+        let (angle_lower, angle_upper) = if is_upper {
+            (
+                (1.0 - 2.0 * probability_lower).acos(),
+                (1.0 - 2.0 * probability_upper).acos(),
+            )
+        } else {
+            (
+                2.0 * PI - (1.0 - 2.0 * probability_upper).acos(),
+                2.0 * PI - (1.0 - 2.0 * probability_lower).acos(),
+            )
+        };
+        let factor = (4 * iteration_count + 2) as f64;
+        let turns = (factor * theta_lower / (2.0 * PI)).floor() * 2.0 * PI;
+        theta_lower = theta_lower.max((turns + angle_lower) / factor);
+        theta_upper = theta_upper.min((turns + angle_upper) / factor);
+    }
+
+    let lower_bound = theta_lower.sin().powi(2);
+    let upper_bound = theta_upper.sin().powi(2);
+    Estimate {
+        value: (lower_bound + upper_bound) / 2.0,
+        lower_bound,
+        upper_bound,
+        confidence_level: 1.0 - alpha,
+    }
+}
+
+// Whether the oracle flips the phase of the basis state of the values of the qubits.
+fn is_marked(oracle: &Circuit, values: &[bool]) -> bool {
+    let mut simulation = QuantumSimulation::new(oracle.qubit_count(), 0);
+    for (qubit_number, &value) in values.iter().enumerate() {
+        if value {
+            simulation.pauli_x(qubit_number);
+        }
+    }
+    oracle.run(&mut simulation);
+    let index = values
+        .iter()
+        .enumerate()
+        .map(|(bit, &value)| (value as usize) << bit)
+        .sum::<usize>();
+    simulation.amplitudes()[index].re < 0.0
+}
+
+// Estimate the number of the items of n qubits marked by the oracle by the iterative amplitude
+// estimation, to within epsilon times the number of the items. The measured items are checked
+// by the oracle.
+pub fn iterative_counting(
+    oracle: &Circuit,
+    n: usize,
+    epsilon: f64,
+    alpha: f64,
+    rnd_seed: u64,
+) -> Estimate {
+    let mut marked_items: HashMap<Vec<bool>, bool> = HashMap::new();
+    let is_good = |values: &[bool]| {
+        *marked_items
+            .entry(values.to_vec())
+            .or_insert_with(|| is_marked(oracle, values))
+    };
+    iterative_amplitude_estimation(
+        &uniform_superposition(n),
+        oracle,
+        is_good,
+        epsilon,
+        alpha,
+        rnd_seed,
+    )
+    .scaled((1u64 << n) as f64)
+}
+
+// The dynamic circuit of the iterative phase estimation of t bits of the phase of the unitary on
+// the qubits 0..n, with the eigenstate prepared on them, the ancilla qubit on the qubit n, and the
+// clean ancilla qubit of the controlled gates on the qubit n+1. The classical bit s is the bit s
+// of the phase times 2^t.
+pub fn iterative_phase_estimation_circuit(
+    unitary: &Circuit,
+    eigenstate_prep: &Circuit,
+    precision: usize,
+) -> Circuit {
+    let n = unitary.qubit_count();
+    assert!(
+        precision > 0,
+        "The phase estimation needs at least 1 bit of precision."
+    );
+    let ancilla = n;
+    let controlled_unitary = controlled(unitary);
+    let mut circuit = Circuit::with_clbits(n + 2, precision);
+    circuit.append(
+        eigenstate_prep,
+        &(0..eigenstate_prep.qubit_count()).collect::<Vec<_>>(),
+    );
+    for s in 0..precision {
+        circuit.hadamard(ancilla);
+        for _ in 0..1usize << (precision - 1 - s) {
+            circuit.extend(&controlled_unitary);
+        }
+        // The phase 2π b_l / 2^(s+1-l) of each bit l measured before.
+        for l in 0..s {
+            let theta = -2.0 * PI / (1u64 << (s + 1 - l)) as f64;
+            circuit.push_conditional(Gate::Rz(theta, ancilla), Condition::bit(l, true));
+        }
+        circuit.hadamard(ancilla);
+        circuit.measure(ancilla, s);
+        circuit.push_conditional(Gate::PauliX(ancilla), Condition::bit(s, true));
+    }
+    circuit
+}
+
+// Estimate the phase φ in [0, 1) of the eigenvalue e^(2πiφ) of the unitary of the eigenstate to
+// t bits by the iterative phase estimation, exact if 2^t φ is an integer.
+pub fn iterative_phase_estimation(
+    unitary: &Circuit,
+    eigenstate_prep: &Circuit,
+    precision: usize,
+    rnd_seed: u64,
+) -> f64 {
+    let circuit = iterative_phase_estimation_circuit(unitary, eigenstate_prep, precision);
+    let mut simulation = QuantumSimulation::new(circuit.qubit_count(), rnd_seed);
+    let measured_value = circuit
+        .run(&mut simulation)
+        .iter()
+        .enumerate()
+        .map(|(bit, &value)| (value as u64) << bit)
+        .sum::<u64>();
+    measured_value as f64 / (1u64 << precision) as f64
+}
+
+// The Hamiltonian -J Σ Z_i Z_(i+1) - h Σ X_i of the transverse-field Ising model of the open
+// chain of n qubits.
+pub fn tfim_hamiltonian(n: usize, h: f64, j: f64) -> Hamiltonian {
+    let mut hamiltonian = Hamiltonian::new();
+    for qubit_number in 0..n.saturating_sub(1) {
+        hamiltonian.add_term(
+            -j,
+            &[(Pauli::Z, qubit_number), (Pauli::Z, qubit_number + 1)],
+        );
+    }
+    for qubit_number in 0..n {
+        hamiltonian.add_term(-h, &[(Pauli::X, qubit_number)]);
+    }
+    hamiltonian
+}
+
+// The rotation RZZ(θ) = exp(-iθ/2 Z Z) of the two qubits, as RZ(θ) of the second qubit between
+// CNOT gates.
+fn rzz(circuit: &mut Circuit, theta: f64, qubit_number0: usize, qubit_number1: usize) {
+    circuit.cnot(qubit_number0, qubit_number1);
+    circuit.rz(theta, qubit_number1);
+    circuit.cnot(qubit_number0, qubit_number1);
+}
+
+// The evolution exp(-iHt) of the time t = steps·dt under the transverse-field Ising model of n
+// qubits by the first-order Trotter formula, from the state the circuit is run on.
+pub fn trotter_tfim(n: usize, steps: usize, dt: f64, h: f64, j: f64) -> Circuit {
+    assert!(
+        n > 0,
+        "The transverse-field Ising model needs at least 1 qubit."
+    );
+    let mut circuit = Circuit::new(n);
+    for _ in 0..steps {
+        for qubit_number in 0..n - 1 {
+            rzz(&mut circuit, -2.0 * j * dt, qubit_number, qubit_number + 1);
+        }
+        for qubit_number in 0..n {
+            circuit.rx(-2.0 * h * dt, qubit_number);
+        }
+    }
+    circuit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hamiltonian::PauliString;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn amplification_of_marked_state() {
+        // The uniform superposition of 4 qubits with the state |1011⟩ marked, the Grover search.
+        let mut state_prep = Circuit::new(4);
+        for qubit_number in 0..4 {
+            state_prep.hadamard(qubit_number);
+        }
+        let marked = 0b1011;
+        let mut oracle = Circuit::new(5);
+        for qubit_number in 0..4 {
+            if (marked >> qubit_number) & 1 == 0 {
+                oracle.pauli_x(qubit_number);
+            }
+        }
+        oracle.hadamard(3);
+        oracle.append(&mcx_with_ancilla(3), &[0, 1, 2, 3, 4]);
+        oracle.hadamard(3);
+        for qubit_number in 0..4 {
+            if (marked >> qubit_number) & 1 == 0 {
+                oracle.pauli_x(qubit_number);
+            }
+        }
+
+        let iterations = optimal_iteration_count(1.0 / 16.0);
+        assert_eq!(iterations, 3);
+        let circuit = amplitude_amplification(&state_prep, &oracle, iterations);
+        let mut simulation = QuantumSimulation::new(circuit.qubit_count(), 0);
+        circuit.run(&mut simulation);
+        let probability = simulation.amplitudes()[marked].norm_sqr();
+        let theta = 0.25f64.asin();
+        assert!((probability - ((2.0 * 3.0 + 1.0) * theta).sin().powi(2)).abs() < 1e-12);
+
+        // A biased state preparation of 1 qubit with the state |1⟩ marked is amplified to
+        // certainty in 1 iteration for the success probability 1/4.
+        let mut state_prep = Circuit::new(1);
+        state_prep.ry(PI / 3.0, 0);
+        let mut oracle = Circuit::new(1);
+        oracle.pauli_z(0);
+        let circuit = amplitude_amplification(&state_prep, &oracle, 1);
+        let mut simulation = QuantumSimulation::new(1, 0);
+        circuit.run(&mut simulation);
+        assert!((simulation.amplitudes()[1].norm_sqr() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn counting_of_marked_items() {
+        // The oracle of 4 qubits marking 3 of the 16 items.
+        let mut oracle = Circuit::new(5);
+        for marked in [0b0011, 0b0110, 0b1100] {
+            let mut phase_flip = Circuit::new(5);
+            for qubit_number in 0..4 {
+                if (marked >> qubit_number) & 1 == 0 {
+                    phase_flip.pauli_x(qubit_number);
+                }
+            }
+            oracle.extend(&phase_flip);
+            oracle.hadamard(3);
+            oracle.append(&mcx_with_ancilla(3), &[0, 1, 2, 3, 4]);
+            oracle.hadamard(3);
+            oracle.extend(&phase_flip);
+        }
+
+        let estimate = quantum_counting(&oracle, 4, 5, 1);
+        assert!(estimate.lower_bound <= 3.0 && 3.0 <= estimate.upper_bound);
+        assert!(estimate.upper_bound - estimate.lower_bound < 3.0);
+
+        let estimate = iterative_counting(&oracle, 4, 0.01, 0.05, 1);
+        assert!(estimate.lower_bound <= 3.0 && 3.0 <= estimate.upper_bound);
+        assert!(estimate.upper_bound - estimate.lower_bound <= 16.0 * 0.02);
+        assert_eq!(estimate.confidence_level, 0.95);
+    }
+
+    #[test]
+    fn trotter_evolution_of_tfim() {
+        // Without the coupling the evolution is exact, ⟨Z_i⟩ = cos(2ht).
+        let (h, t) = (0.7, 1.3);
+        let circuit = trotter_tfim(3, 4, t / 4.0, h, 0.0);
+        let mut simulation = QuantumSimulation::new(3, 0);
+        circuit.run(&mut simulation);
+        for qubit_number in 0..3 {
+            let z = PauliString::new(&[(Pauli::Z, qubit_number)]);
+            let expected = (2.0 * h * t).cos();
+            assert!((z.expectation(&simulation.amplitudes()) - expected).abs() < 1e-12);
+        }
+
+        // The energy of |000⟩, -2J, is conserved up to the Trotter error.
+        let (h, j) = (1.0, 0.5);
+        let hamiltonian = tfim_hamiltonian(3, h, j);
+        let circuit = trotter_tfim(3, 1000, 0.002, h, j);
+        let mut simulation = QuantumSimulation::new(3, 0);
+        circuit.run(&mut simulation);
+        assert!((hamiltonian.expectation(&simulation.amplitudes()) + 2.0 * j).abs() < 1e-2);
+    }
+
+    #[test]
+    fn iterative_phase_estimation_of_eigenphases() {
+        // T S on |11⟩ has the phase 1/8 + 1/4, exact to 3 bits.
+        let mut unitary = Circuit::new(2);
+        unitary.t(0);
+        unitary.s(1);
+        let mut eigenstate_prep = Circuit::new(2);
+        eigenstate_prep.pauli_x(0);
+        eigenstate_prep.pauli_x(1);
+        let circuit = iterative_phase_estimation_circuit(&unitary, &eigenstate_prep, 3);
+        assert_eq!(circuit.qubit_count(), 4);
+        for rnd_seed in 0..5 {
+            let phase = iterative_phase_estimation(&unitary, &eigenstate_prep, 3, rnd_seed);
+            assert_eq!(phase, 0.375);
+        }
+
+        // RZ(4π·0.3) on |1⟩ has the phase 0.3, within 2^-6 of the nearest estimates.
+        let mut unitary = Circuit::new(1);
+        unitary.rz(4.0 * PI * 0.3, 0);
+        let mut eigenstate_prep = Circuit::new(1);
+        eigenstate_prep.pauli_x(0);
+        let phase = iterative_phase_estimation(&unitary, &eigenstate_prep, 6, 0);
+        assert!((phase - 0.3).abs() < 1.0 / 64.0);
+    }
+}
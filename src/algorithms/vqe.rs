@@ -0,0 +1,268 @@
+/*
+Variational quantum eigensolver.
+
+The energy ⟨ψ(θ)|H|ψ(θ)⟩ of the state prepared by the ansatz is an upper bound of the ground
+state energy of the Hamiltonian, minimized over the parameters by a classical optimizer which
+only evaluates the energy. The Nelder–Mead simplex method moves the worst vertex of a simplex
+of n + 1 parameter vectors by reflection, expansion, and contraction. SPSA (simultaneous
+perturbation stochastic approximation) estimates the gradient from two energies at the
+parameters shifted along a random ±1 direction, with the gains decreasing by the standard
+exponents 0.602 and 0.101 of Spall.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::gradient::expectation;
+use crate::hamiltonian::Hamiltonian;
+use crate::parameterized_circuit::ParameterizedCircuit;
+
+// The size of the initial Nelder–Mead simplex along each parameter.
+const SIMPLEX_STEP: f64 = 0.5;
+// The gains of SPSA: the step size a, the perturbation size c, and the stability constant A as a
+// fraction of the number of the iterations.
+const SPSA_STEP: f64 = 1.0;
+const SPSA_PERTURBATION: f64 = 0.1;
+const SPSA_STABILITY: f64 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Optimizer {
+    // Stops when the energies of the vertices of the simplex differ by less than the tolerance.
+    NelderMead {
+        max_iterations: usize,
+        tolerance: f64,
+    },
+    Spsa {
+        max_iterations: usize,
+        rnd_seed: u64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VqeResult {
+    // The least energy found and its parameters.
+    pub energy: f64,
+    pub parameters: Vec<f64>,
+    // The least energy found after each iteration.
+    pub energies: Vec<f64>,
+    // The number of the energy evaluations.
+    pub evaluation_count: usize,
+}
+
+// The energy of the ansatz counting the evaluations.
+struct Objective<'a> {
+    ansatz: &'a ParameterizedCircuit,
+    hamiltonian: &'a Hamiltonian,
+    evaluation_count: usize,
+}
+
+impl Objective<'_> {
+    fn energy(&mut self, parameters: &[f64]) -> f64 {
+        self.evaluation_count += 1;
+        expectation(self.ansatz, self.hamiltonian, parameters)
+    }
+}
+
+// Move the vertex away from the point by the coefficient: point + coefficient (vertex - point).
+fn towards(point: &[f64], vertex: &[f64], coefficient: f64) -> Vec<f64> {
+    point
+        .iter()
+        .zip(vertex.iter())
+        .map(|(p, v)| p + coefficient * (v - p))
+        .collect()
+}
+
+fn nelder_mead(
+    objective: &mut Objective,
+    initial_parameters: &[f64],
+    max_iterations: usize,
+    tolerance: f64,
+) -> (Vec<f64>, f64, Vec<f64>) {
+    let n = initial_parameters.len();
+    let mut simplex: Vec<(Vec<f64>, f64)> = (0..=n)
+        .map(|i| {
+            let mut vertex = initial_parameters.to_vec();
+            if i > 0 {
+                vertex[i - 1] += SIMPLEX_STEP;
+            }
+            let energy = objective.energy(&vertex);
+            (vertex, energy)
+        })
+        .collect();
+    let mut energies = Vec::new();
+    for _ in 0..max_iterations {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        energies.push(simplex[0].1);
+        if simplex[n].1 - simplex[0].1 < tolerance {
+            break;
+        }
+
+        let mut centroid = vec![0.0; n];
+        for (vertex, _) in simplex[..n].iter() {
+            for (c, v) in centroid.iter_mut().zip(vertex.iter()) {
+                *c += v / n as f64;
+            }
+        }
+        let reflected = towards(&centroid, &simplex[n].0, -1.0);
+        let reflected_energy = objective.energy(&reflected);
+        if reflected_energy < simplex[0].1 {
+            let expanded = towards(&centroid, &simplex[n].0, -2.0);
+            let expanded_energy = objective.energy(&expanded);
+            simplex[n] = if expanded_energy < reflected_energy {
+                (expanded, expanded_energy)
+            } else {
+                (reflected, reflected_energy)
+            };
+        } else if reflected_energy < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_energy);
+        } else {
+            let contracted = towards(&centroid, &simplex[n].0, 0.5);
+            let contracted_energy = objective.energy(&contracted);
+            if contracted_energy < simplex[n].1 {
+                simplex[n] = (contracted, contracted_energy);
+            } else {
+                // Shrink the simplex towards the best vertex.
+                let best = simplex[0].0.clone();
+                for vertex in simplex[1..].iter_mut() {
+                    vertex.0 = towards(&best, &vertex.0, 0.5);
+                    vertex.1 = objective.energy(&vertex.0);
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let (parameters, energy) = simplex.swap_remove(0);
+    (parameters, energy, energies)
+}
+
+fn spsa(
+    objective: &mut Objective,
+    initial_parameters: &[f64],
+    max_iterations: usize,
+    rnd_seed: u64,
+) -> (Vec<f64>, f64, Vec<f64>) {
+    let mut rng = ChaCha12Rng::seed_from_u64(rnd_seed);
+    let stability = SPSA_STABILITY * max_iterations as f64;
+    let mut parameters = initial_parameters.to_vec();
+    let mut best_parameters = parameters.clone();
+    let mut best_energy = objective.energy(&parameters);
+    let mut energies = Vec::new();
+    for k in 0..max_iterations {
+        let step = SPSA_STEP / (k as f64 + 1.0 + stability).powf(0.602);
+        let perturbation = SPSA_PERTURBATION / (k as f64 + 1.0).powf(0.101);
+        let direction: Vec<f64> = parameters
+            .iter()
+            .map(|_| if rng.gen::<bool>() { 1.0 } else { -1.0 })
+            .collect();
+        let shifted = |sign: f64| -> Vec<f64> {
+            parameters
+                .iter()
+                .zip(direction.iter())
+                .map(|(p, d)| p + sign * perturbation * d)
+                .collect()
+        };
+        let difference = objective.energy(&shifted(1.0)) - objective.energy(&shifted(-1.0));
+        for (p, d) in parameters.iter_mut().zip(direction.iter()) {
+            // The gradient estimate along the direction, whose components are their own inverses.
+            *p -= step * difference / (2.0 * perturbation) * d;
+        }
+        let energy = objective.energy(&parameters);
+        if energy < best_energy {
+            best_energy = energy;
+            best_parameters = parameters.clone();
+        }
+        energies.push(best_energy);
+    }
+    (best_parameters, best_energy, energies)
+}
+
+// Minimize the energy of the Hamiltonian over the parameters of the ansatz, starting from the
+// initial parameters, on the state vector simulation.
+pub fn vqe(
+    ansatz: &ParameterizedCircuit,
+    hamiltonian: &Hamiltonian,
+    optimizer: Optimizer,
+    initial_parameters: &[f64],
+) -> VqeResult {
+    assert!(
+        initial_parameters.len() == ansatz.parameter_count(),
+        "The number of the initial parameters has to be equal to the number of the parameters {}.",
+        ansatz.parameter_count()
+    );
+    let mut objective = Objective {
+        ansatz,
+        hamiltonian,
+        evaluation_count: 0,
+    };
+    let (parameters, energy, energies) = match optimizer {
+        Optimizer::NelderMead {
+            max_iterations,
+            tolerance,
+        } => nelder_mead(
+            &mut objective,
+            initial_parameters,
+            max_iterations,
+            tolerance,
+        ),
+        Optimizer::Spsa {
+            max_iterations,
+            rnd_seed,
+        } => spsa(&mut objective, initial_parameters, max_iterations, rnd_seed),
+    };
+    VqeResult {
+        energy,
+        parameters,
+        energies,
+        evaluation_count: objective.evaluation_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Gate;
+    use crate::hamiltonian::Pauli;
+
+    #[test]
+    fn optimizers_find_ground_state() {
+        // H = Z0 + X0 + Z1 / 2 with the ground state energy -√2 - 1/2.
+        let mut hamiltonian = Hamiltonian::new();
+        hamiltonian.add_term(1.0, &[(Pauli::Z, 0)]);
+        hamiltonian.add_term(1.0, &[(Pauli::X, 0)]);
+        hamiltonian.add_term(0.5, &[(Pauli::Z, 1)]);
+        let mut ansatz = ParameterizedCircuit::new(2);
+        ansatz.ry(0, 0);
+        ansatz.fixed(Gate::Hadamard(1));
+        ansatz.ry(1, 1);
+        let ground_energy = -std::f64::consts::SQRT_2 - 0.5;
+
+        let result = vqe(
+            &ansatz,
+            &hamiltonian,
+            Optimizer::NelderMead {
+                max_iterations: 200,
+                tolerance: 1e-10,
+            },
+            &[0.1, 0.1],
+        );
+        assert!((result.energy - ground_energy).abs() < 1e-6);
+        assert!(
+            (expectation(&ansatz, &hamiltonian, &result.parameters) - result.energy).abs() < 1e-12
+        );
+        assert!(result.energies.windows(2).all(|pair| pair[1] <= pair[0]));
+
+        let result = vqe(
+            &ansatz,
+            &hamiltonian,
+            Optimizer::Spsa {
+                max_iterations: 300,
+                rnd_seed: 0,
+            },
+            &[0.1, 0.1],
+        );
+        assert!((result.energy - ground_energy).abs() < 1e-3);
+        assert_eq!(result.evaluation_count, 1 + 3 * 300);
+    }
+}
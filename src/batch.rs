@@ -0,0 +1,162 @@
+/*
+Batches of runs of a circuit over many seeds or parameter sets.
+
+Each job of a batch runs the shots of the circuit on its own simulation with its own
+random number generator, so the jobs run on separate threads and the counts of a job
+depend only on its seed, whatever the number of threads. With the parallel feature the jobs
+run on a rayon thread pool, and otherwise on scoped threads, by default one for each available
+core.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::BTreeMap;
+
+use crate::circuit::Circuit;
+use crate::parameterized_circuit::ParameterizedCircuit;
+use crate::state_vector_simulation::QuantumSimulation;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// The number of shots of each measured outcome, the values of the classical bits
+// or of all the qubits if the circuit has no classical bits.
+pub type Counts = BTreeMap<Vec<bool>, usize>;
+
+#[derive(Clone, Debug)]
+pub struct BatchRunner {
+    shot_count: usize,
+    thread_count: usize,
+}
+
+impl BatchRunner {
+    // A runner of the given number of shots for each job, on all the available cores.
+    pub fn new(shot_count: usize) -> BatchRunner {
+        let thread_count = std::thread::available_parallelism().map_or(1, |count| count.get());
+        BatchRunner {
+            shot_count,
+            thread_count,
+        }
+    }
+
+    // Limit the number of threads which run the jobs.
+    pub fn set_thread_count(&mut self, thread_count: usize) {
+        assert!(
+            thread_count > 0,
+            "The number of threads has to be positive."
+        );
+        self.thread_count = thread_count;
+    }
+
+    pub fn shot_count(&self) -> usize {
+        self.shot_count
+    }
+
+    // Run the shots of the circuit with each seed, and add up the counts of all the seeds.
+    pub fn run_seeds(&self, circuit: &Circuit, seeds: &[u64]) -> Counts {
+        let job_counts = self.run_jobs(seeds.len(), |job| self.run_circuit(circuit, seeds[job]));
+        let mut counts = Counts::new();
+        for (outcome, count) in job_counts.into_iter().flatten() {
+            *counts.entry(outcome).or_insert(0) += count;
+        }
+        counts
+    }
+
+    // Run the shots of the circuit bound to each parameter set, the job of the parameter set i
+    // with the seed seed + i, and return the counts of each parameter set.
+    pub fn run_parameters(
+        &self,
+        circuit: &ParameterizedCircuit,
+        parameter_sets: &[Vec<f64>],
+        seed: u64,
+    ) -> Vec<Counts> {
+        self.run_jobs(parameter_sets.len(), |job| {
+            let bound_circuit = circuit.bind(&parameter_sets[job]);
+            self.run_circuit(&bound_circuit, seed.wrapping_add(job as u64))
+        })
+    }
+
+    fn run_circuit(&self, circuit: &Circuit, seed: u64) -> Counts {
+        let mut simulation = QuantumSimulation::new(circuit.qubit_count(), seed);
+        let mut counts = Counts::new();
+        for outcome in simulation.run_shots(circuit, self.shot_count) {
+            *counts.entry(outcome).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // The results of the jobs in their order.
+    #[cfg(feature = "parallel")]
+    fn run_jobs<T, F>(&self, job_count: usize, job: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(usize) -> T + Sync,
+    {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.thread_count)
+            .build()
+            .expect("The thread pool of the batch could not be started.")
+            .install(|| (0..job_count).into_par_iter().map(&job).collect())
+    }
+
+    // The results of the jobs in their order, the thread t running the jobs t, t + n, t + 2n...
+    // of the n threads.
+    #[cfg(not(feature = "parallel"))]
+    fn run_jobs<T, F>(&self, job_count: usize, job: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(usize) -> T + Sync,
+    {
+        let thread_count = self.thread_count.min(job_count);
+        if thread_count <= 1 {
+            return (0..job_count).map(job).collect();
+        }
+        let job = &job;
+        let thread_results: Vec<Vec<T>> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..thread_count)
+                .map(|t| {
+                    scope.spawn(move || (t..job_count).step_by(thread_count).map(job).collect())
+                })
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect()
+        });
+        let mut thread_iters: Vec<_> = thread_results.into_iter().map(Vec::into_iter).collect();
+        (0..job_count)
+            .map(|j| thread_iters[j % thread_count].next().unwrap())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batches_match_sequential_runs() {
+        let mut circuit = ParameterizedCircuit::new(2);
+        circuit.ry(0, 0);
+        circuit.fixed(crate::circuit::Gate::Cnot(0, 1));
+        circuit.rx(1, 1);
+        let parameter_sets = vec![
+            vec![0.3, 1.2],
+            vec![2.0, 0.0],
+            vec![0.0, 3.1],
+            vec![1.0, 1.0],
+        ];
+
+        let mut runner = BatchRunner::new(50);
+        runner.set_thread_count(3);
+        let counts = runner.run_parameters(&circuit, &parameter_sets, 7);
+        for (i, parameters) in parameter_sets.iter().enumerate() {
+            let expected_counts = runner.run_circuit(&circuit.bind(parameters), 7 + i as u64);
+            assert_eq!(counts[i], expected_counts);
+            assert_eq!(counts[i].values().sum::<usize>(), 50);
+        }
+
+        let bound_circuit = circuit.bind(&parameter_sets[0]);
+        let total_counts = runner.run_seeds(&bound_circuit, &[1, 2, 3, 4, 5]);
+        assert_eq!(total_counts.values().sum::<usize>(), 250);
+    }
+}
@@ -0,0 +1,180 @@
+/*
+Standard circuits for benchmarking the simulations.
+
+The quantum Fourier transform mixes every pair of qubits with controlled phases, the random
+Clifford+T circuits apply dense layers of unstructured gates, and the GHZ circuit is a chain
+of CNOTs whose state stays on two basis states. Together they exercise the single-qubit,
+two-qubit, and diagonal kernels, and the sparsity of the states. The model circuits of the
+quantum volume apply the layers of the Haar-random two-qubit unitaries on a random pairing of
+the qubits, and are passed when their heavy outputs, the basis states more likely than the
+median of the ideal distribution, are measured with the probability above 2/3.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::circuit::Circuit;
+use crate::decomposition::{decompose_two_qubit, Matrix4};
+use crate::state_prep::ghz;
+
+// The quantum Fourier transform of the qubits, the qubit 0 being the lowest bit,
+// with the swaps reversing the order of the qubits at the end.
+pub fn qft_circuit(qubit_count: usize) -> Circuit {
+    let mut circuit = Circuit::new(qubit_count);
+    for target in (0..qubit_count).rev() {
+        circuit.hadamard(target);
+        for control in (0..target).rev() {
+            circuit.cphase(PI / (1 << (target - control)) as f64, control, target);
+        }
+    }
+    for qubit_number in 0..qubit_count / 2 {
+        circuit.swap(qubit_number, qubit_count - 1 - qubit_number);
+    }
+    circuit
+}
+
+// The layers of a random H, S, or T gate on each qubit followed by CNOTs on a random pairing
+// of the qubits, the same for a seed.
+pub fn random_clifford_t_circuit(qubit_count: usize, depth: usize, seed: u64) -> Circuit {
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    let mut circuit = Circuit::new(qubit_count);
+    let mut qubit_numbers: Vec<usize> = (0..qubit_count).collect();
+    for _ in 0..depth {
+        for qubit_number in 0..qubit_count {
+            match rng.gen_range(0..3) {
+                0 => circuit.hadamard(qubit_number),
+                1 => circuit.s(qubit_number),
+                _ => circuit.t(qubit_number),
+            }
+        }
+        qubit_numbers.shuffle(&mut rng);
+        for pair in qubit_numbers.chunks_exact(2) {
+            circuit.cnot(pair[0], pair[1]);
+        }
+    }
+    circuit
+}
+
+// A Haar-random two-qubit unitary, the Gram–Schmidt orthonormalization of the columns of
+// a matrix of the standard complex Gaussian entries.
+fn random_unitary(rng: &mut ChaCha12Rng) -> Matrix4 {
+    let mut matrix = [[Complex::new(0.0, 0.0); 4]; 4];
+    for entry in matrix.iter_mut().flatten() {
+        let radius = (-(1.0 - rng.gen::<f64>()).ln()).sqrt();
+        *entry = Complex::from_polar(radius, 2.0 * PI * rng.gen::<f64>());
+    }
+    for column in 0..4 {
+        for previous in 0..column {
+            let projection: Complex<f64> = (0..4)
+                .map(|row| matrix[row][previous].conj() * matrix[row][column])
+                .sum();
+            for row in matrix.iter_mut() {
+                row[column] -= projection * row[previous];
+            }
+        }
+        let norm = (0..4)
+            .map(|row| matrix[row][column].norm_sqr())
+            .sum::<f64>()
+            .sqrt();
+        for row in matrix.iter_mut() {
+            row[column] /= norm;
+        }
+    }
+    matrix
+}
+
+// The model circuit of the quantum volume of the width, with as many layers as qubits, each
+// a random SU(4) unitary on each pair of a random pairing of the qubits, the same for a seed.
+pub fn quantum_volume_circuit(width: usize, seed: u64) -> Circuit {
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    let mut circuit = Circuit::new(width);
+    let mut qubit_numbers: Vec<usize> = (0..width).collect();
+    for _ in 0..width {
+        qubit_numbers.shuffle(&mut rng);
+        for pair in qubit_numbers.chunks_exact(2) {
+            circuit.append(&decompose_two_qubit(&random_unitary(&mut rng)), pair);
+        }
+    }
+    circuit
+}
+
+// The basis states more likely than the median of the ideal probabilities.
+pub fn heavy_outputs(ideal_probabilities: &[f64]) -> Vec<usize> {
+    let mut sorted = ideal_probabilities.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    };
+    (0..ideal_probabilities.len())
+        .filter(|&state| ideal_probabilities[state] > median)
+        .collect()
+}
+
+// The probability of the heavy outputs of the ideal probabilities under the probabilities.
+pub fn heavy_output_probability(ideal_probabilities: &[f64], probabilities: &[f64]) -> f64 {
+    assert!(
+        ideal_probabilities.len() == probabilities.len(),
+        "The number of the probabilities has to be equal to the number of the ideal probabilities {}.",
+        ideal_probabilities.len()
+    );
+    heavy_outputs(ideal_probabilities)
+        .iter()
+        .map(|&state| probabilities[state])
+        .sum()
+}
+
+// The GHZ state (1/sqrt(2))*(|0...0⟩ + |1...1⟩) of the qubits.
+pub fn ghz_circuit(qubit_count: usize) -> Circuit {
+    ghz(qubit_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn qft_of_ground_state_is_uniform() {
+        let mut simulation = QuantumSimulation::new(4, 0u64);
+        qft_circuit(4).run(&mut simulation);
+        assert!(simulation
+            .amplitudes()
+            .iter()
+            .all(|amplitude| (amplitude.re - 0.25).abs() < 1e-12 && amplitude.im.abs() < 1e-12));
+
+        let mut simulation = QuantumSimulation::new(5, 0u64);
+        ghz_circuit(5).run(&mut simulation);
+        let amplitudes = simulation.amplitudes();
+        assert!((amplitudes[0].norm_sqr() - 0.5).abs() < 1e-12);
+        assert!((amplitudes[31].norm_sqr() - 0.5).abs() < 1e-12);
+
+        assert_eq!(random_clifford_t_circuit(6, 3, 1).stats().gate_count, 27);
+    }
+
+    #[test]
+    fn heavy_outputs_of_model_circuit() {
+        // The heavy outputs of the model circuit are measured with about the probability
+        // (1 + ln 2)/2 ≈ 0.85 without the noise, and 1/2 from the uniform distribution.
+        let circuit = quantum_volume_circuit(4, 1);
+        assert!(circuit.stats().gate_counts["cnot"] <= 3 * 4 * 2);
+        let mut simulation = QuantumSimulation::new(4, 0u64);
+        circuit.run(&mut simulation);
+        let ideal: Vec<f64> = simulation
+            .amplitudes()
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .collect();
+        assert_eq!(heavy_outputs(&ideal).len(), 8);
+        assert!(heavy_output_probability(&ideal, &ideal) > 2.0 / 3.0);
+        assert!((heavy_output_probability(&ideal, &[1.0 / 16.0; 16]) - 0.5).abs() < 1e-12);
+    }
+}
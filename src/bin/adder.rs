@@ -0,0 +1,102 @@
+/*
+Quantum addition of classical integers by the ripple-carry adder of Cuccaro et al.
+
+Usage:
+adder [--bits <count>]
+
+The n-bit integers a and b are encoded in the basis states of two registers, the adder block
+writes a + b into the register b and the carry qubit, and the measured sum is checked against
+the classical sum for all the 2^(2n) pairs of the inputs.
+
+Example run:
+cargo run --release --bin adder -- --bits 3
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::blocks::adder;
+use quantum_simulation::register::{QubitAllocator, RegisterOperations};
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: adder [--bits <count>]";
+const DEFAULT_BIT_COUNT: usize = 2;
+// The adder of n bits has 2n + 2 qubits.
+const MAX_BIT_COUNT: usize = 10;
+
+fn parse_bit_count(arguments: &[String]) -> Result<usize, String> {
+    let mut arguments = arguments.iter();
+    let mut bit_count = DEFAULT_BIT_COUNT;
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--bits" => {
+                bit_count = arguments
+                    .next()
+                    .ok_or("The option --bits needs a value.")?
+                    .parse()
+                    .map_err(|_| "The number of bits has to be a positive integer.")?;
+                if bit_count == 0 || bit_count > MAX_BIT_COUNT {
+                    return Err(format!(
+                        "The number of bits has to be from 1 to {}.",
+                        MAX_BIT_COUNT
+                    ));
+                }
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(bit_count)
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let n = match parse_bit_count(&arguments) {
+        Ok(bit_count) => bit_count,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!("Quantum ripple-carry adder of {} bits:", n);
+    let circuit = adder(n);
+    // The registers in the order of the qubits of the adder block.
+    let mut allocator = QubitAllocator::new();
+    let a_register = allocator.alloc_register("a", n);
+    let b_register = allocator.alloc_register("b", n);
+    allocator.alloc_qubit("ancilla");
+    let carry_register = allocator.alloc_register("carry", 1);
+    let mut simulation = QuantumSimulation::new(allocator.qubit_count(), 0u64);
+    let mut error_count = 0;
+    for a in 0..1u64 << n {
+        for b in 0..1u64 << n {
+            simulation.reset();
+            simulation.prepare_value(&a_register, a);
+            simulation.prepare_value(&b_register, b);
+            circuit.run(&mut simulation);
+            // The sum is the register b with the carry qubit as its highest bit.
+            let sum = simulation.measure_value(&b_register)
+                + (simulation.measure_value(&carry_register) << n);
+            if sum != a + b {
+                println!("{} + {} = {}, expected {}", a, b, sum, a + b);
+                error_count += 1;
+            } else if n <= 2 {
+                println!("{} + {} = {}", a, b, sum);
+            }
+        }
+    }
+    println!(
+        "{} of {} sums are correct, with {} gates.",
+        (1 << (2 * n)) - error_count,
+        1 << (2 * n),
+        circuit.gates().len()
+    );
+    println!();
+    if error_count == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -4,6 +4,8 @@ Algorithm to produce Bell state.
 Bell state is a maximum entanglement state of the two qubits:
 (1/sqrt(2))*(|00⟩ + |11⟩)
 
+The measured bits of the two qubits are always equal, so their correlation is 1.
+
 Example run:
 cargo run --bin bell_state
 
@@ -16,6 +18,8 @@ use quantum_simulation::state_vector_simulation::QuantumSimulation;
 
 const QUBIT_COUNT: usize = 2;
 const RUN_COUNT: usize = 100;
+const HISTOGRAM_WIDTH: usize = 40;
+const AMPLITUDE_THRESHOLD: f64 = 1e-9;
 
 // Entangle two qubits into a Bell state (1/sqrt(2))*(|00⟩ + |11⟩).
 // Assume that the qubits are initialized to the state |00⟩.
@@ -27,13 +31,16 @@ fn entangle_into_bell_state(simulation: &mut dyn Simulation, qubit0: usize, qubi
 fn main() {
     println!("Bell state:");
     let mut simulation = QuantumSimulation::new(QUBIT_COUNT, 0u64);
-    let mut measurements = Vec::with_capacity(RUN_COUNT);
-    for _ in 0..RUN_COUNT {
-        simulation.reset();
-        entangle_into_bell_state(&mut simulation, 0, 1);
-        let measured_states = simulation.measure_all();
-        measurements.push(measured_states);
-    }
-    evaluate(measurements);
+    entangle_into_bell_state(&mut simulation, 0, 1);
+    simulation.print_state(AMPLITUDE_THRESHOLD);
+    // The measurements of all the runs are drawn from the entangled state at once.
+    let measurements = simulation.sample(&[0, 1], RUN_COUNT);
+    let result = evaluate(measurements);
+    result.print();
+    result.print_histogram(HISTOGRAM_WIDTH, 0.0);
+    println!(
+        "Correlation of the qubits 0 and 1: {:.3}",
+        result.correlations().correlation[0][1]
+    );
     println!();
 }
@@ -0,0 +1,139 @@
+/*
+Benchmark of the simulations on the standard circuits.
+
+Usage:
+bench [--qubits <count>] [--depth <depth>] [--seed <seed>] [--backend statevector|f32|sparse]
+
+Each of the QFT, random Clifford+T, and GHZ circuits is run once from the ground state,
+and the number of gates applied per second is printed with the peak memory of the process.
+A backend is benchmarked in its own process, so that its peak memory is not the one of
+another backend.
+
+Example run:
+cargo run --release --bin bench -- --qubits 24 --depth 20
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+use std::time::Instant;
+
+use quantum_simulation::benchmarks::{ghz_circuit, qft_circuit, random_clifford_t_circuit};
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::sparse_simulation::SparseSimulation;
+use quantum_simulation::state_vector_simulation::{QuantumSimulation, QuantumSimulationF32};
+
+const USAGE: &str = "Usage: bench [--qubits <count>] [--depth <depth>] [--seed <seed>] [--backend statevector|f32|sparse]";
+const DEFAULT_QUBIT_COUNT: usize = 20;
+const DEFAULT_DEPTH: usize = 10;
+const BACKENDS: [&str; 3] = ["statevector", "f32", "sparse"];
+
+struct Options {
+    qubit_count: usize,
+    depth: usize,
+    seed: u64,
+    backend: String,
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        qubit_count: DEFAULT_QUBIT_COUNT,
+        depth: DEFAULT_DEPTH,
+        seed: 0,
+        backend: BACKENDS[0].to_string(),
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--qubits" => {
+                options.qubit_count = value("--qubits")?
+                    .parse()
+                    .map_err(|_| "The number of qubits has to be a nonnegative integer.")?
+            }
+            "--depth" => {
+                options.depth = value("--depth")?
+                    .parse()
+                    .map_err(|_| "The depth has to be a nonnegative integer.")?
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            "--backend" => {
+                let backend = value("--backend")?;
+                if !BACKENDS.contains(&backend.as_str()) {
+                    return Err(format!("Unknown backend {}.", backend));
+                }
+                options.backend = backend.clone();
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+fn new_simulation(backend: &str, qubit_count: usize, seed: u64) -> Box<dyn Simulation> {
+    match backend {
+        "f32" => Box::new(QuantumSimulationF32::new(qubit_count, seed)),
+        "sparse" => Box::new(SparseSimulation::new(qubit_count, seed)),
+        _ => Box::new(QuantumSimulation::new(qubit_count, seed)),
+    }
+}
+
+// The peak resident memory of the process in bytes, on Linux only.
+fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let circuits: [(&str, Circuit); 3] = [
+        ("qft", qft_circuit(options.qubit_count)),
+        (
+            "clifford+t",
+            random_clifford_t_circuit(options.qubit_count, options.depth, options.seed),
+        ),
+        ("ghz", ghz_circuit(options.qubit_count)),
+    ];
+    println!(
+        "Backend: {}, qubit count: {}",
+        options.backend, options.qubit_count
+    );
+    for (name, circuit) in circuits.iter() {
+        let mut simulation = new_simulation(&options.backend, options.qubit_count, options.seed);
+        let start = Instant::now();
+        circuit.run(simulation.as_mut());
+        let elapsed = start.elapsed();
+        let gate_count = circuit.gates().len();
+        println!(
+            "{}: {} gates in {:.3} s, {:.0} gates/s",
+            name,
+            gate_count,
+            elapsed.as_secs_f64(),
+            gate_count as f64 / elapsed.as_secs_f64()
+        );
+    }
+    match peak_memory() {
+        Some(bytes) => println!("Peak memory: {:.1} MiB", bytes as f64 / (1 << 20) as f64),
+        None => println!("Peak memory: unknown"),
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,195 @@
+/*
+Three-qubit bit-flip code with the syndrome extraction by ancilla qubits.
+
+Usage:
+bitflip_code [--error-qubit <0|1|2>] [--probability <p>] [--seed <seed>]
+
+The state α|0⟩ + β|1⟩ is encoded into α|000⟩ + β|111⟩, which an X error on any one qubit takes
+out of the code space. The parities Z0 Z1 and Z1 Z2 are copied onto two ancilla qubits and
+measured in the middle of the circuit, and the syndrome given by the two classical bits
+selects the qubit flipped back by a conditional X gate, without measuring the encoded state.
+The error is either an X gate on the qubit given by --error-qubit, or the bit-flip channel of
+the probability p on each qubit, which the code corrects unless two or three qubits are
+flipped, with the probability 3p^2 - 2p^3 instead of p.
+
+Example run:
+cargo run --release --bin bitflip_code -- --probability 0.1
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use num_complex::Complex;
+
+use quantum_simulation::circuit::{Circuit, Condition, Gate};
+use quantum_simulation::noise::KrausChannel;
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str =
+    "Usage: bitflip_code [--error-qubit <0|1|2>] [--probability <p>] [--seed <seed>]";
+const QUBIT_COUNT: usize = 5;
+const DATA_QUBITS: [usize; 3] = [0, 1, 2];
+const RUN_COUNT: usize = 1000;
+// The angle of the RY rotation of the encoded state.
+const THETA: f64 = 1.0;
+
+enum Error {
+    Qubit(usize),
+    Probability(f64),
+}
+
+struct Options {
+    error: Error,
+    seed: u64,
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        error: Error::Qubit(0),
+        seed: 0,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--error-qubit" => {
+                let qubit_number = value("--error-qubit")?
+                    .parse()
+                    .map_err(|_| "The error qubit has to be 0, 1, or 2.")?;
+                if qubit_number > 2 {
+                    return Err("The error qubit has to be 0, 1, or 2.".to_string());
+                }
+                options.error = Error::Qubit(qubit_number);
+            }
+            "--probability" => {
+                let probability: f64 = value("--probability")?
+                    .parse()
+                    .map_err(|_| "The probability has to be a number.")?;
+                if !(0.0..=1.0).contains(&probability) {
+                    return Err("The probability has to be in the interval [0, 1].".to_string());
+                }
+                options.error = Error::Probability(probability);
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+// The state RY(θ)|0⟩ of the qubit 0 encoded into the qubits 0, 1, and 2.
+fn encoding_circuit() -> Circuit {
+    let mut circuit = Circuit::new(QUBIT_COUNT);
+    circuit.ry(THETA, 0);
+    circuit.cnot(0, 1);
+    circuit.cnot(0, 2);
+    circuit
+}
+
+// The syndrome extraction into the classical bits 0 and 1, the conditional correction, and the
+// decoding back into the qubit 0.
+fn correction_circuit() -> Circuit {
+    let mut circuit = Circuit::with_clbits(QUBIT_COUNT, 2);
+    circuit.cnot(0, 3);
+    circuit.cnot(1, 3);
+    circuit.cnot(1, 4);
+    circuit.cnot(2, 4);
+    circuit.measure(3, 0);
+    circuit.measure(4, 1);
+    // The syndrome 01 flags the qubit 0, 11 the qubit 1, and 10 the qubit 2.
+    for (syndrome, qubit_number) in [(0b01, 0), (0b11, 1), (0b10, 2)] {
+        let condition = Condition {
+            first_clbit: 0,
+            clbit_count: 2,
+            value: syndrome,
+        };
+        circuit.push_conditional(Gate::PauliX(qubit_number), condition);
+    }
+    circuit.cnot(0, 2);
+    circuit.cnot(0, 1);
+    circuit
+}
+
+// The fidelity of the qubit 0 with the state RY(θ)|0⟩, summed over the states of the other qubits.
+fn fidelity(amplitudes: &[Complex<f64>]) -> f64 {
+    let (alpha, beta) = ((THETA / 2.0).cos(), (THETA / 2.0).sin());
+    amplitudes
+        .chunks_exact(2)
+        .map(|pair| (alpha * pair[0] + beta * pair[1]).norm_sqr())
+        .sum()
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!("Three-qubit bit-flip code:");
+    let encoding = encoding_circuit();
+    let correction = correction_circuit();
+    let mut simulation = QuantumSimulation::new(QUBIT_COUNT, options.seed);
+    let mut logical_fidelity = 0.0;
+    let mut syndrome_counts = [0usize; 4];
+    for _ in 0..RUN_COUNT {
+        simulation.reset();
+        encoding.run(&mut simulation);
+        match options.error {
+            Error::Qubit(qubit_number) => simulation.pauli_x(qubit_number),
+            Error::Probability(probability) => {
+                let channel = KrausChannel::bit_flip(probability);
+                for qubit_number in DATA_QUBITS {
+                    simulation.apply_kraus(channel.kraus_operators(), qubit_number);
+                }
+            }
+        }
+        let clbits = correction.run(&mut simulation);
+        syndrome_counts[clbits[0] as usize | (clbits[1] as usize) << 1] += 1;
+        logical_fidelity += fidelity(&simulation.amplitudes());
+    }
+    logical_fidelity /= RUN_COUNT as f64;
+
+    // The same error on the unencoded qubit, X RY(θ)|0⟩ having the fidelity sin^2 θ.
+    let flipped_fidelity = THETA.sin().powi(2);
+    let physical_fidelity = match options.error {
+        Error::Qubit(qubit_number) => {
+            println!("Error: X on the qubit {}", qubit_number);
+            flipped_fidelity
+        }
+        Error::Probability(probability) => {
+            println!("Error: bit flip with the probability {}", probability);
+            println!(
+                "Expected logical error probability: {:.4}",
+                3.0 * probability.powi(2) - 2.0 * probability.powi(3)
+            );
+            1.0 - probability + probability * flipped_fidelity
+        }
+    };
+    for (syndrome, count) in syndrome_counts.iter().enumerate() {
+        println!(
+            "Syndrome {}{}: {}/{}",
+            syndrome >> 1,
+            syndrome & 1,
+            count,
+            RUN_COUNT
+        );
+    }
+    println!("Logical fidelity: {:.4}", logical_fidelity);
+    println!("Unencoded fidelity: {:.4}", physical_fidelity);
+    println!();
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,61 @@
+/*
+CHSH (Clauser-Horne-Shimony-Holt) violation of the Bell inequality.
+
+Alice and Bob share the Bell state (1/sqrt(2))*(|00⟩ + |11⟩) and measure their qubits along
+the directions in the X-Z plane of the angles a0 = 0 or a1 = π/2, and b0 = π/4 or b1 = -π/4.
+The correlation E(a, b) of the ±1 outcomes is cos(a - b), and
+S = E(a0, b0) + E(a0, b1) + E(a1, b0) - E(a1, b1)
+reaches 2√2, above the bound |S| ≤ 2 of all the local hidden variable theories.
+
+Example run:
+cargo run --bin chsh
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2};
+
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const RUN_COUNT: usize = 10000;
+const ALICE_ANGLES: [f64; 2] = [0.0, FRAC_PI_2];
+const BOB_ANGLES: [f64; 2] = [FRAC_PI_4, -FRAC_PI_4];
+
+// The mean of the product of the ±1 outcomes of the measurements along the angles.
+fn correlation(simulation: &mut QuantumSimulation, alice_angle: f64, bob_angle: f64) -> f64 {
+    let mut sum = 0.0;
+    for _ in 0..RUN_COUNT {
+        simulation.reset();
+        simulation.hadamard(0);
+        simulation.cnot(0, 1);
+        let alice_value = simulation.measure_along(alice_angle, 0.0, 0);
+        let bob_value = simulation.measure_along(bob_angle, 0.0, 1);
+        sum += if alice_value == bob_value { 1.0 } else { -1.0 };
+    }
+    sum / RUN_COUNT as f64
+}
+
+fn main() {
+    println!("CHSH inequality:");
+    let mut simulation = QuantumSimulation::new(2, 0u64);
+    let mut s = 0.0;
+    for (i, &alice_angle) in ALICE_ANGLES.iter().enumerate() {
+        for (j, &bob_angle) in BOB_ANGLES.iter().enumerate() {
+            let value = correlation(&mut simulation, alice_angle, bob_angle);
+            println!("E(a{}, b{}) = {:.4}", i, j, value);
+            s += if i == 1 && j == 1 { -value } else { value };
+        }
+    }
+    println!("S = {:.4}", s);
+    println!(
+        "Classical bound: 2, quantum bound 2√2 = {:.4}",
+        2.0 * SQRT_2
+    );
+    if s > 2.0 {
+        println!("The Bell inequality is violated.");
+    } else {
+        println!("The Bell inequality is not violated.");
+    }
+    println!();
+}
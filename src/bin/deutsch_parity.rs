@@ -12,10 +12,10 @@ Copyright © 2024 AlgoHertz. All rights reserved.
 */
 
 use quantum_simulation::evaluation::evaluate;
+use quantum_simulation::register::{QubitAllocator, QubitRegister, RegisterOperations};
 use quantum_simulation::simulation::Simulation;
 use quantum_simulation::state_vector_simulation::QuantumSimulation;
 
-const QUBIT_COUNT: usize = 2;
 const RUN_COUNT: usize = 100;
 
 // Deutsch's Algorithm to find out the parity problem of the function f.
@@ -25,28 +25,26 @@ const RUN_COUNT: usize = 100;
 // Q1: ∣0⟩ ---------- |H| -------- |     | -- |H| -- = parity
 fn apply_deutsch_algo(
     simulation: &mut dyn Simulation,
-    aux_qubit: usize,
-    target_qubit: usize,
+    aux_register: &QubitRegister,
+    target_register: &QubitRegister,
     f: fn(bool) -> bool,
-) -> Vec<bool> {
-    simulation.pauli_x(aux_qubit);
-    simulation.hadamard(aux_qubit);
-    simulation.hadamard(target_qubit);
-    simulation.apply_u_f(f, aux_qubit, target_qubit);
-    simulation.hadamard(target_qubit);
-
-    simulation.measure(vec![target_qubit])
+) {
+    simulation.pauli_x_register(aux_register);
+    simulation.hadamard_register(aux_register);
+    simulation.hadamard_register(target_register);
+    simulation.apply_u_f_register(f, aux_register, target_register);
+    simulation.hadamard_register(target_register);
 }
 
 fn run_deutsch_algo(f: fn(bool) -> bool, run_count: usize) {
-    let mut simulation = QuantumSimulation::new(QUBIT_COUNT, 0u64);
-    let mut measurements = Vec::with_capacity(RUN_COUNT);
-    for _ in 0..run_count {
-        simulation.reset();
-        let measured_states = apply_deutsch_algo(&mut simulation, 0, 1, f);
-        measurements.push(measured_states);
-    }
-    evaluate(measurements);
+    let mut allocator = QubitAllocator::new();
+    let aux_register = allocator.alloc_register("aux", 1);
+    let target_register = allocator.alloc_register("target", 1);
+    let mut simulation = QuantumSimulation::new(allocator.qubit_count(), 0u64);
+    apply_deutsch_algo(&mut simulation, &aux_register, &target_register, f);
+    // The target qubit of all the runs is measured from the final state at once.
+    let target_qubits: Vec<usize> = target_register.qubits().collect();
+    evaluate(simulation.sample(&target_qubits, run_count)).print();
 }
 
 fn main() {
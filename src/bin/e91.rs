@@ -0,0 +1,101 @@
+/*
+E91 quantum key distribution of Ekert with entangled pairs.
+
+A source sends a qubit of each Bell pair (1/sqrt(2))*(|00⟩ + |11⟩) to Alice and the other to
+Bob, who measure them along the directions in the X-Z plane chosen at random, Alice of the
+angles 0, π/4, and π/2 and Bob of the angles π/4, π/2, and 3π/4. The pairs measured along the
+same direction give equal bits, which make up the key. The pairs measured along the angles
+0 or π/2 by Alice and π/4 or 3π/4 by Bob give the CHSH value
+S = E(0, π/4) - E(0, 3π/4) + E(π/2, π/4) + E(π/2, 3π/4)
+of 2√2 for the entangled pairs. An eavesdropper measuring the qubits of Bob on the way breaks
+the entanglement, which brings S to at most 2 and reveals her.
+
+Example run:
+cargo run --release --bin e91
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const PAIR_COUNT: usize = 5000;
+const ALICE_ANGLES: [f64; 3] = [0.0, FRAC_PI_4, FRAC_PI_2];
+const BOB_ANGLES: [f64; 3] = [FRAC_PI_4, FRAC_PI_2, 3.0 * FRAC_PI_4];
+// The CHSH value below which the pairs are taken as not entangled enough for a secure key.
+const MIN_CHSH_VALUE: f64 = 2.5;
+
+struct Outcome {
+    key_length: usize,
+    // The fraction of the key bits of Alice and Bob differing.
+    error_rate: f64,
+    chsh_value: f64,
+}
+
+fn distribute_key(is_eavesdropped: bool, rng: &mut ChaCha12Rng) -> Outcome {
+    let mut simulation = QuantumSimulation::new(2, rng.gen());
+    let (mut key_length, mut error_count) = (0, 0);
+    // The sums of the products of the ±1 outcomes and the numbers of the pairs of each setting.
+    let mut sums = [[0.0; 3]; 3];
+    let mut counts = [[0usize; 3]; 3];
+    for _ in 0..PAIR_COUNT {
+        simulation.reset();
+        simulation.hadamard(0);
+        simulation.cnot(0, 1);
+        if is_eavesdropped {
+            simulation.measure(vec![1]);
+        }
+        let (i, j) = (rng.gen_range(0..3), rng.gen_range(0..3));
+        let alice_value = simulation.measure_along(ALICE_ANGLES[i], 0.0, 0);
+        let bob_value = simulation.measure_along(BOB_ANGLES[j], 0.0, 1);
+        sums[i][j] += if alice_value == bob_value { 1.0 } else { -1.0 };
+        counts[i][j] += 1;
+        if ALICE_ANGLES[i] == BOB_ANGLES[j] {
+            key_length += 1;
+            if alice_value != bob_value {
+                error_count += 1;
+            }
+        }
+    }
+
+    let correlation = |i: usize, j: usize| sums[i][j] / counts[i][j] as f64;
+    Outcome {
+        key_length,
+        error_rate: error_count as f64 / key_length as f64,
+        chsh_value: correlation(0, 0) - correlation(0, 2) + correlation(2, 0) + correlation(2, 2),
+    }
+}
+
+fn main() {
+    println!("E91 quantum key distribution:");
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    for is_eavesdropped in [false, true] {
+        if is_eavesdropped {
+            println!("With an eavesdropper:");
+        } else {
+            println!("Without an eavesdropper:");
+        }
+        let outcome = distribute_key(is_eavesdropped, &mut rng);
+        println!(
+            "  Key length: {} of {} pairs",
+            outcome.key_length, PAIR_COUNT
+        );
+        println!("  Key error rate: {:.2}%", 100.0 * outcome.error_rate);
+        println!(
+            "  CHSH value S: {:.3} (quantum bound {:.3})",
+            outcome.chsh_value,
+            2.0 * SQRT_2
+        );
+        if outcome.chsh_value >= MIN_CHSH_VALUE {
+            println!("  The key is accepted.");
+        } else {
+            println!("  The eavesdropper is detected and the key is discarded.");
+        }
+    }
+    println!();
+}
@@ -0,0 +1,94 @@
+/*
+Entanglement swapping of two Bell pairs.
+
+The qubits 0 and 1 and the qubits 2 and 3 are prepared in the Bell states
+(1/sqrt(2))*(|00⟩ + |11⟩), and the middle qubits 1 and 2 are measured in the Bell basis by
+a CNOT gate, a Hadamard gate, and the measurements into two classical bits. The outer qubits 0
+and 3, which never interacted, are then left in one of the four Bell states given by the
+classical bits, and the X and Z gates on the qubit 3 conditional on them, the feed-forward,
+turn it into (1/sqrt(2))*(|00⟩ + |11⟩). Its correlations ⟨XX⟩ = 1, ⟨YY⟩ = -1, and ⟨ZZ⟩ = 1 of the
+outer qubits show the entanglement, while without the feed-forward they average to zero over
+the outcomes of the Bell measurement.
+
+Example run:
+cargo run --release --bin entanglement_swapping
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::circuit::{Circuit, Condition, Gate};
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const QUBIT_COUNT: usize = 4;
+const RUN_COUNT: usize = 2000;
+// The tolerance of the measured correlations, about 5 standard deviations of the mean.
+const TOLERANCE: f64 = 0.12;
+
+fn swapping_circuit(is_corrected: bool) -> Circuit {
+    let mut circuit = Circuit::with_clbits(QUBIT_COUNT, 2);
+    circuit.hadamard(0);
+    circuit.cnot(0, 1);
+    circuit.hadamard(2);
+    circuit.cnot(2, 3);
+    // The Bell measurement of the qubits 1 and 2.
+    circuit.cnot(1, 2);
+    circuit.hadamard(1);
+    circuit.measure(1, 0);
+    circuit.measure(2, 1);
+    if is_corrected {
+        circuit.push_conditional(Gate::PauliX(3), Condition::bit(1, true));
+        circuit.push_conditional(Gate::PauliZ(3), Condition::bit(0, true));
+    }
+    circuit
+}
+
+// The correlation of the ±1 outcomes of the qubits 0 and 3 measured in the basis.
+fn correlation(simulation: &mut QuantumSimulation, circuit: &Circuit, basis: char) -> f64 {
+    let mut sum = 0.0;
+    for _ in 0..RUN_COUNT {
+        simulation.reset();
+        circuit.run(simulation);
+        let (value0, value3) = match basis {
+            'X' => (simulation.measure_x(0), simulation.measure_x(3)),
+            'Y' => (simulation.measure_y(0), simulation.measure_y(3)),
+            _ => (
+                simulation.measure(vec![0])[0],
+                simulation.measure(vec![3])[0],
+            ),
+        };
+        sum += if value0 == value3 { 1.0 } else { -1.0 };
+    }
+    sum / RUN_COUNT as f64
+}
+
+fn main() -> ExitCode {
+    println!("Entanglement swapping:");
+    let mut simulation = QuantumSimulation::new(QUBIT_COUNT, 0u64);
+    let expected_correlations = [('X', 1.0), ('Y', -1.0), ('Z', 1.0)];
+    let mut is_entangled = true;
+    for is_corrected in [true, false] {
+        if is_corrected {
+            println!("With the feed-forward:");
+        } else {
+            println!("Without the feed-forward:");
+        }
+        let circuit = swapping_circuit(is_corrected);
+        for (basis, expected) in expected_correlations {
+            let value = correlation(&mut simulation, &circuit, basis);
+            println!("  ⟨{}{}⟩ of the qubits 0 and 3: {:.3}", basis, basis, value);
+            if is_corrected {
+                is_entangled &= (value - expected).abs() < TOLERANCE;
+            }
+        }
+    }
+    println!();
+
+    if is_entangled {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
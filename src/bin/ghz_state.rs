@@ -1,46 +1,102 @@
 /*
 Algorithm to produce GHZ (Greenberger-Horne-Zeilinger) state.
 
-GHZ state is a maximum entanglement state of the three qubits:
-(1/sqrt(2))*(|000⟩ + |111⟩)
+GHZ state is a maximum entanglement state of the n qubits:
+(1/sqrt(2))*(|0...0⟩ + |1...1⟩)
+
+Usage:
+ghz_state [--qubits <count>]
+
+The measured values of all the qubits are equal in each run, so the parity Z_i Z_j of each pair
+of qubits is +1, while each qubit alone is |0⟩ or |1⟩ with the probability 1/2.
 
 Example run:
-cargo run --bin ghz_state
+cargo run --bin ghz_state -- --qubits 5
 
 Copyright © 2024 AlgoHertz. All rights reserved.
 */
 
-use quantum_simulation::evaluation::evaluate;
-use quantum_simulation::simulation::Simulation;
+use std::process::ExitCode;
+
+use quantum_simulation::evaluation::{evaluate, EvaluationResult};
+use quantum_simulation::state_prep::ghz;
 use quantum_simulation::state_vector_simulation::QuantumSimulation;
 
-const QUBIT_COUNT: usize = 3;
+const USAGE: &str = "Usage: ghz_state [--qubits <count>]";
+const DEFAULT_QUBIT_COUNT: usize = 3;
 const RUN_COUNT: usize = 100;
 
-// Entangle three qubits into a GHZ state (1/sqrt(2))*(|000⟩ + |111⟩).
-// Assume that the qubits are initialized to the state |000⟩.
-fn entangle_into_ghz_state(
-    simulation: &mut dyn Simulation,
-    qubit0: usize,
-    qubit1: usize,
-    qubit2: usize,
-) {
-    simulation.hadamard(qubit0);
-    simulation.cnot(qubit0, qubit1);
-    simulation.cnot(qubit1, qubit2);
+fn parse_qubit_count(arguments: &[String]) -> Result<usize, String> {
+    let mut arguments = arguments.iter();
+    let mut qubit_count = DEFAULT_QUBIT_COUNT;
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--qubits" => {
+                qubit_count = arguments
+                    .next()
+                    .ok_or("The option --qubits needs a value.")?
+                    .parse()
+                    .map_err(|_| "The number of qubits has to be a positive integer.")?;
+                if qubit_count == 0 {
+                    return Err("The number of qubits has to be a positive integer.".to_string());
+                }
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(qubit_count)
 }
 
-fn main() {
+// The mean of the parity Z_i Z_j of the qubits i and j, +1 for the equal values.
+fn parity_correlation(result: &EvaluationResult, i: usize, j: usize) -> f64 {
+    result.expectation(|index| {
+        if (index >> i) & 1 == (index >> j) & 1 {
+            1.0
+        } else {
+            -1.0
+        }
+    })
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let qubit_count = match parse_qubit_count(&arguments) {
+        Ok(qubit_count) => qubit_count,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
     println!("GHZ state:");
-    let mut simulation = QuantumSimulation::new(QUBIT_COUNT, 0u64);
-    let mut measurements = Vec::with_capacity(RUN_COUNT);
-    for _ in 0..RUN_COUNT {
-        simulation.reset();
-        entangle_into_ghz_state(&mut simulation, 0, 1, 2);
-        let measured_states = simulation.measure_all();
-        measurements.push(measured_states);
+    let mut simulation = QuantumSimulation::new(qubit_count, 0u64);
+    ghz(qubit_count).run(&mut simulation);
+    // The measurements of all the runs are drawn from the entangled state at once.
+    let qubit_numbers: Vec<usize> = (0..qubit_count).collect();
+    let measurements = simulation.sample(&qubit_numbers, RUN_COUNT);
+    let equal_count = measurements
+        .iter()
+        .filter(|values| values.iter().all(|&value| value == values[0]))
+        .count();
+    let result = evaluate(measurements);
+
+    let mut min_correlation: f64 = 1.0;
+    for i in 0..qubit_count {
+        for j in i + 1..qubit_count {
+            min_correlation = min_correlation.min(parity_correlation(&result, i, j));
+        }
     }
-    evaluate(measurements);
+    result.print();
+    println!(
+        "Runs with all the qubits equal: {}/{}",
+        equal_count, RUN_COUNT
+    );
+    println!("Least parity correlation <Z_i Z_j>: {:?}", min_correlation);
 
     println!();
+    if equal_count == RUN_COUNT {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
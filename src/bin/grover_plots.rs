@@ -0,0 +1,130 @@
+/*
+Charts of the Grover search.
+
+Usage:
+grover_plots [--output <directory>]
+
+The search for the marked item among the N = 16 items of 4 qubits is run for 0 to 8 iterations
+of the amplitude amplification. The probability of the marked item after k iterations is
+sin^2((2k + 1)θ) with sin θ = 1/sqrt(N), the largest after 3 iterations, which the line plot of
+grover_probabilities.png compares with the simulated one. The histogram of grover_histogram.png
+shows the measurements of the shots after the best number of iterations.
+
+Example run:
+cargo run --features plots --bin grover_plots -- --output /tmp
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use quantum_simulation::algorithms::{amplitude_amplification, optimal_iteration_count};
+use quantum_simulation::blocks::mcx_with_ancilla;
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::evaluation::{evaluate, save_probability_plot_png};
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: grover_plots [--output <directory>]";
+const QUBIT_COUNT: usize = 4;
+const MARKED_ITEM: usize = 0b1011;
+const MAX_ITERATION_COUNT: usize = 8;
+const SHOT_COUNT: usize = 1000;
+
+fn parse_output_directory(arguments: &[String]) -> Result<PathBuf, String> {
+    let mut arguments = arguments.iter();
+    let mut directory = PathBuf::from(".");
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--output" => {
+                directory = arguments
+                    .next()
+                    .ok_or("The option --output needs a value.")?
+                    .into()
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(directory)
+}
+
+// The oracle flipping the phase of the marked item, the multi-controlled Z of the qubits between
+// the X gates of the bits 0 of the item.
+fn oracle() -> Circuit {
+    let target = QUBIT_COUNT - 1;
+    let mcx = mcx_with_ancilla(target);
+    let mut circuit = Circuit::new(mcx.qubit_count());
+    let flip_zeros = |circuit: &mut Circuit| {
+        for qubit_number in (0..QUBIT_COUNT).filter(|&q| (MARKED_ITEM >> q) & 1 == 0) {
+            circuit.pauli_x(qubit_number);
+        }
+    };
+    flip_zeros(&mut circuit);
+    circuit.hadamard(target);
+    circuit.extend(&mcx);
+    circuit.hadamard(target);
+    flip_zeros(&mut circuit);
+    circuit
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let directory = match parse_output_directory(&arguments) {
+        Ok(directory) => directory,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!("Grover search charts:");
+    let mut state_prep = Circuit::new(QUBIT_COUNT);
+    for qubit_number in 0..QUBIT_COUNT {
+        state_prep.hadamard(qubit_number);
+    }
+    let oracle = oracle();
+    let item_count = 1 << QUBIT_COUNT;
+    let theta = (1.0 / item_count as f64).sqrt().asin();
+    let best_iteration_count = optimal_iteration_count(1.0 / item_count as f64);
+    let mut simulated = Vec::new();
+    let mut predicted = Vec::new();
+    let mut measurements = Vec::new();
+    for iteration_count in 0..=MAX_ITERATION_COUNT {
+        let circuit = amplitude_amplification(&state_prep, &oracle, iteration_count);
+        let mut simulation = QuantumSimulation::new(circuit.qubit_count(), 0u64);
+        circuit.run(&mut simulation);
+        // The ancilla qubits of the oracle are back in |0⟩.
+        simulated.push(simulation.amplitudes()[MARKED_ITEM].norm_sqr());
+        predicted.push(((2 * iteration_count + 1) as f64 * theta).sin().powi(2));
+        if iteration_count == best_iteration_count {
+            let qubit_numbers: Vec<usize> = (0..QUBIT_COUNT).collect();
+            measurements = simulation.sample(&qubit_numbers, SHOT_COUNT);
+        }
+    }
+    for (iteration_count, probability) in simulated.iter().enumerate() {
+        println!(
+            "{} iterations: probability of the marked item {:.4}",
+            iteration_count, probability
+        );
+    }
+
+    let probabilities_path = directory.join("grover_probabilities.png");
+    let histogram_path = directory.join("grover_histogram.png");
+    let saved = save_probability_plot_png(
+        &probabilities_path,
+        "Probability of the marked item",
+        &[("simulated", &simulated), ("sin²((2k + 1)θ)", &predicted)],
+    )
+    .and_then(|_| evaluate(measurements).save_histogram_png(&histogram_path));
+    if let Err(error) = saved {
+        eprintln!("The charts could not be saved: {}", error);
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "Saved {} and {}",
+        probabilities_path.display(),
+        histogram_path.display()
+    );
+    println!();
+    ExitCode::SUCCESS
+}
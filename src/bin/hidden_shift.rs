@@ -0,0 +1,158 @@
+/*
+Hidden shift problem of the bent functions.
+
+Usage:
+hidden_shift [--bits <count>]
+
+The inner product f(x, y) = x·y of two n-bit strings is a bent function of 2n bits: its
+Walsh–Hadamard transform has the same magnitude 2^n at every point, with the signs given by
+the dual function, which for the inner product is f itself. Given the phase oracles of the
+shifted function g(z) = f(z ⊕ s) and of the dual of f, the algorithm of Rötteler finds the
+hidden shift s with one query of each: the Hadamard gates turn the phases (-1)^g(z) into
+(-1)^(f(w) + w·s), the dual oracle removes (-1)^f(w), and the Hadamard gates turn (-1)^(w·s)
+into |s⟩. Classically, Ω(2^n) queries are needed. All the 2^(2n) shifts are recovered from
+a single measurement each.
+
+The oracles are built from the U_f gates of the 1-bit functions on an auxiliary qubit in |−⟩
+wherever a 1-bit function can express the phase. The U_f gate flips its first qubit if f(true)
+when the second qubit is |0⟩ and if not f(false) when it is |1⟩, so the U_f of the constant
+false function on the auxiliary qubit and a qubit q kicks back the phase (-1)^q. The shifted
+function of the shift (a, b) is g(x, y) = (x ⊕ a)·(y ⊕ b), which is x·y + b·x + a·y + a·b:
+the linear terms b·x and a·y are these U_f gates on the qubits x_i with b_i = 1 and y_i with
+a_i = 1, and a·b is a global phase. The terms x_i y_i of the inner product depend on two bits,
+which the U_f of a 1-bit function cannot compute, so they stay a CZ gate of each pair of the
+bits x_i and y_i.
+
+Example run:
+cargo run --release --bin hidden_shift -- --bits 3
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: hidden_shift [--bits <count>]";
+const DEFAULT_BIT_COUNT: usize = 2;
+// The hidden shift of n bits acts on 2n qubits and the auxiliary qubit.
+const MAX_BIT_COUNT: usize = 6;
+
+fn parse_bit_count(arguments: &[String]) -> Result<usize, String> {
+    let mut arguments = arguments.iter();
+    let mut bit_count = DEFAULT_BIT_COUNT;
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--bits" => {
+                bit_count = arguments
+                    .next()
+                    .ok_or("The option --bits needs a value.")?
+                    .parse()
+                    .map_err(|_| "The number of bits has to be a positive integer.")?;
+                if bit_count == 0 || bit_count > MAX_BIT_COUNT {
+                    return Err(format!(
+                        "The number of bits has to be from 1 to {}.",
+                        MAX_BIT_COUNT
+                    ));
+                }
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(bit_count)
+}
+
+fn constant_false(_: bool) -> bool {
+    false
+}
+
+// The phase oracle (-1)^(x·y) of the bits x on the qubits 0..n and y on the qubits n..2n.
+fn inner_product_oracle(circuit: &mut Circuit, n: usize) {
+    for i in 0..n {
+        circuit.cz(i, n + i);
+    }
+}
+
+// The phase oracle (-1)^(b·x + a·y) of the shift with the bits a on the qubits 0..n and b on
+// the qubits n..2n, kicked back by the U_f gates onto the auxiliary qubit in |−⟩.
+fn shift_oracle(circuit: &mut Circuit, n: usize, shift: u64) {
+    let aux_qubit = 2 * n;
+    for i in 0..n {
+        if (shift >> (n + i)) & 1 == 1 {
+            circuit.apply_u_f(constant_false, aux_qubit, i);
+        }
+        if (shift >> i) & 1 == 1 {
+            circuit.apply_u_f(constant_false, aux_qubit, n + i);
+        }
+    }
+}
+
+fn hadamard_layer(circuit: &mut Circuit, n: usize) {
+    for qubit_number in 0..2 * n {
+        circuit.hadamard(qubit_number);
+    }
+}
+
+fn hidden_shift_circuit(n: usize, shift: u64) -> Circuit {
+    let mut circuit = Circuit::new(2 * n + 1);
+    circuit.pauli_x(2 * n);
+    circuit.hadamard(2 * n);
+    hadamard_layer(&mut circuit, n);
+    // The oracle of g(z) = f(z ⊕ s), up to the global phase (-1)^(a·b).
+    inner_product_oracle(&mut circuit, n);
+    shift_oracle(&mut circuit, n, shift);
+    hadamard_layer(&mut circuit, n);
+    // The oracle of the dual of f, f itself.
+    inner_product_oracle(&mut circuit, n);
+    hadamard_layer(&mut circuit, n);
+    circuit
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let n = match parse_bit_count(&arguments) {
+        Ok(bit_count) => bit_count,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!("Hidden shift of the inner product of {} bits:", n);
+    let mut simulation = QuantumSimulation::new(2 * n + 1, 0u64);
+    let mut error_count = 0;
+    for shift in 0..1u64 << (2 * n) {
+        simulation.reset();
+        hidden_shift_circuit(n, shift).run(&mut simulation);
+        let recovered_shift = simulation
+            .measure((0..2 * n).collect())
+            .iter()
+            .enumerate()
+            .map(|(bit, &value)| (value as u64) << bit)
+            .sum::<u64>();
+        if recovered_shift != shift {
+            println!("The shift {} recovered as {}", shift, recovered_shift);
+            error_count += 1;
+        } else if n <= 1 || shift % (1 << n) == 1 {
+            println!(
+                "The shift {:0width$b} recovered as {:0width$b}",
+                shift,
+                recovered_shift,
+                width = 2 * n
+            );
+        }
+    }
+    println!(
+        "{} of {} shifts are recovered.",
+        (1 << (2 * n)) - error_count,
+        1 << (2 * n)
+    );
+    println!();
+    if error_count == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
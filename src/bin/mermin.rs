@@ -0,0 +1,121 @@
+/*
+Mermin's GHZ paradox.
+
+The qubits 0, 1, and 2 are prepared in the GHZ state (1/sqrt(2))*(|000⟩ + |111⟩) and each is
+measured along X or Y in the settings XXX, XYY, YXY, and YYX. The product of the three ±1
+outcomes is deterministic: +1 for XXX and -1 for the other settings, in every run. A local
+hidden variable theory assigns the values x_i and y_i = ±1 to the qubits in advance, but then
+the product of the four settings is (x_1 x_2 x_3)(x_1 y_2 y_3)(y_1 x_2 y_3)(y_1 y_2 x_3) = +1, as
+each value appears twice, while the products measured multiply to -1. So at most 3 of the
+4 products can agree with the quantum predictions, and the Mermin value
+M = ⟨XXX⟩ - ⟨XYY⟩ - ⟨YXY⟩ - ⟨YYX⟩ is at most 2 for all the 64 assignments, while it is 4 for the
+GHZ state.
+
+Example run:
+cargo run --release --bin mermin
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_prep::ghz;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const QUBIT_COUNT: usize = 3;
+const RUN_COUNT: usize = 1000;
+// The settings with the signs of their products predicted for the GHZ state.
+const SETTINGS: [(&str, i32); 4] = [("XXX", 1), ("XYY", -1), ("YXY", -1), ("YYX", -1)];
+
+// The product of the ±1 outcomes of the qubits measured along the axes of the setting.
+fn measure_product(simulation: &mut dyn Simulation, setting: &str) -> i32 {
+    setting
+        .chars()
+        .enumerate()
+        .map(|(qubit_number, axis)| {
+            let value = match axis {
+                'X' => simulation.measure_x(qubit_number),
+                _ => simulation.measure_y(qubit_number),
+            };
+            if value {
+                -1
+            } else {
+                1
+            }
+        })
+        .product()
+}
+
+// The largest Mermin value of the local hidden variables, the values ±1 of X and Y of each
+// qubit fixed in advance.
+fn local_bound() -> i32 {
+    (0..1 << (2 * QUBIT_COUNT))
+        .map(|assignment: usize| {
+            let value = |axis: char, qubit_number: usize| {
+                let bit = 2 * qubit_number + if axis == 'X' { 0 } else { 1 };
+                if (assignment >> bit) & 1 == 1 {
+                    -1
+                } else {
+                    1
+                }
+            };
+            SETTINGS
+                .iter()
+                .map(|(setting, sign)| {
+                    sign * setting
+                        .chars()
+                        .enumerate()
+                        .map(|(qubit_number, axis)| value(axis, qubit_number))
+                        .product::<i32>()
+                })
+                .sum::<i32>()
+        })
+        .max()
+        .unwrap()
+}
+
+fn main() -> ExitCode {
+    println!("Mermin's GHZ paradox:");
+    let mut simulation = QuantumSimulation::new(QUBIT_COUNT, 0u64);
+    let circuit = ghz(QUBIT_COUNT);
+    let mut mermin_value = 0.0;
+    let mut is_deterministic = true;
+    for (setting, sign) in SETTINGS {
+        let mut sum = 0;
+        let mut counts = [0; 2];
+        for _ in 0..RUN_COUNT {
+            simulation.reset();
+            circuit.run(&mut simulation);
+            let product = measure_product(&mut simulation, setting);
+            sum += product;
+            counts[(product < 0) as usize] += 1;
+        }
+        let expectation = sum as f64 / RUN_COUNT as f64;
+        println!(
+            "⟨{}⟩ = {:+.3}, the product +1 in {} and -1 in {} of {} runs",
+            setting, expectation, counts[0], counts[1], RUN_COUNT
+        );
+        is_deterministic &= counts[(sign < 0) as usize] == RUN_COUNT;
+        mermin_value += sign as f64 * expectation;
+    }
+    println!("M = ⟨XXX⟩ - ⟨XYY⟩ - ⟨YXY⟩ - ⟨YYX⟩ = {:.3}", mermin_value);
+    let bound = local_bound();
+    println!(
+        "Local hidden variable bound: {}, of the 64 assignments of the values of X and Y",
+        bound
+    );
+    let is_contradicted = is_deterministic && mermin_value > bound as f64;
+    if is_contradicted {
+        println!("Every run contradicts the local hidden variables.");
+    } else {
+        println!("The local hidden variables are not contradicted.");
+    }
+    println!();
+
+    if is_contradicted {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
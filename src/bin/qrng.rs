@@ -0,0 +1,257 @@
+/*
+Quantum random number generator.
+
+Usage:
+qrng [--bits <count>] [--extractor von-neumann|toeplitz] [--bias <probability>] [--seed <seed>] [--test]
+
+Each raw bit is the measurement of a qubit put into the superposition by a Hadamard gate, or by
+an RY rotation giving 1 with the probability of the bias to model an imperfect source. The raw
+bits are turned into the nearly uniform output bits by a randomness extractor:
+- von-neumann: the pairs 01 and 10 of the raw bits give 0 and 1, and the pairs 00 and 11 are
+  dropped, which removes any bias of independent bits.
+- toeplitz: the 2m raw bits are multiplied over GF(2) by a random m × 2m Toeplitz matrix, given by
+  its first row and column, which is a strong extractor by the leftover hash lemma for the raw
+  bits with at least a half bit of min-entropy each.
+The output bits are printed in hexadecimal. With --test, the monobit and the runs tests of
+NIST SP 800-22 at the significance level 0.01 and the repetition count test of
+NIST SP 800-90B are run on the output bits.
+
+Example run:
+cargo run --release --bin qrng -- --bits 1024 --extractor toeplitz --bias 0.3 --test
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: qrng [--bits <count>] [--extractor von-neumann|toeplitz] [--bias <probability>] [--seed <seed>] [--test]";
+const DEFAULT_BIT_COUNT: usize = 256;
+const EXTRACTORS: [&str; 2] = ["von-neumann", "toeplitz"];
+// The two-sided critical value of the standard normal distribution at the significance level 0.01.
+const CRITICAL_VALUE: f64 = 2.5758;
+// The cutoff of the repetition count test of the bits of full entropy at the false positive
+// probability 2^-20.
+const REPETITION_CUTOFF: usize = 21;
+
+struct Options {
+    bit_count: usize,
+    extractor: String,
+    bias: f64,
+    seed: u64,
+    is_tested: bool,
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        bit_count: DEFAULT_BIT_COUNT,
+        extractor: EXTRACTORS[0].to_string(),
+        bias: 0.5,
+        seed: 0,
+        is_tested: false,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--bits" => {
+                options.bit_count = value("--bits")?
+                    .parse()
+                    .map_err(|_| "The number of bits has to be a nonnegative integer.")?
+            }
+            "--extractor" => {
+                let extractor = value("--extractor")?;
+                if !EXTRACTORS.contains(&extractor.as_str()) {
+                    return Err(format!("Unknown extractor {}.", extractor));
+                }
+                options.extractor = extractor.clone();
+            }
+            "--bias" => {
+                options.bias = value("--bias")?
+                    .parse()
+                    .map_err(|_| "The bias has to be a number.")?;
+                if options.bias <= 0.0 || options.bias >= 1.0 {
+                    return Err("The bias has to be in the interval (0, 1).".to_string());
+                }
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            "--test" => options.is_tested = true,
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+// The source of the raw bits, measuring a qubit rotated to give 1 with the probability of the bias.
+struct Source {
+    simulation: QuantumSimulation,
+    bias: f64,
+    raw_bit_count: usize,
+}
+
+impl Source {
+    fn next_bit(&mut self) -> bool {
+        self.simulation.reset();
+        if self.bias == 0.5 {
+            self.simulation.hadamard(0);
+        } else {
+            self.simulation.ry(2.0 * self.bias.sqrt().asin(), 0);
+        }
+        self.raw_bit_count += 1;
+        self.simulation.measure(vec![0])[0]
+    }
+}
+
+fn von_neumann(source: &mut Source, bit_count: usize) -> Vec<bool> {
+    let mut bits = Vec::with_capacity(bit_count);
+    while bits.len() < bit_count {
+        let (bit0, bit1) = (source.next_bit(), source.next_bit());
+        if bit0 != bit1 {
+            bits.push(bit0);
+        }
+    }
+    bits
+}
+
+// The product of the Toeplitz matrix T[i][j] = t[i - j + n - 1] of the random bits t with the n raw
+// bits, for the m output bits.
+fn toeplitz(source: &mut Source, bit_count: usize, rng: &mut ChaCha12Rng) -> Vec<bool> {
+    let raw_bits: Vec<bool> = (0..2 * bit_count).map(|_| source.next_bit()).collect();
+    let n = raw_bits.len();
+    let diagonals: Vec<bool> = (0..bit_count + n - 1).map(|_| rng.gen()).collect();
+    (0..bit_count)
+        .map(|i| {
+            raw_bits
+                .iter()
+                .enumerate()
+                .filter(|&(j, &bit)| bit && diagonals[i + n - 1 - j])
+                .count()
+                % 2
+                == 1
+        })
+        .collect()
+}
+
+fn to_hex(bits: &[bool]) -> String {
+    bits.chunks(4)
+        .map(|nibble| {
+            let value = nibble
+                .iter()
+                .fold(0u32, |value, &bit| (value << 1) | bit as u32)
+                << (4 - nibble.len());
+            std::char::from_digit(value, 16).unwrap()
+        })
+        .collect()
+}
+
+// The statistic of the frequency of the ones, standard normal for the random bits.
+fn monobit_statistic(bits: &[bool]) -> f64 {
+    let n = bits.len() as f64;
+    let sum: f64 = bits.iter().map(|&bit| if bit { 1.0 } else { -1.0 }).sum();
+    sum.abs() / n.sqrt()
+}
+
+// The statistic of the number of the runs of equal bits, standard normal for the random bits,
+// or None if the frequency of the ones is too far from 1/2 for the test.
+fn runs_statistic(bits: &[bool]) -> Option<f64> {
+    let n = bits.len() as f64;
+    let pi = bits.iter().filter(|&&bit| bit).count() as f64 / n;
+    if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return None;
+    }
+    let run_count = 1 + bits.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    Some(
+        (run_count as f64 - 2.0 * n * pi * (1.0 - pi)).abs()
+            / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi)),
+    )
+}
+
+fn longest_run(bits: &[bool]) -> usize {
+    let mut longest = 0;
+    let mut length = 0;
+    for (i, &bit) in bits.iter().enumerate() {
+        length = if i > 0 && bit == bits[i - 1] {
+            length + 1
+        } else {
+            1
+        };
+        longest = longest.max(length);
+    }
+    longest
+}
+
+// Run the health tests and return whether all of them passed.
+fn run_tests(bits: &[bool]) -> bool {
+    let verdict = |is_passed: bool| if is_passed { "passed" } else { "failed" };
+    let monobit = monobit_statistic(bits);
+    println!(
+        "Monobit test: z = {:.3}, {}",
+        monobit,
+        verdict(monobit < CRITICAL_VALUE)
+    );
+    let runs = runs_statistic(bits);
+    match runs {
+        Some(runs) => println!(
+            "Runs test: z = {:.3}, {}",
+            runs,
+            verdict(runs < CRITICAL_VALUE)
+        ),
+        None => println!("Runs test: the frequency of the ones is too far from 1/2, failed"),
+    }
+    let longest = longest_run(bits);
+    println!(
+        "Repetition count test: longest run {}, {}",
+        longest,
+        verdict(longest < REPETITION_CUTOFF)
+    );
+    monobit < CRITICAL_VALUE
+        && runs.is_some_and(|runs| runs < CRITICAL_VALUE)
+        && longest < REPETITION_CUTOFF
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut rng = ChaCha12Rng::seed_from_u64(options.seed);
+    let mut source = Source {
+        simulation: QuantumSimulation::new(1, rng.gen()),
+        bias: options.bias,
+        raw_bit_count: 0,
+    };
+    let bits = match options.extractor.as_str() {
+        "toeplitz" => toeplitz(&mut source, options.bit_count, &mut rng),
+        _ => von_neumann(&mut source, options.bit_count),
+    };
+    println!("Quantum random number generator:");
+    println!(
+        "Raw bits: {}, output bits: {}, extractor: {}",
+        source.raw_bit_count,
+        bits.len(),
+        options.extractor
+    );
+    println!("{}", to_hex(&bits));
+    if options.is_tested && !bits.is_empty() && !run_tests(&bits) {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,174 @@
+/*
+Command line runner of OpenQASM 2.0 circuits.
+
+Usage:
+qsim run <circuit.qasm> [--shots <count>] [--seed <seed>] [--backend statevector|stabilizer|densitymatrix|mps] [--json] [--bit-order msb|lsb] [--format binary|hex|decimal] [--registers <sizes>]
+
+Each shot runs the circuit from the ground state and records its classical bits,
+or measures all the qubits if the circuit has no classical bits, on the simulation of
+the backend, which is the state vector by default. The counts of the
+outcomes are printed, or the results as JSON with the --json option.
+The outcomes are printed with the highest qubit first, |q_(n-1)...q_0>, or
+with the qubit 0 first by --bit-order lsb, in hexadecimal or decimal by
+--format, and split into the registers of the comma separated sizes from
+the qubit 0 by --registers.
+
+Example run:
+cargo run --bin qsim -- run circuit.qasm --shots 1000 --seed 42
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use quantum_simulation::circuit::from_qasm;
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::evaluation::{to_json, BitOrder, FormatOptions, OutcomeFormat};
+use quantum_simulation::simulation::{Backend, Simulation};
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: qsim run <circuit.qasm> [--shots <count>] [--seed <seed>] [--backend statevector|stabilizer|densitymatrix|mps] [--json] [--bit-order msb|lsb] [--format binary|hex|decimal] [--registers <sizes>]";
+const DEFAULT_SHOT_COUNT: usize = 1000;
+
+struct Options {
+    path: String,
+    shot_count: usize,
+    seed: u64,
+    backend: Backend,
+    is_json: bool,
+    format_options: FormatOptions,
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    if arguments.next().map(|command| command.as_str()) != Some("run") {
+        return Err("The command has to be run.".to_string());
+    }
+    let mut path = None;
+    let mut options = Options {
+        path: String::new(),
+        shot_count: DEFAULT_SHOT_COUNT,
+        seed: 0,
+        backend: Backend::StateVector,
+        is_json: false,
+        format_options: FormatOptions::default(),
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--shots" => {
+                options.shot_count = value("--shots")?
+                    .parse()
+                    .map_err(|_| "The number of shots has to be a nonnegative integer.")?
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            "--backend" => {
+                let backend = value("--backend")?;
+                options.backend = Backend::from_name(backend)
+                    .ok_or_else(|| format!("Unknown backend {}.", backend))?;
+            }
+            "--json" => options.is_json = true,
+            "--bit-order" => {
+                let bit_order = value("--bit-order")?;
+                options.format_options.bit_order = BitOrder::from_name(bit_order)
+                    .ok_or_else(|| format!("Unknown bit order {}.", bit_order))?;
+            }
+            "--format" => {
+                let format = value("--format")?;
+                options.format_options.outcome_format = OutcomeFormat::from_name(format)
+                    .ok_or_else(|| format!("Unknown format {}.", format))?;
+            }
+            "--registers" => {
+                options.format_options.register_sizes = value("--registers")?
+                    .split(',')
+                    .map(|size| match size.parse() {
+                        Ok(size) if size > 0 => Ok(size),
+                        _ => Err("The register sizes have to be positive integers."),
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            option if option.starts_with("--") => {
+                return Err(format!("Unknown option {}.", option))
+            }
+            _ if path.is_none() => path = Some(argument.clone()),
+            _ => return Err("Only one circuit file can be run.".to_string()),
+        }
+    }
+    options.path = path.ok_or("The circuit file is missing.")?;
+    Ok(options)
+}
+
+// Run the circuit from the ground state for each shot on the simulation of the backend.
+fn run_shots(circuit: &Circuit, options: &Options) -> Vec<Vec<bool>> {
+    if options.backend == Backend::StateVector {
+        let mut simulation = QuantumSimulation::new(circuit.qubit_count(), options.seed);
+        return simulation.run_shots(circuit, options.shot_count);
+    }
+    let mut simulation =
+        <dyn Simulation>::with_backend(options.backend, circuit.qubit_count(), options.seed);
+    (0..options.shot_count)
+        .map(|_| {
+            simulation.reset();
+            let clbits = circuit.run(simulation.as_mut());
+            if circuit.clbit_count() == 0 {
+                simulation.measure_all()
+            } else {
+                clbits
+            }
+        })
+        .collect()
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let source = match std::fs::read_to_string(&options.path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: {}", options.path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let circuit = match from_qasm(&source) {
+        Ok(circuit) => circuit,
+        Err(error) => {
+            eprintln!("{}:{}", options.path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let measurements = run_shots(&circuit, &options);
+    if options.is_json {
+        print!("{}", to_json(&measurements, options.seed));
+    } else {
+        // The outcomes of the same count are in the order of the bitstrings of the highest
+        // qubit first.
+        let mut count_map: BTreeMap<Vec<bool>, usize> = BTreeMap::new();
+        for measurement in measurements.iter() {
+            let outcome: Vec<bool> = measurement.iter().rev().copied().collect();
+            *count_map.entry(outcome).or_insert(0) += 1;
+        }
+        let mut counts: Vec<(Vec<bool>, usize)> = count_map.into_iter().collect();
+        counts.sort_by_key(|pair| std::cmp::Reverse(pair.1));
+        for (outcome, count) in counts {
+            let measurement: Vec<bool> = outcome.into_iter().rev().collect();
+            println!("{}: {}", options.format_options.format(&measurement), count);
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,373 @@
+/*
+HTTP server running simulation jobs for remote clients.
+
+POST /jobs with a JSON body {"qasm": "<OpenQASM 2.0 program>", "shots": 1000, "seed": 42},
+or with "circuit" holding the JSON format of a circuit instead of "qasm", queues a job and
+returns its id as {"id": 1}. GET /jobs/<id> returns the status of the job, queued, running,
+done, or failed, and GET /jobs/<id>/result returns the results in the JSON format of
+evaluation::to_json once the job is done. The jobs run on a pool of worker threads,
+one per available processor, and the results are kept in memory.
+
+The jobs have at most --max-qubits qubits, 24 by default. A job runs only while the state
+vectors of the running jobs, 16 bytes per amplitude, fit into --memory-limit MiB, 1024 by
+default, and otherwise stays queued, as a failed allocation aborts the whole server.
+
+Usage:
+qsim-server [--address <host:port>] [--max-qubits <count>] [--memory-limit <MiB>]
+
+Example run:
+cargo run --release --features server --bin qsim-server -- --address 0.0.0.0:8080
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use serde_json::{json, Value};
+
+use quantum_simulation::circuit::{from_qasm, Circuit};
+use quantum_simulation::evaluation::to_json;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str =
+    "Usage: qsim-server [--address <host:port>] [--max-qubits <count>] [--memory-limit <MiB>]";
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+const DEFAULT_SHOT_COUNT: usize = 1000;
+const MAX_SHOT_COUNT: usize = 1_000_000;
+// The most qubits of a job by default, whose state vector takes 256 MiB.
+const DEFAULT_MAX_QUBIT_COUNT: usize = 24;
+// The most qubits of a job with the option, whose state vector takes 64 GiB.
+const MAX_QUBIT_COUNT: usize = 32;
+// The memory of the state vectors of the running jobs by default, in MiB.
+const DEFAULT_MEMORY_LIMIT: usize = 1024;
+const MAX_BODY_LENGTH: usize = 16 << 20;
+
+struct Options {
+    address: String,
+    max_qubit_count: usize,
+    // The memory limit in bytes.
+    memory_limit: usize,
+}
+
+enum JobStatus {
+    Queued,
+    Running,
+    Done(String),
+    Failed(String),
+}
+
+struct Job {
+    id: u64,
+    circuit: Circuit,
+    shot_count: usize,
+    seed: u64,
+}
+
+// The memory of the state vectors of the running jobs, where a job waits until its state vector
+// fits under the limit.
+struct MemoryBudget {
+    used: Mutex<usize>,
+    released: Condvar,
+    limit: usize,
+}
+
+impl MemoryBudget {
+    fn acquire(&self, size: usize) {
+        let mut used = self.used.lock().unwrap();
+        while *used + size > self.limit {
+            used = self.released.wait(used).unwrap();
+        }
+        *used += size;
+    }
+
+    fn release(&self, size: usize) {
+        *self.used.lock().unwrap() -= size;
+        self.released.notify_all();
+    }
+}
+
+struct Server {
+    jobs: Mutex<HashMap<u64, JobStatus>>,
+    next_id: Mutex<u64>,
+    queue: Mutex<Sender<Job>>,
+    max_qubit_count: usize,
+    memory: MemoryBudget,
+}
+
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+fn response(status: u16, body: Value) -> Response {
+    Response { status, body }
+}
+
+fn error_response(status: u16, message: &str) -> Response {
+    response(status, json!({ "error": message }))
+}
+
+// The bytes of the state vector of the qubits.
+fn state_vector_size(qubit_count: usize) -> usize {
+    16 << qubit_count
+}
+
+fn run_worker(server: Arc<Server>, queue: Arc<Mutex<Receiver<Job>>>) {
+    loop {
+        let job = match queue.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let size = state_vector_size(job.circuit.qubit_count());
+        server.memory.acquire(size);
+        server
+            .jobs
+            .lock()
+            .unwrap()
+            .insert(job.id, JobStatus::Running);
+        // A panic of the simulation fails the job instead of the worker.
+        let result = std::panic::catch_unwind(|| {
+            let mut simulation = QuantumSimulation::new(job.circuit.qubit_count(), job.seed);
+            to_json(
+                &simulation.run_shots(&job.circuit, job.shot_count),
+                job.seed,
+            )
+        });
+        let status = match result {
+            Ok(result) => JobStatus::Done(result),
+            Err(_) => JobStatus::Failed("The simulation failed.".to_string()),
+        };
+        server.memory.release(size);
+        server.jobs.lock().unwrap().insert(job.id, status);
+    }
+}
+
+fn parse_job(body: &[u8], max_qubit_count: usize) -> Result<(Circuit, usize, u64), String> {
+    let request: Value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+    let circuit = match (request.get("qasm"), request.get("circuit")) {
+        (Some(Value::String(qasm)), None) => from_qasm(qasm).map_err(|e| e.to_string())?,
+        (None, Some(circuit)) => Circuit::from_json_value(circuit).map_err(|e| e.message)?,
+        _ => return Err("The job needs either a qasm program or a circuit.".to_string()),
+    };
+    if circuit.qubit_count() > max_qubit_count {
+        return Err(format!(
+            "The number of qubits in the simulation cannot exceed {}.",
+            max_qubit_count
+        ));
+    }
+    let shot_count = match request.get("shots") {
+        None => DEFAULT_SHOT_COUNT,
+        Some(shots) => match shots.as_u64() {
+            Some(shots) if shots as usize <= MAX_SHOT_COUNT => shots as usize,
+            _ => {
+                return Err(format!(
+                    "The number of shots has to be an integer up to {}.",
+                    MAX_SHOT_COUNT
+                ))
+            }
+        },
+    };
+    let seed = match request.get("seed") {
+        None => 0,
+        Some(seed) => seed
+            .as_u64()
+            .ok_or("The seed has to be a nonnegative integer.")?,
+    };
+    Ok((circuit, shot_count, seed))
+}
+
+fn handle(server: &Server, method: &str, path: &str, body: &[u8]) -> Response {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        ("POST", ["jobs"]) => match parse_job(body, server.max_qubit_count) {
+            Ok((circuit, shot_count, seed)) => {
+                let id = {
+                    let mut next_id = server.next_id.lock().unwrap();
+                    *next_id += 1;
+                    *next_id
+                };
+                server.jobs.lock().unwrap().insert(id, JobStatus::Queued);
+                let job = Job {
+                    id,
+                    circuit,
+                    shot_count,
+                    seed,
+                };
+                if server.queue.lock().unwrap().send(job).is_err() {
+                    return error_response(500, "The job queue is closed.");
+                }
+                response(202, json!({ "id": id }))
+            }
+            Err(message) => error_response(400, &message),
+        },
+        ("GET", ["jobs", id]) | ("GET", ["jobs", id, "result"]) => {
+            let jobs = server.jobs.lock().unwrap();
+            let status = match id.parse::<u64>().ok().and_then(|id| jobs.get(&id)) {
+                Some(status) => status,
+                None => return error_response(404, "Unknown job."),
+            };
+            let is_result = segments.len() == 3;
+            match status {
+                JobStatus::Done(result) if is_result => {
+                    response(200, serde_json::from_str(result).unwrap())
+                }
+                JobStatus::Failed(message) if is_result => error_response(500, message),
+                JobStatus::Queued | JobStatus::Running if is_result => {
+                    error_response(409, "The job is not done.")
+                }
+                JobStatus::Queued => response(200, json!({ "status": "queued" })),
+                JobStatus::Running => response(200, json!({ "status": "running" })),
+                JobStatus::Done(_) => response(200, json!({ "status": "done" })),
+                JobStatus::Failed(message) => {
+                    response(200, json!({ "status": "failed", "error": message }))
+                }
+            }
+        }
+        _ => error_response(404, "Unknown path."),
+    }
+}
+
+fn serve(server: &Server, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let response = if content_length > MAX_BODY_LENGTH {
+        error_response(413, "The request body is too large.")
+    } else {
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body)?;
+        handle(server, &method, &path, &body)
+    };
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        address: DEFAULT_ADDRESS.to_string(),
+        max_qubit_count: DEFAULT_MAX_QUBIT_COUNT,
+        memory_limit: DEFAULT_MEMORY_LIMIT << 20,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--address" => options.address = value("--address")?.clone(),
+            "--max-qubits" => {
+                options.max_qubit_count = match value("--max-qubits")?.parse() {
+                    Ok(count) if count <= MAX_QUBIT_COUNT => count,
+                    _ => {
+                        return Err(format!(
+                            "The number of qubits has to be an integer up to {}.",
+                            MAX_QUBIT_COUNT
+                        ))
+                    }
+                }
+            }
+            "--memory-limit" => {
+                let memory_limit: usize = value("--memory-limit")?
+                    .parse()
+                    .map_err(|_| "The memory limit has to be a nonnegative integer.")?;
+                options.memory_limit = memory_limit.saturating_mul(1 << 20);
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    if state_vector_size(options.max_qubit_count) > options.memory_limit {
+        return Err(format!(
+            "The memory limit has to hold the state vector of {} qubits.",
+            options.max_qubit_count
+        ));
+    }
+    Ok(options)
+}
+
+fn main() {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let address = options.address;
+
+    let (sender, receiver) = channel();
+    let server = Arc::new(Server {
+        jobs: Mutex::new(HashMap::new()),
+        next_id: Mutex::new(0),
+        queue: Mutex::new(sender),
+        max_qubit_count: options.max_qubit_count,
+        memory: MemoryBudget {
+            used: Mutex::new(0),
+            released: Condvar::new(),
+            limit: options.memory_limit,
+        },
+    });
+    let receiver = Arc::new(Mutex::new(receiver));
+    let worker_count = thread::available_parallelism().map_or(1, |n| n.get());
+    for _ in 0..worker_count {
+        let server = Arc::clone(&server);
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || run_worker(server, receiver));
+    }
+
+    let listener = TcpListener::bind(&address).unwrap_or_else(|e| {
+        eprintln!("Cannot listen on {}: {}", address, e);
+        std::process::exit(1);
+    });
+    println!("Listening on {} with {} workers", address, worker_count);
+    for stream in listener.incoming().flatten() {
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            if let Err(e) = serve(&server, stream) {
+                eprintln!("Request failed: {}", e);
+            }
+        });
+    }
+}
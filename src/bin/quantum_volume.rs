@@ -0,0 +1,175 @@
+/*
+Quantum volume benchmark under a noise model.
+
+Usage:
+quantum_volume [--max-width <count>] [--circuits <count>] [--trajectories <count>] [--error <p>] [--seed <seed>]
+
+The model circuit of the width m has m layers, each a Haar-random SU(4) unitary, decomposed into
+at most 3 CNOT gates and the single-qubit rotations, on each pair of a random pairing of the
+qubits. The heavy outputs of a circuit are the basis states with the ideal probabilities above
+their median, computed by the state vector simulation, and are measured with the probability
+of about (1 + ln 2)/2 ≈ 0.85 by the ideal circuits and 1/2 by the fully depolarized ones. The
+noise model applies the depolarizing channel of the probability p after each CNOT gate to both
+of its qubits, and the noisy probabilities are averaged over the trajectories. The width m is
+achieved when the mean heavy output probability h over the circuits is above 2/3 with the two
+standard deviations sqrt(h(1 - h)/circuits), and the quantum volume is 2^m of the largest
+width m with all the widths up to m achieved.
+
+Example run:
+cargo run --release --bin quantum_volume -- --max-width 6 --circuits 100 --error 0.01
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::benchmarks::{heavy_output_probability, quantum_volume_circuit};
+use quantum_simulation::noise::{KrausChannel, NoiseModel};
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+use quantum_simulation::trajectory_simulation::TrajectorySimulation;
+
+const USAGE: &str = "Usage: quantum_volume [--max-width <count>] [--circuits <count>] [--trajectories <count>] [--error <p>] [--seed <seed>]";
+const MIN_WIDTH: usize = 2;
+const MAX_WIDTH: usize = 10;
+const HEAVY_OUTPUT_THRESHOLD: f64 = 2.0 / 3.0;
+
+struct Options {
+    max_width: usize,
+    circuit_count: usize,
+    trajectory_count: usize,
+    error_probability: f64,
+    seed: u64,
+}
+
+fn parse_count(value: &str, name: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!(
+            "The number of {} has to be a positive integer.",
+            name
+        )),
+    }
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        max_width: 5,
+        circuit_count: 100,
+        trajectory_count: 50,
+        error_probability: 0.01,
+        seed: 0,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--max-width" => {
+                options.max_width = parse_count(value("--max-width")?, "qubits")?;
+                if !(MIN_WIDTH..=MAX_WIDTH).contains(&options.max_width) {
+                    return Err(format!(
+                        "The maximum width has to be from {} to {}.",
+                        MIN_WIDTH, MAX_WIDTH
+                    ));
+                }
+            }
+            "--circuits" => options.circuit_count = parse_count(value("--circuits")?, "circuits")?,
+            "--trajectories" => {
+                options.trajectory_count = parse_count(value("--trajectories")?, "trajectories")?
+            }
+            "--error" => {
+                options.error_probability = match value("--error")?.parse::<f64>() {
+                    Ok(probability) if (0.0..=1.0).contains(&probability) => probability,
+                    _ => return Err("The error probability has to be from 0 to 1.".to_string()),
+                }
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!(
+        "Quantum volume with the depolarizing error {} after each CNOT, {} circuits of each width:",
+        options.error_probability, options.circuit_count
+    );
+    let mut noise_model = NoiseModel::new();
+    noise_model.add_gate_error(
+        KrausChannel::depolarizing(options.error_probability),
+        &["cnot"],
+    );
+    println!(
+        "{:>5}  {:>8}  {:>8}  {:>10}  achieved",
+        "width", "ideal h", "noisy h", "h - 2σ"
+    );
+    let mut is_ideal_heavy = true;
+    let mut achieved_width: Option<usize> = None;
+    for width in MIN_WIDTH..=options.max_width {
+        let mut ideal_sum = 0.0;
+        let mut noisy_sum = 0.0;
+        for circuit_number in 0..options.circuit_count {
+            let seed = options.seed * options.circuit_count as u64 + circuit_number as u64;
+            let circuit = quantum_volume_circuit(width, seed + ((width as u64) << 32));
+            let mut simulation = QuantumSimulation::new(width, 0u64);
+            circuit.run(&mut simulation);
+            let ideal: Vec<f64> = simulation
+                .amplitudes()
+                .iter()
+                .map(|amplitude| amplitude.norm_sqr())
+                .collect();
+            let mut noisy_simulation = TrajectorySimulation::new(width, seed, noise_model.clone());
+            let noisy = noisy_simulation.average_probabilities(&circuit, options.trajectory_count);
+            ideal_sum += heavy_output_probability(&ideal, &ideal);
+            noisy_sum += heavy_output_probability(&ideal, &noisy);
+        }
+        let ideal_h = ideal_sum / options.circuit_count as f64;
+        let noisy_h = noisy_sum / options.circuit_count as f64;
+        let lower_bound =
+            noisy_h - 2.0 * (noisy_h * (1.0 - noisy_h) / options.circuit_count as f64).sqrt();
+        let is_achieved = lower_bound > HEAVY_OUTPUT_THRESHOLD;
+        println!(
+            "{:>5}  {:>8.4}  {:>8.4}  {:>10.4}  {}",
+            width,
+            ideal_h,
+            noisy_h,
+            lower_bound,
+            if is_achieved { "yes" } else { "no" }
+        );
+        is_ideal_heavy &= ideal_h > HEAVY_OUTPUT_THRESHOLD;
+        // The widths above a width not achieved do not count.
+        if is_achieved
+            && achieved_width.map_or(width == MIN_WIDTH, |achieved| achieved == width - 1)
+        {
+            achieved_width = Some(width);
+        }
+    }
+    match achieved_width {
+        Some(width) => println!("Quantum volume: 2^{} = {}", width, 1u64 << width),
+        None => println!("Quantum volume: 1, the width {} is not achieved", MIN_WIDTH),
+    }
+    println!();
+
+    if is_ideal_heavy {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
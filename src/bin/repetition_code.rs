@@ -0,0 +1,186 @@
+/*
+Threshold sweep of the repetition codes of the distances 3, 5, and 7.
+
+Usage:
+repetition_code [--noise bit-flip|depolarizing] [--rounds <count>] [--runs <count>] [--seed <seed>]
+
+The logical |0⟩ of the repetition code of distance d is kept on d data qubits over the rounds of
+the syndrome extraction, each measuring the parities of the neighbouring data qubits by an
+ancilla qubit which is reset after each measurement. The noise model applies the Pauli channel
+after each CNOT to both of its qubits, X with the probability p for the bit-flip noise, or X, Y,
+and Z with p/3 each for the depolarizing noise, so that the data qubits are flipped and the
+ancilla flips give the wrong syndrome bits. The syndrome bits changing from the previous round
+are the detection events of the errors. After the last round, the data qubits are measured and
+their majority vote gives the logical value, which is wrong if more than half of them are
+flipped. The logical error rates of the distances are tabulated against p with the rate of the
+flips of a data qubit, the physical error rate, and of the detection events. The larger
+distance is better below the threshold of the majority vote, where the curves cross as the flip
+rate of a data qubit approaches 1/2.
+
+Example run:
+cargo run --release --bin repetition_code -- --noise depolarizing --rounds 5 --runs 2000
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::noise::{KrausChannel, NoiseModel};
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::trajectory_simulation::TrajectorySimulation;
+
+const USAGE: &str = "Usage: repetition_code [--noise bit-flip|depolarizing] [--rounds <count>] [--runs <count>] [--seed <seed>]";
+const NOISES: [&str; 2] = ["bit-flip", "depolarizing"];
+const DISTANCES: [usize; 3] = [3, 5, 7];
+const PROBABILITIES: [f64; 8] = [0.01, 0.02, 0.05, 0.08, 0.1, 0.15, 0.2, 0.3];
+const DEFAULT_ROUND_COUNT: usize = 3;
+const DEFAULT_RUN_COUNT: usize = 1000;
+
+struct Options {
+    noise: String,
+    round_count: usize,
+    run_count: usize,
+    seed: u64,
+}
+
+fn parse_count(value: &str, name: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!(
+            "The number of {} has to be a positive integer.",
+            name
+        )),
+    }
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        noise: NOISES[0].to_string(),
+        round_count: DEFAULT_ROUND_COUNT,
+        run_count: DEFAULT_RUN_COUNT,
+        seed: 0,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--noise" => {
+                let noise = value("--noise")?;
+                if !NOISES.contains(&noise.as_str()) {
+                    return Err(format!("Unknown noise {}.", noise));
+                }
+                options.noise = noise.clone();
+            }
+            "--rounds" => options.round_count = parse_count(value("--rounds")?, "rounds")?,
+            "--runs" => options.run_count = parse_count(value("--runs")?, "runs")?,
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+fn pauli_channel(noise: &str, probability: f64) -> KrausChannel {
+    match noise {
+        "depolarizing" => {
+            KrausChannel::pauli(probability / 3.0, probability / 3.0, probability / 3.0)
+        }
+        _ => KrausChannel::pauli(probability, 0.0, 0.0),
+    }
+}
+
+struct Outcome {
+    is_logical_error: bool,
+    // The number of the data qubits measured flipped.
+    flip_count: usize,
+    // The number of the syndrome bits differing from the previous round.
+    detection_count: usize,
+}
+
+// Run the memory experiment of the code of the distance on the data qubits 0..d with the
+// ancilla qubit d, decoding the measured data qubits by the majority vote.
+fn run_memory(
+    simulation: &mut TrajectorySimulation,
+    distance: usize,
+    round_count: usize,
+) -> Outcome {
+    let ancilla = distance;
+    simulation.reset();
+    let mut previous_syndrome = vec![false; distance - 1];
+    let mut detection_count = 0;
+    for _ in 0..round_count {
+        for (i, previous_bit) in previous_syndrome.iter_mut().enumerate() {
+            simulation.cnot(i, ancilla);
+            simulation.cnot(i + 1, ancilla);
+            let bit = simulation.measure(vec![ancilla])[0];
+            if bit {
+                simulation.pauli_x(ancilla);
+            }
+            if bit != *previous_bit {
+                detection_count += 1;
+            }
+            *previous_bit = bit;
+        }
+    }
+    let values = simulation.measure((0..distance).collect());
+    let flip_count = values.iter().filter(|&&value| value).count();
+    Outcome {
+        is_logical_error: 2 * flip_count > distance,
+        flip_count,
+        detection_count,
+    }
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!(
+        "Repetition codes under the {} noise, {} rounds, {} runs:",
+        options.noise, options.round_count, options.run_count
+    );
+    print!("{:>6}", "p");
+    for distance in DISTANCES {
+        print!("  {:>8}", format!("d = {}", distance));
+    }
+    println!("  {:>9}  {:>10}", "data flip", "detections");
+    for probability in PROBABILITIES {
+        print!("{:>6}", probability);
+        // The rates of the flips of the data qubits and of the detection events of the largest
+        // distance, per qubit and per syndrome bit.
+        let (mut flip_rate, mut detection_rate) = (0.0, 0.0);
+        for distance in DISTANCES {
+            let mut noise_model = NoiseModel::new();
+            noise_model.add_gate_error(pauli_channel(&options.noise, probability), &["cnot"]);
+            let mut simulation = TrajectorySimulation::new(distance + 1, options.seed, noise_model);
+            let (mut error_count, mut flip_count, mut detection_count) = (0, 0, 0);
+            for _ in 0..options.run_count {
+                let outcome = run_memory(&mut simulation, distance, options.round_count);
+                error_count += outcome.is_logical_error as usize;
+                flip_count += outcome.flip_count;
+                detection_count += outcome.detection_count;
+            }
+            print!("  {:>8.4}", error_count as f64 / options.run_count as f64);
+            flip_rate = flip_count as f64 / (options.run_count * distance) as f64;
+            detection_rate = detection_count as f64
+                / (options.run_count * options.round_count * (distance - 1)) as f64;
+        }
+        println!("  {:>9.4}  {:>10.4}", flip_rate, detection_rate);
+    }
+    println!();
+    ExitCode::SUCCESS
+}
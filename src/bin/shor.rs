@@ -0,0 +1,269 @@
+/*
+Shor's algorithm factoring small numbers by the quantum order finding.
+
+The order r of a modulo N, the least r with a^r = 1 mod N, is the period of x -> a^x mod N.
+The counting register of 2n qubits, n being the number of the bits of N, is put into the
+uniform superposition, its qubit j controls the multiplication of the work register, starting
+from |1⟩, by a^(2^j) mod N, and its inverse quantum Fourier transform measures a number close
+to s 2^(2n) / r for a random s. The continued fraction expansion of the measured fraction then
+gives r as a denominator of its convergents, or a small multiple of one when s and r have a
+common factor. The measured 0, of s = 0, and the fractions without a convergent of the order
+are failed runs, and the circuit is run again. An even order with
+a^(r/2) ≠ -1 mod N gives the factors gcd(a^(r/2) ± 1, N).
+
+The multiplication by a constant is a permutation of the work register, applied as its cycles
+of the transpositions of two basis states, each a multi-controlled X between CNOT gates.
+
+Example run:
+cargo run --release --bin shor
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use quantum_simulation::blocks::mcx_with_ancilla;
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::fourier::iqft;
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const NUMBERS: [u64; 2] = [15, 21];
+const MAX_ATTEMPT_COUNT: usize = 20;
+// The most runs of the order finding circuit of a base.
+const MAX_RUN_COUNT: usize = 5;
+// The largest multiple of the denominator of a convergent tried as the order, for the common
+// factors of s and r.
+const MAX_ORDER_MULTIPLE: u64 = 4;
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn pow_mod(base: u64, exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1;
+    let mut base = base % modulus;
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result
+}
+
+// The layout of the qubits of the order finding circuit.
+struct Registers {
+    counting: Vec<usize>,
+    work: Vec<usize>,
+    ancillas: Vec<usize>,
+}
+
+impl Registers {
+    fn new(bit_count: usize) -> Registers {
+        let counting_count = 2 * bit_count;
+        // The multi-controlled X of a transposition has the control qubit and all the other
+        // work qubits as its controls.
+        let ancilla_count = bit_count.saturating_sub(2);
+        Registers {
+            counting: (0..counting_count).collect(),
+            work: (counting_count..counting_count + bit_count).collect(),
+            ancillas: (counting_count + bit_count..counting_count + bit_count + ancilla_count)
+                .collect(),
+        }
+    }
+
+    fn qubit_count(&self) -> usize {
+        self.counting.len() + self.work.len() + self.ancillas.len()
+    }
+}
+
+// Swap the basis states x and y of the work register if the control qubit is |1⟩.
+fn controlled_transposition(
+    circuit: &mut Circuit,
+    registers: &Registers,
+    control: usize,
+    x: u64,
+    y: u64,
+) {
+    // With the bit b of x set and of y not, the CNOT gates from b onto the other different bits
+    // map x to y with the bit b set, so that the two states differ only in the bit b.
+    let (x, y) = if x & !y != 0 { (x, y) } else { (y, x) };
+    let difference = x ^ y;
+    let b = (x & !y).trailing_zeros() as usize;
+    let work = &registers.work;
+    let mut conjugation = Circuit::new(circuit.qubit_count());
+    for i in 0..work.len() {
+        if i != b && (difference >> i) & 1 == 1 {
+            conjugation.cnot(work[b], work[i]);
+        }
+    }
+    // The other bits are the controls of the X on the bit b, flipped where they are 0 in y.
+    let other_bits: Vec<usize> = (0..work.len()).filter(|&i| i != b).collect();
+    for &i in other_bits.iter() {
+        if (y >> i) & 1 == 0 {
+            conjugation.pauli_x(work[i]);
+        }
+    }
+
+    circuit.extend(&conjugation);
+    let mcx = mcx_with_ancilla(other_bits.len() + 1);
+    let mut qubit_numbers: Vec<usize> = other_bits.iter().map(|&i| work[i]).collect();
+    qubit_numbers.push(control);
+    qubit_numbers.push(work[b]);
+    qubit_numbers.extend_from_slice(&registers.ancillas[..mcx.qubit_count() - qubit_numbers.len()]);
+    circuit.append(&mcx, &qubit_numbers);
+    for gate in conjugation.gates().iter().rev() {
+        circuit.push(*gate);
+    }
+}
+
+// Multiply the work register by the multiplier modulo the modulus if the control qubit is |1⟩,
+// leaving the states of the numbers from the modulus up unchanged.
+fn controlled_multiplication(
+    circuit: &mut Circuit,
+    registers: &Registers,
+    control: usize,
+    multiplier: u64,
+    modulus: u64,
+) {
+    let mut is_visited = vec![false; modulus as usize];
+    for start in 1..modulus {
+        if is_visited[start as usize] {
+            continue;
+        }
+        // The cycle start -> multiplier·start -> ... as the transpositions of start with the
+        // following numbers in order.
+        let mut x = start;
+        loop {
+            is_visited[x as usize] = true;
+            x = x * multiplier % modulus;
+            if x == start {
+                break;
+            }
+            controlled_transposition(circuit, registers, control, start, x);
+        }
+    }
+}
+
+// The order finding circuit of a modulo the modulus, measuring the counting register.
+fn order_finding_circuit(a: u64, modulus: u64, registers: &Registers) -> Circuit {
+    let mut circuit = Circuit::new(registers.qubit_count());
+    circuit.pauli_x(registers.work[0]);
+    for (j, &control) in registers.counting.iter().enumerate() {
+        circuit.hadamard(control);
+        let multiplier = pow_mod(a, 1 << j, modulus);
+        controlled_multiplication(&mut circuit, registers, control, multiplier, modulus);
+    }
+    circuit
+}
+
+// The order of a modulo the modulus from the fraction numerator / denominator close to s / r:
+// the least denominator of its convergents, or a small multiple of one when s and r have
+// a common factor, whose power of a is 1. The fraction 0 of s = 0 gives no order.
+fn order_from_fraction(numerator: u64, denominator: u64, a: u64, modulus: u64) -> Option<u64> {
+    if numerator == 0 {
+        return None;
+    }
+    let (mut p, mut q) = (numerator, denominator);
+    // The denominators of the last two convergents.
+    let (mut k0, mut k1) = (1u64, 0u64);
+    while q != 0 {
+        let term = p / q;
+        (p, q) = (q, p % q);
+        (k0, k1) = (k1, term * k1 + k0);
+        if k1 > modulus {
+            break;
+        }
+        let order = (1..=MAX_ORDER_MULTIPLE)
+            .map(|multiple| multiple * k1)
+            .take_while(|&r| r <= modulus)
+            .find(|&r| pow_mod(a, r, modulus) == 1);
+        if order.is_some() {
+            return order;
+        }
+    }
+    None
+}
+
+// Find the order of a modulo the modulus, running the circuit again after each failed run.
+fn find_order(a: u64, modulus: u64, rnd_seed: u64) -> Option<u64> {
+    let bit_count = (u64::BITS - modulus.leading_zeros()) as usize;
+    let registers = Registers::new(bit_count);
+    let circuit = order_finding_circuit(a, modulus, &registers);
+    let mut simulation = QuantumSimulation::new(registers.qubit_count(), rnd_seed);
+    for _ in 0..MAX_RUN_COUNT {
+        simulation.reset();
+        circuit.run(&mut simulation);
+        iqft(&mut simulation, &registers.counting);
+        let measured_bits = simulation.measure(registers.counting.clone());
+        let measured_value = measured_bits
+            .iter()
+            .enumerate()
+            .map(|(bit, &value)| (value as u64) << bit)
+            .sum::<u64>();
+        print!(
+            "  a = {}: measured {} / 2^{}",
+            a,
+            measured_value,
+            registers.counting.len()
+        );
+        match order_from_fraction(measured_value, 1 << registers.counting.len(), a, modulus) {
+            Some(order) => {
+                println!();
+                return Some(order);
+            }
+            None => println!(", no order, running again"),
+        }
+    }
+    None
+}
+
+// A nontrivial factor of the odd composite number, not a prime power.
+fn factor(modulus: u64, rng: &mut ChaCha12Rng) -> Option<u64> {
+    for _ in 0..MAX_ATTEMPT_COUNT {
+        // The bases sharing a factor with the number, which give the factor classically,
+        // are skipped to show the order finding.
+        let a = rng.gen_range(2..modulus);
+        if gcd(a, modulus) > 1 {
+            continue;
+        }
+        let order = match find_order(a, modulus, rng.gen()) {
+            Some(order) => order,
+            None => continue,
+        };
+        println!("  The order of {} modulo {} is {}.", a, modulus, order);
+        if order % 2 == 1 {
+            continue;
+        }
+        let half_power = pow_mod(a, order / 2, modulus);
+        if half_power == modulus - 1 {
+            continue;
+        }
+        for divisor in [gcd(half_power + 1, modulus), gcd(half_power - 1, modulus)] {
+            if divisor > 1 && divisor < modulus {
+                return Some(divisor);
+            }
+        }
+    }
+    None
+}
+
+fn main() {
+    println!("Shor's algorithm:");
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    for modulus in NUMBERS {
+        println!("Factoring {}:", modulus);
+        match factor(modulus, &mut rng) {
+            Some(divisor) => println!("{} = {} × {}", modulus, divisor, modulus / divisor),
+            None => println!("No factors of {} were found.", modulus),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+/*
+Nine-qubit code of Shor against the arbitrary errors of a single qubit.
+
+Usage:
+shor_code [--runs <count>] [--seed <seed>]
+
+The qubit is encoded into α|+++⟩ + β|---⟩ of the three blocks |±⟩ = (|000⟩ ± |111⟩)/√2, the
+bit-flip code of each block protecting against the X errors and the phase-flip code of the
+blocks against the Z errors, which together correct any error of one qubit. The depolarizing
+channel of the probability p acts on each of the nine qubits, and the decoding circuit corrects
+the errors by the majority votes of the Toffoli gates. The logical error rate, the average
+infidelity of the decoded qubit over the six eigenstates of X, Y, and Z, is swept over p and
+compared with the error rate p/2 of an unencoded qubit. The code fails for two errors in
+different blocks, so the logical error rate grows as p^2 and falls below p/2 for the small p.
+
+Example run:
+cargo run --release --bin shor_code -- --runs 10000
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use num_complex::Complex;
+
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::noise::KrausChannel;
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: shor_code [--runs <count>] [--seed <seed>]";
+const QUBIT_COUNT: usize = 9;
+const DEFAULT_RUN_COUNT: usize = 6000;
+const PROBABILITIES: [f64; 8] = [0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.3, 0.5];
+// The width of the bars of the error rates on the logarithmic scale from 10^-4 to 1.
+const BAR_WIDTH: f64 = 40.0;
+
+struct Options {
+    run_count: usize,
+    seed: u64,
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        run_count: DEFAULT_RUN_COUNT,
+        seed: 0,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--runs" => {
+                options.run_count = value("--runs")?
+                    .parse()
+                    .map_err(|_| "The number of runs has to be a positive integer.")?;
+                if options.run_count == 0 {
+                    return Err("The number of runs has to be a positive integer.".to_string());
+                }
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+// The eigenstates of X, Y, and Z prepared on the qubit 0, with their amplitudes of |0⟩ and |1⟩.
+fn input_states() -> Vec<(Circuit, [Complex<f64>; 2])> {
+    let r = std::f64::consts::FRAC_1_SQRT_2;
+    let amplitudes = [
+        [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+        [Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+        [Complex::new(r, 0.0), Complex::new(r, 0.0)],
+        [Complex::new(r, 0.0), Complex::new(-r, 0.0)],
+        [Complex::new(r, 0.0), Complex::new(0.0, r)],
+        [Complex::new(r, 0.0), Complex::new(0.0, -r)],
+    ];
+    amplitudes
+        .into_iter()
+        .enumerate()
+        .map(|(i, amplitudes)| {
+            let mut circuit = Circuit::new(QUBIT_COUNT);
+            if i % 2 == 1 {
+                circuit.pauli_x(0);
+            }
+            if i >= 2 {
+                circuit.hadamard(0);
+            }
+            if i >= 4 {
+                circuit.s(0);
+            }
+            (circuit, amplitudes)
+        })
+        .collect()
+}
+
+fn encoding_circuit() -> Circuit {
+    let mut circuit = Circuit::new(QUBIT_COUNT);
+    circuit.cnot(0, 3);
+    circuit.cnot(0, 6);
+    for block in [0, 3, 6] {
+        circuit.hadamard(block);
+        circuit.cnot(block, block + 1);
+        circuit.cnot(block, block + 2);
+    }
+    circuit
+}
+
+// The inverse of the encoding, with the majority vote of each block into its first qubit and
+// then of the blocks into the qubit 0.
+fn decoding_circuit() -> Circuit {
+    let mut circuit = Circuit::new(QUBIT_COUNT);
+    for block in [0, 3, 6] {
+        circuit.cnot(block, block + 1);
+        circuit.cnot(block, block + 2);
+        circuit.toffoli(block + 1, block + 2, block);
+        circuit.hadamard(block);
+    }
+    circuit.cnot(0, 3);
+    circuit.cnot(0, 6);
+    circuit.toffoli(3, 6, 0);
+    circuit
+}
+
+// The fidelity of the qubit 0 with the state of the amplitudes, summed over the states of the
+// other qubits.
+fn fidelity(amplitudes: &[Complex<f64>], state: &[Complex<f64>; 2]) -> f64 {
+    amplitudes
+        .chunks_exact(2)
+        .map(|pair| (state[0].conj() * pair[0] + state[1].conj() * pair[1]).norm_sqr())
+        .sum()
+}
+
+fn bar(error_rate: f64) -> String {
+    let length = (BAR_WIDTH * (1.0 + error_rate.max(1e-4).log10() / 4.0)).round();
+    "#".repeat(length as usize)
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!("Shor nine-qubit code under the depolarizing noise:");
+    let states = input_states();
+    let encoding = encoding_circuit();
+    let decoding = decoding_circuit();
+    let mut simulation = QuantumSimulation::new(QUBIT_COUNT, options.seed);
+    println!(
+        "{:>6}  {:>13}  {:>15}  logical error rate (log scale 10^-4..1)",
+        "p", "logical error", "unencoded error"
+    );
+    // The largest of the probabilities with the logical error rate below the unencoded one.
+    let mut pseudo_threshold = None;
+    for probability in PROBABILITIES {
+        let channel = KrausChannel::depolarizing(probability);
+        let mut infidelity = 0.0;
+        for run in 0..options.run_count {
+            let (preparation, state) = &states[run % states.len()];
+            simulation.reset();
+            preparation.run(&mut simulation);
+            encoding.run(&mut simulation);
+            for qubit_number in 0..QUBIT_COUNT {
+                simulation.apply_kraus(channel.kraus_operators(), qubit_number);
+            }
+            decoding.run(&mut simulation);
+            infidelity += 1.0 - fidelity(&simulation.amplitudes(), state);
+        }
+        let error_rate = infidelity / options.run_count as f64;
+        println!(
+            "{:>6}  {:>13.5}  {:>15.5}  {}",
+            probability,
+            error_rate,
+            probability / 2.0,
+            bar(error_rate)
+        );
+        if error_rate < probability / 2.0 {
+            pseudo_threshold = Some(probability);
+        }
+    }
+    match pseudo_threshold {
+        Some(probability) => println!(
+            "The encoded qubit has the lower error rate up to p = {}.",
+            probability
+        ),
+        None => println!("The encoded qubit has the higher error rate for all p."),
+    }
+    println!();
+    ExitCode::SUCCESS
+}
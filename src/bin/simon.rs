@@ -0,0 +1,177 @@
+/*
+Simon's algorithm with the classical post-processing over GF(2).
+
+Usage:
+simon [--secret <bits>] [--seed <seed>]
+
+The oracle computes a function f of n bits with f(x) = f(x ⊕ s) for the secret mask s, here
+f(x) = x ⊕ (x_k s) with k the lowest bit of s, which is two-to-one for s ≠ 0 and one-to-one for
+s = 0. Each query puts the input register into the uniform superposition, computes f into the
+output register, and measures the input register in the Hadamard basis, which gives a random
+vector y with y·s = 0. The queries are repeated until the measured vectors have the rank n - 1,
+and 10 more queries check that the rank does not reach n, which it does only for s = 0, missing
+it with the probability of at most 2^-10. The secret is the nonzero vector of the null space
+found by the Gaussian elimination over GF(2), and is checked classically by f(0) = f(s). About
+n queries are needed instead of the Ω(2^(n/2)) of the classical algorithms.
+
+Example run:
+cargo run --release --bin simon -- --secret 10110
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::linalg_gf2::{null_space, rank};
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: simon [--secret <bits>] [--seed <seed>]";
+const DEFAULT_SECRET: &str = "1011";
+// The oracle of n bits acts on 2n qubits.
+const MAX_BIT_COUNT: usize = 10;
+// The number of the queries after the rank n - 1 that tell s = 0 from s ≠ 0.
+const CONFIRMATION_QUERY_COUNT: usize = 10;
+
+struct Options {
+    // The secret mask, the last character its bit 0.
+    secret: u64,
+    bit_count: usize,
+    seed: u64,
+}
+
+fn parse_bits(bits: &str) -> Result<u64, String> {
+    if bits.is_empty() || bits.len() > MAX_BIT_COUNT {
+        return Err(format!(
+            "The secret has to have from 1 to {} bits.",
+            MAX_BIT_COUNT
+        ));
+    }
+    u64::from_str_radix(bits, 2)
+        .map_err(|_| "The secret has to be a string of 0 and 1.".to_string())
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        secret: parse_bits(DEFAULT_SECRET)?,
+        bit_count: DEFAULT_SECRET.len(),
+        seed: 0,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--secret" => {
+                let bits = value("--secret")?;
+                options.secret = parse_bits(bits)?;
+                options.bit_count = bits.len();
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+// The oracle |x⟩|y⟩ -> |x⟩|y ⊕ f(x)⟩ of the input x on the qubits 0..n and the output on the
+// qubits n..2n, copying x and adding s if the lowest bit k of s is set in x.
+fn oracle(n: usize, secret: u64) -> Circuit {
+    let mut circuit = Circuit::new(2 * n);
+    for i in 0..n {
+        circuit.cnot(i, n + i);
+    }
+    if secret != 0 {
+        let k = secret.trailing_zeros() as usize;
+        for i in (0..n).filter(|&i| (secret >> i) & 1 == 1) {
+            circuit.cnot(k, n + i);
+        }
+    }
+    circuit
+}
+
+fn decode(values: &[bool]) -> u64 {
+    values
+        .iter()
+        .enumerate()
+        .map(|(bit, &value)| (value as u64) << bit)
+        .sum()
+}
+
+// The classical evaluation of f on the basis state of the input.
+fn evaluate_oracle(oracle: &Circuit, n: usize, input: u64) -> u64 {
+    let mut simulation = QuantumSimulation::new(2 * n, 0u64);
+    for i in (0..n).filter(|&i| (input >> i) & 1 == 1) {
+        simulation.pauli_x(i);
+    }
+    oracle.run(&mut simulation);
+    decode(&simulation.measure((n..2 * n).collect()))
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let n = options.bit_count;
+    println!("Simon's algorithm of {} bits:", n);
+    let oracle = oracle(n, options.secret);
+    let mut query = Circuit::new(2 * n);
+    for i in 0..n {
+        query.hadamard(i);
+    }
+    query.extend(&oracle);
+    for i in 0..n {
+        query.hadamard(i);
+    }
+
+    let mut simulation = QuantumSimulation::new(2 * n, options.seed);
+    let mut vectors = Vec::new();
+    let mut confirmation_query_count = 0;
+    while confirmation_query_count < CONFIRMATION_QUERY_COUNT && rank(&vectors) < n {
+        simulation.reset();
+        query.run(&mut simulation);
+        let vector = decode(&simulation.measure((0..n).collect()));
+        vectors.push(vector);
+        println!("y = {:0width$b}", vector, width = n);
+        if rank(&vectors) + 1 >= n {
+            confirmation_query_count += 1;
+        }
+    }
+    println!(
+        "{} queries, rank {} of the measured vectors",
+        vectors.len(),
+        rank(&vectors)
+    );
+    let recovered_secret = null_space(&vectors, n).first().copied().unwrap_or(0);
+    println!("Recovered secret: {:0width$b}", recovered_secret, width = n);
+    // The rank n of s = 0 leaves nothing to check.
+    let is_verified = recovered_secret == 0
+        || evaluate_oracle(&oracle, n, 0) == evaluate_oracle(&oracle, n, recovered_secret);
+    if recovered_secret != 0 {
+        println!(
+            "f(0) = f(s) check: {}",
+            if is_verified { "passed" } else { "failed" }
+        );
+    }
+    println!();
+
+    if recovered_secret == options.secret && is_verified {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,146 @@
+/*
+Sorting of two quantum registers by a comparator and controlled swaps.
+
+Usage:
+sort [--bits <count>]
+
+The comparator block sets a flag qubit if b < a, and the Fredkin gates controlled by the flag
+swap the registers bit by bit, so that the register a holds min(a, b) and the register b
+max(a, b). The flag keeps the information of the order, which sorting erases, so that the
+network is reversible. All the pairs of the n-bit inputs are checked, and a superposition of
+the inputs is sorted into the superposition of the sorted pairs entangled with the flag.
+
+Example run:
+cargo run --release --bin sort -- --bits 3
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::blocks::comparator;
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::evaluation::evaluate;
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str = "Usage: sort [--bits <count>]";
+const DEFAULT_BIT_COUNT: usize = 2;
+const MAX_BIT_COUNT: usize = 8;
+const RUN_COUNT: usize = 100;
+
+fn parse_bit_count(arguments: &[String]) -> Result<usize, String> {
+    let mut arguments = arguments.iter();
+    let mut bit_count = DEFAULT_BIT_COUNT;
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--bits" => {
+                bit_count = arguments
+                    .next()
+                    .ok_or("The option --bits needs a value.")?
+                    .parse()
+                    .map_err(|_| "The number of bits has to be a positive integer.")?;
+                if bit_count == 0 || bit_count > MAX_BIT_COUNT {
+                    return Err(format!(
+                        "The number of bits has to be from 1 to {}.",
+                        MAX_BIT_COUNT
+                    ));
+                }
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(bit_count)
+}
+
+// The sorting network of the registers a on the qubits 0..n and b on the qubits n..2n, with the
+// ancilla of the comparator on the qubit 2n and the flag b < a on the qubit 2n+1.
+fn sorting_circuit(n: usize) -> Circuit {
+    let mut circuit = Circuit::new(2 * n + 2);
+    // The comparator of b < a, with the registers exchanged.
+    let mut qubit_numbers: Vec<usize> = (n..2 * n).chain(0..n).collect();
+    qubit_numbers.extend_from_slice(&[2 * n, 2 * n + 1]);
+    circuit.append(&comparator(n), &qubit_numbers);
+    let flag = 2 * n + 1;
+    for i in 0..n {
+        circuit.cnot(n + i, i);
+        circuit.toffoli(flag, i, n + i);
+        circuit.cnot(n + i, i);
+    }
+    circuit
+}
+
+fn encode(simulation: &mut dyn Simulation, qubit_numbers: &[usize], value: u64) {
+    for (bit, &qubit_number) in qubit_numbers.iter().enumerate() {
+        if (value >> bit) & 1 == 1 {
+            simulation.pauli_x(qubit_number);
+        }
+    }
+}
+
+fn decode(values: &[bool]) -> u64 {
+    values
+        .iter()
+        .enumerate()
+        .map(|(bit, &value)| (value as u64) << bit)
+        .sum()
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let n = match parse_bit_count(&arguments) {
+        Ok(bit_count) => bit_count,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!("Sorting of two {}-bit registers:", n);
+    let circuit = sorting_circuit(n);
+    let a_qubits: Vec<usize> = (0..n).collect();
+    let b_qubits: Vec<usize> = (n..2 * n).collect();
+    let mut simulation = QuantumSimulation::new(circuit.qubit_count(), 0u64);
+    let mut error_count = 0;
+    for a in 0..1u64 << n {
+        for b in 0..1u64 << n {
+            simulation.reset();
+            encode(&mut simulation, &a_qubits, a);
+            encode(&mut simulation, &b_qubits, b);
+            circuit.run(&mut simulation);
+            let values = simulation.measure_all();
+            let (low, high) = (decode(&values[0..n]), decode(&values[n..2 * n]));
+            if low != a.min(b) || high != a.max(b) || values[2 * n] || values[2 * n + 1] != (b < a)
+            {
+                println!("({}, {}) sorted into ({}, {})", a, b, low, high);
+                error_count += 1;
+            }
+        }
+    }
+    println!(
+        "{} of {} pairs are sorted correctly, with {} gates.",
+        (1 << (2 * n)) - error_count,
+        1 << (2 * n),
+        circuit.gates().len()
+    );
+
+    // The uniform superposition of the register a with b = 1 is sorted into the superposition of
+    // the pairs with the flag of each.
+    println!("Sorting of the superposition of all a with b = 1:");
+    simulation.reset();
+    for &qubit_number in a_qubits.iter() {
+        simulation.hadamard(qubit_number);
+    }
+    encode(&mut simulation, &b_qubits, 1);
+    circuit.run(&mut simulation);
+    let mut measured_qubits: Vec<usize> = (0..2 * n).collect();
+    measured_qubits.push(2 * n + 1);
+    evaluate(simulation.sample(&measured_qubits, RUN_COUNT)).print();
+    println!();
+
+    if error_count == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,136 @@
+/*
+Steane [[7,1,3]] code on the stabilizer simulation.
+
+The logical |0⟩ is encoded into seven qubits, the X, Y, or Z error on each qubit is injected,
+and the syndrome measured by six ancilla qubits gives the position of the error in binary, the
+Z checks for the X part and the X checks for the Z part of the error, which the gates
+conditional on the syndrome bits correct. The transversal H and CNOT then act on the logical
+qubits: H turns the logical |+⟩ into |0⟩, and CNOT of the logical |1⟩ and |0⟩ gives |1⟩|1⟩, read
+out by measuring the blocks and decoding the measured bits.
+
+Example run:
+cargo run --release --bin steane_code
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::circuit::Circuit;
+use quantum_simulation::error_correction::{
+    steane_cnot, steane_encoding, steane_error_correction, steane_hadamard, steane_logical_value,
+    STEANE_QUBIT_COUNT, STEANE_SYNDROME_BIT_COUNT,
+};
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::stabilizer_simulation::StabilizerSimulation;
+
+fn to_bit_string(bits: &[bool]) -> String {
+    bits.iter()
+        .map(|&bit| if bit { '1' } else { '0' })
+        .collect()
+}
+
+// Whether the measured qubits are a codeword of the logical 0 with no flip, of the even weight and
+// the zero syndrome.
+fn is_codeword_of_zero(values: &[bool]) -> bool {
+    values.iter().filter(|&&value| value).count() % 2 == 0 && !steane_logical_value(values)
+}
+
+fn main() -> ExitCode {
+    println!("Steane [[7,1,3]] code:");
+    let block: Vec<usize> = (0..STEANE_QUBIT_COUNT).collect();
+    let qubit_count = STEANE_QUBIT_COUNT + STEANE_SYNDROME_BIT_COUNT;
+    let mut simulation = StabilizerSimulation::new(STEANE_QUBIT_COUNT, 0);
+    steane_encoding().run(&mut simulation);
+    println!("Stabilizers of the logical |0⟩:");
+    for stabilizer in simulation.stabilizers() {
+        println!("  {}", stabilizer);
+    }
+
+    // The syndrome bits are printed as the Z checks and the X checks, the highest bit first.
+    println!("Error  Z checks  X checks  Corrected");
+    let mut error_count = 0;
+    for pauli in ['X', 'Y', 'Z'] {
+        for qubit_number in 0..STEANE_QUBIT_COUNT {
+            // The logical |0⟩ and |+⟩ are corrected if they are read out as the codewords of 0 in
+            // the Z and the X basis, which detect the remaining X and Z errors.
+            let mut syndrome = Vec::new();
+            let mut is_corrected = true;
+            for is_plus in [false, true] {
+                let mut circuit = Circuit::with_clbits(qubit_count, STEANE_SYNDROME_BIT_COUNT);
+                if is_plus {
+                    circuit.hadamard(0);
+                }
+                circuit.append(&steane_encoding(), &block);
+                if pauli != 'Z' {
+                    circuit.pauli_x(qubit_number);
+                }
+                if pauli != 'X' {
+                    circuit.pauli_z(qubit_number);
+                }
+                circuit.extend(&steane_error_correction());
+                if is_plus {
+                    circuit.append(&steane_hadamard(), &block);
+                }
+                let mut simulation = StabilizerSimulation::new(qubit_count, 0);
+                syndrome = circuit.run(&mut simulation);
+                is_corrected &= is_codeword_of_zero(&simulation.measure(block.clone()));
+            }
+            syndrome.reverse();
+            if !is_corrected {
+                error_count += 1;
+            }
+            println!(
+                "{}{}     {}       {}       {}",
+                pauli,
+                qubit_number,
+                to_bit_string(&syndrome[3..]),
+                to_bit_string(&syndrome[..3]),
+                is_corrected
+            );
+        }
+    }
+
+    println!("Transversal H of the logical |+⟩:");
+    let mut simulation = StabilizerSimulation::new(STEANE_QUBIT_COUNT, 0);
+    simulation.hadamard(0);
+    steane_encoding().run(&mut simulation);
+    steane_hadamard().run(&mut simulation);
+    let values = simulation.measure(block.clone());
+    let value = steane_logical_value(&values);
+    println!("  {} decoded as |{}⟩", to_bit_string(&values), value as u8);
+    if value {
+        error_count += 1;
+    }
+
+    println!("Transversal CNOT of the logical |1⟩ and |0⟩:");
+    let both_blocks: Vec<usize> = (0..2 * STEANE_QUBIT_COUNT).collect();
+    let mut circuit = Circuit::new(2 * STEANE_QUBIT_COUNT);
+    circuit.pauli_x(0);
+    circuit.append(&steane_encoding(), &block);
+    circuit.append(&steane_encoding(), &both_blocks[STEANE_QUBIT_COUNT..]);
+    circuit.extend(&steane_cnot());
+    let mut simulation = StabilizerSimulation::new(2 * STEANE_QUBIT_COUNT, 0);
+    circuit.run(&mut simulation);
+    let values = simulation.measure(both_blocks);
+    let (control, target) = values.split_at(STEANE_QUBIT_COUNT);
+    let (control_value, target_value) =
+        (steane_logical_value(control), steane_logical_value(target));
+    println!(
+        "  {} {} decoded as |{}⟩|{}⟩",
+        to_bit_string(control),
+        to_bit_string(target),
+        control_value as u8,
+        target_value as u8
+    );
+    if !control_value || !target_value {
+        error_count += 1;
+    }
+    println!();
+
+    if error_count == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
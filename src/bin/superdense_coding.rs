@@ -52,14 +52,14 @@ fn send_and_receive(first_bit: bool, second_bit: bool) -> (bool, bool) {
 fn denote(first_bit: bool, second_bit: bool) -> String {
     let mut s = String::from("");
     if second_bit {
-        s = s + "1"
+        s += "1"
     } else {
-        s = s + "0"
+        s += "0"
     }
     if first_bit {
-        s = s + "1"
+        s += "1"
     } else {
-        s = s + "0"
+        s += "0"
     }
 
     s
@@ -0,0 +1,349 @@
+/*
+Memory experiment of the distance-3 surface code on the stabilizer simulation.
+
+Usage:
+surface_code [--rounds <count>] [--runs <count>] [--seed <seed>]
+
+The rotated surface code of distance 3 keeps a logical qubit on the 3×3 data qubits 0..9, row by
+row, with four Z and four X stabilizers measured by the ancilla qubits 9..17:
+  Z0 Z1 Z3 Z4, Z4 Z5 Z7 Z8, Z3 Z6, Z2 Z5 and X1 X2 X4 X5, X3 X4 X6 X7, X0 X1, X7 X8.
+The logical |0⟩ is kept over the rounds of the syndrome extraction, each preceded by the
+depolarizing noise of the probability p on each data qubit and with each measured syndrome bit
+flipped with the probability p. The data qubits are measured at the end, which gives the last
+syndrome of the Z stabilizers and the logical Z0 Z1 Z2.
+
+The X errors flip the Z stabilizers, and the syndrome bits changing from the previous round are
+the detection events. A data error makes a pair of events in the same round, or an event at a
+boundary, and a measurement error a pair of events of the same stabilizer in the consecutive
+rounds. The decoder matches the events in pairs or to the boundary with the least total weight,
+the weight of a pair being the number of the data qubits and the rounds between them, by the
+dynamic programming over the subsets of the events, and the logical Z is flipped by the matched
+paths crossing the top row. The logical error rate is tabulated against p with the error rate
+of an unencoded qubit over the same rounds.
+
+Example run:
+cargo run --release --bin surface_code -- --rounds 3 --runs 10000
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use quantum_simulation::simulation::Simulation;
+use quantum_simulation::stabilizer_simulation::StabilizerSimulation;
+
+const USAGE: &str = "Usage: surface_code [--rounds <count>] [--runs <count>] [--seed <seed>]";
+const DATA_QUBIT_COUNT: usize = 9;
+const Z_STABILIZERS: [&[usize]; 4] = [&[0, 1, 3, 4], &[4, 5, 7, 8], &[3, 6], &[2, 5]];
+const X_STABILIZERS: [&[usize]; 4] = [&[1, 2, 4, 5], &[3, 4, 6, 7], &[0, 1], &[7, 8]];
+// The support of the logical Z, the top row of the data qubits.
+const LOGICAL_Z: [usize; 3] = [0, 1, 2];
+const PROBABILITIES: [f64; 7] = [0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1];
+const DEFAULT_ROUND_COUNT: usize = 3;
+const DEFAULT_RUN_COUNT: usize = 5000;
+// The largest number of the detection events matched, the dynamic programming taking 2^n steps.
+const MAX_EVENT_COUNT: usize = 20;
+
+struct Options {
+    round_count: usize,
+    run_count: usize,
+    seed: u64,
+}
+
+fn parse_count(value: &str, name: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!(
+            "The number of {} has to be a positive integer.",
+            name
+        )),
+    }
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        round_count: DEFAULT_ROUND_COUNT,
+        run_count: DEFAULT_RUN_COUNT,
+        seed: 0,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        match argument.as_str() {
+            "--rounds" => options.round_count = parse_count(value("--rounds")?, "rounds")?,
+            "--runs" => options.run_count = parse_count(value("--runs")?, "runs")?,
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "The seed has to be a nonnegative integer.")?
+            }
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+// A shortest path of the X errors between two Z stabilizers, or a Z stabilizer and the
+// boundary, with its number of the data qubits and whether it flips the logical Z.
+#[derive(Clone, Copy, Debug)]
+struct Path {
+    length: usize,
+    is_logical_flip: bool,
+}
+
+// The decoding graph of the X errors, the Z stabilizers joined by the data qubits in two of them
+// and the boundary node joined to each stabilizer by the data qubits in one of them.
+struct DecodingGraph {
+    // The shortest paths between the stabilizers, and the last one to the boundary.
+    paths: Vec<Vec<Path>>,
+}
+
+impl DecodingGraph {
+    fn new() -> DecodingGraph {
+        let boundary = Z_STABILIZERS.len();
+        let node_count = boundary + 1;
+        // The edges of the data qubits, by the Floyd–Warshall algorithm over the paths.
+        let unreachable = Path {
+            length: usize::MAX / 2,
+            is_logical_flip: false,
+        };
+        let mut paths = vec![vec![unreachable; node_count]; node_count];
+        for (node, row) in paths.iter_mut().enumerate() {
+            row[node] = Path {
+                length: 0,
+                is_logical_flip: false,
+            };
+        }
+        for qubit_number in 0..DATA_QUBIT_COUNT {
+            let mut nodes: Vec<usize> = (0..boundary)
+                .filter(|&s| Z_STABILIZERS[s].contains(&qubit_number))
+                .collect();
+            if nodes.len() == 1 {
+                nodes.push(boundary);
+            }
+            let edge = Path {
+                length: 1,
+                is_logical_flip: LOGICAL_Z.contains(&qubit_number),
+            };
+            let (node0, node1) = (nodes[0], nodes[1]);
+            if paths[node0][node1].length > 1 {
+                paths[node0][node1] = edge;
+                paths[node1][node0] = edge;
+            }
+        }
+        for k in 0..node_count {
+            for i in 0..node_count {
+                for j in 0..node_count {
+                    let length = paths[i][k].length + paths[k][j].length;
+                    if length < paths[i][j].length {
+                        paths[i][j] = Path {
+                            length,
+                            is_logical_flip: paths[i][k].is_logical_flip
+                                != paths[k][j].is_logical_flip,
+                        };
+                    }
+                }
+            }
+        }
+        DecodingGraph { paths }
+    }
+
+    // The path between the detection events of the stabilizers in the rounds, the measurement
+    // errors adding the rounds between them.
+    fn path(&self, event0: (usize, usize), event1: (usize, usize)) -> Path {
+        let path = self.paths[event0.0][event1.0];
+        Path {
+            length: path.length + event0.1.abs_diff(event1.1),
+            is_logical_flip: path.is_logical_flip,
+        }
+    }
+
+    fn boundary_path(&self, event: (usize, usize)) -> Path {
+        self.paths[event.0][Z_STABILIZERS.len()]
+    }
+
+    // Whether the matching of the least weight of the events, the stabilizers and the rounds of
+    // the detection events, flips the logical Z, by the dynamic programming over the subsets of
+    // the unmatched events, matching the first of them to the boundary or to another event.
+    fn decode(&self, events: &[(usize, usize)]) -> bool {
+        let n = events.len();
+        assert!(
+            n <= MAX_EVENT_COUNT,
+            "The number of the detection events has to be at most {}.",
+            MAX_EVENT_COUNT
+        );
+        let mut best = vec![
+            Path {
+                length: 0,
+                is_logical_flip: false,
+            };
+            1 << n
+        ];
+        for mask in 1..1usize << n {
+            let i = mask.trailing_zeros() as usize;
+            let rest = mask & !(1 << i);
+            let join = |path: Path, rest: usize| Path {
+                length: path.length + best[rest].length,
+                is_logical_flip: path.is_logical_flip != best[rest].is_logical_flip,
+            };
+            let mut path = join(self.boundary_path(events[i]), rest);
+            for j in (i + 1..n).filter(|&j| (rest >> j) & 1 == 1) {
+                let candidate = join(self.path(events[i], events[j]), rest & !(1 << j));
+                if candidate.length < path.length {
+                    path = candidate;
+                }
+            }
+            best[mask] = path;
+        }
+        best[(1 << n) - 1].is_logical_flip
+    }
+}
+
+// Measure the stabilizer by the ancilla qubit, reset to |0⟩ after the measurement.
+fn measure_stabilizer(
+    simulation: &mut StabilizerSimulation,
+    qubit_numbers: &[usize],
+    ancilla: usize,
+    is_x_type: bool,
+) -> bool {
+    if is_x_type {
+        simulation.hadamard(ancilla);
+        for &qubit_number in qubit_numbers {
+            simulation.cnot(ancilla, qubit_number);
+        }
+        simulation.hadamard(ancilla);
+    } else {
+        for &qubit_number in qubit_numbers {
+            simulation.cnot(qubit_number, ancilla);
+        }
+    }
+    let value = simulation.measure(vec![ancilla])[0];
+    if value {
+        simulation.pauli_x(ancilla);
+    }
+    value
+}
+
+// Add the detection events of the syndrome bits changed from the previous syndrome.
+fn add_events(
+    events: &mut Vec<(usize, usize)>,
+    previous_syndrome: &mut [bool],
+    syndrome: &[bool],
+    round: usize,
+) {
+    for (s, (previous_bit, &bit)) in previous_syndrome.iter_mut().zip(syndrome).enumerate() {
+        if bit != *previous_bit {
+            events.push((s, round));
+        }
+        *previous_bit = bit;
+    }
+}
+
+// Run the memory experiment and return whether the decoded logical Z is wrong.
+fn has_logical_error(
+    simulation: &mut StabilizerSimulation,
+    graph: &DecodingGraph,
+    probability: f64,
+    round_count: usize,
+    rng: &mut ChaCha12Rng,
+) -> bool {
+    simulation.reset();
+    // The logical |0⟩ is the +1 eigenstate of the Z stabilizers, and the first measurement of
+    // the X stabilizers projects it into the code space.
+    let mut previous_syndrome = [false; 4];
+    let mut events = Vec::new();
+    for round in 0..round_count {
+        for qubit_number in 0..DATA_QUBIT_COUNT {
+            if rng.gen::<f64>() < probability {
+                match rng.gen_range(0..3) {
+                    0 => simulation.pauli_x(qubit_number),
+                    1 => simulation.pauli_y(qubit_number),
+                    _ => simulation.pauli_z(qubit_number),
+                }
+            }
+        }
+        for (i, stabilizer) in X_STABILIZERS.iter().enumerate() {
+            let ancilla = DATA_QUBIT_COUNT + Z_STABILIZERS.len() + i;
+            measure_stabilizer(simulation, stabilizer, ancilla, true);
+        }
+        let syndrome: Vec<bool> = Z_STABILIZERS
+            .iter()
+            .enumerate()
+            .map(|(i, stabilizer)| {
+                let value = measure_stabilizer(simulation, stabilizer, DATA_QUBIT_COUNT + i, false);
+                value != (rng.gen::<f64>() < probability)
+            })
+            .collect();
+        add_events(&mut events, &mut previous_syndrome, &syndrome, round);
+    }
+
+    // The last syndrome and the logical Z from the measured data qubits.
+    let values = simulation.measure((0..DATA_QUBIT_COUNT).collect());
+    let parity =
+        |qubit_numbers: &[usize]| qubit_numbers.iter().filter(|&&q| values[q]).count() % 2 == 1;
+    let syndrome: Vec<bool> = Z_STABILIZERS
+        .iter()
+        .map(|stabilizer| parity(stabilizer))
+        .collect();
+    add_events(&mut events, &mut previous_syndrome, &syndrome, round_count);
+    if events.len() > MAX_EVENT_COUNT {
+        // Too many events to match, taken as a failure at such a noise.
+        return true;
+    }
+    parity(&LOGICAL_Z) != graph.decode(&events)
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    println!(
+        "Distance-3 surface code memory, {} rounds, {} runs:",
+        options.round_count, options.run_count
+    );
+    let graph = DecodingGraph::new();
+    let mut rng = ChaCha12Rng::seed_from_u64(options.seed);
+    let qubit_count = DATA_QUBIT_COUNT + Z_STABILIZERS.len() + X_STABILIZERS.len();
+    let mut simulation = StabilizerSimulation::new(qubit_count, rng.gen());
+    println!(
+        "{:>6}  {:>13}  {:>15}",
+        "p", "logical error", "unencoded error"
+    );
+    for probability in PROBABILITIES {
+        let error_count = (0..options.run_count)
+            .filter(|_| {
+                has_logical_error(
+                    &mut simulation,
+                    &graph,
+                    probability,
+                    options.round_count,
+                    &mut rng,
+                )
+            })
+            .count();
+        // The X or the Y error of a qubit, with the probability 2p/3 each round, flips its Z.
+        let unencoded_error =
+            (1.0 - (1.0 - 4.0 * probability / 3.0).powi(options.round_count as i32)) / 2.0;
+        println!(
+            "{:>6}  {:>13.5}  {:>15.5}",
+            probability,
+            error_count as f64 / options.run_count as f64,
+            unencoded_error
+        );
+    }
+    println!();
+    ExitCode::SUCCESS
+}
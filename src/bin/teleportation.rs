@@ -1,7 +1,13 @@
 /*
 Quantum teleportation example.
 
-Transfer the full quantum information from one qubit to another qubit.
+Transfer the full quantum information from one qubit to another qubit. The qubit 0 is prepared
+in the states cos(θ/2)|0⟩ + e^(iφ) sin(θ/2)|1⟩ of a few points and of random points uniform on
+the Bloch sphere, and teleported into the qubit 2 by the Bell pair of the qubits 1 and 2. The
+fidelity of the reduced state of the qubit 2 to the input state is 1 for each of the four
+outcomes of the Bell measurement, which are equally likely by the chi-squared test, while the best classical strategy
+of measuring the input and preparing the output from the outcome reaches the average fidelity
+of only 2/3.
 
 Example run:
 cargo run --bin teleportation
@@ -9,52 +15,85 @@ cargo run --bin teleportation
 Copyright © 2024 AlgoHertz. All rights reserved.
 */
 
-use quantum_simulation::evaluation::evaluate;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+use std::process::ExitCode;
+
+use quantum_simulation::evaluation::chi_squared_test;
+use quantum_simulation::protocols::{bloch_state, prepare_bloch_state, teleport};
 use quantum_simulation::simulation::Simulation;
 use quantum_simulation::state_vector_simulation::QuantumSimulation;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
 
 const QUBIT_COUNT: usize = 3;
 const RUN_COUNT: usize = 1000;
+// The named states with their angles θ and φ.
+const STATES: [(&str, f64, f64); 5] = [
+    ("|0⟩", 0.0, 0.0),
+    ("|1⟩", PI, 0.0),
+    ("|+⟩", FRAC_PI_2, 0.0),
+    ("|+i⟩", FRAC_PI_2, FRAC_PI_2),
+    ("T|+⟩", FRAC_PI_2, FRAC_PI_4),
+];
+const CLASSICAL_FIDELITY: f64 = 2.0 / 3.0;
+const SIGNIFICANCE_LEVEL: f64 = 0.01;
 
-// Teleport the quantum information from the source qubit into the destination qubit using an auxiliary qubit.
-fn teleport(
-    simulation: &mut dyn Simulation,
-    src_qubit: usize,
-    aux_qubit: usize,
-    dest_qubit: usize,
-) {
-    // Entangle qubits 1 and 2.
-    simulation.hadamard(aux_qubit);
-    simulation.cnot(aux_qubit, dest_qubit);
-
-    // Teleport the quantum information from qubit 0 into qubit 2.
-    simulation.cnot(src_qubit, aux_qubit);
-    simulation.hadamard(src_qubit);
-    let measured_states = simulation.measure(vec![src_qubit, aux_qubit]);
-    let first_bit = measured_states[0];
-    let second_bit = measured_states[1];
-    if first_bit {
-        simulation.pauli_z(dest_qubit);
-    }
-    if second_bit {
-        simulation.pauli_x(dest_qubit);
-    }
+// Teleport the state of the angles from the qubit 0 into the qubit 2, and return the fidelity
+// of the qubit 2 to the state and the outcome of the Bell measurement, the bits of the qubits 0
+// and 1.
+fn teleport_state(simulation: &mut QuantumSimulation, theta: f64, phi: f64) -> (f64, usize) {
+    simulation.reset();
+    prepare_bloch_state(simulation, theta, phi, 0);
+    let [value0, value1] = teleport(simulation, 0, 1, 2);
+    let fidelity = simulation.qubit_fidelity(2, &bloch_state(theta, phi));
+    (fidelity, 2 * value0 as usize + value1 as usize)
 }
 
-fn main() {
+fn main() -> ExitCode {
     println!("Teleportation:");
     let mut simulation = QuantumSimulation::new(QUBIT_COUNT, 0u64);
-    let mut measurements = Vec::with_capacity(RUN_COUNT);
-    for _ in 0..RUN_COUNT {
-        simulation.reset();
-
-        // Prepare qubit 0. It will be teleported.
-        simulation.hadamard(0);
+    for (name, theta, phi) in STATES {
+        let (fidelity, outcome) = teleport_state(&mut simulation, theta, phi);
+        println!(
+            "{:>5}: fidelity {:.6}, Bell measurement {:02b}",
+            name, fidelity, outcome
+        );
+    }
 
-        teleport(&mut simulation, 0, 1, 2);
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    let mut fidelity_sum = 0.0;
+    let mut min_fidelity = f64::INFINITY;
+    let mut outcome_counts = [0; 4];
+    for _ in 0..RUN_COUNT {
+        let theta = (1.0 - 2.0 * rng.gen::<f64>()).acos();
+        let phi = 2.0 * PI * rng.gen::<f64>();
+        let (fidelity, outcome) = teleport_state(&mut simulation, theta, phi);
+        fidelity_sum += fidelity;
+        min_fidelity = min_fidelity.min(fidelity);
+        outcome_counts[outcome] += 1;
+    }
+    let average_fidelity = fidelity_sum / RUN_COUNT as f64;
+    println!(
+        "{} random states: average fidelity {:.6}, minimum {:.6}, classical bound {:.4}",
+        RUN_COUNT, average_fidelity, min_fidelity, CLASSICAL_FIDELITY
+    );
+    println!(
+        "Bell measurement outcomes 00, 01, 10, 11: {:?}",
+        outcome_counts
+    );
+    let test = chi_squared_test(&outcome_counts, &[0.25; 4]);
+    let is_uniform = test.p_value > SIGNIFICANCE_LEVEL;
+    println!(
+        "Chi-squared test of the uniform outcomes: statistic {:.3}, p-value {:.4}, {}",
+        test.statistic,
+        test.p_value,
+        if is_uniform { "passed" } else { "failed" }
+    );
+    println!();
 
-        let measured_states = simulation.measure(vec![2]);
-        measurements.push(measured_states);
+    if min_fidelity > 1.0 - 1e-9 && is_uniform {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
     }
-    evaluate(measurements);
 }
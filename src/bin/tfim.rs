@@ -0,0 +1,136 @@
+/*
+Time evolution of the transverse-field Ising model by the Trotter formula.
+
+Usage:
+tfim [--qubits <count>] [--time <t>] [--steps <count>] [--field <h>] [--coupling <j>]
+
+The chain of n qubits starts in |0...0⟩, the ground state of the coupling -J Σ Z_i Z_(i+1),
+and evolves under H = -J Σ Z_i Z_(i+1) - h Σ X_i, the transverse field turning the spins away
+from the Z axis. Each step of the time t/steps is a layer of the RZZ and a layer of the RX
+rotations, and after each step the magnetizations ⟨Z_i⟩ of the qubits, their mean, and the
+energy ⟨H⟩ are printed. The energy is conserved by the exact evolution, so its drift shows the
+Trotter error.
+
+Example run:
+cargo run --release --bin tfim -- --qubits 6 --time 3 --steps 30 --field 1 --coupling 1
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::process::ExitCode;
+
+use quantum_simulation::algorithms::{tfim_hamiltonian, trotter_tfim};
+use quantum_simulation::hamiltonian::{Pauli, PauliString};
+use quantum_simulation::state_vector_simulation::QuantumSimulation;
+
+const USAGE: &str =
+    "Usage: tfim [--qubits <count>] [--time <t>] [--steps <count>] [--field <h>] [--coupling <j>]";
+const MAX_QUBIT_COUNT: usize = 20;
+
+struct Options {
+    qubit_count: usize,
+    time: f64,
+    step_count: usize,
+    field: f64,
+    coupling: f64,
+}
+
+fn parse_options(arguments: &[String]) -> Result<Options, String> {
+    let mut arguments = arguments.iter();
+    let mut options = Options {
+        qubit_count: 6,
+        time: 3.0,
+        step_count: 30,
+        field: 1.0,
+        coupling: 1.0,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| {
+            arguments
+                .next()
+                .ok_or_else(|| format!("The option {} needs a value.", name))
+        };
+        let number = |value: &String| {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("The value {} has to be a number.", value))
+        };
+        match argument.as_str() {
+            "--qubits" => {
+                options.qubit_count = value("--qubits")?
+                    .parse()
+                    .map_err(|_| "The number of qubits has to be a positive integer.")?;
+                if options.qubit_count == 0 || options.qubit_count > MAX_QUBIT_COUNT {
+                    return Err(format!(
+                        "The number of qubits has to be from 1 to {}.",
+                        MAX_QUBIT_COUNT
+                    ));
+                }
+            }
+            "--time" => options.time = number(value("--time")?)?,
+            "--steps" => {
+                options.step_count = value("--steps")?
+                    .parse()
+                    .map_err(|_| "The number of steps has to be a positive integer.")?;
+                if options.step_count == 0 {
+                    return Err("The number of steps has to be a positive integer.".to_string());
+                }
+            }
+            "--field" => options.field = number(value("--field")?)?,
+            "--coupling" => options.coupling = number(value("--coupling")?)?,
+            option => return Err(format!("Unknown option {}.", option)),
+        }
+    }
+    Ok(options)
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_options(&arguments) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let n = options.qubit_count;
+    let (h, j) = (options.field, options.coupling);
+    println!(
+        "Transverse-field Ising model of {} qubits, h = {}, J = {}:",
+        n, h, j
+    );
+    let dt = options.time / options.step_count as f64;
+    let step = trotter_tfim(n, 1, dt, h, j);
+    let hamiltonian = tfim_hamiltonian(n, h, j);
+    let magnetizations: Vec<PauliString> = (0..n)
+        .map(|qubit_number| PauliString::new(&[(Pauli::Z, qubit_number)]))
+        .collect();
+    let mut simulation = QuantumSimulation::new(n, 0u64);
+    print!("{:>6}", "t");
+    for qubit_number in 0..n {
+        print!("  {:>6}", format!("⟨Z{}⟩", qubit_number));
+    }
+    println!("  {:>6}  {:>8}", "mean", "energy");
+    for step_number in 0..=options.step_count {
+        if step_number > 0 {
+            step.run(&mut simulation);
+        }
+        let amplitudes = simulation.amplitudes();
+        let values: Vec<f64> = magnetizations
+            .iter()
+            .map(|z| z.expectation(&amplitudes))
+            .collect();
+        print!("{:>6.3}", step_number as f64 * dt);
+        for value in values.iter() {
+            print!("  {:>6.3}", value);
+        }
+        println!(
+            "  {:>6.3}  {:>8.4}",
+            values.iter().sum::<f64>() / n as f64,
+            hamiltonian.expectation(&amplitudes)
+        );
+    }
+    println!();
+    ExitCode::SUCCESS
+}
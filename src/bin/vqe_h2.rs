@@ -0,0 +1,90 @@
+/*
+Variational quantum eigensolver of the ground state energy of the hydrogen molecule H2.
+
+The electronic Hamiltonian of H2 in the minimal STO-3G basis at the bond length of 0.735 Å,
+mapped to qubits by the parity mapping with the two qubits fixed by the symmetries removed,
+acts on 2 qubits:
+H = g0 + g1 Z0 - g1 Z1 + g2 Z0 Z1 + g3 X0 X1
+Its real ground state is reached by the ansatz of RY rotations around a CNOT gate. The
+exact electronic ground state energy is -1.857275 Ha, and with the nuclear repulsion energy
+of 0.719969 Ha the total energy is -1.137306 Ha.
+
+Example run:
+cargo run --release --bin vqe_h2
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use quantum_simulation::algorithms::{vqe, Optimizer};
+use quantum_simulation::circuit::Gate;
+use quantum_simulation::hamiltonian::{Hamiltonian, Pauli};
+use quantum_simulation::parameterized_circuit::ParameterizedCircuit;
+
+const COEFFICIENTS: [f64; 4] = [
+    -1.052373245772859,
+    0.39793742484318045,
+    -0.01128010425623538,
+    0.18093119978423156,
+];
+const NUCLEAR_REPULSION_ENERGY: f64 = 0.7199689944489797;
+const EXACT_ENERGY: f64 = -1.857275030202378;
+
+fn h2_hamiltonian() -> Hamiltonian {
+    let [g0, g1, g2, g3] = COEFFICIENTS;
+    let mut hamiltonian = Hamiltonian::new();
+    hamiltonian.add_term(g0, &[]);
+    hamiltonian.add_term(g1, &[(Pauli::Z, 0)]);
+    hamiltonian.add_term(-g1, &[(Pauli::Z, 1)]);
+    hamiltonian.add_term(g2, &[(Pauli::Z, 0), (Pauli::Z, 1)]);
+    hamiltonian.add_term(g3, &[(Pauli::X, 0), (Pauli::X, 1)]);
+    hamiltonian
+}
+
+fn ansatz() -> ParameterizedCircuit {
+    let mut circuit = ParameterizedCircuit::new(2);
+    circuit.ry(0, 0);
+    circuit.ry(1, 1);
+    circuit.fixed(Gate::Cnot(0, 1));
+    circuit.ry(2, 0);
+    circuit.ry(3, 1);
+    circuit
+}
+
+fn main() {
+    println!("VQE of H2:");
+    let hamiltonian = h2_hamiltonian();
+    let ansatz = ansatz();
+    let initial_parameters = [0.1; 4];
+    let optimizers = [
+        (
+            "Nelder–Mead",
+            Optimizer::NelderMead {
+                max_iterations: 500,
+                tolerance: 1e-10,
+            },
+        ),
+        (
+            "SPSA",
+            Optimizer::Spsa {
+                max_iterations: 500,
+                rnd_seed: 0,
+            },
+        ),
+    ];
+    for (name, optimizer) in optimizers {
+        let result = vqe(&ansatz, &hamiltonian, optimizer, &initial_parameters);
+        println!("{}:", name);
+        println!("  Iterations: {}", result.energies.len());
+        println!("  Energy evaluations: {}", result.evaluation_count);
+        println!("  Electronic energy: {:.6} Ha", result.energy);
+        println!(
+            "  Total energy: {:.6} Ha",
+            result.energy + NUCLEAR_REPULSION_ENERGY
+        );
+        println!("  Error: {:.2e} Ha", result.energy - EXACT_ENERGY);
+    }
+    println!(
+        "Exact total energy: {:.6} Ha",
+        EXACT_ENERGY + NUCLEAR_REPULSION_ENERGY
+    );
+}
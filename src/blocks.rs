@@ -0,0 +1,555 @@
+/*
+Library of reusable composite circuit blocks.
+
+Each block is a circuit on its own qubits numbered from 0 with the layout described
+at the builder. Blocks are placed onto the qubits of a larger circuit with Circuit::append.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::PI;
+
+use crate::circuit::{Circuit, Gate};
+
+// Quantum Fourier transform |x⟩ -> Σ_y e^{2πi·x·y/2^n}|y⟩ / √(2^n), where the qubit j holds the bit j of x and y.
+pub fn qft(qubit_count: usize) -> Circuit {
+    approximate_qft(qubit_count, qubit_count)
+}
+
+// Inverse of the quantum Fourier transform, the gates of qft in reverse order with the opposite phases.
+pub fn iqft(qubit_count: usize) -> Circuit {
+    approximate_iqft(qubit_count, qubit_count)
+}
+
+// Quantum Fourier transform without the controlled phases π/2^m of m above the cutoff, which
+// saves most of the gates of a large transform at an error of about n·π/2^cutoff.
+pub fn approximate_qft(qubit_count: usize, cutoff: usize) -> Circuit {
+    let mut circuit = Circuit::new(qubit_count);
+    for j in (0..qubit_count).rev() {
+        circuit.hadamard(j);
+        for k in (j.saturating_sub(cutoff)..j).rev() {
+            circuit.cphase(PI / (1u64 << (j - k)) as f64, k, j);
+        }
+    }
+    for i in 0..qubit_count / 2 {
+        circuit.swap(i, qubit_count - 1 - i);
+    }
+    circuit
+}
+
+// Inverse of the approximate quantum Fourier transform of the cutoff.
+pub fn approximate_iqft(qubit_count: usize, cutoff: usize) -> Circuit {
+    let mut circuit = Circuit::new(qubit_count);
+    for i in 0..qubit_count / 2 {
+        circuit.swap(i, qubit_count - 1 - i);
+    }
+    for j in 0..qubit_count {
+        for k in j.saturating_sub(cutoff)..j {
+            circuit.cphase(-PI / (1u64 << (j - k)) as f64, k, j);
+        }
+        circuit.hadamard(j);
+    }
+    circuit
+}
+
+// Ripple-carry adder |a⟩|b⟩|0⟩ -> |a⟩|a + b⟩|0⟩ of Vedral, Barenco, and Ekert for n-bit numbers.
+// The register a is on the qubits 0..n, the register b on the qubits n..2n+1 with the highest
+// qubit receiving the carry out, and the n carry ancilla qubits on 2n+1..3n+1.
+pub fn ripple_adder(bit_count: usize) -> Circuit {
+    assert!(bit_count > 0, "The adder needs at least 1 bit.");
+    let n = bit_count;
+    let a = |i: usize| i;
+    let b = |i: usize| n + i;
+    // The carry into the bit i, with the last carry being the highest bit of the sum.
+    let c = |i: usize| if i == n { 2 * n } else { 2 * n + 1 + i };
+
+    let mut circuit = Circuit::new(3 * n + 1);
+    let carry = |circuit: &mut Circuit, i: usize| {
+        circuit.toffoli(a(i), b(i), c(i + 1));
+        circuit.cnot(a(i), b(i));
+        circuit.toffoli(c(i), b(i), c(i + 1));
+    };
+    let inverse_carry = |circuit: &mut Circuit, i: usize| {
+        circuit.toffoli(c(i), b(i), c(i + 1));
+        circuit.cnot(a(i), b(i));
+        circuit.toffoli(a(i), b(i), c(i + 1));
+    };
+    let sum = |circuit: &mut Circuit, i: usize| {
+        circuit.cnot(a(i), b(i));
+        circuit.cnot(c(i), b(i));
+    };
+
+    for i in 0..n {
+        carry(&mut circuit, i);
+    }
+    circuit.cnot(a(n - 1), b(n - 1));
+    sum(&mut circuit, n - 1);
+    for i in (0..n - 1).rev() {
+        inverse_carry(&mut circuit, i);
+        sum(&mut circuit, i);
+    }
+    circuit
+}
+
+// Ripple-carry adder |a⟩|b⟩|0⟩|z⟩ -> |a⟩|a + b mod 2^n⟩|0⟩|z ⊕ carry⟩ of Cuccaro, Draper, Kutin,
+// and Moulton for n-bit numbers, with a single ancilla qubit. The register a is on the qubits
+// 0..n, the register b on the qubits n..2n, the ancilla on the qubit 2n, and the qubit z
+// receiving the carry out on the qubit 2n+1. The majority gates compute the carries into the
+// qubits of a in place, and the unmajority-and-add gates restore them while writing the sum.
+pub fn adder(bit_count: usize) -> Circuit {
+    assert!(bit_count > 0, "The adder needs at least 1 bit.");
+    let n = bit_count;
+    let a = |i: usize| i;
+    let b = |i: usize| n + i;
+    // The carry into the bit i, held by the qubit of the bit i - 1 of a.
+    let c = |i: usize| if i == 0 { 2 * n } else { a(i - 1) };
+    let z = 2 * n + 1;
+
+    let mut circuit = Circuit::new(2 * n + 2);
+    let majority = |circuit: &mut Circuit, i: usize| {
+        circuit.cnot(a(i), b(i));
+        circuit.cnot(a(i), c(i));
+        circuit.toffoli(c(i), b(i), a(i));
+    };
+    let unmajority_and_add = |circuit: &mut Circuit, i: usize| {
+        circuit.toffoli(c(i), b(i), a(i));
+        circuit.cnot(a(i), c(i));
+        circuit.cnot(c(i), b(i));
+    };
+
+    for i in 0..n {
+        majority(&mut circuit, i);
+    }
+    circuit.cnot(a(n - 1), z);
+    for i in (0..n).rev() {
+        unmajority_and_add(&mut circuit, i);
+    }
+    circuit
+}
+
+// Comparator |a⟩|b⟩|0⟩|z⟩ -> |a⟩|b⟩|0⟩|z ⊕ (a < b)⟩ for n-bit numbers, the carry out of
+// ~a + b = b - a - 1 + 2^n computed by the majority gates of the Cuccaro adder and uncomputed
+// after it is copied. The register a is on the qubits 0..n, the register b on the qubits
+// n..2n, the ancilla on the qubit 2n, and the result qubit z on the qubit 2n+1.
+pub fn comparator(bit_count: usize) -> Circuit {
+    assert!(bit_count > 0, "The comparator needs at least 1 bit.");
+    let n = bit_count;
+    let a = |i: usize| i;
+    let b = |i: usize| n + i;
+    let c = |i: usize| if i == 0 { 2 * n } else { a(i - 1) };
+    let z = 2 * n + 1;
+
+    let mut compute = Circuit::new(2 * n + 2);
+    for i in 0..n {
+        compute.pauli_x(a(i));
+    }
+    for i in 0..n {
+        compute.cnot(a(i), b(i));
+        compute.cnot(a(i), c(i));
+        compute.toffoli(c(i), b(i), a(i));
+    }
+    let mut circuit = compute.clone();
+    circuit.cnot(a(n - 1), z);
+    circuit.extend(&compute.inverse());
+    circuit
+}
+
+// Add the register a, with the sign, to the register b holding the Fourier transform of its
+// value, by the phases e^{±2πi a y / 2^m} of its basis states |y⟩.
+fn add_in_fourier_space(circuit: &mut Circuit, a: &[usize], b: &[usize], sign: f64) {
+    let m = b.len();
+    for (j, &b_qubit) in b.iter().enumerate() {
+        for (k, &a_qubit) in a.iter().enumerate().take(m - j) {
+            let theta = 2.0 * PI / (1u64 << (m - j - k)) as f64;
+            circuit.cphase(sign * theta, a_qubit, b_qubit);
+        }
+    }
+}
+
+// Add the constant to the register b holding the Fourier transform of its value if the control
+// qubit is |1⟩.
+fn add_constant_in_fourier_space(circuit: &mut Circuit, value: i64, b: &[usize], control: usize) {
+    let m = b.len();
+    for (j, &b_qubit) in b.iter().enumerate() {
+        let turns = (value as i128 * (1i128 << j)).rem_euclid(1i128 << m);
+        if turns != 0 {
+            circuit.cphase(
+                2.0 * PI * turns as f64 / (1u64 << m) as f64,
+                control,
+                b_qubit,
+            );
+        }
+    }
+}
+
+// Adder |a⟩|b⟩ -> |a⟩|a + b mod 2^n⟩ of Draper for n-bit numbers, adding the phases of a to the
+// Fourier transform of b, without ancilla qubits. The register a is on the qubits 0..n and the
+// register b on the qubits n..2n.
+pub fn draper_adder(bit_count: usize) -> Circuit {
+    assert!(bit_count > 0, "The adder needs at least 1 bit.");
+    let n = bit_count;
+    let a: Vec<usize> = (0..n).collect();
+    let b: Vec<usize> = (n..2 * n).collect();
+    let mut circuit = Circuit::new(2 * n);
+    circuit.append(&qft(n), &b);
+    add_in_fourier_space(&mut circuit, &a, &b, 1.0);
+    circuit.append(&iqft(n), &b);
+    circuit
+}
+
+// Modular adder |a⟩|b⟩|0⟩ -> |a⟩|a + b mod N⟩|0⟩ for a, b < N ≤ 2^n, after Beauregard, in the
+// Fourier space of b. The register a is on the qubits 0..n, the register b on the qubits n..2n+1
+// with the highest qubit catching the overflow and returned to |0⟩, and the ancilla qubit on
+// the qubit 2n+1. The ancilla records whether a + b < N, when N is added back, and is
+// uncomputed from the sign of (a + b mod N) - a. The constant N is subtracted with the ancilla
+// flipped to |1⟩ as the control, so that the phases are exact and the block can be controlled.
+pub fn modular_adder(bit_count: usize, modulus: u64) -> Circuit {
+    assert!(bit_count > 0, "The adder needs at least 1 bit.");
+    assert!(
+        modulus > 0 && modulus <= 1 << bit_count,
+        "The modulus has to be from 1 to 2^{}.",
+        bit_count
+    );
+    let n = bit_count;
+    let modulus = modulus as i64;
+    let a: Vec<usize> = (0..n).collect();
+    let b: Vec<usize> = (n..2 * n + 1).collect();
+    let msb = 2 * n;
+    let ancilla = 2 * n + 1;
+    let m = n + 1;
+    let mut circuit = Circuit::new(2 * n + 2);
+
+    circuit.append(&qft(m), &b);
+    add_in_fourier_space(&mut circuit, &a, &b, 1.0);
+    circuit.pauli_x(ancilla);
+    add_constant_in_fourier_space(&mut circuit, -modulus, &b, ancilla);
+    circuit.pauli_x(ancilla);
+    circuit.append(&iqft(m), &b);
+    circuit.cnot(msb, ancilla);
+    circuit.append(&qft(m), &b);
+    add_constant_in_fourier_space(&mut circuit, modulus, &b, ancilla);
+
+    add_in_fourier_space(&mut circuit, &a, &b, -1.0);
+    circuit.append(&iqft(m), &b);
+    circuit.pauli_x(msb);
+    circuit.cnot(msb, ancilla);
+    circuit.pauli_x(msb);
+    circuit.append(&qft(m), &b);
+    add_in_fourier_space(&mut circuit, &a, &b, 1.0);
+    circuit.append(&iqft(m), &b);
+    circuit
+}
+
+// Multi-controlled X gate flipping the target if all the controls are in the state |1⟩.
+// The controls are on the qubits 0..k, the target on the qubit k, and the k − 2 clean ancilla qubits
+// computing the conjunction of the controls with a ladder of Toffoli gates follow,
+// returned to the state |0⟩.
+pub fn mcx_with_ancilla(control_count: usize) -> Circuit {
+    let ancilla_count = control_count.saturating_sub(2);
+    let target = control_count;
+    let ancilla = |i: usize| control_count + 1 + i;
+
+    let mut circuit = Circuit::new(control_count + 1 + ancilla_count);
+    match control_count {
+        0 => circuit.pauli_x(target),
+        1 => circuit.cnot(0, target),
+        2 => circuit.toffoli(0, 1, target),
+        _ => {
+            let mut compute = Circuit::new(circuit.qubit_count());
+            compute.toffoli(0, 1, ancilla(0));
+            for i in 1..ancilla_count {
+                compute.toffoli(i + 1, ancilla(i - 1), ancilla(i));
+            }
+            circuit.extend(&compute);
+            circuit.toffoli(control_count - 1, ancilla(ancilla_count - 1), target);
+            for gate in compute.gates().iter().rev() {
+                circuit.push(*gate);
+            }
+        }
+    }
+    circuit
+}
+
+// The block applied only if the control qubit is in the state |1⟩, with the global phase of each
+// gate kept. The block is on the qubits 0..n, the control on the qubit n, and the clean ancilla
+// qubit of the controlled Toffoli gates on the qubit n + 1.
+pub fn controlled(block: &Circuit) -> Circuit {
+    let n = block.qubit_count();
+    let c = n;
+    let ancilla = n + 1;
+    let mut circuit = Circuit::with_clbits(n + 2, block.clbit_count());
+    for (gate, condition) in block.gates().iter().zip(block.conditions().iter()) {
+        let mut controlled_gate = Circuit::new(n + 2);
+        match *gate {
+            Gate::PauliX(q) => controlled_gate.cnot(c, q),
+            // Y = S X S†, with S† = Z S.
+            Gate::PauliY(q) => {
+                controlled_gate.pauli_z(q);
+                controlled_gate.s(q);
+                controlled_gate.cnot(c, q);
+                controlled_gate.s(q);
+            }
+            Gate::PauliZ(q) => controlled_gate.cz(c, q),
+            // H = Ry(π/4) Z Ry(-π/4).
+            Gate::Hadamard(q) => {
+                controlled_gate.ry(-PI / 4.0, q);
+                controlled_gate.cz(c, q);
+                controlled_gate.ry(PI / 4.0, q);
+            }
+            Gate::S(q) => controlled_gate.cphase(PI / 2.0, c, q),
+            Gate::T(q) => controlled_gate.cphase(PI / 4.0, c, q),
+            // R(θ) = R(θ/2) X R(-θ/2) X for the rotations about Y and Z.
+            Gate::Rx(theta, q) => {
+                controlled_gate.hadamard(q);
+                controlled_gate.rz(theta / 2.0, q);
+                controlled_gate.cnot(c, q);
+                controlled_gate.rz(-theta / 2.0, q);
+                controlled_gate.cnot(c, q);
+                controlled_gate.hadamard(q);
+            }
+            Gate::Ry(theta, q) => {
+                controlled_gate.ry(theta / 2.0, q);
+                controlled_gate.cnot(c, q);
+                controlled_gate.ry(-theta / 2.0, q);
+                controlled_gate.cnot(c, q);
+            }
+            Gate::Rz(theta, q) => {
+                controlled_gate.rz(theta / 2.0, q);
+                controlled_gate.cnot(c, q);
+                controlled_gate.rz(-theta / 2.0, q);
+                controlled_gate.cnot(c, q);
+            }
+            Gate::Cnot(q0, q1) => controlled_gate.toffoli(c, q0, q1),
+            Gate::Cz(q0, q1) => {
+                controlled_gate.hadamard(q1);
+                controlled_gate.toffoli(c, q0, q1);
+                controlled_gate.hadamard(q1);
+            }
+            // The phase θ/2 (b + a - (a ⊕ b)) = θ a b on the control.
+            Gate::Cphase(theta, q0, q1) => {
+                controlled_gate.cphase(theta / 2.0, q1, c);
+                controlled_gate.cnot(q0, q1);
+                controlled_gate.cphase(-theta / 2.0, q1, c);
+                controlled_gate.cnot(q0, q1);
+                controlled_gate.cphase(theta / 2.0, q0, c);
+            }
+            Gate::Swap(q0, q1) => {
+                controlled_gate.cnot(q1, q0);
+                controlled_gate.toffoli(c, q0, q1);
+                controlled_gate.cnot(q1, q0);
+            }
+            // U_f flips the qubit q0 if f(true) when the qubit q1 is |0⟩, and if not f(false)
+            // when it is |1⟩.
+            Gate::UF(f, q0, q1) => {
+                if f(true) {
+                    controlled_gate.pauli_x(q1);
+                    controlled_gate.toffoli(c, q1, q0);
+                    controlled_gate.pauli_x(q1);
+                }
+                if !f(false) {
+                    controlled_gate.toffoli(c, q1, q0);
+                }
+            }
+            Gate::Toffoli(q0, q1, q2) => {
+                controlled_gate.toffoli(c, q0, ancilla);
+                controlled_gate.toffoli(ancilla, q1, q2);
+                controlled_gate.toffoli(c, q0, ancilla);
+            }
+            Gate::Measure(_, _) => panic!("A measurement cannot be controlled."),
+        }
+        for gate in controlled_gate.gates() {
+            circuit.push_with_condition(*gate, *condition);
+        }
+    }
+    circuit
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use num_complex::Complex;
+
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    fn prepare(simulation: &mut QuantumSimulation, qubit_numbers: &[usize], value: u64) {
+        for (bit, &qubit_number) in qubit_numbers.iter().enumerate() {
+            if (value >> bit) & 1 == 1 {
+                simulation.pauli_x(qubit_number);
+            }
+        }
+    }
+
+    #[test]
+    fn qft_is_discrete_fourier_transform() {
+        let n = 3;
+        let size = 1 << n;
+        let mut inverse = qft(n);
+        inverse.extend(&iqft(n));
+        let mut simulation = QuantumSimulation::new(n, 0u64);
+        for x in 0..size {
+            simulation.reset();
+            prepare(&mut simulation, &[0, 1, 2], x as u64);
+            qft(n).run(&mut simulation);
+            for (y, amplitude) in simulation.amplitudes().iter().enumerate() {
+                let expected = Complex::from_polar(
+                    1.0 / (size as f64).sqrt(),
+                    2.0 * PI * (x * y) as f64 / size as f64,
+                );
+                assert!((amplitude - expected).norm() < 1e-12);
+            }
+
+            simulation.reset();
+            prepare(&mut simulation, &[0, 1, 2], x as u64);
+            inverse.run(&mut simulation);
+            assert!((simulation.amplitudes()[x].norm() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn arithmetic_blocks_compute_all_inputs() {
+        let n = 2;
+        let adder = ripple_adder(n);
+        for a in 0..(1u64 << n) {
+            for b in 0..(1u64 << n) {
+                let mut simulation = QuantumSimulation::new(adder.qubit_count(), 0u64);
+                prepare(&mut simulation, &[0, 1], a);
+                prepare(&mut simulation, &[2, 3], b);
+                adder.run(&mut simulation);
+                let bits = simulation.measure_all();
+                let value = |range: std::ops::Range<usize>| {
+                    range.rev().fold(0u64, |v, q| (v << 1) | bits[q] as u64)
+                };
+                assert_eq!(value(0..2), a);
+                assert_eq!(value(2..5), a + b);
+                assert_eq!(value(5..7), 0);
+            }
+        }
+
+        let mcx = mcx_with_ancilla(4);
+        for controls in 0..16u64 {
+            let mut simulation = QuantumSimulation::new(mcx.qubit_count(), 0u64);
+            prepare(&mut simulation, &[0, 1, 2, 3], controls);
+            mcx.run(&mut simulation);
+            let bits = simulation.measure_all();
+            assert_eq!(bits[4], controls == 15);
+            assert!(!bits[5] && !bits[6]);
+        }
+    }
+
+    #[test]
+    fn comparator_computes_all_inputs() {
+        let comparator = comparator(2);
+        for a in 0..4u64 {
+            for b in 0..4u64 {
+                let mut simulation = QuantumSimulation::new(comparator.qubit_count(), 0u64);
+                prepare(&mut simulation, &[0, 1], a);
+                prepare(&mut simulation, &[2, 3], b);
+                comparator.run(&mut simulation);
+                let expected = a | b << 2 | ((a < b) as u64) << 5;
+                assert!((simulation.amplitudes()[expected as usize].norm() - 1.0).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn draper_adder_computes_all_inputs() {
+        let draper = draper_adder(3);
+        for a in 0..8u64 {
+            for b in 0..8u64 {
+                let mut simulation = QuantumSimulation::new(draper.qubit_count(), 0u64);
+                prepare(&mut simulation, &[0, 1, 2], a);
+                prepare(&mut simulation, &[3, 4, 5], b);
+                draper.run(&mut simulation);
+                let expected = a | ((a + b) % 8) << 3;
+                assert!((simulation.amplitudes()[expected as usize].norm() - 1.0).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn modular_adder_computes_all_inputs() {
+        // The modular adder is exact up to the phases, so that its controlled block is too.
+        let modular = controlled(&modular_adder(3, 5));
+        for a in 0..5u64 {
+            for b in 0..5u64 {
+                let mut simulation = QuantumSimulation::new(modular.qubit_count(), 0u64);
+                prepare(&mut simulation, &[0, 1, 2], a);
+                prepare(&mut simulation, &[3, 4, 5], b);
+                simulation.hadamard(8);
+                modular.run(&mut simulation);
+                let amplitudes = simulation.amplitudes();
+                let index = a | b << 3;
+                let sum_index = a | ((a + b) % 5) << 3 | 1 << 8;
+                assert!((amplitudes[index as usize].re - FRAC_1_SQRT_2).abs() < 1e-12);
+                assert!((amplitudes[sum_index as usize].re - FRAC_1_SQRT_2).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn cuccaro_adder_computes_all_inputs() {
+        let cuccaro_adder = super::adder(3);
+        for a in 0..8u64 {
+            for b in 0..8u64 {
+                let mut simulation = QuantumSimulation::new(cuccaro_adder.qubit_count(), 0u64);
+                prepare(&mut simulation, &[0, 1, 2], a);
+                prepare(&mut simulation, &[3, 4, 5], b);
+                cuccaro_adder.run(&mut simulation);
+                let bits = simulation.measure_all();
+                let value = |range: std::ops::Range<usize>| {
+                    range.rev().fold(0u64, |v, q| (v << 1) | bits[q] as u64)
+                };
+                assert_eq!(value(0..3), a);
+                assert_eq!(value(3..6) | value(7..8) << 3, a + b);
+                assert_eq!(value(6..7), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn controlled_block_applies_only_for_control() {
+        let f: fn(bool) -> bool = |x| !x;
+        let mut block = Circuit::new(3);
+        block.hadamard(0);
+        block.pauli_y(1);
+        block.t(2);
+        block.rx(0.3, 0);
+        block.ry(0.5, 1);
+        block.rz(0.7, 2);
+        block.cnot(0, 1);
+        block.cz(1, 2);
+        block.cphase(0.9, 2, 0);
+        block.swap(0, 2);
+        block.push(Gate::UF(f, 1, 0));
+        block.toffoli(0, 1, 2);
+        block.s(1);
+        let controlled_block = controlled(&block);
+
+        for x in 0..8u64 {
+            let mut expected = QuantumSimulation::new(5, 0u64);
+            prepare(&mut expected, &[0, 1, 2], x);
+            let mut simulation = QuantumSimulation::new(5, 0u64);
+            prepare(&mut simulation, &[0, 1, 2], x);
+            controlled_block.run(&mut simulation);
+            assert!(expected
+                .amplitudes()
+                .iter()
+                .zip(simulation.amplitudes().iter())
+                .all(|(a, b)| (a - b).norm() < 1e-12));
+
+            prepare(&mut expected, &[3], 1);
+            block.run(&mut expected);
+            simulation.reset();
+            prepare(&mut simulation, &[0, 1, 2, 3], x | 8);
+            controlled_block.run(&mut simulation);
+            assert!(expected
+                .amplitudes()
+                .iter()
+                .zip(simulation.amplitudes().iter())
+                .all(|(a, b)| (a - b).norm() < 1e-12));
+        }
+    }
+}
@@ -0,0 +1,566 @@
+/*
+Quantum circuit as a sequence of gates which can be run on any simulation.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::BTreeMap;
+
+use crate::dag::CircuitDag;
+use crate::simulation::{check_qubits, GateError, Simulation};
+
+#[cfg(feature = "json")]
+mod json;
+mod qasm;
+mod qasm3;
+#[cfg(feature = "qir")]
+mod qir;
+#[cfg(feature = "json")]
+mod qiskit;
+mod quil;
+mod stim;
+
+pub use qasm::{from_qasm, ParseError};
+pub use stim::LocatedChannel;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Gate {
+    // 1-qubit gates.
+    PauliX(usize),
+    PauliY(usize),
+    PauliZ(usize),
+    Hadamard(usize),
+    S(usize),
+    T(usize),
+    Rx(f64, usize),
+    Ry(f64, usize),
+    Rz(f64, usize),
+
+    // 2-qubit gates.
+    Cnot(usize, usize),
+    Cz(usize, usize),
+    Cphase(f64, usize, usize),
+    Swap(usize, usize),
+    UF(fn(bool) -> bool, usize, usize),
+
+    // 3-qubit gates.
+    Toffoli(usize, usize, usize),
+
+    // Measurement of the qubit in the Z-basis into the classical bit.
+    Measure(usize, usize),
+}
+
+impl Gate {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Gate::PauliX(_) => "pauli_x",
+            Gate::PauliY(_) => "pauli_y",
+            Gate::PauliZ(_) => "pauli_z",
+            Gate::Hadamard(_) => "hadamard",
+            Gate::S(_) => "s",
+            Gate::T(_) => "t",
+            Gate::Rx(_, _) => "rx",
+            Gate::Ry(_, _) => "ry",
+            Gate::Rz(_, _) => "rz",
+            Gate::Cnot(_, _) => "cnot",
+            Gate::Cz(_, _) => "cz",
+            Gate::Cphase(_, _, _) => "cphase",
+            Gate::Swap(_, _) => "swap",
+            Gate::UF(_, _, _) => "u_f",
+            Gate::Toffoli(_, _, _) => "toffoli",
+            Gate::Measure(_, _) => "measure",
+        }
+    }
+
+    // The qubit numbers the gate acts on, in the order of the gate arguments.
+    pub fn qubits(&self) -> Vec<usize> {
+        match *self {
+            Gate::PauliX(q) | Gate::PauliY(q) | Gate::PauliZ(q) => vec![q],
+            Gate::Hadamard(q) | Gate::S(q) | Gate::T(q) => vec![q],
+            Gate::Rx(_, q) | Gate::Ry(_, q) | Gate::Rz(_, q) => vec![q],
+            Gate::Cnot(q0, q1) | Gate::Cz(q0, q1) | Gate::Swap(q0, q1) => vec![q0, q1],
+            Gate::Cphase(_, q0, q1) | Gate::UF(_, q0, q1) => vec![q0, q1],
+            Gate::Toffoli(q0, q1, q2) => vec![q0, q1, q2],
+            Gate::Measure(q, _) => vec![q],
+        }
+    }
+
+    // Return the same gate acting on the qubits given by the qubit number mapping.
+    pub fn map_qubits<F>(&self, map: F) -> Gate
+    where
+        F: Fn(usize) -> usize,
+    {
+        match *self {
+            Gate::PauliX(q) => Gate::PauliX(map(q)),
+            Gate::PauliY(q) => Gate::PauliY(map(q)),
+            Gate::PauliZ(q) => Gate::PauliZ(map(q)),
+            Gate::Hadamard(q) => Gate::Hadamard(map(q)),
+            Gate::S(q) => Gate::S(map(q)),
+            Gate::T(q) => Gate::T(map(q)),
+            Gate::Rx(theta, q) => Gate::Rx(theta, map(q)),
+            Gate::Ry(theta, q) => Gate::Ry(theta, map(q)),
+            Gate::Rz(theta, q) => Gate::Rz(theta, map(q)),
+            Gate::Cnot(q0, q1) => Gate::Cnot(map(q0), map(q1)),
+            Gate::Cz(q0, q1) => Gate::Cz(map(q0), map(q1)),
+            Gate::Cphase(theta, q0, q1) => Gate::Cphase(theta, map(q0), map(q1)),
+            Gate::Swap(q0, q1) => Gate::Swap(map(q0), map(q1)),
+            Gate::UF(f, q0, q1) => Gate::UF(f, map(q0), map(q1)),
+            Gate::Toffoli(q0, q1, q2) => Gate::Toffoli(map(q0), map(q1), map(q2)),
+            Gate::Measure(q, c) => Gate::Measure(map(q), c),
+        }
+    }
+
+    // The gates multiplying each amplitude by a phase, which commute with each other.
+    pub fn is_diagonal(&self) -> bool {
+        matches!(
+            self,
+            Gate::PauliZ(_)
+                | Gate::S(_)
+                | Gate::T(_)
+                | Gate::Rz(_, _)
+                | Gate::Cz(_, _)
+                | Gate::Cphase(_, _, _)
+        )
+    }
+
+    // The inverse gate, up to a global phase for S and T, whose inverses are RZ rotations.
+    pub fn inverse(&self) -> Gate {
+        match *self {
+            Gate::S(q) => Gate::Rz(-std::f64::consts::FRAC_PI_2, q),
+            Gate::T(q) => Gate::Rz(-std::f64::consts::FRAC_PI_4, q),
+            Gate::Rx(theta, q) => Gate::Rx(-theta, q),
+            Gate::Ry(theta, q) => Gate::Ry(-theta, q),
+            Gate::Rz(theta, q) => Gate::Rz(-theta, q),
+            Gate::Cphase(theta, q0, q1) => Gate::Cphase(-theta, q0, q1),
+            Gate::Measure(_, _) => panic!("A measurement has no inverse."),
+            // The other gates are their own inverses.
+            _ => *self,
+        }
+    }
+
+    // Apply the gate to the simulation. The outcome of a measurement is discarded, see Circuit::run.
+    pub fn apply(&self, simulation: &mut dyn Simulation) {
+        match *self {
+            Gate::PauliX(q) => simulation.pauli_x(q),
+            Gate::PauliY(q) => simulation.pauli_y(q),
+            Gate::PauliZ(q) => simulation.pauli_z(q),
+            Gate::Hadamard(q) => simulation.hadamard(q),
+            Gate::S(q) => simulation.s(q),
+            Gate::T(q) => simulation.t(q),
+            Gate::Rx(theta, q) => simulation.rx(theta, q),
+            Gate::Ry(theta, q) => simulation.ry(theta, q),
+            Gate::Rz(theta, q) => simulation.rz(theta, q),
+            Gate::Cnot(q0, q1) => simulation.cnot(q0, q1),
+            Gate::Cz(q0, q1) => simulation.cz(q0, q1),
+            Gate::Cphase(theta, q0, q1) => simulation.cphase(theta, q0, q1),
+            Gate::Swap(q0, q1) => simulation.swap(q0, q1),
+            Gate::UF(f, q0, q1) => simulation.apply_u_f(f, q0, q1),
+            Gate::Toffoli(q0, q1, q2) => simulation.toffoli(q0, q1, q2),
+            Gate::Measure(q, _) => {
+                simulation.measure(vec![q]);
+            }
+        }
+    }
+}
+
+// The X and CNOT gates with the same action as the U_f gate of the simulation, which flips
+// the first qubit if f(true) when the second qubit is |0⟩ and if not f(false) when it is |1⟩.
+pub(crate) fn u_f_gates(
+    f: fn(bool) -> bool,
+    qubit_number0: usize,
+    qubit_number1: usize,
+) -> Vec<Gate> {
+    let flip_on_zero = f(true);
+    let flip_on_one = !f(false);
+    let mut gates = Vec::new();
+    if flip_on_zero {
+        gates.push(Gate::PauliX(qubit_number0));
+    }
+    if flip_on_zero != flip_on_one {
+        gates.push(Gate::Cnot(qubit_number1, qubit_number0));
+    }
+    gates
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CircuitStats {
+    // The number of the layers of the gates when each gate is applied as early as possible.
+    pub depth: usize,
+    pub gate_count: usize,
+    // The number of the gates per gate name.
+    pub gate_counts: BTreeMap<&'static str, usize>,
+    pub two_qubit_gate_count: usize,
+}
+
+// Classical condition satisfied when the classical bits first_clbit..first_clbit + clbit_count,
+// read as a binary number with the first bit as the lowest bit, are equal to the value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Condition {
+    pub first_clbit: usize,
+    pub clbit_count: usize,
+    pub value: u64,
+}
+
+impl Condition {
+    // Condition on the value of a single classical bit.
+    pub fn bit(clbit_number: usize, value: bool) -> Condition {
+        Condition {
+            first_clbit: clbit_number,
+            clbit_count: 1,
+            value: value as u64,
+        }
+    }
+
+    pub fn clbits(&self) -> std::ops::Range<usize> {
+        self.first_clbit..self.first_clbit + self.clbit_count
+    }
+
+    pub fn is_satisfied(&self, clbits: &[bool]) -> bool {
+        self.clbits()
+            .enumerate()
+            .all(|(bit, c)| clbits[c] == ((self.value >> bit) & 1 == 1))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Circuit {
+    qubit_count: usize,
+    clbit_count: usize,
+    gates: Vec<Gate>,
+    // The classical condition of each gate.
+    conditions: Vec<Option<Condition>>,
+}
+
+impl Circuit {
+    pub fn new(qubit_count: usize) -> Circuit {
+        Circuit::with_clbits(qubit_count, 0)
+    }
+
+    // Circuit with the classical bits receiving the outcomes of the measurements.
+    pub fn with_clbits(qubit_count: usize, clbit_count: usize) -> Circuit {
+        Circuit {
+            qubit_count,
+            clbit_count,
+            gates: Vec::new(),
+            conditions: Vec::new(),
+        }
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    pub fn clbit_count(&self) -> usize {
+        self.clbit_count
+    }
+
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    // The classical conditions of the gates, in the order of the gates.
+    pub fn conditions(&self) -> &[Option<Condition>] {
+        &self.conditions
+    }
+
+    pub fn push(&mut self, gate: Gate) {
+        self.push_with_condition(gate, None);
+    }
+
+    // Append the gate applied only if the classical condition is satisfied.
+    pub fn push_conditional(&mut self, gate: Gate, condition: Condition) {
+        self.push_with_condition(gate, Some(condition));
+    }
+
+    // Append the gate if its qubits are in the circuit and distinct, or return the error.
+    pub fn try_push(&mut self, gate: Gate) -> Result<(), GateError> {
+        check_qubits(&gate.qubits(), self.qubit_count)?;
+        self.push(gate);
+        Ok(())
+    }
+
+    pub fn push_with_condition(&mut self, gate: Gate, condition: Option<Condition>) {
+        if let Err(error) = check_qubits(&gate.qubits(), self.qubit_count) {
+            panic!("{}", error);
+        }
+        if let Gate::Measure(_, clbit_number) = gate {
+            assert!(
+                clbit_number < self.clbit_count,
+                "The classical bit number has to be less than the number of classical bits {}.",
+                self.clbit_count
+            );
+        }
+        if let Some(condition) = condition {
+            assert!(
+                condition.clbit_count <= 64
+                    && condition.first_clbit + condition.clbit_count <= self.clbit_count,
+                "The classical bit number has to be less than the number of classical bits {}.",
+                self.clbit_count
+            );
+        }
+        self.gates.push(gate);
+        self.conditions.push(condition);
+    }
+
+    // Append all the gates of the other circuit.
+    pub fn extend(&mut self, other: &Circuit) {
+        for (gate, condition) in other.gates.iter().zip(other.conditions.iter()) {
+            self.push_with_condition(*gate, *condition);
+        }
+    }
+
+    // Append all the gates of the block circuit, placing its qubit i on the qubit qubit_numbers[i].
+    pub fn append(&mut self, block: &Circuit, qubit_numbers: &[usize]) {
+        assert!(
+            qubit_numbers.len() == block.qubit_count,
+            "The number of the qubit numbers has to be equal to the number of qubits in the block {}.",
+            block.qubit_count
+        );
+        for (gate, condition) in block.gates.iter().zip(block.conditions.iter()) {
+            self.push_with_condition(gate.map_qubits(|q| qubit_numbers[q]), *condition);
+        }
+    }
+
+    // The inverse circuit, the inverses of the gates in reverse order. A circuit with
+    // measurements has no inverse.
+    pub fn inverse(&self) -> Circuit {
+        let mut circuit = Circuit::with_clbits(self.qubit_count, self.clbit_count);
+        for (gate, condition) in self.gates.iter().zip(self.conditions.iter()).rev() {
+            circuit.push_with_condition(gate.inverse(), *condition);
+        }
+        circuit
+    }
+
+    // Apply all the gates in order and return the classical bits. The simulation is not reset beforehand.
+    // The consecutive unconditional diagonal gates are applied together by apply_phases.
+    pub fn run(&self, simulation: &mut dyn Simulation) -> Vec<bool> {
+        self.run_gates(simulation, true)
+    }
+
+    // Apply the gates other than the measurements, for the circuits with terminal measurements.
+    pub(crate) fn run_without_measurements(&self, simulation: &mut dyn Simulation) {
+        self.run_gates(simulation, false);
+    }
+
+    fn run_gates(&self, simulation: &mut dyn Simulation, is_measured: bool) -> Vec<bool> {
+        let mut clbits = vec![false; self.clbit_count];
+        let mut i = 0;
+        while i < self.gates.len() {
+            let diagonal_gate_count = self.gates[i..]
+                .iter()
+                .zip(self.conditions[i..].iter())
+                .take_while(|(gate, condition)| gate.is_diagonal() && condition.is_none())
+                .count();
+            if diagonal_gate_count > 1 {
+                simulation.apply_phases(&self.gates[i..i + diagonal_gate_count]);
+                i += diagonal_gate_count;
+                continue;
+            }
+            let gate = self.gates[i];
+            let condition = self.conditions[i];
+            i += 1;
+            if let Some(condition) = condition {
+                if !condition.is_satisfied(&clbits) {
+                    continue;
+                }
+            }
+            match gate {
+                Gate::Measure(q, c) => {
+                    if is_measured {
+                        clbits[c] = simulation.measure(vec![q])[0];
+                    }
+                }
+                _ => gate.apply(simulation),
+            }
+        }
+        clbits
+    }
+
+    // The measurements as the pairs of the qubit and the classical bit, if they all come after
+    // the gates on their qubits and no gate is conditional, so that the measured distribution is
+    // the distribution of the final state without the measurements. None otherwise.
+    pub fn terminal_measurements(&self) -> Option<Vec<(usize, usize)>> {
+        if self.conditions.iter().any(|condition| condition.is_some()) {
+            return None;
+        }
+        let mut measurements = Vec::new();
+        let mut is_measured = vec![false; self.qubit_count];
+        for gate in self.gates.iter() {
+            match *gate {
+                Gate::Measure(q, c) => {
+                    is_measured[q] = true;
+                    measurements.push((q, c));
+                }
+                _ => {
+                    if gate.qubits().iter().any(|&q| is_measured[q]) {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(measurements)
+    }
+
+    // Dependency DAG of the gates taking the commutation of the gates into account.
+    pub fn to_dag(&self) -> CircuitDag {
+        CircuitDag::new(self)
+    }
+
+    pub fn stats(&self) -> CircuitStats {
+        let mut qubit_depths = vec![0usize; self.qubit_count];
+        let mut gate_counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+        let mut two_qubit_gate_count = 0;
+        for gate in self.gates.iter() {
+            let qubits = gate.qubits();
+            let layer = qubits.iter().map(|&q| qubit_depths[q]).max().unwrap() + 1;
+            for &qubit_number in qubits.iter() {
+                qubit_depths[qubit_number] = layer;
+            }
+            *gate_counts.entry(gate.name()).or_insert(0) += 1;
+            if qubits.len() == 2 {
+                two_qubit_gate_count += 1;
+            }
+        }
+
+        CircuitStats {
+            depth: qubit_depths.into_iter().max().unwrap_or(0),
+            gate_count: self.gates.len(),
+            gate_counts,
+            two_qubit_gate_count,
+        }
+    }
+
+    // 1-qubit gates.
+    pub fn pauli_x(&mut self, qubit_number: usize) {
+        self.push(Gate::PauliX(qubit_number));
+    }
+
+    pub fn pauli_y(&mut self, qubit_number: usize) {
+        self.push(Gate::PauliY(qubit_number));
+    }
+
+    pub fn pauli_z(&mut self, qubit_number: usize) {
+        self.push(Gate::PauliZ(qubit_number));
+    }
+
+    pub fn hadamard(&mut self, qubit_number: usize) {
+        self.push(Gate::Hadamard(qubit_number));
+    }
+
+    pub fn s(&mut self, qubit_number: usize) {
+        self.push(Gate::S(qubit_number));
+    }
+
+    pub fn t(&mut self, qubit_number: usize) {
+        self.push(Gate::T(qubit_number));
+    }
+
+    pub fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.push(Gate::Rx(theta, qubit_number));
+    }
+
+    pub fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.push(Gate::Ry(theta, qubit_number));
+    }
+
+    pub fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.push(Gate::Rz(theta, qubit_number));
+    }
+
+    // 2-qubit gates.
+    pub fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.push(Gate::Cnot(control_qubit_number, target_qubit_number));
+    }
+
+    pub fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.push(Gate::Cz(control_qubit_number, target_qubit_number));
+    }
+
+    pub fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        self.push(Gate::Cphase(
+            theta,
+            control_qubit_number,
+            target_qubit_number,
+        ));
+    }
+
+    pub fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.push(Gate::Swap(qubit_number0, qubit_number1));
+    }
+
+    pub fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.push(Gate::UF(f, qubit_number0, qubit_number1));
+    }
+
+    pub fn measure(&mut self, qubit_number: usize, clbit_number: usize) {
+        self.push(Gate::Measure(qubit_number, clbit_number));
+    }
+
+    // 3-qubit gates.
+    pub fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        self.push(Gate::Toffoli(
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_count_layers_and_gates() {
+        let mut circuit = Circuit::new(3);
+        circuit.hadamard(0);
+        circuit.hadamard(1);
+        circuit.cnot(0, 1);
+        circuit.cnot(1, 2);
+        circuit.t(0);
+        circuit.toffoli(0, 1, 2);
+
+        let stats = circuit.stats();
+        assert_eq!(stats.depth, 4);
+        assert_eq!(stats.gate_count, 6);
+        assert_eq!(stats.gate_counts["hadamard"], 2);
+        assert_eq!(stats.gate_counts["cnot"], 2);
+        assert_eq!(stats.gate_counts["toffoli"], 1);
+        assert_eq!(stats.two_qubit_gate_count, 2);
+        assert_eq!(Circuit::new(2).stats().depth, 0);
+    }
+
+    #[test]
+    fn try_push_rejects_repeated_qubits() {
+        let mut circuit = Circuit::new(3);
+        assert_eq!(
+            circuit.try_push(Gate::Toffoli(0, 2, 0)),
+            Err(GateError::RepeatedQubit { qubit_number: 0 })
+        );
+        assert_eq!(
+            circuit.try_push(Gate::Cnot(1, 3)),
+            Err(GateError::QubitOutOfRange {
+                qubit_number: 3,
+                qubit_count: 3
+            })
+        );
+        assert_eq!(circuit.try_push(Gate::Cz(2, 0)), Ok(()));
+        assert_eq!(circuit.gates().len(), 1);
+    }
+
+    #[test]
+    fn parsers_reject_repeated_qubits() {
+        let message = GateError::RepeatedQubit { qubit_number: 1 }.to_string();
+        let error = from_qasm("qreg q[2];\ngate g a, b { cz b, b; }\ng q[0], q[1];").unwrap_err();
+        assert_eq!(error.message, message);
+        let error = Circuit::from_quil("CNOT 1 1").unwrap_err();
+        assert_eq!(error.message, message);
+        let error = Circuit::from_stim("CX 1 1").unwrap_err();
+        assert_eq!(error.message, message);
+    }
+}
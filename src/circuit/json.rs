@@ -0,0 +1,247 @@
+/*
+JSON format of circuits.
+
+A circuit is an object with the qubit count, the classical bit count, and the list of the gates.
+Each gate is an object with the gate name, the qubits, the parameters of the rotation gates,
+the classical bits of the measurements, and an optional classical condition, like
+
+{"qubit_count": 2, "clbit_count": 1, "gates": [
+    {"name": "hadamard", "qubits": [0]},
+    {"name": "rz", "qubits": [1], "parameters": [0.5]},
+    {"name": "measure", "qubits": [0], "clbits": [0]},
+    {"name": "pauli_x", "qubits": [1],
+     "condition": {"first_clbit": 0, "clbit_count": 1, "value": 1}}]}
+
+The gate names are the names of Gate::name. The U_f gate is exported as X and CNOT gates.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use serde_json::{json, Map, Value};
+
+use crate::circuit::{u_f_gates, Circuit, Condition, Gate, ParseError};
+use crate::simulation::check_qubits;
+
+// The gates with their numbers of parameters and qubits.
+const GATES: [(&str, usize, usize); 15] = [
+    ("pauli_x", 0, 1),
+    ("pauli_y", 0, 1),
+    ("pauli_z", 0, 1),
+    ("hadamard", 0, 1),
+    ("s", 0, 1),
+    ("t", 0, 1),
+    ("rx", 1, 1),
+    ("ry", 1, 1),
+    ("rz", 1, 1),
+    ("cnot", 0, 2),
+    ("cz", 0, 2),
+    ("cphase", 1, 2),
+    ("swap", 0, 2),
+    ("toffoli", 0, 3),
+    ("measure", 0, 1),
+];
+
+// The errors of the contents have no line, reported as the line 0.
+fn error<T>(message: String) -> Result<T, ParseError> {
+    Err(ParseError { line: 0, message })
+}
+
+fn unsigned(value: &Value, what: &str) -> Result<u64, ParseError> {
+    match value.as_u64() {
+        Some(number) => Ok(number),
+        None => error(format!("The {} has to be a nonnegative integer.", what)),
+    }
+}
+
+fn unsigned_list(gate: &Map<String, Value>, key: &str) -> Result<Vec<usize>, ParseError> {
+    match gate.get(key) {
+        None => Ok(Vec::new()),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| unsigned(value, key).map(|number| number as usize))
+            .collect(),
+        Some(_) => error(format!("The {} have to be a list.", key)),
+    }
+}
+
+fn condition(value: &Value, clbit_count: usize) -> Result<Condition, ParseError> {
+    let field = |key: &str| match value.get(key) {
+        Some(field) => unsigned(field, key),
+        None => error(format!("The condition has no {}.", key)),
+    };
+    let condition = Condition {
+        first_clbit: field("first_clbit")? as usize,
+        clbit_count: field("clbit_count")? as usize,
+        value: field("value")?,
+    };
+    if condition.clbit_count > 64 || condition.first_clbit + condition.clbit_count > clbit_count {
+        return error(format!(
+            "The classical bits of the condition have to be less than the number of classical bits {}.",
+            clbit_count
+        ));
+    }
+    Ok(condition)
+}
+
+fn gate(value: &Value, qubit_count: usize, clbit_count: usize) -> Result<Gate, ParseError> {
+    let object = match value.as_object() {
+        Some(object) => object,
+        None => return error("A gate has to be an object.".to_string()),
+    };
+    let name = match object.get("name").and_then(|name| name.as_str()) {
+        Some(name) => name,
+        None => return error("A gate has no name.".to_string()),
+    };
+    let &(_, parameter_count, gate_qubit_count) = match GATES.iter().find(|&&(n, _, _)| n == name) {
+        Some(gate) => gate,
+        None => return error(format!("Unknown gate {}.", name)),
+    };
+    let q = unsigned_list(object, "qubits")?;
+    let c = unsigned_list(object, "clbits")?;
+    let p: Vec<f64> = match object.get("parameters") {
+        None => Vec::new(),
+        Some(Value::Array(values)) => match values.iter().map(|value| value.as_f64()).collect() {
+            Some(parameters) => parameters,
+            None => return error("The parameters have to be numbers.".to_string()),
+        },
+        Some(_) => return error("The parameters have to be a list.".to_string()),
+    };
+    let clbit_argument_count = if name == "measure" { 1 } else { 0 };
+    if p.len() != parameter_count || q.len() != gate_qubit_count || c.len() != clbit_argument_count
+    {
+        return error(format!(
+            "The gate {} takes {} parameters, {} qubits, and {} classical bits.",
+            name, parameter_count, gate_qubit_count, clbit_argument_count
+        ));
+    }
+    check_qubits(&q, qubit_count).or_else(|e| error(e.to_string()))?;
+    if c.iter().any(|&clbit_number| clbit_number >= clbit_count) {
+        return error(format!(
+            "The classical bit number has to be less than the number of classical bits {}.",
+            clbit_count
+        ));
+    }
+
+    Ok(match name {
+        "pauli_x" => Gate::PauliX(q[0]),
+        "pauli_y" => Gate::PauliY(q[0]),
+        "pauli_z" => Gate::PauliZ(q[0]),
+        "hadamard" => Gate::Hadamard(q[0]),
+        "s" => Gate::S(q[0]),
+        "t" => Gate::T(q[0]),
+        "rx" => Gate::Rx(p[0], q[0]),
+        "ry" => Gate::Ry(p[0], q[0]),
+        "rz" => Gate::Rz(p[0], q[0]),
+        "cnot" => Gate::Cnot(q[0], q[1]),
+        "cz" => Gate::Cz(q[0], q[1]),
+        "cphase" => Gate::Cphase(p[0], q[0], q[1]),
+        "swap" => Gate::Swap(q[0], q[1]),
+        "toffoli" => Gate::Toffoli(q[0], q[1], q[2]),
+        _ => Gate::Measure(q[0], c[0]),
+    })
+}
+
+fn gate_value(gate: &Gate) -> Value {
+    let mut value = json!({"name": gate.name(), "qubits": gate.qubits()});
+    match *gate {
+        Gate::Rx(theta, _)
+        | Gate::Ry(theta, _)
+        | Gate::Rz(theta, _)
+        | Gate::Cphase(theta, _, _) => {
+            value["parameters"] = json!([theta]);
+        }
+        Gate::Measure(_, c) => value["clbits"] = json!([c]),
+        _ => {}
+    }
+    value
+}
+
+impl Circuit {
+    // Parse a circuit from its JSON format, reporting the errors of the JSON syntax with their lines.
+    pub fn from_json(source: &str) -> Result<Circuit, ParseError> {
+        let value: Value = serde_json::from_str(source).map_err(|e| ParseError {
+            line: e.line(),
+            message: e.to_string(),
+        })?;
+        Circuit::from_json_value(&value)
+    }
+
+    // Convert the parsed JSON format of a circuit, like a part of a larger JSON document.
+    pub fn from_json_value(value: &Value) -> Result<Circuit, ParseError> {
+        let count = |key: &str| match value.get(key) {
+            Some(count) => unsigned(count, key).map(|count| count as usize),
+            None if key == "clbit_count" => Ok(0),
+            None => error(format!("The circuit has no {}.", key)),
+        };
+        let qubit_count = count("qubit_count")?;
+        let clbit_count = count("clbit_count")?;
+        let gates = match value.get("gates") {
+            Some(Value::Array(gates)) => gates,
+            Some(_) => return error("The gates have to be a list.".to_string()),
+            None => return error("The circuit has no gates.".to_string()),
+        };
+
+        let mut circuit = Circuit::with_clbits(qubit_count, clbit_count);
+        for value in gates.iter() {
+            let gate = gate(value, qubit_count, clbit_count)?;
+            let condition = match value.get("condition") {
+                Some(condition_value) => Some(condition(condition_value, clbit_count)?),
+                None => None,
+            };
+            circuit.push_with_condition(gate, condition);
+        }
+        Ok(circuit)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut gates = Vec::new();
+        for (gate, condition) in self.gates.iter().zip(self.conditions.iter()) {
+            let expanded = match *gate {
+                Gate::UF(f, q0, q1) => u_f_gates(f, q0, q1),
+                _ => vec![*gate],
+            };
+            for gate in expanded.iter() {
+                let mut value = gate_value(gate);
+                if let Some(condition) = condition {
+                    value["condition"] = json!({
+                        "first_clbit": condition.first_clbit,
+                        "clbit_count": condition.clbit_count,
+                        "value": condition.value,
+                    });
+                }
+                gates.push(value);
+            }
+        }
+        json!({
+            "qubit_count": self.qubit_count,
+            "clbit_count": self.clbit_count,
+            "gates": gates,
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trips() {
+        let mut circuit = Circuit::with_clbits(3, 2);
+        circuit.hadamard(0);
+        circuit.cphase(0.5, 0, 2);
+        circuit.measure(0, 1);
+        circuit.push_conditional(Gate::Toffoli(0, 1, 2), Condition::bit(1, true));
+        let text = circuit.to_json();
+        let parsed = Circuit::from_json(&text).unwrap();
+        assert_eq!(parsed.to_json(), text);
+        assert_eq!(parsed.conditions()[3], Some(Condition::bit(1, true)));
+
+        let error = Circuit::from_json("{\"qubit_count\": 1,\n\"gates\": [}").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(Circuit::from_json(
+            r#"{"qubit_count": 1, "gates": [{"name": "cnot", "qubits": [0, 0]}]}"#
+        )
+        .is_err());
+    }
+}
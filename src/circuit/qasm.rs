@@ -0,0 +1,886 @@
+/*
+OpenQASM 2.0 parser.
+
+The quantum and classical registers are laid out in the order of their declarations.
+The gates of qelib1.inc are always available; the gates without an equivalent gate
+of the circuit are expanded from their qelib1.inc definitions, like user defined gates,
+and the phase gates are applied as Z-rotations, equal up to a global phase.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::circuit::{Circuit, Gate};
+use crate::simulation::check_distinct_qubits;
+
+// The gates of qelib1.inc which are expanded into the gates of the circuit.
+const QELIB1_DEFINITIONS: &str = "
+gate u2(phi,lambda) q { U(pi/2,phi,lambda) q; }
+gate u0(gamma) q { U(0,0,0) q; }
+gate sdg a { u1(-pi/2) a; }
+gate tdg a { u1(-pi/4) a; }
+gate sx a { rx(pi/2) a; }
+gate sxdg a { rx(-pi/2) a; }
+gate cy a,b { sdg b; cx a,b; s b; }
+gate ch a,b { h b; sdg b; cx a,b; h b; t b; cx a,b; t b; h b; s b; x b; s a; }
+gate crz(lambda) a,b { u1(lambda/2) b; cx a,b; u1(-lambda/2) b; cx a,b; }
+gate crx(lambda) a,b { u1(pi/2) b; cx a,b; u3(-lambda/2,0,0) b; cx a,b; u3(lambda/2,-pi/2,0) b; }
+gate cry(lambda) a,b { ry(lambda/2) b; cx a,b; ry(-lambda/2) b; cx a,b; }
+gate cu3(theta,phi,lambda) c,t {
+    u1((lambda+phi)/2) c; u1((lambda-phi)/2) t; cx c,t;
+    u3(-theta/2,0,-(phi+lambda)/2) t; cx c,t; u3(theta/2,phi,0) t;
+}
+gate cu(theta,phi,lambda,gamma) c,t {
+    p(gamma) c; p((lambda+phi)/2) c; p((lambda-phi)/2) t; cx c,t;
+    u(-theta/2,0,-(phi+lambda)/2) t; cx c,t; u(theta/2,phi,0) t;
+}
+gate cswap a,b,c { cx c,b; ccx a,b,c; cx c,b; }
+gate rxx(theta) a,b { h a; h b; cx a,b; rz(theta) b; cx a,b; h a; h b; }
+gate rzz(theta) a,b { cx a,b; rz(theta) b; cx a,b; }
+";
+
+// The gates applied directly as gates of the circuit, with their numbers of parameters and qubits.
+const NATIVE_GATES: [(&str, usize, usize); 24] = [
+    ("U", 3, 1),
+    ("u3", 3, 1),
+    ("u", 3, 1),
+    ("u1", 1, 1),
+    ("p", 1, 1),
+    ("id", 0, 1),
+    ("x", 0, 1),
+    ("y", 0, 1),
+    ("z", 0, 1),
+    ("h", 0, 1),
+    ("s", 0, 1),
+    ("t", 0, 1),
+    ("rx", 1, 1),
+    ("ry", 1, 1),
+    ("rz", 1, 1),
+    ("CX", 0, 2),
+    ("cx", 0, 2),
+    ("cz", 0, 2),
+    ("swap", 0, 2),
+    ("cu1", 1, 2),
+    ("cp", 1, 2),
+    ("ccx", 0, 3),
+    ("barrier", 0, 0),
+    ("measure", 0, 0),
+];
+
+// The maximum nesting of the gate definitions, guarding against recursive definitions.
+const MAX_DEFINITION_DEPTH: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    // The line of the source text where the error was found, starting from 1, or 0 if the error has no line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Identifier(String),
+    Number(f64),
+    Text(String),
+    Symbol(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    const SYMBOLS: [&str; 14] = [
+        "->", "==", ";", ",", "(", ")", "[", "]", "{", "}", "+", "-", "*", "/",
+    ];
+    let mut tokens = Vec::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split("//").next().unwrap();
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let identifier: String = chars[start..i].iter().collect();
+                tokens.push((Token::Identifier(identifier), line_number));
+            } else if c.is_ascii_digit() || c == '.' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    i += 1;
+                    if i < chars.len() && (chars[i] == '+' || chars[i] == '-') {
+                        i += 1;
+                    }
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text.parse::<f64>().map_err(|_| ParseError {
+                    line: line_number,
+                    message: format!("Invalid number {}.", text),
+                })?;
+                tokens.push((Token::Number(number), line_number));
+            } else if c == '"' {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(ParseError {
+                        line: line_number,
+                        message: "Unterminated string.".to_string(),
+                    });
+                }
+                tokens.push((Token::Text(chars[start..i].iter().collect()), line_number));
+                i += 1;
+            } else if c == '^' {
+                tokens.push((Token::Symbol("^"), line_number));
+                i += 1;
+            } else {
+                let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+                    Some(symbol) => {
+                        tokens.push((Token::Symbol(symbol), line_number));
+                        i += symbol.len();
+                    }
+                    None => {
+                        return Err(ParseError {
+                            line: line_number,
+                            message: format!("Unexpected character '{}'.", c),
+                        })
+                    }
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug)]
+enum Expression {
+    Number(f64),
+    Variable(String),
+    Negation(Box<Expression>),
+    Binary(&'static str, Box<Expression>, Box<Expression>),
+    Function(String, Box<Expression>),
+}
+
+impl Expression {
+    fn evaluate(&self, variables: &HashMap<String, f64>) -> Option<f64> {
+        Some(match self {
+            Expression::Number(value) => *value,
+            Expression::Variable(name) if name == "pi" => PI,
+            Expression::Variable(name) => *variables.get(name)?,
+            Expression::Negation(e) => -e.evaluate(variables)?,
+            Expression::Binary(operator, a, b) => {
+                let (a, b) = (a.evaluate(variables)?, b.evaluate(variables)?);
+                match *operator {
+                    "+" => a + b,
+                    "-" => a - b,
+                    "*" => a * b,
+                    "/" => a / b,
+                    _ => a.powf(b),
+                }
+            }
+            Expression::Function(name, e) => {
+                let x = e.evaluate(variables)?;
+                match name.as_str() {
+                    "sin" => x.sin(),
+                    "cos" => x.cos(),
+                    "tan" => x.tan(),
+                    "exp" => x.exp(),
+                    "ln" => x.ln(),
+                    _ => x.sqrt(),
+                }
+            }
+        })
+    }
+}
+
+// An argument of a gate: a whole register or a single element of a register.
+#[derive(Clone, Debug)]
+struct Argument {
+    name: String,
+    index: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct GateCall {
+    name: String,
+    parameters: Vec<Expression>,
+    arguments: Vec<String>,
+    line: usize,
+}
+
+#[derive(Clone, Debug)]
+struct GateDefinition {
+    parameters: Vec<String>,
+    arguments: Vec<String>,
+    body: Vec<GateCall>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    definitions: HashMap<String, GateDefinition>,
+    // Register name to its first bit and its size.
+    quantum_registers: HashMap<String, (usize, usize)>,
+    classical_registers: HashMap<String, (usize, usize)>,
+    qubit_count: usize,
+    clbit_count: usize,
+    gates: Vec<Gate>,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Parser, ParseError> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            definitions: HashMap::new(),
+            quantum_registers: HashMap::new(),
+            classical_registers: HashMap::new(),
+            qubit_count: 0,
+            clbit_count: 0,
+            gates: Vec::new(),
+        })
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.position) {
+            Some(&(_, line)) => line,
+            None => self.tokens.last().map_or(1, |&(_, line)| line),
+        }
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, ParseError> {
+        Err(ParseError {
+            line: self.line(),
+            message,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        match self.tokens.get(self.position) {
+            Some((token, _)) => {
+                self.position += 1;
+                Ok(token.clone())
+            }
+            None => self.error("Unexpected end of the program.".to_string()),
+        }
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ParseError> {
+        if self.is_symbol(symbol) {
+            self.position += 1;
+            Ok(())
+        } else {
+            self.error(format!("Expected '{}'.", symbol))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, ParseError> {
+        match self.next()? {
+            Token::Identifier(name) => Ok(name),
+            token => {
+                self.position -= 1;
+                self.error(format!("Expected an identifier instead of {:?}.", token))
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<usize, ParseError> {
+        match self.next()? {
+            Token::Number(value) if value >= 0.0 && value.fract() == 0.0 => Ok(value as usize),
+            _ => {
+                self.position -= 1;
+                self.error("Expected a nonnegative integer.".to_string())
+            }
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Expression, ParseError> {
+        let mut left = self.term()?;
+        while self.is_symbol("+") || self.is_symbol("-") {
+            let operator = if self.is_symbol("+") { "+" } else { "-" };
+            self.position += 1;
+            left = Expression::Binary(operator, Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    // term := factor (('*' | '/') factor)*
+    fn term(&mut self) -> Result<Expression, ParseError> {
+        let mut left = self.factor()?;
+        while self.is_symbol("*") || self.is_symbol("/") {
+            let operator = if self.is_symbol("*") { "*" } else { "/" };
+            self.position += 1;
+            left = Expression::Binary(operator, Box::new(left), Box::new(self.factor()?));
+        }
+        Ok(left)
+    }
+
+    // factor := '-' factor | primary ('^' factor)?
+    fn factor(&mut self) -> Result<Expression, ParseError> {
+        if self.is_symbol("-") {
+            self.position += 1;
+            return Ok(Expression::Negation(Box::new(self.factor()?)));
+        }
+        let base = self.primary()?;
+        if self.is_symbol("^") {
+            self.position += 1;
+            return Ok(Expression::Binary(
+                "^",
+                Box::new(base),
+                Box::new(self.factor()?),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expression, ParseError> {
+        match self.next()? {
+            Token::Number(value) => Ok(Expression::Number(value)),
+            Token::Symbol("(") => {
+                let e = self.expression()?;
+                self.expect_symbol(")")?;
+                Ok(e)
+            }
+            Token::Identifier(name) => {
+                if ["sin", "cos", "tan", "exp", "ln", "sqrt"].contains(&name.as_str()) {
+                    self.expect_symbol("(")?;
+                    let e = self.expression()?;
+                    self.expect_symbol(")")?;
+                    Ok(Expression::Function(name, Box::new(e)))
+                } else {
+                    Ok(Expression::Variable(name))
+                }
+            }
+            _ => {
+                self.position -= 1;
+                self.error("Expected an expression.".to_string())
+            }
+        }
+    }
+
+    // Comma separated list until the closing symbol, which is consumed.
+    fn list<T, F>(&mut self, close: &str, mut item: F) -> Result<Vec<T>, ParseError>
+    where
+        F: FnMut(&mut Parser) -> Result<T, ParseError>,
+    {
+        let mut items = Vec::new();
+        if self.is_symbol(close) {
+            self.position += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.is_symbol(",") {
+                self.position += 1;
+            } else {
+                self.expect_symbol(close)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn argument(&mut self) -> Result<Argument, ParseError> {
+        let name = self.identifier()?;
+        let index = if self.is_symbol("[") {
+            self.position += 1;
+            let index = self.integer()?;
+            self.expect_symbol("]")?;
+            Some(index)
+        } else {
+            None
+        };
+        Ok(Argument { name, index })
+    }
+
+    fn arguments_until_semicolon(&mut self) -> Result<Vec<Argument>, ParseError> {
+        self.list(";", |parser| parser.argument())
+    }
+
+    fn program(&mut self) -> Result<(), ParseError> {
+        if matches!(self.peek(), Some(Token::Identifier(name)) if name == "OPENQASM") {
+            self.position += 1;
+            match self.next()? {
+                Token::Number(version) if (2.0..3.0).contains(&version) => {}
+                _ => {
+                    self.position -= 1;
+                    return self.error("Only OpenQASM 2 is supported.".to_string());
+                }
+            }
+            self.expect_symbol(";")?;
+        }
+        while self.position < self.tokens.len() {
+            self.statement()?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), ParseError> {
+        let line = self.line();
+        let keyword = self.identifier()?;
+        match keyword.as_str() {
+            "include" => match self.next()? {
+                Token::Text(file) if file == "qelib1.inc" => self.expect_symbol(";"),
+                _ => self.error("Only qelib1.inc can be included.".to_string()),
+            },
+            "qreg" | "creg" => {
+                let name = self.identifier()?;
+                self.expect_symbol("[")?;
+                let size = self.integer()?;
+                self.expect_symbol("]")?;
+                self.expect_symbol(";")?;
+                if self.quantum_registers.contains_key(&name)
+                    || self.classical_registers.contains_key(&name)
+                {
+                    return self.error(format!("The register {} is already declared.", name));
+                }
+                if keyword == "qreg" {
+                    self.quantum_registers
+                        .insert(name, (self.qubit_count, size));
+                    self.qubit_count += size;
+                } else {
+                    self.classical_registers
+                        .insert(name, (self.clbit_count, size));
+                    self.clbit_count += size;
+                }
+                Ok(())
+            }
+            "gate" => self.gate_definition(),
+            "measure" => {
+                let qubit = self.argument()?;
+                self.expect_symbol("->")?;
+                let clbit = self.argument()?;
+                self.expect_symbol(";")?;
+                let qubits = self.resolve(&[qubit], line)?;
+                let clbits = self.resolve_classical(&clbit, qubits.len(), line)?;
+                for (qubit_number, clbit_number) in qubits.into_iter().zip(clbits) {
+                    self.gates
+                        .push(Gate::Measure(qubit_number[0], clbit_number));
+                }
+                Ok(())
+            }
+            "barrier" => {
+                let arguments = self.arguments_until_semicolon()?;
+                for argument in arguments.iter() {
+                    self.register(argument, line)?;
+                }
+                Ok(())
+            }
+            "opaque" | "reset" | "if" => {
+                self.error(format!("The statement {} is not supported.", keyword))
+            }
+            _ => {
+                let parameters = if self.is_symbol("(") {
+                    self.position += 1;
+                    self.list(")", |parser| parser.expression())?
+                } else {
+                    Vec::new()
+                };
+                let arguments = self.arguments_until_semicolon()?;
+                let mut values = Vec::new();
+                for parameter in parameters.iter() {
+                    match parameter.evaluate(&HashMap::new()) {
+                        Some(value) => values.push(value),
+                        None => {
+                            return self.error("Unknown parameter in the expression.".to_string())
+                        }
+                    }
+                }
+                for qubits in self.resolve(&arguments, line)? {
+                    self.apply(&keyword, &values, &qubits, line, 0)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn gate_definition(&mut self) -> Result<(), ParseError> {
+        let name = self.identifier()?;
+        let parameters = if self.is_symbol("(") {
+            self.position += 1;
+            self.list(")", |parser| parser.identifier())?
+        } else {
+            Vec::new()
+        };
+        let mut arguments = vec![self.identifier()?];
+        while self.is_symbol(",") {
+            self.position += 1;
+            arguments.push(self.identifier()?);
+        }
+        self.expect_symbol("{")?;
+
+        let mut body = Vec::new();
+        while !self.is_symbol("}") {
+            let line = self.line();
+            let call_name = self.identifier()?;
+            let call_parameters = if self.is_symbol("(") {
+                self.position += 1;
+                self.list(")", |parser| parser.expression())?
+            } else {
+                Vec::new()
+            };
+            let call_arguments = self.list(";", |parser| parser.identifier())?;
+            for argument in call_arguments.iter() {
+                if !arguments.contains(argument) {
+                    return self.error(format!("Unknown qubit argument {}.", argument));
+                }
+            }
+            if call_name != "barrier" {
+                body.push(GateCall {
+                    name: call_name,
+                    parameters: call_parameters,
+                    arguments: call_arguments,
+                    line,
+                });
+            }
+        }
+        self.expect_symbol("}")?;
+
+        self.definitions.insert(
+            name,
+            GateDefinition {
+                parameters,
+                arguments,
+                body,
+            },
+        );
+        Ok(())
+    }
+
+    fn register(&self, argument: &Argument, line: usize) -> Result<(usize, usize), ParseError> {
+        let &(start, size) =
+            self.quantum_registers
+                .get(&argument.name)
+                .ok_or_else(|| ParseError {
+                    line,
+                    message: format!("Unknown quantum register {}.", argument.name),
+                })?;
+        match argument.index {
+            Some(index) if index >= size => Err(ParseError {
+                line,
+                message: format!(
+                    "The index {} is out of the register {}.",
+                    index, argument.name
+                ),
+            }),
+            Some(index) => Ok((start + index, 1)),
+            None => Ok((start, size)),
+        }
+    }
+
+    // The qubit numbers of each application of a gate, broadcasting it over the whole registers.
+    fn resolve(&self, arguments: &[Argument], line: usize) -> Result<Vec<Vec<usize>>, ParseError> {
+        let mut ranges = Vec::new();
+        let mut repeat_count = 1;
+        for argument in arguments.iter() {
+            let (start, size) = self.register(argument, line)?;
+            if argument.index.is_none() {
+                if repeat_count != 1 && repeat_count != size {
+                    return Err(ParseError {
+                        line,
+                        message: "The registers of the arguments have different sizes.".to_string(),
+                    });
+                }
+                repeat_count = size;
+            }
+            ranges.push((start, argument.index.is_none()));
+        }
+
+        let mut applications = Vec::new();
+        for i in 0..repeat_count {
+            let qubits: Vec<usize> = ranges
+                .iter()
+                .map(|&(start, is_register)| if is_register { start + i } else { start })
+                .collect();
+            check_distinct_qubits(&qubits).map_err(|e| ParseError {
+                line,
+                message: e.to_string(),
+            })?;
+            applications.push(qubits);
+        }
+        Ok(applications)
+    }
+
+    fn resolve_classical(
+        &self,
+        argument: &Argument,
+        count: usize,
+        line: usize,
+    ) -> Result<Vec<usize>, ParseError> {
+        let &(start, size) = self
+            .classical_registers
+            .get(&argument.name)
+            .ok_or_else(|| ParseError {
+                line,
+                message: format!("Unknown classical register {}.", argument.name),
+            })?;
+        let clbits: Vec<usize> = match argument.index {
+            Some(index) if index < size => vec![start + index],
+            Some(index) => {
+                return Err(ParseError {
+                    line,
+                    message: format!(
+                        "The index {} is out of the register {}.",
+                        index, argument.name
+                    ),
+                })
+            }
+            None => (start..start + size).collect(),
+        };
+        if clbits.len() != count {
+            return Err(ParseError {
+                line,
+                message: "The quantum and classical registers have different sizes.".to_string(),
+            });
+        }
+        Ok(clbits)
+    }
+
+    fn apply(
+        &mut self,
+        name: &str,
+        parameters: &[f64],
+        qubits: &[usize],
+        line: usize,
+        depth: usize,
+    ) -> Result<(), ParseError> {
+        let error = |message: String| Err(ParseError { line, message });
+        if let Some(&(_, parameter_count, qubit_count)) =
+            NATIVE_GATES.iter().find(|&&(n, _, _)| n == name)
+        {
+            if qubit_count == 0 {
+                return error(format!("The gate {} is reserved.", name));
+            }
+            if parameters.len() != parameter_count || qubits.len() != qubit_count {
+                return error(format!(
+                    "The gate {} takes {} parameters and {} qubits.",
+                    name, parameter_count, qubit_count
+                ));
+            }
+            let p = parameters;
+            let q = qubits;
+            match name {
+                "U" | "u3" | "u" => {
+                    // U(θ, φ, λ) = Rz(φ)·Ry(θ)·Rz(λ) up to a global phase.
+                    if p[2] != 0.0 {
+                        self.gates.push(Gate::Rz(p[2], q[0]));
+                    }
+                    if p[0] != 0.0 {
+                        self.gates.push(Gate::Ry(p[0], q[0]));
+                    }
+                    if p[1] != 0.0 {
+                        self.gates.push(Gate::Rz(p[1], q[0]));
+                    }
+                }
+                "u1" | "p" => self.gates.push(Gate::Rz(p[0], q[0])),
+                "id" => {}
+                "x" => self.gates.push(Gate::PauliX(q[0])),
+                "y" => self.gates.push(Gate::PauliY(q[0])),
+                "z" => self.gates.push(Gate::PauliZ(q[0])),
+                "h" => self.gates.push(Gate::Hadamard(q[0])),
+                "s" => self.gates.push(Gate::S(q[0])),
+                "t" => self.gates.push(Gate::T(q[0])),
+                "rx" => self.gates.push(Gate::Rx(p[0], q[0])),
+                "ry" => self.gates.push(Gate::Ry(p[0], q[0])),
+                "rz" => self.gates.push(Gate::Rz(p[0], q[0])),
+                "CX" | "cx" => self.gates.push(Gate::Cnot(q[0], q[1])),
+                "cz" => self.gates.push(Gate::Cz(q[0], q[1])),
+                "swap" => self.gates.push(Gate::Swap(q[0], q[1])),
+                "cu1" | "cp" => self.gates.push(Gate::Cphase(p[0], q[0], q[1])),
+                _ => self.gates.push(Gate::Toffoli(q[0], q[1], q[2])),
+            }
+            return Ok(());
+        }
+
+        let definition = match self.definitions.get(name) {
+            Some(definition) => definition.clone(),
+            None => return error(format!("Unknown gate {}.", name)),
+        };
+        if parameters.len() != definition.parameters.len()
+            || qubits.len() != definition.arguments.len()
+        {
+            return error(format!(
+                "The gate {} takes {} parameters and {} qubits.",
+                name,
+                definition.parameters.len(),
+                definition.arguments.len()
+            ));
+        }
+        if depth >= MAX_DEFINITION_DEPTH {
+            return error(format!("The definition of the gate {} is too deep.", name));
+        }
+
+        let variables: HashMap<String, f64> = definition
+            .parameters
+            .iter()
+            .cloned()
+            .zip(parameters.iter().copied())
+            .collect();
+        for call in definition.body.iter() {
+            let mut values = Vec::new();
+            for parameter in call.parameters.iter() {
+                match parameter.evaluate(&variables) {
+                    Some(value) => values.push(value),
+                    None => {
+                        return Err(ParseError {
+                            line: call.line,
+                            message: "Unknown parameter in the expression.".to_string(),
+                        })
+                    }
+                }
+            }
+            let call_qubits: Vec<usize> = call
+                .arguments
+                .iter()
+                .map(|argument| {
+                    qubits[definition
+                        .arguments
+                        .iter()
+                        .position(|a| a == argument)
+                        .unwrap()]
+                })
+                .collect();
+            check_distinct_qubits(&call_qubits).map_err(|e| ParseError {
+                line: call.line,
+                message: e.to_string(),
+            })?;
+            self.apply(&call.name, &values, &call_qubits, call.line, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+// The gates of qelib1.inc, expanding them into the gates of the circuit for the other formats.
+pub(super) struct GateLibrary {
+    parser: Parser,
+}
+
+impl GateLibrary {
+    pub(super) fn new() -> GateLibrary {
+        let mut parser = Parser::new(QELIB1_DEFINITIONS).unwrap();
+        parser.program().unwrap();
+        GateLibrary { parser }
+    }
+
+    // The gates of the circuit equal to the gate of qelib1.inc on the qubits, up to a global phase.
+    #[cfg(feature = "json")]
+    pub(super) fn expand(
+        &mut self,
+        name: &str,
+        parameters: &[f64],
+        qubits: &[usize],
+    ) -> Result<Vec<Gate>, String> {
+        self.parser.gates.clear();
+        self.parser
+            .apply(name, parameters, qubits, 0, 0)
+            .map_err(|error| error.message)?;
+        Ok(std::mem::take(&mut self.parser.gates))
+    }
+}
+
+// Parse an OpenQASM 2.0 program into a circuit with the qubits and classical bits
+// of all the registers in the order of their declarations.
+pub fn from_qasm(source: &str) -> Result<Circuit, ParseError> {
+    let mut parser = Parser::new(source)?;
+    parser.definitions = GateLibrary::new().parser.definitions;
+    parser.program()?;
+
+    let mut circuit = Circuit::with_clbits(parser.qubit_count, parser.clbit_count);
+    for gate in parser.gates.into_iter() {
+        circuit.push(gate);
+    }
+    Ok(circuit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn qasm_program_is_parsed_and_run() {
+        let source = r#"
+            OPENQASM 2.0;
+            include "qelib1.inc";
+            // Custom gate with a parameter expression.
+            gate flip(theta) a, b { rx(2*theta - pi) a; cx a, b; }
+            qreg q[3];
+            qreg r[2];
+            creg c[3];
+            creg d[2];
+            x q;
+            flip(pi/2) r[0], r[1];
+            ccx q[0], q[1], r[1];
+            cswap q[2], r[0], r[1];
+            barrier q, r;
+            measure q -> c;
+            measure r[0] -> d[1];
+            measure r[1] -> d[0];
+        "#;
+        let circuit = from_qasm(source).unwrap();
+        assert_eq!(circuit.qubit_count(), 5);
+        assert_eq!(circuit.clbit_count(), 5);
+        assert_eq!(circuit.stats().gate_counts["measure"], 5);
+
+        let mut simulation = QuantumSimulation::new(5, 0u64);
+        let clbits = circuit.run(&mut simulation);
+        assert_eq!(clbits, vec![true, true, true, false, true]);
+    }
+
+    #[test]
+    fn qasm_errors_report_lines() {
+        let error = from_qasm("qreg q[2];\ncx q[0], q[2];").unwrap_err();
+        assert_eq!(error.line, 2);
+        let error = from_qasm("qreg q[2];\n\nfoo q[0];").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError {
+                line: 3,
+                message: "Unknown gate foo.".to_string()
+            }
+        );
+        assert!(from_qasm("qreg q[2];\ncx q[1], q[1];").is_err());
+        assert!(from_qasm("qreg q[2]\nh q;").is_err());
+        let error =
+            from_qasm("qreg q[2];\ngate bad a, b {\n  cx a, a;\n}\nbad q[0], q[1];").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError {
+                line: 3,
+                message:
+                    "The qubit arguments of a gate have to be distinct, the qubit 0 is repeated."
+                        .to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,130 @@
+/*
+OpenQASM 3 exporter.
+
+The qubits are exported as the register q and the classical bits as the register c,
+using the gates of stdgates.inc. The U_f gate flips its first qubit depending on
+its second qubit, so it is exported as X and CNOT gates.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::circuit::{u_f_gates, Circuit, Condition, Gate};
+
+fn condition_expression(condition: &Condition) -> String {
+    let terms: Vec<String> = condition
+        .clbits()
+        .enumerate()
+        .map(|(bit, c)| {
+            if (condition.value >> bit) & 1 == 1 {
+                format!("c[{}]", c)
+            } else {
+                format!("!c[{}]", c)
+            }
+        })
+        .collect();
+    terms.join(" && ")
+}
+
+fn statements(gate: &Gate) -> Vec<String> {
+    match *gate {
+        Gate::PauliX(q) => vec![format!("x q[{}];", q)],
+        Gate::PauliY(q) => vec![format!("y q[{}];", q)],
+        Gate::PauliZ(q) => vec![format!("z q[{}];", q)],
+        Gate::Hadamard(q) => vec![format!("h q[{}];", q)],
+        Gate::S(q) => vec![format!("s q[{}];", q)],
+        Gate::T(q) => vec![format!("t q[{}];", q)],
+        Gate::Rx(theta, q) => vec![format!("rx({}) q[{}];", theta, q)],
+        Gate::Ry(theta, q) => vec![format!("ry({}) q[{}];", theta, q)],
+        Gate::Rz(theta, q) => vec![format!("rz({}) q[{}];", theta, q)],
+        Gate::Cnot(q0, q1) => vec![format!("cx q[{}], q[{}];", q0, q1)],
+        Gate::Cz(q0, q1) => vec![format!("cz q[{}], q[{}];", q0, q1)],
+        Gate::Cphase(theta, q0, q1) => vec![format!("cp({}) q[{}], q[{}];", theta, q0, q1)],
+        Gate::Swap(q0, q1) => vec![format!("swap q[{}], q[{}];", q0, q1)],
+        Gate::UF(f, q0, q1) => u_f_gates(f, q0, q1).iter().flat_map(statements).collect(),
+        Gate::Toffoli(q0, q1, q2) => vec![format!("ccx q[{}], q[{}], q[{}];", q0, q1, q2)],
+        Gate::Measure(q, c) => vec![format!("c[{}] = measure q[{}];", c, q)],
+    }
+}
+
+impl Circuit {
+    // OpenQASM 3 program text of the circuit.
+    pub fn to_qasm3(&self) -> String {
+        let mut text = String::from("OPENQASM 3.0;\ninclude \"stdgates.inc\";\n");
+        text += &format!("qubit[{}] q;\n", self.qubit_count);
+        if self.clbit_count > 0 {
+            text += &format!("bit[{}] c;\n", self.clbit_count);
+        }
+        for (gate, condition) in self.gates.iter().zip(self.conditions.iter()) {
+            for statement in statements(gate) {
+                match condition {
+                    Some(condition) => {
+                        text += &format!("if ({}) {}\n", condition_expression(condition), statement)
+                    }
+                    None => text += &format!("{}\n", statement),
+                }
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn u_f_export_matches_simulation() {
+        let functions: [fn(bool) -> bool; 4] = [|_| false, |_| true, |x| x, |x| !x];
+        for f in functions {
+            for input in 0..4 {
+                let mut u_f = QuantumSimulation::new(2, 0u64);
+                let mut exported = QuantumSimulation::new(2, 0u64);
+                for qubit_number in 0..2 {
+                    if (input >> qubit_number) & 1 == 1 {
+                        u_f.pauli_x(qubit_number);
+                        exported.pauli_x(qubit_number);
+                    }
+                }
+                u_f.apply_u_f(f, 0, 1);
+                for gate in u_f_gates(f, 0, 1) {
+                    gate.apply(&mut exported);
+                }
+                assert_eq!(u_f.amplitudes(), exported.amplitudes());
+            }
+        }
+    }
+
+    #[test]
+    fn qasm3_export_includes_parameters_and_conditions() {
+        let mut circuit = Circuit::with_clbits(2, 2);
+        circuit.hadamard(0);
+        circuit.rz(0.25, 1);
+        circuit.cphase(-1.5, 0, 1);
+        circuit.measure(0, 0);
+        circuit.push_conditional(Gate::PauliX(1), Condition::bit(0, true));
+        circuit.push_conditional(
+            Gate::PauliZ(1),
+            Condition {
+                first_clbit: 0,
+                clbit_count: 2,
+                value: 1,
+            },
+        );
+
+        assert_eq!(
+            circuit.to_qasm3(),
+            "OPENQASM 3.0;\n\
+             include \"stdgates.inc\";\n\
+             qubit[2] q;\n\
+             bit[2] c;\n\
+             h q[0];\n\
+             rz(0.25) q[1];\n\
+             cp(-1.5) q[0], q[1];\n\
+             c[0] = measure q[0];\n\
+             if (c[0]) x q[1];\n\
+             if (c[0] && !c[1]) z q[1];\n"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+/*
+Lowering of circuits into QIR base profile LLVM IR text.
+
+The qubits and the results are static, with the result of a measurement given by its classical
+bit, and all the classical bits are recorded as the output array at the end of the entry point.
+The base profile has no classical control flow and no gates after a measurement of a qubit.
+The controlled phase gates are emitted as Z-rotations and CNOT gates, equal up to a global phase.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::BTreeSet;
+
+use crate::circuit::{u_f_gates, Circuit, Gate};
+
+fn qubit(qubit_number: usize) -> String {
+    if qubit_number == 0 {
+        "%Qubit* null".to_string()
+    } else {
+        format!("%Qubit* inttoptr (i64 {} to %Qubit*)", qubit_number)
+    }
+}
+
+fn result(clbit_number: usize) -> String {
+    if clbit_number == 0 {
+        "%Result* null".to_string()
+    } else {
+        format!("%Result* inttoptr (i64 {} to %Result*)", clbit_number)
+    }
+}
+
+// The hexadecimal form of a double constant, which is exact for every angle.
+fn angle(theta: f64) -> String {
+    format!("double 0x{:016X}", theta.to_bits())
+}
+
+// The name and the arguments of the QIS calls of the gate.
+fn calls(gate: &Gate) -> Vec<(&'static str, Vec<String>)> {
+    match *gate {
+        Gate::PauliX(q) => vec![("x", vec![qubit(q)])],
+        Gate::PauliY(q) => vec![("y", vec![qubit(q)])],
+        Gate::PauliZ(q) => vec![("z", vec![qubit(q)])],
+        Gate::Hadamard(q) => vec![("h", vec![qubit(q)])],
+        Gate::S(q) => vec![("s", vec![qubit(q)])],
+        Gate::T(q) => vec![("t", vec![qubit(q)])],
+        Gate::Rx(theta, q) => vec![("rx", vec![angle(theta), qubit(q)])],
+        Gate::Ry(theta, q) => vec![("ry", vec![angle(theta), qubit(q)])],
+        Gate::Rz(theta, q) => vec![("rz", vec![angle(theta), qubit(q)])],
+        Gate::Cnot(q0, q1) => vec![("cnot", vec![qubit(q0), qubit(q1)])],
+        Gate::Cz(q0, q1) => vec![("cz", vec![qubit(q0), qubit(q1)])],
+        Gate::Cphase(theta, q0, q1) => vec![
+            ("rz", vec![angle(theta / 2.0), qubit(q0)]),
+            ("rz", vec![angle(theta / 2.0), qubit(q1)]),
+            ("cnot", vec![qubit(q0), qubit(q1)]),
+            ("rz", vec![angle(-theta / 2.0), qubit(q1)]),
+            ("cnot", vec![qubit(q0), qubit(q1)]),
+        ],
+        Gate::Swap(q0, q1) => vec![("swap", vec![qubit(q0), qubit(q1)])],
+        Gate::UF(f, q0, q1) => u_f_gates(f, q0, q1).iter().flat_map(calls).collect(),
+        Gate::Toffoli(q0, q1, q2) => vec![("ccx", vec![qubit(q0), qubit(q1), qubit(q2)])],
+        Gate::Measure(q, c) => vec![("mz", vec![qubit(q), result(c)])],
+    }
+}
+
+fn declaration(name: &str) -> String {
+    let parameters = match name {
+        "rx" | "ry" | "rz" => "double, %Qubit*",
+        "cnot" | "cz" | "swap" => "%Qubit*, %Qubit*",
+        "ccx" => "%Qubit*, %Qubit*, %Qubit*",
+        "mz" => "%Qubit*, %Result*",
+        _ => "%Qubit*",
+    };
+    let attributes = if name == "mz" { " #1" } else { "" };
+    format!(
+        "declare void @__quantum__qis__{}__body({}){}\n",
+        name, parameters, attributes
+    )
+}
+
+impl Circuit {
+    // QIR base profile module with the entry point main running the circuit.
+    pub fn to_qir(&self) -> String {
+        let mut body = String::new();
+        let mut names = BTreeSet::new();
+        let mut is_measured = vec![false; self.qubit_count];
+        for (gate, condition) in self.gates.iter().zip(self.conditions.iter()) {
+            assert!(
+                condition.is_none(),
+                "The QIR base profile does not support classical conditions."
+            );
+            for qubit_number in gate.qubits() {
+                assert!(
+                    !is_measured[qubit_number],
+                    "The QIR base profile does not support gates after a measurement of the qubit {}.",
+                    qubit_number
+                );
+            }
+            for (name, arguments) in calls(gate) {
+                body += &format!(
+                    "  call void @__quantum__qis__{}__body({})\n",
+                    name,
+                    arguments.join(", ")
+                );
+                names.insert(name);
+            }
+            if let Gate::Measure(q, _) = *gate {
+                is_measured[q] = true;
+            }
+        }
+        body += &format!(
+            "  call void @__quantum__rt__array_record_output(i64 {}, i8* null)\n",
+            self.clbit_count
+        );
+        for clbit_number in 0..self.clbit_count {
+            body += &format!(
+                "  call void @__quantum__rt__result_record_output({}, i8* null)\n",
+                result(clbit_number)
+            );
+        }
+
+        let mut text = String::from("%Qubit = type opaque\n%Result = type opaque\n\n");
+        text += "define void @main() #0 {\nentry:\n";
+        text += &body;
+        text += "  ret void\n}\n\n";
+        for name in names.iter() {
+            text += &declaration(name);
+        }
+        text += "declare void @__quantum__rt__array_record_output(i64, i8*)\n";
+        text += "declare void @__quantum__rt__result_record_output(%Result*, i8*)\n\n";
+        text += &format!(
+            "attributes #0 = {{ \"entry_point\" \"output_labeling_schema\" \"qir_profiles\"=\"base_profile\" \
+             \"required_num_qubits\"=\"{}\" \"required_num_results\"=\"{}\" }}\n",
+            self.qubit_count, self.clbit_count
+        );
+        text += "attributes #1 = { \"irreversible\" }\n\n";
+        text += "!llvm.module.flags = !{!0, !1, !2, !3}\n\n";
+        text += "!0 = !{i32 1, !\"qir_major_version\", i32 1}\n";
+        text += "!1 = !{i32 7, !\"qir_minor_version\", i32 0}\n";
+        text += "!2 = !{i32 1, !\"dynamic_qubit_management\", i1 false}\n";
+        text += "!3 = !{i32 1, !\"dynamic_result_management\", i1 false}\n";
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qir_module_has_calls_and_declarations() {
+        let mut circuit = Circuit::with_clbits(2, 2);
+        circuit.hadamard(0);
+        circuit.rz(0.25, 1);
+        circuit.cnot(0, 1);
+        circuit.measure(0, 0);
+        circuit.measure(1, 1);
+        let text = circuit.to_qir();
+        assert!(text.contains(
+            "  call void @__quantum__qis__rz__body(double 0x3FD0000000000000, \
+             %Qubit* inttoptr (i64 1 to %Qubit*))\n"
+        ));
+        assert!(
+            text.contains("  call void @__quantum__qis__mz__body(%Qubit* null, %Result* null)\n")
+        );
+        assert!(text.contains("declare void @__quantum__qis__cnot__body(%Qubit*, %Qubit*)\n"));
+        assert!(text.contains("declare void @__quantum__qis__mz__body(%Qubit*, %Result*) #1\n"));
+        assert!(text.contains("\"required_num_qubits\"=\"2\" \"required_num_results\"=\"2\""));
+        assert_eq!(text.matches("__quantum__qis__h__body").count(), 2);
+    }
+}
@@ -0,0 +1,291 @@
+/*
+Import of the circuits exported by Qiskit.
+
+Qiskit assembles the circuits into a qobj, whose JSON format has a list of experiments,
+each with the qubit and memory slot counts in its header and a list of instructions like
+
+{"name": "cx", "qubits": [0, 1]}
+{"name": "rz", "qubits": [1], "params": [0.5]}
+{"name": "measure", "qubits": [0], "memory": [0]}
+
+The memory slots are the classical bits of the circuit. The gates are the gates of qelib1.inc,
+which covers the standard gates of Qiskit, and the barriers are ignored. The conditions are
+either the classical functions, bfunc instructions setting a register which the conditional
+instructions refer to, or the older conditional objects with the mask and the value, where the
+mask has to select consecutive memory slots.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::circuit::qasm::GateLibrary;
+use crate::circuit::{Circuit, Condition, Gate, ParseError};
+use crate::simulation;
+
+// The errors of the contents have no line, reported as the line 0.
+fn error<T>(message: String) -> Result<T, ParseError> {
+    Err(ParseError { line: 0, message })
+}
+
+fn unsigned_list(instruction: &Map<String, Value>, key: &str) -> Result<Vec<usize>, ParseError> {
+    match instruction.get(key) {
+        None => Ok(Vec::new()),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| match value.as_u64() {
+                Some(number) => Ok(number as usize),
+                None => error(format!("The {} have to be nonnegative integers.", key)),
+            })
+            .collect(),
+        Some(_) => error(format!("The {} have to be a list.", key)),
+    }
+}
+
+// A hexadecimal string like "0x3", or an integer.
+fn hexadecimal(value: Option<&Value>, what: &str) -> Result<u64, ParseError> {
+    let number = match value {
+        Some(Value::String(text)) => {
+            let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+            u64::from_str_radix(digits, 16).ok()
+        }
+        Some(value) => value.as_u64(),
+        None => None,
+    };
+    match number {
+        Some(number) => Ok(number),
+        None => error(format!("The {} has to be a hexadecimal number.", what)),
+    }
+}
+
+// The condition that the memory slots of the mask are equal to the value.
+fn masked_condition(mask: u64, value: u64, clbit_count: usize) -> Result<Condition, ParseError> {
+    let first_clbit = mask.trailing_zeros() as usize;
+    let clbit_count_of_mask = mask.count_ones() as usize;
+    if mask == 0 || (mask >> first_clbit).count_ones() != (mask >> first_clbit).trailing_ones() {
+        return error(
+            "The mask of a condition has to select consecutive memory slots.".to_string(),
+        );
+    }
+    if first_clbit + clbit_count_of_mask > clbit_count {
+        return error(format!(
+            "The classical bits of the condition have to be less than the number of classical bits {}.",
+            clbit_count
+        ));
+    }
+    if value & !mask != 0 {
+        return error("The value of a condition has to be within its mask.".to_string());
+    }
+    Ok(Condition {
+        first_clbit,
+        clbit_count: clbit_count_of_mask,
+        value: value >> first_clbit,
+    })
+}
+
+fn object_condition(
+    value: &Map<String, Value>,
+    clbit_count: usize,
+) -> Result<Condition, ParseError> {
+    let relation = value
+        .get("relation")
+        .or_else(|| value.get("type"))
+        .and_then(|relation| relation.as_str());
+    if !matches!(relation, Some("==") | Some("equals")) {
+        return error("Only the equality conditions are supported.".to_string());
+    }
+    masked_condition(
+        hexadecimal(value.get("mask"), "mask")?,
+        hexadecimal(value.get("val"), "value")?,
+        clbit_count,
+    )
+}
+
+fn check_qubits(qubits: &[usize], qubit_count: usize) -> Result<(), ParseError> {
+    simulation::check_qubits(qubits, qubit_count).or_else(|e| error(e.to_string()))
+}
+
+// The count of the header or the configuration of the experiment, or of the whole qobj.
+fn count(experiment: &Value, qobj: &Value, key: &str) -> Option<usize> {
+    [
+        experiment.get("header"),
+        experiment.get("config"),
+        qobj.get("config"),
+    ]
+    .iter()
+    .flatten()
+    .find_map(|object| object.get(key).and_then(|count| count.as_u64()))
+    .map(|count| count as usize)
+}
+
+fn experiment_circuit(
+    experiment: &Value,
+    qobj: &Value,
+    library: &mut GateLibrary,
+) -> Result<Circuit, ParseError> {
+    let qubit_count = match count(experiment, qobj, "n_qubits") {
+        Some(qubit_count) => qubit_count,
+        None => return error("The experiment has no n_qubits.".to_string()),
+    };
+    let clbit_count = count(experiment, qobj, "memory_slots").unwrap_or(0);
+    let instructions = match experiment.get("instructions") {
+        Some(Value::Array(instructions)) => instructions,
+        Some(_) => return error("The instructions have to be a list.".to_string()),
+        None => return error("The experiment has no instructions.".to_string()),
+    };
+
+    let mut circuit = Circuit::with_clbits(qubit_count, clbit_count);
+    // The conditions of the registers set by the classical functions.
+    let mut registers: HashMap<u64, Condition> = HashMap::new();
+    for instruction in instructions.iter() {
+        let instruction = match instruction.as_object() {
+            Some(instruction) => instruction,
+            None => return error("An instruction has to be an object.".to_string()),
+        };
+        let name = match instruction.get("name").and_then(|name| name.as_str()) {
+            Some(name) => name,
+            None => return error("An instruction has no name.".to_string()),
+        };
+        let qubits = unsigned_list(instruction, "qubits")?;
+        check_qubits(&qubits, qubit_count)?;
+
+        let condition = match instruction.get("conditional") {
+            None => None,
+            Some(Value::Object(object)) => Some(object_condition(object, clbit_count)?),
+            Some(register) => match register
+                .as_u64()
+                .and_then(|register| registers.get(&register))
+            {
+                Some(&condition) => Some(condition),
+                None => {
+                    return error("The conditional register is not set by a bfunc.".to_string())
+                }
+            },
+        };
+
+        match name {
+            "barrier" => {}
+            "bfunc" => {
+                let register = match instruction.get("register").and_then(|r| r.as_u64()) {
+                    Some(register) => register,
+                    None => return error("The bfunc has no register.".to_string()),
+                };
+                registers.insert(register, object_condition(instruction, clbit_count)?);
+            }
+            "measure" => {
+                let memory = unsigned_list(instruction, "memory")?;
+                if memory.len() != qubits.len() {
+                    return error(
+                        "The measurement has to have a memory slot for each qubit.".to_string(),
+                    );
+                }
+                for (&qubit_number, &clbit_number) in qubits.iter().zip(memory.iter()) {
+                    if clbit_number >= clbit_count {
+                        return error(format!(
+                            "The classical bit number has to be less than the number of classical bits {}.",
+                            clbit_count
+                        ));
+                    }
+                    circuit
+                        .push_with_condition(Gate::Measure(qubit_number, clbit_number), condition);
+                }
+            }
+            _ => {
+                let parameters: Vec<f64> = match instruction.get("params") {
+                    None => Vec::new(),
+                    Some(Value::Array(values)) => {
+                        match values.iter().map(|value| value.as_f64()).collect() {
+                            Some(parameters) => parameters,
+                            None => {
+                                return error(format!(
+                                    "The parameters of the gate {} have to be numbers.",
+                                    name
+                                ))
+                            }
+                        }
+                    }
+                    Some(_) => return error("The parameters have to be a list.".to_string()),
+                };
+                let gates = match library.expand(name, &parameters, &qubits) {
+                    Ok(gates) => gates,
+                    Err(message) => return error(message),
+                };
+                for gate in gates {
+                    circuit.push_with_condition(gate, condition);
+                }
+            }
+        }
+    }
+    Ok(circuit)
+}
+
+impl Circuit {
+    // Parse the JSON format of a Qiskit qobj into a circuit for each of its experiments.
+    // A single experiment, an object with the instructions, is accepted as well.
+    pub fn from_qiskit_json(source: &str) -> Result<Vec<Circuit>, ParseError> {
+        let qobj: Value = serde_json::from_str(source).map_err(|e| ParseError {
+            line: e.line(),
+            message: e.to_string(),
+        })?;
+        let mut library = GateLibrary::new();
+        if qobj.get("instructions").is_some() {
+            return Ok(vec![experiment_circuit(&qobj, &Value::Null, &mut library)?]);
+        }
+        match qobj.get("experiments") {
+            Some(Value::Array(experiments)) => experiments
+                .iter()
+                .map(|experiment| experiment_circuit(experiment, &qobj, &mut library))
+                .collect(),
+            Some(_) => error("The experiments have to be a list.".to_string()),
+            None => error("The qobj has no experiments.".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qobj_is_imported() {
+        let source = r#"{
+            "qobj_id": "example", "type": "QASM", "schema_version": "1.3.0",
+            "config": {"shots": 1024, "memory_slots": 2, "n_qubits": 3},
+            "experiments": [{
+                "header": {"n_qubits": 3, "memory_slots": 2, "name": "bell"},
+                "config": {"n_qubits": 3, "memory_slots": 2},
+                "instructions": [
+                    {"name": "h", "qubits": [0]},
+                    {"name": "cx", "qubits": [0, 1]},
+                    {"name": "barrier", "qubits": [0, 1, 2]},
+                    {"name": "u3", "qubits": [2], "params": [0.5, 0.0, 0.25]},
+                    {"name": "measure", "qubits": [0, 1], "memory": [0, 1]},
+                    {"name": "bfunc", "mask": "0x3", "relation": "==", "val": "0x2", "register": 2},
+                    {"name": "x", "qubits": [2], "conditional": 2},
+                    {"name": "z", "qubits": [2],
+                     "conditional": {"mask": "0x2", "type": "equals", "val": "0x2"}}
+                ]
+            }]
+        }"#;
+        let circuits = Circuit::from_qiskit_json(source).unwrap();
+        assert_eq!(circuits.len(), 1);
+        let circuit = &circuits[0];
+        assert_eq!((circuit.qubit_count(), circuit.clbit_count()), (3, 2));
+        assert_eq!(circuit.gates().len(), 8);
+        let value = |first_clbit, clbit_count, value| Condition {
+            first_clbit,
+            clbit_count,
+            value,
+        };
+        assert_eq!(circuit.conditions()[6], Some(value(0, 2, 2)));
+        assert_eq!(circuit.conditions()[7], Some(value(1, 1, 1)));
+
+        let error = Circuit::from_qiskit_json(
+            r#"{"header": {"n_qubits": 1}, "instructions": [{"name": "cx", "qubits": [0, 1]}]}"#,
+        )
+        .unwrap_err();
+        assert!(error.message.contains("less than the number of qubits 1"));
+    }
+}
@@ -0,0 +1,769 @@
+/*
+Quil import and export.
+
+The qubits are the qubit indices of the program and the classical bits are laid out in the order
+of the BIT memory declarations. The DEFGATE matrices are registered in a gate registry and every
+application of a custom gate is decomposed into the gates of the circuit, equal up to a global
+phase. A classical condition is exported as forward JUMP-WHEN and JUMP-UNLESS instructions
+skipping the gate, and of the control flow only such forward jumps are imported.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+
+use crate::circuit::{u_f_gates, Circuit, Condition, Gate, ParseError};
+use crate::gate_registry::{is_unitary, GateRegistry, MAX_CUSTOM_GATE_QUBIT_COUNT};
+use crate::simulation::check_distinct_qubits;
+
+// The standard gates of Quil applied directly as gates of the circuit,
+// with their numbers of parameters and qubits.
+const NATIVE_GATES: [(&str, usize, usize); 18] = [
+    ("I", 0, 1),
+    ("X", 0, 1),
+    ("Y", 0, 1),
+    ("Z", 0, 1),
+    ("H", 0, 1),
+    ("S", 0, 1),
+    ("T", 0, 1),
+    ("RX", 1, 1),
+    ("RY", 1, 1),
+    ("RZ", 1, 1),
+    ("PHASE", 1, 1),
+    ("CNOT", 0, 2),
+    ("CZ", 0, 2),
+    ("SWAP", 0, 2),
+    ("CPHASE", 1, 2),
+    ("ISWAP", 0, 2),
+    ("CCNOT", 0, 3),
+    ("CSWAP", 0, 3),
+];
+
+// The instructions of Quil which have no equivalent in the circuit.
+const UNSUPPORTED_INSTRUCTIONS: [&str; 9] = [
+    "RESET",
+    "WAIT",
+    "JUMP",
+    "DAGGER",
+    "CONTROLLED",
+    "FORKED",
+    "DEFCIRCUIT",
+    "INCLUDE",
+    "MOVE",
+];
+
+// The name of the classical memory region of the exported programs.
+const EXPORT_REGION: &str = "ro";
+
+fn parse_error<T>(line: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { line, message })
+}
+
+// Evaluation of the complex arithmetic expressions of the gate parameters and the DEFGATE matrices.
+struct Expression {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl Expression {
+    fn evaluate(text: &str, line: usize) -> Result<Complex<f64>, ParseError> {
+        let mut expression = Expression {
+            chars: text.chars().filter(|c| !c.is_whitespace()).collect(),
+            position: 0,
+            line,
+        };
+        let value = expression.expression()?;
+        if expression.position != expression.chars.len() {
+            return parse_error(line, format!("Invalid expression {}.", text.trim()));
+        }
+        Ok(value)
+    }
+
+    fn evaluate_real(text: &str, line: usize) -> Result<f64, ParseError> {
+        let value = Expression::evaluate(text, line)?;
+        if value.im.abs() > 1e-12 {
+            return parse_error(line, format!("The parameter {} is not real.", text.trim()));
+        }
+        Ok(value.re)
+    }
+
+    fn is_char(&self, c: char) -> bool {
+        self.chars.get(self.position) == Some(&c)
+    }
+
+    fn expect_char(&mut self, c: char) -> Result<(), ParseError> {
+        if self.is_char(c) {
+            self.position += 1;
+            Ok(())
+        } else {
+            parse_error(self.line, format!("Expected '{}'.", c))
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Complex<f64>, ParseError> {
+        let mut value = self.term()?;
+        while self.is_char('+') || self.is_char('-') {
+            let is_addition = self.is_char('+');
+            self.position += 1;
+            let term = self.term()?;
+            value = if is_addition {
+                value + term
+            } else {
+                value - term
+            };
+        }
+        Ok(value)
+    }
+
+    // term := factor (('*' | '/') factor)*
+    fn term(&mut self) -> Result<Complex<f64>, ParseError> {
+        let mut value = self.factor()?;
+        while self.is_char('*') || self.is_char('/') {
+            let is_multiplication = self.is_char('*');
+            self.position += 1;
+            let factor = self.factor()?;
+            value = if is_multiplication {
+                value * factor
+            } else {
+                value / factor
+            };
+        }
+        Ok(value)
+    }
+
+    // factor := ('-' | '+') factor | primary ('^' factor)?
+    fn factor(&mut self) -> Result<Complex<f64>, ParseError> {
+        if self.is_char('-') || self.is_char('+') {
+            let is_negation = self.is_char('-');
+            self.position += 1;
+            let value = self.factor()?;
+            return Ok(if is_negation { -value } else { value });
+        }
+        let base = self.primary()?;
+        if self.is_char('^') {
+            self.position += 1;
+            return Ok(base.powc(self.factor()?));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Complex<f64>, ParseError> {
+        let start = self.position;
+        let c = match self.chars.get(self.position) {
+            Some(&c) => c,
+            None => return parse_error(self.line, "Expected an expression.".to_string()),
+        };
+        if c == '(' {
+            self.position += 1;
+            let value = self.expression()?;
+            self.expect_char(')')?;
+            Ok(value)
+        } else if c.is_ascii_digit() || c == '.' {
+            while self.position < self.chars.len()
+                && (self.chars[self.position].is_ascii_digit() || self.chars[self.position] == '.')
+            {
+                self.position += 1;
+            }
+            if self.is_char('e') || self.is_char('E') {
+                self.position += 1;
+                if self.is_char('+') || self.is_char('-') {
+                    self.position += 1;
+                }
+                while self.position < self.chars.len() && self.chars[self.position].is_ascii_digit()
+                {
+                    self.position += 1;
+                }
+            }
+            let text: String = self.chars[start..self.position].iter().collect();
+            let number = match text.parse::<f64>() {
+                Ok(number) => number,
+                Err(_) => return parse_error(self.line, format!("Invalid number {}.", text)),
+            };
+            // A number directly followed by i is imaginary.
+            if self.is_char('i') {
+                self.position += 1;
+                return Ok(Complex::new(0.0, number));
+            }
+            Ok(Complex::new(number, 0.0))
+        } else if c.is_ascii_alphabetic() {
+            while self.position < self.chars.len()
+                && self.chars[self.position].is_ascii_alphabetic()
+            {
+                self.position += 1;
+            }
+            let name: String = self.chars[start..self.position].iter().collect();
+            match name.to_lowercase().as_str() {
+                "i" => Ok(Complex::new(0.0, 1.0)),
+                "pi" => Ok(Complex::new(PI, 0.0)),
+                function @ ("sin" | "cos" | "sqrt" | "exp" | "cis") => {
+                    self.expect_char('(')?;
+                    let x = self.expression()?;
+                    self.expect_char(')')?;
+                    Ok(match function {
+                        "sin" => x.sin(),
+                        "cos" => x.cos(),
+                        "sqrt" => x.sqrt(),
+                        "exp" => x.exp(),
+                        _ => (Complex::new(0.0, 1.0) * x).exp(),
+                    })
+                }
+                _ => parse_error(
+                    self.line,
+                    format!("Unknown name {} in the expression.", name),
+                ),
+            }
+        } else {
+            parse_error(self.line, format!("Unexpected character '{}'.", c))
+        }
+    }
+}
+
+// A forward jump over the following instructions until its label, which skips them
+// unless the classical bit has the value.
+struct Jump {
+    label: String,
+    clbit_number: usize,
+    value: bool,
+}
+
+struct Parser {
+    registry: GateRegistry,
+    // Memory region name to its first classical bit and its size.
+    regions: HashMap<String, (usize, usize)>,
+    clbit_count: usize,
+    jumps: Vec<Jump>,
+    labels: HashSet<String>,
+    gates: Vec<(Gate, Option<Condition>)>,
+}
+
+impl Parser {
+    fn new(registry: &GateRegistry) -> Parser {
+        let mut registry = registry.clone();
+        let zero = Complex::new(0.0, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        let i = Complex::new(0.0, 1.0);
+        registry.register(
+            "ISWAP",
+            vec![
+                vec![one, zero, zero, zero],
+                vec![zero, zero, i, zero],
+                vec![zero, i, zero, zero],
+                vec![zero, zero, zero, one],
+            ],
+        );
+        Parser {
+            registry,
+            regions: HashMap::new(),
+            clbit_count: 0,
+            jumps: Vec::new(),
+            labels: HashSet::new(),
+            gates: Vec::new(),
+        }
+    }
+
+    fn program(&mut self, source: &str) -> Result<(), ParseError> {
+        let lines: Vec<&str> = source
+            .lines()
+            .map(|line| line.split('#').next().unwrap())
+            .collect();
+        let mut index = 0;
+        while index < lines.len() {
+            let line_number = index + 1;
+            let words: Vec<&str> = lines[index].split_whitespace().collect();
+            index += 1;
+            match words.first() {
+                None => {}
+                Some(&"DEFGATE") => {
+                    // The rows of the matrix are the following indented lines.
+                    let mut rows = Vec::new();
+                    while index < lines.len()
+                        && lines[index].starts_with([' ', '\t'])
+                        && !lines[index].trim().is_empty()
+                    {
+                        rows.push((lines[index], index + 1));
+                        index += 1;
+                    }
+                    self.gate_definition(&words[1..], &rows, line_number)?;
+                }
+                Some(&"HALT") => break,
+                Some(_) => self.instruction(lines[index - 1].trim(), &words, line_number)?,
+            }
+        }
+        if let Some(jump) = self.jumps.first() {
+            return parse_error(
+                lines.len().max(1),
+                format!("The label @{} is not defined.", jump.label),
+            );
+        }
+        Ok(())
+    }
+
+    fn gate_definition(
+        &mut self,
+        words: &[&str],
+        rows: &[(&str, usize)],
+        line: usize,
+    ) -> Result<(), ParseError> {
+        let name = match words {
+            [name] | [name, "AS", "MATRIX:"] => name.trim_end_matches(':'),
+            _ => return parse_error(line, "Invalid DEFGATE declaration.".to_string()),
+        };
+        if name.contains('(') {
+            return parse_error(line, "Parameterized DEFGATE is not supported.".to_string());
+        }
+        let mut matrix = Vec::new();
+        for &(row, row_line) in rows.iter() {
+            let mut entries = Vec::new();
+            for entry in row.split(',') {
+                entries.push(Expression::evaluate(entry, row_line)?);
+            }
+            matrix.push(entries);
+        }
+        let qubit_count = matrix.len().trailing_zeros() as usize;
+        if matrix.len() != 1 << qubit_count
+            || !(1..=MAX_CUSTOM_GATE_QUBIT_COUNT).contains(&qubit_count)
+        {
+            return parse_error(
+                line,
+                format!(
+                    "The matrix of the gate {} has to act on 1 to {} qubits.",
+                    name, MAX_CUSTOM_GATE_QUBIT_COUNT
+                ),
+            );
+        }
+        if !is_unitary(&matrix) {
+            return parse_error(
+                line,
+                format!("The matrix of the gate {} is not unitary.", name),
+            );
+        }
+        self.registry.register(name, matrix);
+        Ok(())
+    }
+
+    // The classical bit of a memory reference like ro[1], or ro for the first bit.
+    fn clbit(&self, reference: &str, line: usize) -> Result<usize, ParseError> {
+        let (name, index) = match reference.split_once('[') {
+            Some((name, rest)) => {
+                match rest.strip_suffix(']').map(|index| index.parse::<usize>()) {
+                    Some(Ok(index)) => (name, index),
+                    _ => {
+                        return parse_error(
+                            line,
+                            format!("Invalid memory reference {}.", reference),
+                        )
+                    }
+                }
+            }
+            None => (reference, 0),
+        };
+        match self.regions.get(name) {
+            Some(&(start, size)) if index < size => Ok(start + index),
+            Some(_) => parse_error(
+                line,
+                format!("The index {} is out of the memory region {}.", index, name),
+            ),
+            None => parse_error(line, format!("Unknown memory region {}.", name)),
+        }
+    }
+
+    // The condition of the gates inside the open jumps, or None for a gate which is always skipped.
+    fn condition(&self, line: usize) -> Result<Option<Option<Condition>>, ParseError> {
+        let mut values: Vec<(usize, bool)> = self
+            .jumps
+            .iter()
+            .map(|jump| (jump.clbit_number, jump.value))
+            .collect();
+        if values.is_empty() {
+            return Ok(Some(None));
+        }
+        values.sort();
+        values.dedup();
+        if values.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Ok(None);
+        }
+        let first_clbit = values[0].0;
+        let clbit_count = values.len();
+        if values.last().unwrap().0 - first_clbit + 1 != clbit_count || clbit_count > 64 {
+            return parse_error(
+                line,
+                "The jumps over a gate have to test consecutive classical bits.".to_string(),
+            );
+        }
+        let value = values
+            .iter()
+            .enumerate()
+            .fold(0u64, |value, (bit, &(_, v))| value | ((v as u64) << bit));
+        Ok(Some(Some(Condition {
+            first_clbit,
+            clbit_count,
+            value,
+        })))
+    }
+
+    fn push(&mut self, gate: Gate, line: usize) -> Result<(), ParseError> {
+        if let Some(condition) = self.condition(line)? {
+            self.gates.push((gate, condition));
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, text: &str, words: &[&str], line: usize) -> Result<(), ParseError> {
+        match words[0] {
+            "PRAGMA" | "NOP" => Ok(()),
+            "DECLARE" => {
+                let (name, size) = match words[1..] {
+                    [name, "BIT"] => (name, 1),
+                    [name, region_type] => {
+                        match region_type
+                            .strip_prefix("BIT[")
+                            .and_then(|size| size.strip_suffix(']'))
+                            .map(|size| size.parse::<usize>())
+                        {
+                            Some(Ok(size)) => (name, size),
+                            _ => {
+                                return parse_error(
+                                    line,
+                                    "Only BIT memory regions are supported.".to_string(),
+                                )
+                            }
+                        }
+                    }
+                    _ => return parse_error(line, "Invalid DECLARE instruction.".to_string()),
+                };
+                if self.regions.contains_key(name) {
+                    return parse_error(
+                        line,
+                        format!("The memory region {} is already declared.", name),
+                    );
+                }
+                self.regions
+                    .insert(name.to_string(), (self.clbit_count, size));
+                self.clbit_count += size;
+                Ok(())
+            }
+            "MEASURE" => match words[1..] {
+                [qubit, reference] => {
+                    let qubit_number = qubit_index(qubit, line)?;
+                    let clbit_number = self.clbit(reference, line)?;
+                    self.push(Gate::Measure(qubit_number, clbit_number), line)
+                }
+                _ => parse_error(
+                    line,
+                    "A measurement needs a qubit and a memory reference.".to_string(),
+                ),
+            },
+            "JUMP-WHEN" | "JUMP-UNLESS" => match words[1..] {
+                [label, reference] => {
+                    let label = match label.strip_prefix('@') {
+                        Some(label) => label.to_string(),
+                        None => return parse_error(line, format!("Invalid label {}.", label)),
+                    };
+                    if self.labels.contains(&label) {
+                        return parse_error(line, "Only forward jumps are supported.".to_string());
+                    }
+                    let clbit_number = self.clbit(reference, line)?;
+                    self.jumps.push(Jump {
+                        label,
+                        clbit_number,
+                        value: words[0] == "JUMP-UNLESS",
+                    });
+                    Ok(())
+                }
+                _ => parse_error(line, format!("Invalid {} instruction.", words[0])),
+            },
+            "LABEL" => match words[1..] {
+                [label] if label.starts_with('@') => {
+                    let label = &label[1..];
+                    if !self.labels.insert(label.to_string()) {
+                        return parse_error(
+                            line,
+                            format!("The label @{} is already defined.", label),
+                        );
+                    }
+                    self.jumps.retain(|jump| jump.label != label);
+                    Ok(())
+                }
+                _ => parse_error(line, "Invalid LABEL instruction.".to_string()),
+            },
+            keyword if UNSUPPORTED_INSTRUCTIONS.contains(&keyword) => parse_error(
+                line,
+                format!("The instruction {} is not supported.", keyword),
+            ),
+            _ => self.gate(text, line),
+        }
+    }
+
+    fn gate(&mut self, text: &str, line: usize) -> Result<(), ParseError> {
+        let (name, parameters, rest) = match text.split_once('(') {
+            Some((name, rest)) if !name.contains(char::is_whitespace) => {
+                match rest.split_once(')') {
+                    Some((parameters, rest)) => {
+                        let mut values = Vec::new();
+                        for parameter in parameters.split(',') {
+                            values.push(Expression::evaluate_real(parameter, line)?);
+                        }
+                        (name, values, rest)
+                    }
+                    None => return parse_error(line, "Expected ')'.".to_string()),
+                }
+            }
+            _ => {
+                let (name, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+                (name, Vec::new(), rest)
+            }
+        };
+        let mut qubits = Vec::new();
+        for qubit in rest.split_whitespace() {
+            qubits.push(qubit_index(qubit, line)?);
+        }
+        check_distinct_qubits(&qubits).or_else(|e| parse_error(line, e.to_string()))?;
+
+        let (parameter_count, qubit_count) = match NATIVE_GATES.iter().find(|&&(n, _, _)| n == name)
+        {
+            Some(&(_, parameter_count, qubit_count)) => (parameter_count, qubit_count),
+            None => match self.registry.get(name) {
+                Some(gate) => (0, gate.qubit_count()),
+                None => return parse_error(line, format!("Unknown gate {}.", name)),
+            },
+        };
+        if parameters.len() != parameter_count || qubits.len() != qubit_count {
+            return parse_error(
+                line,
+                format!(
+                    "The gate {} takes {} parameters and {} qubits.",
+                    name, parameter_count, qubit_count
+                ),
+            );
+        }
+
+        let p = &parameters;
+        let q = &qubits;
+        let gates = match name {
+            "I" => vec![],
+            "X" => vec![Gate::PauliX(q[0])],
+            "Y" => vec![Gate::PauliY(q[0])],
+            "Z" => vec![Gate::PauliZ(q[0])],
+            "H" => vec![Gate::Hadamard(q[0])],
+            "S" => vec![Gate::S(q[0])],
+            "T" => vec![Gate::T(q[0])],
+            "RX" => vec![Gate::Rx(p[0], q[0])],
+            "RY" => vec![Gate::Ry(p[0], q[0])],
+            // PHASE(θ) = Rz(θ) up to a global phase.
+            "RZ" | "PHASE" => vec![Gate::Rz(p[0], q[0])],
+            "CNOT" => vec![Gate::Cnot(q[0], q[1])],
+            "CZ" => vec![Gate::Cz(q[0], q[1])],
+            "SWAP" => vec![Gate::Swap(q[0], q[1])],
+            "CPHASE" => vec![Gate::Cphase(p[0], q[0], q[1])],
+            "CCNOT" => vec![Gate::Toffoli(q[0], q[1], q[2])],
+            "CSWAP" => vec![
+                Gate::Cnot(q[2], q[1]),
+                Gate::Toffoli(q[0], q[1], q[2]),
+                Gate::Cnot(q[2], q[1]),
+            ],
+            _ => self
+                .registry
+                .get(name)
+                .unwrap()
+                .to_circuit()
+                .gates()
+                .iter()
+                .map(|gate| gate.map_qubits(|local| q[local]))
+                .collect(),
+        };
+        for gate in gates {
+            self.push(gate, line)?;
+        }
+        Ok(())
+    }
+}
+
+fn qubit_index(text: &str, line: usize) -> Result<usize, ParseError> {
+    match text.parse::<usize>() {
+        Ok(qubit_number) => Ok(qubit_number),
+        Err(_) => parse_error(line, format!("Invalid qubit {}.", text)),
+    }
+}
+
+fn instruction(gate: &Gate) -> Vec<String> {
+    match *gate {
+        Gate::PauliX(q) => vec![format!("X {}", q)],
+        Gate::PauliY(q) => vec![format!("Y {}", q)],
+        Gate::PauliZ(q) => vec![format!("Z {}", q)],
+        Gate::Hadamard(q) => vec![format!("H {}", q)],
+        Gate::S(q) => vec![format!("S {}", q)],
+        Gate::T(q) => vec![format!("T {}", q)],
+        Gate::Rx(theta, q) => vec![format!("RX({}) {}", theta, q)],
+        Gate::Ry(theta, q) => vec![format!("RY({}) {}", theta, q)],
+        Gate::Rz(theta, q) => vec![format!("RZ({}) {}", theta, q)],
+        Gate::Cnot(q0, q1) => vec![format!("CNOT {} {}", q0, q1)],
+        Gate::Cz(q0, q1) => vec![format!("CZ {} {}", q0, q1)],
+        Gate::Cphase(theta, q0, q1) => vec![format!("CPHASE({}) {} {}", theta, q0, q1)],
+        Gate::Swap(q0, q1) => vec![format!("SWAP {} {}", q0, q1)],
+        Gate::UF(f, q0, q1) => u_f_gates(f, q0, q1).iter().flat_map(instruction).collect(),
+        Gate::Toffoli(q0, q1, q2) => vec![format!("CCNOT {} {} {}", q0, q1, q2)],
+        Gate::Measure(q, c) => vec![format!("MEASURE {} {}[{}]", q, EXPORT_REGION, c)],
+    }
+}
+
+impl Circuit {
+    // Parse a Quil program into a circuit with the qubits up to the highest qubit index of the program.
+    pub fn from_quil(source: &str) -> Result<Circuit, ParseError> {
+        Circuit::from_quil_with_registry(source, &GateRegistry::new())
+    }
+
+    // Parse a Quil program which can also apply the custom gates of the registry.
+    pub fn from_quil_with_registry(
+        source: &str,
+        registry: &GateRegistry,
+    ) -> Result<Circuit, ParseError> {
+        let mut parser = Parser::new(registry);
+        parser.program(source)?;
+
+        let qubit_count = parser
+            .gates
+            .iter()
+            .flat_map(|(gate, _)| gate.qubits())
+            .max()
+            .map_or(0, |qubit_number| qubit_number + 1);
+        let mut circuit = Circuit::with_clbits(qubit_count, parser.clbit_count);
+        for (gate, condition) in parser.gates.into_iter() {
+            circuit.push_with_condition(gate, condition);
+        }
+        Ok(circuit)
+    }
+
+    // Quil program text of the circuit, with the classical bits in the memory region ro.
+    pub fn to_quil(&self) -> String {
+        let mut text = String::new();
+        if self.clbit_count > 0 {
+            text += &format!("DECLARE {} BIT[{}]\n", EXPORT_REGION, self.clbit_count);
+        }
+        let mut label_count = 0;
+        for (gate, condition) in self.gates.iter().zip(self.conditions.iter()) {
+            let instructions = instruction(gate);
+            match condition {
+                Some(condition) if !instructions.is_empty() => {
+                    let label = format!("@skip{}", label_count);
+                    label_count += 1;
+                    for (bit, clbit_number) in condition.clbits().enumerate() {
+                        let jump = if (condition.value >> bit) & 1 == 1 {
+                            "JUMP-UNLESS"
+                        } else {
+                            "JUMP-WHEN"
+                        };
+                        text +=
+                            &format!("{} {} {}[{}]\n", jump, label, EXPORT_REGION, clbit_number);
+                    }
+                    for instruction in instructions {
+                        text += &format!("{}\n", instruction);
+                    }
+                    text += &format!("LABEL {}\n", label);
+                }
+                _ => {
+                    for instruction in instructions {
+                        text += &format!("{}\n", instruction);
+                    }
+                }
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn quil_program_with_custom_gates_is_parsed_and_run() {
+        let source = [
+            "DECLARE ro BIT[3]",
+            "# The square root of X, applied twice.",
+            "DEFGATE SQRTX:",
+            "    0.5+0.5i, 0.5-0.5i",
+            "    0.5-0.5i, 0.5+0.5i",
+            "DEFGATE CONTROLLEDX AS MATRIX:",
+            "    1, 0, 0, 0",
+            "    0, 1, 0, 0",
+            "    0, 0, 0, 1",
+            "    0, 0, 1, 0",
+            "SQRTX 0",
+            "SQRTX 0",
+            "CONTROLLEDX 0 2",
+            "MEASURE 0 ro[0]",
+            "MEASURE 2 ro[2]",
+            "JUMP-UNLESS @end ro[2]",
+            "CPHASE(pi/2) 1 3",
+            "X 1",
+            "LABEL @end",
+            "MEASURE 1 ro[1]",
+            "TOFFOLI 0 1 2",
+        ]
+        .join("\n");
+
+        let error = Circuit::from_quil(&source).unwrap_err();
+        assert_eq!(error.line, 21);
+        assert_eq!(error.message, "Unknown gate TOFFOLI.");
+
+        let source = source.replace("TOFFOLI", "CCNOT");
+        let circuit = Circuit::from_quil(&source).unwrap();
+        assert_eq!(circuit.qubit_count(), 4);
+        assert_eq!(circuit.clbit_count(), 3);
+        let mut simulation = QuantumSimulation::new(4, 0u64);
+        assert_eq!(circuit.run(&mut simulation), vec![true, true, true]);
+
+        let mut registry = GateRegistry::new();
+        registry.register(
+            "NOT",
+            vec![
+                vec![Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+                vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+            ],
+        );
+        let circuit =
+            Circuit::from_quil_with_registry("DECLARE ro BIT\nNOT 0\nMEASURE 0 ro", &registry)
+                .unwrap();
+        let mut simulation = QuantumSimulation::new(1, 0u64);
+        assert_eq!(circuit.run(&mut simulation), vec![true]);
+    }
+
+    #[test]
+    fn quil_export_round_trips() {
+        let mut circuit = Circuit::with_clbits(3, 2);
+        circuit.hadamard(0);
+        circuit.rx(0.25, 1);
+        circuit.cphase(-1.5, 0, 2);
+        circuit.measure(0, 0);
+        circuit.measure(2, 1);
+        circuit.push_conditional(
+            Gate::Toffoli(0, 1, 2),
+            Condition {
+                first_clbit: 0,
+                clbit_count: 2,
+                value: 1,
+            },
+        );
+        let text = circuit.to_quil();
+        assert_eq!(
+            text,
+            "DECLARE ro BIT[2]\n\
+             H 0\n\
+             RX(0.25) 1\n\
+             CPHASE(-1.5) 0 2\n\
+             MEASURE 0 ro[0]\n\
+             MEASURE 2 ro[1]\n\
+             JUMP-UNLESS @skip0 ro[0]\n\
+             JUMP-WHEN @skip0 ro[1]\n\
+             CCNOT 0 1 2\n\
+             LABEL @skip0\n"
+        );
+        assert_eq!(Circuit::from_quil(&text).unwrap().to_quil(), text);
+    }
+}
@@ -0,0 +1,697 @@
+/*
+Stim circuit import.
+
+The qubits are the qubit targets of the program and the classical bits are the measurement
+record of Stim, followed by a bit for each reset, which the circuit implements as a measurement
+and a conditional X gate. The Clifford gates are applied as gates of the circuit, equal up to
+a global phase, the measurements in the X- and Y-bases are rotated into the Z-basis, and
+CX, CY, and CZ gates controlled by a measurement record target rec[-k] are conditional Pauli
+gates. REPEAT blocks are unrolled.
+
+The circuits have no noise, so from_stim checks and ignores the noise channels like X_ERROR and
+DEPOLARIZE1 and the flip probabilities of the measurements, and the imported circuit is the
+noiseless circuit of the program. from_stim_with_noise also returns the single-qubit channels
+with the number of the gates of the circuit before them and their qubits, for applying them
+with apply_kraus. The flip probability of a measurement is a bit flip before it, which unlike
+Stim also flips the qubit after the measurement unless it is reset. The two-qubit channels
+DEPOLARIZE2 and PAULI_CHANNEL_2 have no single-qubit channel and are an error there.
+
+The annotations like DETECTOR and TICK are checked and ignored.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::PI;
+
+use crate::circuit::{Circuit, Condition, Gate, ParseError};
+use crate::noise::KrausChannel;
+use crate::simulation::check_distinct_qubits;
+
+// A noise channel applied to each of the qubits after the number of the gates of the circuit.
+pub type LocatedChannel = (usize, KrausChannel, Vec<usize>);
+
+// The noise channels with their numbers of parameters and qubit targets per application.
+const NOISE_CHANNELS: [(&str, usize, usize); 9] = [
+    ("X_ERROR", 1, 1),
+    ("Y_ERROR", 1, 1),
+    ("Z_ERROR", 1, 1),
+    ("I_ERROR", 1, 1),
+    ("DEPOLARIZE1", 1, 1),
+    ("DEPOLARIZE2", 1, 2),
+    ("PAULI_CHANNEL_1", 3, 1),
+    ("PAULI_CHANNEL_2", 15, 2),
+    ("II_ERROR", 1, 2),
+];
+
+const ANNOTATIONS: [&str; 5] = [
+    "TICK",
+    "DETECTOR",
+    "OBSERVABLE_INCLUDE",
+    "QUBIT_COORDS",
+    "SHIFT_COORDS",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Basis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Clone, Copy)]
+enum Target {
+    // The qubit and whether the measurement result is inverted.
+    Qubit(usize, bool),
+    // The measurement k results back, rec[-k].
+    Record(usize),
+}
+
+enum Item {
+    Instruction {
+        line: usize,
+        name: String,
+        parameters: Vec<f64>,
+        targets: Vec<Target>,
+    },
+    Repeat {
+        count: usize,
+        body: Vec<Item>,
+    },
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, ParseError> {
+    Err(ParseError { line, message })
+}
+
+fn target(text: &str, line: usize) -> Result<Target, ParseError> {
+    if let Some(k) = text
+        .strip_prefix("rec[-")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return match k.parse::<usize>() {
+            Ok(k) if k > 0 => Ok(Target::Record(k)),
+            _ => error(line, format!("Invalid measurement record target {}.", text)),
+        };
+    }
+    let (is_inverted, number) = match text.strip_prefix('!') {
+        Some(number) => (true, number),
+        None => (false, text),
+    };
+    match number.parse::<usize>() {
+        Ok(qubit_number) => Ok(Target::Qubit(qubit_number, is_inverted)),
+        Err(_) => error(line, format!("The target {} is not supported.", text)),
+    }
+}
+
+// Parse the lines up to the closing brace of a REPEAT block, or up to the end at the top level.
+fn parse_items(
+    lines: &mut impl Iterator<Item = (usize, String)>,
+    is_block: bool,
+) -> Result<Vec<Item>, ParseError> {
+    let mut items = Vec::new();
+    while let Some((line, text)) = lines.next() {
+        if text == "}" {
+            if is_block {
+                return Ok(items);
+            }
+            return error(line, "Unexpected }.".to_string());
+        }
+        let (head, rest) = match text.find(|c: char| c == '(' || c.is_whitespace()) {
+            Some(position) => text.split_at(position),
+            None => (text.as_str(), ""),
+        };
+        let name = head.to_ascii_uppercase();
+        let mut rest = rest.trim_start();
+        let mut parameters = Vec::new();
+        if let Some(after_parenthesis) = rest.strip_prefix('(') {
+            let end = match after_parenthesis.find(')') {
+                Some(end) => end,
+                None => {
+                    return error(
+                        line,
+                        "The parameters have no closing parenthesis.".to_string(),
+                    )
+                }
+            };
+            for parameter in after_parenthesis[..end].split(',') {
+                match parameter.trim().parse::<f64>() {
+                    Ok(value) => parameters.push(value),
+                    Err(_) => {
+                        return error(line, format!("Invalid parameter {}.", parameter.trim()))
+                    }
+                }
+            }
+            rest = after_parenthesis[end + 1..].trim_start();
+        }
+
+        if name == "REPEAT" {
+            let count = match rest
+                .strip_suffix('{')
+                .map(|count| count.trim().parse::<usize>())
+            {
+                Some(Ok(count)) => count,
+                _ => {
+                    return error(
+                        line,
+                        "A REPEAT block needs a count and an opening brace.".to_string(),
+                    )
+                }
+            };
+            let body = parse_items(lines, true)?;
+            items.push(Item::Repeat { count, body });
+            continue;
+        }
+        let targets = rest
+            .split_whitespace()
+            .map(|text| target(text, line))
+            .collect::<Result<Vec<Target>, ParseError>>()?;
+        items.push(Item::Instruction {
+            line,
+            name,
+            parameters,
+            targets,
+        });
+    }
+    if is_block {
+        return error(0, "A REPEAT block has no closing brace.".to_string());
+    }
+    Ok(items)
+}
+
+// The gates rotating the basis into the Z-basis.
+fn basis_gates(basis: Basis, qubit_number: usize) -> Vec<Gate> {
+    match basis {
+        Basis::X => vec![Gate::Hadamard(qubit_number)],
+        Basis::Y => vec![
+            Gate::Rz(-PI / 2.0, qubit_number),
+            Gate::Hadamard(qubit_number),
+        ],
+        Basis::Z => Vec::new(),
+    }
+}
+
+fn inverse_basis_gates(basis: Basis, qubit_number: usize) -> Vec<Gate> {
+    match basis {
+        Basis::X => vec![Gate::Hadamard(qubit_number)],
+        Basis::Y => vec![Gate::Hadamard(qubit_number), Gate::S(qubit_number)],
+        Basis::Z => Vec::new(),
+    }
+}
+
+// The Pauli bases of the controlled gates named like XCZ, with the aliases of the Z-controlled gates.
+fn controlled_bases(name: &str) -> Option<(Basis, Basis)> {
+    let name = match name {
+        "CX" | "CNOT" => "ZCX",
+        "CY" => "ZCY",
+        "CZ" => "ZCZ",
+        name => name,
+    };
+    let basis = |c: u8| match c {
+        b'X' => Some(Basis::X),
+        b'Y' => Some(Basis::Y),
+        b'Z' => Some(Basis::Z),
+        _ => None,
+    };
+    match name.as_bytes() {
+        &[control, b'C', target] => Some((basis(control)?, basis(target)?)),
+        _ => None,
+    }
+}
+
+// The single-qubit channel of a Stim noise channel with valid probabilities, none for the
+// identity channels.
+fn noise_channel(name: &str, parameters: &[f64]) -> Option<KrausChannel> {
+    match name {
+        "X_ERROR" => Some(KrausChannel::bit_flip(parameters[0])),
+        "Y_ERROR" => Some(KrausChannel::pauli(0.0, parameters[0], 0.0)),
+        "Z_ERROR" => Some(KrausChannel::phase_flip(parameters[0])),
+        // Stim depolarizes with X, Y, and Z each with the probability p/3.
+        "DEPOLARIZE1" => {
+            let probability = parameters[0] / 3.0;
+            Some(KrausChannel::pauli(probability, probability, probability))
+        }
+        "PAULI_CHANNEL_1" => Some(KrausChannel::pauli(
+            parameters[0],
+            parameters[1],
+            parameters[2],
+        )),
+        _ => None,
+    }
+}
+
+struct Importer {
+    qubit_count: usize,
+    clbit_count: usize,
+    // The classical bits of the measurement record.
+    record: Vec<usize>,
+    gates: Vec<(Gate, Option<Condition>)>,
+    // The noise channels, none if they are ignored.
+    channels: Option<Vec<LocatedChannel>>,
+}
+
+impl Importer {
+    fn push(&mut self, gates: Vec<Gate>) {
+        for gate in gates {
+            self.gates.push((gate, None));
+        }
+    }
+
+    fn qubit(&mut self, target: Target, line: usize) -> Result<usize, ParseError> {
+        match target {
+            Target::Qubit(qubit_number, false) => {
+                self.qubit_count = self.qubit_count.max(qubit_number + 1);
+                Ok(qubit_number)
+            }
+            Target::Qubit(_, true) => error(
+                line,
+                "Only the measurement targets can be inverted.".to_string(),
+            ),
+            Target::Record(_) => error(
+                line,
+                "A measurement record target cannot be a qubit.".to_string(),
+            ),
+        }
+    }
+
+    fn record_clbit(&self, k: usize, line: usize) -> Result<usize, ParseError> {
+        if k > self.record.len() {
+            return error(
+                line,
+                format!("The target rec[-{}] is before the first measurement.", k),
+            );
+        }
+        Ok(self.record[self.record.len() - k])
+    }
+
+    // Measure the qubit in the basis, recording the result unless it is a reset,
+    // and reset the qubit to the +1 eigenstate of the basis if asked.
+    fn measure(
+        &mut self,
+        target: Target,
+        basis: Basis,
+        is_recorded: bool,
+        is_reset: bool,
+        flip_probability: Option<&f64>,
+        line: usize,
+    ) -> Result<(), ParseError> {
+        let (qubit_number, is_inverted) = match target {
+            Target::Qubit(qubit_number, is_inverted) => (qubit_number, is_inverted),
+            Target::Record(_) => {
+                return error(
+                    line,
+                    "A measurement record target cannot be measured.".to_string(),
+                )
+            }
+        };
+        self.qubit_count = self.qubit_count.max(qubit_number + 1);
+        let clbit_number = self.clbit_count;
+        self.clbit_count += 1;
+        if is_recorded {
+            self.record.push(clbit_number);
+        }
+        self.push(basis_gates(basis, qubit_number));
+        if is_inverted {
+            self.push(vec![Gate::PauliX(qubit_number)]);
+        }
+        if let (Some(channels), Some(&probability)) = (&mut self.channels, flip_probability) {
+            channels.push((
+                self.gates.len(),
+                KrausChannel::bit_flip(probability),
+                vec![qubit_number],
+            ));
+        }
+        self.gates
+            .push((Gate::Measure(qubit_number, clbit_number), None));
+        if is_reset {
+            self.gates.push((
+                Gate::PauliX(qubit_number),
+                Some(Condition::bit(clbit_number, true)),
+            ));
+        } else if is_inverted {
+            self.push(vec![Gate::PauliX(qubit_number)]);
+        }
+        self.push(inverse_basis_gates(basis, qubit_number));
+        Ok(())
+    }
+
+    fn single_qubit_gates(name: &str, q: usize) -> Option<Vec<Gate>> {
+        Some(match name {
+            "I" => Vec::new(),
+            "X" => vec![Gate::PauliX(q)],
+            "Y" => vec![Gate::PauliY(q)],
+            "Z" => vec![Gate::PauliZ(q)],
+            "H" | "H_XZ" => vec![Gate::Hadamard(q)],
+            "S" | "SQRT_Z" => vec![Gate::S(q)],
+            "S_DAG" | "SQRT_Z_DAG" => vec![Gate::Rz(-PI / 2.0, q)],
+            "SQRT_X" => vec![Gate::Rx(PI / 2.0, q)],
+            "SQRT_X_DAG" => vec![Gate::Rx(-PI / 2.0, q)],
+            "SQRT_Y" => vec![Gate::Ry(PI / 2.0, q)],
+            "SQRT_Y_DAG" => vec![Gate::Ry(-PI / 2.0, q)],
+            "H_XY" => vec![Gate::PauliX(q), Gate::S(q)],
+            "H_YZ" => vec![Gate::Rz(-PI / 2.0, q), Gate::Hadamard(q), Gate::S(q)],
+            _ => return None,
+        })
+    }
+
+    fn instruction(
+        &mut self,
+        line: usize,
+        name: &str,
+        parameters: &[f64],
+        targets: &[Target],
+    ) -> Result<(), ParseError> {
+        if ANNOTATIONS.contains(&name) {
+            return Ok(());
+        }
+        if let Some(&(_, parameter_count, qubit_count)) =
+            NOISE_CHANNELS.iter().find(|&&(n, _, _)| n == name)
+        {
+            if parameters.len() != parameter_count {
+                return error(
+                    line,
+                    format!(
+                        "The noise channel {} takes {} parameters.",
+                        name, parameter_count
+                    ),
+                );
+            }
+            if !targets.len().is_multiple_of(qubit_count) {
+                return error(
+                    line,
+                    format!("The noise channel {} takes pairs of qubits.", name),
+                );
+            }
+            let probabilities = if name == "PAULI_CHANNEL_1" || name == "PAULI_CHANNEL_2" {
+                let mut probabilities = parameters.to_vec();
+                probabilities.push(parameters.iter().sum());
+                probabilities
+            } else {
+                parameters.to_vec()
+            };
+            if !probabilities.iter().all(|p| (0.0..=1.0).contains(p)) {
+                return error(
+                    line,
+                    "The probability of a channel has to be between 0 and 1.".to_string(),
+                );
+            }
+            let qubit_numbers = targets
+                .iter()
+                .map(|&target| self.qubit(target, line))
+                .collect::<Result<Vec<usize>, ParseError>>()?;
+            if let Some(channels) = &mut self.channels {
+                if name == "DEPOLARIZE2" || name == "PAULI_CHANNEL_2" {
+                    return error(
+                        line,
+                        format!("The two-qubit noise channel {} is not supported.", name),
+                    );
+                }
+                if let Some(channel) = noise_channel(name, parameters) {
+                    channels.push((self.gates.len(), channel, qubit_numbers));
+                }
+            }
+            return Ok(());
+        }
+
+        let measurement = match name {
+            "M" | "MZ" => Some((Basis::Z, true, false)),
+            "MX" => Some((Basis::X, true, false)),
+            "MY" => Some((Basis::Y, true, false)),
+            "MR" | "MRZ" => Some((Basis::Z, true, true)),
+            "MRX" => Some((Basis::X, true, true)),
+            "MRY" => Some((Basis::Y, true, true)),
+            "R" | "RZ" => Some((Basis::Z, false, true)),
+            "RX" => Some((Basis::X, false, true)),
+            "RY" => Some((Basis::Y, false, true)),
+            _ => None,
+        };
+        if let Some((basis, is_recorded, is_reset)) = measurement {
+            // The measurements may have a flip probability, the resets no parameters.
+            if parameters.len() > usize::from(is_recorded) {
+                return error(
+                    line,
+                    format!("The instruction {} takes no parameters.", name),
+                );
+            }
+            if !parameters.iter().all(|p| (0.0..=1.0).contains(p)) {
+                return error(
+                    line,
+                    "The flip probability of a measurement has to be between 0 and 1.".to_string(),
+                );
+            }
+            for &target in targets.iter() {
+                self.measure(
+                    target,
+                    basis,
+                    is_recorded,
+                    is_reset,
+                    parameters.first(),
+                    line,
+                )?;
+            }
+            return Ok(());
+        }
+        if !parameters.is_empty() {
+            return error(line, format!("The gate {} takes no parameters.", name));
+        }
+
+        if Importer::single_qubit_gates(name, 0).is_some() {
+            for &target in targets.iter() {
+                let q = self.qubit(target, line)?;
+                let gates = Importer::single_qubit_gates(name, q).unwrap();
+                self.push(gates);
+            }
+            return Ok(());
+        }
+
+        let is_two_qubit_gate =
+            controlled_bases(name).is_some() || ["SWAP", "ISWAP", "ISWAP_DAG"].contains(&name);
+        if !is_two_qubit_gate {
+            return error(line, format!("Unknown instruction {}.", name));
+        }
+        if !targets.len().is_multiple_of(2) {
+            return error(line, format!("The gate {} takes pairs of qubits.", name));
+        }
+        for pair in targets.chunks(2) {
+            // A Z-controlled Pauli gate controlled by a measurement result is a conditional Pauli gate.
+            if let (Target::Record(k), Some((Basis::Z, basis))) = (pair[0], controlled_bases(name))
+            {
+                let condition = Condition::bit(self.record_clbit(k, line)?, true);
+                let q = self.qubit(pair[1], line)?;
+                let gate = match basis {
+                    Basis::X => Gate::PauliX(q),
+                    Basis::Y => Gate::PauliY(q),
+                    Basis::Z => Gate::PauliZ(q),
+                };
+                self.gates.push((gate, Some(condition)));
+                continue;
+            }
+            let a = self.qubit(pair[0], line)?;
+            let b = self.qubit(pair[1], line)?;
+            check_distinct_qubits(&[a, b]).or_else(|e| error(line, e.to_string()))?;
+            match (name, controlled_bases(name)) {
+                (_, Some((Basis::Z, Basis::X))) => self.push(vec![Gate::Cnot(a, b)]),
+                (_, Some((control_basis, target_basis))) => {
+                    // The CZ gate between the qubits rotated into the Z-basis.
+                    let mut gates = basis_gates(control_basis, a);
+                    gates.extend(basis_gates(target_basis, b));
+                    gates.push(Gate::Cz(a, b));
+                    gates.extend(inverse_basis_gates(control_basis, a));
+                    gates.extend(inverse_basis_gates(target_basis, b));
+                    self.push(gates);
+                }
+                ("SWAP", None) => self.push(vec![Gate::Swap(a, b)]),
+                // iSWAP = SWAP·CZ·(S⊗S).
+                ("ISWAP", None) => self.push(vec![
+                    Gate::S(a),
+                    Gate::S(b),
+                    Gate::Cz(a, b),
+                    Gate::Swap(a, b),
+                ]),
+                _ => self.push(vec![
+                    Gate::Rz(-PI / 2.0, a),
+                    Gate::Rz(-PI / 2.0, b),
+                    Gate::Cz(a, b),
+                    Gate::Swap(a, b),
+                ]),
+            }
+        }
+        Ok(())
+    }
+
+    fn run(&mut self, items: &[Item]) -> Result<(), ParseError> {
+        for item in items.iter() {
+            match item {
+                Item::Instruction {
+                    line,
+                    name,
+                    parameters,
+                    targets,
+                } => self.instruction(*line, name, parameters, targets)?,
+                Item::Repeat { count, body } => {
+                    for _ in 0..*count {
+                        self.run(body)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Circuit {
+    // Parse a Stim program into its noiseless circuit.
+    pub fn from_stim(source: &str) -> Result<Circuit, ParseError> {
+        Circuit::import_stim(source, false).map(|(circuit, _)| circuit)
+    }
+
+    // Parse a Stim program into its noiseless circuit and its single-qubit noise channels, each
+    // applied to its qubits after the number of the gates of the circuit.
+    pub fn from_stim_with_noise(
+        source: &str,
+    ) -> Result<(Circuit, Vec<LocatedChannel>), ParseError> {
+        Circuit::import_stim(source, true)
+            .map(|(circuit, channels)| (circuit, channels.unwrap_or_default()))
+    }
+
+    fn import_stim(
+        source: &str,
+        keeps_noise: bool,
+    ) -> Result<(Circuit, Option<Vec<LocatedChannel>>), ParseError> {
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .map(|(i, text)| {
+                let text = match text.find('#') {
+                    Some(position) => &text[..position],
+                    None => text,
+                };
+                (i + 1, text.trim().to_string())
+            })
+            .filter(|(_, text)| !text.is_empty());
+        let items = parse_items(&mut lines, false)?;
+
+        let mut importer = Importer {
+            qubit_count: 0,
+            clbit_count: 0,
+            record: Vec::new(),
+            gates: Vec::new(),
+            channels: keeps_noise.then(Vec::new),
+        };
+        importer.run(&items)?;
+
+        // The measurement record comes first, then the classical bits of the resets.
+        let mut clbit_numbers = vec![0; importer.clbit_count];
+        let mut next_reset_clbit = importer.record.len();
+        let mut recorded = vec![false; importer.clbit_count];
+        for (i, &clbit_number) in importer.record.iter().enumerate() {
+            clbit_numbers[clbit_number] = i;
+            recorded[clbit_number] = true;
+        }
+        for clbit_number in 0..importer.clbit_count {
+            if !recorded[clbit_number] {
+                clbit_numbers[clbit_number] = next_reset_clbit;
+                next_reset_clbit += 1;
+            }
+        }
+
+        let mut circuit = Circuit::with_clbits(importer.qubit_count, importer.clbit_count);
+        for (gate, condition) in importer.gates {
+            let gate = match gate {
+                Gate::Measure(q, c) => Gate::Measure(q, clbit_numbers[c]),
+                gate => gate,
+            };
+            let condition = condition.map(|condition| {
+                Condition::bit(clbit_numbers[condition.first_clbit], condition.value == 1)
+            });
+            circuit.push_with_condition(gate, condition);
+        }
+        Ok((circuit, importer.channels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn stim_program_is_imported() {
+        let source = "
+            # A Bell pair with a correction controlled by a measurement.
+            R 0 1 2
+            X_ERROR(0.01) 0 1
+            H 0
+            CNOT 0 1
+            TICK
+            M(0.001) 0 !1
+            DETECTOR rec[-1] rec[-2]
+            REPEAT 3 {
+                X 2
+            }
+            MR 2
+            CX rec[-1] 0
+            M 0
+        ";
+        let circuit = Circuit::from_stim(source).unwrap();
+        assert_eq!((circuit.qubit_count(), circuit.clbit_count()), (3, 7));
+        for seed in 0..8 {
+            let mut simulation = QuantumSimulation::new(3, seed);
+            let clbits = circuit.run(&mut simulation);
+            assert_ne!(clbits[0], clbits[1]);
+            assert!(clbits[2]);
+            assert_ne!(clbits[3], clbits[0]);
+        }
+
+        let error = Circuit::from_stim("H 0\nCX rec[-1] 0\n").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn stim_noise_is_imported() {
+        let source = "
+            H 0
+            X_ERROR(0.1) 0 1
+            DEPOLARIZE1(0.3) 1
+            PAULI_CHANNEL_1(0.1, 0.2, 0.3) 0
+            I_ERROR(0.5) 1
+            MX(0.05) 1
+        ";
+        let (circuit, channels) = Circuit::from_stim_with_noise(source).unwrap();
+        assert_eq!(circuit.gates().len(), 4);
+        assert_eq!(
+            channels,
+            vec![
+                (1, KrausChannel::bit_flip(0.1), vec![0, 1]),
+                (1, KrausChannel::pauli(0.1, 0.1, 0.1), vec![1]),
+                (1, KrausChannel::pauli(0.1, 0.2, 0.3), vec![0]),
+                (2, KrausChannel::bit_flip(0.05), vec![1]),
+            ]
+        );
+        let error = Circuit::from_stim_with_noise(&format!("{}\nDEPOLARIZE2(0.1) 0 1", source))
+            .unwrap_err();
+        assert_eq!(error.line, 9);
+        // The noiseless circuit is the same.
+        assert_eq!(Circuit::from_stim(source).unwrap().gates().len(), 4);
+
+        // A certain flip of a qubit and of a measurement.
+        let (circuit, channels) =
+            Circuit::from_stim_with_noise("X_ERROR(1) 0\nM(1) 1\nM 0").unwrap();
+        let mut simulation = QuantumSimulation::new(2, 0u64);
+        let mut values = Vec::new();
+        for (i, gate) in circuit.gates().iter().enumerate() {
+            for (_, channel, qubit_numbers) in channels.iter().filter(|channel| channel.0 == i) {
+                for &qubit_number in qubit_numbers.iter() {
+                    simulation.apply_kraus(channel.kraus_operators(), qubit_number);
+                }
+            }
+            if let &Gate::Measure(q, _) = gate {
+                values.extend(simulation.measure(vec![q]));
+            }
+        }
+        assert_eq!(values, vec![true, true]);
+
+        let error = Circuit::from_stim("X_ERROR(1.5) 0").unwrap_err();
+        assert_eq!(error.line, 1);
+    }
+}
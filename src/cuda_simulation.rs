@@ -0,0 +1,553 @@
+/*
+Quantum full state vector simulation on an NVIDIA GPU with CUDA.
+
+The amplitudes stay in the device memory as the interleaved real and imaginary parts, and the
+gates are applied by kernels compiled with NVRTC when the simulation is created. Every gate is
+a 2x2 matrix applied to a target qubit when its control qubits have the given values, so the
+CNOT, CZ, controlled phase, Toffoli, and U_f gates are the controlled Paulis and phases, and the
+SWAP gate is three CNOT gates. The consecutive 1-qubit gates on a qubit are fused into one matrix
+on the host, which is applied only before a gate on more qubits or a measurement involving the
+qubit, so a sequence of rotations costs a single pass over the state vector.
+
+The measurements are sampled on the device: a kernel sums the probabilities of the blocks of the
+state vector, the block of the random number is chosen from the sums, and only the amplitudes of
+that block are copied to the host to choose the measured state. The collapse of the state to the
+measured values is a kernel too.
+
+The CUDA driver and NVRTC libraries are loaded at run time, so the crate builds without CUDA and
+CudaSimulation::new returns an error on the computers without them.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::fmt;
+use std::sync::Arc;
+
+use cudarc::driver::{CudaFunction, CudaSlice, CudaStream, LaunchConfig, PushKernelArg};
+use num_complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::gate::{
+    matrix_product, phase_matrix, rx_matrix, ry_matrix, rz_matrix, Matrix, HADAMARD_MATRIX,
+    PAULI_X_MATRIX, PAULI_Y_MATRIX, PAULI_Z_MATRIX, S_MATRIX, T_MATRIX,
+};
+use crate::simulation::{check_qubits, Simulation};
+use crate::state_vector_simulation::MAX_QUBIT_COUNT;
+
+const KERNELS: &str = r#"
+typedef unsigned long long u64;
+
+// Apply the matrix to the pairs of amplitudes with the target bit 0 and 1,
+// where the bits of the control mask are equal to the control value.
+extern "C" __global__ void apply_matrix(
+    double2* amplitudes, u64 pair_count, u64 target_mask, u64 control_mask, u64 control_value,
+    double m00r, double m00i, double m01r, double m01i,
+    double m10r, double m10i, double m11r, double m11i)
+{
+    for (u64 k = blockIdx.x * (u64)blockDim.x + threadIdx.x; k < pair_count;
+         k += (u64)gridDim.x * blockDim.x) {
+        u64 i0 = ((k & ~(target_mask - 1)) << 1) | (k & (target_mask - 1));
+        if ((i0 & control_mask) != control_value) {
+            continue;
+        }
+        u64 i1 = i0 | target_mask;
+        double2 a0 = amplitudes[i0];
+        double2 a1 = amplitudes[i1];
+        amplitudes[i0] = make_double2(
+            m00r * a0.x - m00i * a0.y + m01r * a1.x - m01i * a1.y,
+            m00r * a0.y + m00i * a0.x + m01r * a1.y + m01i * a1.x);
+        amplitudes[i1] = make_double2(
+            m10r * a0.x - m10i * a0.y + m11r * a1.x - m11i * a1.y,
+            m10r * a0.y + m10i * a0.x + m11r * a1.y + m11i * a1.x);
+    }
+}
+
+// Sum the probabilities of the amplitudes of each block whose masked bits are equal to the value.
+extern "C" __global__ void block_probabilities(
+    const double2* amplitudes, u64 length, u64 mask, u64 value, double* sums)
+{
+    __shared__ double partial[THREAD_COUNT];
+    u64 start = (blockIdx.x * (u64)THREAD_COUNT + threadIdx.x) * AMPLITUDES_PER_THREAD;
+    double probability = 0.0;
+    for (u64 i = start; i < start + AMPLITUDES_PER_THREAD && i < length; i++) {
+        if ((i & mask) == value) {
+            double2 a = amplitudes[i];
+            probability += a.x * a.x + a.y * a.y;
+        }
+    }
+    partial[threadIdx.x] = probability;
+    __syncthreads();
+    for (int s = THREAD_COUNT / 2; s > 0; s >>= 1) {
+        if (threadIdx.x < s) {
+            partial[threadIdx.x] += partial[threadIdx.x + s];
+        }
+        __syncthreads();
+    }
+    if (threadIdx.x == 0) {
+        sums[blockIdx.x] = partial[0];
+    }
+}
+
+// Scale the amplitudes whose masked bits are equal to the value by the factor, and zero the others.
+extern "C" __global__ void collapse(
+    double2* amplitudes, u64 length, u64 mask, u64 value, double factor)
+{
+    for (u64 i = blockIdx.x * (u64)blockDim.x + threadIdx.x; i < length;
+         i += (u64)gridDim.x * blockDim.x) {
+        double2 a = amplitudes[i];
+        amplitudes[i] = (i & mask) == value
+            ? make_double2(factor * a.x, factor * a.y)
+            : make_double2(0.0, 0.0);
+    }
+}
+"#;
+
+// The threads of a block of the kernels.
+const THREAD_COUNT: usize = 256;
+// The amplitudes summed by a thread of the probability kernel.
+const AMPLITUDES_PER_THREAD: usize = 16;
+// The amplitudes of a block of the probability kernel, copied to the host by a measurement.
+const BLOCK_LENGTH: usize = THREAD_COUNT * AMPLITUDES_PER_THREAD;
+// The most blocks of the kernels looping over the amplitudes.
+const MAX_BLOCK_COUNT: usize = 1 << 16;
+
+// The error of the CUDA driver or of the compilation of the kernels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CudaError {
+    pub message: String,
+}
+
+impl fmt::Display for CudaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CudaError {}
+
+fn cuda_error<E: fmt::Debug>(error: E) -> CudaError {
+    CudaError {
+        message: format!("{:?}", error),
+    }
+}
+
+pub struct CudaSimulation {
+    qubit_count: usize,
+    stream: Arc<CudaStream>,
+    apply_matrix: CudaFunction,
+    block_probabilities: CudaFunction,
+    collapse: CudaFunction,
+    // The interleaved real and imaginary parts of the amplitudes.
+    amplitudes: CudaSlice<f64>,
+    // The fused 1-qubit gates of each qubit not yet applied to the amplitudes.
+    pending_matrices: Vec<Option<Matrix>>,
+    rng: ChaCha12Rng,
+}
+
+impl CudaSimulation {
+    // Create the simulation on the first CUDA device, compiling the kernels.
+    pub fn new(qubit_count: usize, rnd_seed: u64) -> Result<CudaSimulation, CudaError> {
+        assert!(
+            qubit_count <= MAX_QUBIT_COUNT,
+            "The number of qubits in the simulation cannot exceed {}.",
+            MAX_QUBIT_COUNT
+        );
+        // The loading of the libraries panics when they are missing.
+        let libraries_are_present = unsafe {
+            cudarc::driver::sys::is_culib_present() && cudarc::nvrtc::sys::is_culib_present()
+        };
+        if !libraries_are_present {
+            return Err(CudaError {
+                message: "The CUDA driver and NVRTC libraries are not installed.".to_string(),
+            });
+        }
+
+        let context = cudarc::driver::CudaContext::new(0).map_err(cuda_error)?;
+        let stream = context.default_stream();
+        let source = KERNELS
+            .replace("THREAD_COUNT", &THREAD_COUNT.to_string())
+            .replace("AMPLITUDES_PER_THREAD", &AMPLITUDES_PER_THREAD.to_string());
+        let ptx = cudarc::nvrtc::compile_ptx(source).map_err(cuda_error)?;
+        let module = context.load_module(ptx).map_err(cuda_error)?;
+        let function = |name| module.load_function(name).map_err(cuda_error);
+
+        let mut simulation = CudaSimulation {
+            qubit_count,
+            apply_matrix: function("apply_matrix")?,
+            block_probabilities: function("block_probabilities")?,
+            collapse: function("collapse")?,
+            amplitudes: stream
+                .alloc_zeros::<f64>(2 << qubit_count)
+                .map_err(cuda_error)?,
+            stream,
+            pending_matrices: vec![None; qubit_count],
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        Ok(simulation)
+    }
+
+    // The amplitudes of the basis states, indexed by the bits of the qubit numbers,
+    // copied from the device.
+    pub fn amplitudes(&mut self) -> Vec<Complex<f64>> {
+        self.flush_all();
+        let parts = self
+            .stream
+            .clone_dtoh(&self.amplitudes)
+            .expect("The amplitudes could not be copied from the device.");
+        parts
+            .chunks_exact(2)
+            .map(|part| Complex::new(part[0], part[1]))
+            .collect()
+    }
+
+    fn check_qubit_number(&self, qubit_number: usize) {
+        assert!(
+            qubit_number < self.qubit_count,
+            "The qubit number has to be less than the number of qubits {}.",
+            self.qubit_count
+        );
+    }
+
+    fn launch_config(thread_count: usize) -> LaunchConfig {
+        let block_count = thread_count
+            .div_ceil(THREAD_COUNT)
+            .clamp(1, MAX_BLOCK_COUNT);
+        LaunchConfig {
+            grid_dim: (block_count as u32, 1, 1),
+            block_dim: (THREAD_COUNT as u32, 1, 1),
+            shared_mem_bytes: 0,
+        }
+    }
+
+    // Apply the matrix to the target qubit where the bits of the control mask are
+    // equal to the control value.
+    fn launch_matrix(
+        &mut self,
+        matrix: &Matrix,
+        target_qubit_number: usize,
+        control_mask: u64,
+        control_value: u64,
+    ) {
+        let pair_count = 1u64 << (self.qubit_count - 1);
+        let target_mask = 1u64 << target_qubit_number;
+        let mut builder = self.stream.launch_builder(&self.apply_matrix);
+        builder
+            .arg(&mut self.amplitudes)
+            .arg(&pair_count)
+            .arg(&target_mask)
+            .arg(&control_mask)
+            .arg(&control_value);
+        for element in matrix.iter() {
+            builder.arg(&element.re).arg(&element.im);
+        }
+        unsafe { builder.launch(Self::launch_config(pair_count as usize)) }
+            .expect("The gate kernel could not be launched.");
+    }
+
+    // Fuse the 1-qubit gate with the pending gates of the qubit.
+    fn push_matrix(&mut self, matrix: Matrix, qubit_number: usize) {
+        self.check_qubit_number(qubit_number);
+        let pending_matrix = &mut self.pending_matrices[qubit_number];
+        *pending_matrix = Some(match pending_matrix {
+            Some(pending_matrix) => matrix_product(&matrix, pending_matrix),
+            None => matrix,
+        });
+    }
+
+    // Apply the pending gates of the qubit to the amplitudes.
+    fn flush(&mut self, qubit_number: usize) {
+        if let Some(matrix) = self.pending_matrices[qubit_number].take() {
+            self.launch_matrix(&matrix, qubit_number, 0, 0);
+        }
+    }
+
+    fn flush_all(&mut self) {
+        for qubit_number in 0..self.qubit_count {
+            self.flush(qubit_number);
+        }
+    }
+
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    // Apply the matrix to the target qubit when the control qubits have the given values.
+    fn apply_controlled(&mut self, matrix: &Matrix, controls: &[(usize, bool)], target: usize) {
+        let mut qubit_numbers: Vec<usize> = controls.iter().map(|&(control, _)| control).collect();
+        qubit_numbers.push(target);
+        self.check_qubit_numbers(&qubit_numbers);
+        self.flush(target);
+        let (mut control_mask, mut control_value) = (0, 0);
+        for &(control, value) in controls.iter() {
+            self.flush(control);
+            control_mask |= 1 << control;
+            if value {
+                control_value |= 1 << control;
+            }
+        }
+        self.launch_matrix(matrix, target, control_mask, control_value);
+    }
+
+    // The total probability of the amplitudes of each block whose masked bits are equal to the value.
+    fn block_probabilities(&mut self, mask: u64, value: u64) -> Vec<f64> {
+        let length = 1u64 << self.qubit_count;
+        let block_count = (length as usize).div_ceil(BLOCK_LENGTH);
+        let mut sums = self
+            .stream
+            .alloc_zeros::<f64>(block_count)
+            .expect("The probabilities could not be allocated on the device.");
+        let mut builder = self.stream.launch_builder(&self.block_probabilities);
+        builder
+            .arg(&self.amplitudes)
+            .arg(&length)
+            .arg(&mask)
+            .arg(&value)
+            .arg(&mut sums);
+        let config = LaunchConfig {
+            grid_dim: (block_count as u32, 1, 1),
+            block_dim: (THREAD_COUNT as u32, 1, 1),
+            shared_mem_bytes: 0,
+        };
+        unsafe { builder.launch(config) }.expect("The probability kernel could not be launched.");
+        self.stream
+            .clone_dtoh(&sums)
+            .expect("The probabilities could not be copied from the device.")
+    }
+
+    // Sample a basis state, copying only the sums of the blocks and the amplitudes of one block.
+    fn choose_state(&mut self) -> usize {
+        self.flush_all();
+        let sums = self.block_probabilities(0, 0);
+        let mut random_number = self.rng.gen::<f64>() * sums.iter().sum::<f64>();
+        let mut block = sums.len() - 1;
+        for (i, &sum) in sums.iter().enumerate() {
+            if random_number <= sum {
+                block = i;
+                break;
+            }
+            random_number -= sum;
+        }
+
+        let start = block * BLOCK_LENGTH;
+        let end = (start + BLOCK_LENGTH).min(1 << self.qubit_count);
+        let parts = self
+            .stream
+            .clone_dtoh(&self.amplitudes.slice(2 * start..2 * end))
+            .expect("The amplitudes could not be copied from the device.");
+        let mut state_index = end - 1;
+        for (i, part) in parts.chunks_exact(2).enumerate() {
+            let probability = part[0] * part[0] + part[1] * part[1];
+            if random_number <= probability {
+                state_index = start + i;
+                break;
+            }
+            random_number -= probability;
+        }
+        state_index
+    }
+
+    // Set the state to the basis state of the index.
+    fn set_basis_state(&mut self, state_index: usize) {
+        self.stream
+            .memset_zeros(&mut self.amplitudes)
+            .expect("The amplitudes could not be set on the device.");
+        let offset = 2 * state_index;
+        self.stream
+            .memcpy_htod(
+                &[1.0, 0.0],
+                &mut self.amplitudes.slice_mut(offset..offset + 2),
+            )
+            .expect("The amplitudes could not be set on the device.");
+    }
+
+    fn launch_collapse(&mut self, mask: u64, value: u64, factor: f64) {
+        let length = 1u64 << self.qubit_count;
+        let mut builder = self.stream.launch_builder(&self.collapse);
+        builder
+            .arg(&mut self.amplitudes)
+            .arg(&length)
+            .arg(&mask)
+            .arg(&value)
+            .arg(&factor);
+        unsafe { builder.launch(Self::launch_config(length as usize)) }
+            .expect("The collapse kernel could not be launched.");
+    }
+}
+
+impl Simulation for CudaSimulation {
+    fn reset(&mut self) {
+        self.pending_matrices = vec![None; self.qubit_count];
+        self.set_basis_state(0);
+    }
+
+    // Measure all the qubits in the Z-basis.
+    fn measure_all(&mut self) -> Vec<bool> {
+        let measured_state_index = self.choose_state();
+        self.set_basis_state(measured_state_index);
+
+        (0..self.qubit_count)
+            .map(|qubit_number| measured_state_index & (1 << qubit_number) > 0)
+            .collect()
+    }
+
+    // Measure the selected qubits in the Z-basis.
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
+        for &qubit_number in qubit_numbers.iter() {
+            self.check_qubit_number(qubit_number);
+        }
+
+        let measured_state_index = self.choose_state();
+        let measured_states: Vec<bool> = qubit_numbers
+            .iter()
+            .map(|&qubit_number| measured_state_index & (1 << qubit_number) > 0)
+            .collect();
+
+        let mask = qubit_numbers
+            .iter()
+            .fold(0u64, |mask, &qubit_number| mask | 1 << qubit_number);
+        let value = measured_state_index as u64 & mask;
+        let accumulated_probability: f64 = self.block_probabilities(mask, value).iter().sum();
+        self.launch_collapse(mask, value, (1.0 / accumulated_probability).sqrt());
+
+        measured_states
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.push_matrix(PAULI_X_MATRIX, qubit_number);
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.push_matrix(PAULI_Y_MATRIX, qubit_number);
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.push_matrix(PAULI_Z_MATRIX, qubit_number);
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.push_matrix(HADAMARD_MATRIX, qubit_number);
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.push_matrix(S_MATRIX, qubit_number);
+    }
+
+    fn t(&mut self, qubit_number: usize) {
+        self.push_matrix(T_MATRIX, qubit_number);
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.push_matrix(rx_matrix(theta), qubit_number);
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.push_matrix(ry_matrix(theta), qubit_number);
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.push_matrix(rz_matrix(theta), qubit_number);
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_controlled(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        let matrix = phase_matrix(Complex::new(-1.0, 0.0));
+        self.apply_controlled(
+            &matrix,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        let matrix = phase_matrix(Complex::new(0.0, theta).exp());
+        self.apply_controlled(
+            &matrix,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.cnot(qubit_number0, qubit_number1);
+        self.cnot(qubit_number1, qubit_number0);
+        self.cnot(qubit_number0, qubit_number1);
+    }
+
+    // U_f flips the qubit 0 when the qubit 1 is 0 if f(true), and when the qubit 1 is 1 if not f(false).
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        if f(true) {
+            self.apply_controlled(&PAULI_X_MATRIX, &[(qubit_number1, false)], qubit_number0);
+        }
+        if !f(false) {
+            self.apply_controlled(&PAULI_X_MATRIX, &[(qubit_number1, true)], qubit_number0);
+        }
+    }
+
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        self.apply_controlled(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number0, true), (control_qubit_number1, true)],
+            target_qubit_number,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn gates_match_cpu_simulation() {
+        let fused = matrix_product(
+            &HADAMARD_MATRIX,
+            &matrix_product(&PAULI_X_MATRIX, &HADAMARD_MATRIX),
+        );
+        assert!(fused
+            .iter()
+            .zip(PAULI_Z_MATRIX.iter())
+            .all(|(a, b)| (a - b).norm() < 1e-15));
+
+        // The kernels run only on the computers with a CUDA device.
+        let mut simulation = match CudaSimulation::new(3, 0) {
+            Ok(simulation) => simulation,
+            Err(_) => return,
+        };
+        let mut cpu_simulation = QuantumSimulation::new(3, 0);
+        let gates: [fn(&mut dyn Simulation); 6] = [
+            |s| s.hadamard(0),
+            |s| s.ry(0.7, 2),
+            |s| s.cnot(0, 1),
+            |s| s.cphase(0.9, 1, 2),
+            |s| s.apply_u_f(|x| !x, 2, 0),
+            |s| s.toffoli(0, 2, 1),
+        ];
+        for gate in gates.iter() {
+            gate(&mut simulation);
+            gate(&mut cpu_simulation);
+        }
+        assert!(simulation
+            .amplitudes()
+            .iter()
+            .zip(cpu_simulation.amplitudes().iter())
+            .all(|(a, b)| (a - b).norm() < 1e-12));
+    }
+}
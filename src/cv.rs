@@ -0,0 +1,284 @@
+/*
+Gaussian simulation of continuous-variable modes, such as the modes of light.
+
+A Gaussian state of n modes is given by the means of the quadratures x_k = (a_k + a_k†)/√2 and
+p_k = -i (a_k - a_k†)/√2, ordered x_0, p_0, x_1, p_1, ..., and by their 2n × 2n covariance
+matrix, in the units where the vacuum has the covariance matrix I/2. The Gaussian gates are the
+displacements, which shift the means, and the symplectic maps S of the rotations, squeezing, and
+beam splitters, which map the means r to S r and the covariance matrix V to S V S^T, so a state
+takes O(n^2) memory instead of a truncated Fock space for each mode. The homodyne measurement of
+a quadrature samples its value from the Gaussian distribution and conditions the other modes
+on it.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+// The variance of the quadratures of the vacuum.
+pub const VACUUM_VARIANCE: f64 = 0.5;
+
+#[derive(Debug)]
+pub struct GaussianSimulation {
+    mode_count: usize,
+    // The means of the quadratures x_0, p_0, x_1, p_1, ...
+    means: Vec<f64>,
+    // The covariance matrix of the quadratures, row by row.
+    covariance: Vec<f64>,
+    rng: ChaCha12Rng,
+}
+
+impl GaussianSimulation {
+    pub fn new(mode_count: usize, rnd_seed: u64) -> GaussianSimulation {
+        let size = 2 * mode_count;
+        let mut simulation = GaussianSimulation {
+            mode_count,
+            means: vec![0.0; size],
+            covariance: vec![0.0; size * size],
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    pub fn mode_count(&self) -> usize {
+        self.mode_count
+    }
+
+    pub fn means(&self) -> &[f64] {
+        &self.means
+    }
+
+    pub fn covariance(&self) -> &[f64] {
+        &self.covariance
+    }
+
+    // Set all the modes to the vacuum.
+    pub fn reset(&mut self) {
+        self.means.fill(0.0);
+        self.covariance.fill(0.0);
+        for i in 0..2 * self.mode_count {
+            self.covariance[i * 2 * self.mode_count + i] = VACUUM_VARIANCE;
+        }
+    }
+
+    fn check_mode_number(&self, mode_number: usize) {
+        assert!(
+            mode_number < self.mode_count,
+            "The mode number has to be less than the number of modes {}.",
+            self.mode_count
+        );
+    }
+
+    // The mean number of photons in the mode, (⟨x^2⟩ + ⟨p^2⟩ - 1) / 2.
+    pub fn mean_photon_number(&self, mode_number: usize) -> f64 {
+        self.check_mode_number(mode_number);
+        let size = 2 * self.mode_count;
+        let (x, p) = (2 * mode_number, 2 * mode_number + 1);
+        (self.covariance[x * size + x]
+            + self.covariance[p * size + p]
+            + self.means[x] * self.means[x]
+            + self.means[p] * self.means[p]
+            - 1.0)
+            / 2.0
+    }
+
+    // Apply the k × k symplectic matrix, given row by row, to the k quadratures of the indices.
+    fn apply_symplectic(&mut self, matrix: &[f64], indices: &[usize]) {
+        let size = 2 * self.mode_count;
+        let k = indices.len();
+        let means: Vec<f64> = indices.iter().map(|&i| self.means[i]).collect();
+        for (row, &i) in matrix.chunks_exact(k).zip(indices.iter()) {
+            self.means[i] = row.iter().zip(means.iter()).map(|(s, r)| s * r).sum();
+        }
+
+        // The rows of S V, then the columns of (S V) S^T.
+        let mut values = vec![0.0; k];
+        for column in 0..size {
+            for (value, &i) in values.iter_mut().zip(indices.iter()) {
+                *value = self.covariance[i * size + column];
+            }
+            for (row, &i) in matrix.chunks_exact(k).zip(indices.iter()) {
+                self.covariance[i * size + column] =
+                    row.iter().zip(values.iter()).map(|(s, v)| s * v).sum();
+            }
+        }
+        for row_index in 0..size {
+            for (value, &i) in values.iter_mut().zip(indices.iter()) {
+                *value = self.covariance[row_index * size + i];
+            }
+            for (row, &i) in matrix.chunks_exact(k).zip(indices.iter()) {
+                self.covariance[row_index * size + i] =
+                    row.iter().zip(values.iter()).map(|(s, v)| s * v).sum();
+            }
+        }
+    }
+
+    // The displacement by α = r e^{iφ}, shifting x by √2 r cos φ and p by √2 r sin φ.
+    pub fn displace(&mut self, r: f64, phi: f64, mode_number: usize) {
+        self.check_mode_number(mode_number);
+        self.means[2 * mode_number] += std::f64::consts::SQRT_2 * r * phi.cos();
+        self.means[2 * mode_number + 1] += std::f64::consts::SQRT_2 * r * phi.sin();
+    }
+
+    // The phase shift a → e^{iθ} a, rotating the quadratures by the angle theta.
+    pub fn rotate(&mut self, theta: f64, mode_number: usize) {
+        self.check_mode_number(mode_number);
+        let (cos, sin) = (theta.cos(), theta.sin());
+        self.apply_symplectic(
+            &[cos, -sin, sin, cos],
+            &[2 * mode_number, 2 * mode_number + 1],
+        );
+    }
+
+    // The squeezing by r along the angle phi / 2, which scales x by e^{-r} and p by e^r for
+    // the angle 0.
+    pub fn squeeze(&mut self, r: f64, phi: f64, mode_number: usize) {
+        self.check_mode_number(mode_number);
+        let (cosh, sinh) = (r.cosh(), r.sinh());
+        let (cos, sin) = (phi.cos(), phi.sin());
+        self.apply_symplectic(
+            &[
+                cosh - sinh * cos,
+                -sinh * sin,
+                -sinh * sin,
+                cosh + sinh * cos,
+            ],
+            &[2 * mode_number, 2 * mode_number + 1],
+        );
+    }
+
+    // The beam splitter a_0 → t a_0 - r e^{-iφ} a_1, a_1 → r e^{iφ} a_0 + t a_1, with the
+    // transmission t = cos θ and the reflection r = sin θ, so that θ = π/4 is 50:50.
+    pub fn beam_splitter(
+        &mut self,
+        theta: f64,
+        phi: f64,
+        mode_number0: usize,
+        mode_number1: usize,
+    ) {
+        self.check_mode_number(mode_number0);
+        self.check_mode_number(mode_number1);
+        assert!(
+            mode_number0 != mode_number1,
+            "The mode arguments of a gate have to be distinct."
+        );
+        let (t, r) = (theta.cos(), theta.sin());
+        let (cos, sin) = (phi.cos(), phi.sin());
+        // The quadratures in the order x_0, x_1, p_0, p_1.
+        self.apply_symplectic(
+            &[
+                t,
+                -r * cos,
+                0.0,
+                -r * sin, //
+                r * cos,
+                t,
+                -r * sin,
+                0.0, //
+                0.0,
+                r * sin,
+                t,
+                -r * cos, //
+                r * sin,
+                0.0,
+                r * cos,
+                t,
+            ],
+            &[
+                2 * mode_number0,
+                2 * mode_number1,
+                2 * mode_number0 + 1,
+                2 * mode_number1 + 1,
+            ],
+        );
+    }
+
+    // A sample of the standard normal distribution by the Box–Muller transform.
+    fn standard_normal(&mut self) -> f64 {
+        let u0 = 1.0 - self.rng.gen::<f64>();
+        let u1 = self.rng.gen::<f64>();
+        (-2.0 * u0.ln()).sqrt() * (std::f64::consts::TAU * u1).cos()
+    }
+
+    // Measure the quadrature x cos φ + p sin φ of the mode, conditioning the other modes on the
+    // measured value. The measured mode is left in the vacuum.
+    pub fn homodyne(&mut self, phi: f64, mode_number: usize) -> f64 {
+        self.rotate(-phi, mode_number);
+        let size = 2 * self.mode_count;
+        let x = 2 * mode_number;
+        let variance = self.covariance[x * size + x];
+        let value = self.means[x] + variance.sqrt() * self.standard_normal();
+
+        // The Gaussian conditional distribution of the other quadratures, with the means
+        // r + V_x (value - r_x) / V_xx and the covariance V - V_x V_x^T / V_xx.
+        let column: Vec<f64> = (0..size).map(|i| self.covariance[i * size + x]).collect();
+        let shift = (value - self.means[x]) / variance;
+        for i in 0..size {
+            self.means[i] += column[i] * shift;
+            for j in 0..size {
+                self.covariance[i * size + j] -= column[i] * column[j] / variance;
+            }
+        }
+
+        for quadrature in [x, x + 1] {
+            self.means[quadrature] = 0.0;
+            for i in 0..size {
+                self.covariance[quadrature * size + i] = 0.0;
+                self.covariance[i * size + quadrature] = 0.0;
+            }
+            self.covariance[quadrature * size + quadrature] = VACUUM_VARIANCE;
+        }
+
+        value
+    }
+
+    // Measure the x quadratures of all the modes.
+    pub fn homodyne_all(&mut self) -> Vec<f64> {
+        (0..self.mode_count)
+            .map(|mode_number| self.homodyne(0.0, mode_number))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squeezed_light_on_beam_splitter() {
+        let mut simulation = GaussianSimulation::new(2, 3);
+        let r: f64 = 1.0;
+        simulation.squeeze(r, 0.0, 0);
+        assert!((simulation.mean_photon_number(0) - r.sinh().powi(2)).abs() < 1e-12);
+
+        // A 50:50 beam splitter shares the photons equally, and its inverse undoes it.
+        simulation.beam_splitter(std::f64::consts::FRAC_PI_4, 0.0, 0, 1);
+        for mode_number in 0..2 {
+            let photons = simulation.mean_photon_number(mode_number);
+            assert!((photons - r.sinh().powi(2) / 2.0).abs() < 1e-12);
+        }
+        simulation.beam_splitter(-std::f64::consts::FRAC_PI_4, 0.0, 0, 1);
+        assert!((simulation.covariance()[0] - 0.5 * (-2.0 * r).exp()).abs() < 1e-12);
+        assert!(simulation.mean_photon_number(1).abs() < 1e-12);
+
+        // The strongly squeezed x quadrature of a displaced mode is measured at its mean.
+        simulation.reset();
+        simulation.squeeze(6.0, 0.0, 0);
+        simulation.displace(2.0, 0.0, 0);
+        let value = simulation.homodyne(0.0, 0);
+        assert!((value - 2.0 * std::f64::consts::SQRT_2).abs() < 1e-2);
+
+        // Split on a beam splitter, the sum of the x quadratures of the modes is squeezed, so
+        // the measurement of one of them fixes the other.
+        simulation.squeeze(6.0, 0.0, 0);
+        simulation.displace(2.0, 0.0, 0);
+        simulation.beam_splitter(std::f64::consts::FRAC_PI_4, 0.0, 0, 1);
+        let value = simulation.homodyne(0.0, 0);
+        let other_value = simulation.homodyne(0.0, 1);
+        assert!((value + other_value - 4.0).abs() < 1e-2);
+        assert_eq!(simulation.means(), &[0.0; 4]);
+    }
+}
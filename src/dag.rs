@@ -0,0 +1,243 @@
+/*
+Dependency DAG of a circuit with commutation analysis.
+
+Two gates commute when, on every qubit they share, both act diagonally in the Z-basis
+(like Z, S, T, Rz, and the control qubits) or both act in the X-basis (like X, Rx,
+and the target qubits of CNOT and Toffoli). Each gate depends on the earlier gates
+which it does not commute with, so any topological order of the DAG is a circuit
+equal to the original one. A measurement also depends on the earlier gates reading or writing
+its classical bit, and a gate with a classical condition on the earlier measurements into its bits.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::circuit::{Circuit, Condition, Gate};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    Diagonal,
+    XBasis,
+    General,
+}
+
+// How the gate acts on each of its qubits, in the order of the gate arguments.
+fn actions(gate: &Gate) -> Vec<Action> {
+    match gate {
+        Gate::PauliZ(_) | Gate::S(_) | Gate::T(_) | Gate::Rz(_, _) => vec![Action::Diagonal],
+        Gate::PauliX(_) | Gate::Rx(_, _) => vec![Action::XBasis],
+        Gate::PauliY(_) | Gate::Hadamard(_) | Gate::Ry(_, _) => vec![Action::General],
+        Gate::Cnot(_, _) => vec![Action::Diagonal, Action::XBasis],
+        // U_f flips the first qubit depending on the second one.
+        Gate::UF(_, _, _) => vec![Action::XBasis, Action::Diagonal],
+        Gate::Cz(_, _) | Gate::Cphase(_, _, _) => vec![Action::Diagonal, Action::Diagonal],
+        Gate::Swap(_, _) => vec![Action::General, Action::General],
+        Gate::Toffoli(_, _, _) => vec![Action::Diagonal, Action::Diagonal, Action::XBasis],
+        Gate::Measure(_, _) => vec![Action::Diagonal],
+    }
+}
+
+pub fn gates_commute(gate0: &Gate, gate1: &Gate) -> bool {
+    let actions1 = actions(gate1);
+    gate0
+        .qubits()
+        .into_iter()
+        .zip(actions(gate0))
+        .all(|(qubit_number, action0)| {
+            gate1
+                .qubits()
+                .into_iter()
+                .zip(actions1.iter())
+                .filter(|&(q, _)| q == qubit_number)
+                .all(|(_, &action1)| action0 == action1 && action0 != Action::General)
+        })
+}
+
+// Whether a gate measuring into a classical bit conflicts with a gate accessing the same classical bit.
+fn clbits_conflict(
+    gate0: &Gate,
+    condition0: &Option<Condition>,
+    gate1: &Gate,
+    condition1: &Option<Condition>,
+) -> bool {
+    let writes = |gate: &Gate, clbit_number: usize| matches!(*gate, Gate::Measure(_, c) if c == clbit_number);
+    let reads = |condition: &Option<Condition>, clbit_number: usize| {
+        condition.is_some_and(|condition| condition.clbits().contains(&clbit_number))
+    };
+    let conflicts_with_write =
+        |writer: &Gate, other: &Gate, other_condition: &Option<Condition>| match *writer {
+            Gate::Measure(_, c) => writes(other, c) || reads(other_condition, c),
+            _ => false,
+        };
+    conflicts_with_write(gate0, gate1, condition1) || conflicts_with_write(gate1, gate0, condition0)
+}
+
+fn nodes_commute(
+    gates: &[Gate],
+    conditions: &[Option<Condition>],
+    node0: usize,
+    node1: usize,
+) -> bool {
+    gates_commute(&gates[node0], &gates[node1])
+        && !clbits_conflict(
+            &gates[node0],
+            &conditions[node0],
+            &gates[node1],
+            &conditions[node1],
+        )
+}
+
+#[derive(Clone, Debug)]
+pub struct CircuitDag {
+    qubit_count: usize,
+    clbit_count: usize,
+    gates: Vec<Gate>,
+    conditions: Vec<Option<Condition>>,
+    predecessors: Vec<Vec<usize>>,
+    successors: Vec<Vec<usize>>,
+}
+
+impl CircuitDag {
+    pub fn new(circuit: &Circuit) -> CircuitDag {
+        let gates: Vec<Gate> = circuit.gates().to_vec();
+        let conditions: Vec<Option<Condition>> = circuit.conditions().to_vec();
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); gates.len()];
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); gates.len()];
+        // The gates acting on each qubit so far, followed by the gates accessing each classical bit.
+        let mut wire_gates: Vec<Vec<usize>> =
+            vec![Vec::new(); circuit.qubit_count() + circuit.clbit_count()];
+
+        for (index, gate) in gates.iter().enumerate() {
+            let mut wires = gate.qubits();
+            if let Gate::Measure(_, clbit_number) = *gate {
+                wires.push(circuit.qubit_count() + clbit_number);
+            }
+            if let Some(condition) = conditions[index] {
+                wires.extend(condition.clbits().map(|c| circuit.qubit_count() + c));
+            }
+            for wire in wires {
+                for &earlier in wire_gates[wire].iter() {
+                    if !predecessors[index].contains(&earlier)
+                        && !nodes_commute(&gates, &conditions, earlier, index)
+                    {
+                        predecessors[index].push(earlier);
+                        successors[earlier].push(index);
+                    }
+                }
+                wire_gates[wire].push(index);
+            }
+        }
+
+        CircuitDag {
+            qubit_count: circuit.qubit_count(),
+            clbit_count: circuit.clbit_count(),
+            gates,
+            conditions,
+            predecessors,
+            successors,
+        }
+    }
+
+    // The number of the gates; gates are the nodes numbered in the original circuit order.
+    pub fn node_count(&self) -> usize {
+        self.gates.len()
+    }
+
+    pub fn gate(&self, node: usize) -> &Gate {
+        &self.gates[node]
+    }
+
+    // The earlier gates which have to be applied before the gate.
+    pub fn predecessors(&self, node: usize) -> &[usize] {
+        &self.predecessors[node]
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.successors[node]
+    }
+
+    pub fn commute(&self, node0: usize, node1: usize) -> bool {
+        nodes_commute(&self.gates, &self.conditions, node0, node1)
+    }
+
+    pub fn condition(&self, node: usize) -> Option<Condition> {
+        self.conditions[node]
+    }
+
+    // The gates without any dependencies which can be applied first.
+    pub fn front_layer(&self) -> Vec<usize> {
+        (0..self.node_count())
+            .filter(|&node| self.predecessors[node].is_empty())
+            .collect()
+    }
+
+    // Partition of the gates into layers where each gate depends only on the gates in the earlier layers.
+    // The gates within a layer commute with each other.
+    pub fn layers(&self) -> Vec<Vec<usize>> {
+        let mut node_layers = vec![0usize; self.node_count()];
+        let mut layers: Vec<Vec<usize>> = Vec::new();
+        // Predecessors always have smaller node numbers.
+        for node in 0..self.node_count() {
+            let layer = self.predecessors[node]
+                .iter()
+                .map(|&p| node_layers[p] + 1)
+                .max()
+                .unwrap_or(0);
+            node_layers[node] = layer;
+            if layer == layers.len() {
+                layers.push(Vec::new());
+            }
+            layers[layer].push(node);
+        }
+        layers
+    }
+
+    // Re-linearize the DAG into a circuit, layer by layer.
+    pub fn to_circuit(&self) -> Circuit {
+        let mut circuit = Circuit::with_clbits(self.qubit_count, self.clbit_count);
+        for layer in self.layers() {
+            for node in layer {
+                circuit.push_with_condition(self.gates[node], self.conditions[node]);
+            }
+        }
+        circuit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn dag_relinearization_preserves_circuit() {
+        let mut circuit = Circuit::new(3);
+        circuit.hadamard(0);
+        circuit.cnot(0, 1);
+        circuit.t(0);
+        circuit.cz(0, 2);
+        circuit.pauli_x(1);
+        circuit.cnot(2, 1);
+        circuit.hadamard(1);
+
+        let dag = circuit.to_dag();
+        assert!(dag.commute(2, 3));
+        assert!(dag.commute(1, 2));
+        assert!(dag.commute(4, 5));
+        assert!(!dag.commute(0, 1));
+        assert!(!dag.commute(5, 6));
+        assert_eq!(dag.front_layer(), vec![0, 4, 5]);
+        assert_eq!(dag.predecessors(3), &[0]);
+
+        let relinearized = dag.to_circuit();
+        assert_eq!(relinearized.gates().len(), circuit.gates().len());
+        for seed in 0..8u64 {
+            let mut simulation = QuantumSimulation::new(3, seed);
+            circuit.run(&mut simulation);
+            let expected = simulation.measure_all();
+            let mut simulation = QuantumSimulation::new(3, seed);
+            relinearized.run(&mut simulation);
+            assert_eq!(simulation.measure_all(), expected);
+        }
+    }
+}
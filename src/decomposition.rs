@@ -0,0 +1,429 @@
+/*
+Decomposition of two-qubit unitaries into CNOT and single-qubit rotation gates.
+
+The Cartan (KAK) decomposition writes any two-qubit unitary up to a global phase as
+(A1 ⊗ B1)·exp(i(c1·XX + c2·YY + c3·ZZ))·(A2 ⊗ B2). In the magic basis the local
+unitaries become real orthogonal matrices and the nonlocal part becomes diagonal,
+so the decomposition reduces to diagonalizing a symmetric unitary matrix by a real
+orthogonal one. The nonlocal part is then built with three CNOT gates.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+
+use num_complex::Complex;
+
+use crate::circuit::Circuit;
+
+// A two-qubit unitary matrix indexed by q0 + 2·q1 like the amplitudes of the state vector.
+pub type Matrix4 = [[Complex<f64>; 4]; 4];
+// A single-qubit unitary matrix.
+pub type Matrix2 = [[Complex<f64>; 2]; 2];
+
+// Tolerance of the numerical checks and of the angles treated as zero.
+const TOLERANCE: f64 = 1e-9;
+const ZERO: Complex<f64> = Complex::new(0.0, 0.0);
+
+// Coefficients of the random combinations of the real and imaginary parts to diagonalize simultaneously.
+const MIXING_COEFFICIENTS: [f64; 4] = [0.5833, 1.3247, 2.2361, 0.3];
+
+fn multiply(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut product = [[ZERO; 4]; 4];
+    for (i, row) in product.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            *entry = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    product
+}
+
+fn adjoint(a: &Matrix4) -> Matrix4 {
+    let mut result = [[ZERO; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            *entry = a[j][i].conj();
+        }
+    }
+    result
+}
+
+fn transpose(a: &Matrix4) -> Matrix4 {
+    let mut result = [[ZERO; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            *entry = a[j][i];
+        }
+    }
+    result
+}
+
+// Determinant by the Gaussian elimination with partial pivoting.
+fn determinant(a: &Matrix4) -> Complex<f64> {
+    let mut m = *a;
+    let mut det = Complex::new(1.0, 0.0);
+    for column in 0..4 {
+        let pivot = (column..4)
+            .max_by(|&i, &j| m[i][column].norm().total_cmp(&m[j][column].norm()))
+            .unwrap();
+        if m[pivot][column].norm() == 0.0 {
+            return ZERO;
+        }
+        if pivot != column {
+            m.swap(pivot, column);
+            det = -det;
+        }
+        det *= m[column][column];
+        for row in (column + 1)..4 {
+            let factor = m[row][column] / m[column][column];
+            let pivot_row = m[column];
+            for (entry, value) in m[row].iter_mut().zip(pivot_row.iter()).skip(column) {
+                *entry -= factor * value;
+            }
+        }
+    }
+    det
+}
+
+// Columns are the magic basis states (|00⟩ + |11⟩)/√2, i(|01⟩ + |10⟩)/√2, (|01⟩ − |10⟩)/√2, i(|00⟩ − |11⟩)/√2.
+fn magic_basis() -> Matrix4 {
+    let r = Complex::new(FRAC_1_SQRT_2, 0.0);
+    let i = Complex::new(0.0, FRAC_1_SQRT_2);
+    [
+        [r, ZERO, ZERO, i],
+        [ZERO, i, r, ZERO],
+        [ZERO, i, -r, ZERO],
+        [r, ZERO, ZERO, -i],
+    ]
+}
+
+// Eigenvectors of a real symmetric matrix as the columns of an orthogonal matrix by the cyclic Jacobi method.
+fn symmetric_eigenvectors(matrix: [[f64; 4]; 4]) -> [[f64; 4]; 4] {
+    let mut a = matrix;
+    let mut v = [[0.0; 4]; 4];
+    for (i, row) in v.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+
+    for _ in 0..100 {
+        let off_diagonal: f64 = (0..4)
+            .flat_map(|i| (0..4).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum();
+        if off_diagonal < 1e-30 {
+            break;
+        }
+        for p in 0..4 {
+            for q in (p + 1)..4 {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = c * x - s * y;
+                    row[q] = s * x + c * y;
+                }
+                let (row_p, row_q) = (a[p], a[q]);
+                for k in 0..4 {
+                    a[p][k] = c * row_p[k] - s * row_q[k];
+                    a[q][k] = s * row_p[k] + c * row_q[k];
+                }
+                for row in v.iter_mut() {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = c * x - s * y;
+                    row[q] = s * x + c * y;
+                }
+            }
+        }
+    }
+    v
+}
+
+// Real orthogonal matrix with determinant 1 diagonalizing the complex symmetric unitary matrix.
+// The real and imaginary parts commute, so they are diagonalized together through their combination.
+fn diagonalize_symmetric_unitary(w: &Matrix4) -> [[f64; 4]; 4] {
+    for &coefficient in MIXING_COEFFICIENTS.iter() {
+        let mut combination = [[0.0; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                combination[i][j] = w[i][j].re + coefficient * w[i][j].im;
+            }
+        }
+        let mut p = symmetric_eigenvectors(combination);
+        if determinant(&to_complex(&p)).re < 0.0 {
+            for row in p.iter_mut() {
+                row[0] = -row[0];
+            }
+        }
+
+        let p_complex = to_complex(&p);
+        let d = multiply(&multiply(&transpose(&p_complex), w), &p_complex);
+        let is_diagonal = (0..4).all(|i| (0..4).all(|j| i == j || d[i][j].norm() < TOLERANCE));
+        if is_diagonal {
+            return p;
+        }
+    }
+    panic!("The matrix could not be diagonalized.");
+}
+
+fn to_complex(a: &[[f64; 4]; 4]) -> Matrix4 {
+    let mut result = [[ZERO; 4]; 4];
+    for (i, row) in result.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            *entry = Complex::new(a[i][j], 0.0);
+        }
+    }
+    result
+}
+
+// Split the tensor product a ⊗ b, where a acts on the qubit 1 and b acts on the qubit 0.
+fn kronecker_factors(k: &Matrix4) -> (Matrix2, Matrix2) {
+    let block = |i: usize, j: usize| -> Matrix2 {
+        [
+            [k[2 * i][2 * j], k[2 * i][2 * j + 1]],
+            [k[2 * i + 1][2 * j], k[2 * i + 1][2 * j + 1]],
+        ]
+    };
+    let norm = |m: &Matrix2| m.iter().flatten().map(|x| x.norm_sqr()).sum::<f64>();
+
+    let (i, j) = (0..2)
+        .flat_map(|i| (0..2).map(move |j| (i, j)))
+        .max_by(|&(i0, j0), &(i1, j1)| norm(&block(i0, j0)).total_cmp(&norm(&block(i1, j1))))
+        .unwrap();
+    let largest = block(i, j);
+    let det = largest[0][0] * largest[1][1] - largest[0][1] * largest[1][0];
+    let scale = det.sqrt();
+    let b = [
+        [largest[0][0] / scale, largest[0][1] / scale],
+        [largest[1][0] / scale, largest[1][1] / scale],
+    ];
+
+    let mut a = [[ZERO; 2]; 2];
+    for (i, row) in a.iter_mut().enumerate() {
+        for (j, entry) in row.iter_mut().enumerate() {
+            let m = block(i, j);
+            // Tr(b†·m) / 2 with b unitary.
+            *entry = (0..2)
+                .flat_map(|r| (0..2).map(move |c| (r, c)))
+                .map(|(r, c)| b[r][c].conj() * m[r][c])
+                .sum::<Complex<f64>>()
+                / 2.0;
+        }
+    }
+    (a, b)
+}
+
+fn push_rotation(
+    circuit: &mut Circuit,
+    gate: fn(&mut Circuit, f64, usize),
+    theta: f64,
+    qubit_number: usize,
+) {
+    let theta = theta.rem_euclid(4.0 * PI);
+    if theta.abs() > TOLERANCE && (4.0 * PI - theta).abs() > TOLERANCE {
+        gate(circuit, theta, qubit_number);
+    }
+}
+
+// Emit the single-qubit unitary with the Euler angles u = e^{iα}·Rz(φ)·Ry(θ)·Rz(λ).
+fn push_single_qubit(circuit: &mut Circuit, u: &Matrix2, qubit_number: usize) {
+    let theta = 2.0 * u[1][0].norm().atan2(u[0][0].norm());
+    let sum = if u[0][0].norm() > TOLERANCE {
+        u[1][1].arg() - u[0][0].arg()
+    } else {
+        0.0
+    };
+    let difference = if u[1][0].norm() > TOLERANCE {
+        u[1][0].arg() - (-u[0][1]).arg()
+    } else {
+        0.0
+    };
+    let phi = (sum + difference) / 2.0;
+    let lambda = (sum - difference) / 2.0;
+
+    push_rotation(circuit, Circuit::rz, lambda, qubit_number);
+    push_rotation(circuit, Circuit::ry, theta, qubit_number);
+    push_rotation(circuit, Circuit::rz, phi, qubit_number);
+}
+
+// Emit exp(i(c1·XX + c2·YY + c3·ZZ)) on the qubits 1 and 0 with three CNOT gates.
+// Conjugating by CNOT(1, 0) maps it to exp(i·c1·X1)·exp(i·c3·Z0)·exp(-i·c2·X1·Z0), and
+// exp(-i·c2·X1·Z0) is conjugated exp(-i·c2·X1) by CZ(1, 0). The first CZ merges with
+// the first CNOT into a controlled-Y gate up to an S gate on the control qubit.
+fn push_canonical(circuit: &mut Circuit, c1: f64, c2: f64, c3: f64) {
+    if c1.abs() < TOLERANCE && c2.abs() < TOLERANCE && c3.abs() < TOLERANCE {
+        return;
+    }
+    circuit.pauli_z(0);
+    circuit.s(0);
+    circuit.cnot(1, 0);
+    circuit.s(0);
+    circuit.s(1);
+    push_rotation(circuit, Circuit::rx, 2.0 * c2, 1);
+    circuit.hadamard(0);
+    circuit.cnot(1, 0);
+    circuit.hadamard(0);
+    push_rotation(circuit, Circuit::rx, -2.0 * c1, 1);
+    push_rotation(circuit, Circuit::rz, -2.0 * c3, 0);
+    circuit.cnot(1, 0);
+}
+
+// Decompose the single-qubit unitary matrix into at most 3 rotations, equal to the matrix up to a global phase.
+pub fn decompose_single_qubit(matrix: &Matrix2) -> Circuit {
+    let unitarity = [
+        [
+            matrix[0][0].norm_sqr() + matrix[1][0].norm_sqr(),
+            (matrix[0][0].conj() * matrix[0][1] + matrix[1][0].conj() * matrix[1][1]).norm(),
+        ],
+        [0.0, matrix[0][1].norm_sqr() + matrix[1][1].norm_sqr()],
+    ];
+    assert!(
+        (unitarity[0][0] - 1.0).abs() < 1e-6
+            && unitarity[0][1] < 1e-6
+            && (unitarity[1][1] - 1.0).abs() < 1e-6,
+        "The matrix has to be unitary."
+    );
+    let mut circuit = Circuit::new(1);
+    push_single_qubit(&mut circuit, matrix, 0);
+    circuit
+}
+
+// Decompose the two-qubit unitary matrix, indexed by q0 + 2·q1, into a circuit
+// with at most 3 CNOT gates and single-qubit rotations. The circuit is equal to
+// the matrix up to a global phase.
+pub fn decompose_two_qubit(matrix: &Matrix4) -> Circuit {
+    let unitarity = multiply(&adjoint(matrix), matrix);
+    for (i, row) in unitarity.iter().enumerate() {
+        for (j, entry) in row.iter().enumerate() {
+            let expected = if i == j { 1.0 } else { 0.0 };
+            assert!(
+                (entry - expected).norm() < 1e-6,
+                "The matrix has to be unitary."
+            );
+        }
+    }
+
+    // The unitary with the determinant 1 is expressed in the magic basis.
+    let det = determinant(matrix);
+    let phase = Complex::from_polar(1.0, -det.arg() / 4.0);
+    let mut u = *matrix;
+    for entry in u.iter_mut().flatten() {
+        *entry *= phase;
+    }
+    let magic = magic_basis();
+    let v = multiply(&multiply(&adjoint(&magic), &u), &magic);
+
+    // v = O1·A·O2 with O1 and O2 real orthogonal and A diagonal, so vᵀ·v = O2ᵀ·A²·O2.
+    let p = to_complex(&diagonalize_symmetric_unitary(&multiply(
+        &transpose(&v),
+        &v,
+    )));
+    let d = multiply(&multiply(&transpose(&p), &multiply(&transpose(&v), &v)), &p);
+    let mut angles: Vec<f64> = (0..4).map(|i| d[i][i].arg() / 2.0).collect();
+    // The square roots are chosen so that the determinant of A is 1.
+    if angles.iter().sum::<f64>().cos() < 0.0 {
+        angles[0] += PI;
+    }
+    let mut a_inverse = [[ZERO; 4]; 4];
+    for i in 0..4 {
+        a_inverse[i][i] = Complex::from_polar(1.0, -angles[i]);
+    }
+    let o1 = multiply(&multiply(&v, &p), &a_inverse);
+    let o2 = transpose(&p);
+
+    let k1 = multiply(&multiply(&magic, &o1), &adjoint(&magic));
+    let k2 = multiply(&multiply(&magic, &o2), &adjoint(&magic));
+    let (a1, b1) = kronecker_factors(&k1);
+    let (a2, b2) = kronecker_factors(&k2);
+
+    // The eigenvalues of XX, YY, and ZZ on the magic basis states give the phases
+    // (c1 − c2 + c3, c1 + c2 − c3, −c1 − c2 − c3, −c1 + c2 + c3).
+    let c1 = (angles[0] + angles[1]) / 2.0;
+    let c2 = (angles[1] + angles[3]) / 2.0;
+    let c3 = (angles[0] + angles[3]) / 2.0;
+
+    let mut circuit = Circuit::new(2);
+    push_single_qubit(&mut circuit, &b2, 0);
+    push_single_qubit(&mut circuit, &a2, 1);
+    push_canonical(&mut circuit, c1, c2, c3);
+    push_single_qubit(&mut circuit, &b1, 0);
+    push_single_qubit(&mut circuit, &a1, 1);
+    circuit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    fn unitary(circuit: &Circuit) -> Matrix4 {
+        let mut columns = [[ZERO; 4]; 4];
+        let mut simulation = QuantumSimulation::new(2, 0u64);
+        for (column, amplitudes) in columns.iter_mut().enumerate() {
+            simulation.reset();
+            for qubit_number in 0..2 {
+                if (column >> qubit_number) & 1 == 1 {
+                    simulation.pauli_x(qubit_number);
+                }
+            }
+            circuit.run(&mut simulation);
+            amplitudes.copy_from_slice(&simulation.amplitudes());
+        }
+        transpose(&columns)
+    }
+
+    // |Tr(a†·b)| / 4 is 1 exactly when the unitaries are equal up to a global phase.
+    fn overlap(a: &Matrix4, b: &Matrix4) -> f64 {
+        let product = multiply(&adjoint(a), b);
+        (0..4).map(|i| product[i][i]).sum::<Complex<f64>>().norm() / 4.0
+    }
+
+    #[test]
+    fn decomposition_reproduces_unitaries() {
+        let mut circuits = Vec::new();
+        let mut identity = Circuit::new(2);
+        identity.t(0);
+        circuits.push(identity);
+        let mut swap = Circuit::new(2);
+        swap.swap(0, 1);
+        circuits.push(swap);
+        let mut cz = Circuit::new(2);
+        cz.cz(0, 1);
+        circuits.push(cz);
+        for seed in 0..20 {
+            let angle = |k: usize| ((seed * 7 + k * 13) as f64 * 0.731).sin() * 3.0;
+            let mut random = Circuit::new(2);
+            for layer in 0..3 {
+                random.rx(angle(layer * 6), 0);
+                random.ry(angle(layer * 6 + 1), 1);
+                random.rz(angle(layer * 6 + 2), 0);
+                random.cnot(layer % 2, 1 - layer % 2);
+                random.ry(angle(layer * 6 + 3), 0);
+                random.rz(angle(layer * 6 + 4), 1);
+                random.rx(angle(layer * 6 + 5), 1);
+            }
+            circuits.push(random);
+        }
+
+        for circuit in circuits.iter() {
+            let matrix = unitary(circuit);
+            let decomposed = decompose_two_qubit(&matrix);
+            assert!(
+                decomposed
+                    .stats()
+                    .gate_counts
+                    .get("cnot")
+                    .copied()
+                    .unwrap_or(0)
+                    <= 3
+            );
+            assert!((overlap(&matrix, &unitary(&decomposed)) - 1.0).abs() < 1e-9);
+        }
+    }
+}
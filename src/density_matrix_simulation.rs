@@ -0,0 +1,359 @@
+/*
+Quantum density matrix simulation of mixed states.
+
+The density matrix ρ of n qubits is stored as the 4^n elements ρ[row, column] at the index
+row + column * 2^n, which is the vectorization of ρ as a state of 2n qubits: the qubit q of
+the rows and the qubit n + q of the columns. A gate U maps ρ to U ρ U†, applied as U to the
+qubits of the rows and as the complex conjugate of U to the qubits of the columns.
+A channel with the Kraus operators K_i maps ρ to the sum of K_i ρ K_i†, which represents the
+noise and the other mixed-state evolutions exactly, at the cost of squaring the memory of
+a state vector.
+
+The measurements sample the diagonal of ρ, and project ρ on the measured values.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::gate::{
+    phase_matrix, rx_matrix, ry_matrix, rz_matrix, Matrix, HADAMARD_MATRIX, PAULI_X_MATRIX,
+    PAULI_Y_MATRIX, PAULI_Z_MATRIX, S_MATRIX, T_MATRIX,
+};
+use crate::simulation::{check_qubits, Simulation};
+use crate::state_vector_simulation;
+
+pub const MAX_QUBIT_COUNT: usize = state_vector_simulation::MAX_QUBIT_COUNT / 2;
+
+#[derive(Debug)]
+pub struct DensityMatrixSimulation {
+    qubit_count: usize,
+    elements: Vec<Complex<f64>>,
+    rng: ChaCha12Rng,
+}
+
+impl DensityMatrixSimulation {
+    pub fn new(qubit_count: usize, rnd_seed: u64) -> DensityMatrixSimulation {
+        assert!(
+            qubit_count <= MAX_QUBIT_COUNT,
+            "The number of qubits in the simulation cannot exceed {}.",
+            MAX_QUBIT_COUNT
+        );
+
+        let mut simulation = DensityMatrixSimulation {
+            qubit_count,
+            elements: vec![Complex::new(0.0, 0.0); 1 << (2 * qubit_count)],
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The elements ρ[row, column] of the density matrix at the indices row + column * 2^n.
+    pub fn density_matrix(&self) -> &[Complex<f64>] {
+        &self.elements
+    }
+
+    pub fn element(&self, row: usize, column: usize) -> Complex<f64> {
+        self.elements[row | column << self.qubit_count]
+    }
+
+    // The probabilities of the basis states, the diagonal of ρ.
+    pub fn probabilities(&self) -> Vec<f64> {
+        (0..1usize << self.qubit_count)
+            .map(|i| self.element(i, i).re)
+            .collect()
+    }
+
+    // The purity tr(ρ^2), 1 for a pure state and 1/2^n for the maximally mixed state.
+    pub fn purity(&self) -> f64 {
+        self.elements.iter().map(|element| element.norm_sqr()).sum()
+    }
+
+    // Apply the channel with the Kraus operators to the qubit, ρ becoming the sum of K ρ K†.
+    // The operators have to satisfy the sum of K† K being the identity to preserve the trace.
+    pub fn apply_kraus(&mut self, kraus_operators: &[Matrix], qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        let mut channel_elements = vec![Complex::new(0.0, 0.0); self.elements.len()];
+        let elements = std::mem::take(&mut self.elements);
+        for kraus_operator in kraus_operators.iter() {
+            self.elements.clone_from(&elements);
+            self.apply_matrix(kraus_operator, &[], qubit_number);
+            for (channel_element, element) in channel_elements.iter_mut().zip(self.elements.iter())
+            {
+                *channel_element += element;
+            }
+        }
+        self.elements = channel_elements;
+    }
+
+    fn choose_state(&mut self) -> usize {
+        let random_number = self.rng.gen::<f64>();
+        let mut accumulated_probability = 0.0;
+        let state_count = 1usize << self.qubit_count;
+        for i in 0..state_count {
+            accumulated_probability += self.element(i, i).re;
+            if random_number <= accumulated_probability {
+                return i;
+            }
+        }
+        // The rounding errors can leave the total probability slightly below 1.
+        (0..state_count)
+            .rev()
+            .find(|&i| self.element(i, i).re > 0.0)
+            .unwrap_or(0)
+    }
+
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    // Apply the matrix to the target qubit where the control qubits have the given values,
+    // on the rows, and its complex conjugate on the columns.
+    fn apply_matrix(&mut self, matrix: &Matrix, controls: &[(usize, bool)], target: usize) {
+        let mut qubit_numbers: Vec<usize> = controls.iter().map(|&(control, _)| control).collect();
+        qubit_numbers.push(target);
+        self.check_qubit_numbers(&qubit_numbers);
+
+        let column_controls: Vec<(usize, bool)> = controls
+            .iter()
+            .map(|&(control, value)| (control + self.qubit_count, value))
+            .collect();
+        let conjugate_matrix = matrix.map(|element| element.conj());
+        self.apply_to_elements(matrix, controls, target);
+        self.apply_to_elements(
+            &conjugate_matrix,
+            &column_controls,
+            target + self.qubit_count,
+        );
+    }
+
+    // Apply the matrix to the bit of the index of the elements where the control bits
+    // have the given values.
+    fn apply_to_elements(&mut self, matrix: &Matrix, controls: &[(usize, bool)], target: usize) {
+        let (mut control_mask, mut control_value) = (0, 0);
+        for &(control, value) in controls.iter() {
+            control_mask |= 1 << control;
+            if value {
+                control_value |= 1 << control;
+            }
+        }
+        let stride = 1 << target;
+        for (c, chunk) in self.elements.chunks_exact_mut(2 * stride).enumerate() {
+            let (zeros, ones) = chunk.split_at_mut(stride);
+            let chunk_start = 2 * stride * c;
+            for (j, (element0, element1)) in zeros.iter_mut().zip(ones.iter_mut()).enumerate() {
+                if (chunk_start + j) & control_mask != control_value {
+                    continue;
+                }
+                let (e0, e1) = (*element0, *element1);
+                *element0 = matrix[0] * e0 + matrix[1] * e1;
+                *element1 = matrix[2] * e0 + matrix[3] * e1;
+            }
+        }
+    }
+}
+
+impl Simulation for DensityMatrixSimulation {
+    fn reset(&mut self) {
+        self.elements.fill(Complex::new(0.0, 0.0));
+        self.elements[0] = Complex::new(1.0, 0.0);
+    }
+
+    // Measure all the qubits in the Z-basis.
+    fn measure_all(&mut self) -> Vec<bool> {
+        let measured_state_index = self.choose_state();
+        self.elements.fill(Complex::new(0.0, 0.0));
+        self.elements[measured_state_index | measured_state_index << self.qubit_count] =
+            Complex::new(1.0, 0.0);
+
+        (0..self.qubit_count)
+            .map(|qubit_number| measured_state_index & (1 << qubit_number) > 0)
+            .collect()
+    }
+
+    // Measure the selected qubits in the Z-basis, keeping the elements whose row and column
+    // both have the measured values, rescaled by the probability of the values.
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
+        for &qubit_number in qubit_numbers.iter() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+        }
+
+        let measured_state_index = self.choose_state();
+        let measured_states: Vec<bool> = qubit_numbers
+            .iter()
+            .map(|&qubit_number| measured_state_index & (1 << qubit_number) > 0)
+            .collect();
+
+        let row_mask = qubit_numbers
+            .iter()
+            .fold(0, |mask, &qubit_number| mask | 1 << qubit_number);
+        let mask = row_mask | row_mask << self.qubit_count;
+        let value = (measured_state_index & row_mask) * (1 + (1 << self.qubit_count));
+        let probability: f64 = (0..1usize << self.qubit_count)
+            .filter(|&i| i & row_mask == measured_state_index & row_mask)
+            .map(|i| self.element(i, i).re)
+            .sum();
+        for (i, element) in self.elements.iter_mut().enumerate() {
+            if i & mask == value {
+                *element /= probability;
+            } else {
+                *element = Complex::new(0.0, 0.0);
+            }
+        }
+
+        measured_states
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_X_MATRIX, &[], qubit_number);
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Y_MATRIX, &[], qubit_number);
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Z_MATRIX, &[], qubit_number);
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.apply_matrix(&HADAMARD_MATRIX, &[], qubit_number);
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.apply_matrix(&S_MATRIX, &[], qubit_number);
+    }
+
+    fn t(&mut self, qubit_number: usize) {
+        self.apply_matrix(&T_MATRIX, &[], qubit_number);
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rx_matrix(theta), &[], qubit_number);
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&ry_matrix(theta), &[], qubit_number);
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rz_matrix(theta), &[], qubit_number);
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_matrix(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_matrix(
+            &PAULI_Z_MATRIX,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        let matrix = phase_matrix(Complex::new(0.0, theta).exp());
+        self.apply_matrix(
+            &matrix,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.cnot(qubit_number0, qubit_number1);
+        self.cnot(qubit_number1, qubit_number0);
+        self.cnot(qubit_number0, qubit_number1);
+    }
+
+    // U_f flips the qubit 0 when the qubit 1 is 0 if f(true), and when the qubit 1 is 1 if not f(false).
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        if f(true) {
+            self.apply_matrix(&PAULI_X_MATRIX, &[(qubit_number1, false)], qubit_number0);
+        }
+        if !f(false) {
+            self.apply_matrix(&PAULI_X_MATRIX, &[(qubit_number1, true)], qubit_number0);
+        }
+    }
+
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        self.apply_matrix(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number0, true), (control_qubit_number1, true)],
+            target_qubit_number,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn pure_states_match_state_vector() {
+        let mut simulation = DensityMatrixSimulation::new(3, 0);
+        let mut state_vector_simulation = QuantumSimulation::new(3, 0);
+        let gates: [fn(&mut dyn Simulation); 8] = [
+            |s| s.hadamard(0),
+            |s| s.ry(0.7, 2),
+            |s| s.pauli_y(2),
+            |s| s.cnot(0, 1),
+            |s| s.cphase(0.9, 1, 2),
+            |s| s.rx(-0.4, 1),
+            |s| s.apply_u_f(|x| !x, 2, 0),
+            |s| s.toffoli(0, 2, 1),
+        ];
+        for gate in gates.iter() {
+            gate(&mut simulation);
+            gate(&mut state_vector_simulation);
+        }
+        let amplitudes = state_vector_simulation.amplitudes();
+        for row in 0..8 {
+            for column in 0..8 {
+                let expected_element = amplitudes[row] * amplitudes[column].conj();
+                assert!((simulation.element(row, column) - expected_element).norm() < 1e-12);
+            }
+        }
+        assert!((simulation.purity() - 1.0).abs() < 1e-12);
+
+        // The complete dephasing of a qubit in the state |+⟩ leaves it maximally mixed.
+        let mut simulation = DensityMatrixSimulation::new(1, 0);
+        simulation.hadamard(0);
+        let half = Complex::new(0.5f64.sqrt(), 0.0);
+        let dephasing = [
+            [half, Complex::new(0.0, 0.0), Complex::new(0.0, 0.0), half],
+            [half, Complex::new(0.0, 0.0), Complex::new(0.0, 0.0), -half],
+        ];
+        simulation.apply_kraus(&dephasing, 0);
+        assert!((simulation.purity() - 0.5).abs() < 1e-12);
+        let measured_states = simulation.measure(vec![0]);
+        assert_eq!(simulation.measure(vec![0]), measured_states);
+        assert!((simulation.probabilities().iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
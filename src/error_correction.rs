@@ -0,0 +1,184 @@
+/*
+Circuits of the Steane [[7,1,3]] code.
+
+The Steane code is the CSS code of the classical Hamming [7,4,3] code, whose parity checks
+are the positions 1 to 7 with the bit 0, 1, or 2 set, on the qubits 0 to 6. Each parity check
+is both an X and a Z stabilizer, so that an X error on the qubit j flips the Z checks spelling
+the position j + 1 in binary and a Z error flips the same X checks, and any error of one qubit
+is corrected. The logical X and Z are X and Z on all the seven qubits.
+
+The code states are measured by the stabilizer simulation, all the circuits being Clifford
+circuits. The Hadamard and the CNOT gates are transversal: H on each qubit of a block is the
+logical H, and CNOT between the matching qubits of two blocks is the logical CNOT, neither of
+which spreads an error of one qubit to another qubit of the same block.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::circuit::{Circuit, Condition, Gate};
+
+pub const STEANE_QUBIT_COUNT: usize = 7;
+// The number of the syndrome bits, of the Z checks first and then of the X checks.
+pub const STEANE_SYNDROME_BIT_COUNT: usize = 6;
+
+// The qubits of each parity check of the Hamming code.
+const PARITY_CHECKS: [[usize; 4]; 3] = [[0, 2, 4, 6], [1, 2, 5, 6], [3, 4, 5, 6]];
+
+// The encoding of the state of the qubit 0 into the qubits 0..7, the others starting in |0⟩. The
+// qubits 4, 5, and 6 put into |+⟩ add the codewords of the even-weight subcode of the Hamming
+// code, and the qubit 0 copied to the qubits 1 and 2 adds the logical X.
+pub fn steane_encoding() -> Circuit {
+    let mut circuit = Circuit::new(STEANE_QUBIT_COUNT);
+    circuit.cnot(0, 1);
+    circuit.cnot(0, 2);
+    for (control, targets) in [(4, [1, 2, 3]), (5, [0, 2, 3]), (6, [0, 1, 3])] {
+        circuit.hadamard(control);
+        for target in targets {
+            circuit.cnot(control, target);
+        }
+    }
+    circuit
+}
+
+// The measurement of the syndrome of the code block on the qubits 0..7 by the ancilla qubits
+// 7..13 starting in |0⟩. The Z checks are measured into the classical bits 0..3 and the X checks
+// into the classical bits 3..6, the syndrome bits being the binary position of the error.
+pub fn steane_syndrome_extraction() -> Circuit {
+    let mut circuit = Circuit::with_clbits(
+        STEANE_QUBIT_COUNT + STEANE_SYNDROME_BIT_COUNT,
+        STEANE_SYNDROME_BIT_COUNT,
+    );
+    for (i, check) in PARITY_CHECKS.iter().enumerate() {
+        let ancilla = STEANE_QUBIT_COUNT + i;
+        for &qubit_number in check {
+            circuit.cnot(qubit_number, ancilla);
+        }
+        circuit.measure(ancilla, i);
+    }
+    for (i, check) in PARITY_CHECKS.iter().enumerate() {
+        let ancilla = STEANE_QUBIT_COUNT + 3 + i;
+        circuit.hadamard(ancilla);
+        for &qubit_number in check {
+            circuit.cnot(ancilla, qubit_number);
+        }
+        circuit.hadamard(ancilla);
+        circuit.measure(ancilla, 3 + i);
+    }
+    circuit
+}
+
+// The syndrome extraction followed by the X and the Z gates on the qubits given by the
+// syndrome, conditional on the classical bits.
+pub fn steane_error_correction() -> Circuit {
+    let mut circuit = steane_syndrome_extraction();
+    for position in 1..=STEANE_QUBIT_COUNT {
+        let qubit_number = position - 1;
+        let condition = |first_clbit| Condition {
+            first_clbit,
+            clbit_count: 3,
+            value: position as u64,
+        };
+        circuit.push_conditional(Gate::PauliX(qubit_number), condition(0));
+        circuit.push_conditional(Gate::PauliZ(qubit_number), condition(3));
+    }
+    circuit
+}
+
+// The logical H of a code block, H on each of its qubits.
+pub fn steane_hadamard() -> Circuit {
+    let mut circuit = Circuit::new(STEANE_QUBIT_COUNT);
+    for qubit_number in 0..STEANE_QUBIT_COUNT {
+        circuit.hadamard(qubit_number);
+    }
+    circuit
+}
+
+// The logical CNOT of the control block on the qubits 0..7 and the target block on the qubits
+// 7..14, CNOT between the matching qubits of the blocks.
+pub fn steane_cnot() -> Circuit {
+    let mut circuit = Circuit::new(2 * STEANE_QUBIT_COUNT);
+    for qubit_number in 0..STEANE_QUBIT_COUNT {
+        circuit.cnot(qubit_number, STEANE_QUBIT_COUNT + qubit_number);
+    }
+    circuit
+}
+
+// The logical value of the measured qubits of a code block, correcting a flip of one of them by
+// the parity checks, the codewords of the logical 0 having the even weight and of the logical 1
+// the odd weight.
+pub fn steane_logical_value(values: &[bool]) -> bool {
+    assert!(
+        values.len() == STEANE_QUBIT_COUNT,
+        "The number of the values has to be {}.",
+        STEANE_QUBIT_COUNT
+    );
+    let syndrome: usize = PARITY_CHECKS
+        .iter()
+        .enumerate()
+        .map(|(i, check)| (check.iter().filter(|&&q| values[q]).count() % 2) << i)
+        .sum();
+    let parity = values.iter().filter(|&&value| value).count() % 2 == 1;
+    parity != (syndrome != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::stabilizer_simulation::StabilizerSimulation;
+
+    #[test]
+    fn steane_code_corrects_single_qubit_errors() {
+        let block: Vec<usize> = (0..STEANE_QUBIT_COUNT).collect();
+        let qubit_count = STEANE_QUBIT_COUNT + STEANE_SYNDROME_BIT_COUNT;
+        for is_one in [false, true] {
+            for error in 0..=2 * STEANE_QUBIT_COUNT {
+                let mut circuit = Circuit::new(qubit_count);
+                if is_one {
+                    circuit.pauli_x(0);
+                }
+                circuit.append(&steane_encoding(), &block);
+                // No error, an X error on each qubit, and a Z error on each qubit.
+                match error {
+                    0 => {}
+                    e if e <= STEANE_QUBIT_COUNT => circuit.pauli_x(e - 1),
+                    e => circuit.pauli_z(e - STEANE_QUBIT_COUNT - 1),
+                }
+                let mut simulation = StabilizerSimulation::new(qubit_count, 0);
+                circuit.run(&mut simulation);
+                let syndrome = steane_error_correction().run(&mut simulation);
+                let is_syndrome_zero = syndrome.iter().all(|&bit| !bit);
+                assert_eq!(is_syndrome_zero, error == 0, "error {}", error);
+                let values = simulation.measure(block.clone());
+                assert_eq!(steane_logical_value(&values), is_one, "error {}", error);
+                assert!(values.iter().filter(|&&value| value).count() % 2 == is_one as usize);
+            }
+        }
+
+        // The transversal CNOT of the logical |1⟩ and |0⟩ gives |1⟩|1⟩, and the transversal H of
+        // the logical |+⟩ and |-⟩ gives |0⟩ and |1⟩.
+        let both_blocks: Vec<usize> = (0..2 * STEANE_QUBIT_COUNT).collect();
+        let mut simulation = StabilizerSimulation::new(2 * STEANE_QUBIT_COUNT, 0);
+        simulation.pauli_x(0);
+        steane_encoding().run(&mut simulation);
+        let mut circuit = Circuit::new(2 * STEANE_QUBIT_COUNT);
+        circuit.append(&steane_encoding(), &both_blocks[STEANE_QUBIT_COUNT..]);
+        circuit.append(&steane_cnot(), &both_blocks);
+        circuit.run(&mut simulation);
+        let values = simulation.measure(both_blocks);
+        assert!(steane_logical_value(&values[..STEANE_QUBIT_COUNT]));
+        assert!(steane_logical_value(&values[STEANE_QUBIT_COUNT..]));
+
+        for is_minus in [false, true] {
+            let mut simulation = StabilizerSimulation::new(STEANE_QUBIT_COUNT, 0);
+            if is_minus {
+                simulation.pauli_x(0);
+            }
+            simulation.hadamard(0);
+            steane_encoding().run(&mut simulation);
+            steane_hadamard().run(&mut simulation);
+            let values = simulation.measure(block.clone());
+            assert_eq!(steane_logical_value(&values), is_minus);
+        }
+    }
+}
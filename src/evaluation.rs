@@ -1,81 +1,953 @@
 /*
 Module to evaluate and display the results of the quantum measurements.
 
+The evaluation returns the counts of the measurements and of each qubit measured 1, for the
+programs to use, and displays them only when printed, as the percentages or as the histogram of
+the bars of the measurements. The measured distribution is compared with a reference one, such as
+the exact probabilities of the state vector, by the total variation distance, the
+Kullback-Leibler divergence, and the Hellinger distance, and the counts are tested against the
+expected distribution by Pearson's chi-squared test, whose p-value is the regularized upper
+incomplete gamma function of the statistic. Each printed probability has its Wilson score
+interval, which stays within 0 and 1 and is not empty for the outcomes of the counts 0 or of all
+the shots, so that a rare outcome can be told from the sampling noise.
+
+The measurements are displayed as the bitstrings with the highest qubit number first by default,
+or with the qubit 0 first, in hexadecimal or in decimal, and split into the registers by the
+formatting options. The results can also be written to files with the metadata of the run. The
+outcomes are written as bitstrings with the highest qubit number first, whatever the formatting
+options.
+
 Copyright © 2024 AlgoHertz. All rights reserved.
 */
 
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[cfg(feature = "plots")]
+mod plots;
 
-fn measurement_string(measurement: Vec<bool>) -> String {
-    let mut result = String::from("|");
-    for value in measurement.into_iter().rev() {
-        if value {
-            result.push('1');
-        } else {
-            result.push('0');
+#[cfg(feature = "plots")]
+pub use plots::save_probability_plot_png;
+
+// The normal quantile of the 95% confidence intervals of the printed probabilities.
+pub const CONFIDENCE_Z: f64 = 1.959963984540054;
+
+fn bitstring(measurement: &[bool]) -> String {
+    measurement
+        .iter()
+        .rev()
+        .map(|&value| if value { '1' } else { '0' })
+        .collect()
+}
+
+// The order of the bits of the qubits in the displayed measurements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    // The highest qubit number first, |q_(n-1)...q_0>, as the registers of Qiskit.
+    #[default]
+    MsbFirst,
+    // The qubit 0 first, |q_0...q_(n-1)>, in the order of the measured values.
+    LsbFirst,
+}
+
+impl BitOrder {
+    pub const ALL: [BitOrder; 2] = [BitOrder::MsbFirst, BitOrder::LsbFirst];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            BitOrder::MsbFirst => "msb",
+            BitOrder::LsbFirst => "lsb",
         }
     }
-    result.push('>');
-    result
+
+    pub fn from_name(name: &str) -> Option<BitOrder> {
+        BitOrder::ALL
+            .into_iter()
+            .find(|bit_order| bit_order.name() == name)
+    }
+}
+
+// The notation of the values of the registers in the displayed measurements.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutcomeFormat {
+    #[default]
+    Binary,
+    // The digits of 4 bits, padded to the number of the bits of the register.
+    Hexadecimal,
+    Decimal,
 }
 
-fn measurement_wildcard(qubit_count: usize, qubit_number: usize) -> String {
-    let mut result = String::from("|");
-    for i in (0..qubit_count).rev() {
-        if qubit_number == i {
-            result.push('1');
-        } else {
-            result.push('*');
+impl OutcomeFormat {
+    pub const ALL: [OutcomeFormat; 3] = [
+        OutcomeFormat::Binary,
+        OutcomeFormat::Hexadecimal,
+        OutcomeFormat::Decimal,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            OutcomeFormat::Binary => "binary",
+            OutcomeFormat::Hexadecimal => "hex",
+            OutcomeFormat::Decimal => "decimal",
         }
     }
-    result.push('>');
-    result
+
+    pub fn from_name(name: &str) -> Option<OutcomeFormat> {
+        OutcomeFormat::ALL
+            .into_iter()
+            .find(|outcome_format| outcome_format.name() == name)
+    }
 }
 
-pub fn evaluate(measurements: Vec<Vec<bool>>) {
-    println!("Quantum simulation results");
-    let qubit_count = measurements[0].len();
-    println!("Qubit count: {:?}", qubit_count);
-    let measurement_count = measurements.len();
-    println!("Measurement count: {:?}", measurement_count);
+// The formatting of the displayed measurements. The qubits are split into the registers of the
+// sizes from the qubit 0, the rest of the qubits in the last register, and the value of each
+// register is the number with its first qubit as the lowest bit. The registers are separated by
+// spaces, in the bit order like the bits of a register.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub bit_order: BitOrder,
+    pub outcome_format: OutcomeFormat,
+    pub register_sizes: Vec<usize>,
+}
+
+impl FormatOptions {
+    // The ranges of the qubit numbers of the registers in the bit order.
+    fn registers(&self, qubit_count: usize) -> Vec<std::ops::Range<usize>> {
+        let mut registers = Vec::new();
+        let mut start = 0;
+        for &size in self.register_sizes.iter() {
+            if start + size >= qubit_count {
+                break;
+            }
+            registers.push(start..start + size);
+            start += size;
+        }
+        registers.push(start..qubit_count);
+        if self.bit_order == BitOrder::MsbFirst {
+            registers.reverse();
+        }
+        registers
+    }
+
+    // The symbols of the qubits of each register in the bit order, separated by spaces.
+    fn group(&self, symbols: &[char]) -> String {
+        let registers: Vec<String> = self
+            .registers(symbols.len())
+            .into_iter()
+            .map(|register| match self.bit_order {
+                BitOrder::MsbFirst => symbols[register].iter().rev().collect(),
+                BitOrder::LsbFirst => symbols[register].iter().collect(),
+            })
+            .collect();
+        registers.join(" ")
+    }
+
+    // The measurement as |...> with the values of the registers.
+    pub fn format(&self, measurement: &[bool]) -> String {
+        let registers: Vec<String> = match self.outcome_format {
+            OutcomeFormat::Binary => {
+                let symbols: Vec<char> = measurement
+                    .iter()
+                    .map(|&value| if value { '1' } else { '0' })
+                    .collect();
+                return format!("|{}>", self.group(&symbols));
+            }
+            OutcomeFormat::Hexadecimal => self
+                .registers(measurement.len())
+                .into_iter()
+                .map(|register| {
+                    let width = register.len().div_ceil(4);
+                    format!("{:0width$x}", register_value(&measurement[register]))
+                })
+                .collect(),
+            OutcomeFormat::Decimal => self
+                .registers(measurement.len())
+                .into_iter()
+                .map(|register| register_value(&measurement[register]).to_string())
+                .collect(),
+        };
+        format!("|{}>", registers.join(" "))
+    }
+
+    // The pattern of the measurements with the qubit measured 1, the other qubits as *.
+    fn wildcard(&self, qubit_count: usize, qubit_number: usize) -> String {
+        let symbols: Vec<char> = (0..qubit_count)
+            .map(|i| if i == qubit_number { '1' } else { '*' })
+            .collect();
+        format!("|{}>", self.group(&symbols))
+    }
+}
 
+// The value of the bits with the first bit as the lowest bit.
+fn register_value(bits: &[bool]) -> u128 {
+    bits.iter()
+        .rev()
+        .fold(0, |value, &bit| (value << 1) | bit as u128)
+}
+
+// The number of occurrences of each measurement.
+fn count_map(measurements: &[Vec<bool>]) -> HashMap<Vec<bool>, usize> {
     let mut measurement_count_map: HashMap<Vec<bool>, usize> = HashMap::new();
-    let mut one_counts = vec![0usize; qubit_count];
+    for measurement in measurements.iter() {
+        *measurement_count_map
+            .entry(measurement.clone())
+            .or_insert(0) += 1;
+    }
+    measurement_count_map
+}
+
+// The measurements with their counts, the most frequent first.
+fn sorted_counts(measurement_count_map: &HashMap<Vec<bool>, usize>) -> Vec<(Vec<bool>, usize)> {
+    let mut measurement_count_pairs: Vec<_> = measurement_count_map
+        .iter()
+        .map(|(measurement, &count)| (measurement.clone(), count))
+        .collect();
+    measurement_count_pairs.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| bitstring(&a.0).cmp(&bitstring(&b.0)))
+    });
+    measurement_count_pairs
+}
+
+// The number of occurrences of each measurement, the most frequent first.
+fn measurement_counts(measurements: &[Vec<bool>]) -> Vec<(Vec<bool>, usize)> {
+    sorted_counts(&count_map(measurements))
+}
+
+// The statistics of the measurements of the shots.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluationResult {
+    pub qubit_count: usize,
+    pub shot_count: usize,
+    // The number of occurrences of each measurement.
+    pub counts: HashMap<Vec<bool>, usize>,
+    // The number of the shots with each qubit measured 1.
+    pub one_counts: Vec<usize>,
+    // The formatting of the displayed measurements.
+    pub format_options: FormatOptions,
+}
+
+impl EvaluationResult {
+    // The result displayed with the formatting options.
+    pub fn with_format_options(mut self, format_options: FormatOptions) -> EvaluationResult {
+        self.format_options = format_options;
+        self
+    }
+
+    // The measurements with their counts, the most frequent first.
+    pub fn sorted_counts(&self) -> Vec<(Vec<bool>, usize)> {
+        sorted_counts(&self.counts)
+    }
+
+    // The fraction of the shots with the measurement.
+    pub fn probability(&self, measurement: &[bool]) -> f64 {
+        self.counts.get(measurement).copied().unwrap_or(0) as f64 / self.shot_count as f64
+    }
+
+    // The fraction of the shots with each basis state, indexed by the bits of the qubit numbers
+    // like the amplitudes of the state vector.
+    pub fn probabilities(&self) -> Vec<f64> {
+        let mut probabilities = vec![0.0; 1 << self.qubit_count];
+        for (measurement, &count) in self.counts.iter() {
+            let state_index: usize = measurement
+                .iter()
+                .enumerate()
+                .map(|(qubit_number, &value)| (value as usize) << qubit_number)
+                .sum();
+            probabilities[state_index] = count as f64 / self.shot_count as f64;
+        }
+        probabilities
+    }
+
+    // Test the counts of the basis states, indexed like the probabilities, against the expected
+    // probabilities.
+    pub fn chi_squared_test(&self, expected_probabilities: &[f64]) -> ChiSquaredTest {
+        let counts: Vec<usize> = self
+            .probabilities()
+            .iter()
+            .map(|probability| (probability * self.shot_count as f64).round() as usize)
+            .collect();
+        chi_squared_test(&counts, expected_probabilities)
+    }
+
+    // The fraction of the shots with each qubit measured 1, the marginal probabilities.
+    pub fn marginals(&self) -> Vec<f64> {
+        self.one_counts
+            .iter()
+            .map(|&one_count| one_count as f64 / self.shot_count as f64)
+            .collect()
+    }
+
+    // The result of only the shots whose measurements satisfy the condition, with the
+    // probabilities renormalized to the kept shots. The measurements keep all the qubits.
+    pub fn filter(&self, condition: impl Fn(&[bool]) -> bool) -> EvaluationResult {
+        let counts: HashMap<Vec<bool>, usize> = self
+            .counts
+            .iter()
+            .filter(|(measurement, _)| condition(measurement))
+            .map(|(measurement, &count)| (measurement.clone(), count))
+            .collect();
+        let shot_count = counts.values().sum();
+        assert!(
+            shot_count > 0,
+            "The filtered measurements have to have at least one shot."
+        );
+        let mut one_counts = vec![0usize; self.qubit_count];
+        for (measurement, &count) in counts.iter() {
+            for i in 0..self.qubit_count {
+                if measurement[i] {
+                    one_counts[i] += count;
+                }
+            }
+        }
+        EvaluationResult {
+            qubit_count: self.qubit_count,
+            shot_count,
+            counts,
+            one_counts,
+            format_options: self.format_options.clone(),
+        }
+    }
+
+    // The result of only the shots with the qubit measured the value, such as the heralding
+    // ancilla of a protocol.
+    pub fn post_select(&self, qubit_number: usize, value: bool) -> EvaluationResult {
+        assert!(
+            qubit_number < self.qubit_count,
+            "The post-selected qubit has to be measured."
+        );
+        self.filter(|measurement| measurement[qubit_number] == value)
+    }
+
+    // The Wilson score interval of the probability of the measurement with the normal quantile z,
+    // such as CONFIDENCE_Z for 95%.
+    pub fn confidence_interval(&self, measurement: &[bool], z: f64) -> (f64, f64) {
+        let count = self.counts.get(measurement).copied().unwrap_or(0);
+        wilson_interval(count, self.shot_count, z)
+    }
+
+    // The sample mean of the diagonal observable of the weights of the basis states, indexed
+    // like the amplitudes with the qubit 0 as the lowest bit, such as the cost of a cut or the
+    // parity (-1)^(the number of the ones).
+    pub fn expectation(&self, weights: impl Fn(u64) -> f64) -> f64 {
+        assert!(
+            self.qubit_count <= 64,
+            "The number of qubits has to be at most 64."
+        );
+        self.counts
+            .iter()
+            .map(|(measurement, &count)| {
+                let index = measurement
+                    .iter()
+                    .rev()
+                    .fold(0u64, |index, &bit| (index << 1) | bit as u64);
+                count as f64 * weights(index)
+            })
+            .sum::<f64>()
+            / self.shot_count as f64
+    }
+
+    // The covariance and the correlation of the bits of each pair of the qubits, such as the
+    // correlation 1 of the qubits of the Bell state (1/sqrt(2))*(|00⟩ + |11⟩).
+    pub fn correlations(&self) -> Correlations {
+        let mut both_one_counts = vec![vec![0; self.qubit_count]; self.qubit_count];
+        for (measurement, &count) in self.counts.iter() {
+            for i in 0..self.qubit_count {
+                for j in 0..self.qubit_count {
+                    if measurement[i] && measurement[j] {
+                        both_one_counts[i][j] += count;
+                    }
+                }
+            }
+        }
+        let marginals = self.marginals();
+        let covariance: Vec<Vec<f64>> = (0..self.qubit_count)
+            .map(|i| {
+                (0..self.qubit_count)
+                    .map(|j| {
+                        both_one_counts[i][j] as f64 / self.shot_count as f64
+                            - marginals[i] * marginals[j]
+                    })
+                    .collect()
+            })
+            .collect();
+        let correlation = (0..self.qubit_count)
+            .map(|i| {
+                (0..self.qubit_count)
+                    .map(|j| {
+                        let variance_product = covariance[i][i] * covariance[j][j];
+                        if variance_product > 0.0 {
+                            covariance[i][j] / variance_product.sqrt()
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Correlations {
+            covariance,
+            correlation,
+        }
+    }
+
+    // Display the counts of the measurements in percent, the most frequent first, and the
+    // percentage of each qubit measured 1.
+    pub fn print(&self) {
+        println!("Quantum simulation results");
+        println!("Qubit count: {:?}", self.qubit_count);
+        println!("Measurement count: {:?}", self.shot_count);
+
+        for (measurement, count) in self.sorted_counts() {
+            let probability_pct: f64 = 100.0 * count as f64 / self.shot_count as f64;
+            let (lower, upper) = wilson_interval(count, self.shot_count, CONFIDENCE_Z);
+            println!(
+                "{}: {:?}% (95% interval {:.1}% to {:.1}%)",
+                self.format_options.format(&measurement),
+                probability_pct,
+                100.0 * lower,
+                100.0 * upper
+            );
+        }
+
+        for (qubit_number, &one_count) in self.one_counts.iter().enumerate() {
+            let probability_pct: f64 = 100.0 * one_count as f64 / self.shot_count as f64;
+            println!(
+                "{:?}. {}: {:?}%",
+                qubit_number,
+                self.format_options.wildcard(self.qubit_count, qubit_number),
+                probability_pct
+            );
+        }
+    }
+
+    // The bars of the measurements with the probability at least the cutoff, the most frequent
+    // first, the bar of the most frequent measurement of the width and the others in proportion,
+    // followed by the number of the measurements below the cutoff.
+    pub fn histogram(&self, width: usize, cutoff: f64) -> String {
+        let sorted_counts = self.sorted_counts();
+        let max_count = sorted_counts.first().map_or(1, |&(_, count)| count);
+        let mut histogram = String::new();
+        let mut omitted_count = 0;
+        for (measurement, count) in sorted_counts {
+            let probability = count as f64 / self.shot_count as f64;
+            if probability < cutoff {
+                omitted_count += 1;
+                continue;
+            }
+            let length = (width as f64 * count as f64 / max_count as f64).round() as usize;
+            histogram += &format!(
+                "{} {:<width$} {:5.1}%\n",
+                self.format_options.format(&measurement),
+                "#".repeat(length),
+                100.0 * probability,
+                width = width
+            );
+        }
+        if omitted_count > 0 {
+            histogram += &format!(
+                "{} measurements below {}% omitted\n",
+                omitted_count,
+                100.0 * cutoff
+            );
+        }
+        histogram
+    }
+
+    // Display the histogram of the measurements.
+    pub fn print_histogram(&self, width: usize, cutoff: f64) {
+        print!("{}", self.histogram(width, cutoff));
+    }
+}
+
+// The pairwise statistics of the bits of the qubits measured 0 or 1, indexed by the qubit numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Correlations {
+    // The covariance of the bits, E[b_i b_j] - E[b_i] E[b_j], with the variances on the diagonal.
+    pub covariance: Vec<Vec<f64>>,
+    // The Pearson correlation of the bits from -1 to 1, 0 if either bit was always the same.
+    pub correlation: Vec<Vec<f64>>,
+}
+
+// The distances of a measured distribution from a reference distribution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DistributionDistances {
+    // The total variation distance, half the sum of |p - q|, from 0 to 1.
+    pub total_variation: f64,
+    // The Kullback-Leibler divergence, the sum of p ln(p/q), infinite if a measurement has
+    // the reference probability 0.
+    pub kl_divergence: f64,
+    // The Hellinger distance, sqrt(1 - the sum of sqrt(p q)), from 0 to 1.
+    pub hellinger: f64,
+}
+
+// The Wilson score interval of the probability of the outcome seen count times in the shots, with
+// the normal quantile z.
+pub fn wilson_interval(count: usize, shot_count: usize, z: f64) -> (f64, f64) {
+    assert!(shot_count > 0, "The number of shots has to be positive.");
+    assert!(
+        count <= shot_count,
+        "The count has to be at most the number of shots."
+    );
+    let n = shot_count as f64;
+    let p = count as f64 / n;
+    let z2_n = z * z / n;
+    let center = (p + z2_n / 2.0) / (1.0 + z2_n);
+    let half_width = z / (1.0 + z2_n) * (p * (1.0 - p) / n + z2_n / (4.0 * n)).sqrt();
+    (
+        (center - half_width).max(0.0),
+        (center + half_width).min(1.0),
+    )
+}
+
+// Compare the empirical probabilities p of the basis states with the theoretical ones q, such as
+// the probabilities of the amplitudes of the state vector.
+pub fn compare(empirical: &[f64], theoretical: &[f64]) -> DistributionDistances {
+    assert!(
+        empirical.len() == theoretical.len(),
+        "The number of the empirical probabilities has to be equal to the number of the theoretical probabilities {}.",
+        theoretical.len()
+    );
+    let mut distances = DistributionDistances {
+        total_variation: 0.0,
+        kl_divergence: 0.0,
+        hellinger: 0.0,
+    };
+    let mut bhattacharyya_coefficient = 0.0;
+    for (&p, &q) in empirical.iter().zip(theoretical) {
+        distances.total_variation += (p - q).abs() / 2.0;
+        if p > 0.0 {
+            distances.kl_divergence += if q > 0.0 {
+                p * (p / q).ln()
+            } else {
+                f64::INFINITY
+            };
+        }
+        bhattacharyya_coefficient += (p * q).sqrt();
+    }
+    distances.hellinger = (1.0 - bhattacharyya_coefficient).max(0.0).sqrt();
+    distances
+}
+
+// The result of Pearson's chi-squared test of the counts against the expected distribution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChiSquaredTest {
+    // The sum of (observed - expected)^2 / expected of the counts.
+    pub statistic: f64,
+    // The number of the outcomes of the nonzero expected probability less 1.
+    pub degrees_of_freedom: usize,
+    // The probability of the statistic at least as large if the counts follow the expected
+    // distribution, the hypothesis rejected at the significance level above it.
+    pub p_value: f64,
+}
+
+// The coefficients of the Lanczos approximation of the gamma function with g = 7.
+const LANCZOS_COEFFICIENTS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+// The relative precision of the series and the continued fraction of the incomplete gamma function.
+const GAMMA_PRECISION: f64 = 1e-15;
+const MAX_GAMMA_ITERATION_COUNT: usize = 1000;
+
+// The natural logarithm of the gamma function of the positive x.
+fn ln_gamma(x: f64) -> f64 {
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = LANCZOS_COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(LANCZOS_COEFFICIENTS[0], |sum, (i, &coefficient)| {
+            sum + coefficient / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// The regularized upper incomplete gamma function Q(a, x), by the series of P(a, x) = 1 - Q(a, x)
+// below x = a + 1 and by the continued fraction of Q(a, x) above.
+fn upper_incomplete_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let log_prefactor = a * x.ln() - x - ln_gamma(a);
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..MAX_GAMMA_ITERATION_COUNT {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * GAMMA_PRECISION {
+                break;
+            }
+        }
+        (1.0 - sum * log_prefactor.exp()).max(0.0)
+    } else {
+        // The modified Lentz method.
+        let tiny = f64::MIN_POSITIVE / GAMMA_PRECISION;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut fraction = d;
+        for n in 1..MAX_GAMMA_ITERATION_COUNT {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            fraction *= delta;
+            if (delta - 1.0).abs() < GAMMA_PRECISION {
+                break;
+            }
+        }
+        fraction * log_prefactor.exp()
+    }
+}
 
-    for measurement in measurements {
+// Test the observed counts of the outcomes against the expected probabilities of the outcomes.
+// The outcomes of the expected probability 0 are left out, unless they are observed, which
+// makes the statistic infinite.
+pub fn chi_squared_test(counts: &[usize], expected_probabilities: &[f64]) -> ChiSquaredTest {
+    assert!(
+        counts.len() == expected_probabilities.len(),
+        "The number of the counts has to be equal to the number of the expected probabilities {}.",
+        expected_probabilities.len()
+    );
+    let total_count: usize = counts.iter().sum();
+    let mut statistic = 0.0;
+    let mut outcome_count = 0;
+    for (&count, &probability) in counts.iter().zip(expected_probabilities) {
+        let expected_count = probability * total_count as f64;
+        if expected_count > 0.0 {
+            statistic += (count as f64 - expected_count).powi(2) / expected_count;
+            outcome_count += 1;
+        } else if count > 0 {
+            statistic = f64::INFINITY;
+        }
+    }
+    let degrees_of_freedom = outcome_count.max(1) - 1;
+    let p_value = if statistic.is_infinite() {
+        0.0
+    } else if degrees_of_freedom == 0 {
+        1.0
+    } else {
+        upper_incomplete_gamma(degrees_of_freedom as f64 / 2.0, statistic / 2.0)
+    };
+    ChiSquaredTest {
+        statistic,
+        degrees_of_freedom,
+        p_value,
+    }
+}
+
+// Evaluate the measurements of the shots, each with the same number of qubits.
+pub fn evaluate(measurements: Vec<Vec<bool>>) -> EvaluationResult {
+    assert!(
+        !measurements.is_empty(),
+        "The measurements have to have at least one shot."
+    );
+    let qubit_count = measurements[0].len();
+    let mut one_counts = vec![0usize; qubit_count];
+    for measurement in measurements.iter() {
         for i in 0..qubit_count {
             if measurement[i] {
                 one_counts[i] += 1;
             }
         }
+    }
 
-        if let Some(count) = measurement_count_map.get(&measurement) {
-            measurement_count_map.insert(measurement, count + 1);
-        } else {
-            measurement_count_map.insert(measurement, 1);
-        }
+    EvaluationResult {
+        qubit_count,
+        shot_count: measurements.len(),
+        counts: count_map(&measurements),
+        one_counts,
+        format_options: FormatOptions::default(),
     }
+}
 
-    let mut measurement_count_pairs: Vec<_> = measurement_count_map.into_iter().collect();
-    measurement_count_pairs.sort_by(|a, b| b.1.cmp(&a.1));
+// Display the mean and the variance of the homodyne samples of each mode.
+pub fn evaluate_quadratures(samples: Vec<Vec<f64>>) {
+    println!("Gaussian simulation results");
+    let mode_count = samples[0].len();
+    println!("Mode count: {:?}", mode_count);
+    let sample_count = samples.len();
+    println!("Sample count: {:?}", sample_count);
 
-    for (measurement, count) in measurement_count_pairs {
-        let probability_pct: f64 = 100.0 * count as f64 / measurement_count as f64;
+    for mode_number in 0..mode_count {
+        let mean = samples
+            .iter()
+            .map(|sample| sample[mode_number])
+            .sum::<f64>()
+            / sample_count as f64;
+        let variance = samples
+            .iter()
+            .map(|sample| (sample[mode_number] - mean).powi(2))
+            .sum::<f64>()
+            / sample_count as f64;
         println!(
-            "{}: {:?}%",
-            measurement_string(measurement),
-            probability_pct
+            "{:?}. mean: {:?}, variance: {:?}",
+            mode_number, mean, variance
         );
     }
+}
 
-    for qubit_number in 0..qubit_count {
-        let probability_pct: f64 =
-            100.0 * one_counts[qubit_number] as f64 / measurement_count as f64;
-        println!(
-            "{:?}. {}: {:?}%",
-            qubit_number,
-            measurement_wildcard(qubit_count, qubit_number),
-            probability_pct
+// Write the shots as CSV rows of the shot index and the outcome, after comment lines with the metadata,
+// followed by a blank line and the rows of the counts of the outcomes, most frequent first.
+pub fn write_csv<P: AsRef<Path>>(path: P, measurements: &[Vec<bool>], seed: u64) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "# seed: {}", seed)?;
+    writeln!(
+        writer,
+        "# qubit_count: {}",
+        measurements.first().map_or(0, |m| m.len())
+    )?;
+    writeln!(writer, "# shot_count: {}", measurements.len())?;
+    writeln!(writer, "shot,outcome")?;
+    for (shot, measurement) in measurements.iter().enumerate() {
+        writeln!(writer, "{},{}", shot, bitstring(measurement))?;
+    }
+    writeln!(writer)?;
+    writeln!(writer, "outcome,count")?;
+    for (measurement, count) in measurement_counts(measurements).iter() {
+        writeln!(writer, "{},{}", bitstring(measurement), count)?;
+    }
+    writer.flush()
+}
+
+// The metadata, the counts of the outcomes, and the outcomes of the shots as a JSON object.
+pub fn to_json(measurements: &[Vec<bool>], seed: u64) -> String {
+    let counts: Vec<String> = measurement_counts(measurements)
+        .iter()
+        .map(|(measurement, count)| format!("\"{}\": {}", bitstring(measurement), count))
+        .collect();
+    let shots: Vec<String> = measurements
+        .iter()
+        .map(|measurement| format!("\"{}\"", bitstring(measurement)))
+        .collect();
+    format!(
+        "{{\n  \"seed\": {},\n  \"qubit_count\": {},\n  \"shot_count\": {},\n  \"counts\": {{{}}},\n  \"shots\": [{}]\n}}\n",
+        seed,
+        measurements.first().map_or(0, |m| m.len()),
+        measurements.len(),
+        counts.join(", "),
+        shots.join(", ")
+    )
+}
+
+pub fn write_json<P: AsRef<Path>>(
+    path: P,
+    measurements: &[Vec<bool>],
+    seed: u64,
+) -> io::Result<()> {
+    std::fs::write(path, to_json(measurements, seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_are_written_with_metadata() {
+        let measurements = vec![vec![true, false], vec![false, false], vec![true, false]];
+        let path = std::env::temp_dir().join(format!("results_{}", std::process::id()));
+
+        write_csv(&path, &measurements, 7).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# seed: 7\n# qubit_count: 2\n# shot_count: 3\nshot,outcome\n0,01\n1,00\n2,01\n\
+             \noutcome,count\n01,2\n00,1\n"
+        );
+
+        write_json(&path, &measurements, 7).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\n  \"seed\": 7,\n  \"qubit_count\": 2,\n  \"shot_count\": 3,\n  \
+             \"counts\": {\"01\": 2, \"00\": 1},\n  \"shots\": [\"01\", \"00\", \"01\"]\n}\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn measurements_are_formatted() {
+        // The qubits 0..3 of the value 6, 3..5 of the value 2, and 5..10 of the value 17.
+        let measurement = [
+            false, true, true, false, true, true, false, false, false, true,
+        ];
+        let mut options = FormatOptions::default();
+        assert_eq!(options.format(&measurement), "|1000110110>");
+        options.register_sizes = vec![3, 2];
+        assert_eq!(options.format(&measurement), "|10001 10 110>");
+        assert_eq!(options.wildcard(10, 3), "|***** *1 ***>");
+        options.bit_order = BitOrder::LsbFirst;
+        assert_eq!(options.format(&measurement), "|011 01 10001>");
+        options.outcome_format = OutcomeFormat::Decimal;
+        assert_eq!(options.format(&measurement), "|6 2 17>");
+        options.bit_order = BitOrder::MsbFirst;
+        options.outcome_format = OutcomeFormat::Hexadecimal;
+        assert_eq!(options.format(&measurement), "|11 2 6>");
+        options.register_sizes = vec![];
+        assert_eq!(options.format(&measurement), "|236>");
+        assert_eq!(
+            OutcomeFormat::from_name("hex"),
+            Some(options.outcome_format)
+        );
+        assert_eq!(BitOrder::from_name("lsb"), Some(BitOrder::LsbFirst));
+    }
+
+    // The outcome |01⟩ twice, and |00⟩ and |11⟩ once.
+    fn example_result() -> EvaluationResult {
+        evaluate(vec![
+            vec![true, false],
+            vec![false, false],
+            vec![true, false],
+            vec![true, true],
+        ])
+    }
+
+    #[test]
+    fn evaluation_counts_measurements() {
+        let result = example_result();
+        assert_eq!(result.qubit_count, 2);
+        assert_eq!(result.shot_count, 4);
+        assert_eq!(
+            result.sorted_counts(),
+            vec![
+                (vec![true, false], 2),
+                (vec![false, false], 1),
+                (vec![true, true], 1)
+            ]
+        );
+        assert_eq!(result.probability(&[true, false]), 0.5);
+        assert_eq!(result.probability(&[false, true]), 0.0);
+        assert_eq!(result.one_counts, vec![3, 1]);
+        assert_eq!(result.marginals(), vec![0.75, 0.25]);
+        assert_eq!(result.probabilities(), vec![0.25, 0.5, 0.0, 0.25]);
+    }
+
+    #[test]
+    fn histogram_omits_rare_measurements() {
+        let result = example_result();
+        assert_eq!(
+            result.histogram(8, 0.3),
+            "|01> ########  50.0%\n2 measurements below 30% omitted\n"
+        );
+        assert_eq!(
+            result.histogram(4, 0.0),
+            "|01> ####  50.0%\n|00> ##    25.0%\n|11> ##    25.0%\n"
+        );
+    }
+
+    #[test]
+    fn histogram_follows_bit_order() {
+        let result = example_result().with_format_options(FormatOptions {
+            bit_order: BitOrder::LsbFirst,
+            ..FormatOptions::default()
+        });
+        assert_eq!(
+            result.histogram(4, 0.0),
+            "|10> ####  50.0%\n|00> ##    25.0%\n|11> ##    25.0%\n"
         );
+        assert_eq!(result.format_options.wildcard(3, 0), "|1**>");
+    }
+
+    #[test]
+    fn distributions_are_compared() {
+        let result = example_result();
+        let distances = compare(&result.probabilities(), &[0.5, 0.5, 0.0, 0.0]);
+        assert!((distances.total_variation - 0.25).abs() < 1e-12);
+        assert_eq!(distances.kl_divergence, f64::INFINITY);
+        let hellinger = (1.0 - 0.5f64.sqrt() / 2.0 - 0.5).sqrt();
+        assert!((distances.hellinger - hellinger).abs() < 1e-12);
+        let distances = compare(&[0.5, 0.5], &[0.25, 0.75]);
+        assert!(
+            (distances.kl_divergence - (0.5 * 2.0f64.ln() + 0.5 * (2.0f64 / 3.0).ln())).abs()
+                < 1e-12
+        );
+        assert_eq!(compare(&[0.5, 0.5], &[0.5, 0.5]).hellinger, 0.0);
+    }
+
+    #[test]
+    fn chi_squared_test_of_counts() {
+        // The statistic 4 of 1 degree of freedom has the p-value erfc(sqrt(2)) = 0.0455, and the
+        // statistic 15 of 2 degrees of freedom e^-7.5.
+        let test = chi_squared_test(&[60, 40], &[0.5, 0.5]);
+        assert_eq!(test.degrees_of_freedom, 1);
+        assert!((test.statistic - 4.0).abs() < 1e-12);
+        assert!((test.p_value - 0.045_500_263_896_358_4).abs() < 1e-10);
+        let third = 1.0 / 3.0;
+        let test = chi_squared_test(&[0, 20, 5, 5], &[0.0, third, third, third]);
+        assert_eq!(test.degrees_of_freedom, 2);
+        assert!((test.statistic - 15.0).abs() < 1e-12);
+        assert!((test.p_value - (-7.5f64).exp()).abs() < 1e-12);
+        // Q(2, x) = e^-x (1 + x) by the series below x = 3.
+        assert!((upper_incomplete_gamma(2.0, 0.5) - 1.5 * (-0.5f64).exp()).abs() < 1e-12);
+        let test = chi_squared_test(&[15, 5, 5, 5], &[0.5, 0.5 / 3.0, 0.5 / 3.0, 0.5 / 3.0]);
+        assert!(test.statistic.abs() < 1e-12 && (test.p_value - 1.0).abs() < 1e-12);
+        let result = example_result();
+        assert_eq!(result.chi_squared_test(&[0.5, 0.5, 0.0, 0.0]).p_value, 0.0);
+    }
+
+    #[test]
+    fn correlations_of_qubits() {
+        let result = example_result();
+        let correlations = result.correlations();
+        assert_eq!(
+            correlations.covariance,
+            vec![vec![0.1875, 0.0625], vec![0.0625, 0.1875]]
+        );
+        assert!((correlations.correlation[0][1] - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(correlations.correlation[1][1], 1.0);
+        // The correlation with a qubit of a constant value is 0.
+        let constant = evaluate(vec![vec![true, false], vec![false, false]]);
+        assert_eq!(constant.correlations().correlation[0][1], 0.0);
+    }
+
+    #[test]
+    fn confidence_intervals_of_probabilities() {
+        let result = example_result();
+        let (lower, upper) = result.confidence_interval(&[true, false], CONFIDENCE_Z);
+        assert!((lower + upper - 1.0).abs() < 1e-12 && (0.15..0.2).contains(&lower));
+        let (lower, upper) = result.confidence_interval(&[false, true], CONFIDENCE_Z);
+        assert!(lower.abs() < 1e-12 && (upper - 0.9604 / 1.9604).abs() < 1e-4);
+    }
+
+    #[test]
+    fn measurements_are_post_selected() {
+        let result = example_result();
+        let selected = result.post_select(1, false);
+        assert_eq!(selected.shot_count, 3);
+        assert_eq!(selected.one_counts, vec![2, 0]);
+        assert!((selected.probability(&[true, false]) - 2.0 / 3.0).abs() < 1e-12);
+        let even = result.filter(|measurement| measurement[0] == measurement[1]);
+        assert_eq!(even.probabilities(), vec![0.5, 0.0, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn expectation_of_outcomes() {
+        let result = example_result();
+        let parity = |index: u64| {
+            if index.count_ones().is_multiple_of(2) {
+                1.0
+            } else {
+                -1.0
+            }
+        };
+        assert_eq!(result.expectation(parity), 0.0);
+        assert_eq!(result.expectation(|index| index as f64), 1.25);
     }
 }
@@ -0,0 +1,178 @@
+/*
+Charts of the evaluation results as PNG images, by the plots feature.
+
+The histogram has a bar of the probability of each measured outcome, in the order of the
+bitstrings with the highest qubit number first and labeled by the formatting options of the
+result. The line plot draws the probabilities of a few series, such as the success probability of
+an algorithm, against the iteration from 0.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::io;
+use std::path::Path;
+
+use plotters::prelude::*;
+
+use crate::evaluation::EvaluationResult;
+
+// The size of the images in pixels.
+const IMAGE_SIZE: (u32, u32) = (800, 600);
+const FONT: &str = "sans-serif";
+const CAPTION_FONT_SIZE: u32 = 24;
+const POINT_SIZE: u32 = 4;
+// The colors of the series of the line plot, in turn.
+const COLORS: [RGBColor; 5] = [BLUE, RED, GREEN, MAGENTA, CYAN];
+
+fn plot_error<E: std::error::Error + Send + Sync>(error: DrawingAreaErrorKind<E>) -> io::Error {
+    io::Error::other(error.to_string())
+}
+
+impl EvaluationResult {
+    // Save the histogram of the probabilities of the measured outcomes as a PNG image.
+    pub fn save_histogram_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut outcomes = self.sorted_counts();
+        outcomes
+            .sort_by_key(|(measurement, _)| measurement.iter().rev().copied().collect::<Vec<_>>());
+        let labels: Vec<String> = outcomes
+            .iter()
+            .map(|(measurement, _)| self.format_options.format(measurement))
+            .collect();
+        let max_probability = outcomes
+            .iter()
+            .map(|&(_, count)| count as f64 / self.shot_count as f64)
+            .fold(0.0, f64::max);
+
+        let root = BitMapBackend::new(path.as_ref(), IMAGE_SIZE).into_drawing_area();
+        root.fill(&WHITE).map_err(plot_error)?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(
+                format!("{} shots of {} qubits", self.shot_count, self.qubit_count),
+                (FONT, CAPTION_FONT_SIZE),
+            )
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(
+                (0..outcomes.len() - 1).into_segmented(),
+                0.0..(1.1 * max_probability).min(1.0),
+            )
+            .map_err(plot_error)?;
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .x_labels(outcomes.len())
+            .x_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(index) => labels.get(*index).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_desc("Outcome")
+            .y_desc("Probability")
+            .draw()
+            .map_err(plot_error)?;
+        chart
+            .draw_series(
+                Histogram::vertical(&chart)
+                    .style(BLUE.filled())
+                    .margin(5)
+                    .data(outcomes.iter().enumerate().map(|(index, &(_, count))| {
+                        (index, count as f64 / self.shot_count as f64)
+                    })),
+            )
+            .map_err(plot_error)?;
+        root.present().map_err(plot_error)
+    }
+}
+
+// Save the line plot of the named series of the probabilities against the iteration as a PNG
+// image.
+pub fn save_probability_plot_png<P: AsRef<Path>>(
+    path: P,
+    title: &str,
+    series: &[(&str, &[f64])],
+) -> io::Result<()> {
+    let iteration_count = series
+        .iter()
+        .map(|(_, probabilities)| probabilities.len())
+        .max()
+        .unwrap_or(0);
+    let root = BitMapBackend::new(path.as_ref(), IMAGE_SIZE).into_drawing_area();
+    root.fill(&WHITE).map_err(plot_error)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption(title, (FONT, CAPTION_FONT_SIZE))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0..iteration_count.saturating_sub(1).max(1), 0.0..1.0)
+        .map_err(plot_error)?;
+    chart
+        .configure_mesh()
+        .x_desc("Iteration")
+        .y_desc("Probability")
+        .draw()
+        .map_err(plot_error)?;
+    for (series_index, &(name, probabilities)) in series.iter().enumerate() {
+        let color = COLORS[series_index % COLORS.len()];
+        chart
+            .draw_series(LineSeries::new(
+                probabilities.iter().copied().enumerate(),
+                color.stroke_width(2),
+            ))
+            .map_err(plot_error)?
+            .label(name)
+            .legend(move |(x, y)| {
+                PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2))
+            });
+        // The points of the series, which stay visible where the lines overlap.
+        chart
+            .draw_series(
+                probabilities
+                    .iter()
+                    .enumerate()
+                    .map(|(iteration, &probability)| {
+                        Circle::new((iteration, probability), POINT_SIZE, color)
+                    }),
+            )
+            .map_err(plot_error)?;
+    }
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(plot_error)?;
+    root.present().map_err(plot_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::evaluate;
+
+    #[test]
+    fn charts_are_saved() {
+        let directory = std::env::temp_dir();
+        let histogram_path = directory.join("quantum_simulation_histogram.png");
+        let result = evaluate(vec![
+            vec![true, false],
+            vec![false, false],
+            vec![true, false],
+        ]);
+        result.save_histogram_png(&histogram_path).unwrap();
+        let line_plot_path = directory.join("quantum_simulation_probabilities.png");
+        save_probability_plot_png(
+            &line_plot_path,
+            "Probabilities",
+            &[
+                ("success", &[0.25, 1.0, 0.25]),
+                ("failure", &[0.75, 0.0, 0.75]),
+            ],
+        )
+        .unwrap();
+        for path in [histogram_path, line_plot_path] {
+            // The PNG signature.
+            assert_eq!(std::fs::read(&path).unwrap()[..4], [0x89, b'P', b'N', b'G']);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,784 @@
+/*
+Quantum extended stabilizer simulation of the Clifford circuits with a few non-Clifford gates.
+
+The state is a sum of stabilizer states, each in the CH-form of Bravyi, Browne, Calpin, Campbell,
+Gosset, and Howard: ω U_C U_H |s⟩, where U_C is a Clifford circuit of S, CZ, and CNOT gates which
+maps |0...0⟩ to itself, U_H is the product of the Hadamard gates on the qubits of the bits v,
+s is a basis state, and ω is the complex coefficient. U_C is given by its binary matrices F, G,
+and M and its phases γ: U_C† Z_p U_C is the product of Z_j^G[p, j], and U_C† X_p U_C is
+i^γ[p] times the product of X_j^F[p, j] followed by the product of Z_j^M[p, j]. Unlike the
+stabilizer tableau, the CH-form keeps the global phase of the state, so the amplitudes of the sum
+are exact. The Clifford gates update each stabilizer state in O(n) for S, CZ, and CNOT and in
+O(n^2) for H, and the amplitude of a basis state takes O(n^2).
+
+A non-Clifford gate is the sum of Clifford gates, T = (1 + e^(iπ/4))/2 I + (1 - e^(iπ/4))/2 Z,
+RZ by the other angles cos(θ/2) I - i sin(θ/2) Z, CPHASE (1 + e^(iθ))/2 I + (1 - e^(iθ))/2 CZ,
+and CCZ the sum of the 8 products of Z on its qubits, so each of them multiplies the stabilizer
+states by 2, or 8 for the Toffoli gate, up to MAX_STATE_COUNT.
+
+The measurements are sampled gate by gate, with the method of Bravyi, Gosset, and Liu: a basis
+state sampled from the current state is kept, each gate preserves the distribution of the qubits
+it does not act on, so after a permutation gate the basis state is permuted, after a diagonal gate
+it is kept, and after the other gates its bits of the qubits of the gate are sampled again from
+the amplitudes of the candidates. The measured values are the bits of the basis state, and the
+stabilizer states are then projected on them. After a measurement the amplitudes are known only
+up to a common factor.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4, SQRT_2};
+
+use num_complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::simulation::{check_qubits, Simulation};
+use crate::stabilizer_simulation::quarter_turns;
+
+// The most stabilizer states of the sum, for 16 T gates.
+pub const MAX_STATE_COUNT: usize = 1 << 16;
+
+// i to the power of the exponent modulo 4.
+fn i_power(exponent: u32) -> Complex<f64> {
+    match exponent % 4 {
+        0 => Complex::new(1.0, 0.0),
+        1 => Complex::new(0.0, 1.0),
+        2 => Complex::new(-1.0, 0.0),
+        _ => Complex::new(0.0, -1.0),
+    }
+}
+
+fn bit(bits: &[u64], j: usize) -> bool {
+    (bits[j / 64] >> (j % 64)) & 1 == 1
+}
+
+fn flip_bit(bits: &mut [u64], j: usize) {
+    bits[j / 64] ^= 1 << (j % 64);
+}
+
+fn set_bit(bits: &mut [u64], j: usize, value: bool) {
+    if bit(bits, j) != value {
+        flip_bit(bits, j);
+    }
+}
+
+// The parity of the bits set in both of the bit strings.
+fn parity(a: &[u64], b: &[u64]) -> u32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a & b).count_ones())
+        .sum::<u32>()
+        % 2
+}
+
+fn set_bits(bits: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    bits.iter().enumerate().flat_map(|(w, &word)| {
+        (0..64)
+            .filter(move |b| (word >> b) & 1 == 1)
+            .map(move |b| 64 * w + b)
+    })
+}
+
+// The square binary matrix with its rows of words.
+#[derive(Clone, Debug)]
+struct BitMatrix {
+    word_count: usize,
+    words: Vec<u64>,
+}
+
+impl BitMatrix {
+    fn new(qubit_count: usize, word_count: usize, is_identity: bool) -> BitMatrix {
+        let mut matrix = BitMatrix {
+            word_count,
+            words: vec![0; qubit_count * word_count],
+        };
+        if is_identity {
+            for i in 0..qubit_count {
+                flip_bit(matrix.row_mut(i), i);
+            }
+        }
+        matrix
+    }
+
+    fn row(&self, i: usize) -> &[u64] {
+        &self.words[i * self.word_count..(i + 1) * self.word_count]
+    }
+
+    fn row_mut(&mut self, i: usize) -> &mut [u64] {
+        &mut self.words[i * self.word_count..(i + 1) * self.word_count]
+    }
+
+    fn xor_row(&mut self, target: usize, source: &[u64]) {
+        for (word, source_word) in self.row_mut(target).iter_mut().zip(source.iter()) {
+            *word ^= source_word;
+        }
+    }
+
+    // Add the column source to the column target.
+    fn xor_column(&mut self, target: usize, source: usize) {
+        for row in self.words.chunks_exact_mut(self.word_count) {
+            if bit(row, source) {
+                flip_bit(row, target);
+            }
+        }
+    }
+
+    fn get(&self, i: usize, j: usize) -> bool {
+        bit(self.row(i), j)
+    }
+
+    fn flip(&mut self, i: usize, j: usize) {
+        flip_bit(self.row_mut(i), j);
+    }
+}
+
+// A stabilizer state ω U_C U_H |s⟩ in the CH-form.
+#[derive(Clone, Debug)]
+struct ChForm {
+    f: BitMatrix,
+    g: BitMatrix,
+    m: BitMatrix,
+    gamma: Vec<u32>,
+    v: Vec<u64>,
+    s: Vec<u64>,
+    omega: Complex<f64>,
+}
+
+impl ChForm {
+    // The basis state with the bits.
+    fn basis_state(qubit_count: usize, bits: &[u64]) -> ChForm {
+        let word_count = bits.len();
+        ChForm {
+            f: BitMatrix::new(qubit_count, word_count, true),
+            g: BitMatrix::new(qubit_count, word_count, true),
+            m: BitMatrix::new(qubit_count, word_count, false),
+            gamma: vec![0; qubit_count],
+            v: vec![0; word_count],
+            s: bits.to_vec(),
+            omega: Complex::new(1.0, 0.0),
+        }
+    }
+
+    // The gates multiplied on the left of U_C, updating its action on the Pauli operators.
+    fn left_s(&mut self, q: usize) {
+        let g_row = self.g.row(q).to_vec();
+        self.m.xor_row(q, &g_row);
+        self.gamma[q] = (self.gamma[q] + 3) % 4;
+    }
+
+    fn left_cz(&mut self, q: usize, r: usize) {
+        let (g_row_q, g_row_r) = (self.g.row(q).to_vec(), self.g.row(r).to_vec());
+        self.m.xor_row(q, &g_row_r);
+        self.m.xor_row(r, &g_row_q);
+    }
+
+    fn left_cnot(&mut self, q: usize, r: usize) {
+        self.gamma[q] =
+            (self.gamma[q] + self.gamma[r] + 2 * parity(self.m.row(q), self.f.row(r))) % 4;
+        let (f_row_r, m_row_r, g_row_q) = (
+            self.f.row(r).to_vec(),
+            self.m.row(r).to_vec(),
+            self.g.row(q).to_vec(),
+        );
+        self.f.xor_row(q, &f_row_r);
+        self.m.xor_row(q, &m_row_r);
+        self.g.xor_row(r, &g_row_q);
+    }
+
+    // The gates multiplied on the right of U_C, used to absorb the superpositions of two
+    // basis states.
+    fn right_s(&mut self, q: usize) {
+        for p in 0..self.gamma.len() {
+            if self.f.get(p, q) {
+                self.m.flip(p, q);
+                self.gamma[p] = (self.gamma[p] + 3) % 4;
+            }
+        }
+    }
+
+    fn right_cz(&mut self, q: usize, r: usize) {
+        for p in 0..self.gamma.len() {
+            let (f_q, f_r) = (self.f.get(p, q), self.f.get(p, r));
+            if f_q && f_r {
+                self.gamma[p] = (self.gamma[p] + 2) % 4;
+            }
+            if f_r {
+                self.m.flip(p, q);
+            }
+            if f_q {
+                self.m.flip(p, r);
+            }
+        }
+    }
+
+    fn right_cnot(&mut self, q: usize, r: usize) {
+        self.f.xor_column(r, q);
+        self.g.xor_column(q, r);
+        self.m.xor_column(q, r);
+    }
+
+    // Apply the Pauli operator i^e X^a Z^b, to which U_C maps the applied Pauli operator,
+    // to U_H |s⟩, through the Hadamard gates which exchange X and Z.
+    fn apply_pauli_image(&mut self, exponent: u32, a: &[u64], b: &[u64]) {
+        let (a_mapped, b_mapped) = self.map_through_hadamards(a, b);
+        let sign_exponent = self.hadamard_sign(a, b) + parity(&b_mapped, &self.s);
+        for (s, a) in self.s.iter_mut().zip(a_mapped.iter()) {
+            *s ^= a;
+        }
+        self.omega *= i_power(exponent + 2 * sign_exponent);
+    }
+
+    // The bits of X and Z of U_H X^a Z^b U_H.
+    fn map_through_hadamards(&self, a: &[u64], b: &[u64]) -> (Vec<u64>, Vec<u64>) {
+        let a_mapped = (0..a.len())
+            .map(|w| (a[w] & !self.v[w]) | (b[w] & self.v[w]))
+            .collect();
+        let b_mapped = (0..a.len())
+            .map(|w| (b[w] & !self.v[w]) | (a[w] & self.v[w]))
+            .collect();
+        (a_mapped, b_mapped)
+    }
+
+    // The parity of the signs of the exchanges of X Z into Z X by the Hadamard gates.
+    fn hadamard_sign(&self, a: &[u64], b: &[u64]) -> u32 {
+        (0..a.len())
+            .map(|w| (a[w] & b[w] & self.v[w]).count_ones())
+            .sum::<u32>()
+            % 2
+    }
+
+    fn left_x(&mut self, q: usize) {
+        let (f_row, m_row) = (self.f.row(q).to_vec(), self.m.row(q).to_vec());
+        self.apply_pauli_image(self.gamma[q], &f_row, &m_row);
+    }
+
+    fn left_z(&mut self, q: usize) {
+        let g_row = self.g.row(q).to_vec();
+        self.apply_pauli_image(0, &vec![0; g_row.len()], &g_row);
+    }
+
+    // H = (X + Z)/sqrt(2) maps the state to the sum of the two basis states
+    // of the images of X and Z.
+    fn left_h(&mut self, q: usize) {
+        let (f_row, m_row, g_row) = (
+            self.f.row(q).to_vec(),
+            self.m.row(q).to_vec(),
+            self.g.row(q).to_vec(),
+        );
+        let (a_x, b_x) = self.map_through_hadamards(&f_row, &m_row);
+        let x_exponent =
+            self.gamma[q] + 2 * (self.hadamard_sign(&f_row, &m_row) + parity(&b_x, &self.s));
+        let u: Vec<u64> = self.s.iter().zip(a_x.iter()).map(|(s, a)| s ^ a).collect();
+        let (a_z, b_z) = self.map_through_hadamards(&vec![0; g_row.len()], &g_row);
+        let z_exponent = 2 * parity(&b_z, &self.s);
+        let t: Vec<u64> = self.s.iter().zip(a_z.iter()).map(|(s, a)| s ^ a).collect();
+
+        if t == u {
+            self.omega *= (i_power(z_exponent) + i_power(x_exponent)) * FRAC_1_SQRT_2;
+            self.s = t;
+        } else {
+            self.omega *= i_power(z_exponent) * FRAC_1_SQRT_2;
+            self.superpose(t, u, (x_exponent + 4 - z_exponent) % 4);
+        }
+    }
+
+    // Replace U_H (|t⟩ + i^δ |u⟩) by a CH-form, for the distinct basis states t and u.
+    // The CNOTs from a differing qubit q of t and u to the other differing qubits, which pass
+    // through U_H as CNOT, CZ, or reversed CNOT gates of U_C for the qubit q without a Hadamard
+    // gate when there is one, leave only the qubit q in the superposition |0⟩ + i^δ |1⟩,
+    // which is S^δ H |0⟩ up to a factor, or H of it.
+    fn superpose(&mut self, mut t: Vec<u64>, mut u: Vec<u64>, mut delta: u32) {
+        let differences: Vec<u64> = t.iter().zip(u.iter()).map(|(t, u)| t ^ u).collect();
+        let q = set_bits(&differences)
+            .find(|&j| !bit(&self.v, j))
+            .or_else(|| set_bits(&differences).next())
+            .unwrap();
+        if bit(&t, q) {
+            std::mem::swap(&mut t, &mut u);
+            self.omega *= i_power(delta);
+            delta = (4 - delta) % 4;
+        }
+        let has_hadamard = bit(&self.v, q);
+        for j in set_bits(&differences)
+            .filter(|&j| j != q)
+            .collect::<Vec<usize>>()
+        {
+            if has_hadamard {
+                self.right_cnot(j, q);
+            } else if bit(&self.v, j) {
+                self.right_cz(q, j);
+            } else {
+                self.right_cnot(q, j);
+            }
+        }
+
+        self.s = t;
+        if !has_hadamard {
+            for _ in 0..delta {
+                self.right_s(q);
+            }
+            set_bit(&mut self.v, q, true);
+            self.omega *= SQRT_2;
+            return;
+        }
+        match delta {
+            0 | 2 => {
+                set_bit(&mut self.v, q, false);
+                set_bit(&mut self.s, q, delta == 2);
+                self.omega *= SQRT_2;
+            }
+            1 => {
+                for _ in 0..3 {
+                    self.right_s(q);
+                }
+                self.omega *= Complex::new(0.0, FRAC_PI_4).exp() * SQRT_2;
+            }
+            _ => {
+                self.right_s(q);
+                self.omega *= Complex::new(0.0, -FRAC_PI_4).exp() * SQRT_2;
+            }
+        }
+    }
+
+    // Project the state on the value of the qubit, (I ± Z)/2 giving U_H times the sum of |s⟩
+    // and the image of Z. Return whether the projected state is nonzero.
+    fn project(&mut self, q: usize, value: bool) -> bool {
+        let g_row = self.g.row(q).to_vec();
+        let (a_z, b_z) = self.map_through_hadamards(&vec![0; g_row.len()], &g_row);
+        let is_negative = (parity(&b_z, &self.s) == 1) != value;
+        if a_z.iter().all(|&a| a == 0) {
+            return !is_negative;
+        }
+        let u: Vec<u64> = self.s.iter().zip(a_z.iter()).map(|(s, a)| s ^ a).collect();
+        self.omega *= 0.5;
+        self.superpose(self.s.clone(), u, if is_negative { 2 } else { 0 });
+        true
+    }
+
+    // The amplitude ⟨x|ψ⟩ = ω ⟨0| X^x U_C U_H |s⟩, with U_C† X^x U_C = i^e X^f Z^m,
+    // times 2^(hadamard_count/2) to keep the amplitudes of many Hadamard gates from underflowing.
+    fn amplitude(&self, x: &[u64], hadamard_count: u32) -> Complex<f64> {
+        let word_count = x.len();
+        let (mut f, mut m) = (vec![0u64; word_count], vec![0u64; word_count]);
+        let mut exponent = 0;
+        for p in set_bits(x) {
+            exponent += self.gamma[p] + 2 * parity(&m, self.f.row(p));
+            for w in 0..word_count {
+                f[w] ^= self.f.row(p)[w];
+                m[w] ^= self.m.row(p)[w];
+            }
+        }
+        exponent += 2 * parity(&m, &f);
+        if (0..word_count).any(|w| (f[w] ^ self.s[w]) & !self.v[w] != 0) {
+            return Complex::new(0.0, 0.0);
+        }
+        let f_and_s: Vec<u64> = f.iter().zip(self.s.iter()).map(|(f, s)| f & s).collect();
+        exponent += 2 * parity(&f_and_s, &self.v);
+        let state_hadamard_count: u32 = self.v.iter().map(|v| v.count_ones()).sum();
+        self.omega
+            * i_power(exponent)
+            * 2f64.powf(-(state_hadamard_count as f64 - hadamard_count as f64) / 2.0)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExtendedStabilizerSimulation {
+    qubit_count: usize,
+    states: Vec<ChForm>,
+    // The basis state sampled from the state.
+    sample: Vec<u64>,
+    rng: ChaCha12Rng,
+}
+
+impl ExtendedStabilizerSimulation {
+    pub fn new(qubit_count: usize, rnd_seed: u64) -> ExtendedStabilizerSimulation {
+        let mut simulation = ExtendedStabilizerSimulation {
+            qubit_count,
+            states: Vec::new(),
+            sample: vec![0; qubit_count.div_ceil(64).max(1)],
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The number of the stabilizer states of the sum.
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    // The amplitude of the basis state with the values of the qubits, exact before the measurements.
+    pub fn amplitude(&self, values: &[bool]) -> Complex<f64> {
+        assert!(
+            values.len() == self.qubit_count,
+            "The basis state has to have a value for each of the qubits {}.",
+            self.qubit_count
+        );
+        let mut x = vec![0; self.sample.len()];
+        for (qubit_number, _) in values.iter().enumerate().filter(|(_, &value)| value) {
+            flip_bit(&mut x, qubit_number);
+        }
+        self.states.iter().map(|state| state.amplitude(&x, 0)).sum()
+    }
+
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    fn for_each_state<F: Fn(&mut ChForm)>(&mut self, update: F) {
+        for state in self.states.iter_mut() {
+            update(state);
+        }
+    }
+
+    // Replace each stabilizer state by the sum of the terms, the term k being the coefficient
+    // times the Clifford gates applied by apply_term(k, state).
+    fn expand<F: Fn(usize, &mut ChForm)>(&mut self, coefficients: &[Complex<f64>], apply_term: F) {
+        assert!(
+            self.states.len() * coefficients.len() <= MAX_STATE_COUNT,
+            "The extended stabilizer simulation cannot exceed {} stabilizer states.",
+            MAX_STATE_COUNT
+        );
+        let mut states = Vec::with_capacity(self.states.len() * coefficients.len());
+        for state in self.states.iter() {
+            for (k, &coefficient) in coefficients.iter().enumerate() {
+                let mut term = state.clone();
+                apply_term(k, &mut term);
+                term.omega *= coefficient;
+                states.push(term);
+            }
+        }
+        self.states = states;
+    }
+
+    // Sample again the value of the qubit in the basis state from the amplitudes
+    // of the two candidates.
+    fn resample(&mut self, qubit_number: usize) {
+        let hadamard_count = self
+            .states
+            .iter()
+            .map(|state| state.v.iter().map(|v| v.count_ones()).sum::<u32>())
+            .min()
+            .unwrap_or(0);
+        let mut candidate = self.sample.clone();
+        let mut probabilities = [0.0; 2];
+        for (value, probability) in probabilities.iter_mut().enumerate() {
+            set_bit(&mut candidate, qubit_number, value == 1);
+            let amplitude: Complex<f64> = self
+                .states
+                .iter()
+                .map(|state| state.amplitude(&candidate, hadamard_count))
+                .sum();
+            *probability = amplitude.norm_sqr();
+        }
+        let is_one =
+            self.rng.gen::<f64>() * (probabilities[0] + probabilities[1]) < probabilities[1];
+        set_bit(&mut self.sample, qubit_number, is_one);
+    }
+
+    // Keep the largest coefficient at 1, since the projections shrink the state.
+    fn rescale(&mut self) {
+        let largest = self
+            .states
+            .iter()
+            .map(|state| state.omega.norm())
+            .fold(0.0, f64::max);
+        for state in self.states.iter_mut() {
+            state.omega /= largest;
+        }
+    }
+
+    fn apply_h(&mut self, qubit_number: usize) {
+        self.for_each_state(|state| state.left_h(qubit_number));
+    }
+
+    // RZ by the multiples of π/2 is e^(-ikπ/4) S^k, and otherwise the sum of I and Z.
+    fn apply_rz(&mut self, theta: f64, qubit_number: usize) {
+        match quarter_turns(theta) {
+            Some(k) => {
+                // The phase of the angle itself, since RZ(θ + 2π) = -RZ(θ).
+                let phase = Complex::new(0.0, -theta / 2.0).exp();
+                self.for_each_state(|state| {
+                    for _ in 0..k {
+                        state.left_s(qubit_number);
+                    }
+                    state.omega *= phase;
+                });
+            }
+            None => {
+                let coefficients = [
+                    Complex::new((theta / 2.0).cos(), 0.0),
+                    Complex::new(0.0, -(theta / 2.0).sin()),
+                ];
+                self.expand(&coefficients, |k, state| {
+                    if k == 1 {
+                        state.left_z(qubit_number);
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl Simulation for ExtendedStabilizerSimulation {
+    fn reset(&mut self) {
+        self.sample.fill(0);
+        self.states = vec![ChForm::basis_state(self.qubit_count, &self.sample)];
+    }
+
+    // Measure all the qubits in the Z-basis, leaving the sampled basis state.
+    fn measure_all(&mut self) -> Vec<bool> {
+        self.states = vec![ChForm::basis_state(self.qubit_count, &self.sample)];
+        (0..self.qubit_count)
+            .map(|qubit_number| bit(&self.sample, qubit_number))
+            .collect()
+    }
+
+    // Measure the selected qubits in the Z-basis, projecting the stabilizer states
+    // on the values of the sampled basis state.
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
+        for &qubit_number in qubit_numbers.iter() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+        }
+        let measured_states: Vec<bool> = qubit_numbers
+            .iter()
+            .map(|&qubit_number| bit(&self.sample, qubit_number))
+            .collect();
+        for (&qubit_number, &value) in qubit_numbers.iter().zip(measured_states.iter()) {
+            self.states
+                .retain_mut(|state| state.project(qubit_number, value));
+        }
+        self.rescale();
+        measured_states
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.for_each_state(|state| state.left_x(qubit_number));
+        flip_bit(&mut self.sample, qubit_number);
+    }
+
+    // Y = i X Z.
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.for_each_state(|state| {
+            state.left_z(qubit_number);
+            state.left_x(qubit_number);
+            state.omega *= Complex::new(0.0, 1.0);
+        });
+        flip_bit(&mut self.sample, qubit_number);
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.for_each_state(|state| state.left_z(qubit_number));
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.apply_h(qubit_number);
+        self.resample(qubit_number);
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.for_each_state(|state| state.left_s(qubit_number));
+    }
+
+    fn t(&mut self, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        let phase = Complex::new(0.0, FRAC_PI_4).exp();
+        let coefficients = [(1.0 + phase) / 2.0, (1.0 - phase) / 2.0];
+        self.expand(&coefficients, |k, state| {
+            if k == 1 {
+                state.left_z(qubit_number);
+            }
+        });
+    }
+
+    // RX = H RZ H.
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.apply_h(qubit_number);
+        self.apply_rz(theta, qubit_number);
+        self.apply_h(qubit_number);
+        self.resample(qubit_number);
+    }
+
+    // RY = S RX S†.
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.for_each_state(|state| {
+            for _ in 0..3 {
+                state.left_s(qubit_number);
+            }
+        });
+        self.apply_h(qubit_number);
+        self.apply_rz(theta, qubit_number);
+        self.apply_h(qubit_number);
+        self.for_each_state(|state| state.left_s(qubit_number));
+        self.resample(qubit_number);
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        self.apply_rz(theta, qubit_number);
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.check_qubit_numbers(&[control_qubit_number, target_qubit_number]);
+        self.for_each_state(|state| state.left_cnot(control_qubit_number, target_qubit_number));
+        if bit(&self.sample, control_qubit_number) {
+            flip_bit(&mut self.sample, target_qubit_number);
+        }
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.check_qubit_numbers(&[control_qubit_number, target_qubit_number]);
+        self.for_each_state(|state| state.left_cz(control_qubit_number, target_qubit_number));
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        self.check_qubit_numbers(&[control_qubit_number, target_qubit_number]);
+        match quarter_turns(theta) {
+            Some(0) => {}
+            Some(2) => self.cz(control_qubit_number, target_qubit_number),
+            _ => {
+                let phase = Complex::new(0.0, theta).exp();
+                let coefficients = [(1.0 + phase) / 2.0, (1.0 - phase) / 2.0];
+                self.expand(&coefficients, |k, state| {
+                    if k == 1 {
+                        state.left_cz(control_qubit_number, target_qubit_number);
+                    }
+                });
+            }
+        }
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.cnot(qubit_number0, qubit_number1);
+        self.cnot(qubit_number1, qubit_number0);
+        self.cnot(qubit_number0, qubit_number1);
+    }
+
+    // U_f flips the qubit 0 when the qubit 1 is 0 if f(true), and when the qubit 1 is 1 if not f(false).
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        if f(true) {
+            self.pauli_x(qubit_number1);
+            self.cnot(qubit_number1, qubit_number0);
+            self.pauli_x(qubit_number1);
+        }
+        if !f(false) {
+            self.cnot(qubit_number1, qubit_number0);
+        }
+    }
+
+    // The Toffoli gate is H CCZ H on the target, CCZ = I - 2 |111⟩⟨111| being the sum of
+    // the products of Z on the subsets of its qubits, 3/4 for the empty one and ±1/4
+    // for the others, positive for the odd ones.
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        let qubit_numbers = [
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        ];
+        self.check_qubit_numbers(&qubit_numbers);
+        let coefficients: Vec<Complex<f64>> = (0..8u32)
+            .map(|subset| match subset.count_ones() {
+                0 => Complex::new(0.75, 0.0),
+                1 | 3 => Complex::new(0.25, 0.0),
+                _ => Complex::new(-0.25, 0.0),
+            })
+            .collect();
+        self.apply_h(target_qubit_number);
+        self.expand(&coefficients, |subset, state| {
+            for (k, &qubit_number) in qubit_numbers.iter().enumerate() {
+                if (subset >> k) & 1 == 1 {
+                    state.left_z(qubit_number);
+                }
+            }
+        });
+        self.apply_h(target_qubit_number);
+        if bit(&self.sample, control_qubit_number0) && bit(&self.sample, control_qubit_number1) {
+            flip_bit(&mut self.sample, target_qubit_number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Gate;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn amplitudes_match_state_vector() {
+        let gates = [
+            Gate::Hadamard(0),
+            Gate::T(0),
+            Gate::Cnot(0, 1),
+            Gate::Hadamard(2),
+            Gate::S(2),
+            Gate::Cz(2, 0),
+            Gate::Rx(0.3, 3),
+            Gate::T(1),
+            Gate::Hadamard(1),
+            Gate::PauliY(3),
+            Gate::Cphase(0.7, 1, 3),
+            Gate::Ry(std::f64::consts::FRAC_PI_2, 0),
+            Gate::Swap(3, 2),
+            Gate::Toffoli(0, 2, 1),
+            Gate::UF(|x| !x, 3, 0),
+            Gate::Rz(-1.1, 2),
+            Gate::Hadamard(3),
+        ];
+        let mut simulation = ExtendedStabilizerSimulation::new(4, 0);
+        let mut state_vector_simulation = QuantumSimulation::new(4, 0);
+        for gate in gates.iter() {
+            gate.apply(&mut simulation);
+            gate.apply(&mut state_vector_simulation);
+        }
+        for (i, amplitude) in state_vector_simulation.amplitudes().iter().enumerate() {
+            let values: Vec<bool> = (0..4).map(|q| (i >> q) & 1 == 1).collect();
+            assert!((simulation.amplitude(&values) - amplitude).norm() < 1e-10);
+        }
+
+        // The sampled outcomes follow the probabilities of the state vector.
+        let run_count = 1000;
+        let mut counts = [0usize; 16];
+        for seed in 0..run_count {
+            let mut simulation = ExtendedStabilizerSimulation::new(4, seed);
+            for gate in gates.iter() {
+                gate.apply(&mut simulation);
+            }
+            let measured_states = simulation.measure(vec![2]);
+            assert_eq!(simulation.measure(vec![2]), measured_states);
+            let values = simulation.measure_all();
+            counts[(0..4).filter(|&q| values[q]).map(|q| 1 << q).sum::<usize>()] += 1;
+        }
+        for (count, amplitude) in counts
+            .iter()
+            .zip(state_vector_simulation.amplitudes().iter())
+        {
+            assert!((*count as f64 / run_count as f64 - amplitude.norm_sqr()).abs() < 0.05);
+        }
+    }
+}
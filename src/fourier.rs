@@ -0,0 +1,101 @@
+/*
+Quantum Fourier transform applied directly to the qubits of a simulation.
+
+The qubit_numbers[j] holds the bit j of the transformed number, so that the transform can act on
+any register of a larger simulation, as in the phase estimation and the arithmetic in the
+Fourier basis. The gates are those of blocks::qft and blocks::approximate_qft.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::blocks;
+use crate::circuit::Circuit;
+use crate::simulation::Simulation;
+
+// Apply the gates of the block, its qubit j placed on the qubit qubit_numbers[j].
+fn apply_block(simulation: &mut dyn Simulation, block: &Circuit, qubit_numbers: &[usize]) {
+    for (i, &qubit_number) in qubit_numbers.iter().enumerate() {
+        assert!(
+            !qubit_numbers[..i].contains(&qubit_number),
+            "The qubits of the Fourier transform have to be distinct."
+        );
+    }
+    for gate in block.gates().iter() {
+        gate.map_qubits(|q| qubit_numbers[q]).apply(simulation);
+    }
+}
+
+pub fn qft(simulation: &mut dyn Simulation, qubit_numbers: &[usize]) {
+    apply_block(simulation, &blocks::qft(qubit_numbers.len()), qubit_numbers);
+}
+
+pub fn iqft(simulation: &mut dyn Simulation, qubit_numbers: &[usize]) {
+    apply_block(
+        simulation,
+        &blocks::iqft(qubit_numbers.len()),
+        qubit_numbers,
+    );
+}
+
+// The transform without the controlled phases π/2^m of m above the cutoff.
+pub fn approximate_qft(simulation: &mut dyn Simulation, qubit_numbers: &[usize], cutoff: usize) {
+    let block = blocks::approximate_qft(qubit_numbers.len(), cutoff);
+    apply_block(simulation, &block, qubit_numbers);
+}
+
+pub fn approximate_iqft(simulation: &mut dyn Simulation, qubit_numbers: &[usize], cutoff: usize) {
+    let block = blocks::approximate_iqft(qubit_numbers.len(), cutoff);
+    apply_block(simulation, &block, qubit_numbers);
+}
+
+#[cfg(test)]
+mod tests {
+    use num_complex::Complex;
+
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn transform_of_register() {
+        // The number 5 on the register of the qubits 3, 0, and 2, the qubit 1 being |1⟩.
+        let register = [3, 0, 2];
+        let mut simulation = QuantumSimulation::new(4, 0);
+        simulation.pauli_x(3);
+        simulation.pauli_x(2);
+        simulation.pauli_x(1);
+        qft(&mut simulation, &register);
+        for (i, amplitude) in simulation.amplitudes().iter().enumerate() {
+            if (i >> 1) & 1 == 0 {
+                assert!(amplitude.norm() < 1e-12);
+                continue;
+            }
+            let y = register
+                .iter()
+                .enumerate()
+                .map(|(bit, &q)| ((i >> q) & 1) << bit)
+                .sum::<usize>();
+            let phase = std::f64::consts::TAU * (5 * y) as f64 / 8.0;
+            assert!((amplitude - Complex::from_polar(1.0 / 8f64.sqrt(), phase)).norm() < 1e-12);
+        }
+        iqft(&mut simulation, &register);
+        assert!((simulation.amplitudes()[0b1110].norm() - 1.0).abs() < 1e-12);
+
+        // The approximate transform of the cutoff 2 of 6 qubits is close to the exact one.
+        let register: Vec<usize> = (0..6).collect();
+        let mut exact_simulation = QuantumSimulation::new(6, 0);
+        let mut approximate_simulation = QuantumSimulation::new(6, 0);
+        for simulation in [&mut exact_simulation, &mut approximate_simulation] {
+            simulation.pauli_x(0);
+            simulation.pauli_x(4);
+        }
+        qft(&mut exact_simulation, &register);
+        approximate_qft(&mut approximate_simulation, &register, 2);
+        let overlap: Complex<f64> = exact_simulation
+            .amplitudes()
+            .iter()
+            .zip(approximate_simulation.amplitudes().iter())
+            .map(|(a, b)| a.conj() * b)
+            .sum();
+        assert!(overlap.norm() > 0.9 && overlap.norm() < 1.0 - 1e-6);
+    }
+}
@@ -8,23 +8,8 @@ use num_complex::Complex;
 
 const INV_SQRT_2: f64 = 0.7071067811865475;
 
-pub(crate) fn pauli_x(
-    amplitude0: Complex<f64>,
-    amplitude1: Complex<f64>,
-) -> (Complex<f64>, Complex<f64>) {
-    (amplitude1, amplitude0)
-}
-
-pub(crate) fn pauli_y(
-    amplitude0: Complex<f64>,
-    amplitude1: Complex<f64>,
-) -> (Complex<f64>, Complex<f64>) {
-    (
-        Complex::new(0.0, -1.0) * amplitude1,
-        Complex::new(0.0, 1.0) * amplitude0,
-    )
-}
-
+// The gates with vectorized kernels on x86_64 are the kernels of the other targets.
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
 pub(crate) fn pauli_z(
     amplitude0: Complex<f64>,
     amplitude1: Complex<f64>,
@@ -32,6 +17,7 @@ pub(crate) fn pauli_z(
     (amplitude0, -amplitude1)
 }
 
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
 pub(crate) fn hadamard(
     amplitude0: Complex<f64>,
     amplitude1: Complex<f64>,
@@ -42,6 +28,7 @@ pub(crate) fn hadamard(
     )
 }
 
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
 pub(crate) fn s(
     amplitude0: Complex<f64>,
     amplitude1: Complex<f64>,
@@ -49,6 +36,7 @@ pub(crate) fn s(
     (amplitude0, Complex::new(0.0, 1.0) * amplitude1)
 }
 
+#[cfg_attr(target_arch = "x86_64", allow(dead_code))]
 pub(crate) fn t(
     amplitude0: Complex<f64>,
     amplitude1: Complex<f64>,
@@ -59,6 +47,65 @@ pub(crate) fn t(
     )
 }
 
+// The 1-qubit gates without vectorized kernels. The gate is matched once for each chunk
+// of the state vector, so that the loop over the pairs of amplitudes is specialized to it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum GateKernel {
+    PauliY,
+    // Rotation exp(-iθX/2) around the X-axis by cos(θ/2) and sin(θ/2).
+    Rx(f64, f64),
+    // Rotation exp(-iθY/2) around the Y-axis by cos(θ/2) and sin(θ/2).
+    Ry(f64, f64),
+    // Rotation exp(-iθZ/2) around the Z-axis by the phase e^{iθ/2}.
+    Rz(Complex<f64>),
+}
+
+impl GateKernel {
+    pub(crate) fn rx(theta: f64) -> GateKernel {
+        GateKernel::Rx((theta / 2.0).cos(), (theta / 2.0).sin())
+    }
+
+    pub(crate) fn ry(theta: f64) -> GateKernel {
+        GateKernel::Ry((theta / 2.0).cos(), (theta / 2.0).sin())
+    }
+
+    pub(crate) fn rz(theta: f64) -> GateKernel {
+        GateKernel::Rz(Complex::new(0.0, theta / 2.0).exp())
+    }
+
+    // Apply the gate to the pairs of the amplitudes with the qubit 0 and 1.
+    pub(crate) fn apply(&self, zeros: &mut [Complex<f64>], ones: &mut [Complex<f64>]) {
+        let pairs = zeros.iter_mut().zip(ones.iter_mut());
+        // The product -i a is Complex::new(a.im, -a.re).
+        match *self {
+            GateKernel::PauliY => {
+                for (a0, a1) in pairs {
+                    (*a0, *a1) = (Complex::new(a1.im, -a1.re), Complex::new(-a0.im, a0.re));
+                }
+            }
+            GateKernel::Rx(cos, sin) => {
+                for (a0, a1) in pairs {
+                    (*a0, *a1) = (
+                        cos * *a0 + sin * Complex::new(a1.im, -a1.re),
+                        sin * Complex::new(a0.im, -a0.re) + cos * *a1,
+                    );
+                }
+            }
+            GateKernel::Ry(cos, sin) => {
+                for (a0, a1) in pairs {
+                    (*a0, *a1) = (cos * *a0 - sin * *a1, sin * *a0 + cos * *a1);
+                }
+            }
+            GateKernel::Rz(phase) => {
+                let conjugate_phase = phase.conj();
+                for (a0, a1) in pairs {
+                    (*a0, *a1) = (conjugate_phase * *a0, phase * *a1);
+                }
+            }
+        }
+    }
+}
+
 //CX gate
 pub(crate) fn cnot(
     amplitude00: Complex<f64>,
@@ -78,6 +125,22 @@ pub(crate) fn cz(
     (amplitude00, amplitude01, amplitude10, -amplitude11)
 }
 
+// Controlled phase gate multiplying the amplitude of |11⟩ by e^{iθ}.
+#[allow(clippy::type_complexity)]
+pub(crate) fn cphase(
+    theta: f64,
+) -> impl Fn(
+    Complex<f64>,
+    Complex<f64>,
+    Complex<f64>,
+    Complex<f64>,
+) -> (Complex<f64>, Complex<f64>, Complex<f64>, Complex<f64>) {
+    let phase = Complex::new(0.0, theta).exp();
+    move |amplitude00, amplitude01, amplitude10, amplitude11| {
+        (amplitude00, amplitude01, amplitude10, phase * amplitude11)
+    }
+}
+
 pub(crate) fn swap(
     amplitude00: Complex<f64>,
     amplitude01: Complex<f64>,
@@ -87,6 +150,7 @@ pub(crate) fn swap(
     (amplitude00, amplitude10, amplitude01, amplitude11)
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub(crate) fn toffoli(
     amplitude000: Complex<f64>,
     amplitude001: Complex<f64>,
@@ -117,3 +181,56 @@ pub(crate) fn toffoli(
         amplitude011,
     )
 }
+
+// The 2x2 matrices of the 1-qubit gates in the order m00, m01, m10, m11,
+// for the simulations applying every gate as a matrix.
+pub type Matrix = [Complex<f64>; 4];
+
+const ZERO: Complex<f64> = Complex::new(0.0, 0.0);
+const ONE: Complex<f64> = Complex::new(1.0, 0.0);
+
+pub(crate) const PAULI_X_MATRIX: Matrix = [ZERO, ONE, ONE, ZERO];
+pub(crate) const PAULI_Y_MATRIX: Matrix =
+    [ZERO, Complex::new(0.0, -1.0), Complex::new(0.0, 1.0), ZERO];
+pub(crate) const PAULI_Z_MATRIX: Matrix = [ONE, ZERO, ZERO, Complex::new(-1.0, 0.0)];
+pub(crate) const HADAMARD_MATRIX: Matrix = [
+    Complex::new(INV_SQRT_2, 0.0),
+    Complex::new(INV_SQRT_2, 0.0),
+    Complex::new(INV_SQRT_2, 0.0),
+    Complex::new(-INV_SQRT_2, 0.0),
+];
+pub(crate) const S_MATRIX: Matrix = [ONE, ZERO, ZERO, Complex::new(0.0, 1.0)];
+pub(crate) const T_MATRIX: Matrix = [ONE, ZERO, ZERO, Complex::new(INV_SQRT_2, INV_SQRT_2)];
+
+// The matrix multiplying the amplitude of |1⟩ by the phase.
+pub(crate) fn phase_matrix(phase: Complex<f64>) -> Matrix {
+    [ONE, ZERO, ZERO, phase]
+}
+
+pub(crate) fn rx_matrix(theta: f64) -> Matrix {
+    let cos = Complex::new((theta / 2.0).cos(), 0.0);
+    let minus_i_sin = Complex::new(0.0, -(theta / 2.0).sin());
+    [cos, minus_i_sin, minus_i_sin, cos]
+}
+
+pub(crate) fn ry_matrix(theta: f64) -> Matrix {
+    let cos = Complex::new((theta / 2.0).cos(), 0.0);
+    let sin = Complex::new((theta / 2.0).sin(), 0.0);
+    [cos, -sin, sin, cos]
+}
+
+pub(crate) fn rz_matrix(theta: f64) -> Matrix {
+    let phase = Complex::new(0.0, theta / 2.0).exp();
+    [phase.conj(), ZERO, ZERO, phase]
+}
+
+// The product of the matrices, applying first the matrix a and then the matrix b.
+#[cfg(feature = "cuda")]
+pub(crate) fn matrix_product(b: &Matrix, a: &Matrix) -> Matrix {
+    [
+        b[0] * a[0] + b[1] * a[2],
+        b[0] * a[1] + b[1] * a[3],
+        b[2] * a[0] + b[3] * a[2],
+        b[2] * a[1] + b[3] * a[3],
+    ]
+}
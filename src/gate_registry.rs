@@ -0,0 +1,107 @@
+/*
+Registry of custom gates given by their unitary matrices.
+
+The matrix of a gate is indexed by the bits of its qubit arguments with the first argument
+as the most significant bit, like the matrices of Quil DEFGATE. A custom gate is added to
+a circuit by its decomposition into the gates of the circuit, equal up to a global phase.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::BTreeMap;
+
+use num_complex::Complex;
+
+use crate::circuit::Circuit;
+use crate::decomposition::{decompose_single_qubit, decompose_two_qubit, Matrix4};
+
+// The highest number of the qubits of a custom gate supported by the decompositions.
+pub const MAX_CUSTOM_GATE_QUBIT_COUNT: usize = 2;
+
+// Whether the square matrix is unitary up to the tolerance of the decompositions.
+pub fn is_unitary(matrix: &[Vec<Complex<f64>>]) -> bool {
+    let n = matrix.len();
+    if matrix.iter().any(|row| row.len() != n) {
+        return false;
+    }
+    (0..n).all(|i| {
+        (0..n).all(|j| {
+            let product: Complex<f64> = (0..n).map(|k| matrix[k][i].conj() * matrix[k][j]).sum();
+            let expected = if i == j { 1.0 } else { 0.0 };
+            (product - expected).norm() < 1e-6
+        })
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomGate {
+    qubit_count: usize,
+    matrix: Vec<Vec<Complex<f64>>>,
+}
+
+impl CustomGate {
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    pub fn matrix(&self) -> &[Vec<Complex<f64>>] {
+        &self.matrix
+    }
+
+    // The circuit of the gate with its qubit i as the argument i.
+    pub fn to_circuit(&self) -> Circuit {
+        let m = &self.matrix;
+        if self.qubit_count == 1 {
+            return decompose_single_qubit(&[[m[0][0], m[0][1]], [m[1][0], m[1][1]]]);
+        }
+        // The decomposition indexes the matrix by q0 + 2·q1, so the bits of the indices are swapped.
+        let swap_bits = |i: usize| ((i & 1) << 1) | (i >> 1);
+        let mut matrix: Matrix4 = [[Complex::new(0.0, 0.0); 4]; 4];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = m[swap_bits(i)][swap_bits(j)];
+            }
+        }
+        decompose_two_qubit(&matrix)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GateRegistry {
+    gates: BTreeMap<String, CustomGate>,
+}
+
+impl GateRegistry {
+    pub fn new() -> GateRegistry {
+        GateRegistry {
+            gates: BTreeMap::new(),
+        }
+    }
+
+    // Register the unitary matrix under the name, replacing a gate registered before with the same name.
+    pub fn register(&mut self, name: &str, matrix: Vec<Vec<Complex<f64>>>) {
+        let qubit_count = matrix.len().trailing_zeros() as usize;
+        assert!(
+            matrix.len() == 1 << qubit_count
+                && (1..=MAX_CUSTOM_GATE_QUBIT_COUNT).contains(&qubit_count),
+            "The matrix has to act on 1 to {} qubits.",
+            MAX_CUSTOM_GATE_QUBIT_COUNT
+        );
+        assert!(is_unitary(&matrix), "The matrix has to be unitary.");
+        self.gates.insert(
+            name.to_string(),
+            CustomGate {
+                qubit_count,
+                matrix,
+            },
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomGate> {
+        self.gates.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.gates.keys().map(|name| name.as_str())
+    }
+}
@@ -0,0 +1,102 @@
+/*
+Parameter-shift gradients of the energy of parameterized circuits.
+
+Every parameterized gate is exp(-iθG) for a generator G with two eigenvalues differing by 1,
+like G = X/2 for Rx(θ) and G = −|11⟩⟨11| for the controlled phase, so the derivative of
+the energy E(θ) = ⟨ψ(θ)|H|ψ(θ)⟩ by the angle of one gate is exactly
+(E(θ + π/2) − E(θ − π/2)) / 2. A parameter used by several gates gets the sum over the gates.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::FRAC_PI_2;
+
+use crate::circuit::Circuit;
+use crate::hamiltonian::Hamiltonian;
+use crate::parameterized_circuit::ParameterizedCircuit;
+use crate::state_vector_simulation::QuantumSimulation;
+
+fn energy(circuit: &Circuit, hamiltonian: &Hamiltonian) -> f64 {
+    let mut simulation = QuantumSimulation::new(circuit.qubit_count(), 0u64);
+    circuit.run(&mut simulation);
+    hamiltonian.expectation(&simulation.amplitudes())
+}
+
+// The energy ⟨ψ|H|ψ⟩ of the state prepared by the circuit from |0…0⟩ on the state vector simulation.
+pub fn expectation(
+    circuit: &ParameterizedCircuit,
+    hamiltonian: &Hamiltonian,
+    parameters: &[f64],
+) -> f64 {
+    energy(&circuit.bind(parameters), hamiltonian)
+}
+
+// The gradient of the energy by the parameters, running two shifted circuits per parameterized gate.
+pub fn gradient(
+    circuit: &ParameterizedCircuit,
+    hamiltonian: &Hamiltonian,
+    parameters: &[f64],
+) -> Vec<f64> {
+    let mut gradient = vec![0.0; circuit.parameter_count()];
+    for (index, gate) in circuit.gates().iter().enumerate() {
+        if let Some(p) = gate.parameter_index() {
+            let plus = energy(
+                &circuit.bind_shifted(parameters, Some(index), FRAC_PI_2),
+                hamiltonian,
+            );
+            let minus = energy(
+                &circuit.bind_shifted(parameters, Some(index), -FRAC_PI_2),
+                hamiltonian,
+            );
+            gradient[p] += (plus - minus) / 2.0;
+        }
+    }
+    gradient
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Gate;
+    use crate::hamiltonian::Pauli;
+
+    #[test]
+    fn parameter_shift_matches_finite_differences() {
+        let mut circuit = ParameterizedCircuit::new(2);
+        circuit.ry(0, 0);
+        circuit.fixed(Gate::Hadamard(1));
+        circuit.rx(1, 1);
+        circuit.fixed(Gate::Cnot(0, 1));
+        circuit.rz(0, 1);
+        circuit.cphase(2, 0, 1);
+        circuit.ry(2, 1);
+
+        let mut hamiltonian = Hamiltonian::new();
+        hamiltonian.add_term(0.7, &[(Pauli::Z, 0), (Pauli::Z, 1)]);
+        hamiltonian.add_term(-0.4, &[(Pauli::X, 1)]);
+        hamiltonian.add_term(0.25, &[(Pauli::Y, 0), (Pauli::X, 1)]);
+        hamiltonian.add_term(1.5, &[]);
+
+        let parameters = [0.3, -1.1, 0.8];
+        let exact = gradient(&circuit, &hamiltonian, &parameters);
+        assert_eq!(exact.len(), 3);
+        for p in 0..3 {
+            let h = 1e-6;
+            let mut plus = parameters;
+            plus[p] += h;
+            let mut minus = parameters;
+            minus[p] -= h;
+            let numeric = (expectation(&circuit, &hamiltonian, &plus)
+                - expectation(&circuit, &hamiltonian, &minus))
+                / (2.0 * h);
+            assert!((exact[p] - numeric).abs() < 1e-6);
+        }
+
+        let mut rotation = ParameterizedCircuit::new(1);
+        rotation.ry(0, 0);
+        let mut z = Hamiltonian::new();
+        z.add_term(1.0, &[(Pauli::Z, 0)]);
+        assert!((expectation(&rotation, &z, &[0.5]) - 0.5f64.cos()).abs() < 1e-12);
+        assert!((gradient(&rotation, &z, &[0.5])[0] + 0.5f64.sin()).abs() < 1e-12);
+    }
+}
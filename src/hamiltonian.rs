@@ -0,0 +1,118 @@
+/*
+Hamiltonians as weighted sums of Pauli strings and their expectation values.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pauli {
+    X,
+    Y,
+    Z,
+}
+
+// Tensor product of Pauli operators on the given qubits and the identity on all the other qubits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PauliString {
+    // The qubits with the X or Y operator.
+    x_mask: u64,
+    // The qubits with the Z or Y operator.
+    z_mask: u64,
+}
+
+impl PauliString {
+    pub fn new(paulis: &[(Pauli, usize)]) -> PauliString {
+        let mut x_mask = 0u64;
+        let mut z_mask = 0u64;
+        for &(pauli, qubit_number) in paulis.iter() {
+            assert!(
+                qubit_number < 64,
+                "The qubit number has to be less than 64."
+            );
+            let bit = 1u64 << qubit_number;
+            assert!(
+                (x_mask | z_mask) & bit == 0,
+                "The qubit {} has more than one Pauli operator.",
+                qubit_number
+            );
+            match pauli {
+                Pauli::X => x_mask |= bit,
+                Pauli::Y => {
+                    x_mask |= bit;
+                    z_mask |= bit
+                }
+                Pauli::Z => z_mask |= bit,
+            }
+        }
+        PauliString { x_mask, z_mask }
+    }
+
+    // The Pauli operators in the order of the qubit numbers.
+    pub fn paulis(&self) -> Vec<(Pauli, usize)> {
+        (0..64)
+            .filter_map(|qubit_number| {
+                let x = (self.x_mask >> qubit_number) & 1 == 1;
+                let z = (self.z_mask >> qubit_number) & 1 == 1;
+                match (x, z) {
+                    (true, false) => Some((Pauli::X, qubit_number)),
+                    (true, true) => Some((Pauli::Y, qubit_number)),
+                    (false, true) => Some((Pauli::Z, qubit_number)),
+                    (false, false) => None,
+                }
+            })
+            .collect()
+    }
+
+    // ⟨ψ|P|ψ⟩ for the state vector indexed by the bits of the qubit numbers.
+    // With Y = iXZ, P|i⟩ = i^{#Y}·(−1)^{|i ∧ z_mask|}·|i ⊕ x_mask⟩.
+    pub fn expectation(&self, amplitudes: &[Complex<f64>]) -> f64 {
+        let y_count = (self.x_mask & self.z_mask).count_ones();
+        let y_phase = [
+            Complex::new(1.0, 0.0),
+            Complex::new(0.0, 1.0),
+            Complex::new(-1.0, 0.0),
+            Complex::new(0.0, -1.0),
+        ][(y_count % 4) as usize];
+        let x_mask = self.x_mask as usize;
+        let mut sum = Complex::new(0.0, 0.0);
+        for (i, amplitude) in amplitudes.iter().enumerate() {
+            let term = amplitudes[i ^ x_mask].conj() * amplitude;
+            if (i as u64 & self.z_mask).count_ones().is_multiple_of(2) {
+                sum += term;
+            } else {
+                sum -= term;
+            }
+        }
+        (y_phase * sum).re
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Hamiltonian {
+    terms: Vec<(f64, PauliString)>,
+}
+
+impl Hamiltonian {
+    pub fn new() -> Hamiltonian {
+        Hamiltonian { terms: Vec::new() }
+    }
+
+    // Add the Pauli string multiplied by the coefficient; an empty string is a constant energy offset.
+    pub fn add_term(&mut self, coefficient: f64, paulis: &[(Pauli, usize)]) {
+        self.terms.push((coefficient, PauliString::new(paulis)));
+    }
+
+    pub fn terms(&self) -> &[(f64, PauliString)] {
+        &self.terms
+    }
+
+    // ⟨ψ|H|ψ⟩ for the state vector indexed by the bits of the qubit numbers.
+    pub fn expectation(&self, amplitudes: &[Complex<f64>]) -> f64 {
+        self.terms
+            .iter()
+            .map(|(coefficient, pauli_string)| coefficient * pauli_string.expectation(amplitudes))
+            .sum()
+    }
+}
@@ -4,9 +4,51 @@ Quantum simulation library.
 Copyright © 2024 AlgoHertz. All rights reserved.
 */
 
+pub mod algorithms;
+pub mod batch;
+pub mod benchmarks;
+pub mod blocks;
+pub mod circuit;
+#[cfg(feature = "cuda")]
+pub mod cuda_simulation;
+pub mod cv;
+pub mod dag;
+pub mod decomposition;
+pub mod density_matrix_simulation;
+pub mod error_correction;
 pub mod evaluation;
+pub mod extended_stabilizer_simulation;
+pub mod fourier;
 pub mod gate;
+pub mod gate_registry;
+pub mod gradient;
+pub mod hamiltonian;
+pub mod linalg_gf2;
+pub mod mitigation;
+pub mod mps_simulation;
+pub mod noise;
+pub mod optimization;
+pub mod parameterized_circuit;
 pub mod parity;
+pub mod pauli_propagation;
+pub mod protocols;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod qudit_simulation;
+pub mod register;
+pub mod ring;
+pub mod routing;
+pub mod scheduling;
+pub mod schrodinger_feynman_simulation;
 pub mod simulation;
+pub mod sparse_simulation;
+pub mod stabilizer_simulation;
+pub mod state_prep;
 pub mod state_vector_init;
 pub mod state_vector_simulation;
+pub mod synthesis;
+pub mod tensor_network;
+pub mod trajectory_simulation;
+pub mod unitary_simulation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
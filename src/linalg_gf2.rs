@@ -0,0 +1,123 @@
+/*
+Linear algebra over GF(2) of the bit vectors.
+
+A vector of up to 64 bits is a u64 with the bit i as its component i, so that the addition is
+the XOR and the dot product the parity of the AND. The Gaussian elimination brings the rows of
+a matrix into the reduced row echelon form, which gives the rank, the solutions of the linear
+systems, and the basis of the null space, the vectors orthogonal to all the rows. The null space
+of the measured vectors of Simon's algorithm is spanned by the secret mask.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+// The dot product of the vectors over GF(2).
+pub fn dot(vector0: u64, vector1: u64) -> bool {
+    (vector0 & vector1).count_ones() % 2 == 1
+}
+
+// The nonzero rows of the reduced row echelon form of the matrix, with the pivot of each row, its
+// lowest bit, in none of the other rows, sorted by the pivots.
+pub fn reduced_row_echelon(rows: &[u64]) -> Vec<u64> {
+    let mut basis: Vec<u64> = Vec::new();
+    for &row in rows {
+        let reduced = basis.iter().fold(row, |row, &basis_row| {
+            if row & (basis_row & basis_row.wrapping_neg()) != 0 {
+                row ^ basis_row
+            } else {
+                row
+            }
+        });
+        if reduced == 0 {
+            continue;
+        }
+        let pivot = reduced & reduced.wrapping_neg();
+        for basis_row in basis.iter_mut() {
+            if *basis_row & pivot != 0 {
+                *basis_row ^= reduced;
+            }
+        }
+        basis.push(reduced);
+    }
+    basis.sort_by_key(|row| row.trailing_zeros());
+    basis
+}
+
+pub fn rank(rows: &[u64]) -> usize {
+    reduced_row_echelon(rows).len()
+}
+
+// The basis of the null space of the matrix with the n columns, the vectors x of n bits with
+// r·x = 0 for all the rows r.
+pub fn null_space(rows: &[u64], n: usize) -> Vec<u64> {
+    assert!(n <= 64, "The number of the columns has to be at most 64.");
+    let basis = reduced_row_echelon(rows);
+    let pivots: u64 = basis.iter().map(|row| row & row.wrapping_neg()).sum();
+    // Each free column f gives the vector with the bit f and the pivot bits of the rows with the
+    // bit f, which makes each row sum to 0.
+    (0..n)
+        .filter(|&column| (pivots >> column) & 1 == 0)
+        .map(|column| {
+            basis
+                .iter()
+                .filter(|&&row| (row >> column) & 1 == 1)
+                .fold(1u64 << column, |vector, &row| {
+                    vector | (row & row.wrapping_neg())
+                })
+        })
+        .collect()
+}
+
+// A solution x of the linear system r_i·x = b_i of the rows and the right-hand sides, or None if
+// the system has no solution.
+pub fn solve(rows: &[u64], values: &[bool]) -> Option<u64> {
+    assert!(
+        rows.len() == values.len(),
+        "The number of the values has to be equal to the number of the rows {}.",
+        rows.len()
+    );
+    assert!(
+        rows.iter().all(|&row| row.leading_zeros() > 0),
+        "The rows have to be of at most 63 bits."
+    );
+    // The augmented rows with the right-hand side as the bit 63.
+    let augmented: Vec<u64> = rows
+        .iter()
+        .zip(values)
+        .map(|(&row, &value)| row | ((value as u64) << 63))
+        .collect();
+    let mut solution = 0u64;
+    for row in reduced_row_echelon(&augmented) {
+        let pivot = row & row.wrapping_neg();
+        if pivot == 1 << 63 {
+            // The row 0 = 1.
+            return None;
+        }
+        // The free variables are 0, so the pivot variable is the right-hand side.
+        if row >> 63 == 1 {
+            solution |= pivot;
+        }
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_elimination_over_gf2() {
+        let rows = [0b0110, 0b1010, 0b1100, 0b0011];
+        assert_eq!(reduced_row_echelon(&rows), vec![0b1001, 0b1010, 0b1100]);
+        assert_eq!(rank(&rows), 3);
+        let null_vectors = null_space(&rows, 4);
+        assert_eq!(null_vectors, vec![0b1111]);
+        assert!(rows.iter().all(|&row| !dot(row, null_vectors[0])));
+        assert_eq!(null_space(&[], 2), vec![0b01, 0b10]);
+
+        // x = 0b101 satisfies the system, and the inconsistent system has no solution.
+        let rows = [0b011, 0b110, 0b111];
+        let values: Vec<bool> = rows.iter().map(|&row| dot(row, 0b101)).collect();
+        assert_eq!(solve(&rows, &values), Some(0b101));
+        assert_eq!(solve(&[0b11, 0b11], &[true, false]), None);
+    }
+}
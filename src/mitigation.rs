@@ -0,0 +1,47 @@
+/*
+Mitigation of the errors of the noisy simulations, estimating the noiseless results from the
+results of noisy runs.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+pub mod readout;
+pub mod zne;
+
+// Solve the linear equations with the matrix, given row by row, by the Gaussian elimination
+// with partial pivoting.
+fn solve(mut matrix: Vec<f64>, mut values: Vec<f64>) -> Vec<f64> {
+    let n = values.len();
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|&i, &j| {
+                matrix[i * n + column]
+                    .abs()
+                    .total_cmp(&matrix[j * n + column].abs())
+            })
+            .unwrap();
+        assert!(
+            matrix[pivot * n + column].abs() > 1e-12,
+            "The linear equations have no unique solution."
+        );
+        for j in 0..n {
+            matrix.swap(pivot * n + j, column * n + j);
+        }
+        values.swap(pivot, column);
+        for row in column + 1..n {
+            let factor = matrix[row * n + column] / matrix[column * n + column];
+            for j in column..n {
+                matrix[row * n + j] -= factor * matrix[column * n + j];
+            }
+            values[row] -= factor * values[column];
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n)
+            .map(|j| matrix[row * n + j] * solution[j])
+            .sum();
+        solution[row] = (values[row] - sum) / matrix[row * n + row];
+    }
+    solution
+}
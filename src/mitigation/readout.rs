@@ -0,0 +1,189 @@
+/*
+Mitigation of the readout errors by a calibration matrix.
+
+The calibration circuits prepare each basis state of the qubits and measure them, and the
+frequencies of the measured states form the confusion matrix A, whose element A[m][p] is the
+probability of measuring the state m when the state p is prepared. The measured distribution
+is then A times the true one, which is estimated by the least-squares solution of the linear
+equations, the pseudoinverse of A applied to the measured distribution, with the negative
+probabilities clipped and the rest normalized.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use super::solve;
+use crate::circuit::Circuit;
+
+// The most qubits calibrated together, with 2^n calibration circuits.
+pub const MAX_QUBIT_COUNT: usize = 12;
+
+// The index of the basis state of the measured values, the qubit 0 being the lowest bit.
+fn state_index(measurement: &[bool]) -> usize {
+    measurement
+        .iter()
+        .enumerate()
+        .filter(|&(_, &value)| value)
+        .map(|(qubit_number, _)| 1 << qubit_number)
+        .sum()
+}
+
+// The circuits preparing the basis states of the qubits in the order of their indices,
+// without measurements, so that all the qubits are measured at the end of a run.
+pub fn calibration_circuits(qubit_count: usize) -> Vec<Circuit> {
+    assert!(
+        qubit_count <= MAX_QUBIT_COUNT,
+        "The number of the calibrated qubits cannot exceed {}.",
+        MAX_QUBIT_COUNT
+    );
+    (0..1usize << qubit_count)
+        .map(|state_index| {
+            let mut circuit = Circuit::new(qubit_count);
+            for qubit_number in 0..qubit_count {
+                if (state_index >> qubit_number) & 1 == 1 {
+                    circuit.pauli_x(qubit_number);
+                }
+            }
+            circuit
+        })
+        .collect()
+}
+
+// The probabilities of the basis states in the measurements of the qubits.
+pub fn probabilities(measurements: &[Vec<bool>], qubit_count: usize) -> Vec<f64> {
+    let mut probabilities = vec![0.0; 1 << qubit_count];
+    for measurement in measurements.iter() {
+        assert!(
+            measurement.len() == qubit_count,
+            "The measurements have to have the values of the qubits {}.",
+            qubit_count
+        );
+        probabilities[state_index(measurement)] += 1.0;
+    }
+    for probability in probabilities.iter_mut() {
+        *probability /= measurements.len() as f64;
+    }
+    probabilities
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfusionMatrix {
+    qubit_count: usize,
+    // The probabilities of the measured states, the rows, for the prepared states, the columns.
+    matrix: Vec<f64>,
+}
+
+impl ConfusionMatrix {
+    // The confusion matrix of the rows of the measured states, given column by column as the
+    // distributions of the measured states for each prepared state.
+    pub fn new(qubit_count: usize, columns: &[Vec<f64>]) -> ConfusionMatrix {
+        let state_count = 1 << qubit_count;
+        assert!(
+            columns.len() == state_count && columns.iter().all(|c| c.len() == state_count),
+            "The confusion matrix of {} qubits has to be {} × {}.",
+            qubit_count,
+            state_count,
+            state_count
+        );
+        let mut matrix = vec![0.0; state_count * state_count];
+        for (prepared, column) in columns.iter().enumerate() {
+            for (measured, &probability) in column.iter().enumerate() {
+                matrix[measured * state_count + prepared] = probability;
+            }
+        }
+        ConfusionMatrix {
+            qubit_count,
+            matrix,
+        }
+    }
+
+    // Run the calibration circuits with the run function, returning the measured values of all
+    // the qubits for each shot, and build the matrix from the frequencies of the measured states.
+    pub fn calibrate<F>(qubit_count: usize, mut run: F) -> ConfusionMatrix
+    where
+        F: FnMut(&Circuit) -> Vec<Vec<bool>>,
+    {
+        let columns: Vec<Vec<f64>> = calibration_circuits(qubit_count)
+            .iter()
+            .map(|circuit| probabilities(&run(circuit), qubit_count))
+            .collect();
+        ConfusionMatrix::new(qubit_count, &columns)
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The probability of measuring the state when the prepared state is prepared.
+    pub fn probability(&self, measured_state: usize, prepared_state: usize) -> f64 {
+        self.matrix[measured_state * (1 << self.qubit_count) + prepared_state]
+    }
+
+    // The estimate of the true distribution of the measured distribution, from the normal
+    // equations A^T A x = A^T p of the least-squares solution.
+    pub fn correct(&self, measured_probabilities: &[f64]) -> Vec<f64> {
+        let state_count = 1 << self.qubit_count;
+        assert!(
+            measured_probabilities.len() == state_count,
+            "The distribution of {} qubits has to have {} probabilities.",
+            self.qubit_count,
+            state_count
+        );
+        let mut normal_matrix = vec![0.0; state_count * state_count];
+        let mut values = vec![0.0; state_count];
+        for (a_row, &measured_probability) in self
+            .matrix
+            .chunks_exact(state_count)
+            .zip(measured_probabilities.iter())
+        {
+            for i in 0..state_count {
+                values[i] += a_row[i] * measured_probability;
+                for j in 0..state_count {
+                    normal_matrix[i * state_count + j] += a_row[i] * a_row[j];
+                }
+            }
+        }
+
+        let mut probabilities = solve(normal_matrix, values);
+        for probability in probabilities.iter_mut() {
+            *probability = probability.max(0.0);
+        }
+        let sum: f64 = probabilities.iter().sum();
+        for probability in probabilities.iter_mut() {
+            *probability /= sum;
+        }
+        probabilities
+    }
+
+    // The corrected distribution of the measured values of the qubits.
+    pub fn correct_measurements(&self, measurements: &[Vec<bool>]) -> Vec<f64> {
+        self.correct(&probabilities(measurements, self.qubit_count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::ReadoutError;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn readout_errors_are_corrected() {
+        let mut simulation = QuantumSimulation::new(2, 1);
+        simulation.set_readout_error(0, ReadoutError::new(0.05, 0.1));
+        simulation.set_readout_error(1, ReadoutError::new(0.02, 0.2));
+        let confusion_matrix =
+            ConfusionMatrix::calibrate(2, |circuit| simulation.run_shots(circuit, 20000));
+        assert!((confusion_matrix.probability(0b01, 0b11) - 0.9 * 0.2).abs() < 0.01);
+
+        let mut circuit = Circuit::new(2);
+        circuit.hadamard(0);
+        circuit.cnot(0, 1);
+        let measurements = simulation.run_shots(&circuit, 20000);
+        let measured_probabilities = probabilities(&measurements, 2);
+        assert!(measured_probabilities[0b11] < 0.4);
+        let corrected_probabilities = confusion_matrix.correct_measurements(&measurements);
+        for (state, expected) in [(0b00, 0.5), (0b01, 0.0), (0b10, 0.0), (0b11, 0.5)] {
+            assert!((corrected_probabilities[state] - expected).abs() < 0.02);
+        }
+    }
+}
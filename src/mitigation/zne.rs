@@ -0,0 +1,222 @@
+/*
+Zero-noise extrapolation of expectation values.
+
+The noise of a circuit is amplified by folding its gates, each gate G becoming G (G† G)^k for
+the odd scale factor 2k + 1, which leaves the noiseless circuit unchanged while multiplying the
+number of the noisy gates. The inverses keep the names of the gates, so that the noise models
+matching the gates by name apply to the folded copies too: S† is folded as S^3 and T† as T^7,
+which amplifies the noise of these gates by more than the scale factor. The expectation values
+measured at several scale factors are fitted by a polynomial in the scale factor, whose value at
+the scale factor 0 is the mitigated value.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::fmt;
+
+use super::solve;
+use crate::circuit::{Circuit, Gate};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extrapolation {
+    // The least-squares line.
+    Linear,
+    // The least-squares polynomial of the order.
+    Polynomial(usize),
+    // The polynomial through all the values, of the order of the number of values - 1.
+    Richardson,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZneResult {
+    // The extrapolated value at the zero noise.
+    pub mitigated_value: f64,
+    pub scale_factors: Vec<usize>,
+    // The expectation values measured at the scale factors.
+    pub noisy_values: Vec<f64>,
+    // The coefficients of the fitted polynomial, from the constant term up.
+    pub coefficients: Vec<f64>,
+    // The root mean square of the differences of the values from the fit.
+    pub residual: f64,
+}
+
+impl fmt::Display for ZneResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Zero-noise extrapolation")?;
+        for (scale_factor, value) in self.scale_factors.iter().zip(self.noisy_values.iter()) {
+            writeln!(f, "Scale factor {}: {:?}", scale_factor, value)?;
+        }
+        writeln!(f, "Fit coefficients: {:?}", self.coefficients)?;
+        writeln!(f, "Fit residual: {:?}", self.residual)?;
+        write!(f, "Mitigated value: {:?}", self.mitigated_value)
+    }
+}
+
+// The gates of the inverse of the gate with the name of the gate.
+fn inverse_gates(gate: &Gate) -> Vec<Gate> {
+    match *gate {
+        Gate::S(_) => vec![*gate; 3],
+        Gate::T(_) => vec![*gate; 7],
+        _ => vec![gate.inverse()],
+    }
+}
+
+// The circuit with each gate G replaced by G (G† G)^k for the odd scale factor 2k + 1.
+// The measurements are not folded.
+pub fn fold_gates(circuit: &Circuit, scale_factor: usize) -> Circuit {
+    assert!(
+        scale_factor % 2 == 1,
+        "The scale factor of the folding has to be odd."
+    );
+    let mut folded_circuit = Circuit::with_clbits(circuit.qubit_count(), circuit.clbit_count());
+    for (gate, condition) in circuit.gates().iter().zip(circuit.conditions().iter()) {
+        folded_circuit.push_with_condition(*gate, *condition);
+        if let Gate::Measure(_, _) = gate {
+            continue;
+        }
+        for _ in 0..scale_factor / 2 {
+            for inverse_gate in inverse_gates(gate) {
+                folded_circuit.push_with_condition(inverse_gate, *condition);
+            }
+            folded_circuit.push_with_condition(*gate, *condition);
+        }
+    }
+    folded_circuit
+}
+
+// The coefficients of the least-squares polynomial of the order through the points, from the
+// normal equations.
+fn fit_polynomial(xs: &[f64], ys: &[f64], order: usize) -> Vec<f64> {
+    let n = order + 1;
+    let mut matrix = vec![0.0; n * n];
+    let mut values = vec![0.0; n];
+    for (&x, &y) in xs.iter().zip(ys.iter()) {
+        let powers: Vec<f64> = (0..n).map(|i| x.powi(i as i32)).collect();
+        for i in 0..n {
+            for j in 0..n {
+                matrix[i * n + j] += powers[i] * powers[j];
+            }
+            values[i] += powers[i] * y;
+        }
+    }
+    solve(matrix, values)
+}
+
+// Extrapolate the values measured at the scale factors to the zero noise.
+pub fn extrapolate(
+    scale_factors: &[usize],
+    noisy_values: &[f64],
+    extrapolation: Extrapolation,
+) -> ZneResult {
+    assert!(
+        scale_factors.len() == noisy_values.len(),
+        "There has to be one value for each scale factor."
+    );
+    let order = match extrapolation {
+        Extrapolation::Linear => 1,
+        Extrapolation::Polynomial(order) => order,
+        Extrapolation::Richardson => scale_factors.len().saturating_sub(1),
+    };
+    assert!(
+        scale_factors.len() > order,
+        "The fit of the order {} needs more than {} scale factors.",
+        order,
+        order
+    );
+    let xs: Vec<f64> = scale_factors.iter().map(|&s| s as f64).collect();
+    let coefficients = fit_polynomial(&xs, noisy_values, order);
+    let squared_error: f64 = xs
+        .iter()
+        .zip(noisy_values.iter())
+        .map(|(&x, &y)| {
+            let fitted: f64 = coefficients
+                .iter()
+                .enumerate()
+                .map(|(i, c)| c * x.powi(i as i32))
+                .sum();
+            (fitted - y).powi(2)
+        })
+        .sum();
+
+    ZneResult {
+        mitigated_value: coefficients[0],
+        scale_factors: scale_factors.to_vec(),
+        noisy_values: noisy_values.to_vec(),
+        residual: (squared_error / xs.len() as f64).sqrt(),
+        coefficients,
+    }
+}
+
+// Run the circuit folded by each of the scale factors, with the expectation function returning
+// its noisy expectation value, and extrapolate the values to the zero noise.
+pub fn zne<F>(
+    circuit: &Circuit,
+    scale_factors: &[usize],
+    extrapolation: Extrapolation,
+    mut expectation: F,
+) -> ZneResult
+where
+    F: FnMut(&Circuit) -> f64,
+{
+    let noisy_values: Vec<f64> = scale_factors
+        .iter()
+        .map(|&scale_factor| expectation(&fold_gates(circuit, scale_factor)))
+        .collect();
+    extrapolate(scale_factors, &noisy_values, extrapolation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn extrapolation_of_folded_circuits() {
+        let mut circuit = Circuit::new(2);
+        circuit.hadamard(0);
+        circuit.t(0);
+        circuit.cnot(0, 1);
+        circuit.ry(0.3, 1);
+        circuit.cphase(0.7, 0, 1);
+
+        // The folded circuit has the same state up to a global phase, and the T gate is folded
+        // with T^7 as its inverse.
+        let mut simulation = QuantumSimulation::new(2, 0);
+        circuit.run(&mut simulation);
+        let amplitudes = simulation.amplitudes().to_vec();
+        let folded_circuit = fold_gates(&circuit, 5);
+        assert_eq!(folded_circuit.gates().len(), 37);
+        assert!(folded_circuit
+            .gates()
+            .iter()
+            .all(|gate| gate.name() != "rz"));
+        simulation.reset();
+        folded_circuit.run(&mut simulation);
+        let overlap: num_complex::Complex<f64> = amplitudes
+            .iter()
+            .zip(simulation.amplitudes().iter())
+            .map(|(a, b)| a.conj() * b)
+            .sum();
+        assert!((overlap.norm() - 1.0).abs() < 1e-12);
+
+        // The values quadratic in the number of the Hadamard gates are extrapolated exactly by
+        // the Richardson extrapolation.
+        let result = zne(&circuit, &[1, 3, 5], Extrapolation::Richardson, |folded| {
+            let scale = folded
+                .gates()
+                .iter()
+                .filter(|gate| matches!(gate, Gate::Hadamard(_)))
+                .count() as f64;
+            0.9 - 0.1 * scale + 0.01 * scale * scale
+        });
+        assert!((result.mitigated_value - 0.9).abs() < 1e-9);
+        assert!(result.residual < 1e-9);
+        let result = extrapolate(&[1, 3, 5], &result.noisy_values, Extrapolation::Linear);
+        assert!((result.coefficients[1] + 0.04).abs() < 1e-9);
+        assert!((result.mitigated_value - 2.51 / 3.0).abs() < 1e-9);
+        assert!(result
+            .to_string()
+            .starts_with("Zero-noise extrapolation\nScale factor 1: "));
+    }
+}
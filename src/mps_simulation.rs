@@ -0,0 +1,653 @@
+/*
+Quantum matrix product state simulation.
+
+The state of n qubits is the product of the tensors A_k[l, s, r] of the qubits, the amplitude
+of |s_0 ... s_{n-1}⟩ being the product of the matrices A_0[s_0] ... A_{n-1}[s_{n-1}], with
+the bonds between the neighbouring qubits of dimensions growing with their entanglement.
+The tensors left of the orthogonality center are left-normalized and the tensors right of it
+right-normalized, so that the singular values of a bond are the Schmidt coefficients of the
+cut. A gate on two neighbouring qubits contracts their tensors and splits them again by a
+singular value decomposition, dropping the smallest singular values within the truncation
+threshold and beyond the maximum bond dimension, and the qubits of a gate further apart are
+brought next to each other by swaps. The memory is linear in the number of qubits for the
+slightly entangled states, such as the states of the shallow circuits on a line.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::circuit::u_f_gates;
+use crate::gate::{
+    phase_matrix, rx_matrix, ry_matrix, rz_matrix, Matrix, HADAMARD_MATRIX, PAULI_X_MATRIX,
+    PAULI_Y_MATRIX, PAULI_Z_MATRIX, S_MATRIX, T_MATRIX,
+};
+use crate::simulation::{check_qubits, Simulation};
+
+// The largest weight of the dropped singular values of a bond, relative to the weight of all
+// of them, by default.
+pub const DEFAULT_TRUNCATION_THRESHOLD: f64 = 1e-14;
+
+const MAX_SWEEP_COUNT: usize = 64;
+const ORTHOGONALITY_TOLERANCE: f64 = 1e-15;
+
+// A gate on two qubits, with the first qubit as the low bit of the row and the column indices.
+type TwoQubitMatrix = [[Complex<f64>; 4]; 4];
+
+#[derive(Clone, Debug)]
+struct Tensor {
+    left_dimension: usize,
+    right_dimension: usize,
+    // The elements A[l, s, r] at the indices (2 l + s) r_dim + r.
+    elements: Vec<Complex<f64>>,
+}
+
+impl Tensor {
+    fn index(&self, left: usize, value: usize, right: usize) -> usize {
+        (2 * left + value) * self.right_dimension + right
+    }
+}
+
+// The singular value decomposition M = U Σ V† of the matrix given row by row, by the one-sided
+// Jacobi method, with the singular values in decreasing order. U is given row by row with one
+// column per singular value, and V† row by row with one row per singular value.
+fn svd(
+    matrix: &[Complex<f64>],
+    row_count: usize,
+    column_count: usize,
+) -> (Vec<Complex<f64>>, Vec<f64>, Vec<Complex<f64>>) {
+    // The columns of M V, made orthogonal by the rotations accumulated in V.
+    let mut columns: Vec<Vec<Complex<f64>>> = (0..column_count)
+        .map(|j| {
+            (0..row_count)
+                .map(|i| matrix[i * column_count + j])
+                .collect()
+        })
+        .collect();
+    let mut v_columns: Vec<Vec<Complex<f64>>> = (0..column_count)
+        .map(|j| {
+            let mut column = vec![Complex::new(0.0, 0.0); column_count];
+            column[j] = Complex::new(1.0, 0.0);
+            column
+        })
+        .collect();
+    for _ in 0..MAX_SWEEP_COUNT {
+        let mut is_rotated = false;
+        for p in 0..column_count {
+            for q in p + 1..column_count {
+                let alpha: f64 = columns[p].iter().map(|a| a.norm_sqr()).sum();
+                let beta: f64 = columns[q].iter().map(|a| a.norm_sqr()).sum();
+                let gamma: Complex<f64> = columns[p]
+                    .iter()
+                    .zip(columns[q].iter())
+                    .map(|(a, b)| a.conj() * b)
+                    .sum();
+                let gamma_norm = gamma.norm();
+                if gamma_norm <= ORTHOGONALITY_TOLERANCE * (alpha * beta).sqrt() {
+                    continue;
+                }
+                is_rotated = true;
+                // The phase of the column q makes the overlap real before the real rotation.
+                let phase = (gamma / gamma_norm).conj();
+                let zeta = (beta - alpha) / (2.0 * gamma_norm);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+                for vectors in [&mut columns, &mut v_columns] {
+                    let (left, right) = vectors.split_at_mut(q);
+                    for (a, b) in left[p].iter_mut().zip(right[0].iter_mut()) {
+                        let b_rotated = *b * phase;
+                        (*a, *b) = (*a * c - b_rotated * s, *a * s + b_rotated * c);
+                    }
+                }
+            }
+        }
+        if !is_rotated {
+            break;
+        }
+    }
+
+    let norms: Vec<f64> = columns
+        .iter()
+        .map(|column| column.iter().map(|a| a.norm_sqr()).sum::<f64>().sqrt())
+        .collect();
+    let mut order: Vec<usize> = (0..column_count).collect();
+    order.sort_by(|&i, &j| norms[j].total_cmp(&norms[i]));
+    let mut u = vec![Complex::new(0.0, 0.0); row_count * column_count];
+    let mut singular_values = Vec::with_capacity(column_count);
+    let mut v_dagger = Vec::with_capacity(column_count * column_count);
+    for (k, &j) in order.iter().enumerate() {
+        if norms[j] > 0.0 {
+            for i in 0..row_count {
+                u[i * column_count + k] = columns[j][i] / norms[j];
+            }
+        }
+        singular_values.push(norms[j]);
+        v_dagger.extend(v_columns[j].iter().map(|v| v.conj()));
+    }
+    (u, singular_values, v_dagger)
+}
+
+fn controlled_matrix(matrix: &Matrix) -> TwoQubitMatrix {
+    let mut controlled = [[Complex::new(0.0, 0.0); 4]; 4];
+    controlled[0][0] = Complex::new(1.0, 0.0);
+    controlled[2][2] = Complex::new(1.0, 0.0);
+    for row in 0..2 {
+        for column in 0..2 {
+            controlled[1 + 2 * row][1 + 2 * column] = matrix[2 * row + column];
+        }
+    }
+    controlled
+}
+
+fn swap_matrix() -> TwoQubitMatrix {
+    let mut swap = [[Complex::new(0.0, 0.0); 4]; 4];
+    for (row, column) in [(0, 0), (1, 2), (2, 1), (3, 3)] {
+        swap[row][column] = Complex::new(1.0, 0.0);
+    }
+    swap
+}
+
+// The same gate with the order of its qubits exchanged.
+fn exchange_qubits(matrix: &TwoQubitMatrix) -> TwoQubitMatrix {
+    let exchanged_index = [0, 2, 1, 3];
+    let mut exchanged = [[Complex::new(0.0, 0.0); 4]; 4];
+    for row in 0..4 {
+        for column in 0..4 {
+            exchanged[exchanged_index[row]][exchanged_index[column]] = matrix[row][column];
+        }
+    }
+    exchanged
+}
+
+#[derive(Debug)]
+pub struct MpsSimulation {
+    qubit_count: usize,
+    tensors: Vec<Tensor>,
+    // The qubit whose tensor is neither left- nor right-normalized.
+    orthogonality_center: usize,
+    max_bond_dimension: usize,
+    truncation_threshold: f64,
+    rng: ChaCha12Rng,
+}
+
+impl MpsSimulation {
+    pub fn new(qubit_count: usize, rnd_seed: u64) -> MpsSimulation {
+        let mut simulation = MpsSimulation {
+            qubit_count,
+            tensors: Vec::new(),
+            orthogonality_center: 0,
+            max_bond_dimension: usize::MAX,
+            truncation_threshold: DEFAULT_TRUNCATION_THRESHOLD,
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    // Limit the dimension of the bonds, the simulation being approximate once it is reached.
+    pub fn set_max_bond_dimension(&mut self, max_bond_dimension: usize) {
+        assert!(
+            max_bond_dimension > 0,
+            "The maximum bond dimension has to be positive."
+        );
+        self.max_bond_dimension = max_bond_dimension;
+    }
+
+    // Set the largest weight of the dropped singular values of a bond, relative to the weight
+    // of all of them.
+    pub fn set_truncation_threshold(&mut self, truncation_threshold: f64) {
+        assert!(
+            (0.0..1.0).contains(&truncation_threshold),
+            "The truncation threshold has to be at least 0 and less than 1."
+        );
+        self.truncation_threshold = truncation_threshold;
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The dimensions of the bonds between the qubits k and k + 1.
+    pub fn bond_dimensions(&self) -> Vec<usize> {
+        self.tensors
+            .iter()
+            .skip(1)
+            .map(|tensor| tensor.left_dimension)
+            .collect()
+    }
+
+    // The amplitude of the basis state with the values of the qubits.
+    pub fn amplitude(&self, values: &[bool]) -> Complex<f64> {
+        assert!(
+            values.len() == self.qubit_count,
+            "The basis state has to have one value for each of the qubits {}.",
+            self.qubit_count
+        );
+        let mut vector = vec![Complex::new(1.0, 0.0)];
+        for (tensor, &value) in self.tensors.iter().zip(values.iter()) {
+            vector = (0..tensor.right_dimension)
+                .map(|right| {
+                    vector
+                        .iter()
+                        .enumerate()
+                        .map(|(left, a)| {
+                            a * tensor.elements[tensor.index(left, value as usize, right)]
+                        })
+                        .sum()
+                })
+                .collect();
+        }
+        vector.first().copied().unwrap_or(Complex::new(1.0, 0.0))
+    }
+
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    // The number of the largest singular values kept, at most the given number, which are
+    // rescaled to the unit weight.
+    fn truncate(&self, singular_values: &mut [f64], max_kept_count: usize) -> usize {
+        let total_weight: f64 = singular_values.iter().map(|s| s * s).sum();
+        let mut kept_count = singular_values.len().min(max_kept_count);
+        let mut dropped_weight: f64 = singular_values[kept_count..].iter().map(|s| s * s).sum();
+        while kept_count > 1 {
+            let weight = singular_values[kept_count - 1].powi(2);
+            if dropped_weight + weight > self.truncation_threshold * total_weight {
+                break;
+            }
+            dropped_weight += weight;
+            kept_count -= 1;
+        }
+        let scale = 1.0 / (1.0 - dropped_weight / total_weight).sqrt();
+        for s in singular_values[..kept_count].iter_mut() {
+            *s *= scale;
+        }
+        kept_count
+    }
+
+    // Move the orthogonality center to the qubit, one bond at a time.
+    fn move_orthogonality_center(&mut self, qubit_number: usize) {
+        while self.orthogonality_center < qubit_number {
+            let k = self.orthogonality_center;
+            let tensor = &self.tensors[k];
+            let (row_count, column_count) = (2 * tensor.left_dimension, tensor.right_dimension);
+            let (u, mut singular_values, v_dagger) = svd(&tensor.elements, row_count, column_count);
+            let kept_count = self.truncate(&mut singular_values, row_count);
+            self.tensors[k] = Tensor {
+                left_dimension: row_count / 2,
+                right_dimension: kept_count,
+                elements: (0..row_count * kept_count)
+                    .map(|i| u[i / kept_count * column_count + i % kept_count])
+                    .collect(),
+            };
+            // Σ V† is absorbed into the next tensor.
+            let next = &self.tensors[k + 1];
+            let next_columns = 2 * next.right_dimension;
+            let elements = (0..kept_count * next_columns)
+                .map(|i| {
+                    let (row, column) = (i / next_columns, i % next_columns);
+                    (0..column_count)
+                        .map(|j| {
+                            v_dagger[row * column_count + j]
+                                * singular_values[row]
+                                * next.elements[j * next_columns + column]
+                        })
+                        .sum()
+                })
+                .collect();
+            self.tensors[k + 1] = Tensor {
+                left_dimension: kept_count,
+                right_dimension: next.right_dimension,
+                elements,
+            };
+            self.orthogonality_center += 1;
+        }
+        while self.orthogonality_center > qubit_number {
+            let k = self.orthogonality_center;
+            let tensor = &self.tensors[k];
+            let (row_count, column_count) = (tensor.left_dimension, 2 * tensor.right_dimension);
+            let (u, mut singular_values, v_dagger) = svd(&tensor.elements, row_count, column_count);
+            let kept_count = self.truncate(&mut singular_values, row_count);
+            self.tensors[k] = Tensor {
+                left_dimension: kept_count,
+                right_dimension: column_count / 2,
+                elements: v_dagger[..kept_count * column_count].to_vec(),
+            };
+            // U Σ is absorbed into the previous tensor.
+            let previous = &self.tensors[k - 1];
+            let previous_rows = 2 * previous.left_dimension;
+            let elements = (0..previous_rows * kept_count)
+                .map(|i| {
+                    let (row, column) = (i / kept_count, i % kept_count);
+                    (0..row_count)
+                        .map(|j| {
+                            previous.elements[row * row_count + j]
+                                * u[j * column_count + column]
+                                * singular_values[column]
+                        })
+                        .sum()
+                })
+                .collect();
+            self.tensors[k - 1] = Tensor {
+                left_dimension: previous.left_dimension,
+                right_dimension: kept_count,
+                elements,
+            };
+            self.orthogonality_center -= 1;
+        }
+    }
+
+    fn apply_matrix(&mut self, matrix: &Matrix, qubit_number: usize) {
+        self.check_qubit_numbers(&[qubit_number]);
+        let tensor = &mut self.tensors[qubit_number];
+        let right_dimension = tensor.right_dimension;
+        for block in tensor.elements.chunks_exact_mut(2 * right_dimension) {
+            let (zeros, ones) = block.split_at_mut(right_dimension);
+            for (a0, a1) in zeros.iter_mut().zip(ones.iter_mut()) {
+                (*a0, *a1) = (
+                    matrix[0] * *a0 + matrix[1] * *a1,
+                    matrix[2] * *a0 + matrix[3] * *a1,
+                );
+            }
+        }
+    }
+
+    // Apply the gate to the qubits k and k + 1, the qubit k being the first qubit of the gate.
+    fn apply_neighbour_matrix(&mut self, matrix: &TwoQubitMatrix, qubit_number: usize) {
+        self.move_orthogonality_center(qubit_number);
+        let (tensor, next) = (&self.tensors[qubit_number], &self.tensors[qubit_number + 1]);
+        let (left_dimension, bond_dimension, right_dimension) = (
+            tensor.left_dimension,
+            tensor.right_dimension,
+            next.right_dimension,
+        );
+        // Θ[l, s0, s1, r] at the row 2 l + s0 and the column s1 r_dim + r.
+        let column_count = 2 * right_dimension;
+        let row_count = 2 * left_dimension;
+        let mut theta = vec![Complex::new(0.0, 0.0); row_count * column_count];
+        for row in 0..row_count {
+            for k in 0..bond_dimension {
+                let a = tensor.elements[row * bond_dimension + k];
+                if a == Complex::new(0.0, 0.0) {
+                    continue;
+                }
+                for column in 0..column_count {
+                    theta[row * column_count + column] +=
+                        a * next.elements[k * column_count + column];
+                }
+            }
+        }
+        for left in 0..left_dimension {
+            for right in 0..right_dimension {
+                let indices: Vec<usize> = (0..4)
+                    .map(|b| {
+                        (2 * left + (b & 1)) * column_count + (b >> 1) * right_dimension + right
+                    })
+                    .collect();
+                let values: Vec<Complex<f64>> = indices.iter().map(|&i| theta[i]).collect();
+                for (row, &i) in matrix.iter().zip(indices.iter()) {
+                    theta[i] = row.iter().zip(values.iter()).map(|(m, a)| m * a).sum();
+                }
+            }
+        }
+
+        let (u, mut singular_values, v_dagger) = svd(&theta, row_count, column_count);
+        let kept_count =
+            self.truncate(&mut singular_values, self.max_bond_dimension.min(row_count));
+        self.tensors[qubit_number] = Tensor {
+            left_dimension,
+            right_dimension: kept_count,
+            elements: (0..row_count * kept_count)
+                .map(|i| u[i / kept_count * column_count + i % kept_count])
+                .collect(),
+        };
+        self.tensors[qubit_number + 1] = Tensor {
+            left_dimension: kept_count,
+            right_dimension,
+            elements: (0..kept_count * column_count)
+                .map(|i| v_dagger[i] * singular_values[i / column_count])
+                .collect(),
+        };
+        self.orthogonality_center = qubit_number + 1;
+    }
+
+    // Apply the gate to the qubits, moving the qubit 1 next to the qubit 0 by swaps and back.
+    fn apply_two_qubit_matrix(
+        &mut self,
+        matrix: &TwoQubitMatrix,
+        qubit_number0: usize,
+        qubit_number1: usize,
+    ) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        let swap = swap_matrix();
+        if qubit_number0 < qubit_number1 {
+            for k in (qubit_number0 + 1..qubit_number1).rev() {
+                self.apply_neighbour_matrix(&swap, k);
+            }
+            self.apply_neighbour_matrix(matrix, qubit_number0);
+            for k in qubit_number0 + 1..qubit_number1 {
+                self.apply_neighbour_matrix(&swap, k);
+            }
+        } else {
+            for k in qubit_number1..qubit_number0 - 1 {
+                self.apply_neighbour_matrix(&swap, k);
+            }
+            self.apply_neighbour_matrix(&exchange_qubits(matrix), qubit_number0 - 1);
+            for k in (qubit_number1..qubit_number0 - 1).rev() {
+                self.apply_neighbour_matrix(&swap, k);
+            }
+        }
+    }
+
+    // Measure the qubit in the Z-basis, from the tensor of the orthogonality center.
+    fn measure_qubit(&mut self, qubit_number: usize) -> bool {
+        self.move_orthogonality_center(qubit_number);
+        let tensor = &mut self.tensors[qubit_number];
+        let right_dimension = tensor.right_dimension;
+        let probability_one: f64 = tensor
+            .elements
+            .chunks_exact(2 * right_dimension)
+            .flat_map(|block| block[right_dimension..].iter())
+            .map(|a| a.norm_sqr())
+            .sum();
+        let value = self.rng.gen::<f64>() < probability_one;
+        let probability = if value {
+            probability_one
+        } else {
+            1.0 - probability_one
+        };
+        let scale = 1.0 / probability.sqrt();
+        for block in tensor.elements.chunks_exact_mut(2 * right_dimension) {
+            let (zeros, ones) = block.split_at_mut(right_dimension);
+            let (measured, other) = if value { (ones, zeros) } else { (zeros, ones) };
+            measured.iter_mut().for_each(|a| *a *= scale);
+            other.fill(Complex::new(0.0, 0.0));
+        }
+        value
+    }
+}
+
+impl Simulation for MpsSimulation {
+    // Reset the qubits to the product state |0...0⟩, with the bonds of dimension 1.
+    fn reset(&mut self) {
+        self.tensors = (0..self.qubit_count)
+            .map(|_| Tensor {
+                left_dimension: 1,
+                right_dimension: 1,
+                elements: vec![Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+            })
+            .collect();
+        self.orthogonality_center = 0;
+    }
+
+    fn measure_all(&mut self) -> Vec<bool> {
+        (0..self.qubit_count)
+            .map(|qubit_number| self.measure_qubit(qubit_number))
+            .collect()
+    }
+
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
+        for &qubit_number in qubit_numbers.iter() {
+            self.check_qubit_numbers(&[qubit_number]);
+        }
+        qubit_numbers
+            .iter()
+            .map(|&qubit_number| self.measure_qubit(qubit_number))
+            .collect()
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_X_MATRIX, qubit_number);
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Y_MATRIX, qubit_number);
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Z_MATRIX, qubit_number);
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.apply_matrix(&HADAMARD_MATRIX, qubit_number);
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.apply_matrix(&S_MATRIX, qubit_number);
+    }
+
+    fn t(&mut self, qubit_number: usize) {
+        self.apply_matrix(&T_MATRIX, qubit_number);
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rx_matrix(theta), qubit_number);
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&ry_matrix(theta), qubit_number);
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rz_matrix(theta), qubit_number);
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_two_qubit_matrix(
+            &controlled_matrix(&PAULI_X_MATRIX),
+            control_qubit_number,
+            target_qubit_number,
+        );
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_two_qubit_matrix(
+            &controlled_matrix(&PAULI_Z_MATRIX),
+            control_qubit_number,
+            target_qubit_number,
+        );
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        let matrix = phase_matrix(Complex::new(0.0, theta).exp());
+        self.apply_two_qubit_matrix(
+            &controlled_matrix(&matrix),
+            control_qubit_number,
+            target_qubit_number,
+        );
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.apply_two_qubit_matrix(&swap_matrix(), qubit_number0, qubit_number1);
+    }
+
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        for gate in u_f_gates(f, qubit_number0, qubit_number1) {
+            gate.apply(self);
+        }
+    }
+
+    // The Toffoli gate from the CNOT, H, T, and T† gates.
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        self.check_qubit_numbers(&[
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        ]);
+        let (a, b, c) = (
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        );
+        let t_dagger = phase_matrix(Complex::from_polar(1.0, -std::f64::consts::FRAC_PI_4));
+        self.hadamard(c);
+        self.cnot(b, c);
+        self.apply_matrix(&t_dagger, c);
+        self.cnot(a, c);
+        self.t(c);
+        self.cnot(b, c);
+        self.apply_matrix(&t_dagger, c);
+        self.cnot(a, c);
+        self.t(b);
+        self.t(c);
+        self.hadamard(c);
+        self.cnot(a, b);
+        self.t(a);
+        self.apply_matrix(&t_dagger, b);
+        self.cnot(a, b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn amplitudes_match_state_vector() {
+        let gates: [fn(&mut dyn Simulation); 10] = [
+            |s| s.hadamard(0),
+            |s| s.ry(0.7, 3),
+            |s| s.cnot(0, 3),
+            |s| s.cphase(0.9, 3, 1),
+            |s| s.t(1),
+            |s| s.hadamard(2),
+            |s| s.apply_u_f(|x| !x, 2, 0),
+            |s| s.toffoli(3, 0, 1),
+            |s| s.swap(0, 2),
+            |s| s.rx(1.3, 1),
+        ];
+        let mut simulation = MpsSimulation::new(4, 0);
+        let mut state_vector_simulation = QuantumSimulation::new(4, 0);
+        for gate in gates.iter() {
+            gate(&mut simulation);
+            gate(&mut state_vector_simulation);
+        }
+        for (i, amplitude) in state_vector_simulation.amplitudes().iter().enumerate() {
+            let values: Vec<bool> = (0..4).map(|q| (i >> q) & 1 == 1).collect();
+            assert!((simulation.amplitude(&values) - amplitude).norm() < 1e-10);
+        }
+
+        // The GHZ state has the bonds of dimension 2, and its measured qubits are all equal.
+        let mut ghz_simulation = MpsSimulation::new(6, 1);
+        ghz_simulation.hadamard(0);
+        for q in 1..6 {
+            ghz_simulation.cnot(q - 1, q);
+        }
+        assert_eq!(ghz_simulation.bond_dimensions(), vec![2; 5]);
+        let values = ghz_simulation.measure_all();
+        assert!(values.iter().all(|&value| value == values[0]));
+    }
+}
@@ -0,0 +1,639 @@
+/*
+Noise channels and the noise models attaching them to the gates.
+
+A channel on a qubit is given by its Kraus operators K_i, with the sum of K_i† K_i being the
+identity, and maps the density matrix ρ to the sum of K_i ρ K_i†. The density matrix simulation
+applies it exactly, and the trajectory simulation applies one of the operators chosen at random.
+A noise model lists the channels applied after the gates, either all of them or the gates of
+given names, to each of the qubits of a gate or only to given qubits. With the relaxation times
+T1 and T2 of the qubits and the durations of the gates, the thermal relaxation over a gate is
+applied to its qubits after the other channels. The coherent rotation errors add a fixed or
+a normally distributed error to the angles of the rotation gates, RX, RY, RZ, and CPHASE.
+The crosstalk of a coupled pair of qubits is a two-qubit unitary, such as a small ZZ rotation,
+applied to the pair after every gate acting on either of its qubits.
+The readout errors flip the measured values of a qubit with the assignment error probabilities
+p(1|0) and p(0|1), without changing the collapsed state.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use num_complex::Complex;
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::circuit::Gate;
+use crate::decomposition::Matrix4;
+use crate::gate::Matrix;
+
+#[cfg(feature = "json")]
+mod calibration;
+
+const COMPLETENESS_TOLERANCE: f64 = 1e-10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KrausChannel {
+    kraus_operators: Vec<Matrix>,
+}
+
+fn check_probability(probability: f64) {
+    assert!(
+        (0.0..=1.0).contains(&probability),
+        "The probability of a channel has to be between 0 and 1."
+    );
+}
+
+fn real_matrix(elements: [f64; 4]) -> Matrix {
+    elements.map(|element| Complex::new(element, 0.0))
+}
+
+impl KrausChannel {
+    pub fn new(kraus_operators: Vec<Matrix>) -> KrausChannel {
+        // The sum of K† K, with (K† K)[i, j] the sum over k of conj(K[k, i]) K[k, j].
+        let mut sum = [Complex::new(0.0, 0.0); 4];
+        for operator in kraus_operators.iter() {
+            for i in 0..2 {
+                for j in 0..2 {
+                    sum[2 * i + j] +=
+                        operator[i].conj() * operator[j] + operator[2 + i].conj() * operator[2 + j];
+                }
+            }
+        }
+        let identity = real_matrix([1.0, 0.0, 0.0, 1.0]);
+        assert!(
+            sum.iter()
+                .zip(identity.iter())
+                .all(|(element, expected)| (element - expected).norm() < COMPLETENESS_TOLERANCE),
+            "The Kraus operators of a channel have to satisfy the sum of K† K being the identity."
+        );
+        KrausChannel { kraus_operators }
+    }
+
+    // X with the probability.
+    pub fn bit_flip(probability: f64) -> KrausChannel {
+        check_probability(probability);
+        KrausChannel::new(vec![
+            real_matrix([
+                (1.0 - probability).sqrt(),
+                0.0,
+                0.0,
+                (1.0 - probability).sqrt(),
+            ]),
+            real_matrix([0.0, probability.sqrt(), probability.sqrt(), 0.0]),
+        ])
+    }
+
+    // Z with the probability.
+    pub fn phase_flip(probability: f64) -> KrausChannel {
+        check_probability(probability);
+        KrausChannel::new(vec![
+            real_matrix([
+                (1.0 - probability).sqrt(),
+                0.0,
+                0.0,
+                (1.0 - probability).sqrt(),
+            ]),
+            real_matrix([probability.sqrt(), 0.0, 0.0, -probability.sqrt()]),
+        ])
+    }
+
+    // The qubit replaced by the maximally mixed state with the probability, ρ becoming
+    // (1 - p) ρ + p I/2, which is X, Y, or Z each with the probability p/4.
+    pub fn depolarizing(probability: f64) -> KrausChannel {
+        check_probability(probability);
+        let identity_factor = (1.0 - 0.75 * probability).sqrt();
+        let pauli_factor = (probability / 4.0).sqrt();
+        KrausChannel::new(vec![
+            real_matrix([identity_factor, 0.0, 0.0, identity_factor]),
+            real_matrix([0.0, pauli_factor, pauli_factor, 0.0]),
+            [
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, -pauli_factor),
+                Complex::new(0.0, pauli_factor),
+                Complex::new(0.0, 0.0),
+            ],
+            real_matrix([pauli_factor, 0.0, 0.0, -pauli_factor]),
+        ])
+    }
+
+    // X, Y, and Z with the probabilities p_x, p_y, and p_z, and the identity otherwise.
+    pub fn pauli(p_x: f64, p_y: f64, p_z: f64) -> KrausChannel {
+        for probability in [p_x, p_y, p_z, p_x + p_y + p_z] {
+            check_probability(probability);
+        }
+        let identity_factor = (1.0 - p_x - p_y - p_z).sqrt();
+        let (x_factor, y_factor, z_factor) = (p_x.sqrt(), p_y.sqrt(), p_z.sqrt());
+        let operators = [
+            real_matrix([identity_factor, 0.0, 0.0, identity_factor]),
+            real_matrix([0.0, x_factor, x_factor, 0.0]),
+            [
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, -y_factor),
+                Complex::new(0.0, y_factor),
+                Complex::new(0.0, 0.0),
+            ],
+            real_matrix([z_factor, 0.0, 0.0, -z_factor]),
+        ];
+        // The operators of the zero probability are left out.
+        KrausChannel::new(
+            operators
+                .into_iter()
+                .filter(|operator| operator.iter().any(|element| element.norm() > 0.0))
+                .collect(),
+        )
+    }
+
+    // The decay of |1⟩ to |0⟩ with the probability gamma.
+    pub fn amplitude_damping(gamma: f64) -> KrausChannel {
+        check_probability(gamma);
+        KrausChannel::new(vec![
+            real_matrix([1.0, 0.0, 0.0, (1.0 - gamma).sqrt()]),
+            real_matrix([0.0, gamma.sqrt(), 0.0, 0.0]),
+        ])
+    }
+
+    // The loss of the coherence between |0⟩ and |1⟩, scaled by sqrt(1 - lambda).
+    pub fn phase_damping(lambda: f64) -> KrausChannel {
+        check_probability(lambda);
+        KrausChannel::new(vec![
+            real_matrix([1.0, 0.0, 0.0, (1.0 - lambda).sqrt()]),
+            real_matrix([0.0, 0.0, 0.0, lambda.sqrt()]),
+        ])
+    }
+
+    // The relaxation of a qubit with the times T1 and T2 over the duration, in the same unit:
+    // the amplitude damping by 1 - e^{-t/T1} followed by the phase damping which brings the
+    // decay of the coherence to e^{-t/T2}. T2 cannot exceed 2 T1.
+    pub fn thermal_relaxation(t1: f64, t2: f64, duration: f64) -> KrausChannel {
+        assert!(
+            t1 > 0.0 && t2 > 0.0 && duration >= 0.0,
+            "The relaxation times have to be positive and the duration nonnegative."
+        );
+        assert!(
+            t2 <= 2.0 * t1 * (1.0 + COMPLETENESS_TOLERANCE),
+            "The time T2 cannot exceed 2 T1."
+        );
+        let gamma = 1.0 - (-duration / t1).exp();
+        // The pure dephasing rate 1/T2 - 1/(2 T1).
+        let dephasing_rate = (1.0 / t2 - 0.5 / t1).max(0.0);
+        let lambda = 1.0 - (-2.0 * duration * dephasing_rate).exp();
+        KrausChannel::new(vec![
+            real_matrix([1.0, 0.0, 0.0, ((1.0 - lambda) * (1.0 - gamma)).sqrt()]),
+            real_matrix([0.0, 0.0, 0.0, (lambda * (1.0 - gamma)).sqrt()]),
+            real_matrix([0.0, gamma.sqrt(), 0.0, 0.0]),
+        ])
+    }
+
+    pub fn kraus_operators(&self) -> &[Matrix] {
+        &self.kraus_operators
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReadoutError {
+    // The probability of reading 1 when the qubit is measured 0.
+    prob_1_given_0: f64,
+    // The probability of reading 0 when the qubit is measured 1.
+    prob_0_given_1: f64,
+}
+
+impl ReadoutError {
+    pub fn new(prob_1_given_0: f64, prob_0_given_1: f64) -> ReadoutError {
+        check_probability(prob_1_given_0);
+        check_probability(prob_0_given_1);
+        ReadoutError {
+            prob_1_given_0,
+            prob_0_given_1,
+        }
+    }
+
+    pub fn prob_1_given_0(&self) -> f64 {
+        self.prob_1_given_0
+    }
+
+    pub fn prob_0_given_1(&self) -> f64 {
+        self.prob_0_given_1
+    }
+
+    // The value read for the measured value. No random number is drawn when the value cannot
+    // be flipped, so that the results without the errors stay the same.
+    pub(crate) fn read<R: Rng>(&self, value: bool, rng: &mut R) -> bool {
+        let probability = if value {
+            self.prob_0_given_1
+        } else {
+            self.prob_1_given_0
+        };
+        if probability > 0.0 && rng.gen::<f64>() < probability {
+            !value
+        } else {
+            value
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RotationError {
+    // The angle over-rotated by the error, or under-rotated if it is negative.
+    Fixed(f64),
+    // The angle rotated by an error of the normal distribution with the standard deviation,
+    // drawn for each gate.
+    Gaussian(f64),
+}
+
+impl RotationError {
+    pub(crate) fn perturb<R: Rng>(&self, theta: f64, rng: &mut R) -> f64 {
+        match *self {
+            RotationError::Fixed(epsilon) => theta + epsilon,
+            RotationError::Gaussian(sigma) => {
+                // The Box–Muller transform.
+                let u0 = 1.0 - rng.gen::<f64>();
+                let u1 = rng.gen::<f64>();
+                theta + sigma * (-2.0 * u0.ln()).sqrt() * (std::f64::consts::TAU * u1).cos()
+            }
+        }
+    }
+}
+
+// A channel applied after the matching gates to their matching qubits.
+#[derive(Clone, Debug)]
+struct GateError {
+    channel: KrausChannel,
+    // The names of the gates followed by the channel, all the gates if none.
+    gate_names: Option<Vec<String>>,
+    // The qubits of the gates the channel is applied to, all the qubits if none.
+    qubit_numbers: Option<Vec<usize>>,
+    // The qubits of the gates followed by the channel, in any order, any qubits if none.
+    gate_qubit_numbers: Option<Vec<usize>>,
+}
+
+impl GateError {
+    fn matches(&self, gate: &Gate) -> bool {
+        self.gate_names
+            .as_ref()
+            .is_none_or(|names| names.iter().any(|name| name == gate.name()))
+            && self
+                .gate_qubit_numbers
+                .as_ref()
+                .is_none_or(|qubit_numbers| {
+                    let qubits = gate.qubits();
+                    qubits.len() == qubit_numbers.len()
+                        && qubits.iter().all(|q| qubit_numbers.contains(q))
+                })
+    }
+
+    fn applies_to(&self, qubit_number: usize) -> bool {
+        self.qubit_numbers
+            .as_ref()
+            .is_none_or(|qubit_numbers| qubit_numbers.contains(&qubit_number))
+    }
+}
+
+fn gate_names(names: &[&str]) -> Option<Vec<String>> {
+    Some(names.iter().map(|name| name.to_string()).collect())
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NoiseModel {
+    // The channels applied after the gates, in the order they were added.
+    gate_errors: Vec<GateError>,
+    // The times T1 and T2 of the qubits.
+    relaxation_times: BTreeMap<usize, (f64, f64)>,
+    // The durations of the gates of the names, in the unit of the relaxation times.
+    gate_durations: BTreeMap<String, f64>,
+    rotation_error: Option<RotationError>,
+    // The readout errors of the qubits, set on the simulations with the noise.
+    readout_errors: BTreeMap<usize, ReadoutError>,
+    // The unitaries applied to the coupled pairs of qubits.
+    crosstalks: Vec<(Matrix4, usize, usize)>,
+}
+
+impl NoiseModel {
+    pub fn new() -> NoiseModel {
+        NoiseModel::default()
+    }
+
+    // Apply the channel after every gate to each of its qubits.
+    pub fn add_all_qubit_error(&mut self, channel: KrausChannel) {
+        self.gate_errors.push(GateError {
+            channel,
+            gate_names: None,
+            qubit_numbers: None,
+            gate_qubit_numbers: None,
+        });
+    }
+
+    // Apply the channel after the gates of the names, as given by Gate::name, to each of
+    // their qubits.
+    pub fn add_gate_error(&mut self, channel: KrausChannel, names: &[&str]) {
+        self.gate_errors.push(GateError {
+            channel,
+            gate_names: gate_names(names),
+            qubit_numbers: None,
+            gate_qubit_numbers: None,
+        });
+    }
+
+    // Apply the channel after the gates of the names to those of their qubits in the qubit
+    // numbers.
+    pub fn add_qubit_error(
+        &mut self,
+        channel: KrausChannel,
+        names: &[&str],
+        qubit_numbers: &[usize],
+    ) {
+        self.gate_errors.push(GateError {
+            channel,
+            gate_names: gate_names(names),
+            qubit_numbers: Some(qubit_numbers.to_vec()),
+            gate_qubit_numbers: None,
+        });
+    }
+
+    // Apply the channel after the gates of the names acting on the qubits, in any order,
+    // to each of the qubits.
+    pub fn add_gate_qubits_error(
+        &mut self,
+        channel: KrausChannel,
+        names: &[&str],
+        gate_qubit_numbers: &[usize],
+    ) {
+        self.gate_errors.push(GateError {
+            channel,
+            gate_names: gate_names(names),
+            qubit_numbers: None,
+            gate_qubit_numbers: Some(gate_qubit_numbers.to_vec()),
+        });
+    }
+
+    // Relax the qubit with the times T1 and T2 over the gates with durations.
+    pub fn set_relaxation_times(&mut self, qubit_number: usize, t1: f64, t2: f64) {
+        // The times are checked by the channel.
+        KrausChannel::thermal_relaxation(t1, t2, 0.0);
+        self.relaxation_times.insert(qubit_number, (t1, t2));
+    }
+
+    // The duration of the gates of the name, as given by Gate::name.
+    pub fn set_gate_duration(&mut self, name: &str, duration: f64) {
+        assert!(
+            duration >= 0.0,
+            "The duration of a gate cannot be negative."
+        );
+        self.gate_durations.insert(name.to_string(), duration);
+    }
+
+    // Perturb the angles of the rotation gates by the error.
+    pub fn set_rotation_error(&mut self, rotation_error: RotationError) {
+        self.rotation_error = Some(rotation_error);
+    }
+
+    pub fn rotation_error(&self) -> Option<RotationError> {
+        self.rotation_error
+    }
+
+    // Flip the measured values of the qubit with the probabilities of the readout error.
+    pub fn set_readout_error(&mut self, qubit_number: usize, readout_error: ReadoutError) {
+        self.readout_errors.insert(qubit_number, readout_error);
+    }
+
+    // The readout errors of the qubits, in the order of the qubits.
+    pub fn readout_errors(&self) -> impl Iterator<Item = (usize, ReadoutError)> + '_ {
+        self.readout_errors
+            .iter()
+            .map(|(&qubit_number, &readout_error)| (qubit_number, readout_error))
+    }
+
+    // Apply the two-qubit unitary, indexed by q0 + 2·q1, to the pair of qubits after every gate
+    // acting on either of them.
+    pub fn add_crosstalk(&mut self, unitary: Matrix4, qubit_number0: usize, qubit_number1: usize) {
+        assert!(
+            qubit_number0 != qubit_number1,
+            "The qubits of a crosstalk have to be distinct."
+        );
+        let rows: Vec<Vec<Complex<f64>>> = unitary.iter().map(|row| row.to_vec()).collect();
+        assert!(
+            crate::gate_registry::is_unitary(&rows),
+            "The matrix of a crosstalk has to be unitary."
+        );
+        self.crosstalks
+            .push((unitary, qubit_number0, qubit_number1));
+    }
+
+    // The ZZ interaction exp(-i θ/2 Z⊗Z) as the crosstalk of the pair of qubits.
+    pub fn add_zz_crosstalk(&mut self, theta: f64, qubit_number0: usize, qubit_number1: usize) {
+        let mut unitary = [[Complex::new(0.0, 0.0); 4]; 4];
+        for (i, row) in unitary.iter_mut().enumerate() {
+            // Z⊗Z is 1 for the equal bits and -1 for the different ones.
+            let parity = if (i ^ (i >> 1)) & 1 == 0 { 1.0 } else { -1.0 };
+            row[i] = Complex::from_polar(1.0, -parity * theta / 2.0);
+        }
+        self.add_crosstalk(unitary, qubit_number0, qubit_number1);
+    }
+
+    // The crosstalks applied after the gate, with the pairs of their qubits, in order.
+    pub(crate) fn crosstalks_after(&self, gate: &Gate) -> Vec<(&Matrix4, usize, usize)> {
+        if let Gate::Measure(_, _) = gate {
+            return Vec::new();
+        }
+        let qubits = gate.qubits();
+        self.crosstalks
+            .iter()
+            .filter(|(_, q0, q1)| qubits.contains(q0) || qubits.contains(q1))
+            .map(|(unitary, q0, q1)| (unitary, *q0, *q1))
+            .collect()
+    }
+
+    // The angle of a rotation gate with the rotation error.
+    pub(crate) fn rotation_angle<R: Rng>(&self, theta: f64, rng: &mut R) -> f64 {
+        match self.rotation_error {
+            Some(rotation_error) => rotation_error.perturb(theta, rng),
+            None => theta,
+        }
+    }
+
+    // The channels applied after the gate, with their qubits, in order.
+    pub(crate) fn channels_after(&self, gate: &Gate) -> Vec<(Cow<'_, KrausChannel>, usize)> {
+        if let Gate::Measure(_, _) = gate {
+            return Vec::new();
+        }
+        let qubits = gate.qubits();
+        let mut channels: Vec<(Cow<KrausChannel>, usize)> = self
+            .gate_errors
+            .iter()
+            .filter(|error| error.matches(gate))
+            .flat_map(|error| {
+                qubits
+                    .iter()
+                    .filter(|&&q| error.applies_to(q))
+                    .map(move |&q| (Cow::Borrowed(&error.channel), q))
+            })
+            .collect();
+        if let Some(&duration) = self.gate_durations.get(gate.name()) {
+            for &q in qubits.iter() {
+                if let Some(&(t1, t2)) = self.relaxation_times.get(&q) {
+                    let channel = KrausChannel::thermal_relaxation(t1, t2, duration);
+                    channels.push((Cow::Owned(channel), q));
+                }
+            }
+        }
+        channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    use crate::circuit::Circuit;
+    use crate::density_matrix_simulation::DensityMatrixSimulation;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+    use crate::trajectory_simulation::TrajectorySimulation;
+
+    #[test]
+    fn channels_follow_matching_gates() {
+        let mut noise_model = NoiseModel::new();
+        noise_model.add_all_qubit_error(KrausChannel::depolarizing(0.01));
+        noise_model.add_gate_error(KrausChannel::bit_flip(0.1), &["cnot"]);
+        noise_model.add_qubit_error(KrausChannel::phase_flip(0.2), &["hadamard", "cnot"], &[1]);
+
+        let qubits = |noise_model: &NoiseModel, gate: Gate| -> Vec<usize> {
+            noise_model
+                .channels_after(&gate)
+                .into_iter()
+                .map(|(_, q)| q)
+                .collect()
+        };
+        assert_eq!(qubits(&noise_model, Gate::Hadamard(0)), vec![0]);
+        assert_eq!(qubits(&noise_model, Gate::Hadamard(1)), vec![1, 1]);
+        assert_eq!(qubits(&noise_model, Gate::Cnot(0, 1)), vec![0, 1, 0, 1, 1]);
+        assert_eq!(qubits(&noise_model, Gate::T(1)), vec![1]);
+        assert!(qubits(&noise_model, Gate::Measure(1, 0)).is_empty());
+    }
+
+    #[test]
+    fn pauli_channels_generalize_flips() {
+        assert_eq!(
+            KrausChannel::pauli(0.1, 0.0, 0.0),
+            KrausChannel::bit_flip(0.1)
+        );
+        assert_eq!(
+            KrausChannel::pauli(0.0, 0.0, 0.2),
+            KrausChannel::phase_flip(0.2)
+        );
+
+        // The Y and Z errors flip the phase of |+⟩, which scales its coherence by 1 - 2(p_y + p_z).
+        let channel = KrausChannel::pauli(0.1, 0.05, 0.15);
+        let mut simulation = DensityMatrixSimulation::new(1, 0);
+        simulation.hadamard(0);
+        simulation.apply_kraus(channel.kraus_operators(), 0);
+        assert!((simulation.element(0, 1).re - 0.5 * 0.6).abs() < 1e-12);
+        assert!((simulation.probabilities()[1] - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn thermal_relaxation_follows_timed_gates() {
+        let mut noise_model = NoiseModel::new();
+        noise_model.add_gate_error(KrausChannel::bit_flip(0.1), &["t"]);
+        let qubits = |noise_model: &NoiseModel, gate: Gate| -> Vec<usize> {
+            noise_model
+                .channels_after(&gate)
+                .into_iter()
+                .map(|(_, q)| q)
+                .collect()
+        };
+
+        // Over T1 the population of |1⟩ decays to 1/e, and over T2 its coherence too.
+        noise_model.set_relaxation_times(0, 50.0, 70.0);
+        noise_model.set_gate_duration("t", 50.0);
+        assert_eq!(qubits(&noise_model, Gate::T(0)), vec![0, 0]);
+        assert_eq!(qubits(&noise_model, Gate::T(1)), vec![1]);
+        let channel = KrausChannel::thermal_relaxation(50.0, 70.0, 70.0);
+        let operators = channel.kraus_operators();
+        let population: f64 = operators.iter().map(|k| k[3].norm_sqr()).sum();
+        let coherence: f64 = operators.iter().map(|k| (k[0] * k[3].conj()).re).sum();
+        assert!((population - (-70.0f64 / 50.0).exp()).abs() < 1e-12);
+        assert!((coherence - (-1.0f64).exp()).abs() < 1e-12);
+
+        let mut density_matrix_simulation = DensityMatrixSimulation::new(1, 0);
+        density_matrix_simulation.hadamard(0);
+        density_matrix_simulation.apply_kraus(channel.kraus_operators(), 0);
+        let expected_population = 0.5 * (-70.0f64 / 50.0).exp();
+        assert!((density_matrix_simulation.probabilities()[1] - expected_population).abs() < 1e-12);
+        assert!(
+            (density_matrix_simulation.element(0, 1).norm() - 0.5 * (-1.0f64).exp()).abs() < 1e-12
+        );
+
+        // The trajectories of |1⟩ relaxed over a single gate as long as T1.
+        let mut noise_model = NoiseModel::new();
+        noise_model.set_relaxation_times(0, 50.0, 70.0);
+        noise_model.set_gate_duration("pauli_x", 50.0);
+        let mut circuit = Circuit::new(1);
+        circuit.push(Gate::PauliX(0));
+        let mut simulation = TrajectorySimulation::new(1, 0, noise_model);
+        let probabilities = simulation.average_probabilities(&circuit, 4000);
+        assert!((probabilities[1] - (-1.0f64).exp()).abs() < 0.03);
+    }
+
+    #[test]
+    fn rotation_errors_perturb_angles() {
+        let mut noise_model = NoiseModel::new();
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+        assert_eq!(noise_model.rotation_angle(0.5, &mut rng), 0.5);
+        noise_model.set_rotation_error(RotationError::Fixed(-0.01));
+        assert!((noise_model.rotation_angle(0.5, &mut rng) - 0.49).abs() < 1e-12);
+        noise_model.set_rotation_error(RotationError::Gaussian(0.1));
+        let errors: Vec<f64> = (0..10000)
+            .map(|_| noise_model.rotation_angle(0.5, &mut rng) - 0.5)
+            .collect();
+        let mean = errors.iter().sum::<f64>() / errors.len() as f64;
+        let variance = errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64;
+        assert!(mean.abs() < 0.005);
+        assert!((variance.sqrt() - 0.1).abs() < 0.005);
+
+        // An over-rotation of RX(π/2) measures |1⟩ with the probability sin^2((π/2 + 0.1)/2).
+        let mut noise_model = NoiseModel::new();
+        noise_model.set_rotation_error(RotationError::Fixed(0.1));
+        let mut simulation = TrajectorySimulation::new(1, 0, noise_model);
+        simulation.rx(std::f64::consts::FRAC_PI_2, 0);
+        let expected = ((std::f64::consts::FRAC_PI_2 + 0.1) / 2.0).sin().powi(2);
+        assert!((simulation.amplitudes()[1].norm_sqr() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn crosstalk_follows_gates_on_coupled_qubits() {
+        let mut noise_model = NoiseModel::new();
+        noise_model.add_zz_crosstalk(0.1, 1, 2);
+        let crosstalks = noise_model.crosstalks_after(&Gate::Cnot(0, 1));
+        assert_eq!(crosstalks.len(), 1);
+        let (unitary, q0, q1) = crosstalks[0];
+        assert_eq!((q0, q1), (1, 2));
+        assert!((unitary[1][1] - Complex::from_polar(1.0, 0.05)).norm() < 1e-12);
+        assert!((unitary[3][3] - Complex::from_polar(1.0, -0.05)).norm() < 1e-12);
+        assert!(noise_model.crosstalks_after(&Gate::T(0)).is_empty());
+
+        // With the qubit 2 in |1⟩, the ZZ rotation after the Hadamard gate on the qubit 1
+        // rotates it by RZ(-θ), and the T gate on the uncoupled qubit 0 adds no crosstalk.
+        let mut simulation = TrajectorySimulation::new(3, 0, noise_model);
+        simulation.pauli_x(2);
+        simulation.hadamard(1);
+        simulation.t(0);
+        let mut expected_simulation = QuantumSimulation::new(3, 0);
+        expected_simulation.pauli_x(2);
+        expected_simulation.hadamard(1);
+        expected_simulation.rz(-0.1, 1);
+        let expected = expected_simulation.amplitudes().to_vec();
+        let overlap: Complex<f64> = simulation
+            .amplitudes()
+            .iter()
+            .zip(expected.iter())
+            .map(|(a, b)| a.conj() * b)
+            .sum();
+        assert!((overlap.norm() - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,261 @@
+/*
+Import of the noise models from the calibration data of the devices.
+
+The calibration data is the JSON format of the backend properties of IBM Quantum and Qiskit,
+with a list of the parameters of each qubit and a list of the gates, each with its qubits and
+its parameters, like
+
+{"qubits": [[{"name": "T1", "unit": "us", "value": 120.5},
+             {"name": "T2", "unit": "us", "value": 95.1},
+             {"name": "prob_meas0_prep1", "value": 0.02},
+             {"name": "prob_meas1_prep0", "value": 0.01}]],
+ "gates": [{"gate": "sx", "qubits": [0],
+            "parameters": [{"name": "gate_error", "value": 0.0003},
+                           {"name": "gate_length", "unit": "ns", "value": 35.5}]}]}
+
+The times are converted to nanoseconds. The error e of a gate on n qubits becomes the
+depolarizing channel of the probability p on each of its qubits, whose average gate infidelity
+d/(d + 1) (1 - (1 - 3p/4)^n), with d = 2^n, is e. The duration of a gate is the mean of the
+lengths of the gates of its name. Without the probabilities of the readout errors, the readout
+error of a qubit is symmetric. The gates outside the table of the gate names, like the resets,
+are ignored.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::circuit::ParseError;
+use crate::noise::{KrausChannel, NoiseModel, ReadoutError};
+
+// The gate names of the devices with the names of the gates of the circuits they implement.
+const GATE_NAMES: [(&str, &[&str]); 15] = [
+    ("x", &["pauli_x"]),
+    ("y", &["pauli_y"]),
+    ("z", &["pauli_z"]),
+    ("h", &["hadamard"]),
+    ("s", &["s"]),
+    ("t", &["t"]),
+    ("sx", &["hadamard", "rx", "ry"]),
+    ("rx", &["rx"]),
+    ("ry", &["ry"]),
+    ("rz", &["rz"]),
+    ("cx", &["cnot"]),
+    ("ecr", &["cnot"]),
+    ("cz", &["cz", "cphase"]),
+    ("swap", &["swap"]),
+    ("ccx", &["toffoli"]),
+];
+
+// The errors of the contents have no line, reported as the line 0.
+fn error<T>(message: String) -> Result<T, ParseError> {
+    Err(ParseError { line: 0, message })
+}
+
+// The parameters of a list as the pairs of the name and the value in nanoseconds for the times.
+fn parameters(value: &Value) -> Result<Vec<(String, f64)>, ParseError> {
+    let list = match value.as_array() {
+        Some(list) => list,
+        None => return error("The parameters have to be a list.".to_string()),
+    };
+    list.iter()
+        .map(|parameter| {
+            let name = match parameter.get("name").and_then(|name| name.as_str()) {
+                Some(name) => name,
+                None => return error("A parameter has no name.".to_string()),
+            };
+            let value = match parameter.get("value").and_then(|value| value.as_f64()) {
+                Some(value) => value,
+                None => return error(format!("The parameter {} has no number value.", name)),
+            };
+            let scale = match parameter.get("unit").and_then(|unit| unit.as_str()) {
+                None | Some("") | Some("ns") => 1.0,
+                Some("s") => 1e9,
+                Some("ms") => 1e6,
+                Some("us") | Some("µs") => 1e3,
+                Some("GHz") | Some("MHz") | Some("kHz") | Some("Hz") => 1.0,
+                Some(unit) => return error(format!("Unknown unit {}.", unit)),
+            };
+            Ok((name.to_string(), value * scale))
+        })
+        .collect()
+}
+
+// The probability of the local depolarizing channels on the qubits of the average gate
+// infidelity of a gate on the qubits.
+fn depolarizing_probability(gate_error: f64, qubit_count: usize) -> f64 {
+    let dimension = (1 << qubit_count) as f64;
+    let process_fidelity = (1.0 - gate_error * (dimension + 1.0) / dimension).max(0.0);
+    (4.0 / 3.0 * (1.0 - process_fidelity.powf(1.0 / qubit_count as f64))).min(1.0)
+}
+
+impl NoiseModel {
+    // Parse the noise model of the calibration data of a device, reporting the errors of the JSON
+    // syntax with their lines.
+    pub fn from_calibration_json(source: &str) -> Result<NoiseModel, ParseError> {
+        let value: Value = serde_json::from_str(source).map_err(|e| ParseError {
+            line: e.line(),
+            message: e.to_string(),
+        })?;
+        let mut noise_model = NoiseModel::new();
+
+        let qubits = match value.get("qubits") {
+            Some(Value::Array(qubits)) => qubits.as_slice(),
+            Some(_) => return error("The qubits have to be a list.".to_string()),
+            None => &[],
+        };
+        for (qubit_number, qubit) in qubits.iter().enumerate() {
+            let parameters: BTreeMap<String, f64> = parameters(qubit)?.into_iter().collect();
+            if let (Some(&t1), Some(&t2)) = (parameters.get("T1"), parameters.get("T2")) {
+                if t1 <= 0.0 || t2 <= 0.0 {
+                    return error(format!(
+                        "The times T1 and T2 of the qubit {} have to be positive.",
+                        qubit_number
+                    ));
+                }
+                // The measured T2 can exceed its limit 2 T1 by the errors of the measurements.
+                noise_model.set_relaxation_times(qubit_number, t1, t2.min(2.0 * t1));
+            }
+            let probability = |name: &str| -> Result<Option<f64>, ParseError> {
+                match parameters.get(name) {
+                    Some(&p) if !(0.0..=1.0).contains(&p) => error(format!(
+                        "The {} of the qubit {} has to be between 0 and 1.",
+                        name, qubit_number
+                    )),
+                    p => Ok(p.copied()),
+                }
+            };
+            let readout_error = probability("readout_error")?;
+            let prob_1_given_0 = probability("prob_meas1_prep0")?.or(readout_error);
+            let prob_0_given_1 = probability("prob_meas0_prep1")?.or(readout_error);
+            if prob_1_given_0.is_some() || prob_0_given_1.is_some() {
+                noise_model.set_readout_error(
+                    qubit_number,
+                    ReadoutError::new(prob_1_given_0.unwrap_or(0.0), prob_0_given_1.unwrap_or(0.0)),
+                );
+            }
+        }
+
+        let gates = match value.get("gates") {
+            Some(Value::Array(gates)) => gates.as_slice(),
+            Some(_) => return error("The gates have to be a list.".to_string()),
+            None => &[],
+        };
+        // The sums and the numbers of the lengths of the gates of each name.
+        let mut lengths: BTreeMap<&str, (f64, usize)> = BTreeMap::new();
+        for gate in gates.iter() {
+            let name = match gate.get("gate").and_then(|name| name.as_str()) {
+                Some(name) => name,
+                None => return error("A gate has no name.".to_string()),
+            };
+            let names = match GATE_NAMES.iter().find(|&&(n, _)| n == name) {
+                Some(&(_, names)) => names,
+                None => continue,
+            };
+            let qubits = match gate.get("qubits").and_then(|qubits| qubits.as_array()) {
+                Some(qubits) => qubits,
+                None => return error(format!("The gate {} has no qubits.", name)),
+            };
+            let qubit_numbers: Vec<usize> = match qubits
+                .iter()
+                .map(|q| q.as_u64().map(|q| q as usize))
+                .collect()
+            {
+                Some(qubit_numbers) => qubit_numbers,
+                None => return error("The qubits have to be nonnegative integers.".to_string()),
+            };
+            let parameters = match gate.get("parameters") {
+                Some(value) => parameters(value)?,
+                None => Vec::new(),
+            };
+            for (parameter_name, value) in parameters {
+                match parameter_name.as_str() {
+                    "gate_error" if value > 0.0 => {
+                        if value > 1.0 {
+                            return error(format!(
+                                "The error of the gate {} has to be between 0 and 1.",
+                                name
+                            ));
+                        }
+                        let probability = depolarizing_probability(value, qubit_numbers.len());
+                        noise_model.add_gate_qubits_error(
+                            KrausChannel::depolarizing(probability),
+                            names,
+                            &qubit_numbers,
+                        );
+                    }
+                    "gate_length" => {
+                        for &gate_name in names.iter() {
+                            let (sum, count) = lengths.entry(gate_name).or_insert((0.0, 0));
+                            *sum += value;
+                            *count += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for (name, (sum, count)) in lengths {
+            noise_model.set_gate_duration(name, sum / count as f64);
+        }
+
+        Ok(noise_model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Gate;
+
+    #[test]
+    fn calibration_is_imported() {
+        let source = r#"{
+            "backend_name": "example",
+            "qubits": [
+                [{"name": "T1", "unit": "us", "value": 100.0},
+                 {"name": "T2", "unit": "us", "value": 250.0},
+                 {"name": "prob_meas0_prep1", "value": 0.03},
+                 {"name": "prob_meas1_prep0", "value": 0.01}],
+                [{"name": "T1", "unit": "us", "value": 80.0},
+                 {"name": "T2", "unit": "us", "value": 60.0},
+                 {"name": "readout_error", "value": 0.02}]
+            ],
+            "gates": [
+                {"gate": "sx", "qubits": [0], "parameters": [
+                    {"name": "gate_error", "value": 0.001},
+                    {"name": "gate_length", "unit": "ns", "value": 30.0}]},
+                {"gate": "sx", "qubits": [1], "parameters": [
+                    {"name": "gate_length", "unit": "ns", "value": 40.0}]},
+                {"gate": "cx", "qubits": [0, 1], "parameters": [
+                    {"name": "gate_error", "value": 0.01},
+                    {"name": "gate_length", "unit": "ns", "value": 300.0}]},
+                {"gate": "reset", "qubits": [0], "parameters": []}
+            ]
+        }"#;
+        let noise_model = NoiseModel::from_calibration_json(source).unwrap();
+        let readout_errors: Vec<_> = noise_model.readout_errors().collect();
+        assert_eq!(
+            readout_errors,
+            vec![
+                (0, ReadoutError::new(0.01, 0.03)),
+                (1, ReadoutError::new(0.02, 0.02))
+            ]
+        );
+        assert_eq!(noise_model.gate_durations["hadamard"], 35.0);
+        assert_eq!(noise_model.relaxation_times[&0], (100e3, 200e3));
+
+        // The depolarizing channel on the qubit 0 and the relaxation of the qubits.
+        let channels = noise_model.channels_after(&Gate::Hadamard(0));
+        assert_eq!(channels.len(), 2);
+        let channels = noise_model.channels_after(&Gate::Cnot(1, 0));
+        assert_eq!(channels.len(), 4);
+        assert!(noise_model.channels_after(&Gate::Cnot(0, 2)).len() == 1);
+        assert!((depolarizing_probability(0.001, 1) - 0.002).abs() < 1e-12);
+
+        let error = NoiseModel::from_calibration_json(r#"{"qubits": [[{"name": "T1"}]]}"#);
+        assert!(error.unwrap_err().message.contains("no number value"));
+    }
+}
@@ -0,0 +1,84 @@
+/*
+Circuit optimization passes applied before the simulation.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::circuit::{Circuit, Gate};
+
+// Remove all the gates outside the causal light cone of the measured qubits.
+// Going backwards from the measurements, a gate is kept when it acts on a qubit which can
+// still influence a measured qubit, and then all of its qubits are included in the light cone.
+// The removed gates act only on the other qubits, so the measurement probabilities of
+// the measured qubits do not change. A kept gate with a classical condition also keeps
+// the measurements into its classical bits. The qubit numbers of the circuit are preserved.
+pub fn reduce_to_light_cone(circuit: &Circuit, measured_qubit_numbers: &[usize]) -> Circuit {
+    let mut in_light_cone = vec![false; circuit.qubit_count()];
+    for &qubit_number in measured_qubit_numbers.iter() {
+        assert!(
+            qubit_number < circuit.qubit_count(),
+            "The qubit number has to be less than the number of qubits {}.",
+            circuit.qubit_count()
+        );
+        in_light_cone[qubit_number] = true;
+    }
+    // The classical bits read by the kept conditions before being written by a measurement.
+    let mut is_clbit_read = vec![false; circuit.clbit_count()];
+
+    let mut kept_gates = Vec::new();
+    for (gate, condition) in circuit.gates().iter().zip(circuit.conditions()).rev() {
+        let qubits = gate.qubits();
+        let is_read_measurement = matches!(*gate, Gate::Measure(_, c) if is_clbit_read[c]);
+        if is_read_measurement || qubits.iter().any(|&q| in_light_cone[q]) {
+            for &qubit_number in qubits.iter() {
+                in_light_cone[qubit_number] = true;
+            }
+            if let Gate::Measure(_, clbit_number) = *gate {
+                is_clbit_read[clbit_number] = false;
+            }
+            if let Some(condition) = condition {
+                for clbit_number in condition.clbits() {
+                    is_clbit_read[clbit_number] = true;
+                }
+            }
+            kept_gates.push((*gate, *condition));
+        }
+    }
+
+    let mut reduced = Circuit::with_clbits(circuit.qubit_count(), circuit.clbit_count());
+    for (gate, condition) in kept_gates.into_iter().rev() {
+        reduced.push_with_condition(gate, condition);
+    }
+    reduced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn light_cone_keeps_measured_outcomes() {
+        let mut circuit = Circuit::new(5);
+        circuit.pauli_x(0);
+        circuit.pauli_x(3);
+        circuit.cnot(3, 4);
+        circuit.cnot(0, 1);
+        circuit.hadamard(2);
+        circuit.cnot(1, 2);
+        circuit.toffoli(3, 4, 2);
+        circuit.cnot(1, 0);
+
+        let reduced = reduce_to_light_cone(&circuit, &[0]);
+        assert_eq!(reduced.gates().len(), 5);
+        assert_eq!(reduce_to_light_cone(&circuit, &[2]).gates().len(), 7);
+
+        let mut simulation = QuantumSimulation::new(5, 0u64);
+        circuit.run(&mut simulation);
+        let expected = simulation.measure(vec![0, 1]);
+        simulation.reset();
+        reduced.run(&mut simulation);
+        assert_eq!(simulation.measure(vec![0, 1]), expected);
+    }
+}
@@ -0,0 +1,161 @@
+/*
+Circuits with rotation angles given by the parameters of variational algorithms.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::circuit::{Circuit, Gate};
+
+// A gate with the angle taken from the parameter with the given index.
+#[derive(Clone, Copy, Debug)]
+pub enum ParameterizedGate {
+    Fixed(Gate),
+    Rx(usize, usize),
+    Ry(usize, usize),
+    Rz(usize, usize),
+    Cphase(usize, usize, usize),
+}
+
+impl ParameterizedGate {
+    // The index of the parameter which the gate depends on.
+    pub fn parameter_index(&self) -> Option<usize> {
+        match *self {
+            ParameterizedGate::Fixed(_) => None,
+            ParameterizedGate::Rx(p, _)
+            | ParameterizedGate::Ry(p, _)
+            | ParameterizedGate::Rz(p, _)
+            | ParameterizedGate::Cphase(p, _, _) => Some(p),
+        }
+    }
+
+    pub fn qubits(&self) -> Vec<usize> {
+        match *self {
+            ParameterizedGate::Fixed(gate) => gate.qubits(),
+            ParameterizedGate::Rx(_, q)
+            | ParameterizedGate::Ry(_, q)
+            | ParameterizedGate::Rz(_, q) => {
+                vec![q]
+            }
+            ParameterizedGate::Cphase(_, q0, q1) => vec![q0, q1],
+        }
+    }
+
+    // The gate with the angle of the parameter added to the shift.
+    fn bind(&self, parameters: &[f64], shift: f64) -> Gate {
+        let angle = |p: usize| parameters[p] + shift;
+        match *self {
+            ParameterizedGate::Fixed(gate) => gate,
+            ParameterizedGate::Rx(p, q) => Gate::Rx(angle(p), q),
+            ParameterizedGate::Ry(p, q) => Gate::Ry(angle(p), q),
+            ParameterizedGate::Rz(p, q) => Gate::Rz(angle(p), q),
+            ParameterizedGate::Cphase(p, q0, q1) => Gate::Cphase(angle(p), q0, q1),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParameterizedCircuit {
+    qubit_count: usize,
+    parameter_count: usize,
+    gates: Vec<ParameterizedGate>,
+}
+
+impl ParameterizedCircuit {
+    pub fn new(qubit_count: usize) -> ParameterizedCircuit {
+        ParameterizedCircuit {
+            qubit_count,
+            parameter_count: 0,
+            gates: Vec::new(),
+        }
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // One more than the highest parameter index used by the gates.
+    pub fn parameter_count(&self) -> usize {
+        self.parameter_count
+    }
+
+    pub fn gates(&self) -> &[ParameterizedGate] {
+        &self.gates
+    }
+
+    pub fn push(&mut self, gate: ParameterizedGate) {
+        assert!(
+            !matches!(gate, ParameterizedGate::Fixed(Gate::Measure(_, _))),
+            "A parameterized circuit cannot contain measurements."
+        );
+        for qubit_number in gate.qubits() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+        }
+        if let Some(p) = gate.parameter_index() {
+            self.parameter_count = self.parameter_count.max(p + 1);
+        }
+        self.gates.push(gate);
+    }
+
+    // Append a gate which does not depend on the parameters.
+    pub fn fixed(&mut self, gate: Gate) {
+        self.push(ParameterizedGate::Fixed(gate));
+    }
+
+    pub fn rx(&mut self, parameter_index: usize, qubit_number: usize) {
+        self.push(ParameterizedGate::Rx(parameter_index, qubit_number));
+    }
+
+    pub fn ry(&mut self, parameter_index: usize, qubit_number: usize) {
+        self.push(ParameterizedGate::Ry(parameter_index, qubit_number));
+    }
+
+    pub fn rz(&mut self, parameter_index: usize, qubit_number: usize) {
+        self.push(ParameterizedGate::Rz(parameter_index, qubit_number));
+    }
+
+    pub fn cphase(
+        &mut self,
+        parameter_index: usize,
+        control_qubit_number: usize,
+        target_qubit_number: usize,
+    ) {
+        self.push(ParameterizedGate::Cphase(
+            parameter_index,
+            control_qubit_number,
+            target_qubit_number,
+        ));
+    }
+
+    // The circuit with the angles of the gates set to the parameters.
+    pub fn bind(&self, parameters: &[f64]) -> Circuit {
+        self.bind_shifted(parameters, None, 0.0)
+    }
+
+    // The circuit with the angle of only the gate with the given index shifted.
+    pub(crate) fn bind_shifted(
+        &self,
+        parameters: &[f64],
+        shifted_gate_index: Option<usize>,
+        shift: f64,
+    ) -> Circuit {
+        assert!(
+            parameters.len() == self.parameter_count,
+            "The number of the parameters has to be {}.",
+            self.parameter_count
+        );
+        let mut circuit = Circuit::new(self.qubit_count);
+        for (index, gate) in self.gates.iter().enumerate() {
+            let gate_shift = if shifted_gate_index == Some(index) {
+                shift
+            } else {
+                0.0
+            };
+            circuit.push(gate.bind(parameters, gate_shift));
+        }
+        circuit
+    }
+}
@@ -16,6 +16,7 @@ use num_complex::Complex;
 // ∣01⟩ = (0,1,0,0)
 // ∣10⟩ = (0,0,1,0)
 // ∣11⟩ = (0,0,0,1)
+#[allow(clippy::type_complexity)]
 pub(crate) fn create_u_f(
     f: fn(bool) -> bool,
 ) -> impl Fn(
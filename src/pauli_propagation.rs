@@ -0,0 +1,356 @@
+/*
+Pauli propagation of the observables backwards through a circuit, in the Heisenberg picture.
+
+The observable O is a sum of Pauli strings with real coefficients, and its expectation value
+⟨0|U† O U|0⟩ is taken by conjugating O by the gates from the last one, G† O G, and then by
+summing the coefficients of the strings of I and Z only, the ones with ⟨0|P|0⟩ = 1.
+The Clifford gates map each string to another string with a sign, and the rotations
+R = e^(-iθP/2) map the strings Q anticommuting with P to cos θ Q + sin θ iPQ, doubling them.
+T is the rotation RZ(π/4) up to a global phase, CPHASE the product of the rotations
+of Z, Z, and ZZ, and the Toffoli gate H CCZ H with CCZ the product of the rotations of
+the 7 products of Z on its qubits by ±π/4.
+
+The strings with a coefficient below the threshold, or with more than the maximum weight of
+operators other than I, are dropped after each rotation, which approximates the expectation values
+of the circuits too large for the state vector, the strings outside the light cone of the
+observable staying unchanged by the gates.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::HashMap;
+use std::f64::consts::FRAC_PI_4;
+
+use crate::circuit::{u_f_gates, Circuit, Gate};
+use crate::hamiltonian::{Hamiltonian, Pauli, PauliString};
+
+pub const DEFAULT_COEFFICIENT_THRESHOLD: f64 = 1e-12;
+
+// The Pauli strings with the words of the X bits followed by the words of the Z bits,
+// Y being both, and their coefficients.
+type PauliSum = HashMap<Vec<u64>, f64>;
+
+#[derive(Clone, Debug)]
+pub struct PauliPropagation {
+    qubit_count: usize,
+    word_count: usize,
+    gates: Vec<Gate>,
+    coefficient_threshold: f64,
+    max_weight: usize,
+}
+
+impl PauliPropagation {
+    pub fn new(circuit: &Circuit) -> PauliPropagation {
+        assert!(
+            circuit
+                .conditions()
+                .iter()
+                .all(|condition| condition.is_none()),
+            "The Pauli propagation cannot contain conditional gates."
+        );
+        let qubit_count = circuit.qubit_count();
+        let mut gates = Vec::new();
+        for gate in circuit.gates().iter() {
+            for &q in gate.qubits().iter() {
+                assert!(
+                    q < qubit_count,
+                    "The qubit number has to be less than the number of qubits {}.",
+                    qubit_count
+                );
+            }
+            match *gate {
+                Gate::Measure(_, _) => {
+                    panic!("The Pauli propagation cannot contain measurements.")
+                }
+                Gate::UF(f, q0, q1) => gates.extend(u_f_gates(f, q0, q1)),
+                gate => gates.push(gate),
+            }
+        }
+        PauliPropagation {
+            qubit_count,
+            word_count: qubit_count.div_ceil(64).max(1),
+            gates,
+            coefficient_threshold: DEFAULT_COEFFICIENT_THRESHOLD,
+            max_weight: usize::MAX,
+        }
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // Drop the strings with an absolute coefficient below the threshold, 0 for the exact values.
+    pub fn set_coefficient_threshold(&mut self, coefficient_threshold: f64) {
+        self.coefficient_threshold = coefficient_threshold;
+    }
+
+    // Drop the strings with more operators other than I than the maximum weight.
+    pub fn set_max_weight(&mut self, max_weight: usize) {
+        self.max_weight = max_weight;
+    }
+
+    pub fn pauli_expectation(&self, pauli_string: &PauliString) -> f64 {
+        self.terms_expectation(&[(1.0, pauli_string.paulis())])
+    }
+
+    pub fn expectation(&self, hamiltonian: &Hamiltonian) -> f64 {
+        let terms: Vec<(f64, Vec<(Pauli, usize)>)> = hamiltonian
+            .terms()
+            .iter()
+            .map(|(coefficient, pauli_string)| (*coefficient, pauli_string.paulis()))
+            .collect();
+        self.terms_expectation(&terms)
+    }
+
+    // The expectation value of the sum of the Pauli operators on the qubits times the
+    // coefficients, for the qubit numbers beyond the 64 of a PauliString.
+    pub fn terms_expectation(&self, terms: &[(f64, Vec<(Pauli, usize)>)]) -> f64 {
+        let mut sum = PauliSum::new();
+        for (coefficient, paulis) in terms.iter() {
+            let mut string = vec![0u64; 2 * self.word_count];
+            for &(pauli, qubit_number) in paulis.iter() {
+                assert!(
+                    qubit_number < self.qubit_count,
+                    "The qubit number has to be less than the number of qubits {}.",
+                    self.qubit_count
+                );
+                assert!(
+                    !self.x(&string, qubit_number) && !self.z(&string, qubit_number),
+                    "The qubit {} has more than one Pauli operator.",
+                    qubit_number
+                );
+                let (x, z) = match pauli {
+                    Pauli::X => (true, false),
+                    Pauli::Y => (true, true),
+                    Pauli::Z => (false, true),
+                };
+                self.set(&mut string, qubit_number, x, z);
+            }
+            *sum.entry(string).or_insert(0.0) += coefficient;
+        }
+
+        for gate in self.gates.iter().rev() {
+            sum = self.conjugate(sum, gate);
+        }
+        sum.iter()
+            .filter(|(string, _)| string[..self.word_count].iter().all(|&x| x == 0))
+            .map(|(_, coefficient)| coefficient)
+            .sum()
+    }
+
+    fn x(&self, string: &[u64], q: usize) -> bool {
+        (string[q / 64] >> (q % 64)) & 1 == 1
+    }
+
+    fn z(&self, string: &[u64], q: usize) -> bool {
+        (string[self.word_count + q / 64] >> (q % 64)) & 1 == 1
+    }
+
+    fn set(&self, string: &mut [u64], q: usize, x: bool, z: bool) {
+        let bit = 1u64 << (q % 64);
+        string[q / 64] = (string[q / 64] & !bit) | if x { bit } else { 0 };
+        let z_word = self.word_count + q / 64;
+        string[z_word] = (string[z_word] & !bit) | if z { bit } else { 0 };
+    }
+
+    // G† O G for the gate G.
+    fn conjugate(&self, sum: PauliSum, gate: &Gate) -> PauliSum {
+        match *gate {
+            Gate::PauliX(q) => self.map_clifford(sum, |string| self.z(string, q)),
+            Gate::PauliY(q) => {
+                self.map_clifford(sum, |string| self.x(string, q) != self.z(string, q))
+            }
+            Gate::PauliZ(q) => self.map_clifford(sum, |string| self.x(string, q)),
+            // X and Z are exchanged, and Y becomes -Y.
+            Gate::Hadamard(q) => self.map_clifford(sum, |string| {
+                let (x, z) = (self.x(string, q), self.z(string, q));
+                self.set(string, q, z, x);
+                x && z
+            }),
+            // S† X S = -Y and S† Y S = X.
+            Gate::S(q) => self.map_clifford(sum, |string| {
+                let (x, z) = (self.x(string, q), self.z(string, q));
+                self.set(string, q, x, z != x);
+                x && !z
+            }),
+            Gate::T(q) => self.rotate(sum, &[(Pauli::Z, q)], FRAC_PI_4),
+            Gate::Rx(theta, q) => self.rotate(sum, &[(Pauli::X, q)], theta),
+            Gate::Ry(theta, q) => self.rotate(sum, &[(Pauli::Y, q)], theta),
+            Gate::Rz(theta, q) => self.rotate(sum, &[(Pauli::Z, q)], theta),
+            Gate::Cnot(c, t) => self.map_clifford(sum, |string| {
+                let (x_c, z_c, x_t, z_t) = (
+                    self.x(string, c),
+                    self.z(string, c),
+                    self.x(string, t),
+                    self.z(string, t),
+                );
+                self.set(string, t, x_t != x_c, z_t);
+                self.set(string, c, x_c, z_c != z_t);
+                x_c && z_t && (x_t == z_c)
+            }),
+            Gate::Cz(c, t) => {
+                let sum = self.conjugate(sum, &Gate::Hadamard(t));
+                let sum = self.conjugate(sum, &Gate::Cnot(c, t));
+                self.conjugate(sum, &Gate::Hadamard(t))
+            }
+            // CPHASE(θ) = e^(iθ/4) RZ_0(θ/2) RZ_1(θ/2) RZZ(-θ/2).
+            Gate::Cphase(theta, c, t) => {
+                let sum = self.rotate(sum, &[(Pauli::Z, c)], theta / 2.0);
+                let sum = self.rotate(sum, &[(Pauli::Z, t)], theta / 2.0);
+                self.rotate(sum, &[(Pauli::Z, c), (Pauli::Z, t)], -theta / 2.0)
+            }
+            Gate::Swap(q0, q1) => self.map_clifford(sum, |string| {
+                let (x0, z0, x1, z1) = (
+                    self.x(string, q0),
+                    self.z(string, q0),
+                    self.x(string, q1),
+                    self.z(string, q1),
+                );
+                self.set(string, q0, x1, z1);
+                self.set(string, q1, x0, z0);
+                false
+            }),
+            // CCZ = e^(iπ|111⟩⟨111|) is the product of the rotations of the products of Z
+            // on the nonempty subsets S by -(-1)^|S| π/4.
+            Gate::Toffoli(c0, c1, t) => {
+                let mut sum = self.conjugate(sum, &Gate::Hadamard(t));
+                let qubits = [c0, c1, t];
+                for subset in 1..8usize {
+                    let paulis: Vec<(Pauli, usize)> = (0..3)
+                        .filter(|k| (subset >> k) & 1 == 1)
+                        .map(|k| (Pauli::Z, qubits[k]))
+                        .collect();
+                    let sign = if paulis.len() % 2 == 1 { 1.0 } else { -1.0 };
+                    sum = self.rotate(sum, &paulis, sign * FRAC_PI_4);
+                }
+                self.conjugate(sum, &Gate::Hadamard(t))
+            }
+            Gate::UF(_, _, _) | Gate::Measure(_, _) => unreachable!(),
+        }
+    }
+
+    // Map each string by the Clifford gate, which updates the string and returns whether
+    // its sign flips.
+    fn map_clifford<F: Fn(&mut Vec<u64>) -> bool>(&self, sum: PauliSum, map: F) -> PauliSum {
+        sum.into_iter()
+            .map(|(mut string, coefficient)| {
+                let is_flipped = map(&mut string);
+                (
+                    string,
+                    if is_flipped {
+                        -coefficient
+                    } else {
+                        coefficient
+                    },
+                )
+            })
+            .collect()
+    }
+
+    // R† Q R for the rotation R = e^(-iθP/2) of the Pauli string P, with the truncation.
+    fn rotate(&self, sum: PauliSum, paulis: &[(Pauli, usize)], theta: f64) -> PauliSum {
+        let (cos, sin) = (theta.cos(), theta.sin());
+        let mut rotated_sum = PauliSum::with_capacity(sum.len());
+        for (string, coefficient) in sum.into_iter() {
+            // The strings anticommute when an odd number of their operators on the same qubits
+            // anticommute, and then iPQ has the exponent of i of the products of the operators
+            // plus 1, which is even.
+            let mut product = string.clone();
+            let mut exponent = 1;
+            let mut anticommutation_count = 0;
+            for &(pauli, q) in paulis.iter() {
+                let (x_p, z_p) = match pauli {
+                    Pauli::X => (true, false),
+                    Pauli::Y => (true, true),
+                    Pauli::Z => (false, true),
+                };
+                let (x_q, z_q) = (self.x(&string, q), self.z(&string, q));
+                if (x_p && z_q) != (z_p && x_q) {
+                    anticommutation_count += 1;
+                }
+                exponent += product_exponent(x_p, z_p, x_q, z_q);
+                self.set(&mut product, q, x_p != x_q, z_p != z_q);
+            }
+            if anticommutation_count % 2 == 0 {
+                self.add_term(&mut rotated_sum, string, coefficient);
+                continue;
+            }
+            let sign = if exponent.rem_euclid(4) == 0 {
+                1.0
+            } else {
+                -1.0
+            };
+            self.add_term(&mut rotated_sum, string, cos * coefficient);
+            self.add_term(&mut rotated_sum, product, sign * sin * coefficient);
+        }
+        rotated_sum.retain(|_, coefficient| coefficient.abs() >= self.coefficient_threshold);
+        rotated_sum
+    }
+
+    fn add_term(&self, sum: &mut PauliSum, string: Vec<u64>, coefficient: f64) {
+        if self.max_weight != usize::MAX {
+            let (x, z) = string.split_at(self.word_count);
+            let weight: u32 = x
+                .iter()
+                .zip(z.iter())
+                .map(|(x, z)| (x | z).count_ones())
+                .sum();
+            if weight as usize > self.max_weight {
+                return;
+            }
+        }
+        *sum.entry(string).or_insert(0.0) += coefficient;
+    }
+}
+
+// The exponent of i of the product of the Pauli operators σ_1 σ_2 = i^g σ_3, by their bits.
+fn product_exponent(x1: bool, z1: bool, x2: bool, z2: bool) -> i32 {
+    let (x2, z2) = (x2 as i32, z2 as i32);
+    match (x1, z1) {
+        (false, false) => 0,
+        (true, true) => z2 - x2,
+        (true, false) => z2 * (2 * x2 - 1),
+        (false, true) => x2 * (1 - 2 * z2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn expectations_match_state_vector() {
+        let mut circuit = Circuit::new(4);
+        circuit.hadamard(0);
+        circuit.t(0);
+        circuit.cnot(0, 1);
+        circuit.ry(0.6, 2);
+        circuit.s(2);
+        circuit.cz(2, 3);
+        circuit.rx(-0.8, 3);
+        circuit.cphase(0.9, 1, 3);
+        circuit.swap(0, 2);
+        circuit.pauli_y(1);
+        circuit.toffoli(0, 3, 1);
+        circuit.apply_u_f(|x| !x, 2, 1);
+        circuit.rz(1.3, 1);
+        circuit.hadamard(2);
+        let mut propagation = PauliPropagation::new(&circuit);
+        propagation.set_coefficient_threshold(0.0);
+        let mut simulation = QuantumSimulation::new(4, 0);
+        circuit.run(&mut simulation);
+
+        let mut hamiltonian = Hamiltonian::new();
+        hamiltonian.add_term(0.5, &[(Pauli::Z, 0), (Pauli::X, 3)]);
+        hamiltonian.add_term(-1.5, &[(Pauli::Y, 1)]);
+        hamiltonian.add_term(0.7, &[(Pauli::X, 0), (Pauli::Y, 2), (Pauli::Z, 1)]);
+        hamiltonian.add_term(2.0, &[]);
+        let expected_expectation = hamiltonian.expectation(&simulation.amplitudes());
+        assert!((propagation.expectation(&hamiltonian) - expected_expectation).abs() < 1e-12);
+
+        // Keeping the strings of weight 1 only already changes the value.
+        propagation.set_max_weight(1);
+        assert!((propagation.expectation(&hamiltonian) - expected_expectation).abs() > 1e-6);
+    }
+}
@@ -0,0 +1,95 @@
+/*
+Quantum communication protocols.
+
+The state of a qubit is given by the polar angle θ and the azimuth φ of its point on the Bloch
+sphere, cos(θ/2)|0⟩ + e^(iφ) sin(θ/2)|1⟩, and prepared from |0⟩ by the rotations RY(θ) and
+RZ(φ) up to a global phase. The teleportation moves the state of the source qubit to the
+destination qubit by a Bell pair of the auxiliary and the destination qubits, the Bell
+measurement of the source and the auxiliary qubits, and the Z and X gates on the destination
+qubit conditional on the two measured bits. Only the two classical bits travel from the sender
+to the receiver, and the source qubit is left in a basis state, as the state cannot be copied.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+
+use crate::simulation::Simulation;
+
+// The amplitudes of the state of the qubit at the polar angle θ and the azimuth φ of the Bloch
+// sphere.
+pub fn bloch_state(theta: f64, phi: f64) -> [Complex<f64>; 2] {
+    [
+        Complex::new((theta / 2.0).cos(), 0.0),
+        Complex::from_polar((theta / 2.0).sin(), phi),
+    ]
+}
+
+// Prepare the qubit in |0⟩ in the state of bloch_state, up to a global phase.
+pub fn prepare_bloch_state(
+    simulation: &mut dyn Simulation,
+    theta: f64,
+    phi: f64,
+    qubit_number: usize,
+) {
+    simulation.ry(theta, qubit_number);
+    simulation.rz(phi, qubit_number);
+}
+
+// Teleport the state of the source qubit into the destination qubit in |0⟩ using the auxiliary
+// qubit in |0⟩, and return the two measured bits of the source and the auxiliary qubits.
+pub fn teleport(
+    simulation: &mut dyn Simulation,
+    source_qubit: usize,
+    auxiliary_qubit: usize,
+    destination_qubit: usize,
+) -> [bool; 2] {
+    assert!(
+        source_qubit != auxiliary_qubit
+            && source_qubit != destination_qubit
+            && auxiliary_qubit != destination_qubit,
+        "The qubits of the teleportation have to be distinct."
+    );
+    // The Bell pair shared by the sender and the receiver.
+    simulation.hadamard(auxiliary_qubit);
+    simulation.cnot(auxiliary_qubit, destination_qubit);
+
+    // The Bell measurement of the sender.
+    simulation.cnot(source_qubit, auxiliary_qubit);
+    simulation.hadamard(source_qubit);
+    let measured_values = simulation.measure(vec![source_qubit, auxiliary_qubit]);
+
+    // The corrections of the receiver.
+    if measured_values[0] {
+        simulation.pauli_z(destination_qubit);
+    }
+    if measured_values[1] {
+        simulation.pauli_x(destination_qubit);
+    }
+    [measured_values[0], measured_values[1]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn teleportation_preserves_states() {
+        let mut simulation = QuantumSimulation::new(3, 0u64);
+        let mut outcome_counts = [0; 4];
+        for step in 0..40 {
+            let theta = 0.37 * step as f64;
+            let phi = 1.91 * step as f64;
+            simulation.reset();
+            prepare_bloch_state(&mut simulation, theta, phi, 0);
+            let state = bloch_state(theta, phi);
+            assert!((simulation.qubit_fidelity(0, &state) - 1.0).abs() < 1e-12);
+            let [value0, value1] = teleport(&mut simulation, 0, 1, 2);
+            outcome_counts[value0 as usize + 2 * value1 as usize] += 1;
+            assert!((simulation.qubit_fidelity(2, &state) - 1.0).abs() < 1e-12);
+        }
+        // All the four outcomes of the Bell measurement are corrected.
+        assert!(outcome_counts.iter().all(|&count| count > 0));
+    }
+}
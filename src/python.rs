@@ -0,0 +1,337 @@
+/*
+Python bindings of the state vector simulation, the circuits, and the evaluation.
+
+The invalid qubit numbers raise ValueError instead of panicking, so a notebook session
+survives a mistyped gate.
+
+Example build:
+maturin develop
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::circuit::{from_qasm, Circuit, Gate};
+use crate::evaluation;
+use crate::simulation::{self, Simulation};
+use crate::state_vector_simulation::{QuantumSimulation, MAX_QUBIT_COUNT};
+
+fn check_qubits(qubit_count: usize, qubit_numbers: &[usize]) -> PyResult<()> {
+    simulation::check_qubits(qubit_numbers, qubit_count)
+        .map_err(|error| PyValueError::new_err(error.to_string()))
+}
+
+#[pyclass(name = "QuantumSimulation")]
+pub struct PySimulation {
+    simulation: QuantumSimulation,
+    qubit_count: usize,
+}
+
+impl PySimulation {
+    fn apply(&mut self, gate: Gate) -> PyResult<()> {
+        check_qubits(self.qubit_count, &gate.qubits())?;
+        gate.apply(&mut self.simulation);
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PySimulation {
+    #[new]
+    #[pyo3(signature = (qubit_count, seed = 0))]
+    fn new(qubit_count: usize, seed: u64) -> PyResult<PySimulation> {
+        if qubit_count > MAX_QUBIT_COUNT {
+            return Err(PyValueError::new_err(format!(
+                "The number of qubits in the simulation cannot exceed {}.",
+                MAX_QUBIT_COUNT
+            )));
+        }
+        Ok(PySimulation {
+            simulation: QuantumSimulation::new(qubit_count, seed),
+            qubit_count,
+        })
+    }
+
+    #[getter]
+    fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    fn reset(&mut self) {
+        self.simulation.reset();
+    }
+
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> PyResult<Vec<bool>> {
+        check_qubits(self.qubit_count, &qubit_numbers)?;
+        Ok(self.simulation.measure(qubit_numbers))
+    }
+
+    fn measure_all(&mut self) -> Vec<bool> {
+        self.simulation.measure_all()
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::PauliX(qubit_number))
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::PauliY(qubit_number))
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::PauliZ(qubit_number))
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::Hadamard(qubit_number))
+    }
+
+    fn s(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::S(qubit_number))
+    }
+
+    fn t(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::T(qubit_number))
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::Rx(theta, qubit_number))
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::Ry(theta, qubit_number))
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::Rz(theta, qubit_number))
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::Cnot(control_qubit_number, target_qubit_number))
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) -> PyResult<()> {
+        self.apply(Gate::Cz(control_qubit_number, target_qubit_number))
+    }
+
+    fn cphase(
+        &mut self,
+        theta: f64,
+        control_qubit_number: usize,
+        target_qubit_number: usize,
+    ) -> PyResult<()> {
+        self.apply(Gate::Cphase(
+            theta,
+            control_qubit_number,
+            target_qubit_number,
+        ))
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) -> PyResult<()> {
+        self.apply(Gate::Swap(qubit_number0, qubit_number1))
+    }
+
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) -> PyResult<()> {
+        self.apply(Gate::Toffoli(
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        ))
+    }
+
+    // The amplitudes of the basis states as a list of complex numbers.
+    fn amplitudes(&self) -> Vec<Complex<f64>> {
+        self.simulation.amplitudes().to_vec()
+    }
+
+    fn probabilities(&self) -> Vec<f64> {
+        self.simulation
+            .amplitudes()
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .collect()
+    }
+}
+
+#[pyclass(name = "Circuit")]
+pub struct PyCircuit {
+    circuit: Circuit,
+}
+
+impl PyCircuit {
+    fn push(&mut self, gate: Gate) -> PyResult<()> {
+        check_qubits(self.circuit.qubit_count(), &gate.qubits())?;
+        self.circuit.push(gate);
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyCircuit {
+    #[new]
+    #[pyo3(signature = (qubit_count, clbit_count = 0))]
+    fn new(qubit_count: usize, clbit_count: usize) -> PyCircuit {
+        PyCircuit {
+            circuit: Circuit::with_clbits(qubit_count, clbit_count),
+        }
+    }
+
+    #[staticmethod]
+    fn from_qasm(source: &str) -> PyResult<PyCircuit> {
+        match from_qasm(source) {
+            Ok(circuit) => Ok(PyCircuit { circuit }),
+            Err(error) => Err(PyValueError::new_err(error.to_string())),
+        }
+    }
+
+    #[staticmethod]
+    fn from_quil(source: &str) -> PyResult<PyCircuit> {
+        match Circuit::from_quil(source) {
+            Ok(circuit) => Ok(PyCircuit { circuit }),
+            Err(error) => Err(PyValueError::new_err(error.to_string())),
+        }
+    }
+
+    fn to_qasm3(&self) -> String {
+        self.circuit.to_qasm3()
+    }
+
+    fn to_quil(&self) -> String {
+        self.circuit.to_quil()
+    }
+
+    #[getter]
+    fn qubit_count(&self) -> usize {
+        self.circuit.qubit_count()
+    }
+
+    #[getter]
+    fn clbit_count(&self) -> usize {
+        self.circuit.clbit_count()
+    }
+
+    fn __len__(&self) -> usize {
+        self.circuit.gates().len()
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::PauliX(qubit_number))
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::PauliY(qubit_number))
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::PauliZ(qubit_number))
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::Hadamard(qubit_number))
+    }
+
+    fn s(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::S(qubit_number))
+    }
+
+    fn t(&mut self, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::T(qubit_number))
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::Rx(theta, qubit_number))
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::Ry(theta, qubit_number))
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::Rz(theta, qubit_number))
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::Cnot(control_qubit_number, target_qubit_number))
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) -> PyResult<()> {
+        self.push(Gate::Cz(control_qubit_number, target_qubit_number))
+    }
+
+    fn cphase(
+        &mut self,
+        theta: f64,
+        control_qubit_number: usize,
+        target_qubit_number: usize,
+    ) -> PyResult<()> {
+        self.push(Gate::Cphase(
+            theta,
+            control_qubit_number,
+            target_qubit_number,
+        ))
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) -> PyResult<()> {
+        self.push(Gate::Swap(qubit_number0, qubit_number1))
+    }
+
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) -> PyResult<()> {
+        self.push(Gate::Toffoli(
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        ))
+    }
+
+    fn measure(&mut self, qubit_number: usize, clbit_number: usize) -> PyResult<()> {
+        if clbit_number >= self.circuit.clbit_count() {
+            return Err(PyValueError::new_err(format!(
+                "The classical bit number has to be less than the number of classical bits {}.",
+                self.circuit.clbit_count()
+            )));
+        }
+        self.push(Gate::Measure(qubit_number, clbit_number))
+    }
+
+    // Apply the gates to the simulation, which has at least the qubits of the circuit,
+    // and return the classical bits.
+    fn run(&self, simulation: &mut PySimulation) -> PyResult<Vec<bool>> {
+        if simulation.qubit_count < self.circuit.qubit_count() {
+            return Err(PyValueError::new_err(
+                "The simulation has fewer qubits than the circuit.",
+            ));
+        }
+        Ok(self.circuit.run(&mut simulation.simulation))
+    }
+}
+
+// Print the evaluation of the measurements, a list of lists of booleans.
+#[pyfunction]
+fn evaluate(measurements: Vec<Vec<bool>>) -> PyResult<()> {
+    if measurements.is_empty() {
+        return Err(PyValueError::new_err("There are no measurements."));
+    }
+    evaluation::evaluate(measurements).print();
+    Ok(())
+}
+
+#[pymodule]
+fn quantum_simulation(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySimulation>()?;
+    module.add_class::<PyCircuit>()?;
+    module.add_function(wrap_pyfunction!(evaluate, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,303 @@
+/*
+Quantum state vector simulation of qudits, each with its own number of levels.
+
+The qudit k has d_k levels |0⟩ to |d_k - 1⟩, and the basis state |j_0 ... j_{n-1}⟩ is the index
+j_0 + d_0 (j_1 + d_1 (j_2 + ...)) of the state vector, the qudit 0 being the lowest digit as the
+qubit 0 is the lowest bit of the qubit simulations. The gates are the shift X|j⟩ = |j + 1 mod d⟩
+and the clock Z|j⟩ = ω^j |j⟩, with ω = e^{2πi/d}, generalizing the Paulis, the Fourier gate
+generalizing the Hadamard, any unitary of one qudit, and the controlled shift
+|c, t⟩ → |c, t + c mod d_t⟩ generalizing the CNOT.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::state_vector_simulation;
+
+// The most amplitudes, as many as in the largest qubit state vector.
+pub const MAX_STATE_COUNT: usize = 1 << state_vector_simulation::MAX_QUBIT_COUNT;
+
+#[derive(Debug)]
+pub struct QuditSimulation {
+    dimensions: Vec<usize>,
+    // The distance in the state vector between the consecutive levels of each qudit.
+    strides: Vec<usize>,
+    amplitudes: Vec<Complex<f64>>,
+    rng: ChaCha12Rng,
+}
+
+impl QuditSimulation {
+    pub fn new(dimensions: &[usize], rnd_seed: u64) -> QuditSimulation {
+        let mut strides = Vec::with_capacity(dimensions.len());
+        let mut state_count: usize = 1;
+        for &dimension in dimensions.iter() {
+            assert!(
+                dimension >= 2,
+                "The dimension of a qudit has to be at least 2."
+            );
+            strides.push(state_count);
+            state_count = state_count
+                .checked_mul(dimension)
+                .filter(|&count| count <= MAX_STATE_COUNT)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "The number of states in the simulation cannot exceed {}.",
+                        MAX_STATE_COUNT
+                    )
+                });
+        }
+
+        let mut simulation = QuditSimulation {
+            dimensions: dimensions.to_vec(),
+            strides,
+            amplitudes: vec![Complex::new(0.0, 0.0); state_count],
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    pub fn qudit_count(&self) -> usize {
+        self.dimensions.len()
+    }
+
+    pub fn dimensions(&self) -> &[usize] {
+        &self.dimensions
+    }
+
+    pub fn amplitudes(&self) -> &[Complex<f64>] {
+        &self.amplitudes
+    }
+
+    // The index in the state vector of the basis state with the levels of the qudits.
+    pub fn state_index(&self, levels: &[usize]) -> usize {
+        assert!(
+            levels.len() == self.dimensions.len(),
+            "The basis state has to have one level for each of the qudits {}.",
+            self.dimensions.len()
+        );
+        levels
+            .iter()
+            .zip(self.dimensions.iter().zip(self.strides.iter()))
+            .map(|(&level, (&dimension, &stride))| {
+                assert!(
+                    level < dimension,
+                    "The level of a qudit has to be less than its dimension {}.",
+                    dimension
+                );
+                level * stride
+            })
+            .sum()
+    }
+
+    // Set the state to the ground state |0...0⟩.
+    pub fn reset(&mut self) {
+        self.amplitudes.fill(Complex::new(0.0, 0.0));
+        self.amplitudes[0] = Complex::new(1.0, 0.0);
+    }
+
+    fn check_qudit_number(&self, qudit_number: usize) {
+        assert!(
+            qudit_number < self.dimensions.len(),
+            "The qudit number has to be less than the number of qudits {}.",
+            self.dimensions.len()
+        );
+    }
+
+    fn level(&self, state_index: usize, qudit_number: usize) -> usize {
+        state_index / self.strides[qudit_number] % self.dimensions[qudit_number]
+    }
+
+    // The state indices with the qudit at the level 0.
+    fn ground_indices(&self, qudit_number: usize) -> impl Iterator<Item = usize> + '_ {
+        let stride = self.strides[qudit_number];
+        let block = stride * self.dimensions[qudit_number];
+        (0..self.amplitudes.len())
+            .step_by(block)
+            .flat_map(move |start| start..start + stride)
+    }
+
+    // Apply the d × d matrix of the dimension d of the qudit, given row by row.
+    pub fn apply_matrix(&mut self, matrix: &[Complex<f64>], qudit_number: usize) {
+        self.check_qudit_number(qudit_number);
+        let (dimension, stride) = (self.dimensions[qudit_number], self.strides[qudit_number]);
+        assert!(
+            matrix.len() == dimension * dimension,
+            "The matrix of a qudit gate has to have the square of the dimension {} elements.",
+            dimension
+        );
+        let ground_indices: Vec<usize> = self.ground_indices(qudit_number).collect();
+        let mut levels = vec![Complex::new(0.0, 0.0); dimension];
+        for start in ground_indices {
+            for (j, level) in levels.iter_mut().enumerate() {
+                *level = self.amplitudes[start + j * stride];
+            }
+            for (i, row) in matrix.chunks_exact(dimension).enumerate() {
+                self.amplitudes[start + i * stride] =
+                    row.iter().zip(levels.iter()).map(|(m, a)| m * a).sum();
+            }
+        }
+    }
+
+    // The generalized X, |j⟩ → |j + 1 mod d⟩.
+    pub fn shift(&mut self, qudit_number: usize) {
+        self.check_qudit_number(qudit_number);
+        let (dimension, stride) = (self.dimensions[qudit_number], self.strides[qudit_number]);
+        let ground_indices: Vec<usize> = self.ground_indices(qudit_number).collect();
+        for start in ground_indices {
+            let block = &mut self.amplitudes[start..=start + (dimension - 1) * stride];
+            // Moving the last level to the front moves every level up.
+            for j in (1..dimension).rev() {
+                block.swap(j * stride, (j - 1) * stride);
+            }
+        }
+    }
+
+    // The generalized Z, |j⟩ → ω^j |j⟩ with ω = e^{2πi/d}.
+    pub fn clock(&mut self, qudit_number: usize) {
+        self.check_qudit_number(qudit_number);
+        let dimension = self.dimensions[qudit_number];
+        let phases: Vec<Complex<f64>> = (0..dimension)
+            .map(|j| Complex::from_polar(1.0, std::f64::consts::TAU * j as f64 / dimension as f64))
+            .collect();
+        for i in 0..self.amplitudes.len() {
+            let level = self.level(i, qudit_number);
+            self.amplitudes[i] *= phases[level];
+        }
+    }
+
+    // The generalized Hadamard, |j⟩ → the sum over k of ω^{jk} |k⟩ / sqrt(d).
+    pub fn fourier(&mut self, qudit_number: usize) {
+        self.check_qudit_number(qudit_number);
+        let dimension = self.dimensions[qudit_number];
+        let scale = 1.0 / (dimension as f64).sqrt();
+        let matrix: Vec<Complex<f64>> = (0..dimension * dimension)
+            .map(|i| {
+                let power = (i / dimension) * (i % dimension) % dimension;
+                Complex::from_polar(
+                    scale,
+                    std::f64::consts::TAU * power as f64 / dimension as f64,
+                )
+            })
+            .collect();
+        self.apply_matrix(&matrix, qudit_number);
+    }
+
+    // The generalized CNOT, |c, t⟩ → |c, t + c mod d_t⟩.
+    pub fn controlled_shift(&mut self, control_qudit_number: usize, target_qudit_number: usize) {
+        self.check_qudit_number(control_qudit_number);
+        self.check_qudit_number(target_qudit_number);
+        assert!(
+            control_qudit_number != target_qudit_number,
+            "The qudit arguments of a gate have to be distinct."
+        );
+        let (dimension, stride) = (
+            self.dimensions[target_qudit_number],
+            self.strides[target_qudit_number],
+        );
+        let mut amplitudes = vec![Complex::new(0.0, 0.0); self.amplitudes.len()];
+        for (i, amplitude) in self.amplitudes.iter().enumerate() {
+            let control_level = self.level(i, control_qudit_number);
+            let target_level = self.level(i, target_qudit_number);
+            let shifted_level = (target_level + control_level) % dimension;
+            amplitudes[i + shifted_level * stride - target_level * stride] = *amplitude;
+        }
+        self.amplitudes = amplitudes;
+    }
+
+    fn choose_state(&mut self) -> usize {
+        let random_number = self.rng.gen::<f64>();
+        let mut accumulated_probability = 0.0;
+        let mut state_index = 0;
+
+        for (i, amplitude) in self.amplitudes.iter().enumerate() {
+            accumulated_probability += amplitude.norm_sqr();
+            if random_number <= accumulated_probability {
+                state_index = i;
+                break;
+            }
+        }
+
+        state_index
+    }
+
+    // Measure all the qudits, collapsing the state to the measured basis state.
+    pub fn measure_all(&mut self) -> Vec<usize> {
+        let measured_state_index = self.choose_state();
+        self.amplitudes.fill(Complex::new(0.0, 0.0));
+        self.amplitudes[measured_state_index] = Complex::new(1.0, 0.0);
+
+        (0..self.dimensions.len())
+            .map(|qudit_number| self.level(measured_state_index, qudit_number))
+            .collect()
+    }
+
+    // Measure the selected qudits, keeping the amplitudes with the measured levels,
+    // rescaled by the probability of the levels.
+    pub fn measure(&mut self, qudit_numbers: &[usize]) -> Vec<usize> {
+        for &qudit_number in qudit_numbers.iter() {
+            self.check_qudit_number(qudit_number);
+        }
+        let measured_state_index = self.choose_state();
+        let measured_levels: Vec<usize> = qudit_numbers
+            .iter()
+            .map(|&qudit_number| self.level(measured_state_index, qudit_number))
+            .collect();
+
+        let mut probability = 0.0;
+        for i in 0..self.amplitudes.len() {
+            let is_measured = qudit_numbers
+                .iter()
+                .zip(measured_levels.iter())
+                .all(|(&qudit_number, &level)| self.level(i, qudit_number) == level);
+            if is_measured {
+                probability += self.amplitudes[i].norm_sqr();
+            } else {
+                self.amplitudes[i] = Complex::new(0.0, 0.0);
+            }
+        }
+        let scale = 1.0 / probability.sqrt();
+        for amplitude in self.amplitudes.iter_mut() {
+            *amplitude *= scale;
+        }
+
+        measured_levels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qutrit_gates() {
+        let mut simulation = QuditSimulation::new(&[3, 2, 3], 0);
+        simulation.shift(0);
+        simulation.shift(0);
+        simulation.controlled_shift(0, 2);
+        // The qutrit 2 is shifted by the level 2 of the qutrit 0.
+        let index = simulation.state_index(&[2, 0, 2]);
+        assert!((simulation.amplitudes()[index] - Complex::new(1.0, 0.0)).norm() < 1e-12);
+
+        // F Z F |0⟩ is |-1 mod 3⟩, F^2 being the map |j⟩ → |-j mod 3⟩.
+        simulation.reset();
+        simulation.fourier(0);
+        let amplitudes = simulation.amplitudes().to_vec();
+        for level in 0..3 {
+            let index = simulation.state_index(&[level, 0, 0]);
+            assert!((amplitudes[index].norm_sqr() - 1.0 / 3.0).abs() < 1e-12);
+        }
+        simulation.clock(0);
+        simulation.fourier(0);
+        let index = simulation.state_index(&[2, 0, 0]);
+        assert!((simulation.amplitudes()[index].norm_sqr() - 1.0).abs() < 1e-12);
+
+        let levels = simulation.measure(&[0]);
+        assert_eq!(levels, vec![2]);
+        assert_eq!(simulation.measure_all(), vec![2, 0, 0]);
+    }
+}
@@ -0,0 +1,252 @@
+/*
+Named registers of qubits.
+
+The allocator hands out the registers of consecutive qubits in the order of the allocation from
+the qubit 0, so that the programs name the inputs, the outputs, and the ancilla qubits instead of
+computing their qubit numbers, and the simulation is created for the number of the allocated
+qubits. The value of a register is the integer with its first qubit as the lowest bit, like the
+index of the amplitudes, and the sizes of the registers give the register grouping of the
+displayed measurements.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::ops::Range;
+
+use crate::simulation::Simulation;
+
+// A named range of consecutive qubits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QubitRegister {
+    name: String,
+    start: usize,
+    size: usize,
+}
+
+impl QubitRegister {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    // The qubit number of the qubit of the index in the register.
+    pub fn qubit(&self, index: usize) -> usize {
+        assert!(
+            index < self.size,
+            "The index has to be less than the size {} of the register {}.",
+            self.size,
+            self.name
+        );
+        self.start + index
+    }
+
+    // The qubit numbers of the register, the lowest bit first.
+    pub fn qubits(&self) -> Range<usize> {
+        self.start..self.start + self.size
+    }
+}
+
+// The allocator of the registers of the qubits of a program.
+#[derive(Clone, Debug, Default)]
+pub struct QubitAllocator {
+    registers: Vec<QubitRegister>,
+}
+
+impl QubitAllocator {
+    pub fn new() -> QubitAllocator {
+        QubitAllocator::default()
+    }
+
+    // Allocate the register of the size after the qubits allocated before.
+    pub fn alloc_register(&mut self, name: &str, size: usize) -> QubitRegister {
+        assert!(size > 0, "The register {} needs at least 1 qubit.", name);
+        assert!(
+            self.register(name).is_none(),
+            "The register {} is already allocated.",
+            name
+        );
+        let register = QubitRegister {
+            name: name.to_string(),
+            start: self.qubit_count(),
+            size,
+        };
+        self.registers.push(register.clone());
+        register
+    }
+
+    // Allocate the register of a single qubit and return its qubit number.
+    pub fn alloc_qubit(&mut self, name: &str) -> usize {
+        self.alloc_register(name, 1).qubit(0)
+    }
+
+    // The number of the allocated qubits, the number of the qubits of the simulation.
+    pub fn qubit_count(&self) -> usize {
+        self.registers.iter().map(|register| register.size).sum()
+    }
+
+    pub fn register(&self, name: &str) -> Option<&QubitRegister> {
+        self.registers.iter().find(|register| register.name == name)
+    }
+
+    pub fn registers(&self) -> &[QubitRegister] {
+        &self.registers
+    }
+
+    // The sizes of the registers in the order of the allocation, for the register grouping of
+    // the formatting options of the evaluation.
+    pub fn register_sizes(&self) -> Vec<usize> {
+        self.registers
+            .iter()
+            .map(|register| register.size)
+            .collect()
+    }
+}
+
+fn check_same_size(register0: &QubitRegister, register1: &QubitRegister) {
+    assert!(
+        register0.len() == register1.len(),
+        "The registers {} and {} have to have the same size.",
+        register0.name(),
+        register1.name()
+    );
+}
+
+// The gates and the measurements of the whole registers, for every simulation. The gates of two
+// registers act on the pairs of their qubits of the same index.
+pub trait RegisterOperations: Simulation {
+    fn hadamard_register(&mut self, register: &QubitRegister) {
+        for qubit_number in register.qubits() {
+            self.hadamard(qubit_number);
+        }
+    }
+
+    fn pauli_x_register(&mut self, register: &QubitRegister) {
+        for qubit_number in register.qubits() {
+            self.pauli_x(qubit_number);
+        }
+    }
+
+    fn cnot_registers(
+        &mut self,
+        control_register: &QubitRegister,
+        target_register: &QubitRegister,
+    ) {
+        check_same_size(control_register, target_register);
+        for (control_qubit, target_qubit) in control_register.qubits().zip(target_register.qubits())
+        {
+            self.cnot(control_qubit, target_qubit);
+        }
+    }
+
+    fn cz_registers(&mut self, control_register: &QubitRegister, target_register: &QubitRegister) {
+        check_same_size(control_register, target_register);
+        for (control_qubit, target_qubit) in control_register.qubits().zip(target_register.qubits())
+        {
+            self.cz(control_qubit, target_qubit);
+        }
+    }
+
+    // The U_f gate of each qubit of the first register with the qubit of the second register.
+    fn apply_u_f_register(
+        &mut self,
+        f: fn(bool) -> bool,
+        register0: &QubitRegister,
+        register1: &QubitRegister,
+    ) {
+        check_same_size(register0, register1);
+        for (qubit_number0, qubit_number1) in register0.qubits().zip(register1.qubits()) {
+            self.apply_u_f(f, qubit_number0, qubit_number1);
+        }
+    }
+
+    // Flip the qubits of the register in |0...0⟩ into the basis state of the value.
+    fn prepare_value(&mut self, register: &QubitRegister, value: u64) {
+        assert!(
+            register.len() >= 64 || value >> register.len() == 0,
+            "The value {} does not fit into the register {}.",
+            value,
+            register.name()
+        );
+        for (bit, qubit_number) in register.qubits().enumerate() {
+            if (value >> bit) & 1 == 1 {
+                self.pauli_x(qubit_number);
+            }
+        }
+    }
+
+    fn measure_register(&mut self, register: &QubitRegister) -> Vec<bool> {
+        self.measure(register.qubits().collect())
+    }
+
+    // Measure the register and return its value.
+    fn measure_value(&mut self, register: &QubitRegister) -> u64 {
+        assert!(
+            register.len() <= 64,
+            "The register {} has to have at most 64 qubits.",
+            register.name()
+        );
+        self.measure_register(register)
+            .iter()
+            .enumerate()
+            .map(|(bit, &value)| (value as u64) << bit)
+            .sum()
+    }
+}
+
+impl<S: Simulation + ?Sized> RegisterOperations for S {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn registers_are_allocated_in_order() {
+        let mut allocator = QubitAllocator::new();
+        let input = allocator.alloc_register("input", 3);
+        let answer = allocator.alloc_qubit("answer");
+        let output = allocator.alloc_register("output", 2);
+        assert_eq!(input.qubits(), 0..3);
+        assert_eq!(answer, 3);
+        assert_eq!(output.qubit(1), 5);
+        assert_eq!(allocator.qubit_count(), 6);
+        assert_eq!(allocator.register_sizes(), vec![3, 1, 2]);
+        assert_eq!(allocator.register("output"), Some(&output));
+
+        let mut simulation = QuantumSimulation::new(allocator.qubit_count(), 0u64);
+        simulation.prepare_value(&input, 5);
+        simulation.pauli_x_register(&output);
+        simulation.cnot(input.qubit(2), answer);
+        assert_eq!(simulation.measure_value(&input), 5);
+        assert_eq!(simulation.measure_register(&output), vec![true, true]);
+        assert!(simulation.measure(vec![answer])[0]);
+    }
+
+    #[test]
+    fn gates_act_on_pairs_of_registers() {
+        let mut allocator = QubitAllocator::new();
+        let a = allocator.alloc_register("a", 3);
+        let b = allocator.alloc_register("b", 3);
+        let mut simulation = QuantumSimulation::new(allocator.qubit_count(), 0u64);
+        simulation.prepare_value(&a, 0b101);
+        simulation.prepare_value(&b, 0b011);
+        simulation.cnot_registers(&a, &b);
+        assert_eq!(simulation.measure_value(&b), 0b110);
+        // The U_f of the constant false flips the qubits of a where the qubits of b are |1⟩.
+        simulation.apply_u_f_register(|_| false, &a, &b);
+        assert_eq!(simulation.measure_value(&a), 0b011);
+
+        // The CZ gates flip the phase of the basis states with a pair of |1⟩ qubits.
+        simulation.hadamard_register(&b);
+        simulation.cz_registers(&a, &b);
+        simulation.hadamard_register(&b);
+        assert_eq!(simulation.measure_value(&b), 0b101);
+    }
+}
@@ -0,0 +1,589 @@
+/*
+Exact arithmetic in the rings Z[√2] and Z[ω], with ω = e^{iπ/4},
+and the number theory to solve the norm equation t·t† = ξ.
+
+The entries of the Clifford+T unitaries are elements of Z[ω] divided by a power of √2,
+hence the Clifford+T synthesis can work without any rounding errors.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+const SQRT_2: f64 = std::f64::consts::SQRT_2;
+
+// The trial division is done by the primes up to this bound,
+// the remaining factor has to be a prime below MAX_PRIME_FACTOR.
+const TRIAL_DIVISION_BOUND: u64 = 1 << 16;
+// Bound on the prime factors that keeps the Euclidean algorithm in Z[ω] within i128.
+const MAX_PRIME_FACTOR: i128 = 1 << 44;
+
+// Division rounded to the nearest integer.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let (numerator, denominator) = if denominator < 0 {
+        (-numerator, -denominator)
+    } else {
+        (numerator, denominator)
+    };
+    (2 * numerator + denominator).div_euclid(2 * denominator)
+}
+
+// The exponent n of λ^n closest to the square root of the ratio.
+fn lambda_exponent(ratio: f64) -> i32 {
+    (ratio.ln() / (2.0 * ZRoot2::LAMBDA.to_f64().ln())).round() as i32
+}
+
+// Element a + b√2 of the ring Z[√2].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ZRoot2 {
+    pub(crate) a: i128,
+    pub(crate) b: i128,
+}
+
+impl ZRoot2 {
+    pub(crate) const ZERO: ZRoot2 = ZRoot2::new(0, 0);
+    pub(crate) const ONE: ZRoot2 = ZRoot2::new(1, 0);
+    // The fundamental unit λ = 1 + √2 and its inverse λ⁻¹ = -1 + √2.
+    pub(crate) const LAMBDA: ZRoot2 = ZRoot2::new(1, 1);
+    pub(crate) const LAMBDA_INV: ZRoot2 = ZRoot2::new(-1, 1);
+
+    pub(crate) const fn new(a: i128, b: i128) -> ZRoot2 {
+        ZRoot2 { a, b }
+    }
+
+    pub(crate) const fn from_int(a: i128) -> ZRoot2 {
+        ZRoot2::new(a, 0)
+    }
+
+    // The √2-conjugate a - b√2.
+    pub(crate) fn conj(self) -> ZRoot2 {
+        ZRoot2::new(self.a, -self.b)
+    }
+
+    // The norm (a + b√2)(a - b√2) = a² - 2b².
+    pub(crate) fn norm(self) -> i128 {
+        self.a * self.a - 2 * self.b * self.b
+    }
+
+    pub(crate) fn to_f64(self) -> f64 {
+        self.a as f64 + SQRT_2 * self.b as f64
+    }
+
+    pub(crate) fn is_nonnegative(self) -> bool {
+        match (self.a >= 0, self.b >= 0) {
+            (true, true) => true,
+            (false, false) => false,
+            (true, false) => self.a * self.a >= 2 * self.b * self.b,
+            (false, true) => 2 * self.b * self.b >= self.a * self.a,
+        }
+    }
+
+    // Both the element and its √2-conjugate are nonnegative.
+    pub(crate) fn is_doubly_nonnegative(self) -> bool {
+        self.is_nonnegative() && self.conj().is_nonnegative()
+    }
+
+    pub(crate) fn pow(self, exponent: u32) -> ZRoot2 {
+        (0..exponent).fold(ZRoot2::ONE, |result, _| result * self)
+    }
+
+    // The exact quotient, if the division leaves no remainder.
+    pub(crate) fn checked_div(self, other: ZRoot2) -> Option<ZRoot2> {
+        let numerator = self * other.conj();
+        let norm = other.norm();
+        if norm == 0 || numerator.a % norm != 0 || numerator.b % norm != 0 {
+            return None;
+        }
+        Some(ZRoot2::new(numerator.a / norm, numerator.b / norm))
+    }
+
+    fn rounded_div(self, other: ZRoot2) -> ZRoot2 {
+        let numerator = self * other.conj();
+        let norm = other.norm();
+        ZRoot2::new(div_round(numerator.a, norm), div_round(numerator.b, norm))
+    }
+
+    pub(crate) fn gcd(self, other: ZRoot2) -> ZRoot2 {
+        let (mut x, mut y) = (self, other);
+        while y != ZRoot2::ZERO {
+            let remainder = x - y * x.rounded_div(y);
+            x = y;
+            y = remainder;
+        }
+        x
+    }
+
+    // Multiply by λ^n, for a negative n by (λ⁻¹)^(-n).
+    pub(crate) fn mul_lambda_power(self, n: i32) -> ZRoot2 {
+        let lambda = if n >= 0 {
+            ZRoot2::LAMBDA
+        } else {
+            ZRoot2::LAMBDA_INV
+        };
+        self * lambda.pow(n.unsigned_abs())
+    }
+
+    // The exponent n of the unit ±λ^n, read from the magnitude |a| + |b|√2 of λ^|n|
+    // to avoid the cancellation in the value of λ^n for a negative n.
+    fn unit_exponent(self) -> i32 {
+        let magnitude = self.a.abs() as f64 + SQRT_2 * self.b.abs() as f64;
+        let n = (magnitude.ln() / ZRoot2::LAMBDA.to_f64().ln()).round() as i32;
+        if (self.a >= 0) == (self.b >= 0) {
+            n
+        } else {
+            -n
+        }
+    }
+
+    // The associate with the element and its √2-conjugate of a similar magnitude.
+    fn balanced(self) -> ZRoot2 {
+        let ratio = (self.conj().to_f64() / self.to_f64()).abs();
+        self.mul_lambda_power(lambda_exponent(ratio))
+    }
+
+    pub(crate) fn to_omega(self) -> ZOmega {
+        ZOmega::new(self.a, self.b, 0, -self.b)
+    }
+}
+
+impl Add for ZRoot2 {
+    type Output = ZRoot2;
+
+    fn add(self, other: ZRoot2) -> ZRoot2 {
+        ZRoot2::new(self.a + other.a, self.b + other.b)
+    }
+}
+
+impl Sub for ZRoot2 {
+    type Output = ZRoot2;
+
+    fn sub(self, other: ZRoot2) -> ZRoot2 {
+        ZRoot2::new(self.a - other.a, self.b - other.b)
+    }
+}
+
+impl Mul for ZRoot2 {
+    type Output = ZRoot2;
+
+    fn mul(self, other: ZRoot2) -> ZRoot2 {
+        ZRoot2::new(
+            self.a * other.a + 2 * self.b * other.b,
+            self.a * other.b + self.b * other.a,
+        )
+    }
+}
+
+// Element a + bω + cω² + dω³ of the ring Z[ω], with ω = e^{iπ/4} and ω⁴ = -1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct ZOmega {
+    pub(crate) a: i128,
+    pub(crate) b: i128,
+    pub(crate) c: i128,
+    pub(crate) d: i128,
+}
+
+impl ZOmega {
+    pub(crate) const ZERO: ZOmega = ZOmega::new(0, 0, 0, 0);
+    pub(crate) const ONE: ZOmega = ZOmega::new(1, 0, 0, 0);
+    pub(crate) const I: ZOmega = ZOmega::new(0, 0, 1, 0);
+    // √2 = ω - ω³.
+    pub(crate) const ROOT2: ZOmega = ZOmega::new(0, 1, 0, -1);
+
+    pub(crate) const fn new(a: i128, b: i128, c: i128, d: i128) -> ZOmega {
+        ZOmega { a, b, c, d }
+    }
+
+    fn coefficients(self) -> [i128; 4] {
+        [self.a, self.b, self.c, self.d]
+    }
+
+    fn from_coefficients(coefficients: [i128; 4]) -> ZOmega {
+        ZOmega::new(
+            coefficients[0],
+            coefficients[1],
+            coefficients[2],
+            coefficients[3],
+        )
+    }
+
+    // Multiply by ω^n.
+    pub(crate) fn mul_omega_power(self, n: i32) -> ZOmega {
+        let mut coefficients = self.coefficients();
+        for _ in 0..n.rem_euclid(8) {
+            coefficients = [
+                -coefficients[3],
+                coefficients[0],
+                coefficients[1],
+                coefficients[2],
+            ];
+        }
+        ZOmega::from_coefficients(coefficients)
+    }
+
+    // The complex conjugate, using ω† = ω⁷ = -ω³.
+    pub(crate) fn conj(self) -> ZOmega {
+        ZOmega::new(self.a, -self.d, -self.c, -self.b)
+    }
+
+    // The √2-conjugate, mapping ω to ω⁵ = -ω.
+    pub(crate) fn root2_conj(self) -> ZOmega {
+        ZOmega::new(self.a, -self.b, self.c, -self.d)
+    }
+
+    // The real element x·x† of Z[√2].
+    pub(crate) fn norm_squared(self) -> ZRoot2 {
+        let product = self * self.conj();
+        debug_assert!(product.c == 0 && product.d == -product.b);
+        ZRoot2::new(product.a, product.b)
+    }
+
+    // The absolute norm x·x†·x•·x•†, a nonnegative integer.
+    pub(crate) fn abs_norm(self) -> i128 {
+        self.norm_squared().norm()
+    }
+
+    pub(crate) fn is_divisible_by_root2(self) -> bool {
+        (self.a - self.c) % 2 == 0 && (self.b - self.d) % 2 == 0
+    }
+
+    // Divide by √2, assuming the element is divisible.
+    pub(crate) fn div_root2(self) -> ZOmega {
+        let product = self * ZOmega::ROOT2;
+        ZOmega::new(product.a / 2, product.b / 2, product.c / 2, product.d / 2)
+    }
+
+    // The associate with the element and its √2-conjugate of a similar magnitude.
+    fn balanced(self) -> ZOmega {
+        let ratio =
+            (self.root2_conj().norm_squared().to_f64() / self.norm_squared().to_f64()).sqrt();
+        self * ZRoot2::ONE
+            .mul_lambda_power(lambda_exponent(ratio))
+            .to_omega()
+    }
+
+    pub(crate) fn pow(self, exponent: u32) -> ZOmega {
+        (0..exponent).fold(ZOmega::ONE, |result, _| result * self)
+    }
+
+    fn rounded_div(self, other: ZOmega) -> ZOmega {
+        let other_conj = other.root2_conj();
+        let numerator = self * other.conj() * other_conj * other_conj.conj();
+        let norm = other.abs_norm();
+        ZOmega::from_coefficients(numerator.coefficients().map(|c| div_round(c, norm)))
+    }
+
+    pub(crate) fn gcd(self, other: ZOmega) -> ZOmega {
+        let (mut x, mut y) = (self, other);
+        while y != ZOmega::ZERO {
+            let remainder = x - y * x.rounded_div(y);
+            x = y;
+            y = remainder;
+        }
+        x
+    }
+}
+
+impl Add for ZOmega {
+    type Output = ZOmega;
+
+    fn add(self, other: ZOmega) -> ZOmega {
+        ZOmega::new(
+            self.a + other.a,
+            self.b + other.b,
+            self.c + other.c,
+            self.d + other.d,
+        )
+    }
+}
+
+impl Sub for ZOmega {
+    type Output = ZOmega;
+
+    fn sub(self, other: ZOmega) -> ZOmega {
+        self + -other
+    }
+}
+
+impl Neg for ZOmega {
+    type Output = ZOmega;
+
+    fn neg(self) -> ZOmega {
+        ZOmega::new(-self.a, -self.b, -self.c, -self.d)
+    }
+}
+
+impl Mul for ZOmega {
+    type Output = ZOmega;
+
+    fn mul(self, other: ZOmega) -> ZOmega {
+        let x = self.coefficients();
+        let y = other.coefficients();
+        let mut coefficients = [0i128; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                if i + j < 4 {
+                    coefficients[i + j] += x[i] * y[j];
+                } else {
+                    coefficients[i + j - 4] -= x[i] * y[j];
+                }
+            }
+        }
+        ZOmega::from_coefficients(coefficients)
+    }
+}
+
+fn mul_mod(a: i128, b: i128, modulus: i128) -> i128 {
+    (a * b).rem_euclid(modulus)
+}
+
+fn pow_mod(base: i128, exponent: i128, modulus: i128) -> i128 {
+    let mut result = 1;
+    let mut base = base.rem_euclid(modulus);
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+// Deterministic Miller-Rabin primality test, valid for all the numbers below 3.3·10^24.
+fn is_prime(n: i128) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let witnesses: [i128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+    for &p in witnesses.iter() {
+        if n % p == 0 {
+            return n == p;
+        }
+    }
+    let mut d = n - 1;
+    let mut s = 0;
+    while d % 2 == 0 {
+        d /= 2;
+        s += 1;
+    }
+    'witness: for &a in witnesses.iter() {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+// Square root of n modulo the odd prime p by the Tonelli-Shanks algorithm.
+fn sqrt_mod(n: i128, p: i128) -> Option<i128> {
+    let n = n.rem_euclid(p);
+    if n == 0 {
+        return Some(0);
+    }
+    if pow_mod(n, (p - 1) / 2, p) != 1 {
+        return None;
+    }
+    let mut q = p - 1;
+    let mut s = 0;
+    while q % 2 == 0 {
+        q /= 2;
+        s += 1;
+    }
+    let mut z = 2;
+    while pow_mod(z, (p - 1) / 2, p) != p - 1 {
+        z += 1;
+    }
+    let mut m = s;
+    let mut c = pow_mod(z, q, p);
+    let mut t = pow_mod(n, q, p);
+    let mut r = pow_mod(n, (q + 1) / 2, p);
+    while t != 1 {
+        let mut i = 0;
+        let mut t2 = t;
+        while t2 != 1 {
+            t2 = mul_mod(t2, t2, p);
+            i += 1;
+        }
+        let b = pow_mod(c, 1 << (m - i - 1), p);
+        m = i;
+        c = mul_mod(b, b, p);
+        t = mul_mod(t, c, p);
+        r = mul_mod(r, b, p);
+    }
+    Some(r)
+}
+
+fn isqrt(n: i128) -> i128 {
+    let mut x = (n as f64).sqrt() as i128;
+    while x * x > n {
+        x -= 1;
+    }
+    while (x + 1) * (x + 1) <= n {
+        x += 1;
+    }
+    x
+}
+
+// Cornacchia's algorithm for x² + d·y² = p, given h with h² ≡ -d (mod p).
+fn cornacchia(d: i128, p: i128, h: i128) -> Option<(i128, i128)> {
+    let (mut r0, mut r1) = (p, if 2 * h > p { p - h } else { h });
+    let bound = isqrt(p);
+    while r1 > bound {
+        (r0, r1) = (r1, r0 % r1);
+    }
+    let remainder = p - r1 * r1;
+    if remainder % d != 0 {
+        return None;
+    }
+    let y = isqrt(remainder / d);
+    if y * y * d != remainder {
+        return None;
+    }
+    Some((r1, y))
+}
+
+fn primes_below(bound: u64) -> Vec<i128> {
+    let mut is_composite = vec![false; bound as usize];
+    let mut primes = Vec::new();
+    for i in 2..bound as usize {
+        if !is_composite[i] {
+            primes.push(i as i128);
+            for j in (i * i..bound as usize).step_by(i) {
+                is_composite[j] = true;
+            }
+        }
+    }
+    primes
+}
+
+// Factor n into the prime powers, if n is a product of small primes and at most one larger prime.
+fn factor(n: i128, small_primes: &[i128]) -> Option<Vec<(i128, u32)>> {
+    let mut n = n;
+    let mut factors = Vec::new();
+    for &p in small_primes.iter() {
+        if p * p > n {
+            break;
+        }
+        let mut exponent = 0;
+        while n % p == 0 {
+            n /= p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+    }
+    if n > 1 {
+        if n >= MAX_PRIME_FACTOR || !is_prime(n) {
+            return None;
+        }
+        factors.push((n, 1));
+    }
+    Some(factors)
+}
+
+// Number of times the divisor divides the element, dividing it out.
+fn divide_out(xi: &mut ZRoot2, divisor: ZRoot2) -> u32 {
+    let mut multiplicity = 0;
+    while let Some(quotient) = xi.checked_div(divisor) {
+        *xi = quotient;
+        multiplicity += 1;
+    }
+    multiplicity
+}
+
+// Solver of the norm equations t·t† = ξ, which keeps the table of the small primes.
+pub(crate) struct NormEquationSolver {
+    small_primes: Vec<i128>,
+}
+
+impl NormEquationSolver {
+    pub(crate) fn new() -> NormEquationSolver {
+        NormEquationSolver {
+            small_primes: primes_below(TRIAL_DIVISION_BOUND),
+        }
+    }
+
+    // Find t in Z[ω] with t·t† = ξ for a doubly nonnegative ξ in Z[√2].
+    // Returns None if there is no solution or if the norm of ξ is not easy to factor.
+    pub(crate) fn solve(&self, xi: ZRoot2) -> Option<ZOmega> {
+        if xi == ZRoot2::ZERO {
+            return Some(ZOmega::ZERO);
+        }
+        let mut rest = xi;
+        let mut t = ZOmega::ONE;
+
+        // The prime √2 with δ·δ† = √2·λ for δ = 1 + ω.
+        let root2_multiplicity = divide_out(&mut rest, ZRoot2::new(0, 1));
+        t = t * ZOmega::new(1, 1, 0, 0).pow(root2_multiplicity);
+
+        for (p, exponent) in factor(rest.norm().abs(), &self.small_primes)? {
+            match p % 8 {
+                3 | 5 => {
+                    // The prime p is inert in Z[√2] and p = x² + y² or p = x² + 2y².
+                    if !exponent.is_multiple_of(2) {
+                        return None;
+                    }
+                    let t_p = if p % 8 == 5 {
+                        let (x, y) = cornacchia(1, p, sqrt_mod(-1, p)?)?;
+                        ZOmega::new(x, 0, y, 0)
+                    } else {
+                        let (x, y) = cornacchia(2, p, sqrt_mod(-2, p)?)?;
+                        ZOmega::new(x, y, 0, y)
+                    };
+                    t = t * t_p.pow(exponent / 2);
+                }
+                _ => {
+                    // The prime p splits into η·η• in Z[√2].
+                    let eta = ZRoot2::from_int(p)
+                        .gcd(ZRoot2::new(sqrt_mod(2, p)?, 1))
+                        .balanced();
+                    let multiplicity = divide_out(&mut rest, eta);
+                    let conj_multiplicity = divide_out(&mut rest, eta.conj());
+                    if p % 8 == 7 {
+                        // The prime η is inert in Z[ω].
+                        if !multiplicity.is_multiple_of(2) || !conj_multiplicity.is_multiple_of(2) {
+                            return None;
+                        }
+                        t = t
+                            * eta.to_omega().pow(multiplicity / 2)
+                            * eta.conj().to_omega().pow(conj_multiplicity / 2);
+                    } else {
+                        let h = sqrt_mod(-1, p)?;
+                        let t_eta = eta
+                            .to_omega()
+                            .gcd(ZOmega::new(h, 0, 0, 0) + ZOmega::I)
+                            .balanced();
+                        if t_eta.abs_norm() != p {
+                            return None;
+                        }
+                        t = t * t_eta.pow(multiplicity) * t_eta.root2_conj().pow(conj_multiplicity);
+                    }
+                }
+            }
+        }
+
+        // The remaining doubly positive unit ξ/(t·t†) is λ^(2m), fix it by multiplying t by λ^m.
+        let unit = xi.checked_div(t.norm_squared())?;
+        if unit.norm() != 1 || !unit.is_doubly_nonnegative() {
+            return None;
+        }
+        t = t * ZRoot2::ONE
+            .mul_lambda_power(unit.unit_exponent() / 2)
+            .to_omega();
+        if t.norm_squared() != xi {
+            return None;
+        }
+
+        Some(t)
+    }
+}
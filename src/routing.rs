@@ -0,0 +1,266 @@
+/*
+Qubit routing for a restricted coupling map.
+
+Real devices can only apply multi-qubit gates to physically connected qubits.
+The routing inserts SWAP gates so that every multi-qubit gate of a circuit
+acts on adjacent qubits of the given connectivity graph.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::VecDeque;
+
+use crate::circuit::Circuit;
+
+// Undirected connectivity graph of the physical qubits.
+#[derive(Clone, Debug)]
+pub struct CouplingMap {
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl CouplingMap {
+    pub fn new(qubit_count: usize, edges: &[(usize, usize)]) -> CouplingMap {
+        let mut neighbors = vec![Vec::new(); qubit_count];
+        for &(qubit_number0, qubit_number1) in edges.iter() {
+            assert!(
+                (qubit_number0 < qubit_count) && (qubit_number1 < qubit_count),
+                "The qubit number has to be less than the number of qubits {}.",
+                qubit_count
+            );
+            assert!(
+                qubit_number0 != qubit_number1,
+                "A qubit cannot be coupled to itself."
+            );
+            if !neighbors[qubit_number0].contains(&qubit_number1) {
+                neighbors[qubit_number0].push(qubit_number1);
+                neighbors[qubit_number1].push(qubit_number0);
+            }
+        }
+
+        CouplingMap { neighbors }
+    }
+
+    // Qubits connected in a chain 0 - 1 - ... - (n-1).
+    pub fn line(qubit_count: usize) -> CouplingMap {
+        let edges: Vec<(usize, usize)> = (1..qubit_count).map(|i| (i - 1, i)).collect();
+        CouplingMap::new(qubit_count, &edges)
+    }
+
+    // Qubits connected in a chain with the last qubit connected back to the first one.
+    pub fn ring(qubit_count: usize) -> CouplingMap {
+        let mut edges: Vec<(usize, usize)> = (1..qubit_count).map(|i| (i - 1, i)).collect();
+        if qubit_count > 2 {
+            edges.push((qubit_count - 1, 0));
+        }
+        CouplingMap::new(qubit_count, &edges)
+    }
+
+    // Qubits in a rectangular grid numbered row by row, connected to their horizontal and vertical neighbors.
+    pub fn grid(row_count: usize, column_count: usize) -> CouplingMap {
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for row in 0..row_count {
+            for column in 0..column_count {
+                let qubit_number = row * column_count + column;
+                if column + 1 < column_count {
+                    edges.push((qubit_number, qubit_number + 1));
+                }
+                if row + 1 < row_count {
+                    edges.push((qubit_number, qubit_number + column_count));
+                }
+            }
+        }
+        CouplingMap::new(row_count * column_count, &edges)
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    pub fn are_adjacent(&self, qubit_number0: usize, qubit_number1: usize) -> bool {
+        self.neighbors[qubit_number0].contains(&qubit_number1)
+    }
+
+    // Breadth-first search for the shortest path from the start qubit to a qubit satisfying the goal
+    // which does not pass through any of the blocked qubits.
+    fn shortest_path<F>(&self, start: usize, is_goal: F, blocked: &[usize]) -> Option<Vec<usize>>
+    where
+        F: Fn(usize) -> bool,
+    {
+        let mut previous: Vec<Option<usize>> = vec![None; self.qubit_count()];
+        let mut visited = vec![false; self.qubit_count()];
+        let mut queue = VecDeque::new();
+        visited[start] = true;
+        queue.push_back(start);
+
+        while let Some(qubit_number) = queue.pop_front() {
+            if is_goal(qubit_number) {
+                let mut path = vec![qubit_number];
+                while let Some(p) = previous[*path.last().unwrap()] {
+                    path.push(p);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &neighbor in self.neighbors[qubit_number].iter() {
+                if !visited[neighbor] && !blocked.contains(&neighbor) {
+                    visited[neighbor] = true;
+                    previous[neighbor] = Some(qubit_number);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RoutedCircuit {
+    // The circuit acting on the physical qubits.
+    pub circuit: Circuit,
+    // The number of the inserted SWAP gates.
+    pub swap_count: usize,
+    // The physical qubit number holding each logical qubit at the end of the circuit.
+    pub final_layout: Vec<usize>,
+}
+
+struct Router<'a> {
+    coupling_map: &'a CouplingMap,
+    circuit: Circuit,
+    swap_count: usize,
+    // Logical to physical qubit number.
+    layout: Vec<usize>,
+    // Physical to logical qubit number.
+    inverse_layout: Vec<usize>,
+}
+
+impl Router<'_> {
+    fn swap_physical(&mut self, physical0: usize, physical1: usize) {
+        self.circuit.swap(physical0, physical1);
+        self.swap_count += 1;
+        let logical0 = self.inverse_layout[physical0];
+        let logical1 = self.inverse_layout[physical1];
+        self.layout[logical0] = physical1;
+        self.layout[logical1] = physical0;
+        self.inverse_layout[physical0] = logical1;
+        self.inverse_layout[physical1] = logical0;
+    }
+
+    // Move the logical qubit next to any of the target logical qubits without moving them.
+    fn move_next_to(&mut self, logical: usize, target_logicals: &[usize]) {
+        let targets: Vec<usize> = target_logicals.iter().map(|&q| self.layout[q]).collect();
+        let coupling_map = self.coupling_map;
+        let path = coupling_map
+            .shortest_path(
+                self.layout[logical],
+                |qubit_number| {
+                    targets
+                        .iter()
+                        .any(|&target| coupling_map.are_adjacent(qubit_number, target))
+                },
+                &targets,
+            )
+            .unwrap_or_else(|| {
+                panic!(
+                    "The coupling map does not connect the qubit {} to the qubits {:?}.",
+                    logical, target_logicals
+                )
+            });
+        for i in 1..path.len() {
+            self.swap_physical(path[i - 1], path[i]);
+        }
+    }
+}
+
+// Route the circuit onto the coupling map, starting from the trivial layout
+// where the logical qubit i is placed on the physical qubit i.
+// For the Toffoli gate, the first control qubit is brought next to the target qubit
+// and the second control qubit next to either of them, so the three qubits form a connected chain.
+pub fn route(circuit: &Circuit, coupling_map: &CouplingMap) -> RoutedCircuit {
+    let qubit_count = coupling_map.qubit_count();
+    assert!(
+        circuit.qubit_count() <= qubit_count,
+        "The number of qubits in the circuit cannot exceed the number of qubits in the coupling map {}.",
+        qubit_count
+    );
+
+    let mut router = Router {
+        coupling_map,
+        circuit: Circuit::with_clbits(qubit_count, circuit.clbit_count()),
+        swap_count: 0,
+        layout: (0..qubit_count).collect(),
+        inverse_layout: (0..qubit_count).collect(),
+    };
+
+    for (gate, condition) in circuit.gates().iter().zip(circuit.conditions()) {
+        let qubits = gate.qubits();
+        match qubits.len() {
+            1 => {}
+            2 => {
+                if !coupling_map.are_adjacent(router.layout[qubits[0]], router.layout[qubits[1]]) {
+                    router.move_next_to(qubits[0], &[qubits[1]]);
+                }
+            }
+            _ => {
+                let (control0, control1, target) = (qubits[0], qubits[1], qubits[2]);
+                if !coupling_map.are_adjacent(router.layout[control0], router.layout[target]) {
+                    router.move_next_to(control0, &[target]);
+                }
+                if !coupling_map.are_adjacent(router.layout[control1], router.layout[target])
+                    && !coupling_map.are_adjacent(router.layout[control1], router.layout[control0])
+                {
+                    router.move_next_to(control1, &[control0, target]);
+                }
+            }
+        }
+        let layout = &router.layout;
+        let routed_gate = gate.map_qubits(|q| layout[q]);
+        router.circuit.push_with_condition(routed_gate, *condition);
+    }
+
+    router.layout.truncate(circuit.qubit_count());
+    RoutedCircuit {
+        circuit: router.circuit,
+        swap_count: router.swap_count,
+        final_layout: router.layout,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn routed_gates_act_on_adjacent_qubits() {
+        let mut circuit = Circuit::new(5);
+        circuit.pauli_x(0);
+        circuit.cnot(0, 4);
+        circuit.toffoli(0, 4, 2);
+        circuit.cnot(2, 1);
+        circuit.swap(1, 3);
+
+        let coupling_map = CouplingMap::line(5);
+        let routed = route(&circuit, &coupling_map);
+        assert!(routed.swap_count > 0);
+        for gate in routed.circuit.gates().iter() {
+            let qubits = gate.qubits();
+            for &qubit_number in qubits[1..].iter() {
+                assert!(qubits
+                    .iter()
+                    .any(|&other| coupling_map.are_adjacent(qubit_number, other)));
+            }
+        }
+
+        let mut simulation = QuantumSimulation::new(5, 0u64);
+        circuit.run(&mut simulation);
+        let measurements = simulation.measure_all();
+        simulation.reset();
+        routed.circuit.run(&mut simulation);
+        let routed_measurements = simulation.measure_all();
+        for (logical, &physical) in routed.final_layout.iter().enumerate() {
+            assert_eq!(measurements[logical], routed_measurements[physical]);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+/*
+Layered scheduling of the gates of a circuit for batch application.
+
+The gates are grouped into layers of gates acting on disjoint qubits, so the gates in a layer
+commute and the state vector simulation can apply a whole layer in one sweep over the amplitudes.
+Each measurement and each gate with a classical condition forms a layer of its own.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::circuit::{Circuit, Condition, Gate};
+use crate::simulation::Simulation;
+use crate::state_vector_simulation::QuantumSimulation;
+
+// The highest number of the qubits in a layer, so the block of the amplitudes spanned by a layer
+// of 2^10 amplitudes fits into the cache.
+pub const MAX_LAYER_QUBIT_COUNT: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    qubit_count: usize,
+    clbit_count: usize,
+    layers: Vec<Vec<Gate>>,
+    // The classical condition of each layer formed by a single conditional gate.
+    conditions: Vec<Option<Condition>>,
+}
+
+impl Schedule {
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    pub fn layers(&self) -> &[Vec<Gate>] {
+        &self.layers
+    }
+
+    pub fn conditions(&self) -> &[Option<Condition>] {
+        &self.conditions
+    }
+
+    // Apply the layers in order and return the classical bits. The simulation is not reset beforehand.
+    pub fn run(&self, simulation: &mut QuantumSimulation) -> Vec<bool> {
+        let mut clbits = vec![false; self.clbit_count];
+        for (layer, condition) in self.layers.iter().zip(self.conditions.iter()) {
+            if condition.is_some_and(|condition| !condition.is_satisfied(&clbits)) {
+                continue;
+            }
+            match layer[..] {
+                [Gate::Measure(q, c)] => clbits[c] = simulation.measure(vec![q])[0],
+                _ => simulation.apply_layer(layer),
+            }
+        }
+        clbits
+    }
+}
+
+// Place each gate into the earliest layer after the previous gates on its qubits
+// which does not exceed MAX_LAYER_QUBIT_COUNT qubits.
+pub fn schedule_layers(circuit: &Circuit) -> Schedule {
+    let mut layers: Vec<Vec<Gate>> = Vec::new();
+    let mut layer_qubit_counts: Vec<usize> = Vec::new();
+    // The first layer where each qubit is free.
+    let mut qubit_layers = vec![0usize; circuit.qubit_count()];
+
+    // Measurements and conditional gates are ordered with each other and with all the other layers,
+    // so the next layers start after the last of them.
+    let mut first_free_layer = 0;
+    let mut conditions: Vec<Option<Condition>> = Vec::new();
+
+    for (gate, condition) in circuit.gates().iter().zip(circuit.conditions()) {
+        let qubits = gate.qubits();
+        let mut layer = qubits
+            .iter()
+            .map(|&q| qubit_layers[q])
+            .max()
+            .unwrap()
+            .max(first_free_layer);
+        if matches!(gate, Gate::Measure(_, _)) || condition.is_some() {
+            layer = layers.len();
+            first_free_layer = layer + 1;
+        }
+        while layer < layers.len()
+            && layer_qubit_counts[layer] + qubits.len() > MAX_LAYER_QUBIT_COUNT
+        {
+            layer += 1;
+        }
+        if layer == layers.len() {
+            layers.push(Vec::new());
+            layer_qubit_counts.push(0);
+            conditions.push(*condition);
+        }
+        layers[layer].push(*gate);
+        layer_qubit_counts[layer] += qubits.len();
+        for &qubit_number in qubits.iter() {
+            qubit_layers[qubit_number] = layer + 1;
+        }
+    }
+
+    Schedule {
+        qubit_count: circuit.qubit_count(),
+        clbit_count: circuit.clbit_count(),
+        layers,
+        conditions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduled_layers_match_sequential_run() {
+        let mut circuit = Circuit::new(12);
+        for round in 0..3 {
+            for qubit_number in 0..12 {
+                circuit.hadamard(qubit_number);
+                circuit.rz(0.3 * (qubit_number + round) as f64, qubit_number);
+            }
+            for qubit_number in (round % 2..11).step_by(2) {
+                circuit.cnot(qubit_number, qubit_number + 1);
+            }
+            circuit.toffoli(0, 5, 11);
+            circuit.cphase(0.7, 3, 8);
+        }
+
+        let schedule = schedule_layers(&circuit);
+        let gate_count: usize = schedule.layers().iter().map(|layer| layer.len()).sum();
+        assert_eq!(gate_count, circuit.gates().len());
+        for layer in schedule.layers().iter() {
+            let mut qubits: Vec<usize> = layer.iter().flat_map(|gate| gate.qubits()).collect();
+            assert!(qubits.len() <= MAX_LAYER_QUBIT_COUNT);
+            qubits.sort();
+            qubits.dedup();
+            assert_eq!(
+                qubits.len(),
+                layer.iter().map(|gate| gate.qubits().len()).sum::<usize>()
+            );
+        }
+
+        let mut sequential = QuantumSimulation::new(12, 0u64);
+        circuit.run(&mut sequential);
+        let mut layered = QuantumSimulation::new(12, 0u64);
+        schedule.run(&mut layered);
+        for (a, b) in sequential
+            .amplitudes()
+            .iter()
+            .zip(layered.amplitudes().iter())
+        {
+            assert!((a - b).norm() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,411 @@
+/*
+Quantum hybrid Schrödinger–Feynman simulation of the amplitudes of a circuit.
+
+The qubits are cut into the half A of the qubits below the cut and the half B of the others.
+The gates within a half apply to its state vector, as in the Schrödinger simulation, and each gate
+across the cut is a sum of products of the operators on the halves, as in the Feynman
+path integral: the controlled gates are |0⟩⟨0| ⊗ I + |1⟩⟨1| ⊗ U on their control, the swap gate
+is (II + XX + YY + ZZ)/2, and the Toffoli gate is |0⟩⟨0| ⊗ I + |1⟩⟨1| ⊗ CNOT on its control alone
+in a half, or I ⊗ I + |11⟩⟨11| ⊗ (X - I) on its target alone in a half. Each choice of the terms
+of the gates across the cut is a path, on which the final state is the product of the states of
+the halves, and the amplitude ψ(a, b) is the sum of ψ_A(a) ψ_B(b) over the paths.
+
+The paths are visited depth first, sharing the states of the halves up to each gate across
+the cut, so that the memory is the states of the halves, 2^|A| + 2^|B| amplitudes for each gate
+across the cut, instead of the 2^n amplitudes, at the cost of the number of paths, the product
+of the numbers of the terms of the gates across the cut.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+
+use crate::circuit::{u_f_gates, Circuit, Gate};
+use crate::gate::{
+    phase_matrix, rx_matrix, ry_matrix, rz_matrix, Matrix, HADAMARD_MATRIX, PAULI_X_MATRIX,
+    PAULI_Y_MATRIX, PAULI_Z_MATRIX, S_MATRIX, T_MATRIX,
+};
+use crate::simulation::check_qubits;
+use crate::state_vector_simulation;
+
+const ZERO: Complex<f64> = Complex::new(0.0, 0.0);
+const ONE: Complex<f64> = Complex::new(1.0, 0.0);
+const HALF: Complex<f64> = Complex::new(0.5, 0.0);
+
+const IDENTITY_MATRIX: Matrix = [ONE, ZERO, ZERO, ONE];
+const PROJECTOR0_MATRIX: Matrix = [ONE, ZERO, ZERO, ZERO];
+const PROJECTOR1_MATRIX: Matrix = [ZERO, ZERO, ZERO, ONE];
+
+// The matrix applied to the target qubit where the control qubits have the given values,
+// and the identity elsewhere, not necessarily unitary.
+#[derive(Clone, Debug)]
+struct Operator {
+    matrix: Matrix,
+    controls: Vec<(usize, bool)>,
+    target: usize,
+}
+
+impl Operator {
+    fn new(matrix: Matrix, target: usize) -> Operator {
+        Operator {
+            matrix,
+            controls: Vec::new(),
+            target,
+        }
+    }
+
+    fn controlled(matrix: Matrix, control: (usize, bool), target: usize) -> Operator {
+        Operator {
+            matrix,
+            controls: vec![control],
+            target,
+        }
+    }
+}
+
+// The product of the operators of a term of a gate, applied in order.
+type Term = Vec<Operator>;
+
+#[derive(Clone, Debug)]
+pub struct SchrodingerFeynmanSimulation {
+    qubit_count: usize,
+    cut: usize,
+    // The terms of each gate, a single one for the gates within a half.
+    gate_terms: Vec<Vec<Term>>,
+}
+
+impl SchrodingerFeynmanSimulation {
+    // The qubits below the cut form the half A and the others the half B.
+    pub fn new(circuit: &Circuit, cut: usize) -> SchrodingerFeynmanSimulation {
+        let qubit_count = circuit.qubit_count();
+        assert!(
+            cut <= qubit_count,
+            "The cut has to be at most the number of qubits {}.",
+            qubit_count
+        );
+        assert!(
+            cut.max(qubit_count - cut) <= state_vector_simulation::MAX_QUBIT_COUNT,
+            "The number of qubits in a half of the simulation cannot exceed {}.",
+            state_vector_simulation::MAX_QUBIT_COUNT
+        );
+        assert!(
+            circuit
+                .conditions()
+                .iter()
+                .all(|condition| condition.is_none()),
+            "The Schrödinger–Feynman simulation cannot contain conditional gates."
+        );
+        let mut gates = Vec::new();
+        for gate in circuit.gates().iter() {
+            if let Err(error) = check_qubits(&gate.qubits(), qubit_count) {
+                panic!("{}", error);
+            }
+            match *gate {
+                Gate::Measure(_, _) => {
+                    panic!("The Schrödinger–Feynman simulation cannot contain measurements.")
+                }
+                Gate::UF(f, q0, q1) => gates.extend(u_f_gates(f, q0, q1)),
+                gate => gates.push(gate),
+            }
+        }
+        let mut simulation = SchrodingerFeynmanSimulation {
+            qubit_count,
+            cut,
+            gate_terms: Vec::new(),
+        };
+        simulation.gate_terms = gates.iter().map(|gate| simulation.terms(gate)).collect();
+
+        simulation
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The number of the paths, the product of the numbers of the terms of the gates.
+    pub fn path_count(&self) -> u128 {
+        self.gate_terms
+            .iter()
+            .map(|terms| terms.len() as u128)
+            .product()
+    }
+
+    // The amplitude of the basis state with the values of the qubits.
+    pub fn amplitude(&self, values: &[bool]) -> Complex<f64> {
+        self.amplitudes(&[values.to_vec()])[0]
+    }
+
+    // The amplitudes of the basis states, computed together in a single sum over the paths.
+    pub fn amplitudes(&self, basis_states: &[Vec<bool>]) -> Vec<Complex<f64>> {
+        let indices: Vec<(usize, usize)> = basis_states
+            .iter()
+            .map(|values| {
+                assert!(
+                    values.len() == self.qubit_count,
+                    "The basis state has to have a value for each of the qubits {}.",
+                    self.qubit_count
+                );
+                let index = |qubits: std::ops::Range<usize>| {
+                    qubits
+                        .clone()
+                        .filter(|&q| values[q])
+                        .map(|q| 1 << (q - qubits.start))
+                        .sum::<usize>()
+                };
+                (index(0..self.cut), index(self.cut..self.qubit_count))
+            })
+            .collect();
+        let ground_state = |qubit_count: usize| {
+            let mut amplitudes = vec![ZERO; 1 << qubit_count];
+            amplitudes[0] = ONE;
+            amplitudes
+        };
+        let mut sums = vec![ZERO; basis_states.len()];
+        self.visit_paths(
+            0,
+            ground_state(self.cut),
+            ground_state(self.qubit_count - self.cut),
+            &indices,
+            &mut sums,
+        );
+        sums
+    }
+
+    // Apply the gates from the gate number on the states of the halves, branching on the terms
+    // of the gates across the cut, and add the products of the amplitudes at the end of a path.
+    fn visit_paths(
+        &self,
+        gate_number: usize,
+        mut half_a: Vec<Complex<f64>>,
+        mut half_b: Vec<Complex<f64>>,
+        indices: &[(usize, usize)],
+        sums: &mut [Complex<f64>],
+    ) {
+        let mut gate_number = gate_number;
+        while gate_number < self.gate_terms.len() && self.gate_terms[gate_number].len() == 1 {
+            self.apply_term(&self.gate_terms[gate_number][0], &mut half_a, &mut half_b);
+            gate_number += 1;
+        }
+        if gate_number == self.gate_terms.len() {
+            for (sum, &(index_a, index_b)) in sums.iter_mut().zip(indices.iter()) {
+                *sum += half_a[index_a] * half_b[index_b];
+            }
+            return;
+        }
+        let terms = &self.gate_terms[gate_number];
+        for (k, term) in terms.iter().enumerate() {
+            // The last term takes the states instead of copying them.
+            let (mut term_a, mut term_b) = if k + 1 == terms.len() {
+                (std::mem::take(&mut half_a), std::mem::take(&mut half_b))
+            } else {
+                (half_a.clone(), half_b.clone())
+            };
+            self.apply_term(term, &mut term_a, &mut term_b);
+            if term_a.iter().all(|&a| a == ZERO) || term_b.iter().all(|&b| b == ZERO) {
+                continue;
+            }
+            self.visit_paths(gate_number + 1, term_a, term_b, indices, sums);
+        }
+    }
+
+    fn apply_term(&self, term: &Term, half_a: &mut [Complex<f64>], half_b: &mut [Complex<f64>]) {
+        for operator in term.iter() {
+            if operator.target < self.cut {
+                apply_operator(half_a, operator, 0);
+            } else {
+                apply_operator(half_b, operator, self.cut);
+            }
+        }
+    }
+
+    fn is_in_a(&self, qubit_number: usize) -> bool {
+        qubit_number < self.cut
+    }
+
+    // The terms of the gate, a single one unless the gate is across the cut.
+    fn terms(&self, gate: &Gate) -> Vec<Term> {
+        let single = |matrix: Matrix, q: usize| vec![vec![Operator::new(matrix, q)]];
+        // The controlled matrix within a half, or across the cut the sum of the projectors
+        // of the control times the identity and the matrix on the target.
+        let controlled = |matrix: Matrix, control: usize, target: usize| {
+            if self.is_in_a(control) == self.is_in_a(target) {
+                vec![vec![Operator::controlled(matrix, (control, true), target)]]
+            } else {
+                vec![
+                    vec![Operator::new(PROJECTOR0_MATRIX, control)],
+                    vec![
+                        Operator::new(PROJECTOR1_MATRIX, control),
+                        Operator::new(matrix, target),
+                    ],
+                ]
+            }
+        };
+        match *gate {
+            Gate::PauliX(q) => single(PAULI_X_MATRIX, q),
+            Gate::PauliY(q) => single(PAULI_Y_MATRIX, q),
+            Gate::PauliZ(q) => single(PAULI_Z_MATRIX, q),
+            Gate::Hadamard(q) => single(HADAMARD_MATRIX, q),
+            Gate::S(q) => single(S_MATRIX, q),
+            Gate::T(q) => single(T_MATRIX, q),
+            Gate::Rx(theta, q) => single(rx_matrix(theta), q),
+            Gate::Ry(theta, q) => single(ry_matrix(theta), q),
+            Gate::Rz(theta, q) => single(rz_matrix(theta), q),
+            Gate::Cnot(q0, q1) => controlled(PAULI_X_MATRIX, q0, q1),
+            Gate::Cz(q0, q1) => controlled(PAULI_Z_MATRIX, q0, q1),
+            Gate::Cphase(theta, q0, q1) => {
+                controlled(phase_matrix(Complex::new(0.0, theta).exp()), q0, q1)
+            }
+            Gate::Swap(q0, q1) => {
+                if self.is_in_a(q0) == self.is_in_a(q1) {
+                    let cnot = |c, t| Operator::controlled(PAULI_X_MATRIX, (c, true), t);
+                    vec![vec![cnot(q0, q1), cnot(q1, q0), cnot(q0, q1)]]
+                } else {
+                    let half_identity = IDENTITY_MATRIX.map(|element| element * HALF);
+                    vec![
+                        vec![Operator::new(half_identity, q0)],
+                        vec![
+                            Operator::new(PAULI_X_MATRIX.map(|element| element * HALF), q0),
+                            Operator::new(PAULI_X_MATRIX, q1),
+                        ],
+                        vec![
+                            Operator::new(PAULI_Y_MATRIX.map(|element| element * HALF), q0),
+                            Operator::new(PAULI_Y_MATRIX, q1),
+                        ],
+                        vec![
+                            Operator::new(PAULI_Z_MATRIX.map(|element| element * HALF), q0),
+                            Operator::new(PAULI_Z_MATRIX, q1),
+                        ],
+                    ]
+                }
+            }
+            Gate::Toffoli(c0, c1, t) => {
+                let (in_a0, in_a1, in_a_t) = (self.is_in_a(c0), self.is_in_a(c1), self.is_in_a(t));
+                if in_a0 == in_a1 && in_a1 == in_a_t {
+                    vec![vec![Operator {
+                        matrix: PAULI_X_MATRIX,
+                        controls: vec![(c0, true), (c1, true)],
+                        target: t,
+                    }]]
+                } else if in_a0 == in_a1 {
+                    let x_minus_identity = [-ONE, ONE, ONE, -ONE];
+                    vec![
+                        Vec::new(),
+                        vec![
+                            Operator::new(PROJECTOR1_MATRIX, c0),
+                            Operator::new(PROJECTOR1_MATRIX, c1),
+                            Operator::new(x_minus_identity, t),
+                        ],
+                    ]
+                } else {
+                    // The control alone in its half.
+                    let (alone, other) = if in_a0 == in_a_t { (c1, c0) } else { (c0, c1) };
+                    vec![
+                        vec![Operator::new(PROJECTOR0_MATRIX, alone)],
+                        vec![
+                            Operator::new(PROJECTOR1_MATRIX, alone),
+                            Operator::controlled(PAULI_X_MATRIX, (other, true), t),
+                        ],
+                    ]
+                }
+            }
+            Gate::UF(_, _, _) | Gate::Measure(_, _) => unreachable!(),
+        }
+    }
+}
+
+// Apply the operator to the state of a half, whose first qubit is the qubit number offset.
+fn apply_operator(amplitudes: &mut [Complex<f64>], operator: &Operator, offset: usize) {
+    let (mut control_mask, mut control_value) = (0, 0);
+    for &(control, value) in operator.controls.iter() {
+        control_mask |= 1 << (control - offset);
+        if value {
+            control_value |= 1 << (control - offset);
+        }
+    }
+    let matrix = &operator.matrix;
+    let stride = 1 << (operator.target - offset);
+    for (c, chunk) in amplitudes.chunks_exact_mut(2 * stride).enumerate() {
+        let (zeros, ones) = chunk.split_at_mut(stride);
+        let chunk_start = 2 * stride * c;
+        for (j, (amplitude0, amplitude1)) in zeros.iter_mut().zip(ones.iter_mut()).enumerate() {
+            if (chunk_start + j) & control_mask != control_value {
+                continue;
+            }
+            let (a0, a1) = (*amplitude0, *amplitude1);
+            *amplitude0 = matrix[0] * a0 + matrix[1] * a1;
+            *amplitude1 = matrix[2] * a0 + matrix[3] * a1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn amplitudes_match_state_vector() {
+        let mut circuit = Circuit::new(5);
+        circuit.hadamard(0);
+        circuit.hadamard(3);
+        circuit.cnot(0, 3);
+        circuit.ry(0.6, 4);
+        circuit.cphase(0.9, 4, 1);
+        circuit.swap(1, 2);
+        circuit.swap(2, 4);
+        circuit.t(2);
+        circuit.toffoli(0, 1, 3);
+        circuit.toffoli(3, 4, 0);
+        circuit.toffoli(1, 4, 3);
+        circuit.apply_u_f(|x| !x, 3, 1);
+        circuit.cz(2, 1);
+        circuit.rx(-0.8, 2);
+        let simulation = SchrodingerFeynmanSimulation::new(&circuit, 2);
+        let mut state_vector_simulation = QuantumSimulation::new(5, 0);
+        circuit.run(&mut state_vector_simulation);
+
+        let basis_states: Vec<Vec<bool>> = (0..32)
+            .map(|i| (0..5).map(|q| (i >> q) & 1 == 1).collect())
+            .collect();
+        let amplitudes = simulation.amplitudes(&basis_states);
+        for (amplitude, expected_amplitude) in amplitudes
+            .iter()
+            .zip(state_vector_simulation.amplitudes().iter())
+        {
+            assert!((amplitude - expected_amplitude).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn toffoli_across_cut_matches_state_vector() {
+        use rand::{Rng, SeedableRng};
+        use rand_chacha::ChaCha12Rng;
+
+        // The controls below the cut with the target above it, and the reverse.
+        for (c0, c1, t) in [(0, 1, 2), (2, 3, 0)] {
+            // A random state of all the amplitudes nonzero before the Toffoli gate.
+            let mut rng = ChaCha12Rng::seed_from_u64(0);
+            let mut circuit = Circuit::new(4);
+            for qubit_number in 0..4 {
+                circuit.ry(rng.gen_range(0.3..3.0), qubit_number);
+                circuit.rz(rng.gen_range(0.3..3.0), qubit_number);
+            }
+            circuit.cnot(1, 2);
+            circuit.toffoli(c0, c1, t);
+            let simulation = SchrodingerFeynmanSimulation::new(&circuit, 2);
+            let mut state_vector_simulation = QuantumSimulation::new(4, 0);
+            circuit.run(&mut state_vector_simulation);
+
+            let basis_states: Vec<Vec<bool>> = (0..16)
+                .map(|i| (0..4).map(|q| (i >> q) & 1 == 1).collect())
+                .collect();
+            let amplitudes = simulation.amplitudes(&basis_states);
+            for (amplitude, expected_amplitude) in amplitudes
+                .iter()
+                .zip(state_vector_simulation.amplitudes().iter())
+            {
+                assert!((amplitude - expected_amplitude).norm() < 1e-12);
+            }
+        }
+    }
+}
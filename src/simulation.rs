@@ -1,9 +1,112 @@
 /*
 Trait for a quantum computer simulation with a quantum complete set of gates.
 
+The backend of a simulation can be chosen at run time, the simulations of all the backends
+being used through the trait objects.
+
+The qubits of a gate have to be in the simulation and distinct, as a gate of a repeated qubit,
+such as CNOT(q, q), is not unitary. The simulations of every backend panic with the message of
+GateError on such gates, which the circuits return to be checked before they are run and the
+circuit parsers return as parse errors.
+
 Copyright © 2024 AlgoHertz. All rights reserved.
 */
 
+use std::fmt;
+
+use crate::circuit::Gate;
+use crate::density_matrix_simulation::DensityMatrixSimulation;
+use crate::mps_simulation::MpsSimulation;
+use crate::stabilizer_simulation::StabilizerSimulation;
+use crate::state_vector_simulation::QuantumSimulation;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    StateVector,
+    // Only for the Clifford gates.
+    Stabilizer,
+    DensityMatrix,
+    Mps,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 4] = [
+        Backend::StateVector,
+        Backend::Stabilizer,
+        Backend::DensityMatrix,
+        Backend::Mps,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Backend::StateVector => "statevector",
+            Backend::Stabilizer => "stabilizer",
+            Backend::DensityMatrix => "densitymatrix",
+            Backend::Mps => "mps",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Backend> {
+        Backend::ALL
+            .into_iter()
+            .find(|backend| backend.name() == name)
+    }
+}
+
+// The error of the qubits of a gate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateError {
+    // The qubit number is not less than the number of qubits.
+    QubitOutOfRange {
+        qubit_number: usize,
+        qubit_count: usize,
+    },
+    // The qubit is an argument of the gate more than once.
+    RepeatedQubit {
+        qubit_number: usize,
+    },
+}
+
+impl fmt::Display for GateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GateError::QubitOutOfRange { qubit_count, .. } => write!(
+                f,
+                "The qubit number has to be less than the number of qubits {}.",
+                qubit_count
+            ),
+            GateError::RepeatedQubit { qubit_number } => write!(
+                f,
+                "The qubit arguments of a gate have to be distinct, the qubit {} is repeated.",
+                qubit_number
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GateError {}
+
+// Check that the qubits of a gate are in the simulation of the number of qubits and distinct.
+pub fn check_qubits(qubit_numbers: &[usize], qubit_count: usize) -> Result<(), GateError> {
+    for (i, &qubit_number) in qubit_numbers.iter().enumerate() {
+        if qubit_number >= qubit_count {
+            return Err(GateError::QubitOutOfRange {
+                qubit_number,
+                qubit_count,
+            });
+        }
+        if qubit_numbers[..i].contains(&qubit_number) {
+            return Err(GateError::RepeatedQubit { qubit_number });
+        }
+    }
+    Ok(())
+}
+
+// Check that the qubits of a gate are distinct, when the number of qubits is not known yet.
+pub fn check_distinct_qubits(qubit_numbers: &[usize]) -> Result<(), GateError> {
+    check_qubits(qubit_numbers, usize::MAX)
+}
+
 pub trait Simulation {
     // Initialize the qubits into the ground state |0⟩.
     fn reset(&mut self);
@@ -13,6 +116,35 @@ pub trait Simulation {
     fn measure_all(&mut self) -> Vec<bool>;
     // Measure the selected qubits in the Z-basis.
     fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool>;
+    // Measure the qubit in the X-basis, true for |−⟩, leaving it in the measured basis state.
+    fn measure_x(&mut self, qubit_number: usize) -> bool {
+        self.hadamard(qubit_number);
+        let value = self.measure(vec![qubit_number])[0];
+        self.hadamard(qubit_number);
+        value
+    }
+    // Measure the qubit in the Y-basis, true for |−i⟩, leaving it in the measured basis state.
+    // The inverse of S is S^3, so that the stabilizer simulations can measure in the Y-basis.
+    fn measure_y(&mut self, qubit_number: usize) -> bool {
+        for _ in 0..3 {
+            self.s(qubit_number);
+        }
+        self.hadamard(qubit_number);
+        let value = self.measure(vec![qubit_number])[0];
+        self.hadamard(qubit_number);
+        self.s(qubit_number);
+        value
+    }
+    // Measure the qubit along the direction of the polar angle theta and the azimuthal angle phi
+    // on the Bloch sphere, true for the opposite direction, leaving it in the measured state.
+    fn measure_along(&mut self, theta: f64, phi: f64, qubit_number: usize) -> bool {
+        self.rz(-phi, qubit_number);
+        self.ry(-theta, qubit_number);
+        let value = self.measure(vec![qubit_number])[0];
+        self.ry(theta, qubit_number);
+        self.rz(phi, qubit_number);
+        value
+    }
 
     // 1-qubit gates.
     fn pauli_x(&mut self, qubit_number: usize);
@@ -21,10 +153,16 @@ pub trait Simulation {
     fn hadamard(&mut self, qubit_number: usize);
     fn s(&mut self, qubit_number: usize);
     fn t(&mut self, qubit_number: usize);
+    // Rotations by the angle theta around the X, Y, and Z axes.
+    fn rx(&mut self, theta: f64, qubit_number: usize);
+    fn ry(&mut self, theta: f64, qubit_number: usize);
+    fn rz(&mut self, theta: f64, qubit_number: usize);
 
     // 2-qubit gates.
     fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize);
     fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize);
+    // Controlled phase gate by the angle theta.
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize);
     fn swap(&mut self, qubit_number0: usize, qubit_number1: usize);
     fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize);
 
@@ -35,4 +173,118 @@ pub trait Simulation {
         control_qubit_number1: usize,
         target_qubit_number: usize,
     );
+
+    // Apply the diagonal gates Z, S, T, RZ, CZ, and CPHASE, whose phases commute, so that
+    // a simulation can merge them into one update of the state.
+    fn apply_phases(&mut self, gates: &[Gate]) {
+        for gate in gates.iter() {
+            match *gate {
+                Gate::PauliZ(q) => self.pauli_z(q),
+                Gate::S(q) => self.s(q),
+                Gate::T(q) => self.t(q),
+                Gate::Rz(theta, q) => self.rz(theta, q),
+                Gate::Cz(q0, q1) => self.cz(q0, q1),
+                Gate::Cphase(theta, q0, q1) => self.cphase(theta, q0, q1),
+                _ => panic!("The gate {} is not diagonal.", gate.name()),
+            }
+        }
+    }
+}
+
+impl dyn Simulation {
+    // A simulation of the backend for the number of qubits, in the ground state.
+    pub fn with_backend(
+        backend: Backend,
+        qubit_count: usize,
+        rnd_seed: u64,
+    ) -> Box<dyn Simulation> {
+        match backend {
+            Backend::StateVector => Box::new(QuantumSimulation::new(qubit_count, rnd_seed)),
+            Backend::Stabilizer => Box::new(StabilizerSimulation::new(qubit_count, rnd_seed)),
+            Backend::DensityMatrix => Box::new(DensityMatrixSimulation::new(qubit_count, rnd_seed)),
+            Backend::Mps => Box::new(MpsSimulation::new(qubit_count, rnd_seed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Circuit;
+
+    #[test]
+    fn backends_agree_on_bell_state() {
+        let mut circuit = Circuit::new(2);
+        circuit.push(Gate::Hadamard(0));
+        circuit.push(Gate::Cnot(0, 1));
+        for backend in Backend::ALL {
+            assert_eq!(Backend::from_name(backend.name()), Some(backend));
+            let mut simulation = <dyn Simulation>::with_backend(backend, 2, 7);
+            for _ in 0..10 {
+                simulation.reset();
+                circuit.run(simulation.as_mut());
+                let values = simulation.measure_all();
+                assert_eq!(values[0], values[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn measurements_in_rotated_bases() {
+        // The eigenstates of X and Y are measured with certainty, and left in the measured state.
+        let mut simulation = QuantumSimulation::new(1, 0);
+        simulation.hadamard(0);
+        simulation.pauli_z(0);
+        assert!(simulation.measure_x(0) && simulation.measure_x(0));
+        simulation.reset();
+        simulation.hadamard(0);
+        simulation.s(0);
+        assert!(!simulation.measure_y(0));
+        assert!(!simulation.measure_along(
+            std::f64::consts::FRAC_PI_2,
+            std::f64::consts::FRAC_PI_2,
+            0
+        ));
+        assert!(simulation.measure_along(
+            std::f64::consts::FRAC_PI_2,
+            -std::f64::consts::FRAC_PI_2,
+            0
+        ));
+    }
+
+    #[test]
+    fn qubits_are_checked() {
+        assert_eq!(check_qubits(&[2, 0, 1], 3), Ok(()));
+        assert_eq!(
+            check_qubits(&[0, 3], 3),
+            Err(GateError::QubitOutOfRange {
+                qubit_number: 3,
+                qubit_count: 3
+            })
+        );
+        assert_eq!(
+            check_qubits(&[1, 0, 1], 3),
+            Err(GateError::RepeatedQubit { qubit_number: 1 })
+        );
+        assert_eq!(check_distinct_qubits(&[4, 7]), Ok(()));
+        assert_eq!(
+            check_distinct_qubits(&[4, 7, 4]),
+            Err(GateError::RepeatedQubit { qubit_number: 4 })
+        );
+    }
+
+    #[test]
+    fn backends_panic_with_gate_error() {
+        for backend in Backend::ALL {
+            for gate in [Gate::Cnot(1, 1), Gate::Swap(0, 0), Gate::Cz(0, 2)] {
+                let mut simulation = <dyn Simulation>::with_backend(backend, 2, 0);
+                let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    gate.apply(simulation.as_mut())
+                }))
+                .unwrap_err();
+                let expected = check_qubits(&gate.qubits(), 2).unwrap_err().to_string();
+                assert_eq!(payload.downcast_ref::<String>(), Some(&expected));
+            }
+        }
+    }
 }
@@ -0,0 +1,336 @@
+/*
+Quantum state vector simulation storing only the nonzero amplitudes.
+
+The amplitudes are a hash map from the index of a basis state to its amplitude, so the memory and
+the time of a gate are proportional to the number of the nonzero amplitudes instead of 2^n. The
+circuits like the oracles of Deutsch-Jozsa or Bernstein-Vazirani on the basis states, and the
+arithmetic on classical inputs, keep only a few amplitudes for many layers, and the number of the
+qubits is limited only by the bits of the index. The amplitudes whose probability falls below
+PRUNED_PROBABILITY by cancellation are removed.
+
+When the circuit spreads the state over a large part of the basis, into_dense continues the
+simulation as a QuantumSimulation with the same amplitudes and random number generator.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::HashMap;
+
+use num_complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::gate::{
+    phase_matrix, rx_matrix, ry_matrix, rz_matrix, Matrix, HADAMARD_MATRIX, PAULI_X_MATRIX,
+    PAULI_Y_MATRIX, PAULI_Z_MATRIX, S_MATRIX, T_MATRIX,
+};
+use crate::simulation::{check_qubits, Simulation};
+use crate::state_vector_simulation::{self, QuantumSimulation};
+
+pub const MAX_QUBIT_COUNT: usize = usize::BITS as usize - 1;
+
+// The probability of the amplitudes treated as zero, far below the rounding errors of a normalized state.
+const PRUNED_PROBABILITY: f64 = 1e-30;
+
+#[derive(Debug)]
+pub struct SparseSimulation {
+    qubit_count: usize,
+    amplitudes: HashMap<usize, Complex<f64>>,
+    rng: ChaCha12Rng,
+}
+
+impl SparseSimulation {
+    pub fn new(qubit_count: usize, rnd_seed: u64) -> SparseSimulation {
+        assert!(
+            qubit_count <= MAX_QUBIT_COUNT,
+            "The number of qubits in the simulation cannot exceed {}.",
+            MAX_QUBIT_COUNT
+        );
+
+        let mut simulation = SparseSimulation {
+            qubit_count,
+            amplitudes: HashMap::new(),
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    // The amplitude of the basis state, indexed by the bits of the qubit numbers.
+    pub fn amplitude(&self, state_index: usize) -> Complex<f64> {
+        self.amplitudes
+            .get(&state_index)
+            .copied()
+            .unwrap_or(Complex::new(0.0, 0.0))
+    }
+
+    // The number of the stored nonzero amplitudes.
+    pub fn nonzero_count(&self) -> usize {
+        self.amplitudes.len()
+    }
+
+    // Continue the simulation with the full state vector, once the state is no longer sparse.
+    pub fn into_dense(self) -> QuantumSimulation {
+        assert!(
+            self.qubit_count <= state_vector_simulation::MAX_QUBIT_COUNT,
+            "The number of qubits in the simulation cannot exceed {}.",
+            state_vector_simulation::MAX_QUBIT_COUNT
+        );
+        let mut amplitudes = vec![Complex::new(0.0, 0.0); 1 << self.qubit_count];
+        for (&i, &amplitude) in self.amplitudes.iter() {
+            amplitudes[i] = amplitude;
+        }
+        QuantumSimulation::with_amplitudes(amplitudes, self.rng)
+    }
+
+    // The basis states are sampled in the order of their indices, so that the measurements
+    // of a seed do not depend on the order of the hash map.
+    fn choose_state(&mut self) -> usize {
+        let mut indices: Vec<usize> = self.amplitudes.keys().copied().collect();
+        indices.sort_unstable();
+        let random_number = self.rng.gen::<f64>();
+        let mut accumulated_probability = 0.0;
+        for &i in indices.iter() {
+            accumulated_probability += self.amplitudes[&i].norm_sqr();
+            if random_number <= accumulated_probability {
+                return i;
+            }
+        }
+        *indices.last().unwrap()
+    }
+
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    // Apply the matrix to the target qubit where the control qubits have the given values.
+    // Each amplitude contributes only to the nonzero entries of its column of the matrix.
+    fn apply_matrix(&mut self, matrix: &Matrix, controls: &[(usize, bool)], target: usize) {
+        let mut qubit_numbers: Vec<usize> = controls.iter().map(|&(control, _)| control).collect();
+        qubit_numbers.push(target);
+        self.check_qubit_numbers(&qubit_numbers);
+
+        let (mut control_mask, mut control_value) = (0, 0);
+        for &(control, value) in controls.iter() {
+            control_mask |= 1 << control;
+            if value {
+                control_value |= 1 << control;
+            }
+        }
+        let target_mask = 1 << target;
+        let zero = Complex::new(0.0, 0.0);
+        let mut amplitudes = HashMap::with_capacity(2 * self.amplitudes.len());
+        for (&i, &amplitude) in self.amplitudes.iter() {
+            if i & control_mask != control_value {
+                *amplitudes.entry(i).or_insert(zero) += amplitude;
+                continue;
+            }
+            let (column0, column1) = if i & target_mask == 0 {
+                (matrix[0], matrix[2])
+            } else {
+                (matrix[1], matrix[3])
+            };
+            if column0 != zero {
+                *amplitudes.entry(i & !target_mask).or_insert(zero) += column0 * amplitude;
+            }
+            if column1 != zero {
+                *amplitudes.entry(i | target_mask).or_insert(zero) += column1 * amplitude;
+            }
+        }
+        amplitudes.retain(|_, amplitude| amplitude.norm_sqr() >= PRUNED_PROBABILITY);
+        self.amplitudes = amplitudes;
+    }
+}
+
+impl Simulation for SparseSimulation {
+    fn reset(&mut self) {
+        self.amplitudes.clear();
+        self.amplitudes.insert(0, Complex::new(1.0, 0.0));
+    }
+
+    // Measure all the qubits in the Z-basis.
+    fn measure_all(&mut self) -> Vec<bool> {
+        let measured_state_index = self.choose_state();
+        self.amplitudes.clear();
+        self.amplitudes
+            .insert(measured_state_index, Complex::new(1.0, 0.0));
+
+        (0..self.qubit_count)
+            .map(|qubit_number| measured_state_index & (1 << qubit_number) > 0)
+            .collect()
+    }
+
+    // Measure the selected qubits in the Z-basis.
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
+        for &qubit_number in qubit_numbers.iter() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+        }
+
+        let measured_state_index = self.choose_state();
+        let mask = qubit_numbers
+            .iter()
+            .fold(0, |mask, &qubit_number| mask | 1 << qubit_number);
+        let value = measured_state_index & mask;
+        self.amplitudes.retain(|&i, _| i & mask == value);
+        let accumulated_probability: f64 = self.amplitudes.values().map(|a| a.norm_sqr()).sum();
+        let bump_amplitude_factor = (1.0 / accumulated_probability).sqrt();
+        for amplitude in self.amplitudes.values_mut() {
+            *amplitude *= bump_amplitude_factor;
+        }
+
+        qubit_numbers
+            .iter()
+            .map(|&qubit_number| measured_state_index & (1 << qubit_number) > 0)
+            .collect()
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_X_MATRIX, &[], qubit_number);
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Y_MATRIX, &[], qubit_number);
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Z_MATRIX, &[], qubit_number);
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.apply_matrix(&HADAMARD_MATRIX, &[], qubit_number);
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.apply_matrix(&S_MATRIX, &[], qubit_number);
+    }
+
+    fn t(&mut self, qubit_number: usize) {
+        self.apply_matrix(&T_MATRIX, &[], qubit_number);
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rx_matrix(theta), &[], qubit_number);
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&ry_matrix(theta), &[], qubit_number);
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rz_matrix(theta), &[], qubit_number);
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_matrix(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_matrix(
+            &PAULI_Z_MATRIX,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        let matrix = phase_matrix(Complex::new(0.0, theta).exp());
+        self.apply_matrix(
+            &matrix,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    // The swap only exchanges the bits of the indices.
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        let mask = (1 << qubit_number0) | (1 << qubit_number1);
+        self.amplitudes = self
+            .amplitudes
+            .drain()
+            .map(|(i, amplitude)| {
+                let bits = i & mask;
+                if bits == 0 || bits == mask {
+                    (i, amplitude)
+                } else {
+                    (i ^ mask, amplitude)
+                }
+            })
+            .collect();
+    }
+
+    // U_f flips the qubit 0 when the qubit 1 is 0 if f(true), and when the qubit 1 is 1 if not f(false).
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        if f(true) {
+            self.apply_matrix(&PAULI_X_MATRIX, &[(qubit_number1, false)], qubit_number0);
+        }
+        if !f(false) {
+            self.apply_matrix(&PAULI_X_MATRIX, &[(qubit_number1, true)], qubit_number0);
+        }
+    }
+
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        self.apply_matrix(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number0, true), (control_qubit_number1, true)],
+            target_qubit_number,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparse_gates_match_dense_gates() {
+        let gates: [fn(&mut dyn Simulation); 9] = [
+            |s| s.hadamard(0),
+            |s| s.ry(0.7, 2),
+            |s| s.cnot(0, 1),
+            |s| s.cphase(0.9, 1, 2),
+            |s| s.swap(0, 2),
+            |s| s.apply_u_f(|x| !x, 2, 0),
+            |s| s.toffoli(0, 2, 1),
+            |s| s.hadamard(0),
+            |s| s.hadamard(0),
+        ];
+        let mut simulation = SparseSimulation::new(3, 0);
+        let mut dense_simulation = QuantumSimulation::new(3, 0);
+        for gate in gates.iter() {
+            gate(&mut simulation);
+            gate(&mut dense_simulation);
+        }
+        assert!((0..8)
+            .all(|i| (simulation.amplitude(i) - dense_simulation.amplitudes()[i]).norm() < 1e-12));
+        let amplitudes: Vec<Complex<f64>> = (0..8).map(|i| simulation.amplitude(i)).collect();
+        assert_eq!(simulation.into_dense().amplitudes(), amplitudes);
+
+        // An oracle on a basis state of many qubits keeps a single amplitude.
+        let mut simulation = SparseSimulation::new(60, 0);
+        for qubit_number in 0..59 {
+            simulation.hadamard(qubit_number);
+            simulation.hadamard(qubit_number);
+            simulation.cnot(qubit_number, 59);
+        }
+        assert_eq!(simulation.nonzero_count(), 1);
+        assert_eq!(simulation.measure_all(), vec![false; 60]);
+    }
+}
@@ -0,0 +1,409 @@
+/*
+Quantum stabilizer simulation of the Clifford circuits.
+
+The state of n qubits is stored as the tableau of Aaronson and Gottesman: n destabilizer and
+n stabilizer Pauli operators, each with its X and Z bits for every qubit and its sign. The state
+is the one stabilized by the stabilizers, and the destabilizers complete them into a basis of
+the Pauli group which makes a measurement take O(n^2) instead of O(n^3). The Clifford gates
+H, S, CNOT, and the ones composed of them map the Pauli operators to Pauli operators, and update
+the tableau in O(n), so the memory is O(n^2) bits and thousands of qubits can be simulated.
+
+The rotations are Clifford gates by the multiples of π/2, and the controlled phase by the
+multiples of π. The T and Toffoli gates and the other angles are not Clifford gates and panic.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::f64::consts::FRAC_PI_2;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::circuit::Gate;
+use crate::simulation::{check_qubits, Simulation};
+
+// The tolerance of an angle being a multiple of π/2.
+const ANGLE_TOLERANCE: f64 = 1e-12;
+
+// Whether the gate is a Clifford gate, which the stabilizer simulation can apply.
+pub fn is_clifford_gate(gate: &Gate) -> bool {
+    match *gate {
+        Gate::T(_) | Gate::Toffoli(_, _, _) => false,
+        Gate::Rx(theta, _) | Gate::Ry(theta, _) | Gate::Rz(theta, _) => {
+            quarter_turns(theta).is_some()
+        }
+        Gate::Cphase(theta, _, _) => quarter_turns(theta).is_some_and(|k| k % 2 == 0),
+        _ => true,
+    }
+}
+
+// The number of quarter turns, modulo 4, of an angle which is a multiple of π/2.
+pub(crate) fn quarter_turns(theta: f64) -> Option<usize> {
+    let turns = theta / FRAC_PI_2;
+    if (turns - turns.round()).abs() > ANGLE_TOLERANCE {
+        return None;
+    }
+    Some((turns.round() as i64).rem_euclid(4) as usize)
+}
+
+#[derive(Clone, Debug)]
+pub struct StabilizerSimulation {
+    qubit_count: usize,
+    // The number of the words of the bits of a row.
+    word_count: usize,
+    // The X and Z bits of the 2n rows and a scratch row, the destabilizers before the stabilizers.
+    x: Vec<u64>,
+    z: Vec<u64>,
+    // Whether the sign of each row is negative.
+    signs: Vec<bool>,
+    rng: ChaCha12Rng,
+}
+
+impl StabilizerSimulation {
+    pub fn new(qubit_count: usize, rnd_seed: u64) -> StabilizerSimulation {
+        let word_count = qubit_count.div_ceil(64).max(1);
+        let row_count = 2 * qubit_count + 1;
+        let mut simulation = StabilizerSimulation {
+            qubit_count,
+            word_count,
+            x: vec![0; row_count * word_count],
+            z: vec![0; row_count * word_count],
+            signs: vec![false; row_count],
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The stabilizers of the state as signed Pauli strings, the qubit 0 first, such as "+XXI".
+    pub fn stabilizers(&self) -> Vec<String> {
+        (self.qubit_count..2 * self.qubit_count)
+            .map(|row| {
+                let sign = if self.signs[row] { '-' } else { '+' };
+                let paulis =
+                    (0..self.qubit_count).map(|q| match (self.x_bit(row, q), self.z_bit(row, q)) {
+                        (false, false) => 'I',
+                        (true, false) => 'X',
+                        (true, true) => 'Y',
+                        (false, true) => 'Z',
+                    });
+                std::iter::once(sign).chain(paulis).collect()
+            })
+            .collect()
+    }
+
+    fn x_bit(&self, row: usize, qubit_number: usize) -> bool {
+        (self.x[row * self.word_count + qubit_number / 64] >> (qubit_number % 64)) & 1 == 1
+    }
+
+    fn z_bit(&self, row: usize, qubit_number: usize) -> bool {
+        (self.z[row * self.word_count + qubit_number / 64] >> (qubit_number % 64)) & 1 == 1
+    }
+
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    // Update the sign, the X bits, and the Z bits of the qubits of every row with the function
+    // of the sign and the bits of the qubits in their order.
+    fn update_rows<F>(&mut self, qubit_numbers: &[usize], update: F)
+    where
+        F: Fn(&mut bool, &mut [bool], &mut [bool]),
+    {
+        self.check_qubit_numbers(qubit_numbers);
+        let mut x_bits = vec![false; qubit_numbers.len()];
+        let mut z_bits = vec![false; qubit_numbers.len()];
+        for row in 0..2 * self.qubit_count {
+            for (k, &q) in qubit_numbers.iter().enumerate() {
+                x_bits[k] = self.x_bit(row, q);
+                z_bits[k] = self.z_bit(row, q);
+            }
+            update(&mut self.signs[row], &mut x_bits, &mut z_bits);
+            for (k, &q) in qubit_numbers.iter().enumerate() {
+                let (word, bit) = (row * self.word_count + q / 64, 1u64 << (q % 64));
+                self.x[word] = if x_bits[k] {
+                    self.x[word] | bit
+                } else {
+                    self.x[word] & !bit
+                };
+                self.z[word] = if z_bits[k] {
+                    self.z[word] | bit
+                } else {
+                    self.z[word] & !bit
+                };
+            }
+        }
+    }
+
+    // Multiply the row h by the row i, the exponent of the phase i of the product
+    // being the sum of the exponents of the products of the Pauli operators of the qubits.
+    fn multiply_row(&mut self, h: usize, i: usize) {
+        let (h_start, i_start) = (h * self.word_count, i * self.word_count);
+        let mut exponent: i64 = 2 * (self.signs[h] as i64 + self.signs[i] as i64);
+        for w in 0..self.word_count {
+            let (x1, z1) = (self.x[i_start + w], self.z[i_start + w]);
+            let (x2, z2) = (self.x[h_start + w], self.z[h_start + w]);
+            // The products Y * P, X * P, and Z * P with the phase i or -i.
+            let plus = (x1 & z1 & z2 & !x2) | (x1 & !z1 & z2 & x2) | (!x1 & z1 & x2 & !z2);
+            let minus = (x1 & z1 & x2 & !z2) | (x1 & !z1 & z2 & !x2) | (!x1 & z1 & x2 & z2);
+            exponent += plus.count_ones() as i64 - minus.count_ones() as i64;
+            self.x[h_start + w] = x1 ^ x2;
+            self.z[h_start + w] = z1 ^ z2;
+        }
+        self.signs[h] = exponent.rem_euclid(4) == 2;
+    }
+
+    fn copy_row(&mut self, target: usize, source: usize) {
+        let (t, s) = (target * self.word_count, source * self.word_count);
+        self.x.copy_within(s..s + self.word_count, t);
+        self.z.copy_within(s..s + self.word_count, t);
+        self.signs[target] = self.signs[source];
+    }
+
+    fn clear_row(&mut self, row: usize) {
+        let start = row * self.word_count;
+        self.x[start..start + self.word_count].fill(0);
+        self.z[start..start + self.word_count].fill(0);
+        self.signs[row] = false;
+    }
+
+    // Measure the qubit in the Z-basis. The outcome is random if a stabilizer anticommutes
+    // with Z on the qubit, which is then replaced by ±Z, and otherwise it is the sign of Z
+    // as a product of the stabilizers, accumulated in the scratch row.
+    fn measure_qubit(&mut self, qubit_number: usize) -> bool {
+        let n = self.qubit_count;
+        let anticommuting_row = (n..2 * n).find(|&row| self.x_bit(row, qubit_number));
+        if let Some(p) = anticommuting_row {
+            for row in 0..2 * n {
+                if row != p && self.x_bit(row, qubit_number) {
+                    self.multiply_row(row, p);
+                }
+            }
+            self.copy_row(p - n, p);
+            self.clear_row(p);
+            let (word, bit) = (p * self.word_count + qubit_number / 64, qubit_number % 64);
+            self.z[word] |= 1 << bit;
+            self.signs[p] = self.rng.gen::<bool>();
+            return self.signs[p];
+        }
+        let scratch = 2 * n;
+        self.clear_row(scratch);
+        for row in 0..n {
+            if self.x_bit(row, qubit_number) {
+                self.multiply_row(scratch, row + n);
+            }
+        }
+        self.signs[scratch]
+    }
+
+    fn apply_quarter_turns_z(&mut self, theta: f64, qubit_number: usize) {
+        let turns = quarter_turns(theta)
+            .unwrap_or_else(|| panic!("The rotation by {} is not a Clifford gate.", theta));
+        for _ in 0..turns {
+            self.s(qubit_number);
+        }
+    }
+}
+
+impl Simulation for StabilizerSimulation {
+    // The stabilizers Z_i of the ground state, and the destabilizers X_i.
+    fn reset(&mut self) {
+        self.x.fill(0);
+        self.z.fill(0);
+        self.signs.fill(false);
+        for q in 0..self.qubit_count {
+            let bit = 1u64 << (q % 64);
+            self.x[q * self.word_count + q / 64] = bit;
+            self.z[(q + self.qubit_count) * self.word_count + q / 64] = bit;
+        }
+    }
+
+    // Measure all the qubits in the Z-basis.
+    fn measure_all(&mut self) -> Vec<bool> {
+        (0..self.qubit_count)
+            .map(|qubit_number| self.measure_qubit(qubit_number))
+            .collect()
+    }
+
+    // Measure the selected qubits in the Z-basis.
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
+        for &qubit_number in qubit_numbers.iter() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+        }
+        qubit_numbers
+            .iter()
+            .map(|&qubit_number| self.measure_qubit(qubit_number))
+            .collect()
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.update_rows(&[qubit_number], |sign, _, z| *sign ^= z[0]);
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.update_rows(&[qubit_number], |sign, x, z| *sign ^= x[0] ^ z[0]);
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.update_rows(&[qubit_number], |sign, x, _| *sign ^= x[0]);
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.update_rows(&[qubit_number], |sign, x, z| {
+            *sign ^= x[0] & z[0];
+            std::mem::swap(&mut x[0], &mut z[0]);
+        });
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.update_rows(&[qubit_number], |sign, x, z| {
+            *sign ^= x[0] & z[0];
+            z[0] ^= x[0];
+        });
+    }
+
+    fn t(&mut self, _qubit_number: usize) {
+        panic!("The T gate is not a Clifford gate.");
+    }
+
+    // The rotations by the multiples of π/2 are the powers of S, up to a global phase,
+    // conjugated by H for the X axis and by S for the Y axis.
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.hadamard(qubit_number);
+        self.apply_quarter_turns_z(theta, qubit_number);
+        self.hadamard(qubit_number);
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_quarter_turns_z(-FRAC_PI_2, qubit_number);
+        self.rx(theta, qubit_number);
+        self.s(qubit_number);
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_quarter_turns_z(theta, qubit_number);
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.update_rows(
+            &[control_qubit_number, target_qubit_number],
+            |sign, x, z| {
+                *sign ^= x[0] & z[1] & !(x[1] ^ z[0]);
+                x[1] ^= x[0];
+                z[0] ^= z[1];
+            },
+        );
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.hadamard(target_qubit_number);
+        self.cnot(control_qubit_number, target_qubit_number);
+        self.hadamard(target_qubit_number);
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        match quarter_turns(theta) {
+            Some(0) => self.check_qubit_numbers(&[control_qubit_number, target_qubit_number]),
+            Some(2) => self.cz(control_qubit_number, target_qubit_number),
+            _ => panic!("The controlled phase by {} is not a Clifford gate.", theta),
+        }
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.update_rows(&[qubit_number0, qubit_number1], |_, x, z| {
+            x.swap(0, 1);
+            z.swap(0, 1);
+        });
+    }
+
+    // U_f flips the qubit 0 when the qubit 1 is 0 if f(true), and when the qubit 1 is 1 if not f(false).
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        if f(true) {
+            self.pauli_x(qubit_number1);
+            self.cnot(qubit_number1, qubit_number0);
+            self.pauli_x(qubit_number1);
+        }
+        if !f(false) {
+            self.cnot(qubit_number1, qubit_number0);
+        }
+    }
+
+    fn toffoli(
+        &mut self,
+        _control_qubit_number0: usize,
+        _control_qubit_number1: usize,
+        _target_qubit_number: usize,
+    ) {
+        panic!("The Toffoli gate is not a Clifford gate.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn clifford_circuits_match_state_vector() {
+        let gates = [
+            Gate::Hadamard(0),
+            Gate::Cnot(0, 1),
+            Gate::S(1),
+            Gate::Hadamard(2),
+            Gate::Cz(2, 0),
+            Gate::Ry(FRAC_PI_2, 3),
+            Gate::Swap(3, 1),
+            Gate::PauliY(2),
+            Gate::Rx(-FRAC_PI_2, 0),
+            Gate::UF(|x| !x, 1, 3),
+            Gate::Cphase(std::f64::consts::PI, 1, 2),
+        ];
+        assert!(gates.iter().all(is_clifford_gate));
+        // The probabilities of the outcomes of the stabilizer simulation match the state vector.
+        let run_count = 2000;
+        let mut counts = [0usize; 16];
+        for seed in 0..run_count {
+            let mut simulation = StabilizerSimulation::new(4, seed);
+            for gate in gates.iter() {
+                gate.apply(&mut simulation);
+            }
+            let measured_states = simulation.measure_all();
+            // The measured qubits keep their values.
+            assert_eq!(simulation.measure_all(), measured_states);
+            let index = (0..4)
+                .filter(|&q| measured_states[q])
+                .map(|q| 1 << q)
+                .sum::<usize>();
+            counts[index] += 1;
+        }
+        let mut state_vector_simulation = QuantumSimulation::new(4, 0);
+        for gate in gates.iter() {
+            gate.apply(&mut state_vector_simulation);
+        }
+        for (count, amplitude) in counts
+            .iter()
+            .zip(state_vector_simulation.amplitudes().iter())
+        {
+            assert!((*count as f64 / run_count as f64 - amplitude.norm_sqr()).abs() < 0.05);
+        }
+
+        let mut simulation = StabilizerSimulation::new(2, 0);
+        simulation.hadamard(0);
+        simulation.cnot(0, 1);
+        simulation.pauli_z(0);
+        assert_eq!(simulation.stabilizers(), vec!["-XX", "+ZZ"]);
+        assert!(!is_clifford_gate(&Gate::T(0)));
+    }
+}
@@ -0,0 +1,136 @@
+/*
+Circuits preparing the entangled states from |0…0⟩.
+
+The GHZ state (|0…0⟩ + |1…1⟩) / √2 of n qubits is prepared by a Hadamard gate and a chain of
+CNOT gates.
+
+The Dicke state |D(n, k)⟩ is the equal superposition of the C(n, k) basis states of n qubits
+with k of them in the state |1⟩, and the W state is |D(n, 1)⟩. The circuit of Bärtschi and
+Eidenbenz (2019) starts from the basis state with the k highest qubits |1⟩ and moves the ones
+down the register by the split-and-cyclic-shift unitaries, which share a one with the qubit
+below by the controlled and the doubly controlled RY rotations, using O(kn) gates and no
+ancilla qubits.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use crate::circuit::Circuit;
+
+// The GHZ state (1/sqrt(2))*(|0...0⟩ + |1...1⟩) of n qubits.
+pub fn ghz(qubit_count: usize) -> Circuit {
+    assert!(qubit_count > 0, "The GHZ state needs at least 1 qubit.");
+    let mut circuit = Circuit::new(qubit_count);
+    circuit.hadamard(0);
+    for qubit_number in 1..qubit_count {
+        circuit.cnot(qubit_number - 1, qubit_number);
+    }
+    circuit
+}
+
+// The rotation RY(θ) of the target qubit if the control qubit is |1⟩.
+fn controlled_ry(circuit: &mut Circuit, theta: f64, control: usize, target: usize) {
+    circuit.ry(theta / 2.0, target);
+    circuit.cnot(control, target);
+    circuit.ry(-theta / 2.0, target);
+    circuit.cnot(control, target);
+}
+
+// The rotation RY(θ) of the target qubit if both control qubits are |1⟩, by the angle
+// θ/2 (c0 + c1 - (c0 ⊕ c1)) = θ c0 c1.
+fn doubly_controlled_ry(
+    circuit: &mut Circuit,
+    theta: f64,
+    control0: usize,
+    control1: usize,
+    target: usize,
+) {
+    controlled_ry(circuit, theta / 2.0, control1, target);
+    circuit.cnot(control0, control1);
+    controlled_ry(circuit, -theta / 2.0, control1, target);
+    circuit.cnot(control0, control1);
+    controlled_ry(circuit, theta / 2.0, control0, target);
+}
+
+// The split-and-cyclic-shift unitary of the qubits top - k..=top of the register of the Dicke
+// state, for the top + 1 lowest qubits holding at most k ones.
+fn split_and_cyclic_shift(circuit: &mut Circuit, top: usize, k: usize) {
+    let n = (top + 1) as f64;
+    circuit.cnot(top - 1, top);
+    controlled_ry(circuit, 2.0 * (1.0 / n).sqrt().acos(), top, top - 1);
+    circuit.cnot(top - 1, top);
+    for l in 2..=k {
+        circuit.cnot(top - l, top);
+        doubly_controlled_ry(
+            circuit,
+            2.0 * (l as f64 / n).sqrt().acos(),
+            top,
+            top - l + 1,
+            top - l,
+        );
+        circuit.cnot(top - l, top);
+    }
+}
+
+// The Dicke state of n qubits with k ones.
+pub fn dicke(qubit_count: usize, k: usize) -> Circuit {
+    assert!(
+        k <= qubit_count,
+        "The number of the ones has to be at most the number of qubits {}.",
+        qubit_count
+    );
+    let mut circuit = Circuit::new(qubit_count);
+    for qubit_number in qubit_count - k..qubit_count {
+        circuit.pauli_x(qubit_number);
+    }
+    if k == 0 || k == qubit_count {
+        return circuit;
+    }
+    for top in (k..qubit_count).rev() {
+        split_and_cyclic_shift(&mut circuit, top, k);
+    }
+    for top in (1..k).rev() {
+        split_and_cyclic_shift(&mut circuit, top, top);
+    }
+    circuit
+}
+
+// The W state (|10…0⟩ + |01…0⟩ + … + |0…01⟩) / √n of n qubits.
+pub fn w_state(qubit_count: usize) -> Circuit {
+    assert!(qubit_count > 0, "The W state needs at least 1 qubit.");
+    dicke(qubit_count, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    fn binomial(n: usize, k: usize) -> usize {
+        (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+    }
+
+    #[test]
+    fn dicke_states_are_equal_superpositions() {
+        for n in 1..=6 {
+            for k in 0..=n {
+                let circuit = if k == 1 { w_state(n) } else { dicke(n, k) };
+                let mut simulation = QuantumSimulation::new(n, 0);
+                circuit.run(&mut simulation);
+                let amplitude = 1.0 / (binomial(n, k) as f64).sqrt();
+                for (index, value) in simulation.amplitudes().iter().enumerate() {
+                    let expected = if index.count_ones() as usize == k {
+                        amplitude
+                    } else {
+                        0.0
+                    };
+                    assert!(
+                        (value.re - expected).abs() < 1e-12 && value.im.abs() < 1e-12,
+                        "n = {}, k = {}",
+                        n,
+                        k
+                    );
+                }
+            }
+        }
+    }
+}
@@ -2,33 +2,90 @@
 Quantum full state vector simulation with a quantum complete set of gates.
 
 This simulation evaluates all the possibilities before taking a measurement
-based on the probability from a given random seed.
+based on the probability from a given random seed. The random number generator is
+ChaCha12, the generator of StdRng, whose state is serialized with the amplitudes and
+the readout errors by the serde feature so that a simulation can be resumed exactly.
+
+A swap of qubits only swaps them in the map of each qubit to the qubit of the state vector
+holding it, instead of moving the amplitudes. The gates and the measurements act on the mapped
+qubits, and the amplitudes are moved into the order of the qubit numbers only when the map is
+flushed. Reading or serializing the amplitudes copies them in that order.
+
+The state can be set to any normalized vector of the amplitudes or to a basis state, for running
+the algorithms on the specific superpositions of the inputs.
+
+The readout errors of the qubits flip the values returned by the measurements and the samples
+with their assignment error probabilities, after the state has collapsed to the measured values.
 
 Copyright © 2024 AlgoHertz. All rights reserved.
 */
 
+use std::borrow::Cow;
+
 use num_complex::Complex;
-use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
 
-use crate::gate;
+use crate::circuit::{Circuit, Gate};
+use crate::decomposition::Matrix4;
+use crate::gate::{self, GateKernel, Matrix};
+use crate::noise::{NoiseModel, ReadoutError};
 use crate::parity::create_u_f;
-use crate::simulation::Simulation;
-use crate::state_vector_init::{
-    get_amplitudes, get_ground_state_amplitudes, Qubit, ONE_QUBIT, ZERO_QUBIT,
-};
+use crate::simulation::{check_qubits, Simulation};
+use crate::trajectory_simulation::TrajectorySimulation;
+use alias::AliasTable;
+use buffer::AmplitudeBuffer;
+use phases::PhaseTable;
+
+mod alias;
+mod buffer;
+mod checkpoint;
+mod npy;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod phases;
+#[cfg(feature = "serde")]
+mod serialization;
+mod simd;
+mod single_precision;
 
-const MAX_QUBIT_COUNT: usize = 32;
+pub use buffer::AllocOptions;
+pub use single_precision::QuantumSimulationF32;
+
+// The most qubits whose amplitudes fit in the address space, the bytes of the state vector
+// being at most isize::MAX. The amplitudes are allocated only if they fit in the memory.
+pub const MAX_QUBIT_COUNT: usize =
+    usize::BITS as usize - 2 - std::mem::size_of::<Complex<f64>>().trailing_zeros() as usize;
+
+// The fewest diagonal gates merged into a table of phases.
+const MIN_MERGED_PHASE_COUNT: usize = 4;
+// The largest difference of the sum of the probabilities of a set state from 1.
+const NORM_TOLERANCE: f64 = 1e-9;
 
 #[derive(Debug)]
 pub struct QuantumSimulation {
     qubit_count: usize,
-    amplitudes: Vec<Complex<f64>>,
-    rng: StdRng,
+    amplitudes: AmplitudeBuffer,
+    alloc_options: AllocOptions,
+    // The qubit of the state vector holding each qubit.
+    qubit_map: Vec<usize>,
+    // The readout error of each qubit, none if empty.
+    readout_errors: Vec<ReadoutError>,
+    rng: ChaCha12Rng,
 }
 
 impl QuantumSimulation {
     pub fn new(qubit_count: usize, rnd_seed: u64) -> QuantumSimulation {
+        QuantumSimulation::with_alloc_options(qubit_count, rnd_seed, AllocOptions::default())
+    }
+
+    // A simulation whose amplitudes are allocated with the options, which are kept when
+    // the amplitudes are allocated again.
+    pub fn with_alloc_options(
+        qubit_count: usize,
+        rnd_seed: u64,
+        alloc_options: AllocOptions,
+    ) -> QuantumSimulation {
         assert!(
             qubit_count <= MAX_QUBIT_COUNT,
             "The number of qubits in the simulation cannot exceed {}.",
@@ -37,26 +94,160 @@ impl QuantumSimulation {
 
         let mut simulation = QuantumSimulation {
             qubit_count,
-            amplitudes: Vec::new(),
-            rng: StdRng::seed_from_u64(rnd_seed),
+            amplitudes: Vec::new().into(),
+            alloc_options,
+            qubit_map: (0..qubit_count).collect(),
+            readout_errors: Vec::new(),
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
         };
         simulation.reset();
 
         simulation
     }
 
-    fn _choose_state(&mut self) -> usize {
-        let probabilities: Vec<f64> = self
-            .amplitudes
+    // The simulation applying the channels of the noise model after every matching gate,
+    // as quantum jumps on the state vector of this simulation.
+    pub fn with_noise(self, noise_model: NoiseModel) -> TrajectorySimulation {
+        TrajectorySimulation::with_simulation(self, noise_model)
+    }
+
+    // Continue a simulation of another representation from its amplitudes and generator.
+    pub(crate) fn with_amplitudes(
+        amplitudes: Vec<Complex<f64>>,
+        rng: ChaCha12Rng,
+    ) -> QuantumSimulation {
+        let qubit_count = amplitudes.len().trailing_zeros() as usize;
+        QuantumSimulation {
+            qubit_count,
+            amplitudes: amplitudes.into(),
+            alloc_options: AllocOptions::default(),
+            qubit_map: (0..qubit_count).collect(),
+            readout_errors: Vec::new(),
+            rng,
+        }
+    }
+
+    // The qubit of the state vector holding the qubit.
+    fn physical_qubit(&self, qubit_number: usize) -> usize {
+        assert!(
+            qubit_number < self.qubit_count,
+            "The qubit number has to be less than the number of qubits {}.",
+            self.qubit_count
+        );
+        self.qubit_map[qubit_number]
+    }
+
+    // Check that the qubits of a multi-qubit gate are in the simulation and distinct, as the
+    // masks of a repeated qubit would mix the amplitudes of the wrong basis states.
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    // The value of the qubit in the basis state of the state vector.
+    fn qubit_value(&self, state_index: usize, qubit_number: usize) -> bool {
+        state_index & (1 << self.qubit_map[qubit_number]) > 0
+    }
+
+    // The index in the state vector of the amplitude of the basis state.
+    fn physical_index(&self, state_index: usize) -> usize {
+        self.qubit_map
             .iter()
-            .map(|amplitude| amplitude.norm_sqr())
-            .collect();
+            .enumerate()
+            .filter(|&(qubit_number, _)| (state_index >> qubit_number) & 1 == 1)
+            .fold(0, |index, (_, &physical_qubit)| index | 1 << physical_qubit)
+    }
+
+    fn is_qubit_map_flushed(&self) -> bool {
+        self.qubit_map
+            .iter()
+            .enumerate()
+            .all(|(qubit_number, &physical_qubit)| physical_qubit == qubit_number)
+    }
+
+    // The amplitudes in the order of the basis states, whatever the qubit map.
+    fn ordered_amplitudes(&self) -> impl Iterator<Item = Complex<f64>> + '_ {
+        (0..self.amplitudes.len()).map(|i| self.amplitudes[self.physical_index(i)])
+    }
+
+    // Relabel the qubits, the qubit i becoming the qubit permutation[i], without moving the amplitudes.
+    pub fn permute_qubits(&mut self, permutation: &[usize]) {
+        assert!(
+            permutation.len() == self.qubit_count,
+            "The permutation has to have one qubit number for each of the qubits {}.",
+            self.qubit_count
+        );
+        let mut qubit_map = vec![usize::MAX; self.qubit_count];
+        for (&qubit_number, &physical_qubit) in permutation.iter().zip(self.qubit_map.iter()) {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+            assert!(
+                qubit_map[qubit_number] == usize::MAX,
+                "The qubit numbers of a permutation have to be distinct."
+            );
+            qubit_map[qubit_number] = physical_qubit;
+        }
+        self.qubit_map = qubit_map;
+    }
+
+    // Move the amplitudes into the order of the qubit numbers, with one swap of the qubits
+    // of the state vector for each qubit out of place.
+    pub fn flush_qubit_map(&mut self) {
+        for qubit_number in 0..self.qubit_count {
+            let physical_qubit = self.qubit_map[qubit_number];
+            if physical_qubit == qubit_number {
+                continue;
+            }
+            let other_qubit_number = self
+                .qubit_map
+                .iter()
+                .position(|&q| q == qubit_number)
+                .unwrap();
+            self.apply_two_qubit_gate(gate::swap, physical_qubit, qubit_number);
+            self.qubit_map[other_qubit_number] = physical_qubit;
+            self.qubit_map[qubit_number] = qubit_number;
+        }
+    }
+
+    // Flip the measured values of the qubit with the probabilities of the readout error.
+    pub fn set_readout_error(&mut self, qubit_number: usize, readout_error: ReadoutError) {
+        self.physical_qubit(qubit_number);
+        self.readout_errors
+            .resize(self.qubit_count, ReadoutError::default());
+        self.readout_errors[qubit_number] = readout_error;
+    }
+
+    pub fn readout_error(&self, qubit_number: usize) -> ReadoutError {
+        self.physical_qubit(qubit_number);
+        self.readout_errors
+            .get(qubit_number)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn rng(&mut self) -> &mut ChaCha12Rng {
+        &mut self.rng
+    }
+
+    // The value read for the measured value of the qubit.
+    fn read(&mut self, qubit_number: usize, value: bool) -> bool {
+        match self.readout_errors.get(qubit_number) {
+            Some(readout_error) => readout_error.read(value, &mut self.rng),
+            None => value,
+        }
+    }
+
+    fn _choose_state(&mut self) -> usize {
         let random_number = self.rng.gen::<f64>();
         let mut accumulated_probability = 0.0;
         let mut state_index = 0;
 
-        for (i, &probability) in probabilities.iter().enumerate() {
-            accumulated_probability += probability;
+        for (i, amplitude) in self.amplitudes.iter().enumerate() {
+            accumulated_probability += amplitude.norm_sqr();
             if random_number <= accumulated_probability {
                 state_index = i;
                 break;
@@ -66,28 +257,382 @@ impl QuantumSimulation {
         state_index
     }
 
-    fn apply_one_qubit_gate<F>(&mut self, one_qubit_gate: F, qubit_number: usize)
-    where
-        F: Fn(Complex<f64>, Complex<f64>) -> (Complex<f64>, Complex<f64>),
-    {
+    // Set the allocated amplitudes to the ground state |0...0⟩ in place,
+    // which is the reset of every shot after the first.
+    pub fn reset_into_ground_state_fast(&mut self) {
+        assert!(
+            self.amplitudes.len() == 1 << self.qubit_count,
+            "The amplitudes of the simulation are not allocated."
+        );
+        // The ground state is the same in any order of the qubits.
+        self.qubit_map = (0..self.qubit_count).collect();
+        #[cfg(feature = "parallel")]
+        if self.qubit_count >= parallel::MIN_QUBIT_COUNT {
+            parallel::clear(&mut self.amplitudes);
+            self.amplitudes[0] = Complex::new(1.0, 0.0);
+            return;
+        }
+        self.amplitudes.fill(Complex::new(0.0, 0.0));
+        self.amplitudes[0] = Complex::new(1.0, 0.0);
+    }
+
+    // The amplitudes of the basis states, indexed by the bits of the qubit numbers. They are
+    // borrowed when the qubit map is flushed, and copied into the order of the qubit numbers
+    // otherwise.
+    pub fn amplitudes(&self) -> Cow<'_, [Complex<f64>]> {
+        if self.is_qubit_map_flushed() {
+            Cow::Borrowed(&self.amplitudes)
+        } else {
+            Cow::Owned(self.ordered_amplitudes().collect())
+        }
+    }
+
+    // Replace the state by the amplitudes, indexed like the amplitudes of the simulation, whose
+    // probabilities have to sum to 1.
+    pub fn set_state(&mut self, amplitudes: Vec<Complex<f64>>) {
+        assert!(
+            amplitudes.len() == 1 << self.qubit_count,
+            "The number of the amplitudes has to be 2^{}.",
+            self.qubit_count
+        );
+        let probability_sum: f64 = amplitudes
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .sum();
+        assert!(
+            (probability_sum - 1.0).abs() < NORM_TOLERANCE,
+            "The amplitudes have to be normalized, the sum of their probabilities is {}.",
+            probability_sum
+        );
+        self.amplitudes = AmplitudeBuffer::from_amplitudes(amplitudes, self.alloc_options);
+        self.qubit_map = (0..self.qubit_count).collect();
+    }
+
+    // Set the state to the basis state of the index, the bits of the qubit numbers.
+    pub fn prepare_basis_state(&mut self, state_index: u64) {
+        assert!(
+            self.qubit_count >= 64 || state_index >> self.qubit_count == 0,
+            "The index of the basis state has to be less than 2^{}.",
+            self.qubit_count
+        );
+        self.reset();
+        self.amplitudes[0] = Complex::new(0.0, 0.0);
+        self.amplitudes[state_index as usize] = Complex::new(1.0, 0.0);
+    }
+
+    // The fidelity |⟨φ|ψ⟩|^2 of the state ψ to the pure state φ of the amplitudes.
+    pub fn fidelity(&self, state: &[Complex<f64>]) -> f64 {
+        assert!(
+            state.len() == 1 << self.qubit_count,
+            "The number of the amplitudes has to be 2^{}.",
+            self.qubit_count
+        );
+        self.ordered_amplitudes()
+            .zip(state)
+            .map(|(amplitude, state_amplitude)| state_amplitude.conj() * amplitude)
+            .sum::<Complex<f64>>()
+            .norm_sqr()
+    }
+
+    // The fidelity ⟨φ|ρ|φ⟩ of the reduced state ρ of the qubit, with the other qubits traced
+    // out, to the pure state φ of the qubit.
+    pub fn qubit_fidelity(&self, qubit_number: usize, state: &[Complex<f64>; 2]) -> f64 {
         assert!(
             qubit_number < self.qubit_count,
             "The qubit number has to be less than the number of qubits {}.",
             self.qubit_count
         );
-
         let mask = 1 << qubit_number;
-        for i0 in 0..self.amplitudes.len() {
-            let one: bool = i0 as u64 & mask != 0;
-            if !one {
-                let i1 = i0 + mask as usize;
-                let (a0, a1) = one_qubit_gate(self.amplitudes[i0], self.amplitudes[i1]);
-                self.amplitudes[i0] = a0;
-                self.amplitudes[i1] = a1;
+        let amplitudes = self.amplitudes();
+        (0..amplitudes.len())
+            .filter(|state_index| state_index & mask == 0)
+            .map(|state_index| {
+                (state[0].conj() * amplitudes[state_index]
+                    + state[1].conj() * amplitudes[state_index | mask])
+                    .norm_sqr()
+            })
+            .sum()
+    }
+
+    // The state in the Dirac notation, a line "amplitude |q_(n-1)...q_0⟩" for each basis state
+    // with the magnitude of the amplitude above the threshold, the most probable first.
+    pub fn state_string(&self, threshold: f64) -> String {
+        let qubit_count = self.qubit_count;
+        let mut terms: Vec<(usize, Complex<f64>)> = self
+            .ordered_amplitudes()
+            .enumerate()
+            .filter(|(_, amplitude)| amplitude.norm() > threshold)
+            .collect();
+        terms.sort_by(|a, b| b.1.norm_sqr().total_cmp(&a.1.norm_sqr()));
+        terms
+            .into_iter()
+            .map(|(state_index, amplitude)| {
+                format!(
+                    "{:+.4}{:+.4}i |{:0width$b}⟩\n",
+                    amplitude.re,
+                    amplitude.im,
+                    state_index,
+                    width = qubit_count
+                )
+            })
+            .collect()
+    }
+
+    // Display the state in the Dirac notation, the basis states of the magnitudes of the
+    // amplitudes above the threshold.
+    pub fn print_state(&self, threshold: f64) {
+        print!("{}", self.state_string(threshold));
+    }
+
+    // Draw the values of the qubits for each shot from the current state without collapsing it.
+    // The alias table of the probabilities takes one pass over the amplitudes,
+    // and then each shot is drawn in constant time.
+    pub fn sample(&mut self, qubit_numbers: &[usize], shot_count: usize) -> Vec<Vec<bool>> {
+        for &qubit_number in qubit_numbers.iter() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+        }
+        let probabilities: Vec<f64> = self.amplitudes.iter().map(|a| a.norm_sqr()).collect();
+        let table = AliasTable::new(&probabilities);
+        drop(probabilities);
+        (0..shot_count)
+            .map(|_| {
+                let state_index = table.sample(&mut self.rng);
+                qubit_numbers
+                    .iter()
+                    .map(|&qubit_number| {
+                        let value = self.qubit_value(state_index, qubit_number);
+                        self.read(qubit_number, value)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Apply the channel with the Kraus operators to the qubit as a quantum jump: the operator K
+    // is chosen with the probability |K ψ|^2 and the state becomes K ψ / |K ψ|, so that the
+    // average over the runs is the channel applied to the density matrix.
+    pub fn apply_kraus(&mut self, kraus_operators: &[Matrix], qubit_number: usize) {
+        let physical_qubit = self.physical_qubit(qubit_number);
+        let stride = 1 << physical_qubit;
+        let probabilities: Vec<f64> = kraus_operators
+            .iter()
+            .map(|operator| {
+                self.amplitudes
+                    .chunks_exact(2 * stride)
+                    .flat_map(|chunk| chunk[..stride].iter().zip(chunk[stride..].iter()))
+                    .map(|(a0, a1)| {
+                        (operator[0] * a0 + operator[1] * a1).norm_sqr()
+                            + (operator[2] * a0 + operator[3] * a1).norm_sqr()
+                    })
+                    .sum()
+            })
+            .collect();
+        let random_number = self.rng.gen::<f64>() * probabilities.iter().sum::<f64>();
+        let mut accumulated_probability = 0.0;
+        // The rounding errors can leave the random number above the total probability.
+        let mut k = probabilities.iter().rposition(|&p| p > 0.0).unwrap_or(0);
+        for (i, probability) in probabilities.iter().enumerate() {
+            accumulated_probability += probability;
+            if random_number < accumulated_probability {
+                k = i;
+                break;
+            }
+        }
+        let scale = 1.0 / probabilities[k].sqrt();
+        let operator = kraus_operators[k].map(|element| element * scale);
+        self.apply_to_halves(
+            |zeros, ones| {
+                for (a0, a1) in zeros.iter_mut().zip(ones.iter_mut()) {
+                    (*a0, *a1) = (
+                        operator[0] * *a0 + operator[1] * *a1,
+                        operator[2] * *a0 + operator[3] * *a1,
+                    );
+                }
+            },
+            physical_qubit,
+        );
+    }
+
+    // Apply the two-qubit unitary, indexed by q0 + 2·q1 like the amplitudes, to the qubits.
+    pub fn apply_two_qubit_matrix(
+        &mut self,
+        matrix: &Matrix4,
+        qubit_number0: usize,
+        qubit_number1: usize,
+    ) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        self.apply_two_qubit_gate(
+            |a00, a01, a10, a11| {
+                let amplitudes = [a00, a01, a10, a11];
+                let [b00, b01, b10, b11] =
+                    matrix.map(|row| row.iter().zip(amplitudes.iter()).map(|(m, a)| m * a).sum());
+                (b00, b01, b10, b11)
+            },
+            self.physical_qubit(qubit_number0),
+            self.physical_qubit(qubit_number1),
+        );
+    }
+
+    // Run the circuit from the ground state and return the classical bits of every shot,
+    // or all the qubits measured at the end if the circuit has no classical bits.
+    // When the measurements are at the end of the circuit, it is simulated once and the shots
+    // are drawn from the final state, which the simulation is left in. Otherwise the circuit
+    // is run for each shot.
+    pub fn run_shots(&mut self, circuit: &Circuit, shot_count: usize) -> Vec<Vec<bool>> {
+        assert!(
+            circuit.qubit_count() <= self.qubit_count,
+            "The circuit cannot have more qubits than the simulation {}.",
+            self.qubit_count
+        );
+        if let Some(measurements) = circuit.terminal_measurements() {
+            self.reset();
+            circuit.run_without_measurements(self);
+            if circuit.clbit_count() == 0 {
+                let qubit_numbers: Vec<usize> = (0..self.qubit_count).collect();
+                return self.sample(&qubit_numbers, shot_count);
+            }
+            let qubit_numbers: Vec<usize> = measurements.iter().map(|&(q, _)| q).collect();
+            return self
+                .sample(&qubit_numbers, shot_count)
+                .into_iter()
+                .map(|values| {
+                    let mut clbits = vec![false; circuit.clbit_count()];
+                    for (&(_, c), value) in measurements.iter().zip(values) {
+                        clbits[c] = value;
+                    }
+                    clbits
+                })
+                .collect();
+        }
+
+        let mut measurements = Vec::with_capacity(shot_count);
+        for _ in 0..shot_count {
+            self.reset();
+            let clbits = circuit.run(self);
+            if circuit.clbit_count() == 0 {
+                measurements.push(self.measure_all());
+            } else {
+                measurements.push(clbits);
+            }
+        }
+        measurements
+    }
+
+    // Apply the gates acting on disjoint qubits in one sweep over the state vector.
+    // The block of the amplitudes spanned by the qubits of the layer is gathered for each
+    // assignment of the other qubits, and all the gates are applied to the block at once.
+    pub fn apply_layer(&mut self, gates: &[Gate]) {
+        let layer_qubits: Vec<usize> = gates.iter().flat_map(|gate| gate.qubits()).collect();
+        let mut layer_mask = 0usize;
+        assert!(
+            !gates.iter().any(|gate| matches!(gate, Gate::Measure(_, _))),
+            "A layer cannot contain measurements."
+        );
+        for &qubit_number in layer_qubits.iter() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+            assert!(
+                layer_mask & (1 << qubit_number) == 0,
+                "The gates of a layer have to act on disjoint qubits."
+            );
+            layer_mask |= 1 << qubit_number;
+        }
+
+        // The swaps of the layer only swap their qubits in the qubit map, and the other gates
+        // act on the qubits of the state vector holding their qubits.
+        for gate in gates.iter() {
+            if let Gate::Swap(qubit_number0, qubit_number1) = *gate {
+                self.swap(qubit_number0, qubit_number1);
+            }
+        }
+        let gates: Vec<Gate> = gates
+            .iter()
+            .filter(|gate| !matches!(gate, Gate::Swap(_, _)))
+            .map(|gate| gate.map_qubits(|q| self.qubit_map[q]))
+            .collect();
+        let layer_qubits: Vec<usize> = gates.iter().flat_map(|gate| gate.qubits()).collect();
+        if layer_qubits.is_empty() {
+            return;
+        }
+
+        // The qubit i of the block is the qubit layer_qubits[i] of the state vector.
+        let local_gates: Vec<Gate> = gates
+            .iter()
+            .map(|gate| gate.map_qubits(|q| layer_qubits.iter().position(|&l| l == q).unwrap()))
+            .collect();
+        let offsets: Vec<usize> = (0..1usize << layer_qubits.len())
+            .map(|j| {
+                layer_qubits
+                    .iter()
+                    .enumerate()
+                    .filter(|&(bit, _)| (j >> bit) & 1 == 1)
+                    .map(|(_, &q)| 1 << q)
+                    .sum()
+            })
+            .collect();
+        let mut block = QuantumSimulation {
+            qubit_count: layer_qubits.len(),
+            amplitudes: vec![Complex::new(0.0, 0.0); offsets.len()].into(),
+            alloc_options: AllocOptions::default(),
+            qubit_map: (0..layer_qubits.len()).collect(),
+            readout_errors: Vec::new(),
+            rng: ChaCha12Rng::seed_from_u64(0),
+        };
+
+        // The bases are the indices of the blocks with zero bits inserted at the layer qubits.
+        let mut layer_strides: Vec<usize> = layer_qubits.iter().map(|&q| 1 << q).collect();
+        layer_strides.sort_unstable();
+        for block_number in 0..self.amplitudes.len() >> layer_qubits.len() {
+            let mut base = block_number;
+            for &stride in layer_strides.iter() {
+                base = (base & !(stride - 1)) << 1 | (base & (stride - 1));
+            }
+            for (amplitude, &offset) in block.amplitudes.iter_mut().zip(offsets.iter()) {
+                *amplitude = self.amplitudes[base + offset];
             }
+            for gate in local_gates.iter() {
+                gate.apply(&mut block);
+            }
+            for (amplitude, &offset) in block.amplitudes.iter().zip(offsets.iter()) {
+                self.amplitudes[base + offset] = *amplitude;
+            }
+        }
+    }
+
+    // Apply the kernel updating the halves of each chunk of the state vector
+    // with the qubit 0 and 1.
+    fn apply_to_halves<F>(&mut self, kernel: F, qubit_number: usize)
+    where
+        F: Fn(&mut [Complex<f64>], &mut [Complex<f64>]) + Sync,
+    {
+        assert!(
+            qubit_number < self.qubit_count,
+            "The qubit number has to be less than the number of qubits {}.",
+            self.qubit_count
+        );
+        let stride = 1 << qubit_number;
+        #[cfg(feature = "parallel")]
+        if self.qubit_count >= parallel::MIN_QUBIT_COUNT {
+            parallel::apply_to_halves(&mut self.amplitudes, &kernel, stride);
+            return;
+        }
+        for chunk in self.amplitudes.chunks_exact_mut(2 * stride) {
+            let (zeros, ones) = chunk.split_at_mut(stride);
+            kernel(zeros, ones);
         }
     }
 
+    fn apply_kernel(&mut self, kernel: GateKernel, qubit_number: usize) {
+        self.apply_to_halves(|zeros, ones| kernel.apply(zeros, ones), qubit_number);
+    }
+
     fn apply_two_qubit_gate<F>(
         &mut self,
         two_qubit_gate: F,
@@ -95,38 +640,55 @@ impl QuantumSimulation {
         qubit_number1: usize,
     ) where
         F: Fn(
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-        ) -> (Complex<f64>, Complex<f64>, Complex<f64>, Complex<f64>),
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+            ) -> (Complex<f64>, Complex<f64>, Complex<f64>, Complex<f64>)
+            + Sync,
     {
         assert!(
             (qubit_number0 < self.qubit_count) && (qubit_number1 < self.qubit_count),
             "The qubit number has to be less than the number of qubits {}.",
             self.qubit_count
         );
+        #[cfg(feature = "parallel")]
+        if self.qubit_count >= parallel::MIN_QUBIT_COUNT {
+            parallel::apply_two_qubit_gate(
+                &mut self.amplitudes,
+                &two_qubit_gate,
+                qubit_number0,
+                qubit_number1,
+            );
+            return;
+        }
 
         let mask01 = 1 << qubit_number0;
         let mask10 = 1 << qubit_number1;
-        let mask11 = mask01 | mask10;
-        for i00 in 0..self.amplitudes.len() {
-            let one01: bool = i00 as u64 & mask01 != 0;
-            let one10: bool = i00 as u64 & mask10 != 0;
-            if !one01 && !one10 {
-                let i01 = i00 + mask01 as usize;
-                let i10 = i00 + mask10 as usize;
-                let i11 = i00 + mask11 as usize;
-                let (a00, a01, a10, a11) = two_qubit_gate(
-                    self.amplitudes[i00],
-                    self.amplitudes[i01],
-                    self.amplitudes[i10],
-                    self.amplitudes[i11],
-                );
-                self.amplitudes[i00] = a00;
-                self.amplitudes[i01] = a01;
-                self.amplitudes[i10] = a10;
-                self.amplitudes[i11] = a11;
+        // The nested strided loops visit only the groups of the amplitudes of the two qubits,
+        // splitting the chunks of the higher qubit and then of the lower qubit into their halves.
+        let (low_stride, high_stride) = (mask01.min(mask10), mask01.max(mask10));
+        for chunk in self.amplitudes.chunks_exact_mut(2 * high_stride) {
+            let (high_zeros, high_ones) = chunk.split_at_mut(high_stride);
+            for (part0, part1) in high_zeros
+                .chunks_exact_mut(2 * low_stride)
+                .zip(high_ones.chunks_exact_mut(2 * low_stride))
+            {
+                let (amplitudes00, low_ones) = part0.split_at_mut(low_stride);
+                let (high_ones, amplitudes11) = part1.split_at_mut(low_stride);
+                let (amplitudes01, amplitudes10) = if mask01 == low_stride {
+                    (low_ones, high_ones)
+                } else {
+                    (high_ones, low_ones)
+                };
+                for (((a00, a01), a10), a11) in amplitudes00
+                    .iter_mut()
+                    .zip(amplitudes01.iter_mut())
+                    .zip(amplitudes10.iter_mut())
+                    .zip(amplitudes11.iter_mut())
+                {
+                    (*a00, *a01, *a10, *a11) = two_qubit_gate(*a00, *a01, *a10, *a11);
+                }
             }
         }
     }
@@ -139,24 +701,24 @@ impl QuantumSimulation {
         qubit_number2: usize,
     ) where
         F: Fn(
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-        ) -> (
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-            Complex<f64>,
-        ),
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+            ) -> (
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+                Complex<f64>,
+            ) + Sync,
     {
         assert!(
             (qubit_number0 < self.qubit_count)
@@ -165,6 +727,17 @@ impl QuantumSimulation {
             "The qubit number has to be less than the number of qubits {}.",
             self.qubit_count
         );
+        #[cfg(feature = "parallel")]
+        if self.qubit_count >= parallel::MIN_QUBIT_COUNT {
+            parallel::apply_three_qubit_gate(
+                &mut self.amplitudes,
+                &three_qubit_gate,
+                qubit_number0,
+                qubit_number1,
+                qubit_number2,
+            );
+            return;
+        }
 
         let mask001 = 1 << qubit_number0;
         let mask010 = 1 << qubit_number1;
@@ -173,66 +746,68 @@ impl QuantumSimulation {
         let mask101 = mask001 | mask100;
         let mask110 = mask010 | mask100;
         let mask111 = mask011 | mask100;
-        for i000 in 0..self.amplitudes.len() {
-            let one001: bool = i000 as u64 & mask001 != 0;
-            let one010: bool = i000 as u64 & mask010 != 0;
-            let one100: bool = i000 as u64 & mask100 != 0;
-            if !one001 && !one010 && !one100 {
-                let i001 = i000 + mask001 as usize;
-                let i010 = i000 + mask010 as usize;
-                let i011 = i000 + mask011 as usize;
-                let i100 = i000 + mask100 as usize;
-                let i101 = i000 + mask101 as usize;
-                let i110 = i000 + mask110 as usize;
-                let i111 = i000 + mask111 as usize;
-                let (a000, a001, a010, a011, a100, a101, a110, a111) = three_qubit_gate(
-                    self.amplitudes[i000],
-                    self.amplitudes[i001],
-                    self.amplitudes[i010],
-                    self.amplitudes[i011],
-                    self.amplitudes[i100],
-                    self.amplitudes[i101],
-                    self.amplitudes[i110],
-                    self.amplitudes[i111],
-                );
-                self.amplitudes[i000] = a000;
-                self.amplitudes[i001] = a001;
-                self.amplitudes[i010] = a010;
-                self.amplitudes[i011] = a011;
-                self.amplitudes[i100] = a100;
-                self.amplitudes[i101] = a101;
-                self.amplitudes[i110] = a110;
-                self.amplitudes[i111] = a111;
+        // The nested strided loops visit only the indices with the bits of the three qubits 0.
+        let mut strides = [mask001, mask010, mask100];
+        strides.sort_unstable();
+        let [low_stride, middle_stride, high_stride] = strides;
+        let amplitudes: &mut [Complex<f64>] = &mut self.amplitudes;
+        let length = amplitudes.len();
+        for high_base in (0..length).step_by(2 * high_stride) {
+            for middle_base in (high_base..high_base + high_stride).step_by(2 * middle_stride) {
+                for low_base in (middle_base..middle_base + middle_stride).step_by(2 * low_stride) {
+                    for i000 in low_base..low_base + low_stride {
+                        let (a000, a001, a010, a011, a100, a101, a110, a111) = three_qubit_gate(
+                            amplitudes[i000],
+                            amplitudes[i000 + mask001],
+                            amplitudes[i000 + mask010],
+                            amplitudes[i000 + mask011],
+                            amplitudes[i000 + mask100],
+                            amplitudes[i000 + mask101],
+                            amplitudes[i000 + mask110],
+                            amplitudes[i000 + mask111],
+                        );
+                        amplitudes[i000] = a000;
+                        amplitudes[i000 + mask001] = a001;
+                        amplitudes[i000 + mask010] = a010;
+                        amplitudes[i000 + mask011] = a011;
+                        amplitudes[i000 + mask100] = a100;
+                        amplitudes[i000 + mask101] = a101;
+                        amplitudes[i000 + mask110] = a110;
+                        amplitudes[i000 + mask111] = a111;
+                    }
+                }
             }
         }
     }
 }
 
 impl Simulation for QuantumSimulation {
+    // Reuse the amplitudes if they are allocated, allocating them only for a new simulation.
     fn reset(&mut self) {
-        self.amplitudes = get_ground_state_amplitudes(self.qubit_count)
+        if self.amplitudes.len() == 1 << self.qubit_count {
+            self.reset_into_ground_state_fast();
+        } else {
+            self.amplitudes = AmplitudeBuffer::zeros(1 << self.qubit_count, self.alloc_options);
+            self.amplitudes[0] = Complex::new(1.0, 0.0);
+            self.qubit_map = (0..self.qubit_count).collect();
+        }
     }
 
-    // Measure all the qubits in the Z-basis.
+    // Measure all the qubits in the Z-basis, collapsing the amplitudes in place.
     fn measure_all(&mut self) -> Vec<bool> {
         let measured_state_index = self._choose_state();
-        let mut measured_states: Vec<bool> = Vec::with_capacity(self.qubit_count);
-        let mut qubits: Vec<Qubit<f64>> = Vec::with_capacity(self.qubit_count);
-        for qubit_number in 0..self.qubit_count {
-            let measured_state = measured_state_index & (1 << qubit_number) > 0;
-            measured_states.push(measured_state);
-            if measured_state {
-                qubits.push(ONE_QUBIT);
-            } else {
-                qubits.push(ZERO_QUBIT);
-            }
-        }
-        self.amplitudes = get_amplitudes(qubits);
+        self.amplitudes.fill(Complex::new(0.0, 0.0));
+        self.amplitudes[measured_state_index] = Complex::new(1.0, 0.0);
 
-        measured_states
+        (0..self.qubit_count)
+            .map(|qubit_number| {
+                let value = self.qubit_value(measured_state_index, qubit_number);
+                self.read(qubit_number, value)
+            })
+            .collect()
     }
 
-    // Measure the selected qubits in the Z-basis.
+    // Measure the selected qubits in the Z-basis, collapsing the amplitudes in place.
     fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
         for qubit_number in qubit_numbers.iter() {
             assert!(
@@ -243,75 +818,117 @@ impl Simulation for QuantumSimulation {
         }
 
         let measured_state_index = self._choose_state();
-        let mut measured_states: Vec<bool> = Vec::with_capacity(self.qubit_count);
-
-        for qubit_number in qubit_numbers.iter() {
-            let measured_state = measured_state_index & (1 << qubit_number) > 0;
-            measured_states.push(measured_state);
-        }
+        let measured_states: Vec<bool> = qubit_numbers
+            .iter()
+            .map(|&qubit_number| self.qubit_value(measured_state_index, qubit_number))
+            .collect();
 
+        // The amplitudes of the other measured values are zeroed while the probability
+        // of the measured values is accumulated, and the remaining ones are then rescaled.
+        let mask = qubit_numbers.iter().fold(0, |mask, &qubit_number| {
+            mask | 1 << self.qubit_map[qubit_number]
+        });
+        let value = measured_state_index & mask;
         let mut accumulated_probability = 0.0;
-        let mut possible_amplitude_indices: Vec<usize> = Vec::with_capacity(self.amplitudes.len());
-        'state_iteration: for i in 0..self.amplitudes.len() {
-            for (j, &qubit_number) in qubit_numbers.iter().enumerate() {
-                let qubit_state = i & (1 << qubit_number) > 0;
-                if measured_states[j] != qubit_state {
-                    self.amplitudes[i] = Complex::new(0.0, 0.0);
-                    continue 'state_iteration;
-                }
+        for (i, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if i & mask == value {
+                accumulated_probability += amplitude.norm_sqr();
+            } else {
+                *amplitude = Complex::new(0.0, 0.0);
             }
-            let probability = self.amplitudes[i].norm_sqr();
-            accumulated_probability += probability;
-            possible_amplitude_indices.push(i);
         }
 
         let bump_amplitude_factor = (1.0 / accumulated_probability).sqrt();
-        for i in possible_amplitude_indices.into_iter() {
-            self.amplitudes[i] = bump_amplitude_factor * self.amplitudes[i];
+        for (i, amplitude) in self.amplitudes.iter_mut().enumerate() {
+            if i & mask == value {
+                *amplitude *= bump_amplitude_factor;
+            }
         }
 
-        measured_states
+        qubit_numbers
+            .iter()
+            .zip(measured_states)
+            .map(|(&qubit_number, value)| self.read(qubit_number, value))
+            .collect()
     }
 
     fn pauli_x(&mut self, qubit_number: usize) {
-        self.apply_one_qubit_gate(gate::pauli_x, qubit_number);
+        self.apply_to_halves(simd::pauli_x, self.physical_qubit(qubit_number));
     }
 
     fn pauli_y(&mut self, qubit_number: usize) {
-        self.apply_one_qubit_gate(gate::pauli_y, qubit_number);
+        self.apply_kernel(GateKernel::PauliY, self.physical_qubit(qubit_number));
     }
 
     fn pauli_z(&mut self, qubit_number: usize) {
-        self.apply_one_qubit_gate(gate::pauli_z, qubit_number);
+        self.apply_to_halves(simd::pauli_z, self.physical_qubit(qubit_number));
     }
 
     fn hadamard(&mut self, qubit_number: usize) {
-        self.apply_one_qubit_gate(gate::hadamard, qubit_number);
+        self.apply_to_halves(simd::hadamard, self.physical_qubit(qubit_number));
     }
 
     fn s(&mut self, qubit_number: usize) {
-        self.apply_one_qubit_gate(gate::s, qubit_number);
+        self.apply_to_halves(simd::s, self.physical_qubit(qubit_number));
     }
 
     fn t(&mut self, qubit_number: usize) {
-        self.apply_one_qubit_gate(gate::t, qubit_number);
+        self.apply_to_halves(simd::t, self.physical_qubit(qubit_number));
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_kernel(GateKernel::rx(theta), self.physical_qubit(qubit_number));
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_kernel(GateKernel::ry(theta), self.physical_qubit(qubit_number));
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_kernel(GateKernel::rz(theta), self.physical_qubit(qubit_number));
     }
 
     fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
-        self.apply_two_qubit_gate(gate::cnot, control_qubit_number, target_qubit_number);
+        self.check_qubit_numbers(&[control_qubit_number, target_qubit_number]);
+        self.apply_two_qubit_gate(
+            gate::cnot,
+            self.physical_qubit(control_qubit_number),
+            self.physical_qubit(target_qubit_number),
+        );
     }
 
     fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
-        self.apply_two_qubit_gate(gate::cz, control_qubit_number, target_qubit_number);
+        self.check_qubit_numbers(&[control_qubit_number, target_qubit_number]);
+        self.apply_two_qubit_gate(
+            gate::cz,
+            self.physical_qubit(control_qubit_number),
+            self.physical_qubit(target_qubit_number),
+        );
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        self.check_qubit_numbers(&[control_qubit_number, target_qubit_number]);
+        self.apply_two_qubit_gate(
+            gate::cphase(theta),
+            self.physical_qubit(control_qubit_number),
+            self.physical_qubit(target_qubit_number),
+        );
     }
 
+    // Swap the qubits in the qubit map without moving the amplitudes.
     fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
-        self.apply_two_qubit_gate(gate::swap, qubit_number0, qubit_number1);
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        self.qubit_map.swap(qubit_number0, qubit_number1);
     }
 
     fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
         let u_f = create_u_f(f);
-        self.apply_two_qubit_gate(u_f, qubit_number0, qubit_number1);
+        self.apply_two_qubit_gate(
+            u_f,
+            self.physical_qubit(qubit_number0),
+            self.physical_qubit(qubit_number1),
+        );
     }
 
     fn toffoli(
@@ -320,13 +937,51 @@ impl Simulation for QuantumSimulation {
         control_qubit_number1: usize,
         target_qubit_number: usize,
     ) {
-        self.apply_three_qubit_gate(
-            gate::toffoli,
+        self.check_qubit_numbers(&[
             control_qubit_number0,
             control_qubit_number1,
             target_qubit_number,
+        ]);
+        self.apply_three_qubit_gate(
+            gate::toffoli,
+            self.physical_qubit(control_qubit_number0),
+            self.physical_qubit(control_qubit_number1),
+            self.physical_qubit(target_qubit_number),
         );
     }
+
+    // The phases of the diagonal gates are merged into a table and applied in one pass,
+    // unless there are too few gates to save a pass over the state vector.
+    fn apply_phases(&mut self, gates: &[Gate]) {
+        for gate in gates.iter() {
+            for qubit_number in gate.qubits() {
+                assert!(
+                    qubit_number < self.qubit_count,
+                    "The qubit number has to be less than the number of qubits {}.",
+                    self.qubit_count
+                );
+            }
+        }
+        if gates.len() < MIN_MERGED_PHASE_COUNT {
+            for gate in gates.iter() {
+                gate.apply(self);
+            }
+            return;
+        }
+        let gates: Vec<Gate> = gates
+            .iter()
+            .map(|gate| gate.map_qubits(|q| self.qubit_map[q]))
+            .collect();
+        let table = PhaseTable::new(&gates, self.qubit_count);
+        #[cfg(feature = "parallel")]
+        if self.qubit_count >= parallel::MIN_QUBIT_COUNT {
+            parallel::apply_to_pieces(&mut self.amplitudes, &|start, piece| {
+                table.apply(piece, start)
+            });
+            return;
+        }
+        table.apply(&mut self.amplitudes, 0);
+    }
 }
 
 #[cfg(test)]
@@ -367,4 +1022,243 @@ mod tests {
             assert_eq!(measurements012[2], measurements02[1]);
         }
     }
+
+    #[test]
+    fn swaps_remap_qubits() {
+        let mut simulation = QuantumSimulation::new(4, 0u64);
+        let mut moved_simulation = QuantumSimulation::new(4, 0u64);
+        for q in 0..4 {
+            simulation.ry(0.3 + q as f64, q);
+            moved_simulation.ry(0.3 + q as f64, q);
+        }
+        simulation.swap(0, 3);
+        simulation.swap(3, 1);
+        simulation.permute_qubits(&[2, 0, 1, 3]);
+        for (q0, q1) in [(0, 3), (3, 1), (0, 2), (1, 0)] {
+            moved_simulation.apply_two_qubit_gate(gate::swap, q0, q1);
+        }
+        for simulation in [&mut simulation, &mut moved_simulation] {
+            simulation.cnot(2, 0);
+            simulation.toffoli(1, 3, 2);
+            simulation.apply_phases(&[Gate::T(0), Gate::Cz(0, 3), Gate::S(1), Gate::Rz(0.5, 2)]);
+        }
+        assert!(simulation
+            .amplitudes()
+            .iter()
+            .zip(moved_simulation.amplitudes().iter())
+            .all(|(a, b)| (a - b).norm() < 1e-12));
+    }
+
+    #[test]
+    fn readout_errors_flip_measurements() {
+        let mut simulation = QuantumSimulation::new(2, 0u64);
+        simulation.set_readout_error(0, ReadoutError::new(1.0, 0.0));
+        simulation.set_readout_error(1, ReadoutError::new(0.0, 0.25));
+        let mut one_count = 0;
+        for _ in 0..1000 {
+            simulation.reset();
+            simulation.pauli_x(1);
+            let values = simulation.measure_all();
+            assert!(values[0]);
+            one_count += values[1] as usize;
+        }
+        assert!((one_count as f64 / 1000.0 - 0.75).abs() < 0.05);
+
+        // The state collapses to the measured values, not to the values read.
+        simulation.reset();
+        assert_eq!(simulation.measure(vec![0]), vec![true]);
+        assert!((simulation.amplitudes()[0].norm_sqr() - 1.0).abs() < 1e-12);
+    }
+
+    #[cfg(all(feature = "serde", feature = "json"))]
+    #[test]
+    fn readout_errors_are_serialized() {
+        let mut simulation = QuantumSimulation::new(2, 0u64);
+        simulation.set_readout_error(1, ReadoutError::new(0.1, 0.2));
+        let serialized = serde_json::to_string(&simulation).unwrap();
+        let deserialized: QuantumSimulation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.readout_error(1), ReadoutError::new(0.1, 0.2));
+        assert_eq!(deserialized.readout_error(0), ReadoutError::default());
+    }
+
+    #[cfg(all(feature = "serde", feature = "json"))]
+    #[test]
+    fn swapped_amplitudes_are_serialized_in_order() {
+        let mut simulation = QuantumSimulation::new(3, 0u64);
+        simulation.pauli_x(0);
+        simulation.hadamard(1);
+        simulation.swap(0, 2);
+        let serialized = serde_json::to_string(&simulation).unwrap();
+        let deserialized: QuantumSimulation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.qubit_map, vec![0, 1, 2]);
+        assert_eq!(deserialized.amplitudes(), simulation.amplitudes());
+    }
+
+    #[cfg(all(feature = "serde", feature = "json"))]
+    #[test]
+    fn serialized_simulation_resumes() {
+        let mut simulation = QuantumSimulation::new(3, 7u64);
+        simulation.hadamard(0);
+        simulation.ry(0.4, 1);
+        simulation.cnot(0, 2);
+        simulation.measure(vec![1]);
+        let serialized = serde_json::to_string(&simulation).unwrap();
+        let mut deserialized: QuantumSimulation = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.amplitudes(), simulation.amplitudes());
+        for _ in 0..20 {
+            simulation.hadamard(1);
+            deserialized.hadamard(1);
+            assert_eq!(
+                deserialized.measure(vec![0, 1]),
+                simulation.measure(vec![0, 1])
+            );
+        }
+    }
+
+    #[cfg(all(feature = "serde", feature = "json"))]
+    #[test]
+    fn invalid_simulations_are_not_deserialized() {
+        let simulation = QuantumSimulation::new(2, 0u64);
+        let value = serde_json::to_value(&simulation).unwrap();
+
+        let mut invalid = value.clone();
+        invalid["qubit_count"] = serde_json::json!(3);
+        let error = serde_json::from_value::<QuantumSimulation>(invalid).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("The number of the amplitudes has to be 8"));
+
+        for qubit_map in [vec![0, 0], vec![0, 2], vec![0]] {
+            let mut invalid = value.clone();
+            invalid["qubit_map"] = serde_json::json!(qubit_map);
+            let error = serde_json::from_value::<QuantumSimulation>(invalid).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("The qubit map has to be a permutation"));
+        }
+
+        let mut swapped = value.clone();
+        swapped["qubit_map"] = serde_json::json!([1, 0]);
+        assert!(serde_json::from_value::<QuantumSimulation>(swapped).is_ok());
+    }
+
+    #[test]
+    fn fidelities_of_bell_state() {
+        let mut simulation = QuantumSimulation::new(3, 0u64);
+        simulation.pauli_x(2);
+        simulation.hadamard(0);
+        simulation.cnot(0, 1);
+        simulation.swap(1, 2);
+        let amplitude = Complex::new(std::f64::consts::FRAC_1_SQRT_2, 0.0);
+        let zero = Complex::new(0.0, 0.0);
+        let bell = [zero, zero, amplitude, zero, zero, zero, zero, amplitude];
+        assert!((simulation.fidelity(&bell) - 1.0).abs() < 1e-12);
+        // The reduced state of a qubit of the Bell state is maximally mixed.
+        let plus = [amplitude, amplitude];
+        assert!((simulation.qubit_fidelity(0, &plus) - 0.5).abs() < 1e-12);
+        let one = [zero, Complex::new(1.0, 0.0)];
+        assert!((simulation.qubit_fidelity(1, &one) - 1.0).abs() < 1e-12);
+        assert!(simulation.qubit_fidelity(2, &[zero, zero]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn state_is_printed_in_dirac_notation() {
+        let mut simulation = QuantumSimulation::new(2, 0u64);
+        simulation.set_state(vec![
+            Complex::new(0.1, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(0.0, -0.7),
+            Complex::new(0.5f64.sqrt(), 0.0),
+        ]);
+        // The most probable basis state first, and the amplitudes up to the threshold omitted.
+        assert_eq!(
+            simulation.state_string(0.2),
+            "+0.7071+0.0000i |11⟩\n+0.0000-0.7000i |10⟩\n"
+        );
+        assert_eq!(
+            simulation.state_string(0.05),
+            "+0.7071+0.0000i |11⟩\n+0.0000-0.7000i |10⟩\n+0.1000+0.0000i |00⟩\n"
+        );
+
+        // The qubits of the basis states follow a swap of the qubits.
+        let mut simulation = QuantumSimulation::new(3, 0u64);
+        simulation.pauli_x(2);
+        simulation.hadamard(0);
+        simulation.cnot(0, 1);
+        simulation.swap(1, 2);
+        simulation.rz(std::f64::consts::PI, 2);
+        simulation.ry(0.5, 1);
+        assert_eq!(
+            simulation.state_string(0.5),
+            "+0.0000-0.6851i |010⟩\n+0.0000+0.6851i |111⟩\n"
+        );
+    }
+
+    #[test]
+    fn gates_on_distinct_qubits_are_unitary() {
+        const QUBIT_COUNT: usize = 3;
+        let mut gates = Vec::new();
+        for q0 in 0..QUBIT_COUNT {
+            for q1 in (0..QUBIT_COUNT).filter(|&q1| q1 != q0) {
+                gates.push(Gate::Cnot(q0, q1));
+                gates.push(Gate::Cz(q0, q1));
+                gates.push(Gate::Cphase(0.7, q0, q1));
+                gates.push(Gate::Swap(q0, q1));
+                gates.push(Gate::UF(|x| !x, q0, q1));
+                for q2 in (0..QUBIT_COUNT).filter(|&q2| q2 != q0 && q2 != q1) {
+                    gates.push(Gate::Toffoli(q0, q1, q2));
+                }
+            }
+        }
+        for gate in gates {
+            // The columns of the matrix of the gate, the images of the basis states.
+            let columns: Vec<Vec<Complex<f64>>> = (0..1 << QUBIT_COUNT)
+                .map(|state_index: usize| {
+                    let mut simulation = QuantumSimulation::new(QUBIT_COUNT, 0u64);
+                    for qubit_number in (0..QUBIT_COUNT).filter(|&q| (state_index >> q) & 1 == 1) {
+                        simulation.pauli_x(qubit_number);
+                    }
+                    gate.apply(&mut simulation);
+                    simulation.amplitudes().to_vec()
+                })
+                .collect();
+            for (i, column_i) in columns.iter().enumerate() {
+                for (j, column_j) in columns.iter().enumerate() {
+                    let product: Complex<f64> = column_i
+                        .iter()
+                        .zip(column_j)
+                        .map(|(a, b)| a.conj() * b)
+                        .sum();
+                    let expected = if i == j { 1.0 } else { 0.0 };
+                    assert!(
+                        (product - expected).norm() < 1e-12,
+                        "The gate {:?} is not unitary.",
+                        gate
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn states_are_set() {
+        let mut simulation = QuantumSimulation::new(2, 0u64);
+        simulation.swap(0, 1);
+        let amplitude = std::f64::consts::FRAC_1_SQRT_2;
+        let zero = Complex::new(0.0, 0.0);
+        // The state (|01⟩ + i|10⟩)/sqrt(2), not symmetric in the qubits.
+        let state = vec![
+            zero,
+            Complex::new(amplitude, 0.0),
+            Complex::new(0.0, amplitude),
+            zero,
+        ];
+        simulation.set_state(state.clone());
+        assert!((simulation.fidelity(&state) - 1.0).abs() < 1e-12);
+        let values = simulation.measure_all();
+        assert_ne!(values[0], values[1]);
+
+        simulation.prepare_basis_state(2);
+        assert_eq!(simulation.measure_all(), vec![false, true]);
+    }
 }
@@ -0,0 +1,82 @@
+/*
+Sampling of a discrete distribution with the alias method of Vose.
+
+The table takes one pass over the probabilities to build, and then draws each sample in constant
+time with two random numbers: a uniform entry, and whether to return the entry or its alias.
+The entries are filled to the average probability by moving the excess of the entries above
+the average to the entries below it, which become their aliases.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use rand::Rng;
+
+pub(super) struct AliasTable {
+    // The probabilities of returning the entry instead of its alias.
+    thresholds: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl AliasTable {
+    // Build the table of the probabilities, which are normalized by their sum.
+    pub(super) fn new(probabilities: &[f64]) -> AliasTable {
+        let length = probabilities.len();
+        let total: f64 = probabilities.iter().sum();
+        let mut thresholds: Vec<f64> = probabilities
+            .iter()
+            .map(|&p| p * length as f64 / total)
+            .collect();
+        let mut aliases: Vec<usize> = (0..length).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..length).partition(|&i| thresholds[i] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            aliases[s] = l;
+            thresholds[l] -= 1.0 - thresholds[s];
+            if thresholds[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // The remaining entries are at the average up to the rounding errors.
+        for i in small.into_iter().chain(large) {
+            thresholds[i] = 1.0;
+        }
+        AliasTable {
+            thresholds,
+            aliases,
+        }
+    }
+
+    pub(super) fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let i = rng.gen_range(0..self.thresholds.len());
+        if rng.gen::<f64>() < self.thresholds[i] {
+            i
+        } else {
+            self.aliases[i]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    #[test]
+    fn samples_follow_probabilities() {
+        let probabilities = [0.5, 0.0, 0.125, 0.375];
+        let table = AliasTable::new(&probabilities);
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+        let mut counts = [0usize; 4];
+        let sample_count = 100000;
+        for _ in 0..sample_count {
+            counts[table.sample(&mut rng)] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        for (&count, &probability) in counts.iter().zip(probabilities.iter()) {
+            assert!((count as f64 / sample_count as f64 - probability).abs() < 0.01);
+        }
+    }
+}
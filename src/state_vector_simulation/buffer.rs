@@ -0,0 +1,225 @@
+/*
+Allocation of the amplitudes of the state vector.
+
+By default the amplitudes are in a Vec, aligned to the 8 bytes of an f64. With the alloc options
+they are instead allocated in blocks of 64 bytes aligned to 64 bytes, the size of a cache line and
+of an AVX-512 register, so that no SIMD load or store of the kernels straddles two cache lines.
+On Linux the allocation can also be advised to be backed by transparent huge pages of 2 MiB
+instead of pages of 4 KiB, so that the passes over the state vectors of 2^26 amplitudes (1 GiB)
+and more do not miss the TLB at every page. The advice is given before the amplitudes are zeroed,
+so that the pages are huge from their first touch.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+use num_complex::Complex;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const ALIGNMENT: usize = 64;
+const BLOCK_LENGTH: usize = ALIGNMENT / std::mem::size_of::<Complex<f64>>();
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocOptions {
+    // Whether the amplitudes are aligned to 64 bytes.
+    pub is_aligned: bool,
+    // Whether the amplitudes are advised to be backed by huge pages, on Linux only.
+    pub uses_huge_pages: bool,
+}
+
+// The repr(C) array of repr(C) complex numbers has no padding, so the blocks are
+// the amplitudes one after the other.
+#[derive(Clone, Copy, Debug)]
+#[repr(C, align(64))]
+pub(super) struct AlignedBlock([Complex<f64>; BLOCK_LENGTH]);
+
+#[derive(Clone, Debug)]
+pub(super) enum AmplitudeBuffer {
+    Vec(Vec<Complex<f64>>),
+    Aligned {
+        blocks: Vec<AlignedBlock>,
+        length: usize,
+    },
+}
+
+impl AmplitudeBuffer {
+    // The given number of zero amplitudes.
+    pub(super) fn zeros(length: usize, options: AllocOptions) -> AmplitudeBuffer {
+        let zero = Complex::new(0.0, 0.0);
+        if !options.is_aligned {
+            let mut amplitudes = Vec::new();
+            reserve(&mut amplitudes, length);
+            if options.uses_huge_pages {
+                advise_huge_pages(&mut amplitudes);
+            }
+            amplitudes.resize(length, zero);
+            return AmplitudeBuffer::Vec(amplitudes);
+        }
+        let block_count = length.div_ceil(BLOCK_LENGTH);
+        let mut blocks = Vec::new();
+        reserve(&mut blocks, block_count);
+        if options.uses_huge_pages {
+            advise_huge_pages(&mut blocks);
+        }
+        blocks.resize(block_count, AlignedBlock([zero; BLOCK_LENGTH]));
+        AmplitudeBuffer::Aligned { blocks, length }
+    }
+
+    // The amplitudes in a buffer allocated with the options, copied unless they are a Vec.
+    pub(super) fn from_amplitudes(
+        amplitudes: Vec<Complex<f64>>,
+        options: AllocOptions,
+    ) -> AmplitudeBuffer {
+        if options == AllocOptions::default() {
+            return AmplitudeBuffer::Vec(amplitudes);
+        }
+        let mut buffer = AmplitudeBuffer::zeros(amplitudes.len(), options);
+        buffer.copy_from_slice(&amplitudes);
+        buffer
+    }
+}
+
+impl From<Vec<Complex<f64>>> for AmplitudeBuffer {
+    fn from(amplitudes: Vec<Complex<f64>>) -> AmplitudeBuffer {
+        AmplitudeBuffer::Vec(amplitudes)
+    }
+}
+
+impl Deref for AmplitudeBuffer {
+    type Target = [Complex<f64>];
+
+    fn deref(&self) -> &[Complex<f64>] {
+        match self {
+            AmplitudeBuffer::Vec(amplitudes) => amplitudes,
+            AmplitudeBuffer::Aligned { blocks, length } => {
+                let amplitudes = unsafe {
+                    std::slice::from_raw_parts(
+                        blocks.as_ptr() as *const Complex<f64>,
+                        blocks.len() * BLOCK_LENGTH,
+                    )
+                };
+                &amplitudes[..*length]
+            }
+        }
+    }
+}
+
+impl DerefMut for AmplitudeBuffer {
+    fn deref_mut(&mut self) -> &mut [Complex<f64>] {
+        match self {
+            AmplitudeBuffer::Vec(amplitudes) => amplitudes,
+            AmplitudeBuffer::Aligned { blocks, length } => {
+                let amplitudes = unsafe {
+                    std::slice::from_raw_parts_mut(
+                        blocks.as_mut_ptr() as *mut Complex<f64>,
+                        blocks.len() * BLOCK_LENGTH,
+                    )
+                };
+                &mut amplitudes[..*length]
+            }
+        }
+    }
+}
+
+// The amplitudes are serialized as a sequence, whatever their allocation.
+#[cfg(feature = "serde")]
+impl Serialize for AmplitudeBuffer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.deref().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for AmplitudeBuffer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<AmplitudeBuffer, D::Error> {
+        Vec::deserialize(deserializer).map(AmplitudeBuffer::Vec)
+    }
+}
+
+// An empty Vec with the capacity of the amplitudes read from a file,
+// or an error if they do not fit in the memory.
+pub(super) fn with_capacity(length: usize) -> io::Result<Vec<Complex<f64>>> {
+    let mut amplitudes = Vec::new();
+    amplitudes.try_reserve_exact(length).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::OutOfMemory,
+            "The amplitudes of the file do not fit in the memory.",
+        )
+    })?;
+    Ok(amplitudes)
+}
+
+fn reserve<T>(buffer: &mut Vec<T>, length: usize) {
+    if buffer.try_reserve_exact(length).is_err() {
+        panic!(
+            "The {} bytes of the amplitudes of the simulation do not fit in the memory.",
+            length as u128 * std::mem::size_of::<T>() as u128
+        );
+    }
+}
+
+// Advise the kernel to back the whole pages of the allocated capacity with huge pages.
+// The advice is only a hint, and if it is not taken the usual pages are used.
+#[cfg(target_os = "linux")]
+fn advise_huge_pages<T>(buffer: &mut Vec<T>) {
+    use std::ffi::{c_int, c_void};
+
+    extern "C" {
+        fn madvise(address: *mut c_void, length: usize, advice: c_int) -> c_int;
+    }
+    const MADV_HUGEPAGE: c_int = 14;
+    const PAGE_SIZE: usize = 4096;
+
+    let start = buffer.as_mut_ptr() as usize;
+    let end = start + buffer.capacity() * std::mem::size_of::<T>();
+    let (page_start, page_end) = (
+        start.next_multiple_of(PAGE_SIZE),
+        end / PAGE_SIZE * PAGE_SIZE,
+    );
+    if page_start < page_end {
+        unsafe {
+            madvise(
+                page_start as *mut c_void,
+                page_end - page_start,
+                MADV_HUGEPAGE,
+            );
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn advise_huge_pages<T>(_buffer: &mut Vec<T>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn aligned_amplitudes_match_vec_amplitudes() {
+        let options = AllocOptions {
+            is_aligned: true,
+            uses_huge_pages: true,
+        };
+        let mut simulation = QuantumSimulation::new(5, 0u64);
+        let mut aligned_simulation = QuantumSimulation::with_alloc_options(5, 0u64, options);
+        for simulation in [&mut simulation, &mut aligned_simulation] {
+            simulation.hadamard(0);
+            simulation.ry(0.4, 3);
+            simulation.cnot(0, 4);
+            simulation.toffoli(3, 4, 1);
+        }
+        assert_eq!(
+            aligned_simulation.amplitudes().as_ptr() as usize % ALIGNMENT,
+            0
+        );
+        assert_eq!(aligned_simulation.amplitudes(), simulation.amplitudes());
+
+        let buffer = AmplitudeBuffer::zeros(2, options);
+        assert_eq!(buffer.len(), 2);
+    }
+}
@@ -0,0 +1,181 @@
+/*
+Binary checkpoints of the state vector.
+
+A checkpoint starts with a 16-byte header: the magic bytes QSIM, the format version,
+the endianness of the numbers (0 for little-endian, 1 for big-endian), the precision
+as the number of bytes of a floating-point number (4 or 8), a reserved byte, the qubit
+count as a 32-bit unsigned integer, and 4 reserved bytes. The real and imaginary parts
+of the amplitudes follow in the order of the basis state indices. Checkpoints are written
+little-endian with double precision, and either endianness and precision can be loaded.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use num_complex::Complex;
+
+use super::buffer::{self, AmplitudeBuffer};
+use crate::noise::ReadoutError;
+use crate::state_vector_simulation::{QuantumSimulation, MAX_QUBIT_COUNT};
+
+const MAGIC: &[u8; 4] = b"QSIM";
+const VERSION: u8 = 1;
+const LITTLE_ENDIAN: u8 = 0;
+const BIG_ENDIAN: u8 = 1;
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_number(reader: &mut impl Read, precision: u8, is_big_endian: bool) -> io::Result<f64> {
+    if precision == 4 {
+        let mut bytes = [0u8; 4];
+        reader.read_exact(&mut bytes)?;
+        Ok(if is_big_endian {
+            f32::from_be_bytes(bytes)
+        } else {
+            f32::from_le_bytes(bytes)
+        } as f64)
+    } else {
+        let mut bytes = [0u8; 8];
+        reader.read_exact(&mut bytes)?;
+        Ok(if is_big_endian {
+            f64::from_be_bytes(bytes)
+        } else {
+            f64::from_le_bytes(bytes)
+        })
+    }
+}
+
+impl QuantumSimulation {
+    // Write the amplitudes to the checkpoint file. The state of the random number generator is not saved.
+    pub fn save_state<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, LITTLE_ENDIAN, 8, 0])?;
+        writer.write_all(&(self.qubit_count as u32).to_le_bytes())?;
+        writer.write_all(&[0u8; 4])?;
+        for amplitude in self.ordered_amplitudes() {
+            writer.write_all(&amplitude.re.to_le_bytes())?;
+            writer.write_all(&amplitude.im.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    // Replace the qubits and the amplitudes by the ones of the checkpoint file,
+    // keeping the random number generator.
+    pub fn load_state<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = [0u8; 16];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data(
+                "The file is not a state checkpoint.".to_string(),
+            ));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data(format!(
+                "The checkpoint version {} is not supported.",
+                header[4]
+            )));
+        }
+        let is_big_endian = match header[5] {
+            LITTLE_ENDIAN => false,
+            BIG_ENDIAN => true,
+            endianness => {
+                return Err(invalid_data(format!(
+                    "The endianness {} is not supported.",
+                    endianness
+                )))
+            }
+        };
+        let precision = header[6];
+        if precision != 4 && precision != 8 {
+            return Err(invalid_data(format!(
+                "The precision of {} bytes is not supported.",
+                precision
+            )));
+        }
+        let count_bytes = [header[8], header[9], header[10], header[11]];
+        let qubit_count = if is_big_endian {
+            u32::from_be_bytes(count_bytes)
+        } else {
+            u32::from_le_bytes(count_bytes)
+        } as usize;
+        if qubit_count > MAX_QUBIT_COUNT {
+            return Err(invalid_data(format!(
+                "The number of qubits in the simulation cannot exceed {}.",
+                MAX_QUBIT_COUNT
+            )));
+        }
+
+        let mut amplitudes = buffer::with_capacity(1 << qubit_count)?;
+        for _ in 0..1usize << qubit_count {
+            let re = read_number(&mut reader, precision, is_big_endian)?;
+            let im = read_number(&mut reader, precision, is_big_endian)?;
+            amplitudes.push(Complex::new(re, im));
+        }
+        if reader.read(&mut [0u8; 1])? != 0 {
+            return Err(invalid_data(
+                "The checkpoint has more amplitudes than its qubits.".to_string(),
+            ));
+        }
+        self.qubit_count = qubit_count;
+        self.amplitudes = AmplitudeBuffer::from_amplitudes(amplitudes, self.alloc_options);
+        self.qubit_map = (0..qubit_count).collect();
+        // The readout errors of the qubits kept by the checkpoint stay, the other qubits have none.
+        if !self.readout_errors.is_empty() {
+            self.readout_errors
+                .resize(qubit_count, ReadoutError::default());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+
+    #[test]
+    fn checkpoint_round_trips() {
+        let mut simulation = QuantumSimulation::new(3, 0u64);
+        simulation.hadamard(0);
+        simulation.rx(0.4, 1);
+        simulation.cnot(0, 2);
+        let path = std::env::temp_dir().join(format!("checkpoint_{}.qsim", std::process::id()));
+        simulation.save_state(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 16 + 8 * 16);
+
+        let mut loaded = QuantumSimulation::new(1, 0u64);
+        loaded.load_state(&path).unwrap();
+        assert_eq!(loaded.amplitudes(), simulation.amplitudes());
+        loaded.pauli_x(2);
+        simulation.pauli_x(2);
+        assert_eq!(loaded.amplitudes(), simulation.amplitudes());
+
+        std::fs::write(&path, b"QSIM\x02").unwrap();
+        assert!(loaded.load_state(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn readout_errors_follow_loaded_qubits() {
+        let simulation = QuantumSimulation::new(3, 0u64);
+        let path = std::env::temp_dir().join(format!("readout_{}.qsim", std::process::id()));
+        simulation.save_state(&path).unwrap();
+
+        let readout_error = ReadoutError::new(0.1, 0.2);
+        let mut loaded = QuantumSimulation::new(1, 0u64);
+        loaded.set_readout_error(0, readout_error);
+        loaded.load_state(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.readout_error(0), readout_error);
+        assert_eq!(loaded.readout_error(2), ReadoutError::default());
+        loaded.set_readout_error(2, readout_error);
+        assert_eq!(loaded.readout_error(2), readout_error);
+    }
+}
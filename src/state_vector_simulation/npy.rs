@@ -0,0 +1,174 @@
+/*
+NumPy .npy files of the state vector.
+
+The amplitudes are stored as a one-dimensional complex array indexed by the bits of the qubit
+numbers, the order of the state vectors of Qiskit. The arrays are written as little-endian
+complex128 in the version 1.0 format, and complex64 or complex128 arrays of either endianness
+in the versions 1.0 to 3.0 can be read.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use num_complex::Complex;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use super::buffer;
+use crate::state_vector_simulation::{AllocOptions, QuantumSimulation, MAX_QUBIT_COUNT};
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+// The header is padded so that the data starts at a multiple of the alignment.
+const HEADER_ALIGNMENT: usize = 64;
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// The value of the key in the header dictionary, up to the next comma outside of parentheses.
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find([',', '}'])?
+    };
+    Some(rest[..end].trim())
+}
+
+impl QuantumSimulation {
+    // Write the amplitudes as a complex128 NumPy array.
+    pub fn to_npy<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut header = format!(
+            "{{'descr': '<c16', 'fortran_order': False, 'shape': ({},), }}",
+            self.amplitudes.len()
+        );
+        let unpadded_length = MAGIC.len() + 4 + header.len() + 1;
+        let padding = (HEADER_ALIGNMENT - unpadded_length % HEADER_ALIGNMENT) % HEADER_ALIGNMENT;
+        header += &" ".repeat(padding);
+        header += "\n";
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[1, 0])?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        for amplitude in self.ordered_amplitudes() {
+            writer.write_all(&amplitude.re.to_le_bytes())?;
+            writer.write_all(&amplitude.im.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    // Create a simulation with the amplitudes of a one-dimensional complex NumPy array,
+    // whose length is a power of 2. The amplitudes are not normalized.
+    pub fn from_npy<P: AsRef<Path>>(path: P, rnd_seed: u64) -> io::Result<QuantumSimulation> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut preamble = [0u8; 8];
+        reader.read_exact(&mut preamble)?;
+        if &preamble[0..6] != MAGIC {
+            return Err(invalid_data("The file is not a NumPy array.".to_string()));
+        }
+        let header_length = match preamble[6] {
+            1 => {
+                let mut bytes = [0u8; 2];
+                reader.read_exact(&mut bytes)?;
+                u16::from_le_bytes(bytes) as usize
+            }
+            2 | 3 => {
+                let mut bytes = [0u8; 4];
+                reader.read_exact(&mut bytes)?;
+                u32::from_le_bytes(bytes) as usize
+            }
+            version => {
+                return Err(invalid_data(format!(
+                    "The NumPy format version {} is not supported.",
+                    version
+                )))
+            }
+        };
+        let mut header = vec![0u8; header_length];
+        reader.read_exact(&mut header)?;
+        let header = String::from_utf8_lossy(&header);
+
+        let descr = header_value(&header, "descr").unwrap_or("");
+        let (is_big_endian, precision) = match descr.trim_matches(|c| c == '\'' || c == '"') {
+            "<c16" => (false, 8),
+            ">c16" => (true, 8),
+            "<c8" => (false, 4),
+            ">c8" => (true, 4),
+            _ => {
+                return Err(invalid_data(format!(
+                    "The data type {} is not a complex type.",
+                    descr
+                )))
+            }
+        };
+        let shape = header_value(&header, "shape").unwrap_or("");
+        let length = shape
+            .trim_matches(|c| c == '(' || c == ')')
+            .trim_end_matches(',')
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| invalid_data(format!("The shape {} is not one-dimensional.", shape)))?;
+        let qubit_count = length.trailing_zeros() as usize;
+        if qubit_count > MAX_QUBIT_COUNT || length != 1 << qubit_count {
+            return Err(invalid_data(format!(
+                "The length {} of the array is not a power of 2 up to 2^{}.",
+                length, MAX_QUBIT_COUNT
+            )));
+        }
+
+        let mut amplitudes = buffer::with_capacity(length)?;
+        let mut bytes = [0u8; 16];
+        for _ in 0..length {
+            let bytes = &mut bytes[..2 * precision];
+            reader.read_exact(bytes)?;
+            let (re, im) = bytes.split_at(precision);
+            let number = |part: &[u8]| -> f64 {
+                match (precision, is_big_endian) {
+                    (4, false) => f32::from_le_bytes(part.try_into().unwrap()) as f64,
+                    (4, true) => f32::from_be_bytes(part.try_into().unwrap()) as f64,
+                    (_, false) => f64::from_le_bytes(part.try_into().unwrap()),
+                    (_, true) => f64::from_be_bytes(part.try_into().unwrap()),
+                }
+            };
+            amplitudes.push(Complex::new(number(re), number(im)));
+        }
+        Ok(QuantumSimulation {
+            qubit_count,
+            amplitudes: amplitudes.into(),
+            alloc_options: AllocOptions::default(),
+            qubit_map: (0..qubit_count).collect(),
+            readout_errors: Vec::new(),
+            rng: ChaCha12Rng::seed_from_u64(rnd_seed),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::Simulation;
+
+    #[test]
+    fn npy_round_trips() {
+        let mut simulation = QuantumSimulation::new(2, 0u64);
+        simulation.hadamard(0);
+        simulation.ry(0.3, 1);
+        simulation.s(0);
+        let path = std::env::temp_dir().join(format!("state_{}.npy", std::process::id()));
+        simulation.to_npy(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!((bytes.len() - 4 * 16) % HEADER_ALIGNMENT, 0);
+        assert!(String::from_utf8_lossy(&bytes).contains("'shape': (4,)"));
+
+        let loaded = QuantumSimulation::from_npy(&path, 0u64).unwrap();
+        assert_eq!(loaded.amplitudes(), simulation.amplitudes());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
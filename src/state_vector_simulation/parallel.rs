@@ -0,0 +1,257 @@
+/*
+Parallel gate application on the state vector with rayon.
+
+The single-qubit kernels update the halves of the chunks with the qubit 0 and 1, many chunks
+or parts of a chunk at a time. For the gates on more qubits,
+the amplitudes mixed by a gate differ only in the bits of its qubits, so the state vector is
+split recursively: a slice twice as long as the stride of the highest remaining qubit is split
+into its halves with the qubit 0 and 1, which stay together as a group, and any longer slices
+of a group are split into halves handled by separate threads. The amplitudes of each group
+are then updated independently, and a group below MIN_PARALLEL_LENGTH amplitudes is updated
+by a sequential loop.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+use rayon::prelude::*;
+
+// The smallest number of qubits whose gates are applied in parallel,
+// below which starting the threads costs more than the loops.
+pub(super) const MIN_QUBIT_COUNT: usize = 16;
+// The smallest number of amplitudes updated by a thread.
+const MIN_PARALLEL_LENGTH: usize = 1 << 14;
+
+// The strides of the qubits of a gate from the highest qubit to the lowest, and for each
+// combination of the bits of the qubits in the order of the gate its position in a group,
+// whose amplitudes are ordered by the bits of the qubits from the highest to the lowest.
+fn layout<const N: usize>(qubit_numbers: &[usize]) -> (Vec<usize>, [usize; N]) {
+    let mut sorted_qubit_numbers = qubit_numbers.to_vec();
+    sorted_qubit_numbers.sort_unstable();
+    let strides = sorted_qubit_numbers.iter().rev().map(|&q| 1 << q).collect();
+    let positions = std::array::from_fn(|c| {
+        qubit_numbers
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| (c >> i) & 1 == 1)
+            .map(|(_, q)| 1 << sorted_qubit_numbers.binary_search(q).unwrap())
+            .sum()
+    });
+    (strides, positions)
+}
+
+// Update the groups of the amplitudes spanned by the remaining strides within the slices.
+fn apply_sequentially<K, const N: usize>(
+    slices: &mut [&mut [Complex<f64>]],
+    strides: &[usize],
+    positions: &[usize; N],
+    kernel: &K,
+) where
+    K: Fn(&mut [Complex<f64>; N]),
+{
+    // The slice and the offset of each amplitude of a group in the order of the gate,
+    // the remaining strides being the lowest bits of the position.
+    let offset_count = 1usize << strides.len();
+    let slots: [(usize, usize); N] = std::array::from_fn(|c| {
+        let j = positions[c];
+        let offset = strides
+            .iter()
+            .rev()
+            .enumerate()
+            .filter(|&(bit, _)| (j >> bit) & 1 == 1)
+            .map(|(_, &stride)| stride)
+            .sum();
+        (j / offset_count, offset)
+    });
+    let mut values = [Complex::new(0.0, 0.0); N];
+    for g in 0..slices[0].len() >> strides.len() {
+        // The index of the group with zero bits inserted at the remaining strides.
+        let mut i = g;
+        for &stride in strides.iter().rev() {
+            i = (i & !(stride - 1)) << 1 | (i & (stride - 1));
+        }
+        for (value, &(p, offset)) in values.iter_mut().zip(slots.iter()) {
+            *value = slices[p][i + offset];
+        }
+        kernel(&mut values);
+        for (value, &(p, offset)) in values.iter().zip(slots.iter()) {
+            slices[p][i + offset] = *value;
+        }
+    }
+}
+
+fn apply_group<K, const N: usize>(
+    mut slices: Vec<&mut [Complex<f64>]>,
+    strides: &[usize],
+    positions: &[usize; N],
+    kernel: &K,
+) where
+    K: Fn(&mut [Complex<f64>; N]) + Sync,
+{
+    let length = slices[0].len();
+    if length * slices.len() < MIN_PARALLEL_LENGTH {
+        apply_sequentially(&mut slices, strides, positions, kernel);
+        return;
+    }
+    match strides.first() {
+        Some(&stride) if length == 2 * stride => {
+            let group = slices
+                .into_iter()
+                .flat_map(|slice| {
+                    let (zeros, ones) = slice.split_at_mut(stride);
+                    [zeros, ones]
+                })
+                .collect();
+            apply_group(group, &strides[1..], positions, kernel);
+        }
+        _ => {
+            let (left, right): (Vec<_>, Vec<_>) = slices
+                .into_iter()
+                .map(|slice| slice.split_at_mut(length / 2))
+                .unzip();
+            rayon::join(
+                || apply_group(left, strides, positions, kernel),
+                || apply_group(right, strides, positions, kernel),
+            );
+        }
+    }
+}
+
+// Apply the kernel to the halves of the chunks of twice the stride, many short chunks
+// or parts of the long chunks at a time.
+pub(super) fn apply_to_halves<F>(amplitudes: &mut [Complex<f64>], kernel: &F, stride: usize)
+where
+    F: Fn(&mut [Complex<f64>], &mut [Complex<f64>]) + Sync,
+{
+    if 2 * stride <= MIN_PARALLEL_LENGTH {
+        amplitudes
+            .par_chunks_mut(MIN_PARALLEL_LENGTH)
+            .for_each(|chunks| {
+                for chunk in chunks.chunks_exact_mut(2 * stride) {
+                    let (zeros, ones) = chunk.split_at_mut(stride);
+                    kernel(zeros, ones);
+                }
+            });
+    } else {
+        amplitudes.par_chunks_mut(2 * stride).for_each(|chunk| {
+            let (zeros, ones) = chunk.split_at_mut(stride);
+            zeros
+                .par_chunks_mut(MIN_PARALLEL_LENGTH / 2)
+                .zip(ones.par_chunks_mut(MIN_PARALLEL_LENGTH / 2))
+                .for_each(|(zeros, ones)| kernel(zeros, ones));
+        });
+    }
+}
+
+// Apply the function to the pieces of the amplitudes with the index of their first amplitude.
+pub(super) fn apply_to_pieces<F>(amplitudes: &mut [Complex<f64>], f: &F)
+where
+    F: Fn(usize, &mut [Complex<f64>]) + Sync,
+{
+    amplitudes
+        .par_chunks_mut(MIN_PARALLEL_LENGTH)
+        .enumerate()
+        .for_each(|(k, piece)| f(k * MIN_PARALLEL_LENGTH, piece));
+}
+
+// Zero the amplitudes, many pieces at a time.
+pub(super) fn clear(amplitudes: &mut [Complex<f64>]) {
+    amplitudes
+        .par_chunks_mut(MIN_PARALLEL_LENGTH)
+        .for_each(|chunk| chunk.fill(Complex::new(0.0, 0.0)));
+}
+
+pub(super) fn apply_two_qubit_gate<F>(
+    amplitudes: &mut [Complex<f64>],
+    two_qubit_gate: &F,
+    qubit_number0: usize,
+    qubit_number1: usize,
+) where
+    F: Fn(
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+        ) -> (Complex<f64>, Complex<f64>, Complex<f64>, Complex<f64>)
+        + Sync,
+{
+    let (strides, positions) = layout(&[qubit_number0, qubit_number1]);
+    let kernel = |a: &mut [Complex<f64>; 4]| {
+        (a[0], a[1], a[2], a[3]) = two_qubit_gate(a[0], a[1], a[2], a[3]);
+    };
+    apply_group(vec![amplitudes], &strides, &positions, &kernel);
+}
+
+pub(super) fn apply_three_qubit_gate<F>(
+    amplitudes: &mut [Complex<f64>],
+    three_qubit_gate: &F,
+    qubit_number0: usize,
+    qubit_number1: usize,
+    qubit_number2: usize,
+) where
+    F: Fn(
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+        ) -> (
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+            Complex<f64>,
+        ) + Sync,
+{
+    let (strides, positions) = layout(&[qubit_number0, qubit_number1, qubit_number2]);
+    let kernel = |a: &mut [Complex<f64>; 8]| {
+        (a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7]) =
+            three_qubit_gate(a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7]);
+    };
+    apply_group(vec![amplitudes], &strides, &positions, &kernel);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::Gate;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn parallel_gates_match_sequential_gates() {
+        let qubit_count = super::MIN_QUBIT_COUNT + 2;
+        let top = qubit_count - 1;
+        let gates = [
+            Gate::Hadamard(0),
+            Gate::Hadamard(top),
+            Gate::Ry(0.7, top - 1),
+            Gate::Rx(0.3, 5),
+            Gate::Cnot(top, 0),
+            Gate::Cnot(1, top - 1),
+            Gate::Cphase(0.9, top - 1, top),
+            Gate::Swap(top, 2),
+            Gate::UF(|x| !x, 3, top),
+            Gate::Toffoli(top, 0, top - 2),
+            Gate::Toffoli(1, top - 1, top),
+            Gate::Toffoli(top - 2, top, 4),
+            Gate::T(top),
+        ];
+        // The layers run the gates on the small simulations of their qubits, sequentially.
+        let mut parallel_simulation = QuantumSimulation::new(qubit_count, 0u64);
+        let mut sequential_simulation = QuantumSimulation::new(qubit_count, 0u64);
+        for gate in gates.iter() {
+            gate.apply(&mut parallel_simulation);
+            sequential_simulation.apply_layer(&[*gate]);
+        }
+        assert!(parallel_simulation
+            .amplitudes()
+            .iter()
+            .zip(sequential_simulation.amplitudes().iter())
+            .all(|(a, b)| (a - b).norm() < 1e-12));
+    }
+}
@@ -0,0 +1,161 @@
+/*
+Merged application of the diagonal gates Z, S, T, RZ, CZ, and CPHASE.
+
+The diagonal gates only multiply each amplitude by a phase depending on the bits of its index,
+so a run of them is merged into the phase factors of the values of each qubit and the phases of
+the pairs of qubits both 1. The factors are tabulated for each byte of the index, and for each
+two bytes holding the qubits of a pair, so that the phase of an amplitude is the product of a
+few table entries however many gates were merged, and the state vector is updated in one pass.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::BTreeMap;
+
+use num_complex::Complex;
+
+use crate::circuit::Gate;
+
+// The bits of the index of a table.
+const BYTE_BITS: usize = 8;
+const BYTE_MASK: usize = (1 << BYTE_BITS) - 1;
+
+pub(super) struct PhaseTable {
+    // The phase of each value of the bits of a byte of the index.
+    byte_phases: Vec<Vec<Complex<f64>>>,
+    // The phase of each value of the bits of two bytes of the index, for the pairs of qubits in
+    // different bytes, by the two bytes.
+    byte_pair_phases: Vec<(usize, usize, Vec<Complex<f64>>)>,
+}
+
+impl PhaseTable {
+    pub(super) fn new(gates: &[Gate], qubit_count: usize) -> PhaseTable {
+        let one = Complex::new(1.0, 0.0);
+        // The factors of the values 0 and 1 of each qubit, and of each pair of qubits both 1.
+        let mut qubit_factors = vec![[one, one]; qubit_count];
+        let mut pair_factors: BTreeMap<(usize, usize), Complex<f64>> = BTreeMap::new();
+        for gate in gates.iter() {
+            match *gate {
+                Gate::PauliZ(q) => qubit_factors[q][1] *= -one,
+                Gate::S(q) => qubit_factors[q][1] *= Complex::new(0.0, 1.0),
+                Gate::T(q) => {
+                    qubit_factors[q][1] *= Complex::new(0.0, std::f64::consts::FRAC_PI_4).exp()
+                }
+                Gate::Rz(theta, q) => {
+                    let phase = Complex::new(0.0, theta / 2.0).exp();
+                    qubit_factors[q][0] *= phase.conj();
+                    qubit_factors[q][1] *= phase;
+                }
+                Gate::Cz(q0, q1) => {
+                    *pair_factors.entry((q0.min(q1), q0.max(q1))).or_insert(one) *= -one
+                }
+                Gate::Cphase(theta, q0, q1) => {
+                    *pair_factors.entry((q0.min(q1), q0.max(q1))).or_insert(one) *=
+                        Complex::new(0.0, theta).exp()
+                }
+                _ => panic!("The gate {} is not diagonal.", gate.name()),
+            }
+        }
+
+        let byte_count = qubit_count.div_ceil(BYTE_BITS).max(1);
+        let mut byte_phases: Vec<Vec<Complex<f64>>> = (0..byte_count)
+            .map(|byte| {
+                (0..=BYTE_MASK)
+                    .map(|value| {
+                        (0..BYTE_BITS)
+                            .map(|bit| (byte * BYTE_BITS + bit, (value >> bit) & 1))
+                            .filter(|&(q, _)| q < qubit_count)
+                            .map(|(q, bit_value)| qubit_factors[q][bit_value])
+                            .product()
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut byte_pair_phases: Vec<(usize, usize, Vec<Complex<f64>>)> = Vec::new();
+        for (&(q0, q1), &factor) in pair_factors.iter() {
+            let (byte0, byte1) = (q0 / BYTE_BITS, q1 / BYTE_BITS);
+            let (bit0, bit1) = (q0 % BYTE_BITS, q1 % BYTE_BITS);
+            if byte0 == byte1 {
+                for (value, phase) in byte_phases[byte0].iter_mut().enumerate() {
+                    if (value >> bit0) & 1 == 1 && (value >> bit1) & 1 == 1 {
+                        *phase *= factor;
+                    }
+                }
+                continue;
+            }
+            if !byte_pair_phases
+                .iter()
+                .any(|&(b0, b1, _)| (b0, b1) == (byte0, byte1))
+            {
+                byte_pair_phases.push((byte0, byte1, vec![one; 1 << (2 * BYTE_BITS)]));
+            }
+            let phases = &mut byte_pair_phases
+                .iter_mut()
+                .find(|(b0, b1, _)| (*b0, *b1) == (byte0, byte1))
+                .unwrap()
+                .2;
+            // The entry of the values of the two bytes is value0 + value1 * 256.
+            for (value, phase) in phases.iter_mut().enumerate() {
+                if (value >> bit0) & 1 == 1 && (value >> (BYTE_BITS + bit1)) & 1 == 1 {
+                    *phase *= factor;
+                }
+            }
+        }
+        PhaseTable {
+            byte_phases,
+            byte_pair_phases,
+        }
+    }
+
+    // Multiply the amplitudes, the first of which has the index start, by their phases.
+    pub(super) fn apply(&self, amplitudes: &mut [Complex<f64>], start: usize) {
+        for (i, amplitude) in (start..).zip(amplitudes.iter_mut()) {
+            let mut phase = self.byte_phases[0][i & BYTE_MASK];
+            for (byte, phases) in self.byte_phases.iter().enumerate().skip(1) {
+                phase *= phases[(i >> (byte * BYTE_BITS)) & BYTE_MASK];
+            }
+            for (byte0, byte1, phases) in self.byte_pair_phases.iter() {
+                let value0 = (i >> (byte0 * BYTE_BITS)) & BYTE_MASK;
+                let value1 = (i >> (byte1 * BYTE_BITS)) & BYTE_MASK;
+                phase *= phases[value0 | value1 << BYTE_BITS];
+            }
+            *amplitude *= phase;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuit::Gate;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn merged_phases_match_gates() {
+        let gates = [
+            Gate::PauliZ(9),
+            Gate::S(0),
+            Gate::T(3),
+            Gate::Rz(0.3, 8),
+            Gate::Cz(1, 6),
+            Gate::Cphase(0.7, 2, 9),
+            Gate::Cphase(-1.1, 9, 5),
+            Gate::T(3),
+        ];
+        let mut simulation = QuantumSimulation::new(10, 0);
+        let mut merged_simulation = QuantumSimulation::new(10, 0);
+        for q in 0..10 {
+            simulation.hadamard(q);
+            merged_simulation.hadamard(q);
+        }
+        for gate in gates.iter() {
+            gate.apply(&mut simulation);
+        }
+        merged_simulation.apply_phases(&gates);
+        assert!(simulation
+            .amplitudes()
+            .iter()
+            .zip(merged_simulation.amplitudes().iter())
+            .all(|(a, b)| (a - b).norm() < 1e-12));
+    }
+}
@@ -0,0 +1,112 @@
+/*
+Serialization of the state vector simulation.
+
+The amplitudes are written in the order of the qubit numbers with the identity map of the
+qubits, so that a serialized simulation does not depend on the swaps not yet flushed.
+
+A simulation is checked when it is deserialized: the number of the amplitudes has to be
+2 to the power of the qubit count and the qubit map has to be a permutation of the qubits.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::borrow::Cow;
+
+use num_complex::Complex;
+use rand_chacha::ChaCha12Rng;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::buffer::{AllocOptions, AmplitudeBuffer};
+use crate::noise::ReadoutError;
+use crate::state_vector_simulation::{QuantumSimulation, MAX_QUBIT_COUNT};
+
+// The fields of a simulation as they are serialized.
+#[derive(Serialize)]
+struct SerializedSimulation<'a> {
+    qubit_count: usize,
+    amplitudes: Cow<'a, [Complex<f64>]>,
+    qubit_map: Vec<usize>,
+    readout_errors: &'a [ReadoutError],
+    rng: &'a ChaCha12Rng,
+}
+
+// The fields of a simulation as they are deserialized, before they are checked.
+#[derive(Deserialize)]
+struct DeserializedSimulation {
+    qubit_count: usize,
+    amplitudes: AmplitudeBuffer,
+    qubit_map: Vec<usize>,
+    #[serde(default)]
+    readout_errors: Vec<ReadoutError>,
+    rng: ChaCha12Rng,
+}
+
+impl Serialize for QuantumSimulation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedSimulation {
+            qubit_count: self.qubit_count,
+            amplitudes: self.amplitudes(),
+            qubit_map: (0..self.qubit_count).collect(),
+            readout_errors: &self.readout_errors,
+            rng: &self.rng,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl TryFrom<DeserializedSimulation> for QuantumSimulation {
+    type Error = String;
+
+    fn try_from(simulation: DeserializedSimulation) -> Result<QuantumSimulation, String> {
+        let qubit_count = simulation.qubit_count;
+        if qubit_count > MAX_QUBIT_COUNT {
+            return Err(format!(
+                "The number of qubits in the simulation cannot exceed {}.",
+                MAX_QUBIT_COUNT
+            ));
+        }
+        if simulation.amplitudes.len() != 1 << qubit_count {
+            return Err(format!(
+                "The number of the amplitudes has to be {} for {} qubits.",
+                1usize << qubit_count,
+                qubit_count
+            ));
+        }
+        let mut is_mapped = vec![false; qubit_count];
+        if simulation.qubit_map.len() != qubit_count
+            || !simulation.qubit_map.iter().all(|&physical_qubit| {
+                physical_qubit < qubit_count
+                    && !std::mem::replace(&mut is_mapped[physical_qubit], true)
+            })
+        {
+            return Err(format!(
+                "The qubit map has to be a permutation of the qubits {}.",
+                qubit_count
+            ));
+        }
+        if simulation.readout_errors.len() > qubit_count {
+            return Err(format!(
+                "The readout errors have to be at most one for each of the qubits {}.",
+                qubit_count
+            ));
+        }
+
+        Ok(QuantumSimulation {
+            qubit_count,
+            amplitudes: simulation.amplitudes,
+            alloc_options: AllocOptions::default(),
+            qubit_map: simulation.qubit_map,
+            readout_errors: simulation.readout_errors,
+            rng: simulation.rng,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for QuantumSimulation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<QuantumSimulation, D::Error> {
+        DeserializedSimulation::deserialize(deserializer)?
+            .try_into()
+            .map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,134 @@
+/*
+Vectorized kernels of the single-qubit gates X, Z, H, S, and T.
+
+The kernels update the contiguous halves of a chunk of the state vector with the qubit 0 and 1,
+so each amplitude is loaded and stored as an SSE2 vector of its real and imaginary parts
+instead of calling a gate closure for each pair of amplitudes. SSE2 is a part of every x86_64
+processor, so the intrinsics are always available there, and the other targets run the scalar
+gates in the same loops.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use num_complex::Complex;
+
+#[cfg(not(target_arch = "x86_64"))]
+use crate::gate;
+
+#[cfg(target_arch = "x86_64")]
+const INV_SQRT_2: f64 = 0.7071067811865475;
+
+// Complex is repr(C) with the real part first, two contiguous f64 values.
+#[cfg(target_arch = "x86_64")]
+unsafe fn load(amplitude: &Complex<f64>) -> __m128d {
+    _mm_loadu_pd(amplitude as *const Complex<f64> as *const f64)
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn store(amplitude: &mut Complex<f64>, value: __m128d) {
+    _mm_storeu_pd(amplitude as *mut Complex<f64> as *mut f64, value)
+}
+
+// Multiply the amplitudes by the phase.
+#[cfg(target_arch = "x86_64")]
+fn phase(amplitudes: &mut [Complex<f64>], phase: Complex<f64>) {
+    unsafe {
+        // (re + i im)(c + i s) = (re c - im s) + i (im c + re s)
+        let cos = _mm_set1_pd(phase.re);
+        let sin = _mm_set_pd(phase.im, -phase.im);
+        for amplitude in amplitudes.iter_mut() {
+            let value = load(amplitude);
+            let swapped = _mm_shuffle_pd(value, value, 1);
+            store(
+                amplitude,
+                _mm_add_pd(_mm_mul_pd(value, cos), _mm_mul_pd(swapped, sin)),
+            );
+        }
+    }
+}
+
+pub(super) fn pauli_x(zeros: &mut [Complex<f64>], ones: &mut [Complex<f64>]) {
+    zeros.swap_with_slice(ones);
+}
+
+pub(super) fn pauli_z(_zeros: &mut [Complex<f64>], ones: &mut [Complex<f64>]) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        let sign = _mm_set1_pd(-0.0);
+        for amplitude in ones.iter_mut() {
+            store(amplitude, _mm_xor_pd(load(amplitude), sign));
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    for (amplitude0, amplitude1) in _zeros.iter_mut().zip(ones.iter_mut()) {
+        (*amplitude0, *amplitude1) = gate::pauli_z(*amplitude0, *amplitude1);
+    }
+}
+
+pub(super) fn hadamard(zeros: &mut [Complex<f64>], ones: &mut [Complex<f64>]) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        let factor = _mm_set1_pd(INV_SQRT_2);
+        for (amplitude0, amplitude1) in zeros.iter_mut().zip(ones.iter_mut()) {
+            let value0 = load(amplitude0);
+            let value1 = load(amplitude1);
+            store(amplitude0, _mm_mul_pd(_mm_add_pd(value0, value1), factor));
+            store(amplitude1, _mm_mul_pd(_mm_sub_pd(value0, value1), factor));
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    for (amplitude0, amplitude1) in zeros.iter_mut().zip(ones.iter_mut()) {
+        (*amplitude0, *amplitude1) = gate::hadamard(*amplitude0, *amplitude1);
+    }
+}
+
+pub(super) fn s(_zeros: &mut [Complex<f64>], ones: &mut [Complex<f64>]) {
+    #[cfg(target_arch = "x86_64")]
+    phase(ones, Complex::new(0.0, 1.0));
+    #[cfg(not(target_arch = "x86_64"))]
+    for (amplitude0, amplitude1) in _zeros.iter_mut().zip(ones.iter_mut()) {
+        (*amplitude0, *amplitude1) = gate::s(*amplitude0, *amplitude1);
+    }
+}
+
+pub(super) fn t(_zeros: &mut [Complex<f64>], ones: &mut [Complex<f64>]) {
+    #[cfg(target_arch = "x86_64")]
+    phase(ones, Complex::new(INV_SQRT_2, INV_SQRT_2));
+    #[cfg(not(target_arch = "x86_64"))]
+    for (amplitude0, amplitude1) in _zeros.iter_mut().zip(ones.iter_mut()) {
+        (*amplitude0, *amplitude1) = gate::t(*amplitude0, *amplitude1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(target_arch = "x86_64")]
+    use crate::gate;
+
+    #[test]
+    fn kernels_match_gates() {
+        let amplitudes: Vec<Complex<f64>> = (0..8)
+            .map(|i| Complex::new((i as f64 * 0.7).sin(), (i as f64 * 1.3).cos()))
+            .collect();
+        type Kernel = fn(&mut [Complex<f64>], &mut [Complex<f64>]);
+        type Gate = fn(Complex<f64>, Complex<f64>) -> (Complex<f64>, Complex<f64>);
+        let pairs: [(Kernel, Gate); 4] = [
+            (pauli_z, gate::pauli_z),
+            (hadamard, gate::hadamard),
+            (s, gate::s),
+            (t, gate::t),
+        ];
+        for (kernel, gate) in pairs {
+            let (mut zeros, mut ones) = (amplitudes[..4].to_vec(), amplitudes[4..].to_vec());
+            kernel(&mut zeros, &mut ones);
+            for i in 0..4 {
+                let (a0, a1) = gate(amplitudes[i], amplitudes[i + 4]);
+                assert!((zeros[i] - a0).norm() < 1e-15 && (ones[i] - a1).norm() < 1e-15);
+            }
+        }
+    }
+}
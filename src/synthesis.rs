@@ -0,0 +1,507 @@
+/*
+Circuit synthesis.
+
+Reversible circuits of X, CNOT, and Toffoli gates computing classical boolean functions,
+built from the positive polarity Reed-Muller expansion of their truth tables.
+
+Clifford+T approximation of the Z-rotations rz(θ) = diag(e^{-iθ/2}, e^{iθ/2}),
+following the approach of Ross and Selinger:
+find u in Z[ω]/√2^k close to e^{-iθ/2} by solving a grid problem,
+solve the norm equation t·t† = 1 - u·u† for the off-diagonal entry,
+and decompose the exact unitary [[u, -t†], [t, u†]] into H and T gates
+by the exact synthesis of Kliuchnikov, Maslov, and Mosca.
+The grid problems are solved by enumerating their upright bounding boxes
+by a scaling with a power of the unit λ = 1 + √2 instead of the full grid operators,
+and in f64 precision, which supports the precisions down to 1e-7.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::f64::consts::{PI, SQRT_2};
+use std::sync::OnceLock;
+
+use num_complex::Complex;
+
+use crate::blocks::mcx_with_ancilla;
+use crate::circuit::Circuit;
+use crate::ring::{NormEquationSolver, ZOmega, ZRoot2};
+
+// Below this precision, the bound 1 - ε²/2 of the ε-region is lost in the f64 rounding.
+const MIN_EPSILON: f64 = 1e-7;
+// Bound on the denominator exponent of the grid problems keeping the norm equations within i128.
+const MAX_DENOMINATOR_EXPONENT: u32 = 62;
+// Bound on the exponent n of the λ^n scaling of the grid problems keeping their solutions within i128.
+const MAX_GRID_SCALING_EXPONENT: i32 = 20;
+// The exact synthesis looks up the unitaries with sde(|u|²) up to this bound in a table.
+const TABLE_SDE: i32 = 3;
+
+// Element x/√2^k of the ring D[ω] = Z[ω][1/√2] in the form with the smallest k.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct DOmega {
+    x: ZOmega,
+    k: i32,
+}
+
+impl DOmega {
+    fn new(x: ZOmega, k: i32) -> DOmega {
+        let (mut x, mut k) = (x, k);
+        if x == ZOmega::ZERO {
+            return DOmega { x, k: 0 };
+        }
+        while k > 0 && x.is_divisible_by_root2() {
+            x = x.div_root2();
+            k -= 1;
+        }
+        DOmega { x, k }
+    }
+
+    fn with_exponent(self, k: i32) -> ZOmega {
+        (self.k..k).fold(self.x, |x, _| x * ZOmega::ROOT2)
+    }
+
+    fn add(self, other: DOmega) -> DOmega {
+        let k = self.k.max(other.k);
+        DOmega::new(self.with_exponent(k) + other.with_exponent(k), k)
+    }
+
+    fn sub(self, other: DOmega) -> DOmega {
+        self.add(DOmega::new(-other.x, other.k))
+    }
+
+    fn div_root2(self) -> DOmega {
+        DOmega::new(self.x, self.k + 1)
+    }
+
+    fn mul_omega_power(self, n: i32) -> DOmega {
+        DOmega::new(self.x.mul_omega_power(n), self.k)
+    }
+
+    // The smallest denominator exponent of |x/√2^k|².
+    fn sde_abs_squared(self) -> i32 {
+        DOmega::new(self.x * self.x.conj(), 2 * self.k).k
+    }
+}
+
+type Matrix = [[DOmega; 2]; 2];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Step {
+    H,
+    T,
+}
+
+// Multiply the matrix by the gate from the left.
+fn apply_step(matrix: &Matrix, step: Step) -> Matrix {
+    match step {
+        Step::H => [
+            [
+                matrix[0][0].add(matrix[1][0]).div_root2(),
+                matrix[0][1].add(matrix[1][1]).div_root2(),
+            ],
+            [
+                matrix[0][0].sub(matrix[1][0]).div_root2(),
+                matrix[0][1].sub(matrix[1][1]).div_root2(),
+            ],
+        ],
+        Step::T => apply_t_power(matrix, 1),
+    }
+}
+
+fn apply_t_power(matrix: &Matrix, n: i32) -> Matrix {
+    [
+        matrix[0],
+        [
+            matrix[1][0].mul_omega_power(n),
+            matrix[1][1].mul_omega_power(n),
+        ],
+    ]
+}
+
+// Representation of the matrix which is the same for all the global phases ω^n.
+fn canonical(matrix: &Matrix) -> [DOmega; 4] {
+    (0..8)
+        .map(|n| {
+            [
+                matrix[0][0].mul_omega_power(n),
+                matrix[0][1].mul_omega_power(n),
+                matrix[1][0].mul_omega_power(n),
+                matrix[1][1].mul_omega_power(n),
+            ]
+        })
+        .min()
+        .unwrap()
+}
+
+// Breadth-first search of the shortest H and T gate sequences of the unitaries with small sde(|u|²).
+fn build_table() -> HashMap<[DOmega; 4], Vec<Step>> {
+    let one = DOmega::new(ZOmega::ONE, 0);
+    let zero = DOmega::new(ZOmega::ZERO, 0);
+    let identity: Matrix = [[one, zero], [zero, one]];
+
+    let mut table = HashMap::new();
+    let mut queue = VecDeque::new();
+    table.insert(canonical(&identity), Vec::new());
+    queue.push_back((identity, Vec::new()));
+    while let Some((matrix, steps)) = queue.pop_front() {
+        for step in [Step::H, Step::T] {
+            let next = apply_step(&matrix, step);
+            if next[0][0].sde_abs_squared() > TABLE_SDE + 1 {
+                continue;
+            }
+            if let Entry::Vacant(entry) = table.entry(canonical(&next)) {
+                let mut next_steps: Vec<Step> = steps.clone();
+                next_steps.push(step);
+                entry.insert(next_steps.clone());
+                queue.push_back((next, next_steps));
+            }
+        }
+    }
+
+    table
+}
+
+// Decompose the exact Clifford+T unitary into the steps in the order of their application.
+fn exact_synthesis(matrix: &Matrix) -> Vec<Step> {
+    static TABLE: OnceLock<HashMap<[DOmega; 4], Vec<Step>>> = OnceLock::new();
+    let table = TABLE.get_or_init(build_table);
+
+    // Reduce the sde by one with each H·T^(-j), so that the matrix = T^j·H·(the reduced matrix).
+    let mut matrix = *matrix;
+    let mut t_powers = Vec::new();
+    loop {
+        let sde = matrix[0][0].sde_abs_squared();
+        if sde <= TABLE_SDE {
+            break;
+        }
+        let (j, reduced) = (0..4)
+            .map(|j| (j, apply_step(&apply_t_power(&matrix, -j), Step::H)))
+            .find(|(_, reduced)| reduced[0][0].sde_abs_squared() == sde - 1)
+            .expect("The matrix is not a Clifford+T unitary.");
+        matrix = reduced;
+        t_powers.push(j);
+    }
+
+    let mut steps = table
+        .get(&canonical(&matrix))
+        .expect("The matrix is not a Clifford+T unitary.")
+        .clone();
+    for &j in t_powers.iter().rev() {
+        steps.push(Step::H);
+        steps.extend((0..j).map(|_| Step::T));
+    }
+
+    steps
+}
+
+// Build the circuit, merging the runs of the T gates into Z, S, and T gates.
+fn to_circuit(steps: &[Step]) -> Circuit {
+    let mut circuit = Circuit::new(1);
+    let mut t_count = 0;
+    for (i, step) in steps.iter().enumerate() {
+        if *step == Step::T {
+            t_count += 1;
+        }
+        if *step == Step::H || i + 1 == steps.len() {
+            if t_count & 4 != 0 {
+                circuit.pauli_z(0);
+            }
+            if t_count & 2 != 0 {
+                circuit.s(0);
+            }
+            if t_count & 1 != 0 {
+                circuit.t(0);
+            }
+            t_count = 0;
+        }
+        if *step == Step::H {
+            circuit.hadamard(0);
+        }
+    }
+
+    circuit
+}
+
+// All the elements a + b√2 with a + b√2 in [x0, x1] and a - b√2 in [y0, y1].
+fn grid_points(x0: f64, x1: f64, y0: f64, y1: f64) -> Vec<ZRoot2> {
+    if x0 > x1 || y0 > y1 {
+        return Vec::new();
+    }
+
+    // Scale the element by λ^n, which scales its √2-conjugate by (-λ)^(-n),
+    // so that both the intervals have a similar width and only a few b have to be tried.
+    let lambda = ZRoot2::LAMBDA.to_f64();
+    let width_ratio = (y1 - y0).max(f64::MIN_POSITIVE) / (x1 - x0).max(f64::MIN_POSITIVE);
+    let n = ((width_ratio.ln() / (2.0 * lambda.ln())).round() as i32)
+        .clamp(-MAX_GRID_SCALING_EXPONENT, MAX_GRID_SCALING_EXPONENT);
+    let scale = lambda.powi(n);
+    let (x0, x1) = (x0 * scale, x1 * scale);
+    let (y0, y1) = if n % 2 == 0 {
+        (y0 / scale, y1 / scale)
+    } else {
+        (-y1 / scale, -y0 / scale)
+    };
+    let unscale = ZRoot2::ONE.mul_lambda_power(-n);
+
+    let mut points = Vec::new();
+    let b_min = ((x0 - y1) / (2.0 * SQRT_2)).ceil() as i128;
+    let b_max = ((x1 - y0) / (2.0 * SQRT_2)).floor() as i128;
+    for b in b_min..=b_max {
+        let shift = b as f64 * SQRT_2;
+        let a_min = (x0 - shift).max(y0 + shift).ceil() as i128;
+        let a_max = (x1 - shift).min(y1 + shift).floor() as i128;
+        for a in a_min..=a_max {
+            points.push(ZRoot2::new(a, b) * unscale);
+        }
+    }
+    points
+}
+
+// The range of the cosine of the angles within the half width around the center angle.
+fn cos_range(center: f64, half_width: f64) -> (f64, f64) {
+    let contains =
+        |angle: f64| ((center - angle + PI).rem_euclid(2.0 * PI) - PI).abs() <= half_width;
+    let cos0 = (center - half_width).cos();
+    let cos1 = (center + half_width).cos();
+    let min = if contains(PI) { -1.0 } else { cos0.min(cos1) };
+    let max = if contains(0.0) { 1.0 } else { cos0.max(cos1) };
+    (min, max)
+}
+
+// Find u = x/√2^(k-1) in the ε-region around z, that is |u| ≤ 1 and Re(u·z†) ≥ 1 - ε²/2,
+// with |u•| ≤ 1 and a solution t/√2^(k-1) of the norm equation.
+// The candidate u is enumerated as (α + iβ)/√2^k with α, β in Z[√2], whose integer parts have the same parity.
+fn find_candidate(
+    k: u32,
+    z: Complex<f64>,
+    epsilon: f64,
+    solver: &NormEquationSolver,
+) -> Option<(ZOmega, ZOmega)> {
+    let scale = SQRT_2.powi(k as i32);
+    let threshold = 1.0 - epsilon * epsilon / 2.0;
+    let (x_min, x_max) = cos_range(z.arg(), threshold.acos());
+
+    for alpha in grid_points(x_min * scale, x_max * scale, -scale, scale) {
+        let re = alpha.to_f64() / scale;
+        let disk_bound = (1.0 - re * re).max(0.0).sqrt();
+        let (mut im_min, mut im_max) = (-disk_bound, disk_bound);
+        if z.im > 0.0 {
+            im_min = im_min.max((threshold - re * z.re) / z.im);
+        } else if z.im < 0.0 {
+            im_max = im_max.min((threshold - re * z.re) / z.im);
+        } else if re * z.re < threshold {
+            continue;
+        }
+        if im_min > im_max {
+            continue;
+        }
+        let alpha_conj = alpha.conj().to_f64();
+        let conj_bound = (scale * scale - alpha_conj * alpha_conj).max(0.0).sqrt();
+
+        for beta in grid_points(im_min * scale, im_max * scale, -conj_bound, conj_bound) {
+            if (beta.a - alpha.a) % 2 != 0 {
+                continue;
+            }
+            let x = ZOmega::new(
+                alpha.b,
+                (alpha.a + beta.a) / 2,
+                beta.b,
+                (beta.a - alpha.a) / 2,
+            );
+            let xi = ZRoot2::from_int(1 << (k - 1)) - x.norm_squared();
+            if !xi.is_doubly_nonnegative() {
+                continue;
+            }
+            if let Some(t) = solver.solve(xi) {
+                return Some((x, t));
+            }
+        }
+    }
+
+    None
+}
+
+// Approximate the rotation rz(θ) up to a global phase by a single qubit Clifford+T circuit
+// with the operator norm error at most epsilon.
+pub fn synthesize_rz(theta: f64, epsilon: f64) -> Circuit {
+    assert!(
+        (MIN_EPSILON..1.0).contains(&epsilon),
+        "The precision has to be at least {} and less than 1.",
+        MIN_EPSILON
+    );
+
+    let z = Complex::new((theta / 2.0).cos(), -(theta / 2.0).sin());
+    let solver = NormEquationSolver::new();
+    for k in 1..=MAX_DENOMINATOR_EXPONENT {
+        if let Some((u, t)) = find_candidate(k, z, epsilon, &solver) {
+            let exponent = k as i32 - 1;
+            let matrix: Matrix = [
+                [DOmega::new(u, exponent), DOmega::new(-t.conj(), exponent)],
+                [DOmega::new(t, exponent), DOmega::new(u.conj(), exponent)],
+            ];
+            return to_circuit(&exact_synthesis(&matrix));
+        }
+    }
+
+    panic!("No Clifford+T approximation of rz({}) was found.", theta);
+}
+
+// Synthesize the reversible circuit |x⟩|y⟩|0⟩ -> |x⟩|y ⊕ f(x)⟩|0⟩ for the boolean function given by its truth table,
+// where the bit j of truth_table[x] is the output j of f for the input x.
+// The input register is on the qubits 0..input_count, the output register on the next output_count qubits,
+// followed by the ancilla qubits needed for the products of more than two inputs, returned to the state |0⟩.
+pub fn synthesize_truth_table(
+    input_count: usize,
+    output_count: usize,
+    truth_table: &[u64],
+) -> Circuit {
+    assert!(
+        input_count < 64 && output_count <= 64,
+        "The boolean function can have at most 63 inputs and 64 outputs."
+    );
+    assert!(
+        truth_table.len() == 1 << input_count,
+        "The truth table has to contain 2^{} entries.",
+        input_count
+    );
+
+    // The coefficients of the Reed-Muller expansion f_j(x) = ⊕ c_j(m)·∏_{i in m} x_i
+    // by the Möbius transform of each output column of the truth table.
+    let mut monomials: Vec<Vec<u64>> = Vec::with_capacity(output_count);
+    for j in 0..output_count {
+        let mut coefficients: Vec<bool> =
+            truth_table.iter().map(|&row| row >> j & 1 == 1).collect();
+        for i in 0..input_count {
+            for x in 0..coefficients.len() {
+                if x & (1 << i) != 0 {
+                    coefficients[x] ^= coefficients[x ^ (1 << i)];
+                }
+            }
+        }
+        monomials.push(
+            (0..coefficients.len() as u64)
+                .filter(|&m| coefficients[m as usize])
+                .collect(),
+        );
+    }
+
+    let max_degree = monomials
+        .iter()
+        .flatten()
+        .map(|m| m.count_ones() as usize)
+        .max()
+        .unwrap_or(0);
+    let ancilla_count = max_degree.saturating_sub(2);
+    let ancilla_start = input_count + output_count;
+    let ancillas: Vec<usize> = (ancilla_start..ancilla_start + ancilla_count).collect();
+
+    let mut circuit = Circuit::new(ancilla_start + ancilla_count);
+    for (j, output_monomials) in monomials.iter().enumerate() {
+        for &m in output_monomials.iter() {
+            let mut qubit_numbers: Vec<usize> =
+                (0..input_count).filter(|&i| m & (1 << i) != 0).collect();
+            let control_count = qubit_numbers.len();
+            qubit_numbers.push(input_count + j);
+            qubit_numbers.extend_from_slice(&ancillas[..control_count.saturating_sub(2)]);
+            circuit.append(&mcx_with_ancilla(control_count), &qubit_numbers);
+        }
+    }
+
+    circuit
+}
+
+// Synthesize the reversible circuit of the boolean function given by a closure from the input bits to the output bits,
+// with the same qubit layout as synthesize_truth_table.
+pub fn synthesize_function<F>(input_count: usize, output_count: usize, f: F) -> Circuit
+where
+    F: Fn(u64) -> u64,
+{
+    assert!(
+        input_count < 64,
+        "The boolean function can have at most 63 inputs."
+    );
+    let truth_table: Vec<u64> = (0..1u64 << input_count).map(f).collect();
+    synthesize_truth_table(input_count, output_count, &truth_table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::Gate;
+    use crate::gate;
+    use crate::simulation::Simulation;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    // Columns of the unitary of a single qubit circuit.
+    fn unitary(circuit: &Circuit) -> [[Complex<f64>; 2]; 2] {
+        let mut columns = [
+            [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)],
+            [Complex::new(0.0, 0.0), Complex::new(1.0, 0.0)],
+        ];
+        for column in columns.iter_mut() {
+            for g in circuit.gates().iter() {
+                let (a0, a1) = match g {
+                    Gate::Hadamard(_) => gate::hadamard(column[0], column[1]),
+                    Gate::T(_) => gate::t(column[0], column[1]),
+                    Gate::S(_) => gate::s(column[0], column[1]),
+                    Gate::PauliZ(_) => gate::pauli_z(column[0], column[1]),
+                    _ => panic!("Unexpected gate {}.", g.name()),
+                };
+                *column = [a0, a1];
+            }
+        }
+        columns
+    }
+
+    #[test]
+    fn rz_approximation_is_within_precision() {
+        for &(theta, epsilon) in [(0.3, 1e-2), (1.0, 1e-4), (-2.5, 1e-6), (PI / 4.0, 1e-3)].iter() {
+            let circuit = synthesize_rz(theta, epsilon);
+            let columns = unitary(&circuit);
+            // |tr(rz(θ)†·U)|/2 = Re(u·z†) for the exact unitary up to the global phase.
+            let z = Complex::new((theta / 2.0).cos(), -(theta / 2.0).sin());
+            let trace = z.conj() * columns[0][0] + z * columns[1][1];
+            assert!(trace.norm() / 2.0 >= 1.0 - epsilon * epsilon / 2.0 - 1e-12);
+        }
+    }
+
+    #[test]
+    fn reversible_circuit_computes_truth_table() {
+        // Majority and parity of four bits.
+        let f = |x: u64| -> u64 {
+            let ones = x.count_ones() as u64;
+            (ones >= 3) as u64 | (ones % 2) << 1
+        };
+        let circuit = synthesize_function(4, 2, f);
+        assert_eq!(circuit.qubit_count(), 4 + 2 + 2);
+
+        let mut simulation = QuantumSimulation::new(circuit.qubit_count(), 0u64);
+        for x in 0..16u64 {
+            for y in 0..4u64 {
+                simulation.reset();
+                for i in 0..4 {
+                    if x & (1 << i) != 0 {
+                        simulation.pauli_x(i);
+                    }
+                }
+                for j in 0..2 {
+                    if y & (1 << j) != 0 {
+                        simulation.pauli_x(4 + j);
+                    }
+                }
+                circuit.run(&mut simulation);
+                let measured_states = simulation.measure_all();
+                let value = |range: std::ops::Range<usize>| -> u64 {
+                    range
+                        .enumerate()
+                        .map(|(i, q)| (measured_states[q] as u64) << i)
+                        .sum()
+                };
+                assert_eq!(value(0..4), x);
+                assert_eq!(value(4..6), y ^ f(x));
+                assert_eq!(value(6..8), 0);
+            }
+        }
+    }
+}
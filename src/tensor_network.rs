@@ -0,0 +1,453 @@
+/*
+Tensor network of a circuit, contracted for the amplitudes and the expectation values.
+
+Each gate is a tensor with an input and an output index for each of its qubits, the initial
+state is a vector |0⟩ on each qubit, and an amplitude ⟨x|U|0⟩ closes the output indices with
+the vectors ⟨x_q|. An expectation value ⟨0|U† P U|0⟩ joins the network with its conjugate through
+the Pauli operators, keeping only the gates in the backward light cone of the Pauli operators,
+since the other gates cancel with their adjoints. The network is contracted to a scalar without
+the state vector, in the order of the greedy heuristic, which contracts at each step the pair of
+tensors sharing an index with the least growth of the size, so that the cost depends on the
+width of the contraction, the largest rank of the intermediate tensors, rather than on the
+number of qubits.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::collections::HashMap;
+
+use num_complex::Complex;
+
+use crate::circuit::{u_f_gates, Circuit, Gate};
+use crate::gate::{
+    rx_matrix, ry_matrix, rz_matrix, Matrix, HADAMARD_MATRIX, PAULI_X_MATRIX, PAULI_Y_MATRIX,
+    PAULI_Z_MATRIX, S_MATRIX, T_MATRIX,
+};
+use crate::hamiltonian::{Hamiltonian, Pauli, PauliString};
+use crate::simulation::check_qubits;
+
+// The largest rank of an intermediate tensor, of 2^MAX_TENSOR_RANK amplitudes.
+pub const MAX_TENSOR_RANK: usize = 28;
+
+// The tensor with its amplitudes indexed by the bits of its indices, the first index being
+// the least significant bit.
+#[derive(Clone, Debug)]
+struct Tensor {
+    indices: Vec<usize>,
+    data: Vec<Complex<f64>>,
+}
+
+impl Tensor {
+    fn vector(index: usize, amplitudes: [Complex<f64>; 2]) -> Tensor {
+        Tensor {
+            indices: vec![index],
+            data: amplitudes.to_vec(),
+        }
+    }
+
+    // The tensor of the 2x2 matrix from the input index to the output index.
+    fn matrix(output_index: usize, input_index: usize, matrix: &Matrix) -> Tensor {
+        Tensor {
+            indices: vec![output_index, input_index],
+            data: vec![matrix[0], matrix[2], matrix[1], matrix[3]],
+        }
+    }
+
+    fn conj(&self) -> Tensor {
+        Tensor {
+            indices: self.indices.clone(),
+            data: self.data.iter().map(|amplitude| amplitude.conj()).collect(),
+        }
+    }
+
+    // The sum over the shared indices of the products of the amplitudes, with the other indices
+    // of self and then the other indices of other.
+    fn contract(&self, other: &Tensor) -> Tensor {
+        let shared: Vec<usize> = self
+            .indices
+            .iter()
+            .copied()
+            .filter(|index| other.indices.contains(index))
+            .collect();
+        let indices: Vec<usize> = self
+            .indices
+            .iter()
+            .chain(other.indices.iter())
+            .copied()
+            .filter(|index| !shared.contains(index))
+            .collect();
+        let stride = |tensor: &Tensor, index: usize| {
+            tensor
+                .indices
+                .iter()
+                .position(|&i| i == index)
+                .map_or(0, |position| 1 << position)
+        };
+        let offsets = |indices: &[usize]| -> Vec<(usize, usize)> {
+            (0..1usize << indices.len())
+                .map(|bits| {
+                    indices
+                        .iter()
+                        .enumerate()
+                        .filter(|(k, _)| (bits >> k) & 1 == 1)
+                        .fold((0, 0), |(a, b), (_, &index)| {
+                            (a + stride(self, index), b + stride(other, index))
+                        })
+                })
+                .collect()
+        };
+        let shared_offsets = offsets(&shared);
+        let data = offsets(&indices)
+            .iter()
+            .map(|&(a, b)| {
+                shared_offsets
+                    .iter()
+                    .map(|&(shared_a, shared_b)| self.data[a + shared_a] * other.data[b + shared_b])
+                    .sum()
+            })
+            .collect();
+        Tensor { indices, data }
+    }
+}
+
+// The rank of the contraction of the tensors with the indices.
+fn contracted_rank(a: &[usize], b: &[usize]) -> usize {
+    let shared_count = a.iter().filter(|index| b.contains(index)).count();
+    a.len() + b.len() - 2 * shared_count
+}
+
+// The pairs of the tensors contracted by the greedy heuristic, the result of the step k being
+// the tensor tensor_count + k, with the largest rank of the results.
+fn greedy_order(tensors: &[Vec<usize>]) -> (Vec<(usize, usize)>, usize) {
+    let mut alive: HashMap<usize, Vec<usize>> = tensors.iter().cloned().enumerate().collect();
+    let mut tensors_of_index: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (t, indices) in tensors.iter().enumerate() {
+        for &index in indices.iter() {
+            tensors_of_index.entry(index).or_default().push(t);
+        }
+    }
+    let mut order = Vec::new();
+    let mut width = 0;
+    let mut next_tensor = tensors.len();
+    while alive.len() > 1 {
+        // The size growth 2^rank(ab) - 2^rank(a) - 2^rank(b) of the pairs sharing an index,
+        // or of the outer product of two tensors when none do.
+        let size = |rank: usize| (1i128) << rank.min(120);
+        let pair = tensors_of_index
+            .values()
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| (pair[0].min(pair[1]), pair[0].max(pair[1])))
+            .min_by_key(|&(a, b)| {
+                let rank = contracted_rank(&alive[&a], &alive[&b]);
+                (
+                    size(rank) - size(alive[&a].len()) - size(alive[&b].len()),
+                    a,
+                    b,
+                )
+            })
+            .unwrap_or_else(|| {
+                let mut smallest: Vec<usize> = alive.keys().copied().collect();
+                smallest.sort_by_key(|t| (alive[t].len(), *t));
+                (smallest[0].min(smallest[1]), smallest[0].max(smallest[1]))
+            });
+        let (a, b) = pair;
+        let (indices_a, indices_b) = (alive.remove(&a).unwrap(), alive.remove(&b).unwrap());
+        let indices: Vec<usize> = indices_a
+            .iter()
+            .chain(indices_b.iter())
+            .copied()
+            .filter(|index| !(indices_a.contains(index) && indices_b.contains(index)))
+            .collect();
+        for index in indices_a.iter().chain(indices_b.iter()) {
+            if indices.contains(index) {
+                for t in tensors_of_index.get_mut(index).unwrap().iter_mut() {
+                    if *t == a || *t == b {
+                        *t = next_tensor;
+                    }
+                }
+            } else {
+                tensors_of_index.remove(index);
+            }
+        }
+        width = width.max(indices.len());
+        alive.insert(next_tensor, indices);
+        order.push(pair);
+        next_tensor += 1;
+    }
+    (order, width)
+}
+
+// Contract the tensors with all their indices shared to the scalar.
+fn contract(tensors: Vec<Tensor>) -> Complex<f64> {
+    let indices: Vec<Vec<usize>> = tensors
+        .iter()
+        .map(|tensor| tensor.indices.clone())
+        .collect();
+    let (order, width) = greedy_order(&indices);
+    assert!(
+        width <= MAX_TENSOR_RANK,
+        "The contraction of the tensor network needs a tensor of rank {}, more than {}.",
+        width,
+        MAX_TENSOR_RANK
+    );
+    let mut tensors: Vec<Option<Tensor>> = tensors.into_iter().map(Some).collect();
+    for (a, b) in order {
+        let (tensor_a, tensor_b) = (tensors[a].take().unwrap(), tensors[b].take().unwrap());
+        tensors.push(Some(tensor_a.contract(&tensor_b)));
+    }
+    tensors.into_iter().flatten().next().unwrap().data[0]
+}
+
+#[derive(Clone, Debug)]
+pub struct TensorNetwork {
+    qubit_count: usize,
+    gates: Vec<Gate>,
+}
+
+impl TensorNetwork {
+    pub fn from_circuit(circuit: &Circuit) -> TensorNetwork {
+        assert!(
+            circuit
+                .conditions()
+                .iter()
+                .all(|condition| condition.is_none()),
+            "The tensor network of a circuit cannot contain conditional gates."
+        );
+        let mut gates = Vec::new();
+        for gate in circuit.gates().iter() {
+            match *gate {
+                Gate::Measure(_, _) => {
+                    panic!("The tensor network of a circuit cannot contain measurements.")
+                }
+                Gate::UF(f, q0, q1) => gates.extend(u_f_gates(f, q0, q1)),
+                gate => gates.push(gate),
+            }
+        }
+        TensorNetwork {
+            qubit_count: circuit.qubit_count(),
+            gates,
+        }
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The amplitude ⟨x|U|0⟩ of the basis state with the values of the qubits.
+    pub fn amplitude(&self, values: &[bool]) -> Complex<f64> {
+        assert!(
+            values.len() == self.qubit_count,
+            "The basis state has to have a value for each of the qubits {}.",
+            self.qubit_count
+        );
+        let mut index_count = 0;
+        let (mut tensors, wires) = self.tensors(&self.gates, &mut index_count);
+        let zero = Complex::new(0.0, 0.0);
+        let one = Complex::new(1.0, 0.0);
+        for (&wire, &value) in wires.iter().zip(values.iter()) {
+            tensors.push(Tensor::vector(
+                wire,
+                if value { [zero, one] } else { [one, zero] },
+            ));
+        }
+        contract(tensors)
+    }
+
+    pub fn probability(&self, values: &[bool]) -> f64 {
+        self.amplitude(values).norm_sqr()
+    }
+
+    // ⟨0|U† P U|0⟩ of the Pauli string, with the gates in its backward light cone.
+    pub fn pauli_expectation(&self, pauli_string: &PauliString) -> f64 {
+        let paulis = pauli_string.paulis();
+        for &(_, qubit_number) in paulis.iter() {
+            assert!(
+                qubit_number < self.qubit_count,
+                "The qubit number has to be less than the number of qubits {}.",
+                self.qubit_count
+            );
+        }
+        let mut is_in_light_cone = vec![false; self.qubit_count];
+        for &(_, qubit_number) in paulis.iter() {
+            is_in_light_cone[qubit_number] = true;
+        }
+        let mut gates: Vec<Gate> = Vec::new();
+        for gate in self.gates.iter().rev() {
+            let qubits = gate.qubits();
+            if qubits.iter().any(|&q| is_in_light_cone[q]) {
+                for &q in qubits.iter() {
+                    is_in_light_cone[q] = true;
+                }
+                gates.push(*gate);
+            }
+        }
+        gates.reverse();
+
+        let mut index_count = 0;
+        let (mut tensors, ket_wires) = self.tensors(&gates, &mut index_count);
+        // The conjugated network has the indices shifted by the index count, except for its
+        // output indices, joined to the outputs of the Pauli operators, or to the outputs
+        // of the network on the qubits without a Pauli operator.
+        let mut bra_wires = ket_wires.clone();
+        let pauli_offset = 2 * index_count;
+        for (k, &(pauli, qubit_number)) in paulis.iter().enumerate() {
+            let matrix = match pauli {
+                Pauli::X => PAULI_X_MATRIX,
+                Pauli::Y => PAULI_Y_MATRIX,
+                Pauli::Z => PAULI_Z_MATRIX,
+            };
+            bra_wires[qubit_number] = pauli_offset + k;
+            tensors.push(Tensor::matrix(
+                pauli_offset + k,
+                ket_wires[qubit_number],
+                &matrix,
+            ));
+        }
+        let bra_tensors: Vec<Tensor> = tensors[..tensors.len() - paulis.len()]
+            .iter()
+            .map(|tensor| {
+                let mut tensor = tensor.conj();
+                for index in tensor.indices.iter_mut() {
+                    *index = match ket_wires.iter().position(|wire| wire == index) {
+                        Some(q) => bra_wires[q],
+                        None => *index + index_count,
+                    };
+                }
+                tensor
+            })
+            .collect();
+        tensors.extend(bra_tensors);
+        contract(tensors).re
+    }
+
+    pub fn expectation(&self, hamiltonian: &Hamiltonian) -> f64 {
+        hamiltonian
+            .terms()
+            .iter()
+            .map(|(coefficient, pauli_string)| coefficient * self.pauli_expectation(pauli_string))
+            .sum()
+    }
+
+    // The width of the contraction of an amplitude by the greedy heuristic.
+    pub fn contraction_width(&self) -> usize {
+        let mut index_count = 0;
+        let (mut tensors, wires) = self.tensors(&self.gates, &mut index_count);
+        for &wire in wires.iter() {
+            tensors.push(Tensor::vector(wire, [Complex::new(1.0, 0.0); 2]));
+        }
+        let indices: Vec<Vec<usize>> = tensors
+            .iter()
+            .map(|tensor| tensor.indices.clone())
+            .collect();
+        greedy_order(&indices).1
+    }
+
+    // The tensors of the initial state and of the gates, with the output index of each qubit.
+    fn tensors(&self, gates: &[Gate], index_count: &mut usize) -> (Vec<Tensor>, Vec<usize>) {
+        let mut new_index = || {
+            *index_count += 1;
+            *index_count - 1
+        };
+        let mut wires: Vec<usize> = (0..self.qubit_count).map(|_| new_index()).collect();
+        let mut tensors: Vec<Tensor> = wires
+            .iter()
+            .map(|&wire| Tensor::vector(wire, [Complex::new(1.0, 0.0), Complex::new(0.0, 0.0)]))
+            .collect();
+        for gate in gates.iter() {
+            let qubits = gate.qubits();
+            if let Err(error) = check_qubits(&qubits, self.qubit_count) {
+                panic!("{}", error);
+            }
+            let inputs: Vec<usize> = qubits.iter().map(|&q| wires[q]).collect();
+            let outputs: Vec<usize> = qubits.iter().map(|_| new_index()).collect();
+            for (&q, &output) in qubits.iter().zip(outputs.iter()) {
+                wires[q] = output;
+            }
+            tensors.push(gate_tensor(gate, &outputs, &inputs));
+        }
+        (tensors, wires)
+    }
+}
+
+// The tensor of the gate with the output and the input indices of its qubits.
+fn gate_tensor(gate: &Gate, outputs: &[usize], inputs: &[usize]) -> Tensor {
+    let matrix = match *gate {
+        Gate::PauliX(_) => Some(PAULI_X_MATRIX),
+        Gate::PauliY(_) => Some(PAULI_Y_MATRIX),
+        Gate::PauliZ(_) => Some(PAULI_Z_MATRIX),
+        Gate::Hadamard(_) => Some(HADAMARD_MATRIX),
+        Gate::S(_) => Some(S_MATRIX),
+        Gate::T(_) => Some(T_MATRIX),
+        Gate::Rx(theta, _) => Some(rx_matrix(theta)),
+        Gate::Ry(theta, _) => Some(ry_matrix(theta)),
+        Gate::Rz(theta, _) => Some(rz_matrix(theta)),
+        _ => None,
+    };
+    if let Some(matrix) = matrix {
+        return Tensor::matrix(outputs[0], inputs[0], &matrix);
+    }
+
+    // The multi-qubit gates map each basis state to a basis state with a phase,
+    // the bit k of the states being the qubit k of the gate.
+    let basis_map = |bits: usize| -> (usize, Complex<f64>) {
+        let one = Complex::new(1.0, 0.0);
+        match *gate {
+            Gate::Cnot(_, _) => (bits ^ ((bits & 1) << 1), one),
+            Gate::Cz(_, _) if bits == 3 => (bits, -one),
+            Gate::Cphase(theta, _, _) if bits == 3 => (bits, Complex::new(0.0, theta).exp()),
+            Gate::Swap(_, _) => (((bits & 1) << 1) | (bits >> 1), one),
+            Gate::Toffoli(_, _, _) if bits & 3 == 3 => (bits ^ 4, one),
+            _ => (bits, one),
+        }
+    };
+    let qubit_count = inputs.len();
+    let mut data = vec![Complex::new(0.0, 0.0); 1 << (2 * qubit_count)];
+    for bits in 0..1usize << qubit_count {
+        let (output_bits, phase) = basis_map(bits);
+        data[output_bits | (bits << qubit_count)] = phase;
+    }
+    Tensor {
+        indices: outputs.iter().chain(inputs.iter()).copied().collect(),
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn contraction_matches_state_vector() {
+        let mut circuit = Circuit::new(4);
+        circuit.hadamard(0);
+        circuit.cnot(0, 1);
+        circuit.t(1);
+        circuit.ry(0.4, 2);
+        circuit.toffoli(0, 2, 3);
+        circuit.cphase(0.9, 3, 1);
+        circuit.swap(2, 0);
+        circuit.apply_u_f(|x| !x, 1, 3);
+        circuit.rx(-0.7, 3);
+        circuit.pauli_y(2);
+        let network = TensorNetwork::from_circuit(&circuit);
+        let mut simulation = QuantumSimulation::new(4, 0);
+        for gate in circuit.gates() {
+            gate.apply(&mut simulation);
+        }
+
+        let amplitudes = simulation.amplitudes();
+        for (i, amplitude) in amplitudes.iter().enumerate() {
+            let values: Vec<bool> = (0..4).map(|q| (i >> q) & 1 == 1).collect();
+            assert!((network.amplitude(&values) - amplitude).norm() < 1e-10);
+        }
+        let mut hamiltonian = Hamiltonian::new();
+        hamiltonian.add_term(0.5, &[(Pauli::Z, 0), (Pauli::X, 3)]);
+        hamiltonian.add_term(-1.5, &[(Pauli::Y, 1)]);
+        hamiltonian.add_term(2.0, &[]);
+        assert!(
+            (network.expectation(&hamiltonian) - hamiltonian.expectation(&amplitudes)).abs()
+                < 1e-10
+        );
+    }
+}
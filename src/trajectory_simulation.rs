@@ -0,0 +1,267 @@
+/*
+Quantum trajectory simulation of the noisy circuits.
+
+Each run of a circuit is a trajectory of the state vector, where the channels of the noise model
+are applied after each gate as quantum jumps: one Kraus operator K of a channel is chosen with
+the probability |K ψ|^2 and the state becomes K ψ / |K ψ|. The average over the trajectories
+of the measured states is the result of the noisy circuit on the density matrix, at the memory
+cost of a state vector instead of its square. The angles of the rotation gates are perturbed by
+the coherent rotation error of the noise model, if any, and the crosstalks of the coupled
+qubits of a gate follow its channels.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use std::borrow::Cow;
+
+use num_complex::Complex;
+
+use crate::circuit::{Circuit, Gate};
+use crate::noise::NoiseModel;
+use crate::simulation::Simulation;
+use crate::state_vector_simulation::QuantumSimulation;
+
+#[derive(Debug)]
+pub struct TrajectorySimulation {
+    simulation: QuantumSimulation,
+    noise_model: NoiseModel,
+}
+
+impl TrajectorySimulation {
+    pub fn new(qubit_count: usize, rnd_seed: u64, noise_model: NoiseModel) -> TrajectorySimulation {
+        TrajectorySimulation::with_simulation(
+            QuantumSimulation::new(qubit_count, rnd_seed),
+            noise_model,
+        )
+    }
+
+    // Continue the simulation with the noise, as QuantumSimulation::with_noise.
+    // The readout errors of the noise model are set on the simulation.
+    pub(crate) fn with_simulation(
+        mut simulation: QuantumSimulation,
+        noise_model: NoiseModel,
+    ) -> TrajectorySimulation {
+        for (qubit_number, readout_error) in noise_model.readout_errors() {
+            simulation.set_readout_error(qubit_number, readout_error);
+        }
+        TrajectorySimulation {
+            simulation,
+            noise_model,
+        }
+    }
+
+    pub fn noise_model(&self) -> &NoiseModel {
+        &self.noise_model
+    }
+
+    // The amplitudes of the current trajectory.
+    pub fn amplitudes(&self) -> Cow<'_, [Complex<f64>]> {
+        self.simulation.amplitudes()
+    }
+
+    // The probabilities of the basis states averaged over the trajectories of the circuit,
+    // each run from the ground state.
+    pub fn average_probabilities(
+        &mut self,
+        circuit: &Circuit,
+        trajectory_count: usize,
+    ) -> Vec<f64> {
+        assert!(
+            trajectory_count > 0,
+            "The number of trajectories has to be positive."
+        );
+        let mut probabilities = vec![0.0; self.simulation.amplitudes().len()];
+        for _ in 0..trajectory_count {
+            self.reset();
+            circuit.run(self);
+            for (probability, amplitude) in probabilities.iter_mut().zip(self.amplitudes().iter()) {
+                *probability += amplitude.norm_sqr();
+            }
+        }
+        for probability in probabilities.iter_mut() {
+            *probability /= trajectory_count as f64;
+        }
+        probabilities
+    }
+
+    // Run the circuit from the ground state for each shot, on a trajectory of its own, and return
+    // the classical bits, or the values of all the qubits when the circuit has no classical bits.
+    pub fn run_shots(&mut self, circuit: &Circuit, shot_count: usize) -> Vec<Vec<bool>> {
+        let mut measurements = Vec::with_capacity(shot_count);
+        for _ in 0..shot_count {
+            self.reset();
+            let clbits = circuit.run(self);
+            if circuit.clbit_count() == 0 {
+                measurements.push(self.measure_all());
+            } else {
+                measurements.push(clbits);
+            }
+        }
+        measurements
+    }
+
+    // The angle of a rotation gate perturbed by the rotation error of the noise model.
+    fn rotation_angle(&mut self, theta: f64) -> f64 {
+        self.noise_model
+            .rotation_angle(theta, self.simulation.rng())
+    }
+
+    // Apply the channels and the crosstalks of the noise model following the gate.
+    fn apply_noise(&mut self, gate: Gate) {
+        for (channel, qubit_number) in self.noise_model.channels_after(&gate) {
+            self.simulation
+                .apply_kraus(channel.kraus_operators(), qubit_number);
+        }
+        for (unitary, qubit_number0, qubit_number1) in self.noise_model.crosstalks_after(&gate) {
+            self.simulation
+                .apply_two_qubit_matrix(unitary, qubit_number0, qubit_number1);
+        }
+    }
+}
+
+impl Simulation for TrajectorySimulation {
+    fn reset(&mut self) {
+        self.simulation.reset();
+    }
+
+    fn measure_all(&mut self) -> Vec<bool> {
+        self.simulation.measure_all()
+    }
+
+    fn measure(&mut self, qubit_numbers: Vec<usize>) -> Vec<bool> {
+        self.simulation.measure(qubit_numbers)
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.simulation.pauli_x(qubit_number);
+        self.apply_noise(Gate::PauliX(qubit_number));
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.simulation.pauli_y(qubit_number);
+        self.apply_noise(Gate::PauliY(qubit_number));
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.simulation.pauli_z(qubit_number);
+        self.apply_noise(Gate::PauliZ(qubit_number));
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.simulation.hadamard(qubit_number);
+        self.apply_noise(Gate::Hadamard(qubit_number));
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.simulation.s(qubit_number);
+        self.apply_noise(Gate::S(qubit_number));
+    }
+
+    fn t(&mut self, qubit_number: usize) {
+        self.simulation.t(qubit_number);
+        self.apply_noise(Gate::T(qubit_number));
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        let theta = self.rotation_angle(theta);
+        self.simulation.rx(theta, qubit_number);
+        self.apply_noise(Gate::Rx(theta, qubit_number));
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        let theta = self.rotation_angle(theta);
+        self.simulation.ry(theta, qubit_number);
+        self.apply_noise(Gate::Ry(theta, qubit_number));
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        let theta = self.rotation_angle(theta);
+        self.simulation.rz(theta, qubit_number);
+        self.apply_noise(Gate::Rz(theta, qubit_number));
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.simulation
+            .cnot(control_qubit_number, target_qubit_number);
+        self.apply_noise(Gate::Cnot(control_qubit_number, target_qubit_number));
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.simulation
+            .cz(control_qubit_number, target_qubit_number);
+        self.apply_noise(Gate::Cz(control_qubit_number, target_qubit_number));
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        let theta = self.rotation_angle(theta);
+        self.simulation
+            .cphase(theta, control_qubit_number, target_qubit_number);
+        self.apply_noise(Gate::Cphase(
+            theta,
+            control_qubit_number,
+            target_qubit_number,
+        ));
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.simulation.swap(qubit_number0, qubit_number1);
+        self.apply_noise(Gate::Swap(qubit_number0, qubit_number1));
+    }
+
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.simulation.apply_u_f(f, qubit_number0, qubit_number1);
+        self.apply_noise(Gate::UF(f, qubit_number0, qubit_number1));
+    }
+
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        self.simulation.toffoli(
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        );
+        self.apply_noise(Gate::Toffoli(
+            control_qubit_number0,
+            control_qubit_number1,
+            target_qubit_number,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::density_matrix_simulation::DensityMatrixSimulation;
+    use crate::noise::KrausChannel;
+
+    #[test]
+    fn trajectories_average_to_density_matrix() {
+        let channel = KrausChannel::amplitude_damping(0.3);
+        let mut noise_model = NoiseModel::new();
+        noise_model.add_all_qubit_error(channel.clone());
+        let mut circuit = Circuit::new(2);
+        circuit.push(Gate::PauliX(0));
+        circuit.push(Gate::Hadamard(1));
+        circuit.push(Gate::Cnot(0, 1));
+
+        let mut density_matrix_simulation = DensityMatrixSimulation::new(2, 0);
+        density_matrix_simulation.pauli_x(0);
+        density_matrix_simulation.apply_kraus(channel.kraus_operators(), 0);
+        density_matrix_simulation.hadamard(1);
+        density_matrix_simulation.apply_kraus(channel.kraus_operators(), 1);
+        density_matrix_simulation.cnot(0, 1);
+        density_matrix_simulation.apply_kraus(channel.kraus_operators(), 0);
+        density_matrix_simulation.apply_kraus(channel.kraus_operators(), 1);
+        let expected_probabilities = density_matrix_simulation.probabilities();
+
+        let mut simulation = TrajectorySimulation::new(2, 0, noise_model);
+        let probabilities = simulation.average_probabilities(&circuit, 4000);
+        for (probability, expected) in probabilities.iter().zip(expected_probabilities.iter()) {
+            assert!((probability - expected).abs() < 0.03);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+/*
+Quantum unitary simulation, accumulating the unitary of a circuit.
+
+The unitary U of n qubits is stored as the 4^n elements U[row, column] at the index
+row + column * 2^n, column j being the image of the basis state |j⟩. A gate G maps U to G U,
+applied to the qubits of the rows, so that each column evolves as a state vector, at the cost
+of squaring the memory of a state vector. The unitaries of two circuits are compared up to
+a global phase for the equivalence checks.
+
+A unitary has no measurements, so the measurements panic.
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use num_complex::Complex;
+
+use crate::gate::{
+    phase_matrix, rx_matrix, ry_matrix, rz_matrix, Matrix, HADAMARD_MATRIX, PAULI_X_MATRIX,
+    PAULI_Y_MATRIX, PAULI_Z_MATRIX, S_MATRIX, T_MATRIX,
+};
+use crate::simulation::{check_qubits, Simulation};
+use crate::state_vector_simulation;
+
+pub const MAX_QUBIT_COUNT: usize = state_vector_simulation::MAX_QUBIT_COUNT / 2;
+
+const EQUIVALENCE_TOLERANCE: f64 = 1e-10;
+
+#[derive(Clone, Debug)]
+pub struct UnitarySimulation {
+    qubit_count: usize,
+    elements: Vec<Complex<f64>>,
+}
+
+impl UnitarySimulation {
+    pub fn new(qubit_count: usize) -> UnitarySimulation {
+        assert!(
+            qubit_count <= MAX_QUBIT_COUNT,
+            "The number of qubits in the simulation cannot exceed {}.",
+            MAX_QUBIT_COUNT
+        );
+
+        let mut simulation = UnitarySimulation {
+            qubit_count,
+            elements: vec![Complex::new(0.0, 0.0); 1 << (2 * qubit_count)],
+        };
+        simulation.reset();
+
+        simulation
+    }
+
+    pub fn qubit_count(&self) -> usize {
+        self.qubit_count
+    }
+
+    // The elements U[row, column] of the unitary at the indices row + column * 2^n.
+    pub fn unitary(&self) -> &[Complex<f64>] {
+        &self.elements
+    }
+
+    pub fn element(&self, row: usize, column: usize) -> Complex<f64> {
+        self.elements[row | column << self.qubit_count]
+    }
+
+    // Whether the unitaries are equal up to a global phase, taken from the largest element.
+    pub fn is_equivalent(&self, other: &UnitarySimulation) -> bool {
+        if self.qubit_count != other.qubit_count {
+            return false;
+        }
+        let (largest_index, _) = self
+            .elements
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.norm_sqr().total_cmp(&b.norm_sqr()))
+            .unwrap();
+        let (element, other_element) =
+            (self.elements[largest_index], other.elements[largest_index]);
+        if other_element.norm() < EQUIVALENCE_TOLERANCE {
+            return false;
+        }
+        let phase = element / other_element;
+        self.elements
+            .iter()
+            .zip(other.elements.iter())
+            .all(|(element, other_element)| {
+                (element - phase * other_element).norm() < EQUIVALENCE_TOLERANCE
+            })
+    }
+
+    fn check_qubit_numbers(&self, qubit_numbers: &[usize]) {
+        if let Err(error) = check_qubits(qubit_numbers, self.qubit_count) {
+            panic!("{}", error);
+        }
+    }
+
+    // Apply the matrix to the target qubit of the rows where the control qubits
+    // have the given values.
+    fn apply_matrix(&mut self, matrix: &Matrix, controls: &[(usize, bool)], target: usize) {
+        let mut qubit_numbers: Vec<usize> = controls.iter().map(|&(control, _)| control).collect();
+        qubit_numbers.push(target);
+        self.check_qubit_numbers(&qubit_numbers);
+
+        let (mut control_mask, mut control_value) = (0, 0);
+        for &(control, value) in controls.iter() {
+            control_mask |= 1 << control;
+            if value {
+                control_value |= 1 << control;
+            }
+        }
+        let stride = 1 << target;
+        for (c, chunk) in self.elements.chunks_exact_mut(2 * stride).enumerate() {
+            let (zeros, ones) = chunk.split_at_mut(stride);
+            let chunk_start = 2 * stride * c;
+            for (j, (element0, element1)) in zeros.iter_mut().zip(ones.iter_mut()).enumerate() {
+                if (chunk_start + j) & control_mask != control_value {
+                    continue;
+                }
+                let (e0, e1) = (*element0, *element1);
+                *element0 = matrix[0] * e0 + matrix[1] * e1;
+                *element1 = matrix[2] * e0 + matrix[3] * e1;
+            }
+        }
+    }
+}
+
+impl Simulation for UnitarySimulation {
+    // Reset the unitary to the identity.
+    fn reset(&mut self) {
+        self.elements.fill(Complex::new(0.0, 0.0));
+        for i in 0..1usize << self.qubit_count {
+            self.elements[i | i << self.qubit_count] = Complex::new(1.0, 0.0);
+        }
+    }
+
+    fn measure_all(&mut self) -> Vec<bool> {
+        panic!("The unitary simulation cannot measure the qubits.")
+    }
+
+    fn measure(&mut self, _qubit_numbers: Vec<usize>) -> Vec<bool> {
+        panic!("The unitary simulation cannot measure the qubits.")
+    }
+
+    fn pauli_x(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_X_MATRIX, &[], qubit_number);
+    }
+
+    fn pauli_y(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Y_MATRIX, &[], qubit_number);
+    }
+
+    fn pauli_z(&mut self, qubit_number: usize) {
+        self.apply_matrix(&PAULI_Z_MATRIX, &[], qubit_number);
+    }
+
+    fn hadamard(&mut self, qubit_number: usize) {
+        self.apply_matrix(&HADAMARD_MATRIX, &[], qubit_number);
+    }
+
+    fn s(&mut self, qubit_number: usize) {
+        self.apply_matrix(&S_MATRIX, &[], qubit_number);
+    }
+
+    fn t(&mut self, qubit_number: usize) {
+        self.apply_matrix(&T_MATRIX, &[], qubit_number);
+    }
+
+    fn rx(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rx_matrix(theta), &[], qubit_number);
+    }
+
+    fn ry(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&ry_matrix(theta), &[], qubit_number);
+    }
+
+    fn rz(&mut self, theta: f64, qubit_number: usize) {
+        self.apply_matrix(&rz_matrix(theta), &[], qubit_number);
+    }
+
+    fn cnot(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_matrix(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cz(&mut self, control_qubit_number: usize, target_qubit_number: usize) {
+        self.apply_matrix(
+            &PAULI_Z_MATRIX,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn cphase(&mut self, theta: f64, control_qubit_number: usize, target_qubit_number: usize) {
+        let matrix = phase_matrix(Complex::new(0.0, theta).exp());
+        self.apply_matrix(
+            &matrix,
+            &[(control_qubit_number, true)],
+            target_qubit_number,
+        );
+    }
+
+    fn swap(&mut self, qubit_number0: usize, qubit_number1: usize) {
+        self.cnot(qubit_number0, qubit_number1);
+        self.cnot(qubit_number1, qubit_number0);
+        self.cnot(qubit_number0, qubit_number1);
+    }
+
+    // U_f flips the qubit 0 when the qubit 1 is 0 if f(true), and when the qubit 1 is 1 if not f(false).
+    fn apply_u_f(&mut self, f: fn(bool) -> bool, qubit_number0: usize, qubit_number1: usize) {
+        self.check_qubit_numbers(&[qubit_number0, qubit_number1]);
+        if f(true) {
+            self.apply_matrix(&PAULI_X_MATRIX, &[(qubit_number1, false)], qubit_number0);
+        }
+        if !f(false) {
+            self.apply_matrix(&PAULI_X_MATRIX, &[(qubit_number1, true)], qubit_number0);
+        }
+    }
+
+    fn toffoli(
+        &mut self,
+        control_qubit_number0: usize,
+        control_qubit_number1: usize,
+        target_qubit_number: usize,
+    ) {
+        self.apply_matrix(
+            &PAULI_X_MATRIX,
+            &[(control_qubit_number0, true), (control_qubit_number1, true)],
+            target_qubit_number,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_vector_simulation::QuantumSimulation;
+
+    #[test]
+    fn columns_match_state_vector() {
+        let gates: [fn(&mut dyn Simulation); 7] = [
+            |s| s.hadamard(0),
+            |s| s.ry(0.7, 2),
+            |s| s.cnot(0, 1),
+            |s| s.cphase(0.9, 1, 2),
+            |s| s.t(1),
+            |s| s.apply_u_f(|x| !x, 2, 0),
+            |s| s.toffoli(0, 2, 1),
+        ];
+        let mut simulation = UnitarySimulation::new(3);
+        for gate in gates.iter() {
+            gate(&mut simulation);
+        }
+        for column in 0..8 {
+            let mut state_vector_simulation = QuantumSimulation::new(3, 0);
+            for q in (0..3).filter(|q| (column >> q) & 1 == 1) {
+                state_vector_simulation.pauli_x(q);
+            }
+            for gate in gates.iter() {
+                gate(&mut state_vector_simulation);
+            }
+            for (row, amplitude) in state_vector_simulation.amplitudes().iter().enumerate() {
+                assert!((simulation.element(row, column) - amplitude).norm() < 1e-12);
+            }
+        }
+
+        // CZ is H CNOT H, and RZ differs from the phase gate S only by a global phase.
+        let mut cz = UnitarySimulation::new(2);
+        cz.cz(0, 1);
+        cz.rz(std::f64::consts::FRAC_PI_2, 0);
+        let mut equivalent = UnitarySimulation::new(2);
+        equivalent.hadamard(1);
+        equivalent.cnot(0, 1);
+        equivalent.hadamard(1);
+        equivalent.s(0);
+        assert!(cz.is_equivalent(&equivalent));
+        equivalent.t(1);
+        assert!(!cz.is_equivalent(&equivalent));
+    }
+}
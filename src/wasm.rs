@@ -0,0 +1,136 @@
+/*
+JavaScript API of the state vector simulation for WebAssembly.
+
+The gates are applied by their OpenQASM names, and the errors are thrown as JavaScript
+errors instead of aborting the WebAssembly module with a panic.
+
+Example build:
+wasm-pack build --target web -- --features wasm
+
+Copyright © 2024 AlgoHertz. All rights reserved.
+*/
+
+use wasm_bindgen::prelude::*;
+
+use crate::circuit::Gate;
+use crate::simulation::{check_qubits, Simulation};
+use crate::state_vector_simulation::QuantumSimulation;
+
+// The gates with their numbers of parameters and qubits.
+const GATES: [(&str, usize, usize); 14] = [
+    ("x", 0, 1),
+    ("y", 0, 1),
+    ("z", 0, 1),
+    ("h", 0, 1),
+    ("s", 0, 1),
+    ("t", 0, 1),
+    ("rx", 1, 1),
+    ("ry", 1, 1),
+    ("rz", 1, 1),
+    ("cx", 0, 2),
+    ("cz", 0, 2),
+    ("cp", 1, 2),
+    ("swap", 0, 2),
+    ("ccx", 0, 3),
+];
+
+// The highest number of qubits, with the 2^24 amplitudes taking 256 MiB of the 4 GiB WebAssembly memory.
+const MAX_QUBIT_COUNT: usize = 24;
+
+#[wasm_bindgen(js_name = Simulation)]
+pub struct WasmSimulation {
+    simulation: QuantumSimulation,
+    qubit_count: usize,
+}
+
+#[wasm_bindgen(js_class = Simulation)]
+impl WasmSimulation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(qubit_count: u32, seed: u32) -> Result<WasmSimulation, JsError> {
+        let qubit_count = qubit_count as usize;
+        if qubit_count > MAX_QUBIT_COUNT {
+            return Err(JsError::new(&format!(
+                "The number of qubits in the simulation cannot exceed {}.",
+                MAX_QUBIT_COUNT
+            )));
+        }
+        Ok(WasmSimulation {
+            simulation: QuantumSimulation::new(qubit_count, seed as u64),
+            qubit_count,
+        })
+    }
+
+    #[wasm_bindgen(getter, js_name = qubitCount)]
+    pub fn qubit_count(&self) -> u32 {
+        self.qubit_count as u32
+    }
+
+    pub fn reset(&mut self) {
+        self.simulation.reset();
+    }
+
+    // Apply the gate like applyGate("cp", [0, 1], [Math.PI / 2]).
+    #[wasm_bindgen(js_name = applyGate)]
+    pub fn apply_gate(
+        &mut self,
+        name: &str,
+        qubits: &[u32],
+        parameters: &[f64],
+    ) -> Result<(), JsError> {
+        let &(_, parameter_count, qubit_count) = GATES
+            .iter()
+            .find(|&&(n, _, _)| n == name)
+            .ok_or_else(|| JsError::new(&format!("Unknown gate {}.", name)))?;
+        if parameters.len() != parameter_count || qubits.len() != qubit_count {
+            return Err(JsError::new(&format!(
+                "The gate {} takes {} parameters and {} qubits.",
+                name, parameter_count, qubit_count
+            )));
+        }
+        let q = self.qubit_numbers(qubits)?;
+        let p = parameters;
+        let gate = match name {
+            "x" => Gate::PauliX(q[0]),
+            "y" => Gate::PauliY(q[0]),
+            "z" => Gate::PauliZ(q[0]),
+            "h" => Gate::Hadamard(q[0]),
+            "s" => Gate::S(q[0]),
+            "t" => Gate::T(q[0]),
+            "rx" => Gate::Rx(p[0], q[0]),
+            "ry" => Gate::Ry(p[0], q[0]),
+            "rz" => Gate::Rz(p[0], q[0]),
+            "cx" => Gate::Cnot(q[0], q[1]),
+            "cz" => Gate::Cz(q[0], q[1]),
+            "cp" => Gate::Cphase(p[0], q[0], q[1]),
+            "swap" => Gate::Swap(q[0], q[1]),
+            _ => Gate::Toffoli(q[0], q[1], q[2]),
+        };
+        gate.apply(&mut self.simulation);
+        Ok(())
+    }
+
+    // Measure the qubits, returning 0 or 1 for each of them.
+    pub fn measure(&mut self, qubits: &[u32]) -> Result<Vec<u8>, JsError> {
+        let qubit_numbers = self.qubit_numbers(qubits)?;
+        let outcomes = self.simulation.measure(qubit_numbers);
+        Ok(outcomes.into_iter().map(u8::from).collect())
+    }
+
+    // The probabilities of the basis states, indexed by the bits of the qubit numbers.
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.simulation
+            .amplitudes()
+            .iter()
+            .map(|amplitude| amplitude.norm_sqr())
+            .collect()
+    }
+}
+
+impl WasmSimulation {
+    fn qubit_numbers(&self, qubits: &[u32]) -> Result<Vec<usize>, JsError> {
+        let qubit_numbers: Vec<usize> = qubits.iter().map(|&q| q as usize).collect();
+        check_qubits(&qubit_numbers, self.qubit_count)
+            .map_err(|error| JsError::new(&error.to_string()))?;
+        Ok(qubit_numbers)
+    }
+}